@@ -0,0 +1,84 @@
+//! Compare JSON and binary (zstd + MessagePack) cache entries.
+//!
+//! Run with `cargo bench --bench cache_format`. Writes entries for a
+//! synthetic project to a temp dir, then times reading them all back.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use lit::core::cache::{Cache, CachedGeneration};
+use lit::core::config::{CacheConfig, CacheFormat};
+use lit::core::generator::PARSE_VERSION;
+
+const ENTRIES: usize = 200;
+const FILES_PER_ENTRY: usize = 4;
+const LINES_PER_FILE: usize = 400;
+const ROUNDS: usize = 5;
+
+fn synthetic_entry(i: usize) -> CachedGeneration {
+    let files = (0..FILES_PER_ENTRY)
+        .map(|f| {
+            let content: String = (0..LINES_PER_FILE)
+                .map(|l| format!("def handler_{i}_{f}_{l}(request):\n    return process(request, {l})\n"))
+                .collect();
+            (PathBuf::from(format!("src/module_{i}/file_{f}.py")), content)
+        })
+        .collect::<HashMap<_, _>>();
+    CachedGeneration {
+        input_hash: format!("{:064x}", i),
+        files,
+        tokens_in: 1_000,
+        tokens_out: 5_000,
+        created_at: Some(chrono::Utc::now()),
+        model: Some("claude-sonnet-4-5-20250929".to_string()),
+        parse_version: PARSE_VERSION,
+    }
+}
+
+fn bench(format: CacheFormat, entries: &[CachedGeneration]) -> (Duration, Duration, u64) {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().to_path_buf()).with_config(CacheConfig {
+        format,
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    for entry in entries {
+        cache.put(entry).unwrap();
+    }
+    let write = start.elapsed();
+
+    let mut read = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        for entry in entries {
+            assert!(cache.get(&entry.input_hash).is_some());
+        }
+        read = read.min(start.elapsed());
+    }
+
+    let size = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().metadata().unwrap().len())
+        .sum();
+    (write, read, size)
+}
+
+fn main() {
+    let entries: Vec<_> = (0..ENTRIES).map(synthetic_entry).collect();
+    println!(
+        "{} entries x {} files x {} lines (best of {} reads)",
+        ENTRIES, FILES_PER_ENTRY, LINES_PER_FILE, ROUNDS
+    );
+    for (name, format) in [("json", CacheFormat::Json), ("binary", CacheFormat::Binary)] {
+        let (write, read, size) = bench(format, &entries);
+        println!(
+            "  {:<7} write {:>8.1?}  read {:>8.1?}  size {:>7} KiB",
+            name,
+            write,
+            read,
+            size / 1024
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! In-process API for tools that drive lit, such as CI plugins and editor
+//! extensions.
+//!
+//! The CLI's commands read the working directory and report on stderr; a
+//! [`Lit`] is opened on a project once and returns what happened as values:
+//!
+//! ```ignore
+//! let lit = Lit::open("path/to/project")?;
+//! let report = lit.regenerate(RegenerateOptions::default()).await?;
+//! println!("{} file(s), ${:.4}", report.files.len(), report.record.summary.total_cost_usd);
+//! let stale = lit.status()?.stale;
+//! let spent = lit.cost_summary()?.total_cost_usd;
+//! lit.commit("Regenerate", false)?;
+//! ```
+//!
+//! Runs still print progress lines; silence them with
+//! `lit::core::progress::set_verbosity(Verbosity::Quiet)`. Failures a caller
+//! may want to handle are described in [`crate::core::error`].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::core::cache::Cache;
+use crate::core::config::LitConfig;
+use crate::core::format::Formatter;
+use crate::core::freeze::FreezeList;
+use crate::core::error::LitError;
+use crate::core::generation_record::{
+    CostSummary, GenerationRecord, build_record, format_cost, report_budget, write_record,
+};
+use crate::core::generator::{Generator, OutputReviewer};
+use crate::core::hooks::{HookPoint, Hooks};
+use crate::core::interrupt::Interrupted;
+use crate::core::last_run::{build_last_run, write_last_run};
+use crate::core::lock::RepoLock;
+use crate::core::manifest::Manifest;
+use crate::core::patch::{PatchStore, write_conflict_report};
+use crate::core::progress;
+use crate::core::project::{
+    ConflictResolver, ensure_prompts, load_code, load_prompts, select_prompts, stale_prompts, update_manifest,
+    write_output_file, write_outputs,
+};
+use crate::core::prompt::TagFilter;
+use crate::core::repo::{LitRepo, RepoStatus};
+use crate::core::style;
+use crate::core::transcript::TranscriptStore;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
+
+/// A lit project opened for in-process use
+#[derive(Debug, Clone)]
+pub struct Lit {
+    config: LitConfig,
+    root: PathBuf,
+}
+
+/// What [`Lit::regenerate`] regenerates; the default is every prompt,
+/// like `lit regenerate`
+#[derive(Default)]
+pub struct RegenerateOptions {
+    /// Prompts to regenerate with their dependents; empty for all
+    pub prompts: Vec<PathBuf>,
+    /// Regenerate just `prompts`, leaving their dependents stale
+    pub only: bool,
+    pub tags: TagFilter,
+    /// Call the LLM even on a cache hit
+    pub no_cache: bool,
+    /// Write generated code without reapplying saved patches
+    pub no_patches: bool,
+    /// Cascade to dependents even when an upstream output regenerates
+    /// identically
+    pub no_prune: bool,
+    /// Submit each DAG level as one batch job
+    pub batch: bool,
+    /// Write the code here (relative to the project root) instead of the
+    /// code directory, leaving the manifest and patches as they are
+    pub output_dir: Option<PathBuf>,
+    /// Provider to generate with, instead of the ones configured in lit.toml
+    pub provider: Option<Box<dyn LlmProvider>>,
+    /// Asked to approve, retry or skip each fresh output
+    pub reviewer: Option<Box<dyn OutputReviewer>>,
+    /// Resolves patch conflicts; without one they get conflict markers
+    pub resolve_conflicts: Option<ConflictResolver<'static>>,
+}
+
+/// Outcome of a regeneration run
+#[derive(Debug, Clone)]
+pub struct RegenerateReport {
+    /// Prompts the run covered, in DAG order
+    pub selected: Vec<PathBuf>,
+    /// Prompts generated by a fresh LLM call
+    pub generated: Vec<PathBuf>,
+    /// Prompts whose output came from the cache
+    pub cached: Vec<PathBuf>,
+    /// Prompts that kept their code: those outside the selection, and
+    /// dependents whose imports regenerated unchanged
+    pub skipped: Vec<PathBuf>,
+    /// Files written to the code directory, static files included
+    pub files: Vec<PathBuf>,
+    /// Where they were written: the code directory, or `output_dir`
+    pub written_to: PathBuf,
+    pub patches_applied: usize,
+    /// Files written with conflict markers because a saved patch conflicted
+    pub conflicts: Vec<PathBuf>,
+    /// `.lit/reports/` file listing the conflicts
+    pub conflict_report: Option<PathBuf>,
+    /// Dependents `only` left behind, marked stale in the manifest
+    pub left_stale: Vec<PathBuf>,
+    /// Prompts left out because they are frozen or drafts
+    pub frozen: usize,
+    pub drafts: usize,
+    /// Size and import warnings, patch conflicts and budget alerts
+    pub warnings: Vec<String>,
+    /// The run's generation record, with its tokens and cost
+    pub record: GenerationRecord,
+}
+
+/// A commit made by [`Lit::commit`]
+#[derive(Debug)]
+pub struct Commit {
+    pub hash: String,
+    /// The changes it committed
+    pub changes: RepoStatus,
+    /// Generation records it committed, whose total cost is in its message
+    pub records: Vec<GenerationRecord>,
+}
+
+/// State of a project, as `lit status` reports it
+#[derive(Debug)]
+pub struct Status {
+    /// Every prompt, in DAG order
+    pub prompts: Vec<PathBuf>,
+    /// Prompts whose code is behind their inputs; None before the first
+    /// regeneration
+    pub stale: Option<Vec<PathBuf>>,
+    pub frozen: Vec<PathBuf>,
+    pub drafts: Vec<PathBuf>,
+    /// Uncommitted changes; None outside a git repository
+    pub changes: Option<RepoStatus>,
+}
+
+impl Lit {
+    /// Open the project containing `path` (the directory holding lit.toml,
+    /// or any directory below it)
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let (config, root) = LitConfig::find_and_load(path.as_ref())?;
+        Ok(Self { config, root })
+    }
+
+    pub fn config(&self) -> &LitConfig {
+        &self.config
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Regenerate prompts into the code directory, as `lit regenerate
+    /// --yes` does: patches are reapplied (conflicts get conflict markers
+    /// unless `resolve_conflicts` settles them), the manifest, generation
+    /// record and last-run summary are updated, and `[budget.alerts]` are
+    /// checked. A failed generation leaves the code directory untouched and
+    /// returns the failure.
+    pub async fn regenerate(&self, options: RegenerateOptions) -> Result<RegenerateReport> {
+        let root = self.root.as_path();
+        let mut config = self.config.clone();
+        config.generation.no_prune = options.no_prune;
+        let config = &config;
+        let lit_dir = root.join(".lit");
+        let _lock = RepoLock::acquire(&lit_dir, "regenerate", false)?;
+
+        ensure_prompts(config, root)?;
+        let (prompts, dag) = load_prompts(config, root)?;
+        let selection = select_prompts(root, &dag, &prompts, &options.prompts, options.only, &options.tags)?;
+        if selection.frozen > 0 {
+            progress::line(&format!(
+                "  {}",
+                format!(
+                    "Frozen (reusing code.lock/): {} prompt(s); `lit unfreeze` to regenerate them",
+                    selection.frozen
+                )
+                .dimmed()
+            ));
+        }
+        if selection.drafts > 0 {
+            progress::line(&format!("  {}", format!("Drafts (not generated): {} prompt(s)", selection.drafts).dimmed()));
+        }
+        progress::line(&style::regen_header(selection.prompts.len(), dag.len()));
+
+        let mut warnings = Vec::new();
+        for path in &selection.prompts {
+            warnings.extend(prompts[path].size_warning(config));
+            warnings.extend(prompts[path].undeclared_body_imports().into_iter().map(|reference| {
+                format!("@import({}) in {} is not declared in its frontmatter imports", reference, path.display())
+            }));
+        }
+        for warning in &warnings {
+            progress::warn(&format!("  {}", style::warning(warning)));
+        }
+
+        let hooks = Hooks::new(root, config.hooks.clone());
+        hooks.run(HookPoint::PreGenerate, None)?;
+
+        let detached = options.output_dir.is_some();
+        let write_dir = match &options.output_dir {
+            Some(dir) => root.join(dir),
+            None => config.code_dir(root),
+        };
+        if config.project.prompt_dirs.iter().any(|dir| write_dir.starts_with(root.join(dir)))
+            || write_dir.starts_with(&lit_dir)
+        {
+            anyhow::bail!("Refusing to write generated code into {}", write_dir.display());
+        }
+
+        // Static files are context for the prompts, but only written once
+        // generation has succeeded
+        let mut existing_code = load_code(config, root);
+        let mut static_files = Vec::new();
+        for sf in &config.r#static {
+            let content = sf.read(root)?;
+            if let Ok(text) = String::from_utf8(content.clone()) {
+                existing_code.insert(PathBuf::from(&sf.path), text);
+            }
+            static_files.push((sf, content));
+        }
+
+        let cache = if options.no_cache {
+            progress::line(&format!("  {}", "Cache disabled (--no-cache)".dimmed()));
+            None
+        } else {
+            let cache = Cache::new(lit_dir.join("cache")).with_config(config.cache.clone());
+            cache.init().context("Failed to initialize cache directory")?;
+            Some(cache)
+        };
+
+        // The API key is only resolved on the first cache miss, so fully
+        // cached runs work without one
+        let provider = options.provider.unwrap_or_else(|| Box::new(build_registry(config, false)));
+        let mut generator = Generator::new(provider, config.clone());
+        if let Some(reviewer) = options.reviewer {
+            generator = generator.with_reviewer(reviewer);
+        }
+        if !config.format.is_empty() {
+            generator = generator.with_formatter(Formatter::new(root, config.format.clone()));
+        }
+        if options.batch {
+            progress::line(&format!(
+                "  {}",
+                "Batch mode: each DAG level is submitted as one batch job (slower, half price)".dimmed()
+            ));
+            generator = generator.with_batch();
+        }
+        if config.logging.transcripts {
+            generator = generator.with_transcripts(TranscriptStore::new(&lit_dir));
+        }
+        if let Some(mut manifest) = Manifest::load(&lit_dir).ok().flatten() {
+            // Prompts marked stale are regenerated rather than kept, so the
+            // mark clears
+            manifest.inputs.retain(|prompt, _| !manifest.stale.contains(prompt));
+            generator = generator.with_last_inputs(manifest.inputs);
+        }
+        let result = generator
+            .run_pipeline(&dag, &prompts, &selection.prompts, &existing_code, cache.as_ref())
+            .await?;
+
+        // A failed generation leaves the code directory untouched; record
+        // what happened and stop
+        if let Some(failure) = result.failures.first() {
+            let (record, _) = build_record(config, &result, 0, 0, 0);
+            write_record(root, &record);
+            let last_run = build_last_run(
+                &dag,
+                &selection.prompts,
+                &result,
+                &record,
+                &[],
+                warnings,
+                Some(failure.message.clone()),
+            );
+            write_last_run(root, &last_run);
+            anyhow::bail!("{}", failure.message);
+        }
+        // Ctrl-C: keep what was generated (it is cached) and leave the code alone
+        if result.interrupted {
+            let (record, _) = build_record(config, &result, 0, 0, 0);
+            write_record(root, &record);
+            write_last_run(root, &build_last_run(&dag, &selection.prompts, &result, &record, &[], warnings, None));
+            progress::warn(&format!(
+                "{} generated {} of {} prompt(s); code.lock/ was not changed",
+                "Interrupted:".yellow().bold(),
+                result.outputs.len(),
+                selection.prompts.len()
+            ));
+            progress::warn(&format!(
+                "  {}",
+                "Hint: Generated prompts are cached, so `lit regenerate` picks up where this left off.".dimmed()
+            ));
+            return Err(Interrupted.into());
+        }
+
+        for (sf, content) in &static_files {
+            write_output_file(&write_dir, Path::new(&sf.path), content, sf.mode)?;
+        }
+        if !static_files.is_empty() {
+            progress::line(&format!("  Static files written: {}", static_files.len().to_string().dimmed()));
+        }
+
+        let patch_store = if options.no_patches {
+            progress::line(&format!("  {}", "Patches disabled (--no-patches)".dimmed()));
+            None
+        } else {
+            let ps = PatchStore::new(lit_dir.join("patches"));
+            let _ = ps.init();
+            Some(ps)
+        };
+        let written = write_outputs(
+            root,
+            config,
+            &result.outputs,
+            &prompts,
+            &write_dir,
+            patch_store.as_ref(),
+            &hooks,
+            detached,
+            options.resolve_conflicts,
+        )?;
+        let (record, total_cost) = build_record(
+            config,
+            &result,
+            written.files.len(),
+            written.patches_applied,
+            written.conflicts.len(),
+        );
+        write_record(root, &record);
+
+        // With `only`, dependents keep their code and are marked stale in
+        // the manifest until they are regenerated themselves
+        let left_stale: Vec<PathBuf> = if options.only && !detached {
+            let frozen = FreezeList::load(&lit_dir)?;
+            dag.regeneration_set(&selection.prompts)
+                .into_iter()
+                .filter(|p| !selection.prompts.contains(p) && !prompts[p].frontmatter.draft && !frozen.is_frozen(p))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if !detached {
+            update_manifest(root, &result, record.timestamp, &written, &prompts, &left_stale);
+        }
+
+        let conflicts: Vec<PathBuf> = written.conflicts.iter().map(|c| c.output_path.clone()).collect();
+        warnings.extend(conflicts.iter().map(|path| format!("Patch conflict in {}", path.display())));
+        let mut conflict_report = None;
+        if !written.conflicts.is_empty() {
+            match write_conflict_report(&lit_dir.join("reports"), &written.conflicts, record.timestamp) {
+                Ok(path) => {
+                    warnings.push(format!(
+                        "{} patch conflict(s) — see {}",
+                        conflicts.len(),
+                        path.strip_prefix(root).unwrap_or(&path).display()
+                    ));
+                    conflict_report = Some(path);
+                }
+                Err(e) => progress::warn(&style::warning(&format!("Failed to write conflict report: {}", e))),
+            }
+        }
+
+        warnings.extend(report_budget(config, root, Some(total_cost), true).await);
+
+        let files = written.last_run_files(config, &result);
+        write_last_run(
+            root,
+            &build_last_run(&dag, &selection.prompts, &result, &record, &files, warnings.clone(), None),
+        );
+
+        let (cached, generated) = result.outputs.iter().partition::<Vec<_>, _>(|output| output.from_cache);
+        Ok(RegenerateReport {
+            generated: generated.into_iter().map(|o| o.prompt_path.clone()).collect(),
+            cached: cached.into_iter().map(|o| o.prompt_path.clone()).collect(),
+            skipped: result.skipped.clone(),
+            selected: selection.prompts,
+            files: files.into_iter().map(|f| f.path).collect(),
+            written_to: write_dir,
+            patches_applied: written.patches_applied,
+            conflicts,
+            conflict_report,
+            left_stale,
+            frozen: selection.frozen,
+            drafts: selection.drafts,
+            warnings,
+            record,
+        })
+    }
+
+    /// Prompts, staleness and uncommitted changes
+    pub fn status(&self) -> Result<Status> {
+        let (prompts, dag) = load_prompts(&self.config, &self.root)?;
+        let frozen = FreezeList::load(&self.root.join(".lit"))?;
+        let changes = match LitRepo::open(&self.root) {
+            Ok(repo) => Some(repo.with_project_dirs(&self.config.project).status()?),
+            Err(_) => None,
+        };
+        let by_order = |keep: &dyn Fn(&PathBuf) -> bool| dag.order().iter().filter(|p| keep(p)).cloned().collect();
+        Ok(Status {
+            stale: stale_prompts(&self.config, &self.root, &TagFilter::default())?,
+            frozen: by_order(&|p| frozen.is_frozen(p)),
+            drafts: by_order(&|p| prompts[p].frontmatter.draft),
+            prompts: dag.order().to_vec(),
+            changes,
+        })
+    }
+
+    /// Stage the project's files and commit them, as `lit commit` does.
+    /// Refuses with [`LitError::Stale`] while code.lock/ is behind a prompt,
+    /// unless `allow_stale`. None when there is nothing to commit.
+    pub fn commit(&self, message: &str, allow_stale: bool) -> Result<Option<Commit>> {
+        let (config, root) = (&self.config, self.root.as_path());
+        let repo = LitRepo::open(root)?.with_project_dirs(&config.project);
+
+        // Keep "code is derived from prompts" true in history
+        if !allow_stale
+            && let Some(stale) = stale_prompts(config, root, &TagFilter::default())?
+            && !stale.is_empty()
+        {
+            return Err(LitError::Stale { prompts: stale }.into());
+        }
+
+        Hooks::new(root, config.hooks.clone()).run(HookPoint::PreCommit, None)?;
+
+        // Patches saved since the last regeneration change the manifest's patch status
+        let lit_dir = root.join(".lit");
+        if let Some(mut manifest) = Manifest::load(&lit_dir)? {
+            manifest.sync_patches(&PatchStore::new(lit_dir.join("patches")).load_index()?);
+            manifest.save(&lit_dir)?;
+        }
+
+        repo.stage_all()?;
+        let changes = repo.status()?;
+        if !changes.has_changes() {
+            return Ok(None);
+        }
+
+        // Record what the generation runs being committed cost, so history can
+        // answer "what did this cost" (see `lit cost --commit`)
+        let records: Vec<GenerationRecord> = changes
+            .config_modified
+            .iter()
+            .filter(|p| p.starts_with(".lit/generations"))
+            .filter_map(|p| GenerationRecord::read(&root.join(p)).ok())
+            .collect();
+        let message = if records.is_empty() {
+            message.to_string()
+        } else {
+            format!(
+                "{}\n\nLit-Cost: {} ({} generation run(s))",
+                message.trim_end(),
+                format_cost(records.iter().map(|r| r.summary.total_cost_usd).sum()),
+                records.len()
+            )
+        };
+        let hash = repo.commit(&message)?;
+        Ok(Some(Commit { hash, changes, records }))
+    }
+
+    /// Spend and token totals across every recorded generation run
+    pub fn cost_summary(&self) -> Result<CostSummary> {
+        let records = GenerationRecord::list(&self.root.join(".lit").join("generations"))?;
+        Ok(CostSummary::of(&records))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::interrupt::SimulatedInterrupt;
+    use crate::providers::{GenerationRequest, GenerationResponse};
+
+    /// The run stops before its first LLM call, so this is never asked
+    struct UnusedProvider;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for UnusedProvider {
+        async fn generate(&self, _request: GenerationRequest) -> Result<GenerationResponse> {
+            unreachable!("an interrupted run makes no LLM calls")
+        }
+
+        fn name(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_regenerate_leaves_code_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("lit.toml"),
+            "[project]\nname = \"interrupt\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n[language]\ndefault = \"python\"\nversion = \"3.12\"\n\n\
+             [model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5\"\ntemperature = 0.0\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("prompts")).unwrap();
+        std::fs::write(root.join("prompts/user.prompt.md"), "---\noutputs:\n  - src/user.py\n---\n\n# User\n").unwrap();
+        std::fs::create_dir_all(root.join("code.lock/src")).unwrap();
+        std::fs::write(root.join("code.lock/src/user.py"), "class User: ...\n").unwrap();
+
+        let lit = Lit::open(root).unwrap();
+        let _ctrl_c = SimulatedInterrupt::press();
+        let options = RegenerateOptions { provider: Some(Box::new(UnusedProvider)), ..Default::default() };
+        let err = lit.regenerate(options).await.unwrap_err();
+
+        assert!(err.downcast_ref::<Interrupted>().is_some(), "{:#}", err);
+        assert_eq!(std::fs::read_to_string(root.join("code.lock/src/user.py")).unwrap(), "class User: ...\n");
+        assert!(Manifest::load(&root.join(".lit")).unwrap().is_none());
+    }
+}
@@ -9,7 +9,7 @@ use crate::core::style;
 
 pub async fn run(path: PathBuf) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
 
     let full_path = if path.is_absolute() {
         path.clone()
@@ -37,9 +37,17 @@ pub async fn run(path: PathBuf) -> Result<()> {
         let relative = full_path
             .strip_prefix(&root)
             .unwrap_or(&full_path);
-        if !relative.starts_with("prompts") {
+        if !config.project.in_prompt_dir(relative) {
             eprintln!(
-                "{} {} is not inside prompts/. Move it to prompts/ for lit to track it.",
+                "{} {} is not inside {}. Move it there for lit to track it.",
+                "⚠".yellow().bold(),
+                relative.display(),
+                config.project.prompt_dirs_display()
+            );
+        }
+        if config.project.is_excluded(relative) {
+            eprintln!(
+                "{} {} matches [project] exclude in lit.toml, so lit will skip it.",
                 "⚠".yellow().bold(),
                 relative.display()
             );
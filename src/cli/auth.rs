@@ -0,0 +1,68 @@
+use std::io::IsTerminal;
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::cli::AuthCommands;
+use crate::core::config::{LitConfig, VALID_PROVIDERS};
+use crate::core::keychain;
+use crate::core::style;
+
+/// `lit auth login|logout|status` — keep provider API keys in the OS
+/// keychain instead of environment variables
+pub async fn run(action: AuthCommands) -> Result<()> {
+    match action {
+        AuthCommands::Login { provider } => {
+            check_provider(&provider)?;
+            let key = if std::io::stdin().is_terminal() {
+                rpassword::prompt_password(format!("API key for {}: ", provider))
+                    .context("Failed to read the API key")?
+            } else {
+                // Piped, e.g. `echo "$KEY" | lit auth login anthropic`
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).context("Failed to read the API key")?;
+                line
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                bail!("No API key given");
+            }
+            keychain::store(&provider, key)?;
+            eprintln!("{} Stored the {} API key in the OS keychain", "✓".green().bold(), provider);
+        }
+        AuthCommands::Logout { provider } => {
+            check_provider(&provider)?;
+            if keychain::delete(&provider)? {
+                eprintln!("{} Removed the {} API key from the OS keychain", "✓".green().bold(), provider);
+            } else {
+                eprintln!("{}", format!("No {} API key in the OS keychain.", provider).dimmed());
+            }
+        }
+        AuthCommands::Status => {
+            let cwd = std::env::current_dir()?;
+            let (config, _) = LitConfig::find_and_load(&cwd)?;
+            eprintln!("{}", style::header("API Keys"));
+            let mut providers = vec![config.model.provider.clone()];
+            providers.extend(config.providers.keys().filter(|p| **p != config.model.provider).cloned());
+            for provider in providers {
+                let key_env = config.api_config(&provider).map_or("LIT_API_KEY", |api| api.key_env.as_str());
+                let source = if std::env::var(key_env).is_ok() {
+                    format!("${}", key_env).green()
+                } else if keychain::load(&provider).is_some() {
+                    "OS keychain".green()
+                } else {
+                    format!("missing (set ${} or run `lit auth login {}`)", key_env, provider).red()
+                };
+                eprintln!("  {:<12} {}", provider, source);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_provider(provider: &str) -> Result<()> {
+    if !VALID_PROVIDERS.contains(&provider) {
+        bail!("Unknown provider '{}'. Must be one of: {}", provider, VALID_PROVIDERS.join(", "));
+    }
+    Ok(())
+}
@@ -1,27 +1,53 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
 use colored::Colorize;
 
+use crate::core::atomic;
 use crate::core::config::LitConfig;
+use crate::core::patch::{PatchResult, PatchStatus, PatchStore};
+use crate::core::paths::relative_to;
+use crate::core::project::load_existing_code;
 use crate::core::repo::LitRepo;
 use crate::core::style;
 
-pub async fn run(ref_: String) -> Result<()> {
+pub async fn run(ref_: String, force: bool, keep_patches: bool) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
 
-    let repo = LitRepo::open(&root)?;
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+    let code_dir = Path::new(&config.project.code_dir);
 
-    // Check for uncommitted changes first
-    let status = repo.status()?;
-    if status.has_changes() {
+    // Refuse to lose uncommitted work, except what --keep-patches carries over
+    let mut carried = Vec::new();
+    let mut lost = Vec::new();
+    for path in repo.checkout_conflicts(&ref_)? {
+        let hand_edit = path.starts_with(code_dir) && root.join(&path).is_file() && repo.file_at("HEAD", &path)?.is_some();
+        if keep_patches && (path.starts_with(".lit/patches") || hand_edit) {
+            carried.push(path);
+        } else {
+            lost.push(path);
+        }
+    }
+    if !lost.is_empty() && !force {
         anyhow::bail!(
-            "You have uncommitted changes ({} file(s)).\n\
-             Hint: Commit them first with `lit commit -m \"message\"`, or see `lit status`.",
-            status.total_changes()
+            "Checking out '{}' would discard uncommitted changes to {} file(s):\n{}\n\
+             Hint: Commit them first with `lit commit -m \"message\"`, carry code.lock/ hand edits and \
+             .lit/patches/ over with --keep-patches, or discard them with --force.",
+            ref_,
+            lost.len(),
+            lost.iter().map(|p| format!("  {}", p.display())).collect::<Vec<_>>().join("\n")
         );
     }
 
-    repo.checkout_ref(&ref_)?;
+    let kept = if keep_patches {
+        Some(KeptWork::collect(&root, &repo, code_dir, &carried)?)
+    } else {
+        None
+    };
+
+    repo.checkout_ref(&ref_, true)?;
 
     // Show where we landed
     if let Some(head) = repo.head_commit() {
@@ -39,5 +65,91 @@ pub async fn run(ref_: String) -> Result<()> {
         );
     }
 
+    if let Some(kept) = kept {
+        kept.restore(&root, &config.code_dir(&root))?;
+    }
+
     Ok(())
 }
+
+/// Uncommitted work carried across a checkout by `--keep-patches`
+struct KeptWork {
+    /// Every file under .lit/patches/, by path relative to it
+    patch_files: HashMap<PathBuf, String>,
+    /// Hand edits to code.lock/ files: (path under code.lock/, generated
+    /// content the edit started from, edited content)
+    hand_edits: Vec<(PathBuf, String, String)>,
+}
+
+impl KeptWork {
+    fn collect(root: &Path, repo: &LitRepo, code_dir: &Path, carried: &[PathBuf]) -> Result<Self> {
+        let patches_dir = root.join(".lit").join("patches");
+        let mut patch_files = load_existing_code(&patches_dir);
+        // Lock and temp files belong to whoever is writing the index
+        patch_files.retain(|path, _| {
+            let name = path.to_string_lossy();
+            !name.ends_with(".lock") && !name.contains(".tmp")
+        });
+
+        let store = PatchStore::new(patches_dir);
+        let mut hand_edits = Vec::new();
+        for path in carried.iter().filter(|p| p.starts_with(code_dir)) {
+            let output = relative_to(path, code_dir);
+            let edited = std::fs::read_to_string(root.join(path))
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            // An already-patched file's edit is measured from what was
+            // generated, not from the committed (patched) version
+            let original = match store.load_patch(&output) {
+                Some(patch) => patch.original_content,
+                None => repo.file_at("HEAD", path)?.unwrap_or_default(),
+            };
+            hand_edits.push((output, original, edited));
+        }
+        hand_edits.sort();
+        Ok(Self { patch_files, hand_edits })
+    }
+
+    /// Put .lit/patches/ back as it was, save each hand edit as a patch and
+    /// merge it into the checked-out code
+    fn restore(self, root: &Path, code_lock: &Path) -> Result<()> {
+        let patches_dir = root.join(".lit").join("patches");
+        if patches_dir.exists() {
+            std::fs::remove_dir_all(&patches_dir)
+                .with_context(|| format!("Failed to clear {}", patches_dir.display()))?;
+        }
+        for (path, content) in &self.patch_files {
+            let full = patches_dir.join(path);
+            if let Some(parent) = full.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            atomic::write(&full, content).with_context(|| format!("Failed to restore {}", full.display()))?;
+        }
+        if !self.patch_files.is_empty() {
+            eprintln!("  {} .lit/patches/", "Kept".dimmed());
+        }
+
+        let store = PatchStore::new(patches_dir);
+        for (output, original, edited) in &self.hand_edits {
+            store.save_patch(output, original, edited)?;
+            let target = code_lock.join(output);
+            let Ok(current) = std::fs::read_to_string(&target) else {
+                eprintln!("  {} {} {}", "Kept".dimmed(), output.display(), "(as a patch; not in this commit)".dimmed());
+                continue;
+            };
+            match store.apply_patch(original, &current, edited) {
+                PatchResult::Applied(merged) => {
+                    atomic::write(&target, merged)
+                        .with_context(|| format!("Failed to write {}", target.display()))?;
+                    eprintln!("{}", style::patch_applied(&output.display().to_string()));
+                }
+                PatchResult::Conflict(merged) => {
+                    atomic::write(&target, merged)
+                        .with_context(|| format!("Failed to write {}", target.display()))?;
+                    store.set_status(output, PatchStatus::Conflicted)?;
+                    eprintln!("{}", style::patch_conflict(&output.display().to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,62 +1,133 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use crate::core::cache::Cache;
 use crate::core::config::LitConfig;
+use crate::core::generation_record::GenerationRecord;
+use crate::core::manifest::Manifest;
+use crate::core::patch::PatchStore;
+use crate::core::project::{load_code, load_prompts, stale_prompts};
+use crate::core::prompt::TagFilter;
+use crate::core::repo::LitRepo;
+use crate::core::style;
 
 /// Clone a lit repository from a remote URL.
 ///
-/// Shells out to `git clone` for transport, then validates the result
-/// is a valid lit project (has lit.toml).
-pub async fn run(url: String) -> Result<()> {
-    eprintln!("Cloning {}...", url);
-
-    let output = Command::new("git")
-        .args(["clone", &url])
-        .output()
-        .context("Failed to run `git clone`. Is git installed?")?;
-
-    // Forward git's output
-    if !output.stdout.is_empty() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        eprint!("{}", stdout);
-    }
-    if !output.stderr.is_empty() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprint!("{}", stderr);
+/// Clones over git2 (credentials as for `lit push`), then bootstraps the local lit
+/// state: validates lit.toml, creates `.lit/` and its cache, builds the
+/// DAG and reports whether code.lock/ is current. With `warm_cache`, cache
+/// entries are rebuilt from the committed code.lock/ and manifest so the
+/// first `lit regenerate` only calls the LLM for what actually changed.
+pub async fn run(url: String, directory: Option<PathBuf>, warm_cache: bool) -> Result<()> {
+    // Without an explicit directory, name it after the URL as git does
+    let repo_name = match directory {
+        Some(dir) => dir,
+        None => PathBuf::from(
+            url.trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or("repo")
+                .trim_end_matches(".git"),
+        ),
+    };
+    let clone_dir = std::env::current_dir()?.join(&repo_name);
+    if clone_dir.exists() && clone_dir.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        anyhow::bail!("Destination {} already exists and is not empty", repo_name.display());
     }
 
-    if !output.status.success() {
-        anyhow::bail!("git clone failed (exit code: {:?})", output.status.code());
+    eprintln!("Cloning {} into {}...", url, repo_name.display());
+    LitRepo::clone(&url, &clone_dir)?;
+
+    // Validate it's a lit project
+    eprintln!();
+    if !clone_dir.join("lit.toml").exists() {
+        eprintln!(
+            "{}",
+            style::warning("Cloned repository does not appear to be a lit project (no lit.toml found).")
+        );
+        eprintln!("  You can initialize it with: cd {} && lit init", repo_name.display());
+        return Ok(());
     }
+    let config = match LitConfig::from_file(&clone_dir.join("lit.toml")) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", style::warning(&format!("lit.toml is invalid: {:#}", e)));
+            eprintln!("{}", style::hint("Fix it, or run `lit migrate` if it was written by an older lit."));
+            return Ok(());
+        }
+    };
 
-    // Try to figure out the clone directory name from the URL
-    let repo_name = url
-        .trim_end_matches('/')
-        .rsplit('/')
-        .next()
-        .unwrap_or("repo")
-        .trim_end_matches(".git");
+    eprintln!(
+        "{}",
+        style::project_header(&config.project.name, &config.project.version)
+    );
+    bootstrap(&config, &clone_dir, warm_cache)?;
+    eprintln!();
+    eprintln!("  cd {} && lit status", repo_name.display());
+    Ok(())
+}
 
-    let clone_dir = std::env::current_dir()?.join(repo_name);
+/// Create the local-only state a clone lacks and report on the project
+fn bootstrap(config: &LitConfig, root: &Path, warm_cache: bool) -> Result<()> {
+    let lit_dir = root.join(".lit");
+    std::fs::create_dir_all(config.code_dir(root))
+        .context("Failed to create code.lock/ directory")?;
+    let cache = Cache::new(lit_dir.join("cache")).with_config(config.cache.clone());
+    cache.init().context("Failed to initialize cache directory")?;
 
-    // Validate it's a lit project
-    match LitConfig::find_and_load(&clone_dir) {
-        Ok((config, _root)) => {
-            eprintln!();
-            eprintln!(
-                "Cloned lit project: {} v{}",
-                config.project.name, config.project.version
-            );
-            eprintln!("  cd {} && lit status", repo_name);
+    let (prompts, dag) = match load_prompts(config, root) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("  {}", style::warning(&format!("Prompts don't form a valid DAG: {:#}", e)));
+            return Ok(());
         }
-        Err(_) => {
-            eprintln!();
+    };
+    eprintln!(
+        "  {:<20} {} ({} level(s))",
+        "Prompts:".dimmed(),
+        prompts.len().to_string().bold(),
+        dag.levels().len()
+    );
+
+    let code = load_code(config, root);
+    eprintln!("  {:<20} {}", "code.lock/ files:".dimmed(), code.len());
+
+    let Some(manifest) = Manifest::load(&lit_dir)? else {
+        eprintln!("  {:<20} {}", "Manifest:".dimmed(), "none committed".dimmed());
+        eprintln!();
+        eprintln!("{}", style::hint("Run `lit regenerate` to build code.lock/ and its manifest."));
+        return Ok(());
+    };
+
+    if warm_cache {
+        let patch_store = PatchStore::new(lit_dir.join("patches"));
+        let originals: HashMap<PathBuf, String> = manifest
+            .outputs
+            .iter()
+            .filter(|(_, entry)| entry.patch.is_some())
+            .filter_map(|(path, _)| Some((path.clone(), patch_store.load_patch(path)?.original_content)))
+            .collect();
+        let records = GenerationRecord::list(&lit_dir.join("generations"))?;
+        let warmed = cache.warm_from_manifest(&manifest, &code, &originals, &records)?;
+        eprintln!("  {:<20} {} entr(ies) from code.lock/", "Cache warmed:".dimmed(), warmed);
+    }
+
+    match stale_prompts(config, root, &TagFilter::default())? {
+        Some(stale) if !stale.is_empty() => {
             eprintln!(
-                "Warning: Cloned repository does not appear to be a lit project (no lit.toml found)."
+                "  {:<20} {} prompt(s) differ from code.lock/",
+                "Stale:".dimmed(),
+                stale.len().to_string().yellow()
             );
-            eprintln!("  You can initialize it with: cd {} && lit init", repo_name);
+            for path in &stale {
+                eprintln!("{}", style::file_modified(&path.display().to_string()));
+            }
+            eprintln!();
+            eprintln!("{}", style::hint("Run `lit regenerate` to bring code.lock/ up to date."));
         }
+        _ => eprintln!("{}", style::success("code.lock/ is up to date with the prompts")),
     }
-
     Ok(())
 }
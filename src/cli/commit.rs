@@ -1,30 +1,48 @@
-use anyhow::Result;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
 use colored::Colorize;
 
-use crate::core::config::LitConfig;
+use crate::api::{Lit, RegenerateOptions};
+use crate::cli::regenerate::{self, TerminalOptions};
+use crate::core::generation_record::report_budget;
 use crate::core::repo::LitRepo;
 use crate::core::style;
 
-pub async fn run(message: String) -> Result<()> {
+/// `lit commit` — stage lit files and commit them through [`Lit::commit`].
+///
+/// With `regenerate` (or `[commit] regenerate = true` when not given), prompts
+/// changed since HEAD and their dependents are regenerated first.
+pub async fn run(message: String, allow_stale: bool, regenerate: Option<bool>) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
-
-    // Open git repo
-    let repo = LitRepo::open(&root)?;
+    let lit = Lit::open(&cwd)?;
+    let (config, root) = (lit.config(), lit.root());
 
-    // Stage all lit-related files
-    repo.stage_all()?;
+    let regenerate_first = regenerate.unwrap_or(config.commit.regenerate);
+    if regenerate_first {
+        let status = LitRepo::open(root)?.with_project_dirs(&config.project).status()?;
+        let changed: Vec<PathBuf> = status
+            .prompts_new
+            .into_iter()
+            .chain(status.prompts_modified)
+            .filter(|p| root.join(p).is_file())
+            .collect();
+        if changed.is_empty() {
+            eprintln!("{}", "No prompt changes to regenerate.".dimmed());
+        } else {
+            regenerate::run(RegenerateOptions { prompts: changed, ..Default::default() }, TerminalOptions::UNATTENDED)
+                .await
+                .context("Regeneration failed; nothing was committed")?;
+            eprintln!();
+        }
+    }
 
-    // Check if there are changes to commit
-    let status = repo.status()?;
-    if !status.has_changes() {
+    let Some(commit) = lit.commit(&message, allow_stale)? else {
         eprintln!("{}", "Nothing to commit (working tree clean).".dimmed());
         eprintln!("{}", style::hint("Hint: Run `lit regenerate` to generate code, then commit."));
         return Ok(());
-    }
-
-    // Create commit
-    let hash = repo.commit(&message)?;
+    };
+    let (hash, status, records) = (commit.hash, commit.changes, commit.records);
 
     // Summary
     eprintln!(
@@ -49,5 +67,12 @@ pub async fn run(message: String) -> Result<()> {
     }
     eprintln!("  Total:     {} file(s)", status.total_changes().to_string().bold());
 
+    // Check the most expensive run being committed against the budget
+    let run_cost = records.iter().map(|r| r.summary.total_cost_usd).reduce(f64::max);
+    // regenerate has already reported this run
+    if !regenerate_first {
+        report_budget(config, root, run_cost, false).await;
+    }
+
     Ok(())
 }
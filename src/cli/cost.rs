@@ -1,24 +1,95 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
 use colored::Colorize;
 
+use crate::core::cache::Cache;
 use crate::core::config::LitConfig;
-use crate::core::generation_record::{GenerationRecord, format_cost, format_tokens};
+use crate::core::context::dedupe_context;
+use crate::core::dag::Dag;
+use crate::core::generation_record::{
+    CostSummary, GenerationRecord, InputBreakdown, estimate_cost, format_cost, format_prompt_cache,
+    cost_rows, cost_rows_csv, format_tokens, prompt_cost_totals, prompt_history,
+};
+use crate::core::generator::{
+    build_request, context_window, current_input_hashes, gather_context,
+    prune_context_offline, request_token_breakdown,
+};
+use crate::core::paths::relative_to;
+use crate::core::project::load_code;
+use crate::core::prompt::{Prompt, TagFilter, discover_project_prompts, resolve_imports};
+use crate::core::repo::{CommitInfo, LitRepo};
 use crate::core::style;
+use crate::core::tokens::estimate_tokens;
+
 
-pub async fn run(last: bool, breakdown: bool) -> Result<()> {
+/// Formats for `lit cost --export`
+pub const EXPORT_FORMATS: &[&str] = &["csv", "json"];
+
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    last: bool,
+    breakdown: bool,
+    estimate: bool,
+    history: Option<PathBuf>,
+    top: Option<usize>,
+    export: Option<String>,
+    commit: Option<String>,
+    tags: TagFilter,
+) -> Result<()> {
+    if let Some(ref format) = export
+        && !EXPORT_FORMATS.contains(&format.as_str())
+    {
+        anyhow::bail!(
+            "Unknown export format '{}'. Must be one of: {}",
+            format,
+            EXPORT_FORMATS.join(", ")
+        );
+    }
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    if estimate {
+        return print_estimate(&config, &root, breakdown, &tags);
+    }
+    if !tags.is_empty() {
+        anyhow::bail!(
+            "--tag and --exclude-tag only apply to --estimate\n\
+             Hint: Generation records don't keep prompt tags; try `lit cost --estimate --tag <TAG>`."
+        );
+    }
+
+    if let Some(spec) = commit {
+        let repo = LitRepo::open(&root)?;
+        return print_commits(&repo, &spec, breakdown);
+    }
 
     let generations_dir = root.join(".lit").join("generations");
     let records = GenerationRecord::list(&generations_dir)?;
 
+    // Exports go to stdout, even when empty, so they can be piped
+    if let Some(format) = export {
+        let rows = cost_rows(&records);
+        match format.as_str() {
+            "json" => println!("{}", serde_json::to_string_pretty(&rows)?),
+            _ => print!("{}", cost_rows_csv(&rows)),
+        }
+        return Ok(());
+    }
+
     if records.is_empty() {
         eprintln!("{}", "No generation records found.".dimmed());
         eprintln!("{}", style::hint("Hint: Run `lit regenerate` first."));
         return Ok(());
     }
 
-    if last {
+    if let Some(prompt) = history {
+        let prompt = relative_to(&prompt, &root);
+        print_history(&records, &prompt);
+    } else if let Some(n) = top {
+        print_top(&records, n);
+    } else if last {
         let latest = &records[0];
         print_record_summary(latest, breakdown);
     } else {
@@ -45,10 +116,11 @@ fn print_record_summary(record: &GenerationRecord, breakdown: bool) {
         record.summary.skipped.to_string().dimmed()
     );
     eprintln!(
-        "  {:<16} {} in / {} out",
+        "  {:<16} {} in / {} out{}",
         "Tokens:".dimmed(),
         format_tokens(record.summary.total_tokens_in).dimmed(),
-        format_tokens(record.summary.total_tokens_out).dimmed()
+        format_tokens(record.summary.total_tokens_out).dimmed(),
+        format_prompt_cache(&record.summary.total_prompt_cache).dimmed()
     );
     eprintln!(
         "  {:<16} {}",
@@ -60,6 +132,25 @@ fn print_record_summary(record: &GenerationRecord, breakdown: bool) {
         "Duration:".dimmed(),
         format!("{:.1}s", record.summary.total_duration_ms as f64 / 1000.0).dimmed()
     );
+    for failure in &record.failures {
+        eprintln!(
+            "  {:<16} {} — {} {}{}",
+            "Failed:".dimmed(),
+            failure.prompt_path.display(),
+            failure.error_type.red(),
+            failure
+                .status
+                .map(|s| format!("(HTTP {}) ", s))
+                .unwrap_or_default()
+                .dimmed(),
+            format!(
+                "after {} attempt(s){}",
+                failure.attempts,
+                if failure.retriable { ", retriable" } else { "" }
+            )
+            .dimmed()
+        );
+    }
 
     if breakdown {
         eprintln!();
@@ -74,65 +165,90 @@ fn print_record_summary(record: &GenerationRecord, breakdown: bool) {
                 "generated".yellow()
             };
             eprintln!(
-                "    {} ({}) — {} in / {} out — {}",
+                "    {} ({}) — {} in{} / {} out — {}",
                 p.prompt_path.display(),
                 status,
                 format_tokens(p.tokens_in).dimmed(),
+                format_prompt_cache(&p.prompt_cache).dimmed(),
                 format_tokens(p.tokens_out).dimmed(),
                 style::cost(&format_cost(p.cost_usd)),
             );
+            if let Some(ref b) = p.input_breakdown {
+                eprintln!("      {}", format_input_breakdown(b).dimmed());
+            }
         }
     }
 }
 
+/// One-line summary of where a prompt's input tokens went
+pub(crate) fn format_input_breakdown(b: &InputBreakdown) -> String {
+    let total = b.total().max(1) as f64;
+    [("system", b.system), ("context", b.context), ("prompt", b.prompt)]
+        .iter()
+        .map(|(label, tokens)| {
+            format!(
+                "{} ~{} ({:.0}%)",
+                label,
+                format_tokens(*tokens),
+                *tokens as f64 / total * 100.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
 fn print_aggregate(records: &[GenerationRecord], breakdown: bool) {
-    let total_cost: f64 = records.iter().map(|r| r.summary.total_cost_usd).sum();
-    let total_tokens_in: u64 = records.iter().map(|r| r.summary.total_tokens_in).sum();
-    let total_tokens_out: u64 = records.iter().map(|r| r.summary.total_tokens_out).sum();
-    let total_cache_hits: usize = records.iter().map(|r| r.summary.cache_hits).sum();
-    let total_cache_misses: usize = records.iter().map(|r| r.summary.cache_misses).sum();
+    let summary = CostSummary::of(records);
 
     eprintln!(
         "{}",
-        style::header(&format!("Cost Summary ({} generation(s))", records.len()))
+        style::header(&format!("Cost Summary ({} generation(s))", summary.generations))
     );
     eprintln!(
         "  {:<16} {}",
         "Total cost:".dimmed(),
-        style::cost(&format_cost(total_cost))
+        style::cost(&format_cost(summary.total_cost_usd))
     );
     eprintln!(
         "  {:<16} {} in / {} out",
         "Total tokens:".dimmed(),
-        format_tokens(total_tokens_in).dimmed(),
-        format_tokens(total_tokens_out).dimmed()
+        format_tokens(summary.total_tokens_in).dimmed(),
+        format_tokens(summary.total_tokens_out).dimmed()
     );
     eprintln!(
         "  {:<16} {} hit(s), {} miss(es)",
         "Cache:".dimmed(),
-        total_cache_hits.to_string().green(),
-        total_cache_misses.to_string().yellow()
+        summary.cache_hits.to_string().green(),
+        summary.cache_misses.to_string().yellow()
     );
+    if summary.other_cost_usd > 0.0 {
+        eprintln!(
+            "  {:<16} {} {}",
+            "Other calls:".dimmed(),
+            style::cost(&format_cost(summary.other_cost_usd)),
+            "(lit verify-determinism; not in the totals above)".dimmed()
+        );
+    }
 
-    if let Some(first) = records.last() {
+    if let Some(first) = summary.first_run {
         eprintln!(
             "  {:<16} {}",
             "First run:".dimmed(),
-            first.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string().dimmed()
+            first.format("%Y-%m-%d %H:%M:%S UTC").to_string().dimmed()
         );
     }
-    if let Some(latest) = records.first() {
+    if let Some(latest) = summary.latest_run {
         eprintln!(
             "  {:<16} {}",
             "Latest run:".dimmed(),
-            latest.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string().dimmed()
+            latest.format("%Y-%m-%d %H:%M:%S UTC").to_string().dimmed()
         );
     }
 
     if breakdown {
         eprintln!();
         eprintln!("  {}", "Per-generation breakdown:".bold());
-        for (i, record) in records.iter().enumerate() {
+        for (i, record) in records.iter().filter(|r| r.is_generation()).enumerate() {
             eprintln!(
                 "    {}. {} — {} ({} generated, {} cached) — {} in / {} out — {}",
                 (i + 1).to_string().dimmed(),
@@ -147,3 +263,349 @@ fn print_aggregate(records: &[GenerationRecord], breakdown: bool) {
         }
     }
 }
+
+/// `lit cost --history <prompt>` — one row per generation record that
+/// included the prompt, oldest first, with a running total
+fn print_history(records: &[GenerationRecord], prompt: &Path) {
+    let history = prompt_history(records, prompt);
+    eprintln!("{}", style::header(&format!("Cost History: {}", prompt.display())));
+    if history.is_empty() {
+        eprintln!("  {}", "No generation records include this prompt.".dimmed());
+        return;
+    }
+
+    eprintln!(
+        "  {:<20} {:<28} {:>10} {:>10} {:>10} {:>10}",
+        "Time".bold(),
+        "Model".bold(),
+        "In".bold(),
+        "Out".bold(),
+        "Cost".bold(),
+        "Total".bold()
+    );
+    let mut running = 0.0;
+    for (timestamp, p) in &history {
+        running += p.cost_usd;
+        let model = if p.from_cache { "(cached)".to_string() } else { p.model.clone() };
+        eprintln!(
+            "  {:<20} {:<28} {:>10} {:>10} {:>10} {:>10}",
+            timestamp.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+            model,
+            format_tokens(p.tokens_in),
+            format_tokens(p.tokens_out),
+            format_cost(p.cost_usd),
+            format_cost(running).yellow()
+        );
+    }
+
+    let generated = history.iter().filter(|(_, p)| !p.from_cache).count();
+    eprintln!();
+    eprintln!(
+        "  {} generation(s), {} cache hit(s), {} total",
+        generated.to_string().bold(),
+        (history.len() - generated).to_string().green(),
+        style::cost(&format_cost(running))
+    );
+}
+
+/// `lit cost --top N` — the prompts that have cost the most across every
+/// generation record
+fn print_top(records: &[GenerationRecord], n: usize) {
+    let totals = prompt_cost_totals(records);
+    let all_cost: f64 = totals.iter().map(|t| t.cost_usd).sum();
+    eprintln!(
+        "{}",
+        style::header(&format!("Most Expensive Prompts ({} generation record(s))", records.len()))
+    );
+    eprintln!(
+        "  {:<4} {:<44} {:>5} {:>10} {:>10} {:>10} {:>6}",
+        "#".bold(),
+        "Prompt".bold(),
+        "Runs".bold(),
+        "In".bold(),
+        "Out".bold(),
+        "Cost".bold(),
+        "Share".bold()
+    );
+    for (i, total) in totals.iter().take(n).enumerate() {
+        eprintln!(
+            "  {:<4} {:<44} {:>5} {:>10} {:>10} {:>10} {:>5.0}%",
+            (i + 1).to_string().dimmed(),
+            total.prompt_path.display(),
+            total.generations,
+            format_tokens(total.tokens_in),
+            format_tokens(total.tokens_out),
+            format_cost(total.cost_usd).yellow(),
+            if all_cost > 0.0 { total.cost_usd / all_cost * 100.0 } else { 0.0 }
+        );
+    }
+    if totals.len() > n {
+        eprintln!(
+            "  {}",
+            format!("… {} more prompt(s)", totals.len() - n).dimmed()
+        );
+    }
+    eprintln!();
+    eprintln!("  {:<16} {}", "Total cost:".dimmed(), style::cost(&format_cost(all_cost)));
+}
+
+/// The generation records a commit added under .lit/generations/, read
+/// from the commit itself. `lit commit` stages each run's record with the
+/// code it produced, so these are the runs behind that commit.
+pub fn commit_records(repo: &LitRepo, commit: &str) -> Result<Vec<GenerationRecord>> {
+    let mut records: Vec<GenerationRecord> = repo
+        .files_added_in(commit, Path::new(".lit/generations"))?
+        .into_iter()
+        .filter(|(path, _)| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|(_, content)| serde_json::from_str(&content).ok())
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    Ok(records)
+}
+
+/// `lit cost --commit <ref>` — what the generation runs committed in one
+/// commit, or a range `A..B` of them, cost
+fn print_commits(repo: &LitRepo, spec: &str, breakdown: bool) -> Result<()> {
+    let commits = repo.commits_in(spec)?;
+    let mut per_commit: Vec<(CommitInfo, Vec<GenerationRecord>)> = Vec::new();
+    for commit in commits {
+        let records = commit_records(repo, &commit.hash)?;
+        per_commit.push((commit, records));
+    }
+    let all: Vec<GenerationRecord> = per_commit.iter().flat_map(|(_, r)| r.iter().cloned()).collect();
+    // fold, not sum: an empty f64 sum is -0.0
+    let cost = |records: &[GenerationRecord]| records.iter().fold(0.0, |sum, r| sum + r.summary.total_cost_usd);
+
+    eprintln!("{}", style::header(&format!("Cost of {}", spec)));
+    eprintln!(
+        "  {:<8} {:<40} {:>5} {:>10} {:>10} {:>10}",
+        "Commit".bold(),
+        "Message".bold(),
+        "Runs".bold(),
+        "In".bold(),
+        "Out".bold(),
+        "Cost".bold()
+    );
+    for (commit, records) in &per_commit {
+        let message: String = commit.message.chars().take(40).collect();
+        eprintln!(
+            "  {:<8} {:<40} {:>5} {:>10} {:>10} {:>10}",
+            commit.short_hash.yellow(),
+            message,
+            records.len(),
+            format_tokens(records.iter().map(|r| r.summary.total_tokens_in).sum()),
+            format_tokens(records.iter().map(|r| r.summary.total_tokens_out).sum()),
+            format_cost(cost(records)).yellow()
+        );
+    }
+
+    let totals = prompt_cost_totals(&all);
+    if breakdown && !totals.is_empty() {
+        eprintln!();
+        eprintln!("{}", style::header("By Prompt"));
+        for total in totals {
+            eprintln!(
+                "  {:<49} {:>5} {:>10} {:>10} {:>10}",
+                total.prompt_path.display(),
+                total.generations,
+                format_tokens(total.tokens_in),
+                format_tokens(total.tokens_out),
+                format_cost(total.cost_usd).yellow()
+            );
+        }
+    }
+
+    eprintln!();
+    eprintln!(
+        "  {:<16} {} across {} commit(s), {} generation run(s)",
+        "Total cost:".dimmed(),
+        style::cost(&format_cost(cost(&all))),
+        per_commit.len(),
+        all.len()
+    );
+    if all.is_empty() {
+        eprintln!(
+            "{}",
+            style::hint("Hint: Generation records are committed by `lit commit`; runs not yet committed aren't counted.")
+        );
+    }
+    Ok(())
+}
+
+/// Per-prompt line of a cost estimate
+pub struct PromptEstimate {
+    pub prompt_path: PathBuf,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub cost_usd: f64,
+    pub cached: bool,
+    pub context_window: u64,
+    pub input_breakdown: InputBreakdown,
+}
+
+/// Estimate generating `paths` (in DAG order) without calling the LLM.
+///
+/// Requests are assembled against `existing_code`, a prompt is cached when
+/// `cache` has a fresh entry for its current input hash, and output tokens
+/// are approximated by the current size of its outputs.
+pub fn estimate_prompts(
+    config: &LitConfig,
+    dag: &Dag,
+    prompts: &HashMap<PathBuf, Prompt>,
+    existing_code: &HashMap<PathBuf, String>,
+    cache: Option<&Cache>,
+    paths: &[PathBuf],
+) -> Vec<PromptEstimate> {
+    let today = chrono::Utc::now().date_naive();
+    let input_hashes = current_input_hashes(config, dag, prompts, existing_code);
+    let mut estimates = Vec::new();
+    for prompt_path in dag.order().iter().filter(|p| paths.contains(p)) {
+        let prompt = &prompts[prompt_path];
+
+        let cached = cache.is_some_and(|cache| {
+            cache
+                .get(&input_hashes[prompt_path])
+                .is_some_and(|entry| cache.stale_reason(&entry).is_none())
+        });
+
+        let (context, _) = dedupe_context(gather_context(prompt, prompts, existing_code));
+        let context = prune_context_offline(config, context);
+        let request = build_request(config, prompt, &context);
+        let input_breakdown = request_token_breakdown(&request);
+        let tokens_in = input_breakdown.total();
+        let tokens_out: u64 = prompt
+            .frontmatter
+            .outputs
+            .iter()
+            .filter_map(|o| existing_code.get(o))
+            .map(|code| estimate_tokens(code))
+            .sum();
+        let cost_usd = if cached {
+            0.0
+        } else {
+            estimate_cost(
+                &request.model,
+                tokens_in,
+                tokens_out,
+                Default::default(),
+                config.model_pricing(&request.model, today).as_ref(),
+            )
+        };
+
+        estimates.push(PromptEstimate {
+            prompt_path: prompt_path.clone(),
+            tokens_in,
+            tokens_out,
+            cost_usd,
+            cached,
+            context_window: context_window(config, &request.model),
+            input_breakdown,
+        });
+    }
+    estimates
+}
+
+/// `lit cost --estimate` — predict the cost of regenerating every prompt
+/// without calling the LLM.
+///
+/// Input tokens come from the assembled request (using code.lock/ as the
+/// imports' context); output tokens are approximated by the current size of
+/// each prompt's outputs. Prompts with a valid cache entry cost nothing.
+fn print_estimate(config: &LitConfig, root: &Path, breakdown: bool, tags: &TagFilter) -> Result<()> {
+    let prompt_paths = discover_project_prompts(config, root)?;
+    if prompt_paths.is_empty() {
+        eprintln!("{}", "No prompts found.".dimmed());
+        return Ok(());
+    }
+
+    let mut prompts_vec = Vec::new();
+    for p in &prompt_paths {
+        prompts_vec.push(
+            Prompt::from_file(p, root, config)
+                .with_context(|| format!("Failed to parse {}", p.display()))?,
+        );
+    }
+    resolve_imports(&mut prompts_vec)?;
+    let dag = Dag::build(&prompts_vec)?;
+    let prompts: HashMap<PathBuf, Prompt> = prompts_vec
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect();
+
+    let existing_code = load_code(config, root);
+    let cache = Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone());
+    let selected: Vec<PathBuf> = dag
+        .order()
+        .iter()
+        .filter(|p| tags.matches(&prompts[*p]))
+        .cloned()
+        .collect();
+    let estimates = estimate_prompts(config, &dag, &prompts, &existing_code, Some(&cache), &selected);
+
+    let to_generate: Vec<&PromptEstimate> = estimates.iter().filter(|e| !e.cached).collect();
+    let total_in: u64 = to_generate.iter().map(|e| e.tokens_in).sum();
+    let total_out: u64 = to_generate.iter().map(|e| e.tokens_out).sum();
+    let total_cost: f64 = to_generate.iter().map(|e| e.cost_usd).sum();
+
+    eprintln!("{}", style::header("Cost Estimate (full regeneration)"));
+    eprintln!(
+        "  {:<16} {} total ({} to generate, {} cached)",
+        "Prompts:".dimmed(),
+        estimates.len().to_string().bold(),
+        to_generate.len().to_string().yellow(),
+        (estimates.len() - to_generate.len()).to_string().green()
+    );
+    eprintln!(
+        "  {:<16} ~{} in / ~{} out",
+        "Tokens:".dimmed(),
+        format_tokens(total_in).dimmed(),
+        format_tokens(total_out).dimmed()
+    );
+    eprintln!(
+        "  {:<16} ~{}",
+        "Cost:".dimmed(),
+        style::cost(&format_cost(total_cost))
+    );
+
+    for e in &estimates {
+        if e.tokens_in > e.context_window {
+            eprintln!(
+                "  {}",
+                style::warning(&format!(
+                    "{} needs ~{} input tokens, over the {} token context window",
+                    e.prompt_path.display(),
+                    format_tokens(e.tokens_in),
+                    format_tokens(e.context_window)
+                ))
+            );
+        }
+    }
+
+    if breakdown {
+        eprintln!();
+        eprintln!("  {}", "Per-prompt estimate:".bold());
+        for e in &estimates {
+            let status = if e.cached {
+                "cached".green()
+            } else {
+                "generate".yellow()
+            };
+            eprintln!(
+                "    {} ({}) — ~{} in / ~{} out — {}",
+                e.prompt_path.display(),
+                status,
+                format_tokens(e.tokens_in).dimmed(),
+                format_tokens(e.tokens_out).dimmed(),
+                style::cost(&format_cost(e.cost_usd)),
+            );
+            eprintln!("      {}", format_input_breakdown(&e.input_breakdown).dimmed());
+        }
+    }
+
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::hint("Output tokens are approximated from the current code.lock/ contents.")
+    );
+    Ok(())
+}
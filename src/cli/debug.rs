@@ -1,7 +1,21 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use crate::cli::DebugCommands;
-use crate::core::config::LitConfig;
+use crate::core::cache::Cache;
+use crate::core::config::{CacheFormat, ContextStrategy, LitConfig};
+use crate::core::context::dedupe_context;
 use crate::core::dag::Dag;
-use crate::core::prompt::{Prompt, discover_prompts};
+use crate::core::generation_record::format_tokens;
+use crate::core::generator::{
+    current_input_hashes, gather_context, hash_components, import_hashes, prompt_input_hash,
+    prune_context_offline,
+};
+use crate::core::manifest::Manifest;
+use crate::core::project::load_code;
+use crate::core::prompt::{Prompt, discover_project_prompts, resolve_imports};
+use crate::core::tokens::estimate_tokens;
+use crate::core::transcript::TranscriptStore;
 
 pub async fn run(what: DebugCommands) -> anyhow::Result<()> {
     let cwd = std::env::current_dir()?;
@@ -10,13 +24,21 @@ pub async fn run(what: DebugCommands) -> anyhow::Result<()> {
     match what {
         DebugCommands::Config => dump_config(&config, &root),
         DebugCommands::Prompts => dump_prompts(&config, &root)?,
-        DebugCommands::Dag => dump_dag(&config, &root)?,
+        DebugCommands::Dag { focus: None } => dump_dag(&config, &root)?,
+        DebugCommands::Dag { focus: Some(prompt) } => dump_dag_focus(&config, &root, &prompt)?,
+        DebugCommands::Context { prompt } => dump_context(&config, &root, prompt.as_deref())?,
+        DebugCommands::Transcript { prompt } => dump_transcript(&config, &root, &prompt)?,
+        DebugCommands::Cache { prompt } => dump_cache(&config, &root, prompt.as_deref())?,
         DebugCommands::All => {
             dump_config(&config, &root);
             println!();
             dump_prompts(&config, &root)?;
             println!();
             dump_dag(&config, &root)?;
+            println!();
+            dump_context(&config, &root, None)?;
+            println!();
+            dump_cache(&config, &root, None)?;
         }
     }
 
@@ -37,6 +59,22 @@ fn dump_config(config: &LitConfig, root: &std::path::Path) {
     } else {
         println!("  framework:          (none)");
     }
+    for profile in &config.language.profiles {
+        println!(
+            "  language.profile:   [{}] → {} {}{}",
+            profile.paths.join(", "),
+            profile.language,
+            profile.version,
+            profile
+                .framework
+                .as_ref()
+                .map(|fw| format!(" ({} {})", fw.name, fw.version))
+                .unwrap_or_default()
+        );
+    }
+    for (dir, template) in &config.layout {
+        println!("  layout:             {} → {}", dir, template);
+    }
     println!("  model.provider:     {}", config.model.provider);
     println!("  model.model:        {}", config.model.model);
     println!("  model.temperature:  {}", config.model.temperature);
@@ -48,12 +86,39 @@ fn dump_config(config: &LitConfig, root: &std::path::Path) {
             .map(|s| s.to_string())
             .unwrap_or_else(|| "(none)".to_string())
     );
+    if !config.pricing_table.models.is_empty() {
+        println!("  pricing file entries: {}", config.pricing_table.models.len());
+    }
     if let Some(ref api) = config.model.api {
         let key_status = std::env::var(&api.key_env)
             .map(|k| format!("set ({}...)", &k[..k.len().min(8)]))
             .unwrap_or_else(|_| "NOT SET".to_string());
         println!("  model.api.key_env:  {} [{}]", api.key_env, key_status);
     }
+    if config.generation.system_prompt_template.is_some() {
+        println!("  generation.system_prompt_template: (custom)");
+    }
+    if let Some(ref conventions) = config.generation.conventions_content {
+        println!(
+            "  generation.conventions: {} ({} chars)",
+            config
+                .generation
+                .conventions
+                .as_deref()
+                .unwrap_or(std::path::Path::new("conventions.md"))
+                .display(),
+            conventions.len()
+        );
+    }
+    if config.generation.context_strategy != ContextStrategy::Full {
+        println!(
+            "  generation.context_strategy: {}",
+            config.generation.context_strategy.as_str()
+        );
+    }
+    if config.generation.interfaces {
+        println!("  generation.interfaces: on (.lit/interfaces/)");
+    }
     println!("  repo root:          {}", root.display());
 }
 
@@ -61,16 +126,14 @@ fn dump_prompts(
     config: &LitConfig,
     root: &std::path::Path,
 ) -> anyhow::Result<()> {
-    let prompts_dir = root.join("prompts");
-    if !prompts_dir.exists() {
+    let prompt_paths = discover_project_prompts(config, root)?;
+    if prompt_paths.is_empty() {
         println!("=== PROMPTS ===");
         println!();
-        println!("  (no prompts/ directory found)");
+        println!("  (no prompts found in {})", config.project.prompt_dirs_display());
         return Ok(());
     }
 
-    let prompt_paths = discover_prompts(&prompts_dir)?;
-
     println!("=== PROMPTS ({} files) ===", prompt_paths.len());
     println!();
 
@@ -109,9 +172,27 @@ fn dump_prompts(
                 if let Some(ref model) = prompt.frontmatter.model {
                     println!("    model override: {} ({})", model.model, model.provider);
                 }
+                for fallback in &prompt.frontmatter.fallback_models {
+                    println!("    fallback model: {} ({})", fallback.model, fallback.provider);
+                }
                 if let Some(ref lang) = prompt.frontmatter.language {
                     println!("    language override: {}", lang);
                 }
+                let resolved = config.resolve_language(&prompt.path, None);
+                if let Some(i) = resolved.profile {
+                    println!(
+                        "    language profile: {} {} (profile {})",
+                        resolved.language,
+                        resolved.version,
+                        i + 1
+                    );
+                }
+                if let Some(ref system) = prompt.frontmatter.system {
+                    println!("    system instructions: {} chars", system.len());
+                }
+                if !prompt.frontmatter.tags.is_empty() {
+                    println!("    tags: {}", prompt.frontmatter.tags.join(", "));
+                }
 
                 let body_imports = prompt.body_imports();
                 if !body_imports.is_empty() {
@@ -124,6 +205,17 @@ fn dump_prompts(
                             .join(", ")
                     );
                 }
+                if !prompt.includes.is_empty() {
+                    println!(
+                        "    @include() partials: [{}]",
+                        prompt
+                            .includes
+                            .keys()
+                            .map(|i| i.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
                 println!("    body: {} chars, {} lines",
                     prompt.body.len(),
                     prompt.body.lines().count()
@@ -148,15 +240,13 @@ fn dump_dag(
     config: &LitConfig,
     root: &std::path::Path,
 ) -> anyhow::Result<()> {
-    let prompts_dir = root.join("prompts");
-    if !prompts_dir.exists() {
+    let prompt_paths = discover_project_prompts(config, root)?;
+    if prompt_paths.is_empty() {
         println!("=== DAG ===");
         println!();
-        println!("  (no prompts/ directory found)");
+        println!("  (no prompts found in {})", config.project.prompt_dirs_display());
         return Ok(());
     }
-
-    let prompt_paths = discover_prompts(&prompts_dir)?;
     let mut prompts = Vec::new();
     for path in &prompt_paths {
         if let Ok(prompt) = Prompt::from_file(path, root, config) {
@@ -168,7 +258,7 @@ fn dump_dag(
     println!("=== DAG ===" );
     println!();
 
-    match Dag::build(&prompts) {
+    match resolve_imports(&mut prompts).and_then(|_| Dag::build(&prompts)) {
         Ok(dag) => {
             // Generation order (topological sort)
             println!("  Generation order ({} prompts):", dag.len());
@@ -228,7 +318,7 @@ fn dump_dag(
 
             // Regeneration examples
             for root in &roots {
-                let regen = dag.regeneration_set(&[root.prompt_path.clone()]);
+                let regen = dag.regeneration_set(std::slice::from_ref(&root.prompt_path));
                 println!(
                     "  If {} changes → {} prompt(s) need regeneration",
                     root.prompt_path.display(),
@@ -250,3 +340,322 @@ fn dump_dag(
 
     Ok(())
 }
+
+/// Show one prompt's upstream and downstream instead of the whole DAG.
+fn dump_dag_focus(config: &LitConfig, root: &Path, focus: &Path) -> anyhow::Result<()> {
+    let mut prompts = Vec::new();
+    for path in &discover_project_prompts(config, root)? {
+        if let Ok(prompt) = Prompt::from_file(path, root, config) {
+            prompts.push(prompt);
+        }
+    }
+    resolve_imports(&mut prompts)?;
+    let dag = Dag::build(&prompts)?;
+
+    let focus = focus.to_path_buf();
+    let Some(depth) = dag.depth(&focus) else {
+        anyhow::bail!("Prompt not found in DAG: {}", focus.display());
+    };
+    let ancestors = dag.ancestors(&focus);
+    let descendants = dag.descendants(&focus);
+
+    println!("=== DAG (focus: {}) ===", focus.display());
+    println!();
+    println!("  Depth: {}", depth);
+    println!();
+
+    println!("  Upstream ({}):", ancestors.len());
+    for path in &ancestors {
+        println!("    ← {} (depth {})", path.display(), dag.depth(path).unwrap_or(0));
+    }
+    println!();
+
+    println!("  Downstream ({}):", descendants.len());
+    for path in &descendants {
+        println!("    → {} (depth {})", path.display(), dag.depth(path).unwrap_or(0));
+    }
+    println!();
+
+    let members: Vec<PathBuf> = ancestors
+        .iter()
+        .chain(std::iter::once(&focus))
+        .chain(descendants.iter())
+        .cloned()
+        .collect();
+    let sub = dag.subgraph(&members);
+    println!("  Edges:");
+    for path in sub.order() {
+        if let Some(node) = sub.get(path) {
+            for import in &node.imports {
+                println!("    {} ← {}", path.display(), import.display());
+            }
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Show the context each prompt would receive, built from code.lock/:
+/// files after deduplication and the offline context strategy, with token counts.
+fn dump_context(config: &LitConfig, root: &Path, only: Option<&Path>) -> anyhow::Result<()> {
+    println!(
+        "=== CONTEXT (strategy: {}) ===",
+        config.generation.context_strategy.as_str()
+    );
+    println!();
+
+    let prompt_paths = discover_project_prompts(config, root)?;
+    if prompt_paths.is_empty() {
+        println!("  (no prompts found in {})", config.project.prompt_dirs_display());
+        return Ok(());
+    }
+
+    let mut prompts_vec = Vec::new();
+    for path in &prompt_paths {
+        if let Ok(prompt) = Prompt::from_file(path, root, config) {
+            prompts_vec.push(prompt);
+        }
+    }
+    resolve_imports(&mut prompts_vec)?;
+    let dag = Dag::build(&prompts_vec)?;
+    let prompts: HashMap<PathBuf, Prompt> = prompts_vec
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect();
+
+    if let Some(path) = only
+        && !prompts.contains_key(path)
+    {
+        anyhow::bail!("Prompt not found: {}", path.display());
+    }
+
+    if config.generation.context_strategy == ContextStrategy::Summary {
+        println!("  (summaries are written at generation time; showing full file content)");
+        println!();
+    }
+
+    let existing_code = load_code(config, root);
+    for prompt_path in dag.order() {
+        if only.is_some_and(|p| p != prompt_path.as_path()) {
+            continue;
+        }
+        let prompt = &prompts[prompt_path];
+
+        let (context, duplicates) =
+            dedupe_context(gather_context(prompt, &prompts, &existing_code));
+        let context = prune_context_offline(config, context);
+
+        let mut files: Vec<_> = context
+            .iter()
+            .map(|(path, content)| (path, estimate_tokens(content)))
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        let total: u64 = files.iter().map(|(_, tokens)| tokens).sum();
+
+        println!(
+            "  {} ({} file(s), {} tokens)",
+            prompt_path.display(),
+            files.len(),
+            format_tokens(total)
+        );
+        for (path, tokens) in &files {
+            println!("    {:<40} {:>8} tokens", path.display(), format_tokens(*tokens));
+        }
+        for (dropped, kept) in &duplicates {
+            println!(
+                "    {:<40} duplicate of {} (dropped)",
+                dropped.display(),
+                kept.display()
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Show each prompt's cache state: its current input hash, the entry stored
+/// for it (size, age, model) or why it will miss, and what the hash is
+/// computed from. A change to any of those, including an import's output,
+/// gives a new hash and so a miss.
+fn dump_cache(config: &LitConfig, root: &Path, only: Option<&Path>) -> anyhow::Result<()> {
+    let lit_dir = root.join(".lit");
+    let cache = Cache::new(lit_dir.join("cache")).with_config(config.cache.clone());
+    let format = match config.cache.format {
+        CacheFormat::Json => "json",
+        CacheFormat::Binary => "binary",
+    };
+    println!("=== CACHE (.lit/cache, {}) ===", format);
+    println!();
+
+    let mut prompts_vec = Vec::new();
+    for path in &discover_project_prompts(config, root)? {
+        if let Ok(prompt) = Prompt::from_file(path, root, config) {
+            prompts_vec.push(prompt);
+        }
+    }
+    resolve_imports(&mut prompts_vec)?;
+    let dag = Dag::build(&prompts_vec)?;
+    let prompts: HashMap<PathBuf, Prompt> = prompts_vec
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect();
+    if let Some(path) = only
+        && !prompts.contains_key(path)
+    {
+        anyhow::bail!("Prompt not found: {}", path.display());
+    }
+
+    let existing_code = load_code(config, root);
+    let manifest = Manifest::load(&lit_dir)?;
+    let now = chrono::Utc::now();
+    let mut input_hashes = HashMap::new();
+    for prompt_path in dag.order() {
+        let prompt = &prompts[prompt_path];
+        let imports = import_hashes(config, prompt, &prompts, &input_hashes, &existing_code);
+        let input_hash = prompt_input_hash(config, prompt, &imports);
+        input_hashes.insert(prompt_path.clone(), input_hash.clone());
+        if only.is_some_and(|p| p != prompt_path.as_path()) {
+            continue;
+        }
+
+        println!("  {}", prompt_path.display());
+        println!("    input hash:   {}", input_hash);
+        let entry = match cache.peek(&input_hash) {
+            None => "none (a miss: regenerating calls the LLM)".to_string(),
+            Some(entry) => {
+                let mut details = Vec::new();
+                if let Some(size) = cache.entry_file(&input_hash).and_then(|f| std::fs::metadata(f).ok()) {
+                    details.push(format_size(size.len()));
+                }
+                if let Some(created) = entry.created_at {
+                    details.push(format!("{} old", format_age(now - created)));
+                }
+                details.push(entry.model.clone().unwrap_or_else(|| "model unknown".to_string()));
+                details.push(format!("{} file(s)", entry.files.len()));
+                match cache.stale_reason(&entry) {
+                    Some(reason) => format!("expired, {} ({})", reason, details.join(", ")),
+                    None => format!("hit ({})", details.join(", ")),
+                }
+            }
+        };
+        println!("    entry:        {}", entry);
+        let recorded = manifest.as_ref().and_then(|m| {
+            prompt.frontmatter.outputs.iter().find_map(|output| m.owner(output)).map(|e| e.input_hash.as_str())
+        });
+        match recorded {
+            Some(hash) if hash == input_hash => println!("    code.lock/:   generated from this hash"),
+            Some(hash) => println!("    code.lock/:   generated from {} (inputs changed since)", short(hash)),
+            None => {}
+        }
+
+        let components = hash_components(config, prompt, &imports);
+        println!("    hashed from:");
+        println!("      {:<10} {}", "prompt", short(&components.prompt));
+        println!("      {:<10} {}", "model", components.model);
+        println!("      {:<10} {}", "language", components.language);
+        for (import, hash) in &components.imports {
+            println!("      {:<10} {}  {}", "import", import.display(), short(hash));
+        }
+        for (label, hash) in &components.system {
+            println!("      {:<10} {}  {}", "system", label, short(hash));
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// The first 12 characters of a hash
+fn short(hash: &str) -> &str {
+    &hash[..12.min(hash.len())]
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+fn format_age(age: chrono::Duration) -> String {
+    match age {
+        age if age.num_days() > 0 => format!("{}d", age.num_days()),
+        age if age.num_hours() > 0 => format!("{}h", age.num_hours()),
+        age => format!("{}m", age.num_minutes().max(0)),
+    }
+}
+
+/// Show the saved request and raw response for a prompt's last generation,
+/// preferring the transcript for its current inputs.
+fn dump_transcript(config: &LitConfig, root: &Path, prompt_path: &Path) -> anyhow::Result<()> {
+    let mut prompts_vec = Vec::new();
+    for path in &discover_project_prompts(config, root)? {
+        if let Ok(prompt) = Prompt::from_file(path, root, config) {
+            prompts_vec.push(prompt);
+        }
+    }
+    resolve_imports(&mut prompts_vec)?;
+    let dag = Dag::build(&prompts_vec)?;
+    let prompts: HashMap<PathBuf, Prompt> = prompts_vec
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect();
+    if !prompts.contains_key(prompt_path) {
+        anyhow::bail!("Prompt not found: {}", prompt_path.display());
+    }
+
+    let store = TranscriptStore::new(&root.join(".lit"));
+    let existing_code = load_code(config, root);
+    let input_hashes = current_input_hashes(config, &dag, &prompts, &existing_code);
+    let current = match input_hashes.get(prompt_path) {
+        Some(hash) => store.load(hash)?,
+        None => None,
+    };
+    let is_current = current.is_some();
+    let Some(transcript) = current.or(store.latest_for(prompt_path)?) else {
+        anyhow::bail!(
+            "No transcript saved for {}.\n\
+             Hint: Set `transcripts = true` under [logging] in lit.toml, then run `lit regenerate`.",
+            prompt_path.display()
+        );
+    };
+
+    println!("=== TRANSCRIPT ({}) ===", prompt_path.display());
+    println!();
+    println!("  input hash:         {}", transcript.input_hash);
+    if !is_current {
+        println!("                      (inputs have changed since; regenerate for a current transcript)");
+    }
+    println!("  generated:          {}", transcript.timestamp.format("%Y-%m-%d %H:%M:%S UTC"));
+    println!("  model:              {}{}", transcript.model, if transcript.batch { " (batch)" } else { "" });
+    println!("  temperature:        {}", transcript.temperature);
+    println!(
+        "  seed:               {}",
+        transcript.seed.map(|s| s.to_string()).unwrap_or_else(|| "(none)".to_string())
+    );
+    println!(
+        "  tokens:             {} in, {} out",
+        format_tokens(transcript.tokens_in),
+        format_tokens(transcript.tokens_out)
+    );
+    for (title, text) in [
+        ("SYSTEM PROMPT", &transcript.system_prompt),
+        ("CONTEXT", &transcript.context),
+        ("USER PROMPT", &transcript.user_prompt),
+        ("RESPONSE", &transcript.response),
+    ] {
+        println!();
+        println!("=== {} ===", title);
+        println!();
+        if text.is_empty() {
+            println!("  (empty)");
+        } else {
+            println!("{}", text.trim_end());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::cli::cost::commit_records;
+use crate::core::config::LitConfig;
+use crate::core::describe::{self, BranchSummary, DEFAULT_TEMPLATE, DESCRIBE_PLACEHOLDERS, DESCRIBE_SYSTEM_PROMPT};
+use crate::core::forge::{Forge, PullRequest};
+use crate::core::generator::summary_model;
+use crate::core::repo::LitRepo;
+use crate::core::style;
+use crate::providers::{GenerationRequest, LlmProvider};
+use crate::providers::setup::build_registry;
+
+/// Branch pull requests merge into without `--base` or `[describe] base`
+const DEFAULT_BASE: &str = "main";
+
+/// `lit describe` — a pull request description for the current branch:
+/// the prompts it changed, the code.lock/ files they regenerated, what the
+/// generation runs committed on it cost and the manual patches it added,
+/// laid out by `[describe] template`. `llm` adds an overview paragraph from
+/// one call to a cheap model. The description goes to stdout, or with
+/// `post` to the branch's pull request on GitHub or GitLab (see
+/// [`Forge`]), which is opened if there is none.
+pub async fn run(base: Option<String>, llm: bool, post: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+
+    let upstream = repo.upstream().ok();
+    let remote = upstream.as_ref().map_or("origin", |u| u.remote.as_str()).to_string();
+    let base = base.or_else(|| config.describe.base.clone()).unwrap_or_else(|| DEFAULT_BASE.to_string());
+    // The remote's copy of the base is what the pull request merges into
+    let base_ref = [format!("{}/{}", remote, base), base.clone()]
+        .into_iter()
+        .find(|candidate| repo.commit_info(candidate).is_ok())
+        .with_context(|| format!("No branch '{}' to describe changes against.\nHint: Pass --base <branch>.", base))?;
+    let (from, to) = repo.resolve_range(&format!("{}...HEAD", base_ref))?;
+    if from == to {
+        bail!("HEAD has no commits that {} doesn't.", base_ref);
+    }
+
+    let summary = branch_summary(&repo, &from, &to)?;
+    eprintln!(
+        "{}",
+        format!(
+            "{} commit(s) since {}, {} prompt change(s)",
+            summary.commits.len(),
+            base_ref,
+            summary.prompt_count()
+        )
+        .dimmed()
+    );
+
+    let overview = if llm {
+        Some(overview(&config, &repo, &summary, (&from, &to)).await?)
+    } else {
+        None
+    };
+    let template = match &config.describe.template {
+        Some(path) => std::fs::read_to_string(root.join(path))
+            .with_context(|| format!("Failed to read [describe] template {}", path.display()))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    let unknown = describe::unknown_placeholders(&template);
+    if !unknown.is_empty() {
+        eprintln!(
+            "{}",
+            style::warning(&format!(
+                "Unknown placeholder(s) in the template, left as is: {}. Available: {}",
+                unknown.join(", "),
+                DESCRIBE_PLACEHOLDERS.join(", ")
+            ))
+        );
+    }
+    let body = describe::render(&template, &summary, overview.as_deref());
+
+    if !post {
+        print!("{}", body);
+        return Ok(());
+    }
+
+    let Some(upstream) = upstream.filter(|u| u.tracked) else {
+        bail!("The current branch hasn't been pushed.\nHint: Run `lit push` first.");
+    };
+    let remote_url = repo.remotes()?.into_iter().find(|r| r.name == upstream.remote).map(|r| r.url);
+    let forge = Forge::resolve(&config.forge, remote_url.as_deref())?;
+    let pr = PullRequest {
+        title: summary.title(),
+        body,
+        head: upstream.remote_branch.clone(),
+        base: base_ref.strip_prefix(&format!("{}/", remote)).unwrap_or(&base_ref).to_string(),
+    };
+    eprintln!("  {} {} on {}", "Posting".cyan(), format!("{} → {}", pr.head, pr.base).dimmed(), forge.repo);
+    let posted = forge.post(&pr).await?;
+    let action = if posted.opened {
+        "Opened"
+    } else {
+        "Updated the description of"
+    };
+    eprintln!("{}", style::success(&format!("{} {}", action, posted.url)));
+    Ok(())
+}
+
+/// What the commits `from..to` changed
+fn branch_summary(repo: &LitRepo, from: &str, to: &str) -> Result<BranchSummary> {
+    let status = repo.status_between(from, to)?;
+    let prompt_stats = repo
+        .diff_prompt_stats(Some((from, to)))?
+        .into_iter()
+        .map(|stat| (stat.path, (stat.insertions, stat.deletions)))
+        .collect();
+    let commits = repo.commits_in(&format!("{}..{}", from, to))?;
+
+    let mut records = Vec::new();
+    let mut patches_added = BTreeSet::new();
+    for commit in &commits {
+        records.extend(commit_records(repo, &commit.hash)?);
+        for (path, _) in repo.files_added_in(&commit.hash, Path::new(".lit/patches"))? {
+            // Patches added and dropped again on the branch don't count
+            if path.extension().is_some_and(|ext| ext == "patch") && repo.object_at(to, &path)?.is_some() {
+                let output = path.strip_prefix(".lit/patches").unwrap_or(&path).with_extension("");
+                patches_added.insert(output);
+            }
+        }
+    }
+
+    let mut code_changed: Vec<PathBuf> = status.code_new.into_iter().chain(status.code_modified).collect();
+    code_changed.sort();
+    Ok(BranchSummary {
+        commits,
+        prompts_new: status.prompts_new,
+        prompts_modified: status.prompts_modified,
+        prompts_deleted: status.prompts_deleted,
+        prompt_stats,
+        code_changed,
+        records,
+        patches_added: patches_added.into_iter().collect(),
+    })
+}
+
+/// `lit describe --llm`: an overview paragraph from one call to the
+/// `[describe] model` (default: the summary model)
+async fn overview(config: &LitConfig, repo: &LitRepo, summary: &BranchSummary, range: (&str, &str)) -> Result<String> {
+    let model = config.describe.model.clone().unwrap_or_else(|| summary_model(config));
+    eprintln!("  {} {}", "Asking".cyan(), model.dimmed());
+    let request = GenerationRequest {
+        system_prompt: DESCRIBE_SYSTEM_PROMPT.to_string(),
+        context: String::new(),
+        user_prompt: describe::overview_request(summary, &repo.diff_prompts(Some(range))?),
+        provider: config.model.provider.clone(),
+        model,
+        temperature: 0.0,
+        seed: None,
+        json: false,
+    };
+    let response = build_registry(config, std::io::stdin().is_terminal())
+        .generate(request)
+        .await
+        .context("Failed to write the overview")?;
+    eprintln!(
+        "{}",
+        format!("  {} tokens in, {} out", response.tokens_in, response.tokens_out).dimmed()
+    );
+    Ok(response.content)
+}
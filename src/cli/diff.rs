@@ -1,32 +1,52 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 
+use crate::core::cache::Cache;
 use crate::core::config::LitConfig;
 use crate::core::dag::Dag;
-use crate::core::prompt::{Prompt, discover_prompts};
+use crate::core::format::Formatter;
+use crate::core::generation_record::{build_record, format_cost, write_record};
+use crate::core::generator::Generator;
+use crate::core::patch::{PatchResult, PatchStore};
+use crate::core::project::{load_code, load_prompts, stale_prompts, write_output_file};
+use crate::core::prompt::{Prompt, TagFilter, discover_project_prompts, exclude_drafts, resolve_imports};
 use crate::core::repo::LitRepo;
 use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
 
-pub async fn run(code: bool, all: bool, summary: bool) -> Result<()> {
+/// Where `lit diff --preview` writes the would-be code.lock/ files
+const PREVIEW_DIR: &str = "preview";
+
+/// `lit diff [<ref1>..<ref2>]` — prompt (or code) changes in the working
+/// tree, or between two commits
+pub async fn run(range: Option<String>, code: bool, all: bool, summary: bool, preview: bool) -> Result<()> {
     let cwd = std::env::current_dir()?;
     let (config, root) = LitConfig::find_and_load(&cwd)?;
 
-    let repo = LitRepo::open(&root)?;
+    if preview {
+        return run_preview(&config, &root).await;
+    }
+
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+    let range = range.map(|spec| repo.resolve_range(&spec)).transpose()?;
+    let range = range.as_ref().map(|(from, to)| (from.as_str(), to.as_str()));
 
     if summary {
-        return run_summary(&config, &root, &repo).await;
+        return run_summary(&config, &root, &repo, range).await;
     }
 
     let diff = if all {
-        repo.diff_all()?
+        repo.diff_all(range)?
     } else if code {
-        repo.diff_code()?
+        repo.diff_code(range)?
     } else {
         // Default: show prompt diffs
-        repo.diff_prompts()?
+        repo.diff_prompts(range)?
     };
 
     if diff.is_empty() {
@@ -37,7 +57,10 @@ pub async fn run(code: bool, all: bool, summary: bool) -> Result<()> {
         } else {
             "prompts/"
         };
-        eprintln!("No changes in {}.", scope);
+        match range {
+            Some((from, to)) => eprintln!("No changes in {} between {} and {}.", scope, &from[..7], &to[..7]),
+            None => eprintln!("No changes in {}.", scope),
+        }
     } else {
         print!("{}", diff);
     }
@@ -45,8 +68,18 @@ pub async fn run(code: bool, all: bool, summary: bool) -> Result<()> {
     Ok(())
 }
 
-async fn run_summary(config: &LitConfig, root: &std::path::Path, repo: &LitRepo) -> Result<()> {
-    let status = repo.status()?;
+/// `lit diff --summary` — changed prompts, the prompts they regenerate and
+/// the code that affects. For a range, the DAG is the one at `<ref1>`.
+async fn run_summary(
+    config: &LitConfig,
+    root: &std::path::Path,
+    repo: &LitRepo,
+    range: Option<(&str, &str)>,
+) -> Result<()> {
+    let status = match range {
+        Some((from, to)) => repo.status_between(from, to)?,
+        None => repo.status()?,
+    };
 
     // Collect all changed prompts
     let has_prompt_changes = !status.prompts_modified.is_empty()
@@ -60,7 +93,7 @@ async fn run_summary(config: &LitConfig, root: &std::path::Path, repo: &LitRepo)
     }
 
     // Get per-file diff stats for prompts
-    let diff_stats = repo.diff_prompt_stats()?;
+    let diff_stats = repo.diff_prompt_stats(range)?;
     let stats_map: HashMap<PathBuf, (usize, usize)> = diff_stats
         .into_iter()
         .map(|s| (s.path, (s.insertions, s.deletions)))
@@ -73,9 +106,12 @@ async fn run_summary(config: &LitConfig, root: &std::path::Path, repo: &LitRepo)
         eprintln!("  {}", "Prompts:".bold());
 
         for path in &status.prompts_new {
-            let line_count = std::fs::read_to_string(root.join(path))
-                .map(|c| c.lines().count())
-                .unwrap_or(0);
+            let line_count = match range {
+                Some(_) => stats_map.get(path).map_or(0, |(ins, _)| *ins),
+                None => std::fs::read_to_string(root.join(path))
+                    .map(|c| c.lines().count())
+                    .unwrap_or(0),
+            };
             eprintln!(
                 "    {}  {}",
                 style::file_new(&path.display().to_string()),
@@ -106,128 +142,142 @@ async fn run_summary(config: &LitConfig, root: &std::path::Path, repo: &LitRepo)
     }
 
     // -- DAG impact section --
-    if has_prompt_changes {
-        let prompts_dir = root.join("prompts");
-        if prompts_dir.exists() {
-            if let Ok(prompt_paths) = discover_prompts(&prompts_dir) {
-                let prompts_vec: Vec<Prompt> = prompt_paths
-                    .iter()
-                    .filter_map(|p| Prompt::from_file(p, root, config).ok())
-                    .collect();
-
-                if let Ok(dag) = Dag::build(&prompts_vec) {
-                    // Combine modified + new as changed (deleted won't be in DAG)
-                    let changed: Vec<PathBuf> = status
-                        .prompts_modified
-                        .iter()
-                        .chain(status.prompts_new.iter())
-                        .cloned()
-                        .collect();
-
-                    let regen_set = dag.regeneration_set(&changed);
-
-                    if !regen_set.is_empty() {
-                        eprintln!();
-                        eprintln!(
-                            "  {}",
-                            "Impact (prompts that will regenerate):".bold()
-                        );
-
-                        // Build a set of directly-changed prompts for annotation
-                        let directly_changed: std::collections::HashSet<PathBuf> =
-                            changed.iter().cloned().collect();
-
-                        for regen_path in &regen_set {
-                            let reason = if directly_changed.contains(regen_path) {
-                                String::new()
-                            } else {
-                                // Find which of its imports are in the regen set
-                                if let Some(node) = dag.nodes().get(regen_path) {
-                                    let import_names: Vec<String> = node
-                                        .imports
-                                        .iter()
-                                        .filter(|imp| {
-                                            regen_set.contains(*imp)
-                                                || directly_changed.contains(*imp)
-                                        })
-                                        .map(|imp| {
-                                            // Extract a short name: prompts/models/user.prompt.md → "user model"
-                                            let stem = imp
-                                                .file_stem()
-                                                .and_then(|s| s.to_str())
-                                                .unwrap_or("?")
-                                                .replace(".prompt", "");
-                                            let parent = imp
-                                                .parent()
-                                                .and_then(|p| p.file_name())
-                                                .and_then(|s| s.to_str())
-                                                .unwrap_or("");
-                                            if parent == "prompts" || parent.is_empty() {
-                                                stem
-                                            } else {
-                                                format!("{} {}", stem, parent)
-                                            }
-                                        })
-                                        .collect();
-
-                                    if import_names.is_empty() {
-                                        String::new()
-                                    } else {
-                                        format!("  (imports {})", import_names.join(", "))
-                                            .dimmed()
-                                            .to_string()
-                                    }
+    let prompts_vec: Option<Vec<Prompt>> = match range {
+        Some((from, _)) => repo.prompt_files_at(from).ok().map(|files| {
+            files
+                .into_iter()
+                .filter_map(|(path, raw)| Prompt::parse(&raw, path, config).ok())
+                .collect()
+        }),
+        None => discover_project_prompts(config, root).ok().map(|paths| {
+            paths
+                .iter()
+                .filter_map(|p| Prompt::from_file(p, root, config).ok())
+                .collect()
+        }),
+    };
+    if has_prompt_changes
+        && let Some(mut prompts_vec) = prompts_vec
+        && let Ok(dag) = resolve_imports(&mut prompts_vec).and_then(|_| Dag::build(&prompts_vec))
+    {
+        // Prompts missing from the DAG (new ones, or deleted ones in the
+        // working tree's) are skipped; a deleted prompt still cascades
+        // to its dependents in <ref1>'s DAG, but doesn't regenerate itself
+        let changed: Vec<PathBuf> = status
+            .prompts_modified
+            .iter()
+            .chain(status.prompts_new.iter())
+            .chain(status.prompts_deleted.iter())
+            .cloned()
+            .collect();
+
+        let mut regen_set = dag.regeneration_set(&changed);
+        regen_set.retain(|p| !status.prompts_deleted.contains(p));
+
+        if !regen_set.is_empty() {
+            eprintln!();
+            eprintln!(
+                "  {}",
+                match range {
+                    Some(_) => "Impact (prompts these changes regenerate):",
+                    None => "Impact (prompts that will regenerate):",
+                }
+                .bold()
+            );
+
+            // Build a set of directly-changed prompts for annotation
+            let directly_changed: std::collections::HashSet<PathBuf> =
+                changed.iter().cloned().collect();
+
+            for regen_path in &regen_set {
+                let reason = if directly_changed.contains(regen_path) {
+                    String::new()
+                } else {
+                    // Find which of its imports are in the regen set
+                    if let Some(node) = dag.nodes().get(regen_path) {
+                        let import_names: Vec<String> = node
+                            .imports
+                            .iter()
+                            .filter(|imp| {
+                                regen_set.contains(*imp)
+                                    || directly_changed.contains(*imp)
+                            })
+                            .map(|imp| {
+                                // Extract a short name: prompts/models/user.prompt.md → "user model"
+                                let stem = imp
+                                    .file_stem()
+                                    .and_then(|s| s.to_str())
+                                    .unwrap_or("?")
+                                    .replace(".prompt", "");
+                                let parent = imp
+                                    .parent()
+                                    .and_then(|p| p.file_name())
+                                    .and_then(|s| s.to_str())
+                                    .unwrap_or("");
+                                let top = config.project.prompt_dirs.iter().any(|d| imp.parent() == Some(d.as_path()));
+                                if top || parent.is_empty() {
+                                    stem
                                 } else {
-                                    String::new()
+                                    format!("{} {}", stem, parent)
                                 }
-                            };
-
-                            eprintln!(
-                                "    {} {}{}",
-                                "→".cyan(),
-                                regen_path.display(),
-                                reason
-                            );
-                        }
+                            })
+                            .collect();
 
-                        // -- Generated code affected --
-                        let mut affected_outputs: Vec<PathBuf> = Vec::new();
-                        for regen_path in &regen_set {
-                            if let Some(node) = dag.nodes().get(regen_path) {
-                                for output in &node.outputs {
-                                    let code_path =
-                                        PathBuf::from("code.lock").join(output);
-                                    affected_outputs.push(code_path);
-                                }
-                            }
-                        }
-                        affected_outputs.sort();
-                        affected_outputs.dedup();
-
-                        if !affected_outputs.is_empty() {
-                            eprintln!();
-                            eprintln!("  {}", "Generated code affected:".bold());
-                            for path in &affected_outputs {
-                                eprintln!(
-                                    "    {}",
-                                    style::file_modified(&path.display().to_string())
-                                );
-                            }
+                        if import_names.is_empty() {
+                            String::new()
+                        } else {
+                            format!("  (imports {})", import_names.join(", "))
+                                .dimmed()
+                                .to_string()
                         }
+                    } else {
+                        String::new()
+                    }
+                };
+
+                eprintln!(
+                    "    {} {}{}",
+                    "→".cyan(),
+                    regen_path.display(),
+                    reason
+                );
+            }
 
-                        // -- Summary line --
-                        let total = dag.len();
-                        let regen_count = regen_set.len();
-                        let unchanged = total - regen_count;
-                        eprintln!();
-                        eprintln!(
-                            "  {} prompt(s) will regenerate, {} unchanged",
-                            regen_count.to_string().yellow(),
-                            unchanged.to_string().dimmed()
-                        );
+            // -- Generated code affected --
+            let mut affected_outputs: Vec<PathBuf> = Vec::new();
+            for regen_path in &regen_set {
+                if let Some(node) = dag.nodes().get(regen_path) {
+                    for output in &node.outputs {
+                        let code_path = config.project.code_dir.join(output);
+                        affected_outputs.push(code_path);
                     }
                 }
             }
+            affected_outputs.sort();
+            affected_outputs.dedup();
+
+            if !affected_outputs.is_empty() {
+                eprintln!();
+                eprintln!("  {}", "Generated code affected:".bold());
+                for path in &affected_outputs {
+                    eprintln!(
+                        "    {}",
+                        style::file_modified(&path.display().to_string())
+                    );
+                }
+            }
+
+            // -- Summary line --
+            let total = dag.len();
+            let regen_count = regen_set.len();
+            let unchanged = total - regen_count;
+            eprintln!();
+            eprintln!(
+                "  {} prompt(s) {}, {} unchanged",
+                regen_count.to_string().yellow(),
+                if range.is_some() { "regenerate" } else { "will regenerate" },
+                unchanged.to_string().dimmed()
+            );
         }
     }
 
@@ -247,3 +297,177 @@ async fn run_summary(config: &LitConfig, root: &std::path::Path, repo: &LitRepo)
 
     Ok(())
 }
+
+/// `lit diff --code --preview` — generate the stale prompts (and their
+/// dependents) into `.lit/preview/` and diff the result against code.lock/,
+/// which is left untouched.
+///
+/// Saved patches are applied as `lit regenerate` would. Fresh generations
+/// go through the cache and are recorded like any other, so applying the
+/// preview with `lit regenerate` afterwards costs nothing more.
+async fn run_preview(config: &LitConfig, root: &Path) -> Result<()> {
+    let provider = Box::new(build_registry(config, std::io::stdin().is_terminal()));
+    preview_with(config, root, provider).await?;
+    Ok(())
+}
+
+/// Generate the preview with `provider`, returning how many files would change
+async fn preview_with(config: &LitConfig, root: &Path, provider: Box<dyn LlmProvider>) -> Result<usize> {
+    let (prompts, dag) = load_prompts(config, root)?;
+    let stale = match stale_prompts(config, root, &TagFilter::default())? {
+        Some(stale) => stale,
+        None => dag.order().to_vec(),
+    };
+    let mut regeneration_set = dag.regeneration_set(&stale);
+    exclude_drafts(&mut regeneration_set, &prompts)?;
+    if regeneration_set.is_empty() {
+        eprintln!("code.lock/ is up to date with the prompts; nothing to preview.");
+        return Ok(0);
+    }
+
+    eprintln!(
+        "{}",
+        style::header(&format!("Previewing {} prompt(s)", regeneration_set.len()))
+    );
+    let existing_code = load_code(config, root);
+    let cache = Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone());
+    cache.init().context("Failed to initialize cache directory")?;
+
+    let mut generator = Generator::new(provider, config.clone());
+    if !config.format.is_empty() {
+        generator = generator.with_formatter(Formatter::new(root, config.format.clone()));
+    }
+    let result = generator
+        .run_pipeline(&dag, &prompts, &regeneration_set, &existing_code, Some(&cache))
+        .await?;
+    let (record, cost) = build_record(config, &result, 0, 0, 0);
+    write_record(root, &record);
+    if let Some(failure) = result.failures.first() {
+        anyhow::bail!("{}", failure.message);
+    }
+
+    // The would-be code.lock/ files, with saved patches applied
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    // Binary static files (copied from a `source`) have no text diff
+    let mut preview: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for sf in &config.r#static {
+        if let Ok(content) = String::from_utf8(sf.read(root)?) {
+            preview.insert(PathBuf::from(&sf.path), content);
+        }
+    }
+    for output in &result.outputs {
+        for (file_path, content) in &output.files {
+            let content = match patch_store.load_patch(file_path) {
+                Some(stored) => match patch_store.apply_patch(&stored.original_content, content, &stored.manual_content) {
+                    PatchResult::Applied(merged) | PatchResult::Conflict(merged) => merged,
+                },
+                None => content.clone(),
+            };
+            preview.insert(file_path.clone(), content);
+        }
+    }
+
+    let preview_dir = root.join(".lit").join(PREVIEW_DIR);
+    if preview_dir.exists() {
+        std::fs::remove_dir_all(&preview_dir)
+            .with_context(|| format!("Failed to clear {}", preview_dir.display()))?;
+    }
+    let mut changed = 0;
+    for (file_path, content) in &preview {
+        write_output_file(&preview_dir, file_path, content, None)?;
+        let current = existing_code.get(file_path).map(String::as_str).unwrap_or("");
+        if current == content {
+            continue;
+        }
+        changed += 1;
+        let old_header = if existing_code.contains_key(file_path) {
+            format!("a/{}", config.project.code_dir.join(file_path).display())
+        } else {
+            "/dev/null".to_string()
+        };
+        print!(
+            "{}",
+            similar::TextDiff::from_lines(current, content.as_str())
+                .unified_diff()
+                .context_radius(3)
+                .header(&old_header, &format!("b/{}", config.project.code_dir.join(file_path).display()))
+        );
+    }
+
+    eprintln!();
+    eprintln!(
+        "  {} of {} file(s) would change; preview written to .lit/{}/",
+        changed.to_string().bold(),
+        preview.len(),
+        PREVIEW_DIR
+    );
+    eprintln!(
+        "  {:<12} {} hit(s), {} miss(es), {}",
+        "Cache:".dimmed(),
+        result.cache_hits.to_string().green(),
+        result.cache_misses.to_string().yellow(),
+        style::cost(&format_cost(cost))
+    );
+    if changed > 0 {
+        eprintln!("{}", style::hint("Run `lit regenerate` to apply it (cached, no further LLM calls)."));
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::generation_record::GenerationRecord;
+    use crate::providers::{GenerationRequest, GenerationResponse, PromptCacheTokens};
+
+    /// Answers with a User model that gained a name field
+    struct StubProvider;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for StubProvider {
+        async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+            Ok(GenerationResponse {
+                content: "=== FILE: src/user.py ===\nclass User:\n    name: str\n".to_string(),
+                tokens_in: 200,
+                tokens_out: 20,
+                prompt_cache: PromptCacheTokens::default(),
+                model: request.model,
+                batch: false,
+            })
+        }
+
+        fn name(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preview_leaves_code_lock_and_applies_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("lit.toml"),
+            "[project]\nname = \"preview\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n[language]\ndefault = \"python\"\nversion = \"3.12\"\n\n\
+             [model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5\"\ntemperature = 0.0\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("prompts")).unwrap();
+        std::fs::write(root.join("prompts/user.prompt.md"), "---\noutputs:\n  - src/user.py\n---\n\n# User\n").unwrap();
+        let current = "class User:\n    pass\n\n# keep\n";
+        std::fs::create_dir_all(root.join("code.lock/src")).unwrap();
+        std::fs::write(root.join("code.lock/src/user.py"), current).unwrap();
+        let patches = PatchStore::new(root.join(".lit/patches"));
+        patches.init().unwrap();
+        patches.save_patch(Path::new("src/user.py"), "class User:\n    pass\n", current).unwrap();
+
+        let (config, root) = LitConfig::find_and_load(root).unwrap();
+        let changed = preview_with(&config, &root, Box::new(StubProvider)).await.unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(std::fs::read_to_string(root.join("code.lock/src/user.py")).unwrap(), current);
+        let preview = std::fs::read_to_string(root.join(".lit/preview/src/user.py")).unwrap();
+        assert_eq!(preview, "class User:\n    name: str\n\n# keep\n");
+
+        let records = GenerationRecord::list(&root.join(".lit/generations")).unwrap();
+        assert_eq!((records.len(), records[0].summary.cache_misses), (1, 1));
+    }
+}
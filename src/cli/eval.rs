@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::core::assertions::run_tests;
+use crate::core::cache::Cache;
+use crate::core::config::LitConfig;
+use crate::core::format::Formatter;
+use crate::core::generation_record::{build_record, format_cost, format_tokens, write_record};
+use crate::core::generator::Generator;
+use crate::core::hooks::shell;
+use crate::core::progress;
+use crate::core::project::{load_prompts, write_output_file};
+use crate::core::prompt::exclude_drafts;
+use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
+
+/// Where each model's code is written, under `.lit/`
+const EVAL_DIR: &str = "eval";
+
+/// How one model did on the whole project
+struct ModelRun {
+    model: String,
+    dir: PathBuf,
+    /// Generated files (statics included), by path under the eval directory
+    files: BTreeMap<PathBuf, String>,
+    generated: usize,
+    /// First generation failure, which stops the run
+    failure: Option<String>,
+    tests_passed: usize,
+    tests_total: usize,
+    /// Whether `--verify` passed, if given
+    verified: Option<bool>,
+    tokens_in: u64,
+    tokens_out: u64,
+    cost: f64,
+    duration_ms: u64,
+}
+
+/// `lit eval --model-a <model> --model-b <model>` — generate the whole DAG
+/// with each model into `.lit/eval/a/` and `.lit/eval/b/`, run the prompts'
+/// tests (and the `--verify` command) against both, and compare pass rates,
+/// cost, tokens and how far apart the two outputs are.
+///
+/// Every prompt uses the model under evaluation, overriding `model:`
+/// frontmatter and `[model.tiers]`. Generations go through the cache and
+/// are recorded like any other, so `lit cost` counts them.
+pub async fn run(model_a: String, model_b: String, verify: Option<String>, no_cache: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    eprintln!("{}", style::header(&format!("Eval: {} vs {}", model_a, model_b)));
+    let a = evaluate(&config, &root, &model_a, "a", verify.as_deref(), no_cache).await?;
+    let b = evaluate(&config, &root, &model_b, "b", verify.as_deref(), no_cache).await?;
+    print_report(&a, &b);
+    Ok(())
+}
+
+/// Generate everything with `model` into `.lit/eval/<label>/` and check it
+async fn evaluate(
+    config: &LitConfig,
+    root: &Path,
+    model: &str,
+    label: &str,
+    verify: Option<&str>,
+    no_cache: bool,
+) -> Result<ModelRun> {
+    let mut config = config.clone();
+    config.model.provider = config.provider_for_model(model);
+    config.model.model = model.to_string();
+    config.model.context_window = None;
+    config.model.tiers.clear();
+    let provider = Box::new(build_registry(&config, std::io::stdin().is_terminal()));
+    evaluate_with(&config, root, label, verify, no_cache, provider).await
+}
+
+/// Generate everything with `provider` and the project model of `config`
+async fn evaluate_with(
+    config: &LitConfig,
+    root: &Path,
+    label: &str,
+    verify: Option<&str>,
+    no_cache: bool,
+    provider: Box<dyn LlmProvider>,
+) -> Result<ModelRun> {
+    let model = config.model.model.as_str();
+    let (mut prompts, dag) = load_prompts(config, root)?;
+    for prompt in prompts.values_mut() {
+        prompt.frontmatter.model = None;
+        prompt.frontmatter.fallback_models.clear();
+    }
+
+    eprintln!();
+    eprintln!("  {} {}", format!("Model {}:", label.to_uppercase()).bold(), model);
+    let cache = if no_cache {
+        None
+    } else {
+        let cache = Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone());
+        cache.init().context("Failed to initialize cache directory")?;
+        Some(cache)
+    };
+    let mut generator = Generator::new(provider, config.clone());
+    if !config.format.is_empty() {
+        generator = generator.with_formatter(Formatter::new(root, config.format.clone()));
+    }
+    let mut regeneration_set = dag.order().to_vec();
+    exclude_drafts(&mut regeneration_set, &prompts)?;
+    let result = generator
+        .run_pipeline(&dag, &prompts, &regeneration_set, &HashMap::new(), cache.as_ref())
+        .await?;
+    let (record, cost) = build_record(config, &result, 0, 0, 0);
+    write_record(root, &record);
+
+    // Raw model output: no patches, so the comparison is between the models
+    let dir = root.join(".lit").join(EVAL_DIR).join(label);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to clear {}", dir.display()))?;
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut files = BTreeMap::new();
+    for sf in &config.r#static {
+        let content = sf.read(root)?;
+        write_output_file(&dir, Path::new(&sf.path), &content, sf.mode)?;
+        files.insert(PathBuf::from(&sf.path), String::from_utf8_lossy(&content).into_owned());
+    }
+    for output in &result.outputs {
+        let mode = prompts[&output.prompt_path].frontmatter.mode;
+        for (path, content) in &output.files {
+            write_output_file(&dir, path, content, mode)?;
+            files.insert(path.clone(), content.clone());
+        }
+    }
+
+    let outcomes = run_tests(root, dag.order().iter().map(|p| &prompts[p]), &dir)?;
+    let verified = match verify {
+        Some(command) => Some(run_verify(root, &dir, command)?),
+        None => None,
+    };
+
+    Ok(ModelRun {
+        model: model.to_string(),
+        dir,
+        files,
+        generated: result.outputs.len(),
+        failure: result.failures.first().map(|f| f.message.lines().next().unwrap_or_default().to_string()),
+        tests_passed: outcomes.iter().filter(|o| o.failure.is_none()).count(),
+        tests_total: outcomes.len(),
+        verified,
+        tokens_in: result.total_tokens_in,
+        tokens_out: result.total_tokens_out,
+        cost,
+        duration_ms: result.total_duration_ms,
+    })
+}
+
+/// Run the `--verify` command from the repo root against one model's code,
+/// given as `{dir}` and `LIT_CODE_DIR`
+fn run_verify(root: &Path, dir: &Path, command: &str) -> Result<bool> {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    let command = command.replace("{dir}", &crate::core::hooks::shell_quote(&relative.to_string_lossy()));
+    progress::line(&format!("    {} {}", "verify:".dimmed(), command));
+    let output = shell(&command)
+        .current_dir(root)
+        .env("LIT_ROOT", root)
+        .env("LIT_CODE_DIR", dir)
+        .output()
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    if !output.status.success() {
+        let mut details = String::from_utf8_lossy(&output.stdout).into_owned();
+        details.push_str(&String::from_utf8_lossy(&output.stderr));
+        for line in details.trim_end().lines().rev().take(10).collect::<Vec<_>>().into_iter().rev() {
+            progress::line(&format!("      {}", line.dimmed()));
+        }
+    }
+    Ok(output.status.success())
+}
+
+/// Lines added and removed going from `a`'s files to `b`'s, and how many
+/// files differ
+fn diff_size(a: &BTreeMap<PathBuf, String>, b: &BTreeMap<PathBuf, String>) -> (usize, usize, usize) {
+    let (mut added, mut removed, mut files) = (0, 0, 0);
+    let paths: BTreeSet<&PathBuf> = a.keys().chain(b.keys()).collect();
+    for path in paths {
+        let old = a.get(path).map(String::as_str).unwrap_or("");
+        let new = b.get(path).map(String::as_str).unwrap_or("");
+        if old == new {
+            continue;
+        }
+        files += 1;
+        for change in similar::TextDiff::from_lines(old, new).iter_all_changes() {
+            match change.tag() {
+                similar::ChangeTag::Insert => added += 1,
+                similar::ChangeTag::Delete => removed += 1,
+                similar::ChangeTag::Equal => {}
+            }
+        }
+    }
+    (added, removed, files)
+}
+
+fn print_report(a: &ModelRun, b: &ModelRun) {
+    let lines = |run: &ModelRun| run.files.values().map(|c| c.lines().count()).sum::<usize>();
+    let status = |run: &ModelRun| match &run.failure {
+        Some(_) => format!("failed after {}", run.generated),
+        None => format!("{} generated", run.generated),
+    };
+    let tests = |run: &ModelRun| match run.tests_total {
+        0 => "—".to_string(),
+        total => format!("{}/{} ({:.0}%)", run.tests_passed, total, 100.0 * run.tests_passed as f64 / total as f64),
+    };
+    let verified = |run: &ModelRun| match run.verified {
+        Some(true) => "passed".to_string(),
+        Some(false) => "failed".to_string(),
+        None => "—".to_string(),
+    };
+    let rows = [
+        ("Prompts", status(a), status(b)),
+        ("Tests passed", tests(a), tests(b)),
+        ("Verify", verified(a), verified(b)),
+        (
+            "Tokens in / out",
+            format!("{} / {}", format_tokens(a.tokens_in), format_tokens(a.tokens_out)),
+            format!("{} / {}", format_tokens(b.tokens_in), format_tokens(b.tokens_out)),
+        ),
+        ("Cost", format_cost(a.cost), format_cost(b.cost)),
+        (
+            "Time",
+            format!("{:.1}s", a.duration_ms as f64 / 1000.0),
+            format!("{:.1}s", b.duration_ms as f64 / 1000.0),
+        ),
+        ("Lines of code", lines(a).to_string(), lines(b).to_string()),
+    ];
+
+    let width = a.model.len().max(24);
+    eprintln!();
+    eprintln!("  {:<18} {:<width$} {}", "", a.model.bold(), b.model.bold(), width = width);
+    for (label, left, right) in rows {
+        eprintln!("  {:<18} {:<width$} {}", label.dimmed(), left, right, width = width);
+    }
+    let (added, removed, files) = diff_size(&a.files, &b.files);
+    eprintln!("  {:<18} +{}/−{} lines in {} file(s)", "B vs A".dimmed(), added, removed, files);
+    for run in [a, b] {
+        if let Some(ref failure) = run.failure {
+            eprintln!("  {}", style::warning(&format!("{}: {}", run.model, failure)));
+        }
+    }
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::hint(&format!(
+            "Outputs are in {} and {}; diff them with `diff -r`.",
+            a.dir.display(),
+            b.dir.display()
+        ))
+    );
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{GenerationRequest, GenerationResponse, PromptCacheTokens};
+
+    /// Answers with a User model that names the model asked
+    struct StubProvider;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for StubProvider {
+        async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+            Ok(GenerationResponse {
+                content: format!("=== FILE: src/user.py ===\nclass User:\n    model = \"{}\"\n", request.model),
+                tokens_in: 300,
+                tokens_out: 40,
+                prompt_cache: PromptCacheTokens::default(),
+                model: request.model,
+                batch: false,
+            })
+        }
+
+        fn name(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_overrides_prompt_models_and_runs_tests() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("lit.toml"),
+            "[project]\nname = \"eval\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n[language]\ndefault = \"python\"\nversion = \"3.12\"\n\n\
+             [model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5\"\ntemperature = 0.0\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("prompts")).unwrap();
+        std::fs::write(
+            root.join("prompts/user.prompt.md"),
+            "---\noutputs:\n  - src/user.py\nmodel: claude-opus-4-1\ntests:\n  - defines: User\n  - contains: claude-haiku-4-5\n---\n\n# User\n",
+        )
+        .unwrap();
+
+        let (mut config, root) = LitConfig::find_and_load(root).unwrap();
+        config.model.model = "claude-haiku-4-5".to_string();
+        let run = evaluate_with(&config, &root, "b", Some("test -f {dir}/src/user.py"), true, Box::new(StubProvider))
+            .await
+            .unwrap();
+
+        assert_eq!(run.model, "claude-haiku-4-5");
+        assert_eq!(run.dir, root.join(".lit/eval/b"));
+        let user = std::fs::read_to_string(run.dir.join("src/user.py")).unwrap();
+        assert!(user.contains("model = \"claude-haiku-4-5\""), "{}", user);
+        assert_eq!((run.generated, run.tests_passed, run.tests_total), (1, 2, 2));
+        assert_eq!(run.verified, Some(true));
+        assert_eq!((run.tokens_in, run.tokens_out), (300, 40));
+        assert!(run.failure.is_none());
+    }
+
+    #[test]
+    fn test_diff_size() {
+        let a = BTreeMap::from([
+            (PathBuf::from("src/user.py"), "class User:\n    pass\n".to_string()),
+            (PathBuf::from("src/old.py"), "x = 1\n".to_string()),
+            (PathBuf::from("README.md"), "# App\n".to_string()),
+        ]);
+        let b = BTreeMap::from([
+            (PathBuf::from("src/user.py"), "class User:\n    name: str\n    email: str\n".to_string()),
+            (PathBuf::from("src/new.py"), "y = 2\n".to_string()),
+            (PathBuf::from("README.md"), "# App\n".to_string()),
+        ]);
+        assert_eq!(diff_size(&a, &b), (3, 2, 3));
+        assert_eq!(diff_size(&a, &a), (0, 0, 0));
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::cli::cost::{estimate_prompts, format_input_breakdown};
+use crate::core::cache::Cache;
+use crate::core::config::LitConfig;
+use crate::core::context::dedupe_context;
+use crate::core::freeze::FreezeList;
+use crate::core::generation_record::{GenerationRecord, format_cost, format_tokens, prompt_history};
+use crate::core::generator::{gather_context, prune_context_offline};
+use crate::core::manifest::Manifest;
+use crate::core::patch::{PatchStatus, PatchStore};
+use crate::core::paths::normalize;
+use crate::core::project::{load_code, load_prompts, stale_prompts};
+use crate::core::prompt::TagFilter;
+use crate::core::style;
+use crate::core::tokens::estimate_tokens;
+
+/// `lit explain <prompt>` — one screen on a prompt: the outputs it owns and
+/// their patches, the prompts upstream of it and the context they give it,
+/// the prompts downstream of it, whether it is stale or cached, and its last
+/// generation.
+pub async fn run(prompt: PathBuf) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let path = cwd.join(&prompt);
+    let path = normalize(path.strip_prefix(&root).unwrap_or(&prompt));
+
+    let (prompts, dag) = load_prompts(&config, &root)?;
+    let Some(node) = dag.nodes().get(&path) else {
+        bail!("Prompt {} not found in DAG", path.display());
+    };
+    let lit_dir = root.join(".lit");
+    let code = load_code(&config, &root);
+    let manifest = Manifest::load(&lit_dir)?;
+    let patches = PatchStore::new(lit_dir.join("patches")).load_index()?;
+    let frozen = FreezeList::load(&lit_dir)?.is_frozen(&path);
+
+    eprintln!("{}", style::header(&path.display().to_string()));
+
+    // Outputs, with where their code.lock/ copy came from
+    eprintln!("  {}", "Outputs:".bold());
+    for output in &node.outputs {
+        let mut notes = Vec::new();
+        if !code.contains_key(output) {
+            notes.push("missing".red().to_string());
+        } else if let Some(entry) = manifest.as_ref().and_then(|m| m.owner(output)) {
+            notes.push(format!("generated {}", entry.generated_at.format("%Y-%m-%d %H:%M")).dimmed().to_string());
+        }
+        if let Some(patch) = patches.patches.get(output) {
+            let patch = match patch.status {
+                PatchStatus::Active => "patched".yellow(),
+                PatchStatus::Conflicted => "patched, conflicted".red(),
+            };
+            notes.push(patch.to_string());
+        }
+        let line = format!("{}  {}", config.project.code_dir.join(output).display(), notes.join(", "));
+        eprintln!("    {}", line.trim_end());
+    }
+
+    // Upstream: every prompt it depends on, then the context it is sent
+    let ancestors = dag.ancestors(&path);
+    eprintln!("  {}", "Upstream:".bold());
+    if ancestors.is_empty() {
+        eprintln!("    {}", "(none — a root prompt)".dimmed());
+    }
+    for ancestor in dag.order().iter().filter(|p| ancestors.contains(p)) {
+        let how = if node.imports.contains(ancestor) { "import" } else { "indirect" };
+        eprintln!("    {}  {}", ancestor.display(), how.dimmed());
+    }
+    let (context, _) = dedupe_context(gather_context(&prompts[&path], &prompts, &code));
+    let mut context: Vec<(PathBuf, String)> = prune_context_offline(&config, context).into_iter().collect();
+    context.sort();
+    if !context.is_empty() {
+        eprintln!("  {}", "Context received:".bold());
+        for (file, content) in &context {
+            eprintln!("    {}  {}", file.display(), format!("~{} tokens", format_tokens(estimate_tokens(content))).dimmed());
+        }
+    }
+
+    // Downstream: everything regenerated along with it
+    let descendants = dag.descendants(&path);
+    eprintln!("  {}", "Downstream:".bold());
+    if descendants.is_empty() {
+        eprintln!("    {}", "(none — nothing imports it)".dimmed());
+    }
+    for descendant in dag.order().iter().filter(|p| descendants.contains(p)) {
+        let how = if node.dependents.contains(descendant) { "imports it" } else { "indirect" };
+        eprintln!("    {}  {}", descendant.display(), how.dimmed());
+    }
+
+    // State: staleness against the manifest, and what regenerating would cost
+    eprintln!("  {}", "State:".bold());
+    let stale = match stale_prompts(&config, &root, &TagFilter::default())? {
+        _ if frozen => "frozen (regenerate reuses its code.lock/ content)".cyan(),
+        _ if node.draft => "draft (never generated; nothing may import it)".cyan(),
+        Some(stale) if stale.contains(&path) => "stale (inputs changed since it was generated)".yellow(),
+        Some(_) => "up to date".green(),
+        None => "unknown (no .lit/manifest.json yet)".dimmed(),
+    };
+    eprintln!("    {:<12} {}", "Code:".dimmed(), stale);
+    let cache = Cache::new(lit_dir.join("cache")).with_config(config.cache.clone());
+    if let Some(estimate) = estimate_prompts(&config, &dag, &prompts, &code, Some(&cache), std::slice::from_ref(&path)).first() {
+        let status = if estimate.cached {
+            "hit (regenerating it makes no LLM call)".green()
+        } else {
+            format!(
+                "miss (~{} in / ~{} out, about {} to regenerate)",
+                format_tokens(estimate.tokens_in),
+                format_tokens(estimate.tokens_out),
+                format_cost(estimate.cost_usd)
+            )
+            .yellow()
+        };
+        eprintln!("    {:<12} {}", "Cache:".dimmed(), status);
+    }
+
+    print_last_generation(&lit_dir, &path)?;
+    Ok(())
+}
+
+/// The prompt's most recent entry in `.lit/generations/`
+fn print_last_generation(lit_dir: &Path, path: &Path) -> Result<()> {
+    eprintln!("  {}", "Last generation:".bold());
+    let records = GenerationRecord::list(&lit_dir.join("generations"))?;
+    for line in last_generation(&records, path) {
+        eprintln!("    {}", line);
+    }
+    Ok(())
+}
+
+/// The "Last generation" lines: the latest run, where its input tokens went
+/// and what all recorded runs of the prompt cost
+fn last_generation(records: &[GenerationRecord], path: &Path) -> Vec<String> {
+    let history = prompt_history(records, path);
+    let Some((timestamp, last)) = history.last() else {
+        return vec!["(never generated)".dimmed().to_string()];
+    };
+    let detail = if last.from_cache {
+        "from cache".to_string()
+    } else {
+        format!(
+            "{}, {} in / {} out, {:.1}s",
+            last.model,
+            format_tokens(last.tokens_in),
+            format_tokens(last.tokens_out),
+            last.duration_ms as f64 / 1000.0
+        )
+    };
+    let mut lines = vec![format!(
+        "{}  {}  {}",
+        timestamp.format("%Y-%m-%d %H:%M"),
+        detail.dimmed(),
+        style::cost(&format_cost(last.cost_usd))
+    )];
+    if let Some(breakdown) = &last.input_breakdown {
+        lines.push(format!("input: {}", format_input_breakdown(breakdown)).dimmed().to_string());
+    }
+    let total: f64 = history.iter().map(|(_, p)| p.cost_usd).sum();
+    lines.push(format!("{} generation(s) recorded, {} in total", history.len(), format_cost(total)).dimmed().to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::core::generation_record::{GenerationSummary, InputBreakdown, PromptRecord};
+    use crate::providers::PromptCacheTokens;
+
+    fn record(prompt: &str, input_breakdown: Option<InputBreakdown>) -> GenerationRecord {
+        GenerationRecord {
+            timestamp: Utc::now(),
+            project: "shop".to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            temperature: 0.0,
+            seed: None,
+            language: "python".to_string(),
+            framework: None,
+            prompts: vec![PromptRecord {
+                prompt_path: PathBuf::from(prompt),
+                output_files: vec![PathBuf::from("src/user.py")],
+                input_hash: "abc".to_string(),
+                from_cache: input_breakdown.is_none(),
+                tokens_in: 500,
+                tokens_out: 1200,
+                prompt_cache: PromptCacheTokens::default(),
+                batch: false,
+                duration_ms: 3500,
+                model: "claude-sonnet-4-5".to_string(),
+                cost_usd: 0.02,
+                input_breakdown,
+            }],
+            summary: GenerationSummary::default(),
+            failures: Vec::new(),
+            command: None,
+        }
+    }
+
+    #[test]
+    fn test_last_generation_shows_input_breakdown() {
+        let breakdown = InputBreakdown { system: 100, context: 300, prompt: 100 };
+        let records = vec![record("prompts/user.prompt.md", Some(breakdown))];
+        let lines = last_generation(&records, Path::new("prompts/user.prompt.md"));
+        assert_eq!(lines.len(), 3, "{:?}", lines);
+        assert!(lines[1].contains(&format_input_breakdown(&breakdown)), "{:?}", lines);
+        assert!(lines[1].contains("context ~300 (60%)"), "{:?}", lines);
+
+        // Cache hits have no breakdown of their own
+        let records = vec![record("prompts/user.prompt.md", None)];
+        let lines = last_generation(&records, Path::new("prompts/user.prompt.md"));
+        assert!(lines[0].contains("from cache") && !lines.iter().any(|l| l.contains("input:")), "{:?}", lines);
+        assert!(last_generation(&records, Path::new("prompts/other.prompt.md"))[0].contains("never generated"));
+    }
+}
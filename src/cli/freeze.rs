@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::freeze::{FREEZE_FILE, FreezeList};
+use crate::core::paths::relative_to;
+use crate::core::prompt::Prompt;
+use crate::core::style;
+
+/// `lit freeze [prompts...]` — pin prompts to their current code.lock/
+/// content so `lit regenerate` never calls the LLM for them. With no
+/// prompts, list the frozen ones.
+pub async fn freeze(prompts: Vec<PathBuf>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let lit_dir = root.join(".lit");
+    let mut list = FreezeList::load(&lit_dir)?;
+
+    if prompts.is_empty() {
+        eprintln!("{}", style::header("Frozen prompts"));
+        if list.prompts.is_empty() {
+            eprintln!("  {}", "(none)".dimmed());
+        }
+        for prompt in &list.prompts {
+            eprintln!("  {}", prompt.display());
+        }
+        return Ok(());
+    }
+
+    for path in &prompts {
+        let relative = relative_to(path, &root);
+        if !root.join(&relative).is_file() {
+            bail!("Prompt not found: {}", relative.display());
+        }
+        let prompt = Prompt::from_file(&root.join(&relative), &root, &config)?;
+        if let Some(missing) = prompt
+            .frontmatter
+            .outputs
+            .iter()
+            .find(|output| !config.code_dir(&root).join(output).exists())
+        {
+            bail!(
+                "{} has no generated code to freeze (code.lock/{} is missing).\n\
+                 Hint: Run `lit regenerate {}` first.",
+                relative.display(),
+                missing.display(),
+                relative.display()
+            );
+        }
+        if list.freeze(&relative) {
+            eprintln!("  {} {}", "frozen:".green(), relative.display());
+        } else {
+            eprintln!("  {} {}", "already frozen:".dimmed(), relative.display());
+        }
+    }
+    list.save(&lit_dir)?;
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::hint(&format!(
+            "`lit regenerate` will reuse their code.lock/ content. Commit .lit/{} to share the freeze.",
+            FREEZE_FILE
+        ))
+    );
+    Ok(())
+}
+
+/// `lit unfreeze <prompts...>` — let `lit regenerate` generate prompts again
+pub async fn unfreeze(prompts: Vec<PathBuf>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let root = LitConfig::find_root(&cwd)?;
+    let lit_dir = root.join(".lit");
+    let mut list = FreezeList::load(&lit_dir)?;
+
+    for path in &prompts {
+        let relative = relative_to(path, &root);
+        if list.unfreeze(&relative) {
+            eprintln!("  {} {}", "unfrozen:".green(), relative.display());
+        } else {
+            eprintln!("  {} {}", "not frozen:".dimmed(), relative.display());
+        }
+    }
+    list.save(&lit_dir)
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cli::GitignoreCommands;
+use crate::core::config::LitConfig;
+use crate::core::repo::LitRepo;
+use crate::core::style;
+
+pub async fn run(action: GitignoreCommands) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?;
+
+    match action {
+        GitignoreCommands::Sync => sync(&repo),
+    }
+}
+
+/// `lit gitignore sync` — add missing lit entries to .gitignore
+fn sync(repo: &LitRepo) -> Result<()> {
+    let added = repo.sync_gitignore()?;
+    if added.is_empty() {
+        eprintln!("{}", ".gitignore is up to date.".dimmed());
+        return Ok(());
+    }
+
+    eprintln!("{}", style::header("Updated .gitignore"));
+    for entry in &added {
+        eprintln!("{}", style::file_new(entry));
+    }
+    Ok(())
+}
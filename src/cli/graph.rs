@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::core::config::LitConfig;
+use crate::core::dag::{Dag, DagDiff};
+use crate::core::prompt::{Prompt, discover_project_prompts, resolve_imports};
+use crate::core::repo::LitRepo;
+
+/// Output formats supported by `lit graph`
+pub const GRAPH_FORMATS: &[&str] = &["dot", "mermaid"];
+
+/// `lit graph` — render the prompt DAG, highlighting what uncommitted
+/// prompt changes would regenerate
+pub async fn run(format: &str, diff: Option<&str>) -> Result<()> {
+    if let Some(base_ref) = diff {
+        return print_diff(base_ref);
+    }
+
+    if !GRAPH_FORMATS.contains(&format) {
+        anyhow::bail!(
+            "Unknown graph format '{}'. Must be one of: {}",
+            format,
+            GRAPH_FORMATS.join(", ")
+        );
+    }
+
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    let prompt_paths = discover_project_prompts(&config, &root)?;
+    let mut prompts = Vec::new();
+    for p in &prompt_paths {
+        prompts.push(
+            Prompt::from_file(p, &root, &config)
+                .with_context(|| format!("Failed to parse {}", p.display()))?,
+        );
+    }
+    resolve_imports(&mut prompts)?;
+    let dag = Dag::build(&prompts)?;
+
+    // Modified and new prompts seed the highlighted regeneration set
+    let changed: Vec<PathBuf> = match LitRepo::open(&root).and_then(|repo| repo.with_project_dirs(&config.project).status()) {
+        Ok(status) => status
+            .prompts_modified
+            .into_iter()
+            .chain(status.prompts_new)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    match format {
+        "dot" => print!("{}", dag.to_dot(&changed)),
+        _ => print!("{}", dag.to_mermaid(&changed)),
+    }
+
+    Ok(())
+}
+
+/// Build the DAG from the prompts committed at `ref_str`.
+fn dag_at(repo: &LitRepo, config: &LitConfig, ref_str: &str) -> Result<Dag> {
+    let mut prompts = Vec::new();
+    for (path, raw) in repo.prompt_files_at(ref_str)? {
+        prompts.push(
+            Prompt::parse(&raw, path.clone(), config)
+                .with_context(|| format!("Failed to parse {} at {}", path.display(), ref_str))?,
+        );
+    }
+    resolve_imports(&mut prompts)
+        .and_then(|_| Dag::build(&prompts))
+        .with_context(|| format!("Invalid prompt DAG at {}", ref_str))
+}
+
+/// `lit graph --diff <ref>` — structural changes to the DAG from `<ref>` to HEAD
+fn print_diff(base_ref: &str) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+
+    let base = dag_at(&repo, &config, base_ref)?;
+    let head = dag_at(&repo, &config, "HEAD")?;
+    let diff = head.diff(&base);
+
+    println!("DAG changes: {} → HEAD", base_ref);
+    if diff.is_empty() {
+        println!("  (no structural changes)");
+        return Ok(());
+    }
+    print_report(&diff);
+    Ok(())
+}
+
+fn print_report(diff: &DagDiff) {
+    if !diff.added_prompts.is_empty() || !diff.removed_prompts.is_empty() {
+        println!();
+        println!(
+            "  Prompts (+{} / -{}):",
+            diff.added_prompts.len(),
+            diff.removed_prompts.len()
+        );
+        for p in &diff.added_prompts {
+            println!("    + {}", p.display());
+        }
+        for p in &diff.removed_prompts {
+            println!("    - {}", p.display());
+        }
+    }
+
+    if !diff.added_edges.is_empty() || !diff.removed_edges.is_empty() {
+        println!();
+        println!(
+            "  Imports (+{} / -{}):",
+            diff.added_edges.len(),
+            diff.removed_edges.len()
+        );
+        for (prompt, import) in &diff.added_edges {
+            println!("    + {} ← {}", prompt.display(), import.display());
+        }
+        for (prompt, import) in &diff.removed_edges {
+            println!("    - {} ← {}", prompt.display(), import.display());
+        }
+    }
+
+    if !diff.ownership_changes.is_empty() {
+        println!();
+        println!("  Output ownership:");
+        let owner = |p: &Option<PathBuf>| {
+            p.as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "(none)".to_string())
+        };
+        for change in &diff.ownership_changes {
+            println!(
+                "    {}: {} → {}",
+                change.output.display(),
+                owner(&change.before),
+                owner(&change.after)
+            );
+        }
+    }
+
+    if !diff.cascade_changes.is_empty() {
+        println!();
+        println!("  Cascade size (transitive dependents):");
+        for (prompt, before, after) in &diff.cascade_changes {
+            println!("    {}: {} → {}", prompt.display(), before, after);
+        }
+    }
+}
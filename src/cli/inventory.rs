@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+
+use crate::core::config::LitConfig;
+use crate::core::generation_record::GenerationRecord;
+use crate::core::inventory::Inventory;
+use crate::core::patch::PatchStore;
+use crate::core::prompt::{Prompt, discover_project_prompts};
+
+/// Output formats supported by `lit inventory`
+pub const INVENTORY_FORMATS: &[&str] = &["json", "cyclonedx"];
+
+/// `lit inventory` — list every generated artifact with its provenance
+pub async fn run(format: &str) -> Result<()> {
+    if !INVENTORY_FORMATS.contains(&format) {
+        anyhow::bail!(
+            "Unknown inventory format '{}'. Must be one of: {}",
+            format,
+            INVENTORY_FORMATS.join(", ")
+        );
+    }
+
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    let prompt_paths = discover_project_prompts(&config, &root)?;
+    let mut prompts = Vec::new();
+    for p in &prompt_paths {
+        prompts.push(
+            Prompt::from_file(p, &root, &config)
+                .with_context(|| format!("Failed to parse {}", p.display()))?,
+        );
+    }
+
+    let records = GenerationRecord::list(&root.join(".lit").join("generations"))?;
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+
+    let inventory = Inventory::build(
+        &config,
+        &prompts,
+        &records,
+        &config.code_dir(&root),
+        &patch_store,
+    );
+
+    let output = match format {
+        "cyclonedx" => serde_json::to_string_pretty(&inventory.to_cyclonedx())?,
+        _ => serde_json::to_string_pretty(&inventory)?,
+    };
+    println!("{}", output);
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::generation_record::{format_cost, format_tokens};
+use crate::core::last_run::{LAST_RUN_FILE, LastRun, PromptStatus, RunStatus};
+use crate::core::style;
+
+/// `lit last` — show the summary of the last `lit regenerate` run, from
+/// `.lit/last-run.json`. With `json`, print the file itself to stdout.
+pub async fn run(json: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let lit_dir = root.join(".lit");
+
+    let last = LastRun::load(&lit_dir)?.context(
+        "No run recorded yet.\n\
+         Hint: Run `lit regenerate` first.",
+    )?;
+    if json {
+        let raw = std::fs::read_to_string(lit_dir.join(LAST_RUN_FILE))?;
+        print!("{}", raw);
+        return Ok(());
+    }
+
+    let status = match last.status {
+        RunStatus::Success => "success".green().bold(),
+        RunStatus::Conflicts => "patch conflicts".yellow().bold(),
+        RunStatus::Failed => "failed".red().bold(),
+        RunStatus::Interrupted => "interrupted".yellow().bold(),
+    };
+    eprintln!("{}", style::header("Last run"));
+    eprintln!(
+        "  {:<20} {}",
+        "When:".dimmed(),
+        last.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    eprintln!("  {:<20} {}", "Status:".dimmed(), status);
+    if let Some(ref error) = last.error {
+        eprintln!("  {:<20} {}", "Error:".dimmed(), error.lines().next().unwrap_or("").red());
+    }
+    eprintln!(
+        "  {:<20} {} generated, {} cached, {} rejected, {} unchanged",
+        "Prompts:".dimmed(),
+        last.count(PromptStatus::Generated).to_string().bold(),
+        last.count(PromptStatus::Cached),
+        last.count(PromptStatus::Rejected),
+        last.count(PromptStatus::Unchanged)
+    );
+    eprintln!("  {:<20} {}", "Files written:".dimmed(), last.files.len().to_string().bold());
+    eprintln!(
+        "  {:<20} {} in / {} out",
+        "Tokens:".dimmed(),
+        format_tokens(last.summary.total_tokens_in).dimmed(),
+        format_tokens(last.summary.total_tokens_out).dimmed()
+    );
+    eprintln!(
+        "  {:<20} {}",
+        "Cost:".dimmed(),
+        style::cost(&format_cost(last.summary.total_cost_usd))
+    );
+
+    let touched: Vec<_> = last
+        .prompts
+        .iter()
+        .filter(|p| p.status != PromptStatus::Unchanged)
+        .collect();
+    if !touched.is_empty() {
+        eprintln!();
+        eprintln!("{}", style::section("Prompts:"));
+        for prompt in touched {
+            let status = match prompt.status {
+                PromptStatus::Generated => "generated".cyan(),
+                PromptStatus::Cached => "cached".green(),
+                PromptStatus::Rejected => "rejected".yellow(),
+                PromptStatus::Failed => "failed".red(),
+                PromptStatus::Pending => "not reached".dimmed(),
+                PromptStatus::Unchanged => "unchanged".dimmed(),
+            };
+            eprintln!("    {:<11} {}", status, prompt.prompt.display());
+        }
+    }
+
+    if !last.warnings.is_empty() {
+        eprintln!();
+        eprintln!("{}", style::section("Warnings:"));
+        for warning in &last.warnings {
+            eprintln!("  {}", style::warning(warning));
+        }
+    }
+    Ok(())
+}
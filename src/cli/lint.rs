@@ -0,0 +1,88 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::core::config::{LintLevel, LitConfig};
+use crate::core::lint::{Finding, PARSE_RULE, RULES, level, lint_prompts};
+use crate::core::paths::relative_to;
+use crate::core::prompt::{Prompt, discover_project_prompts};
+use crate::core::style;
+
+/// `lit lint` — check every prompt against the `[lint]` rules.
+///
+/// Prompts that fail to parse are reported as errors rather than aborting,
+/// so one run shows everything. With `ci`, any error-level finding makes
+/// the command fail. With `list_rules`, print the rules instead.
+pub async fn run(ci: bool, list_rules: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    if list_rules {
+        eprintln!("{}", style::header("Lint rules"));
+        for rule in RULES {
+            let level = match level(&config, rule) {
+                LintLevel::Off => "off  ".dimmed(),
+                LintLevel::Warn => "warn ".yellow(),
+                LintLevel::Error => "error".red().bold(),
+            };
+            eprintln!("  {} {:<26} {}", level, rule.name, rule.description.dimmed());
+        }
+        return Ok(());
+    }
+
+    let paths = discover_project_prompts(&config, &root)?;
+    let mut prompts = Vec::new();
+    let mut findings = Vec::new();
+    for path in &paths {
+        match Prompt::from_file(path, &root, &config) {
+            Ok(prompt) => prompts.push(prompt),
+            Err(e) => findings.push(Finding {
+                prompt: relative_to(path, &root),
+                rule: PARSE_RULE,
+                level: LintLevel::Error,
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+    findings.extend(lint_prompts(&prompts, &config));
+    findings.sort_by(|a, b| a.prompt.cmp(&b.prompt));
+
+    eprintln!("{}", style::header("Lint"));
+    let mut current = None;
+    for finding in &findings {
+        if current != Some(&finding.prompt) {
+            eprintln!("  {}", finding.prompt.display().to_string().bold());
+            current = Some(&finding.prompt);
+        }
+        let level = match finding.level {
+            LintLevel::Error => "error".red().bold(),
+            _ => "warn ".yellow(),
+        };
+        eprintln!(
+            "    {} {:<26} {}",
+            level,
+            finding.rule.dimmed(),
+            finding.message.lines().next().unwrap_or("")
+        );
+    }
+
+    let errors = findings.iter().filter(|f| f.level == LintLevel::Error).count();
+    let warnings = findings.len() - errors;
+    if findings.is_empty() {
+        eprintln!("{}", style::success(&format!("{} prompt(s), no problems", paths.len())));
+        return Ok(());
+    }
+    eprintln!();
+    eprintln!(
+        "  {} error(s), {} warning(s) in {} prompt(s)",
+        errors.to_string().bold(),
+        warnings.to_string().bold(),
+        paths.len()
+    );
+    if ci && errors > 0 {
+        anyhow::bail!("lint failed with {} error(s)", errors);
+    }
+    if errors > 0 {
+        eprintln!("{}", style::hint("Run `lit lint --ci` to fail on errors, e.g. in CI."));
+    }
+    Ok(())
+}
@@ -1,15 +1,31 @@
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
 use chrono::TimeZone;
 use colored::Colorize;
 
+use crate::cli::cost::commit_records;
+use crate::cli::regenerate::print_diff;
 use crate::core::config::LitConfig;
+use crate::core::generation_record::{GenerationRecord, format_cost, format_tokens};
+use crate::core::generator::change_summary;
+use crate::core::history::{body_changes, fresh_generations, frontmatter_changes};
+use crate::core::migrate::frontmatter_value;
+use crate::core::paths::relative_to;
+use crate::core::prompt::Prompt;
 use crate::core::repo::LitRepo;
+use crate::core::style;
 
-pub async fn run(limit: usize) -> Result<()> {
+pub async fn run(limit: usize, prompt: Option<PathBuf>, patch: bool) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
 
     let repo = LitRepo::open(&root)?;
+    if let Some(prompt) = prompt {
+        let path = cwd.join(&prompt);
+        let path = relative_to(&path, &root);
+        return prompt_log(&config, &root, &repo, &path, limit, patch);
+    }
     let commits = repo.log(limit)?;
 
     if commits.is_empty() {
@@ -18,16 +34,18 @@ pub async fn run(limit: usize) -> Result<()> {
     }
 
     for commit in &commits {
-        let datetime = chrono::Utc
-            .timestamp_opt(commit.timestamp, 0)
-            .single()
-            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-            .unwrap_or_else(|| "unknown".to_string());
-
+        // What the generation runs committed here cost (see `lit cost --commit`)
+        let cost = commit_records(&repo, &commit.hash)?.iter().fold(0.0, |sum, r| sum + r.summary.total_cost_usd);
+        let cost = if cost > 0.0 {
+            format!("{:>9}", format_cost(cost)).yellow()
+        } else {
+            format!("{:>9}", "").normal()
+        };
         eprintln!(
-            "{} {} — {}",
+            "{} {} {} — {}",
             commit.short_hash.yellow(),
-            datetime.dimmed(),
+            format_timestamp(commit.timestamp).dimmed(),
+            cost,
             commit.message
         );
     }
@@ -42,3 +60,153 @@ pub async fn run(limit: usize) -> Result<()> {
 
     Ok(())
 }
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    chrono::Utc
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// `lit log --prompt <path>` — the commits that touched one prompt, newest
+/// first, each with its frontmatter changes, body line changes, the fresh
+/// generations it took (from .lit/generations/) and what it changed in the
+/// prompt's outputs. Uncommitted edits are shown first.
+fn prompt_log(config: &LitConfig, root: &Path, repo: &LitRepo, path: &Path, limit: usize, patch: bool) -> Result<()> {
+    // One more than shown, so the oldest shown commit knows when the
+    // edit before it was committed
+    let revisions = repo.file_history(path, limit + 1)?;
+    let working = std::fs::read_to_string(root.join(path)).ok();
+    if revisions.is_empty() && working.is_none() {
+        bail!("No history for {}", path.display());
+    }
+    let records = GenerationRecord::list(&root.join(".lit").join("generations"))?;
+    let code_dir = PathBuf::from(&config.project.code_dir);
+
+    eprintln!("{}", style::header(&format!("History of {}", path.display())));
+
+    let head = revisions.first().and_then(|r| r.after.clone());
+    if working != head {
+        eprintln!("{} — {}", "(working tree)".yellow(), "uncommitted".dimmed());
+        let since = revisions.first().map(|r| r.commit.timestamp);
+        describe_edit(records.as_slice(), path, head.as_deref(), working.as_deref(), (since, None), patch);
+        for output in outputs(config, path, working.as_deref().or(head.as_deref())) {
+            let before = repo.file_at("HEAD", &code_dir.join(&output)).ok().flatten();
+            let after = std::fs::read_to_string(root.join(&code_dir).join(&output)).ok();
+            print_output_change(&output, before.as_deref(), after.as_deref());
+        }
+        eprintln!();
+    }
+
+    for (i, revision) in revisions.iter().take(limit).enumerate() {
+        let commit = &revision.commit;
+        eprintln!(
+            "{} {} — {}",
+            commit.short_hash.yellow(),
+            format_timestamp(commit.timestamp).dimmed(),
+            commit.message
+        );
+        let since = revisions.get(i + 1).map(|r| r.commit.timestamp);
+        describe_edit(
+            records.as_slice(),
+            path,
+            revision.before.as_deref(),
+            revision.after.as_deref(),
+            (since, Some(commit.timestamp)),
+            patch,
+        );
+        for output in outputs(config, path, revision.after.as_deref().or(revision.before.as_deref())) {
+            let output_path = code_dir.join(&output);
+            let before = match &revision.parent {
+                Some(parent) => repo.file_at(parent, &output_path)?,
+                None => None,
+            };
+            let after = repo.file_at(&commit.hash, &output_path)?;
+            print_output_change(&output, before.as_deref(), after.as_deref());
+        }
+        eprintln!();
+    }
+
+    if revisions.len() > limit {
+        eprintln!(
+            "{}",
+            format!("(showing {} of possibly more — use -n to increase)", limit).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Print what one edit changed in the prompt and what regenerating it cost
+fn describe_edit(
+    records: &[GenerationRecord],
+    path: &Path,
+    before: Option<&str>,
+    after: Option<&str>,
+    (since, until): (Option<i64>, Option<i64>),
+    patch: bool,
+) {
+    match (before, after) {
+        (None, Some(_)) => eprintln!("    {}", "created".green()),
+        (Some(_), None) => eprintln!("    {}", "deleted".red()),
+        _ => {}
+    }
+    for change in frontmatter_changes(before, after) {
+        eprintln!("    {}", change.to_string().cyan());
+    }
+    let (added, removed) = body_changes(before, after);
+    if added + removed > 0 {
+        eprintln!(
+            "    body {}/{} lines",
+            format!("+{}", added).green(),
+            format!("−{}", removed).red()
+        );
+    }
+
+    let generations = fresh_generations(records, path, since, until);
+    if !generations.is_empty() {
+        let tokens_in: u64 = generations.iter().map(|g| g.tokens_in).sum();
+        let tokens_out: u64 = generations.iter().map(|g| g.tokens_out).sum();
+        let cost: f64 = generations.iter().map(|g| g.cost_usd).sum();
+        eprintln!(
+            "    {} generation{}, {} in / {} out, {}",
+            generations.len(),
+            if generations.len() == 1 { "" } else { "s" },
+            format_tokens(tokens_in),
+            format_tokens(tokens_out),
+            style::cost(&format_cost(cost))
+        );
+    }
+
+    if patch {
+        print_diff(before.unwrap_or(""), after.unwrap_or(""));
+    }
+}
+
+/// The prompt's output files as of one version of it. A version that no
+/// longer parses against today's lit.toml (e.g. naming a removed tier)
+/// falls back to its literal `outputs:` list.
+fn outputs(config: &LitConfig, path: &Path, raw: Option<&str>) -> Vec<PathBuf> {
+    let Some(raw) = raw else {
+        return Vec::new();
+    };
+    if let Ok(prompt) = Prompt::parse(raw, path.to_path_buf(), config) {
+        return prompt.frontmatter.outputs;
+    }
+    frontmatter_value(raw)
+        .ok()
+        .and_then(|value| value.get("outputs").and_then(|o| o.as_sequence()).cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|output| output.as_str().map(PathBuf::from))
+        .collect()
+}
+
+fn print_output_change(output: &Path, before: Option<&str>, after: Option<&str>) {
+    match (before, after) {
+        (before, _) if before == after => {}
+        (Some(_), None) => eprintln!("    {} {}", "removed".red(), output.display()),
+        (before, Some(after)) => eprintln!("    {}", change_summary(output, before, after)),
+        (None, None) => {}
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::api::RegenerateOptions;
+use crate::cli::regenerate::{self, TerminalOptions};
+use crate::core::config::{LintLevel, LitConfig};
+use crate::core::lsp::{Project, path_to_uri, read_message, uri_to_path, write_message};
+use crate::core::paths::normalize;
+
+/// Command offered as a code action on every prompt
+const REGENERATE_COMMAND: &str = "lit.regenerate";
+
+/// JSON-RPC "method not found"
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// `lit lsp` — a language server on stdin/stdout for editor plugins.
+///
+/// Publishes diagnostics for prompt files (parse errors, missing imports,
+/// output conflicts and `[lint]` findings) as they are edited, answers
+/// hovers with a prompt's outputs and downstream impact, and offers a
+/// "Regenerate" code action. Logs go to stderr.
+pub async fn run() -> Result<()> {
+    let mut reader = BufReader::new(std::io::stdin());
+    let mut server = Server::new(std::env::current_dir()?);
+
+    while let Some(message) = read_message(&mut reader)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        match method {
+            "initialize" => {
+                if let Some(root) = params["rootUri"].as_str().and_then(uri_to_path) {
+                    server.root = root;
+                }
+                if let Ok(root) = LitConfig::find_root(&server.root) {
+                    std::env::set_current_dir(&root)?;
+                    server.root = root;
+                }
+                server.respond(
+                    id,
+                    json!({
+                        "capabilities": {
+                            "textDocumentSync": 1,
+                            "hoverProvider": true,
+                            "codeActionProvider": true,
+                            "executeCommandProvider": { "commands": [REGENERATE_COMMAND] },
+                        },
+                        "serverInfo": { "name": "lit", "version": env!("CARGO_PKG_VERSION") },
+                    }),
+                )?;
+            }
+            "initialized" => server.publish_diagnostics()?,
+            "shutdown" => server.respond(id, Value::Null)?,
+            "exit" => return Ok(()),
+            "textDocument/didOpen" => {
+                if let Some(path) = server.document_path(&params["textDocument"]) {
+                    let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                    server.open.insert(path, text.to_string());
+                }
+                server.publish_diagnostics()?;
+            }
+            "textDocument/didChange" => {
+                // Full document sync: the last change holds the whole text
+                if let Some(path) = server.document_path(&params["textDocument"])
+                    && let Some(text) = params["contentChanges"]
+                        .as_array()
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change["text"].as_str())
+                {
+                    server.open.insert(path, text.to_string());
+                }
+                server.publish_diagnostics()?;
+            }
+            "textDocument/didSave" => server.publish_diagnostics()?,
+            "textDocument/didClose" => {
+                if let Some(path) = server.document_path(&params["textDocument"]) {
+                    server.open.remove(&path);
+                }
+                server.publish_diagnostics()?;
+            }
+            "textDocument/hover" => {
+                let hover = server
+                    .document_path(&params["textDocument"])
+                    .and_then(|path| Some((server.project()?, path)))
+                    .and_then(|(project, path)| project.hover(&path));
+                let result = match hover {
+                    Some(text) => json!({ "contents": { "kind": "markdown", "value": text } }),
+                    None => Value::Null,
+                };
+                server.respond(id, result)?;
+            }
+            "textDocument/codeAction" => {
+                let mut actions = Vec::new();
+                if let Some(path) = server.document_path(&params["textDocument"])
+                    && server.project().is_some_and(|project| project.has_prompt(&path))
+                {
+                    let title = format!("Regenerate {}", path.display());
+                    actions.push(json!({
+                        "title": title,
+                        "kind": "source",
+                        "command": {
+                            "title": title,
+                            "command": REGENERATE_COMMAND,
+                            "arguments": [path.display().to_string()],
+                        },
+                    }));
+                }
+                server.respond(id, Value::Array(actions))?;
+            }
+            "workspace/executeCommand" if params["command"] == REGENERATE_COMMAND => {
+                let paths: Vec<PathBuf> = params["arguments"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|arg| arg.as_str().map(PathBuf::from))
+                    .collect();
+                let options = RegenerateOptions { prompts: paths.clone(), ..Default::default() };
+                let result = regenerate::run(options, TerminalOptions::UNATTENDED).await;
+                let (kind, text) = match result {
+                    Ok(()) => (3, format!("Regenerated {}", display_paths(&paths))),
+                    Err(e) => (1, format!("lit regenerate failed: {:#}", e)),
+                };
+                server.notify("window/showMessage", json!({ "type": kind, "message": text }))?;
+                server.respond(id, Value::Null)?;
+                server.publish_diagnostics()?;
+            }
+            _ => {
+                // Unknown notifications are ignored; unknown requests get an error
+                if id.is_some() {
+                    server.send(json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": format!("Unsupported method: {}", method) },
+                    }))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    root: PathBuf,
+    /// Open documents (path relative to root → buffer content)
+    open: HashMap<PathBuf, String>,
+    /// Prompts last published with diagnostics, to clear once fixed
+    published: BTreeSet<PathBuf>,
+}
+
+impl Server {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            open: HashMap::new(),
+            published: BTreeSet::new(),
+        }
+    }
+
+    /// The project as currently edited; None (logged) when lit.toml is
+    /// missing or invalid
+    fn project(&self) -> Option<Project> {
+        let result = LitConfig::from_file(&self.root.join("lit.toml"))
+            .and_then(|config| Project::load(&config, &self.root, &self.open));
+        match result {
+            Ok(project) => Some(project),
+            Err(e) => {
+                eprintln!("lit lsp: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Path relative to root of a `{ "uri": ... }` document identifier
+    fn document_path(&self, document: &Value) -> Option<PathBuf> {
+        let path = uri_to_path(document["uri"].as_str()?)?;
+        Some(normalize(path.strip_prefix(&self.root).ok()?))
+    }
+
+    fn publish_diagnostics(&mut self) -> Result<()> {
+        let Some(project) = self.project() else {
+            return Ok(());
+        };
+        let all = project.diagnostics();
+        let mut published = BTreeSet::new();
+        for (path, diagnostics) in &all {
+            if diagnostics.is_empty() && !self.published.contains(path) {
+                continue;
+            }
+            let items: Vec<Value> = diagnostics
+                .iter()
+                .map(|d| {
+                    json!({
+                        "range": {
+                            "start": { "line": d.line, "character": 0 },
+                            "end": { "line": d.line + 1, "character": 0 },
+                        },
+                        "severity": if d.level == LintLevel::Error { 1 } else { 2 },
+                        "source": "lit",
+                        "code": d.rule,
+                        "message": d.message,
+                    })
+                })
+                .collect();
+            if !items.is_empty() {
+                published.insert(path.clone());
+            }
+            self.notify(
+                "textDocument/publishDiagnostics",
+                json!({ "uri": path_to_uri(&self.root.join(path)), "diagnostics": items }),
+            )?;
+        }
+        // Prompts deleted since the last publish
+        for path in self.published.iter().filter(|path| !all.contains_key(*path)) {
+            self.notify(
+                "textDocument/publishDiagnostics",
+                json!({ "uri": path_to_uri(&self.root.join(path)), "diagnostics": [] }),
+            )?;
+        }
+        self.published = published;
+        Ok(())
+    }
+
+    fn respond(&self, id: Option<Value>, result: Value) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn notify(&self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn send(&self, message: Value) -> Result<()> {
+        write_message(&mut std::io::stdout().lock(), &message)
+    }
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    if paths.is_empty() {
+        return "changed prompts".to_string();
+    }
+    paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+}
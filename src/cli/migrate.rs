@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::migrate::{
+    SCHEMA_MIGRATIONS, SCHEMA_VERSION, config_schema, migrate_config, migrate_prompt,
+    pending_migrations,
+};
+use crate::core::paths::relative_to;
+use crate::core::prompt::{Prompt, discover_project_prompts};
+use crate::core::style;
+
+use super::regenerate::print_diff;
+
+/// `lit migrate` — upgrade lit.toml and every prompt file to the current
+/// schema.
+///
+/// Works on repos the current lit refuses to load. Everything is migrated
+/// and validated before anything is written; `--dry-run` only shows the
+/// diffs, and `--check` fails if anything needs migrating (for CI).
+pub async fn run(dry_run: bool, check: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let root = LitConfig::find_root(&cwd)?;
+
+    let config_path = root.join("lit.toml");
+    let config_raw = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    let schema = config_schema(&config_raw)?;
+    let mut changes: Vec<(PathBuf, String, String)> = Vec::new();
+    if let Some(new) = migrate_config(&config_raw, SCHEMA_MIGRATIONS).context("Failed to migrate lit.toml")? {
+        changes.push((PathBuf::from("lit.toml"), config_raw.clone(), new));
+    }
+    let config_raw = changes.first().map_or(&config_raw, |(_, _, new)| new);
+    let config = LitConfig::from_str(config_raw).context("lit.toml would be invalid after migrating")?;
+
+    let prompt_paths = discover_project_prompts(&config, &root)?;
+    for full_path in &prompt_paths {
+        let relative = relative_to(full_path, &root);
+        let raw = std::fs::read_to_string(full_path)
+            .with_context(|| format!("Failed to read {}", full_path.display()))?;
+        let updated = migrate_prompt(&raw, SCHEMA_MIGRATIONS)
+            .with_context(|| format!("Failed to migrate {}", relative.display()))?;
+        let content = updated.as_deref().unwrap_or(&raw);
+        Prompt::parse(content, relative.clone(), &config)
+            .with_context(|| format!("{} would be invalid after migrating", relative.display()))?;
+        if let Some(new) = updated {
+            changes.push((relative, raw, new));
+        }
+    }
+
+    eprintln!("{}", style::header("Schema migration"));
+    eprintln!("  {:<20} {}", "Current schema:".dimmed(), SCHEMA_VERSION.to_string().bold());
+    if changes.is_empty() {
+        eprintln!(
+            "{}",
+            style::success(&format!(
+                "lit.toml and {} prompt(s) are up to date",
+                prompt_paths.len()
+            ))
+        );
+        return Ok(());
+    }
+    for migration in pending_migrations(schema, SCHEMA_MIGRATIONS)? {
+        eprintln!("  {} schema {}: {}", "→".cyan(), migration.to, migration.description);
+    }
+    eprintln!("  {} file(s) to migrate", changes.len().to_string().bold());
+    eprintln!();
+
+    if check {
+        for (path, _, _) in &changes {
+            eprintln!("{}", style::file_modified(&path.display().to_string()));
+        }
+        anyhow::bail!("{} file(s) need migrating. Run `lit migrate`.", changes.len());
+    }
+
+    for (path, old, new) in &changes {
+        eprintln!("{}", style::file_modified(&path.display().to_string()));
+        if dry_run {
+            print_diff(old, new);
+        } else {
+            let full_path = root.join(path);
+            std::fs::write(&full_path, new)
+                .with_context(|| format!("Failed to write {}", full_path.display()))?;
+        }
+    }
+
+    eprintln!();
+    if dry_run {
+        eprintln!("{}", style::hint("Dry run — no files written. Re-run without --dry-run to apply."));
+    } else {
+        eprintln!(
+            "{}",
+            style::hint("Migrated prompts have new cache keys; run `lit regenerate` to rebuild them.")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::dag::Dag;
+use crate::core::migrate::{Filter, FrontmatterEdit, apply_edits, frontmatter_value};
+use crate::core::paths::{relative_to, to_slash};
+use crate::core::prompt::{Prompt, discover_project_prompts, resolve_imports};
+use crate::core::style;
+
+use super::regenerate::print_diff;
+
+/// `lit migrate-frontmatter` — apply the same frontmatter change to every
+/// prompt matching the `--where` conditions.
+///
+/// All edited prompts are validated (and the DAG rebuilt) before anything
+/// is written; `--dry-run` only shows the diffs.
+pub async fn run(
+    set: Vec<String>,
+    unset: Vec<String>,
+    filters: Vec<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut edits = Vec::new();
+    for arg in &set {
+        edits.push(FrontmatterEdit::parse_set(arg)?);
+    }
+    for arg in &unset {
+        edits.push(FrontmatterEdit::parse_unset(arg)?);
+    }
+    if edits.is_empty() {
+        anyhow::bail!("Nothing to change.\nHint: Pass at least one --set key=value or --unset key.");
+    }
+    let filters = filters
+        .iter()
+        .map(|f| Filter::parse(f))
+        .collect::<Result<Vec<_>>>()?;
+
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let prompt_paths = discover_project_prompts(&config, &root)?;
+
+    let mut matched = 0;
+    let mut changes: Vec<(PathBuf, String, String)> = Vec::new();
+    let mut prompts = Vec::new();
+    for full_path in &prompt_paths {
+        let relative = relative_to(full_path, &root);
+        let raw = std::fs::read_to_string(full_path)
+            .with_context(|| format!("Failed to read {}", full_path.display()))?;
+        let frontmatter = frontmatter_value(&raw)
+            .with_context(|| format!("Failed to parse {}", relative.display()))?;
+
+        let path_str = to_slash(&relative);
+        let updated = if filters.iter().all(|f| f.matches(&path_str, &frontmatter)) {
+            matched += 1;
+            apply_edits(&raw, &edits)
+                .with_context(|| format!("Failed to edit {}", relative.display()))?
+        } else {
+            None
+        };
+
+        let content = updated.as_deref().unwrap_or(&raw);
+        prompts.push(
+            Prompt::parse(content, relative.clone(), &config)
+                .with_context(|| format!("{} would be invalid after the change", relative.display()))?,
+        );
+        if let Some(new) = updated {
+            changes.push((relative, raw, new));
+        }
+    }
+    resolve_imports(&mut prompts)
+        .and_then(|_| Dag::build(&prompts))
+        .context("The change would break the prompt DAG")?;
+
+    eprintln!("{}", style::header("Frontmatter migration"));
+    eprintln!(
+        "  {} of {} prompt(s) match, {} to change",
+        matched.to_string().bold(),
+        prompt_paths.len(),
+        changes.len().to_string().bold()
+    );
+    if changes.is_empty() {
+        return Ok(());
+    }
+    eprintln!();
+
+    for (path, old, new) in &changes {
+        eprintln!("{}", style::file_modified(&path.display().to_string()));
+        if dry_run {
+            print_diff(old, new);
+        } else {
+            let full_path = root.join(path);
+            std::fs::write(&full_path, new)
+                .with_context(|| format!("Failed to write {}", full_path.display()))?;
+        }
+    }
+
+    eprintln!();
+    if dry_run {
+        eprintln!("{}", style::hint("Dry run — no files written. Re-run without --dry-run to apply."));
+    } else {
+        eprintln!(
+            "{}",
+            style::hint("Review with `lit diff`, then `lit regenerate` to apply the new settings.")
+        );
+    }
+    Ok(())
+}
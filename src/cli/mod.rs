@@ -3,33 +3,81 @@ pub mod add;
 pub mod commit;
 pub mod diff;
 pub mod status;
+pub mod lint;
 pub mod log;
 pub mod regenerate;
+pub mod last;
 pub mod checkout;
 pub mod push;
 pub mod pull;
 pub mod clone;
+pub mod remote;
+pub mod describe;
 pub mod cost;
+pub mod inventory;
+pub mod gitignore;
+pub mod graph;
+pub mod migrate;
+pub mod migrate_frontmatter;
+pub mod mv;
+pub mod sync_from_code;
+pub mod verify_determinism;
 pub mod debug;
 pub mod patch;
+pub mod freeze;
+pub mod workspace;
+pub mod lsp;
+pub mod serve;
+pub mod explain;
+pub mod test;
+pub mod eval;
+pub mod sweep;
+pub mod stash;
+pub mod auth;
+pub mod show;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
+use crate::api::RegenerateOptions;
+use crate::cli::regenerate::TerminalOptions;
+use crate::core::config::LitConfig;
+use crate::core::lock::RepoLock;
+use crate::core::progress::{self, Verbosity};
+use crate::core::run_log;
+use crate::core::prompt::TagFilter;
+use crate::core::workspace::Workspace;
+
 #[derive(Parser)]
 #[command(name = "lit")]
 #[command(about = "Prompt-first version control — prompts are source, code is the artifact")]
 #[command(version)]
 pub struct Cli {
-    /// Enable verbose output
+    /// Enable verbose output (context files, input hashes, cache decisions)
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
+    /// Only print errors, warnings and final summaries (for CI)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Append a structured JSON-lines log of the run (provider calls and
+    /// latency, retries, cache decisions, patches) to this file, e.g.
+    /// .lit/logs/run.jsonl
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Wait for another lit command running in this repository to finish
+    /// instead of failing
+    #[arg(long, global = true)]
+    pub wait: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
-#[derive(Subcommand)]
+#[derive(Clone, Subcommand)]
 pub enum Commands {
     /// Initialize a new lit repository
     Init {
@@ -49,13 +97,76 @@ pub enum Commands {
         /// Commit message
         #[arg(short, long)]
         message: String,
+
+        /// Commit even if code.lock/ is stale relative to the prompts
+        #[arg(long)]
+        allow_stale: bool,
+
+        /// Regenerate changed prompts and their dependents before committing
+        #[arg(long, conflicts_with = "no_regenerate")]
+        regenerate: bool,
+
+        /// Commit as-is even if lit.toml sets [commit] regenerate = true
+        #[arg(long)]
+        no_regenerate: bool,
     },
 
     /// Show the state of prompts and generated code
-    Status,
+    Status {
+        #[command(flatten)]
+        tags: TagArgs,
+    },
+
+    /// Check prompts for common mistakes ([lint] in lit.toml)
+    Lint {
+        /// Exit non-zero when any error-level rule fails (for CI)
+        #[arg(long)]
+        ci: bool,
+
+        /// List the rules and their configured levels
+        #[arg(long)]
+        rules: bool,
+    },
+
+    /// Check code.lock/ against each prompt's tests (`tests:` in the
+    /// frontmatter, or a sibling <name>.test.yaml)
+    Test {
+        /// Prompts to test (default: every prompt with tests)
+        paths: Vec<PathBuf>,
+
+        #[command(flatten)]
+        tags: TagArgs,
+    },
+
+    /// Generate the whole project with two models and compare test pass
+    /// rates, cost, tokens and output (into .lit/eval/a and .lit/eval/b)
+    Eval {
+        /// First model to evaluate
+        #[arg(long)]
+        model_a: String,
+
+        /// Second model to evaluate
+        #[arg(long)]
+        model_b: String,
+
+        /// Command to run against each model's code, with `{dir}` replaced by
+        /// its directory (e.g. "pytest {dir}")
+        #[arg(long)]
+        verify: Option<String>,
 
-    /// Show prompt changes since last commit
+        /// Skip the cache and call the models for every prompt
+        #[arg(long)]
+        no_cache: bool,
+    },
+
+    /// Show prompt changes since last commit, or between two commits
     Diff {
+        /// Diff two commits instead of the working tree against HEAD:
+        /// `<ref1>..<ref2>`, or `<ref1>...<ref2>` for what <ref2> changed
+        /// since it branched off <ref1>
+        #[arg(value_name = "RANGE", conflicts_with = "preview")]
+        range: Option<String>,
+
         /// Show generated code diffs instead of prompt diffs
         #[arg(long)]
         code: bool,
@@ -67,13 +178,35 @@ pub enum Commands {
         /// Show structured change summary with DAG impact analysis
         #[arg(long)]
         summary: bool,
+
+        /// With --code: generate stale prompts into .lit/preview/ and diff
+        /// code.lock/ against that, without touching code.lock/
+        #[arg(long, requires = "code")]
+        preview: bool,
     },
 
-    /// Show commit history
+    /// Show commit history, or one prompt's history with --prompt
     Log {
         /// Maximum number of commits to show
         #[arg(short = 'n', long, default_value = "10")]
         limit: usize,
+        /// Show the commits that touched this prompt, with frontmatter
+        /// changes, generation cost and output changes
+        #[arg(long, value_name = "PATH")]
+        prompt: Option<PathBuf>,
+        /// With --prompt, also show each edit's diff
+        #[arg(short = 'p', long, requires = "prompt")]
+        patch: bool,
+    },
+
+    /// Print a prompt or generated file as of a commit, without checking it out
+    Show {
+        /// Commit-ish (hash, HEAD~N, branch), or `<ref>:<path>`
+        #[arg(value_name = "REF")]
+        spec: String,
+        /// Prompt, code.lock/ file or directory to show (code.lock/ may be
+        /// left off); without one, the commit and the prompts it changed
+        path: Option<PathBuf>,
     },
 
     /// Re-derive code.lock/ from current prompts without committing
@@ -92,6 +225,56 @@ pub enum Commands {
         /// Ignore manual patches (regenerate purely from prompts)
         #[arg(long)]
         no_patches: bool,
+
+        /// Regenerate dependents even when an import's output is unchanged
+        #[arg(long)]
+        no_prune: bool,
+
+        /// Review each fresh generation (approve / retry / skip) before writing
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Resolve patch conflicts in the terminal instead of writing conflict markers
+        #[arg(long)]
+        interactive_conflicts: bool,
+
+        /// Submit each DAG level as a batch job (half price, results can take hours)
+        #[arg(long)]
+        batch: bool,
+
+        /// Write the generated files here instead of the code directory,
+        /// leaving the manifest, patches and interfaces untouched
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+
+        /// Regenerate just this prompt, leaving its dependents stale instead
+        /// of cascading to them
+        #[arg(long, requires = "path", conflicts_with = "all")]
+        only: bool,
+
+        /// Don't ask before a large regeneration (see `[generation] confirm_above`)
+        #[arg(short, long)]
+        yes: bool,
+
+        #[command(flatten)]
+        tags: TagArgs,
+    },
+
+    /// Pin prompts to their code.lock/ content so regenerate never calls
+    /// the LLM for them (lists frozen prompts when none are given)
+    Freeze {
+        prompts: Vec<PathBuf>,
+    },
+    /// Let regenerate generate frozen prompts again
+    Unfreeze {
+        #[arg(required = true)]
+        prompts: Vec<PathBuf>,
+    },
+    /// Show the summary of the last `lit regenerate` run
+    Last {
+        /// Print .lit/last-run.json to stdout
+        #[arg(long)]
+        json: bool,
     },
 
     /// Manage manual patches to generated code
@@ -100,23 +283,81 @@ pub enum Commands {
         action: PatchCommands,
     },
 
+    /// Park uncommitted prompt and patch changes, restoring them as at
+    /// HEAD (default: push)
+    Stash {
+        #[command(subcommand)]
+        action: Option<StashCommands>,
+    },
+
     /// Restore prompts and code from a previous commit
     Checkout {
         /// Commit hash or ref (e.g., HEAD~3)
         #[arg(name = "ref")]
         ref_: String,
+        /// Discard uncommitted changes instead of refusing
+        #[arg(long)]
+        force: bool,
+        /// Carry .lit/patches/ and uncommitted code.lock/ hand edits over to
+        /// the checked-out commit
+        #[arg(long)]
+        keep_patches: bool,
     },
 
-    /// Push to remote (thin wrapper around git push)
-    Push,
+    /// Add or list git remotes (lists them when no subcommand is given)
+    Remote {
+        #[command(subcommand)]
+        action: Option<RemoteCommands>,
+    },
 
-    /// Pull from remote (thin wrapper around git pull)
-    Pull,
+    /// Push to remote (refuses when code.lock/ is stale)
+    Push {
+        /// Push even if code.lock/ is stale relative to the prompts (not a force-push)
+        #[arg(long)]
+        force: bool,
+        /// Then post a pull request description, as `lit describe --post`
+        #[arg(long)]
+        describe: bool,
+    },
+
+    /// Pull from remote and offer to regenerate prompts left stale
+    Pull {
+        /// Regenerate stale prompts without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Write a pull request description: prompt changes, regenerated code,
+    /// generation cost and new patches since the base branch
+    Describe {
+        /// Branch the pull request merges into (default: [describe] base, or main)
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+        /// Open with an overview paragraph written by one cheap LLM call
+        #[arg(long)]
+        llm: bool,
+        /// Post it to the branch's pull request on GitHub or GitLab, opening
+        /// one if there is none
+        #[arg(long)]
+        post: bool,
+    },
 
-    /// Clone a lit repository
+    /// Clone a lit repository and set up its local lit state
     Clone {
         /// Repository URL
         url: String,
+        /// Directory to clone into (default: named after the repository)
+        directory: Option<PathBuf>,
+        /// Rebuild cache entries from the committed code.lock/ so unchanged
+        /// prompts need no LLM calls
+        #[arg(long)]
+        warm_cache: bool,
+    },
+
+    /// Store provider API keys in the OS keychain
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommands,
     },
 
     /// Show token and cost tracking
@@ -128,6 +369,145 @@ pub enum Commands {
         /// Show per-prompt cost breakdown
         #[arg(long)]
         breakdown: bool,
+
+        /// Estimate the cost of regenerating all prompts (no API calls)
+        #[arg(long, conflicts_with = "last")]
+        estimate: bool,
+        /// Show tokens and cost of one prompt across every generation record
+        #[arg(long, value_name = "PROMPT", conflicts_with_all = ["last", "estimate"])]
+        history: Option<PathBuf>,
+        /// Show the N most expensive prompts, cumulatively
+        #[arg(long, value_name = "N", conflicts_with_all = ["last", "estimate", "history"])]
+        top: Option<usize>,
+        /// Print every prompt entry of every generation record to stdout: csv or json
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["last", "estimate", "history", "top"])]
+        export: Option<String>,
+        /// Show what the generation runs committed in a commit, or a range A..B, cost
+        #[arg(long, value_name = "REF", conflicts_with_all = ["last", "estimate", "history", "top", "export"])]
+        commit: Option<String>,
+
+        #[command(flatten)]
+        tags: TagArgs,
+    },
+
+    /// Upgrade lit.toml and prompt files to the current schema
+    Migrate {
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Fail if anything needs migrating, without writing (for CI)
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+    },
+
+    /// Bulk-edit prompt frontmatter
+    MigrateFrontmatter {
+        /// Set a frontmatter key (dotted path, YAML value), e.g. model.provider=anthropic
+        #[arg(long, value_name = "KEY=VALUE")]
+        set: Vec<String>,
+
+        /// Remove a frontmatter key
+        #[arg(long, value_name = "KEY")]
+        unset: Vec<String>,
+
+        /// Only edit prompts matching this condition (repeatable, all must hold),
+        /// e.g. 'imports contains prompts/models/base.prompt.md'
+        #[arg(long = "where", value_name = "CONDITION")]
+        filters: Vec<String>,
+
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Rename a prompt, rewriting imports that reference it
+    Mv {
+        /// Current prompt path
+        from: PathBuf,
+
+        /// New prompt path
+        to: PathBuf,
+
+        /// Also move one of the prompt's outputs (repeatable), with its code and patch
+        #[arg(long = "output", value_name = "OLD=NEW")]
+        outputs: Vec<String>,
+    },
+
+    /// Fold a hand-edit of a generated file back into its prompt
+    SyncFromCode {
+        /// Generated file (relative to code.lock/, or a path inside it)
+        file: PathBuf,
+
+        /// Write the proposed prompt without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Generate prompts several times at temperature 0 and report which
+    /// ones produce different code from run to run
+    VerifyDeterminism {
+        /// Prompts to check (default: all)
+        prompts: Vec<PathBuf>,
+        /// Generations per prompt
+        #[arg(long, default_value = "3")]
+        runs: usize,
+        /// Only check this many prompts, spread evenly through the DAG
+        #[arg(long, value_name = "N")]
+        sample: Option<usize>,
+        #[command(flatten)]
+        tags: TagArgs,
+    },
+    /// Generate prompts once per seed and temperature, score how much their
+    /// output varies, and record the score for `lit status`
+    Sweep {
+        /// Prompts to sweep
+        #[arg(required = true)]
+        prompts: Vec<PathBuf>,
+        /// Seeds to generate with (comma-separated)
+        #[arg(long, value_delimiter = ',', default_value = "1,2,3")]
+        seeds: Vec<u64>,
+        /// Temperatures to generate at (comma-separated; default: each prompt's own)
+        #[arg(long, value_delimiter = ',')]
+        temperatures: Vec<f64>,
+    },
+    /// Run a language server on stdin/stdout for editor integrations
+    /// (diagnostics, hovers and a regenerate code action for prompts)
+    Lsp,
+    /// Serve a local web dashboard: the DAG, cost history, patches, and
+    /// regeneration of a selected prompt
+    Serve {
+        /// Port to listen on (127.0.0.1 only)
+        #[arg(long, default_value = "4848")]
+        port: u16,
+    },
+    /// Explain a prompt: its outputs and patches, upstream context,
+    /// downstream dependents, cache state and last generation
+    Explain {
+        prompt: PathBuf,
+    },
+    /// Render the prompt DAG (uncommitted changes and their cascade highlighted)
+    Graph {
+        /// Output format: dot or mermaid
+        #[arg(long, default_value = "mermaid")]
+        format: String,
+
+        /// Report structural changes to the DAG between this ref and HEAD
+        #[arg(long, value_name = "REF")]
+        diff: Option<String>,
+    },
+
+    /// Maintain the project's .gitignore
+    Gitignore {
+        #[command(subcommand)]
+        action: GitignoreCommands,
+    },
+
+    /// List generated artifacts with provenance (for supply-chain tooling)
+    Inventory {
+        /// Output format: json or cyclonedx
+        #[arg(long, default_value = "json")]
+        format: String,
     },
 
     /// Inspect internal state (config, prompts, DAG)
@@ -138,7 +518,40 @@ pub enum Commands {
     },
 }
 
-#[derive(Subcommand)]
+/// Select prompts by frontmatter `tags:`
+#[derive(Clone, Args)]
+pub struct TagArgs {
+    /// Only include prompts with this tag (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    include: Vec<String>,
+
+    /// Leave out prompts with this tag (repeatable)
+    #[arg(long = "exclude-tag", value_name = "TAG")]
+    exclude: Vec<String>,
+}
+
+impl From<TagArgs> for TagFilter {
+    fn from(args: TagArgs) -> Self {
+        TagFilter { include: args.include, exclude: args.exclude }
+    }
+}
+
+#[derive(Clone, Subcommand)]
+pub enum AuthCommands {
+    /// Store a provider's API key in the OS keychain (read from stdin when piped)
+    Login {
+        /// Provider: anthropic, openai, azure or openrouter
+        provider: String,
+    },
+    /// Remove a provider's API key from the OS keychain
+    Logout {
+        provider: String,
+    },
+    /// Show where each configured provider's API key comes from
+    Status,
+}
+
+#[derive(Clone, Subcommand)]
 pub enum PatchCommands {
     /// Save current manual edits to code.lock/ as patches
     Save,
@@ -154,39 +567,247 @@ pub enum PatchCommands {
         /// Output file path to show the patch for
         path: PathBuf,
     },
+    /// Merge a saved patch into the current code.lock/ file, hunk by hunk
+    Apply {
+        /// Output file path to apply the patch to
+        path: PathBuf,
+    },
+    /// Ask the model for a prompt edit that makes a patch unnecessary
+    Promote {
+        /// Output file path of the patch to promote
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum StashCommands {
+    /// Stash changes to prompts and .lit/patches/
+    Push {
+        /// Description of the stashed work
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// List stash entries, newest first
+    List,
+    /// Show the files in a stash entry
+    Show {
+        /// Entry to show (0 is the newest)
+        #[arg(default_value = "0")]
+        index: usize,
+    },
+    /// Restore a stash entry and remove it from the stash
+    Pop {
+        /// Entry to restore (0 is the newest)
+        #[arg(default_value = "0")]
+        index: usize,
+    },
+    /// Restore a stash entry, keeping it in the stash
+    Apply {
+        /// Entry to restore (0 is the newest)
+        #[arg(default_value = "0")]
+        index: usize,
+    },
+    /// Remove a stash entry without restoring it
+    Drop {
+        /// Entry to remove (0 is the newest)
+        #[arg(default_value = "0")]
+        index: usize,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum RemoteCommands {
+    /// Add a remote
+    Add {
+        /// Remote name, e.g. origin
+        name: String,
+        /// Repository URL (https:// or SSH)
+        url: String,
+    },
+    /// List remotes and their URLs
+    List,
 }
 
-#[derive(Subcommand)]
+#[derive(Clone, Subcommand)]
+pub enum GitignoreCommands {
+    /// Add missing lit entries, keeping existing ones
+    Sync,
+}
+
+#[derive(Clone, Subcommand)]
 pub enum DebugCommands {
     /// Dump parsed lit.toml config
     Config,
     /// Dump all parsed prompts with frontmatter
     Prompts,
     /// Show the dependency DAG
-    Dag,
-    /// Show everything (config + prompts + DAG)
+    Dag {
+        /// Only show this prompt's upstream and downstream
+        #[arg(long)]
+        focus: Option<PathBuf>,
+    },
+    /// Show the context each prompt receives, with per-file token counts
+    Context {
+        /// Only show this prompt
+        prompt: Option<PathBuf>,
+    },
+    /// Show the exact request and raw response of a prompt's last generation
+    /// (needs `[logging] transcripts = true`)
+    Transcript {
+        prompt: PathBuf,
+    },
+    /// Show each prompt's input hash, its cache entry (size, age, model) and
+    /// what the hash is computed from
+    Cache {
+        /// Only show this prompt
+        prompt: Option<PathBuf>,
+    },
+    /// Show everything (config + prompts + DAG + context + cache)
     All,
 }
 
+impl Commands {
+    /// Commands that run once per member when started from a workspace
+    /// root (see [`Workspace`])
+    fn runs_across_workspace(&self) -> bool {
+        matches!(
+            self,
+            Commands::Status { .. }
+                | Commands::Lint { .. }
+                | Commands::Regenerate { path: None, .. }
+                | Commands::Cost { export: None, .. }
+                | Commands::Last { json: false }
+        )
+    }
+
+    /// Commands that write to code.lock/ or .lit/ and so hold the
+    /// repository lock (see [`RepoLock`]) while they run, by name
+    fn locks_repo(&self) -> Option<&'static str> {
+        let name = match self {
+            Commands::Commit { .. } => "commit",
+            Commands::Regenerate { .. } => "regenerate",
+            Commands::Freeze { .. } => "freeze",
+            Commands::Unfreeze { .. } => "unfreeze",
+            Commands::Patch { action: PatchCommands::List | PatchCommands::Show { .. } } => return None,
+            Commands::Patch { .. } => "patch",
+            Commands::Stash { action: Some(StashCommands::List | StashCommands::Show { .. }) } => return None,
+            Commands::Stash { .. } => "stash",
+            Commands::Checkout { .. } => "checkout",
+            Commands::Eval { .. } => "eval",
+            Commands::Sweep { .. } => "sweep",
+            Commands::Pull { .. } => "pull",
+            Commands::Migrate { .. } => "migrate",
+            Commands::MigrateFrontmatter { .. } => "migrate-frontmatter",
+            Commands::Mv { .. } => "mv",
+            Commands::SyncFromCode { .. } => "sync-from-code",
+            Commands::Gitignore { .. } => "gitignore",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
 impl Cli {
     pub async fn run(self) -> anyhow::Result<()> {
-        match self.command {
-            Commands::Init { defaults } => init::run(defaults).await,
-            Commands::Add { path } => add::run(path).await,
-            Commands::Commit { message } => commit::run(message).await,
-            Commands::Status => status::run().await,
-            Commands::Diff { code, all, summary } => diff::run(code, all, summary).await,
-            Commands::Log { limit } => log::run(limit).await,
-            Commands::Regenerate { path, all, no_cache, no_patches } => {
-                regenerate::run(path, all, no_cache, no_patches).await
-            }
-            Commands::Patch { action } => patch::run(action).await,
-            Commands::Checkout { ref_ } => checkout::run(ref_).await,
-            Commands::Push => push::run().await,
-            Commands::Pull => pull::run().await,
-            Commands::Clone { url } => clone::run(url).await,
-            Commands::Cost { last, breakdown } => cost::run(last, breakdown).await,
-            Commands::Debug { what } => debug::run(what).await,
+        progress::set_verbosity(match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        });
+        if let Some(path) = &self.log_file {
+            run_log::init(path)?;
+        }
+        if self.command.runs_across_workspace()
+            && let Some(ws) = Workspace::find(&std::env::current_dir()?)?
+        {
+            return workspace::run(ws, self.command, self.wait).await;
+        }
+        run_command(self.command, self.wait).await
+    }
+}
+
+/// Run a command in the lit project around the current directory; with
+/// `wait`, queue behind another lit command holding the repository lock
+pub async fn run_command(command: Commands, wait: bool) -> anyhow::Result<()> {
+    // Outside a lit repository the command reports that itself
+    let _lock = match (command.locks_repo(), LitConfig::find_root(&std::env::current_dir()?)) {
+        (Some(name), Ok(root)) => Some(RepoLock::acquire(&root.join(".lit"), name, wait)?),
+        _ => None,
+    };
+    match command {
+        Commands::Init { defaults } => init::run(defaults).await,
+        Commands::Add { path } => add::run(path).await,
+        Commands::Commit { message, allow_stale, regenerate, no_regenerate } => {
+            let regenerate = if regenerate || no_regenerate { Some(regenerate) } else { None };
+            commit::run(message, allow_stale, regenerate).await
+        },
+        Commands::Status { tags } => status::run(tags.into()).await,
+        Commands::Lint { ci, rules } => lint::run(ci, rules).await,
+        Commands::Test { paths, tags } => test::run(paths, tags.into()).await,
+        Commands::Eval { model_a, model_b, verify, no_cache } => eval::run(model_a, model_b, verify, no_cache).await,
+        Commands::Diff { range, code, all, summary, preview } => diff::run(range, code, all, summary, preview).await,
+        Commands::Log { limit, prompt, patch } => log::run(limit, prompt, patch).await,
+        Commands::Show { spec, path } => show::run(spec, path).await,
+        Commands::Regenerate {
+            path,
+            all,
+            no_cache,
+            no_patches,
+            no_prune,
+            interactive,
+            interactive_conflicts,
+            batch,
+            output_dir,
+            only,
+            yes,
+            tags,
+        } => {
+            let options = RegenerateOptions {
+                prompts: if all { Vec::new() } else { path.into_iter().collect() },
+                only,
+                tags: tags.into(),
+                no_cache,
+                no_patches,
+                no_prune,
+                batch,
+                output_dir,
+                ..Default::default()
+            };
+            let ask_keys = std::io::stdin().is_terminal();
+            let terminal = TerminalOptions { interactive, interactive_conflicts, yes, ask_keys };
+            regenerate::run(options, terminal).await
+        }
+        Commands::Freeze { prompts } => freeze::freeze(prompts).await,
+        Commands::Unfreeze { prompts } => freeze::unfreeze(prompts).await,
+        Commands::Last { json } => last::run(json).await,
+        Commands::Patch { action } => patch::run(action).await,
+        Commands::Stash { action } => stash::run(action).await,
+        Commands::Checkout { ref_, force, keep_patches } => checkout::run(ref_, force, keep_patches).await,
+        Commands::Remote { action } => remote::run(action).await,
+        Commands::Push { force, describe } => push::run(force, describe).await,
+        Commands::Describe { base, llm, post } => describe::run(base, llm, post).await,
+        Commands::Pull { yes } => pull::run(yes).await,
+        Commands::Clone { url, directory, warm_cache } => clone::run(url, directory, warm_cache).await,
+        Commands::Auth { action } => auth::run(action).await,
+        Commands::Cost { last, breakdown, estimate, history, top, export, commit, tags } => {
+            cost::run(last, breakdown, estimate, history, top, export, commit, tags.into()).await
+        }
+        Commands::Migrate { dry_run, check } => migrate::run(dry_run, check).await,
+        Commands::MigrateFrontmatter { set, unset, filters, dry_run } => {
+            migrate_frontmatter::run(set, unset, filters, dry_run).await
+        }
+        Commands::Mv { from, to, outputs } => mv::run(from, to, outputs).await,
+        Commands::SyncFromCode { file, yes } => sync_from_code::run(file, yes).await,
+        Commands::VerifyDeterminism { prompts, runs, sample, tags } => {
+            verify_determinism::run(prompts, runs, sample, tags.into()).await
         }
+        Commands::Sweep { prompts, seeds, temperatures } => sweep::run(prompts, seeds, temperatures).await,
+        Commands::Lsp => lsp::run().await,
+        Commands::Serve { port } => serve::run(port).await,
+        Commands::Explain { prompt } => explain::run(prompt).await,
+        Commands::Graph { format, diff } => graph::run(&format, diff.as_deref()).await,
+        Commands::Gitignore { action } => gitignore::run(action).await,
+        Commands::Inventory { format } => inventory::run(&format).await,
+        Commands::Debug { what } => debug::run(what).await,
     }
 }
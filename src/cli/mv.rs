@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::dag::Dag;
+use crate::core::freeze::{FREEZE_FILE, FreezeList};
+use crate::core::migrate::rewrite_path;
+use crate::core::paths::{relative_to, to_slash};
+use crate::core::patch::PatchStore;
+use crate::core::prompt::{Prompt, discover_project_prompts, is_prompt_file, resolve_imports};
+use crate::core::repo::LitRepo;
+use crate::core::style;
+
+/// `lit mv` — rename a prompt and rewrite every reference to it.
+///
+/// Dependents' `imports:` entries and `@import()` references are updated,
+/// outputs listed with `--output OLD=NEW` are moved in the frontmatter,
+/// code.lock/ and `.lit/patches/`, and the result is staged in git.
+pub async fn run(from: PathBuf, to: PathBuf, output_moves: Vec<String>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    let from = relative_to_root(&cwd, &root, &from);
+    let to = relative_to_root(&cwd, &root, &to);
+    if !root.join(&from).is_file() {
+        bail!("Prompt not found: {}", from.display());
+    }
+    if root.join(&to).exists() {
+        bail!("{} already exists.", to.display());
+    }
+    if !is_prompt_file(&from) || !is_prompt_file(&to) {
+        bail!("Both paths must be .prompt.md files.");
+    }
+    if !config.project.in_prompt_dir(&to) {
+        bail!(
+            "{} is not inside a prompt directory.\nHint: lit only tracks prompts under {}.",
+            to.display(),
+            config.project.prompt_dirs_display()
+        );
+    }
+
+    let mut moves = Vec::new();
+    for arg in &output_moves {
+        let Some((old, new)) = arg.split_once('=') else {
+            bail!("Invalid --output '{}': expected OLD=NEW", arg);
+        };
+        moves.push((PathBuf::from(old.trim()), PathBuf::from(new.trim())));
+    }
+
+    let prompt_paths = discover_project_prompts(&config, &root)?;
+
+    // Rewrite everything in memory and validate before touching the disk
+    let from_str = to_slash(&from);
+    let to_str = to_slash(&to);
+    let mut rewritten: Vec<(PathBuf, String)> = Vec::new();
+    let mut prompts = Vec::new();
+    for full_path in &prompt_paths {
+        let relative = relative_to(full_path, &root);
+        let raw = std::fs::read_to_string(full_path)
+            .with_context(|| format!("Failed to read {}", full_path.display()))?;
+
+        let mut content = rewrite_path(&raw, "imports", &from_str, &to_str)?;
+        let path = if relative == from {
+            let current = Prompt::parse(&raw, relative.clone(), &config)?;
+            for (old, new) in &moves {
+                if !current.frontmatter.outputs.contains(old) {
+                    bail!("{} is not an output of {}", old.display(), from.display());
+                }
+                let base = content.as_deref().unwrap_or(&raw);
+                content = rewrite_path(base, "outputs", &to_slash(old), &to_slash(new))?
+                    .or(content);
+            }
+            to.clone()
+        } else {
+            relative
+        };
+
+        let text = content.as_deref().unwrap_or(&raw);
+        prompts.push(
+            Prompt::parse(text, path.clone(), &config)
+                .with_context(|| format!("{} would be invalid after the move", path.display()))?,
+        );
+        if content.is_some() || path == to {
+            rewritten.push((path, text.to_string()));
+        }
+    }
+    if !prompts.iter().any(|p| p.path == to) {
+        bail!(
+            "{} is not a tracked prompt (prompts live under {}).",
+            from.display(),
+            config.project.prompt_dirs_display()
+        );
+    }
+    resolve_imports(&mut prompts)
+        .and_then(|_| Dag::build(&prompts))
+        .context("The move would break the prompt DAG")?;
+
+    // Write the moved prompt and its dependents
+    for (path, content) in &rewritten {
+        let full_path = root.join(path);
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&full_path, content)
+            .with_context(|| format!("Failed to write {}", full_path.display()))?;
+    }
+    std::fs::remove_file(root.join(&from))
+        .with_context(|| format!("Failed to remove {}", from.display()))?;
+
+    // Move generated code and patches along with the outputs
+    let code_lock_dir = config.code_dir(&root);
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    let mut moved_files = vec![(from.clone(), to.clone())];
+    let mut moved_patches = Vec::new();
+    for (old, new) in &moves {
+        let old_code = code_lock_dir.join(old);
+        if old_code.exists() {
+            let new_code = code_lock_dir.join(new);
+            if let Some(parent) = new_code.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&old_code, &new_code)
+                .with_context(|| format!("Failed to move {}", old_code.display()))?;
+            moved_files.push((config.project.code_dir.join(old), config.project.code_dir.join(new)));
+        }
+        if patch_store.rename_patch(old, new)? {
+            moved_patches.push((old.clone(), new.clone()));
+            moved_files.push((
+                relative_to_root(&root, &root, &patch_store.patch_file_path(old)),
+                relative_to_root(&root, &root, &patch_store.patch_file_path(new)),
+            ));
+        }
+    }
+
+    // A frozen prompt stays frozen under its new name
+    let lit_dir = root.join(".lit");
+    let mut frozen = FreezeList::load(&lit_dir)?;
+    let moved_freeze = frozen.rename(&from, &to);
+    if moved_freeze {
+        frozen.save(&lit_dir)?;
+    }
+
+    eprintln!("{}", style::header("Move"));
+    eprintln!("  {} {} → {}", "renamed:".green(), from.display(), to.display());
+    for (path, _) in rewritten.iter().filter(|(p, _)| *p != to) {
+        eprintln!("{}", style::file_modified(&path.display().to_string()));
+    }
+    for (old, new) in &moves {
+        eprintln!("  {} {} → {}", "output:".green(), old.display(), new.display());
+    }
+    for (old, new) in &moved_patches {
+        eprintln!("  {} {} → {}", "patch:".green(), old.display(), new.display());
+    }
+
+    // Stage the rename so git records it as a move
+    match LitRepo::open(&root) {
+        Ok(repo) => {
+            let repo = repo.with_project_dirs(&config.project);
+            for (old, new) in &moved_files {
+                repo.stage_rename(old, new)?;
+            }
+            for (path, _) in rewritten.iter().filter(|(p, _)| *p != to) {
+                repo.stage_file(path)?;
+            }
+            if !moved_patches.is_empty() {
+                repo.stage_file(Path::new(".lit/patches/index.json"))?;
+            }
+            if moved_freeze {
+                repo.stage_file(&Path::new(".lit").join(FREEZE_FILE))?;
+            }
+            eprintln!();
+            eprintln!("{}", style::hint("Staged. Dependents of the moved prompt will regenerate on the next `lit regenerate`."));
+        }
+        Err(_) => {
+            eprintln!();
+            eprintln!("{}", style::warning("Not a git repository — the move was not staged."));
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a user-supplied path to a path relative to the project root
+fn relative_to_root(cwd: &Path, root: &Path, path: &Path) -> PathBuf {
+    let full = if path.is_absolute() { path.to_path_buf() } else { cwd.join(path) };
+    relative_to(&full, root)
+}
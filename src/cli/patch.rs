@@ -1,14 +1,28 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anyhow::{Context, Result};
+use colored::Colorize;
 
 use crate::cli::PatchCommands;
+use crate::cli::regenerate::print_diff;
+use crate::core::atomic;
 use crate::core::cache::Cache;
 use crate::core::config::LitConfig;
 use crate::core::dag::Dag;
-use crate::core::patch::PatchStore;
-use crate::core::prompt::{Prompt, discover_prompts};
+use crate::core::generation_record::{build_record, format_cost, report_budget, write_record};
+use crate::core::generator::{
+    PipelineResult, build_sync_request, import_hashes, prompt_input_hash, strip_markdown_fences,
+};
+use crate::core::manifest::Manifest;
+use crate::core::patch::{HunkOutcome, PatchStatus, PatchStore, StalePatch};
+use crate::core::project::{load_code, load_prompts};
+use crate::core::prompt::{Prompt, discover_project_prompts, resolve_imports};
+use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
 
 pub async fn run(action: PatchCommands) -> Result<()> {
     match action {
@@ -16,6 +30,8 @@ pub async fn run(action: PatchCommands) -> Result<()> {
         PatchCommands::List => list().await,
         PatchCommands::Drop { path } => drop_patch(path).await,
         PatchCommands::Show { path } => show(path).await,
+        PatchCommands::Apply { path } => apply(path).await,
+        PatchCommands::Promote { path } => promote(path).await,
     }
 }
 
@@ -24,17 +40,17 @@ async fn save() -> Result<()> {
     let cwd = std::env::current_dir()?;
     let (config, root) = LitConfig::find_and_load(&cwd)?;
 
-    let code_lock_dir = root.join("code.lock");
+    let code_lock_dir = config.code_dir(&root);
     if !code_lock_dir.exists() {
         anyhow::bail!("No code.lock/ directory found. Run `lit regenerate` first.");
     }
 
     // Load actual code from disk
-    let actual_code = load_code_from_dir(&code_lock_dir);
+    let actual_code = load_code(&config, &root);
 
     // Load cached generation results to get the "original generated" content
     let cache_dir = root.join(".lit").join("cache");
-    let cache = Cache::new(cache_dir);
+    let cache = Cache::new(cache_dir).with_config(config.cache.clone());
 
     // Build the generated content map from cache entries
     let generated_code = load_generated_from_cache(&root, &config, &cache)?;
@@ -86,25 +102,51 @@ async fn list() -> Result<()> {
     let (_config, root) = LitConfig::find_and_load(&cwd)?;
 
     let patch_store = PatchStore::new(root.join(".lit").join("patches"));
-    let patches = patch_store.list_patches();
+    let index = patch_store.load_index()?;
 
-    if patches.is_empty() {
+    if index.patches.is_empty() {
         eprintln!("No patches tracked. Use `lit patch save` to save manual edits.");
         return Ok(());
     }
 
     eprintln!("Tracked patches:");
-    for path in &patches {
+    for (path, entry) in &index.patches {
+        let status = match entry.status {
+            PatchStatus::Active => String::new(),
+            PatchStatus::Conflicted => " [conflicted]".to_string(),
+        };
         if let Some(stored) = patch_store.load_patch(path) {
             let lines: Vec<&str> = stored.diff.lines().collect();
             let added = lines.iter().filter(|l| l.starts_with('+')).count();
             let removed = lines.iter().filter(|l| l.starts_with('-')).count();
-            eprintln!("  {} (+{} -{})", path.display(), added, removed);
+            eprintln!(
+                "  {} (+{} -{}) saved {}{}",
+                path.display(),
+                added,
+                removed,
+                entry.updated_at.format("%Y-%m-%d %H:%M"),
+                status
+            );
         } else {
-            eprintln!("  {}", path.display());
+            eprintln!("  {}{}", path.display(), status);
+        }
+    }
+    eprintln!("\n{} patch(es) total", index.patches.len());
+
+    let stale = patch_store.stale_patches()?;
+    if !stale.is_empty() {
+        eprintln!("\nStale patches:");
+        for s in &stale {
+            match s {
+                StalePatch::Missing(path) => {
+                    eprintln!("  {} (patch file missing — `lit patch drop` to untrack)", path.display())
+                }
+                StalePatch::Modified(path) => {
+                    eprintln!("  {} (patch file modified outside lit)", path.display())
+                }
+            }
         }
     }
-    eprintln!("\n{} patch(es) total", patches.len());
     Ok(())
 }
 
@@ -115,7 +157,8 @@ async fn drop_patch(path: PathBuf) -> Result<()> {
 
     let patch_store = PatchStore::new(root.join(".lit").join("patches"));
 
-    if !patch_store.has_patch(&path) {
+    let indexed = patch_store.load_index()?.patches.contains_key(&path);
+    if !patch_store.has_patch(&path) && !indexed {
         anyhow::bail!("No patch found for {}", path.display());
     }
 
@@ -140,32 +183,149 @@ async fn show(path: PathBuf) -> Result<()> {
     Ok(())
 }
 
-/// Load all code files from a directory
-fn load_code_from_dir(dir: &std::path::Path) -> HashMap<PathBuf, String> {
-    let mut code = HashMap::new();
-    if !dir.exists() {
-        return code;
+/// `lit patch apply <path>` — merge a saved patch into the current file.
+///
+/// Clean hunks merge; hunks that clash with changes in the current file get
+/// conflict markers, and the patch is marked conflicted until resolved.
+async fn apply(path: PathBuf) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    let stored = patch_store
+        .load_patch(&path)
+        .with_context(|| format!("No patch found for {}", path.display()))?;
+
+    let full_path = config.code_dir(&root).join(&path);
+    let current = std::fs::read_to_string(&full_path)
+        .with_context(|| format!("Failed to read code.lock/{}", path.display()))?;
+    if current == stored.manual_content {
+        eprintln!("{} already has the patch applied.", path.display());
+        return Ok(());
     }
 
-    fn walk_dir(dir: &std::path::Path, base: &std::path::Path, out: &mut HashMap<PathBuf, String>) {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    walk_dir(&path, base, out);
-                } else if path.is_file() {
-                    if let Ok(content) = std::fs::read_to_string(&path) {
-                        if let Ok(relative) = path.strip_prefix(base) {
-                            out.insert(relative.to_path_buf(), content);
-                        }
-                    }
-                }
-            }
-        }
+    let merge = patch_store.merge_patch(&stored.original_content, &current, &stored.manual_content);
+    atomic::write(&full_path, &merge.content)
+        .with_context(|| format!("Failed to write code.lock/{}", path.display()))?;
+
+    eprintln!("{}", style::header(&format!("Applied patch to {}", path.display())));
+    for hunk in &merge.hunks {
+        let outcome = match hunk.outcome {
+            HunkOutcome::Applied => "applied".green(),
+            HunkOutcome::AlreadyPresent => "already present".dimmed(),
+            HunkOutcome::Conflict => "conflict".red().bold(),
+        };
+        eprintln!("  {:<16} {}", outcome, hunk.location());
     }
 
-    walk_dir(dir, dir, &mut code);
-    code
+    let conflicts = merge.hunks.iter().filter(|h| h.outcome == HunkOutcome::Conflict).count();
+    eprintln!();
+    if conflicts > 0 {
+        patch_store.set_status(&path, PatchStatus::Conflicted)?;
+        eprintln!(
+            "{}",
+            style::warning(&format!(
+                "{} of {} hunk(s) conflicted — resolve the markers in code.lock/{}, then run `lit patch save`",
+                conflicts,
+                merge.hunks.len(),
+                path.display()
+            ))
+        );
+    } else {
+        patch_store.set_status(&path, PatchStatus::Active)?;
+        eprintln!("{}", style::success(&format!("All {} hunk(s) merged", merge.hunks.len())));
+    }
+    Ok(())
+}
+
+/// `lit patch promote <path>` — propose a prompt edit that carries the patch.
+///
+/// The owning prompt, the originally generated file and the manual edit go to
+/// the model, and the proposed prompt is written next to the prompt as
+/// `<prompt>.suggested` for review; nothing else changes. The call is
+/// recorded in `.lit/generations/` like a generation.
+async fn promote(path: PathBuf) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let registry = build_registry(&config, std::io::stdin().is_terminal());
+    promote_with(&config, &root, &path, &registry).await?;
+    Ok(())
+}
+
+/// [`promote`] with `provider`; returns the path of the suggestion
+async fn promote_with(config: &LitConfig, root: &Path, path: &Path, provider: &dyn LlmProvider) -> Result<PathBuf> {
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    let stored = patch_store
+        .load_patch(path)
+        .with_context(|| format!("No patch found for {}", path.display()))?;
+
+    let (prompts, _) = load_prompts(config, root)?;
+    let owner = Manifest::load(&root.join(".lit"))?
+        .and_then(|manifest| manifest.owner(path).map(|entry| entry.prompt.clone()))
+        .or_else(|| {
+            prompts
+                .values()
+                .find(|p| p.frontmatter.outputs.iter().any(|output| output == path))
+                .map(|p| p.path.clone())
+        })
+        .with_context(|| format!("No prompt generates {}", path.display()))?;
+    let prompt = prompts
+        .get(&owner)
+        .with_context(|| format!("Owning prompt {} no longer exists", owner.display()))?;
+
+    eprintln!("{}", style::header("Promote patch"));
+    eprintln!("  {:<8} {}", "File:".dimmed(), path.display());
+    eprintln!("  {:<8} {}", "Prompt:".dimmed(), prompt.path.display().to_string().bold());
+    eprintln!();
+    print_diff(&stored.original_content, &stored.manual_content);
+
+    let request = build_sync_request(
+        config,
+        prompt,
+        path,
+        &stored.original_content,
+        &stored.manual_content,
+    );
+    eprintln!();
+    eprintln!("  {} {}", "Asking".cyan(), request.model.dimmed());
+    let started = Instant::now();
+    let response = provider.generate(request).await?;
+    let calls = [(prompt.path.clone(), response, started.elapsed().as_millis() as u64)];
+    let (record, cost) = build_record(config, &PipelineResult::of_calls(&calls), 0, 0, 0);
+    write_record(root, &record);
+    let response = &calls[0].1;
+    eprintln!(
+        "{}",
+        format!("  {} tokens in, {} out, {}", response.tokens_in, response.tokens_out, format_cost(cost)).dimmed()
+    );
+
+    let body = strip_markdown_fences(&response.content);
+    let suggested = prompt.with_body(&format!("{}\n", body.trim_end()));
+    Prompt::parse(&suggested, prompt.path.clone(), config)
+        .context("The proposed prompt does not parse")?;
+
+    let mut suggestion_path = prompt.path.clone().into_os_string();
+    suggestion_path.push(".suggested");
+    let suggestion_path = PathBuf::from(suggestion_path);
+    std::fs::write(root.join(&suggestion_path), &suggested)
+        .with_context(|| format!("Failed to write {}", suggestion_path.display()))?;
+
+    eprintln!();
+    eprintln!("{}", style::file_modified(&prompt.path.display().to_string()));
+    print_diff(&prompt.raw, &suggested);
+    eprintln!();
+    eprintln!("{}", style::success(&format!("Wrote {}", suggestion_path.display())));
+    eprintln!(
+        "{}",
+        style::hint(&format!(
+            "Review it, move it over {}, then `lit patch drop {}` and `lit regenerate {}`.",
+            prompt.path.display(),
+            path.display(),
+            prompt.path.display()
+        ))
+    );
+    report_budget(config, root, Some(cost), true).await;
+    Ok(suggestion_path)
 }
 
 /// Load what the LLM generated from cache entries.
@@ -177,12 +337,7 @@ fn load_generated_from_cache(
     config: &LitConfig,
     cache: &Cache,
 ) -> Result<HashMap<PathBuf, String>> {
-    let prompts_dir = root.join("prompts");
-    if !prompts_dir.exists() {
-        return Ok(HashMap::new());
-    }
-
-    let prompt_paths = discover_prompts(&prompts_dir)?;
+    let prompt_paths = discover_project_prompts(config, root)?;
     let mut prompts_vec = Vec::new();
     for p in &prompt_paths {
         prompts_vec.push(
@@ -191,6 +346,7 @@ fn load_generated_from_cache(
         );
     }
 
+    resolve_imports(&mut prompts_vec)?;
     let dag = Dag::build(&prompts_vec)?;
 
     let prompts_map: HashMap<PathBuf, Prompt> = prompts_vec
@@ -198,9 +354,6 @@ fn load_generated_from_cache(
         .map(|p| (p.path.clone(), p))
         .collect();
 
-    let language = &config.language.default;
-    let framework = config.framework.as_ref().map(|fw| fw.name.as_str());
-
     let mut input_hashes: HashMap<PathBuf, String> = HashMap::new();
     let mut generated_code: HashMap<PathBuf, String> = HashMap::new();
 
@@ -209,28 +362,9 @@ fn load_generated_from_cache(
             .get(prompt_path)
             .with_context(|| format!("Prompt {} not found", prompt_path.display()))?;
 
-        let (model, temperature, seed) = resolve_model_config(prompt, config);
-
-        let import_hashes: Vec<(&std::path::Path, &str)> = prompt
-            .frontmatter
-            .imports
-            .iter()
-            .filter_map(|import_path| {
-                input_hashes
-                    .get(import_path)
-                    .map(|h| (import_path.as_path(), h.as_str()))
-            })
-            .collect();
-
-        let input_hash = Cache::compute_input_hash(
-            &prompt.raw,
-            &import_hashes,
-            &model,
-            temperature,
-            seed,
-            language,
-            framework,
-        );
+        let import_hashes =
+            import_hashes(config, prompt, &prompts_map, &input_hashes, &generated_code);
+        let input_hash = prompt_input_hash(config, prompt, &import_hashes);
 
         input_hashes.insert(prompt_path.clone(), input_hash.clone());
 
@@ -245,19 +379,63 @@ fn load_generated_from_cache(
     Ok(generated_code)
 }
 
-/// Resolve model config for a prompt (mirrors Generator::resolve_model_config)
-fn resolve_model_config(prompt: &Prompt, config: &LitConfig) -> (String, f64, Option<u64>) {
-    if let Some(ref model_override) = prompt.frontmatter.model {
-        (
-            model_override.model.clone(),
-            model_override.temperature,
-            model_override.seed,
-        )
-    } else {
-        (
-            config.model.model.clone(),
-            config.model.temperature,
-            config.model.seed,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::generation_record::GenerationRecord;
+    use crate::providers::{GenerationRequest, GenerationResponse, PromptCacheTokens};
+
+    /// Answers with a prompt body that carries the manual edit
+    struct StubProvider;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for StubProvider {
+        async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+            Ok(GenerationResponse {
+                content: "# User\n\nA User model with an email field.\n".to_string(),
+                tokens_in: 400,
+                tokens_out: 30,
+                prompt_cache: PromptCacheTokens::default(),
+                model: request.model,
+                batch: false,
+            })
+        }
+
+        fn name(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_promote_writes_suggestion_and_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("lit.toml"),
+            "[project]\nname = \"promote\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n[language]\ndefault = \"python\"\nversion = \"3.12\"\n\n\
+             [model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5\"\ntemperature = 0.0\n",
         )
+        .unwrap();
+        std::fs::create_dir_all(root.join("prompts")).unwrap();
+        let raw = "---\noutputs:\n  - src/user.py\n---\n\n# User\n\nA User model.\n";
+        std::fs::write(root.join("prompts/user.prompt.md"), raw).unwrap();
+        let output = PathBuf::from("src/user.py");
+        let patches = PatchStore::new(root.join(".lit/patches"));
+        patches.init().unwrap();
+        patches.save_patch(&output, "class User: ...\n", "class User:\n    email: str\n").unwrap();
+
+        let (config, root) = LitConfig::find_and_load(root).unwrap();
+        let suggestion = promote_with(&config, &root, &output, &StubProvider).await.unwrap();
+        assert_eq!(suggestion, PathBuf::from("prompts/user.prompt.md.suggested"));
+        let suggested = std::fs::read_to_string(root.join(&suggestion)).unwrap();
+        assert!(suggested.starts_with("---\noutputs:\n  - src/user.py\n---\n"), "{}", suggested);
+        assert!(suggested.contains("with an email field"));
+        assert_eq!(std::fs::read_to_string(root.join("prompts/user.prompt.md")).unwrap(), raw);
+
+        let records = GenerationRecord::list(&root.join(".lit/generations")).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].prompts[0].prompt_path, PathBuf::from("prompts/user.prompt.md"));
+        assert_eq!((records[0].summary.total_tokens_in, records[0].summary.cache_misses), (400, 1));
+        assert!(records[0].summary.total_cost_usd > 0.0);
     }
 }
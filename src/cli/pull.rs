@@ -1,39 +1,84 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use anyhow::Result;
+use colored::Colorize;
 
+use crate::api::RegenerateOptions;
+use crate::cli::regenerate::{self, TerminalOptions, confirm};
 use crate::core::config::LitConfig;
+use crate::core::project::stale_prompts;
+use crate::core::prompt::TagFilter;
+use crate::core::repo::{LitRepo, PullOutcome};
+use crate::core::style;
 
-/// Thin wrapper around `git pull`.
+/// `git pull` of the current branch that keeps code.lock/ consistent.
 ///
-/// We shell out to git for remote operations because git2's transport layer
-/// requires complex SSH/credential setup. The system git already handles
-/// credentials, SSH keys, and proxies correctly.
-pub async fn run() -> Result<()> {
+/// Fetches over git2 (credentials as for `lit push`), then fast-forwards or
+/// merges. A merge that conflicts is left for the user to resolve and finish
+/// with `lit commit`.
+///
+/// After pulling, prompts changed upstream are listed, and if they leave
+/// code.lock/ stale the stale prompts are offered for regeneration (or
+/// regenerated straight away with `yes`).
+pub async fn run(yes: bool) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+    let before = repo.head_commit().map(|c| c.hash);
 
     eprintln!("Pulling from remote...");
-
-    let output = Command::new("git")
-        .arg("pull")
-        .current_dir(&root)
-        .output()
-        .context("Failed to run `git pull`. Is git installed?")?;
-
-    // Forward git's output
-    if !output.stdout.is_empty() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        eprint!("{}", stdout);
-    }
-    if !output.stderr.is_empty() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprint!("{}", stderr);
+    match repo.pull()? {
+        PullOutcome::UpToDate => {
+            eprintln!("Already up to date.");
+            return Ok(());
+        }
+        PullOutcome::FastForward => eprintln!("{}", "Fast-forwarded.".dimmed()),
+        PullOutcome::Merged => eprintln!("{}", "Merged the remote changes.".dimmed()),
+        PullOutcome::Conflicts(paths) => {
+            eprintln!("{}", style::warning(&format!("Merge conflicts in {} file(s):", paths.len())));
+            for path in &paths {
+                eprintln!("{}", style::file_modified(&path.display().to_string()));
+            }
+            eprintln!();
+            eprintln!("{}", style::hint("Resolve the conflict markers, then run `lit commit` to finish the merge."));
+            anyhow::bail!("Pull stopped on merge conflicts");
+        }
     }
 
-    if !output.status.success() {
-        anyhow::bail!("git pull failed (exit code: {:?})", output.status.code());
+    let after = repo.head_commit().map(|c| c.hash);
+    if let (Some(before), Some(after)) = (&before, &after)
+        && before != after
+    {
+        let changed = repo.prompts_changed_between(before, after)?;
+        if !changed.is_empty() {
+            eprintln!();
+            eprintln!("{}", style::section(&format!("Pulled prompt changes ({}):", changed.len())));
+            for path in &changed {
+                eprintln!("{}", style::file_modified(&path.display().to_string()));
+            }
+        }
     }
 
     eprintln!("Pull complete.");
+
+    // Pulled prompt changes may not have come with their regenerated code
+    let stale = stale_prompts(&config, &root, &TagFilter::default())?.unwrap_or_default();
+    if stale.is_empty() {
+        return Ok(());
+    }
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::warning(&format!("code.lock is stale for {} prompt(s):", stale.len()))
+    );
+    for path in &stale {
+        eprintln!("{}", style::file_modified(&path.display().to_string()));
+    }
+    eprintln!();
+    if yes || confirm("Regenerate them now?", true).unwrap_or(false) {
+        regenerate::run(RegenerateOptions { prompts: stale, ..Default::default() }, TerminalOptions::UNATTENDED)
+            .await?;
+    } else {
+        eprintln!("{}", "code.lock/ left as pulled.".dimmed());
+        eprintln!("{}", style::hint("Run `lit regenerate` to bring it up to date."));
+    }
     Ok(())
 }
@@ -1,39 +1,55 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use anyhow::Result;
+use colored::Colorize;
 
+use crate::cli::describe;
 use crate::core::config::LitConfig;
+use crate::core::project::stale_prompts;
+use crate::core::prompt::TagFilter;
+use crate::core::repo::LitRepo;
 
-/// Thin wrapper around `git push`.
+/// `git push` of the current branch that refuses to publish a stale
+/// code.lock/.
 ///
-/// We shell out to git for remote operations because git2's transport layer
-/// requires complex SSH/credential setup. The system git already handles
-/// credentials, SSH keys, and proxies correctly.
-pub async fn run() -> Result<()> {
+/// Pushes over git2 with credentials from ssh-agent, SSH keys or an access
+/// token (see [`crate::core::credentials`]). A branch without an upstream is
+/// pushed to the same-named branch on `origin` (or the only remote), which
+/// then becomes its upstream.
+///
+/// The consistency check is the one `lit commit` runs; `force` skips it
+/// (it does not force-push). With `describe`, the branch's pull request
+/// description is posted afterwards.
+pub async fn run(force: bool, describe: bool) -> Result<()> {
     let cwd = std::env::current_dir()?;
-    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
 
-    eprintln!("Pushing to remote...");
+    if !force
+        && let Some(stale) = stale_prompts(&config, &root, &TagFilter::default())?
+        && !stale.is_empty()
+    {
+        anyhow::bail!(
+            "code.lock is stale for {} prompt(s):\n{}\n\
+             Hint: Run `lit regenerate` and commit first, or pass --force to push anyway.",
+            stale.len(),
+            stale.iter().map(|p| format!("  {}", p.display())).collect::<Vec<_>>().join("\n")
+        );
+    }
 
-    let output = Command::new("git")
-        .arg("push")
-        .current_dir(&root)
-        .output()
-        .context("Failed to run `git push`. Is git installed?")?;
+    let repo = LitRepo::open(&root)?;
+    let upstream = repo.upstream()?;
+    eprintln!("Pushing {} to {}/{}...", upstream.branch, upstream.remote, upstream.remote_branch);
+    repo.push(&upstream)?;
 
-    // Forward git's output
-    if !output.stdout.is_empty() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        eprint!("{}", stdout);
-    }
-    if !output.stderr.is_empty() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprint!("{}", stderr);
+    if !upstream.tracked {
+        eprintln!(
+            "{}",
+            format!("Branch '{}' now tracks {}/{}.", upstream.branch, upstream.remote, upstream.remote_branch).dimmed()
+        );
     }
+    eprintln!("Push complete.");
 
-    if !output.status.success() {
-        anyhow::bail!("git push failed (exit code: {:?})", output.status.code());
+    if describe {
+        eprintln!();
+        describe::run(None, false, true).await?;
     }
-
-    eprintln!("Push complete.");
     Ok(())
 }
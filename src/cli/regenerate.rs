@@ -1,366 +1,559 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
+use crate::api::{Lit, RegenerateOptions};
+use crate::cli::cost::estimate_prompts;
 use crate::core::cache::Cache;
 use crate::core::config::LitConfig;
 use crate::core::dag::Dag;
-use crate::core::generation_record::{
-    GenerationRecord, GenerationSummary, PromptRecord, estimate_cost, format_cost,
-    format_tokens,
-};
-use crate::core::generator::Generator;
-use crate::core::patch::{PatchResult, PatchStore};
-use crate::core::prompt::{Prompt, discover_prompts};
+use crate::core::generation_record::{format_cost, format_prompt_cache, format_tokens};
+use crate::core::generator::{GenerationOutput, OutputReviewer, ReviewDecision, estimate_request_tokens};
+use crate::core::hooks::{shell, shell_quote};
+use crate::core::interrupt::InterruptGuard;
+use crate::core::patch::PatchMerge;
+use crate::core::progress;
+use crate::core::project::{ensure_prompts, load_code, load_prompts, select_prompts};
+use crate::core::prompt::Prompt;
+use crate::core::session::SessionStore;
 use crate::core::style;
-use crate::providers::anthropic::AnthropicProvider;
-use crate::providers::openai::OpenAiProvider;
-
-pub async fn run(path: Option<PathBuf>, all: bool, no_cache: bool, no_patches: bool) -> Result<()> {
-    let cwd = std::env::current_dir()?;
-    let (config, root) = LitConfig::find_and_load(&cwd)?;
-
-    // Discover and parse all prompts
-    let prompts_dir = root.join("prompts");
-    if !prompts_dir.exists() {
-        anyhow::bail!(
-            "No prompts/ directory found in {}\n\
-             Hint: Create prompt files in prompts/ first, then run `lit regenerate`.",
-            root.display()
-        );
-    }
-
-    let prompt_paths = discover_prompts(&prompts_dir)?;
-    if prompt_paths.is_empty() {
-        anyhow::bail!(
-            "No .prompt.md files found in prompts/\n\
-             Hint: Create a prompt file like prompts/hello.prompt.md and try again."
-        );
-    }
-
-    let mut prompts_vec = Vec::new();
-    for p in &prompt_paths {
-        prompts_vec.push(
-            Prompt::from_file(p, &root, &config)
-                .with_context(|| format!("Failed to parse {}", p.display()))?,
-        );
-    }
-
-    // Build DAG
-    let dag = Dag::build(&prompts_vec)?;
-
-    // Build prompts map
-    let prompts_map: HashMap<PathBuf, Prompt> = prompts_vec
-        .into_iter()
-        .map(|p| (p.path.clone(), p))
-        .collect();
-
-    // Determine regeneration set
-    let regeneration_set = if all {
-        dag.order().to_vec()
-    } else if let Some(ref specific_path) = path {
-        let relative = if specific_path.is_absolute() {
-            specific_path
-                .strip_prefix(&root)
-                .unwrap_or(specific_path)
-                .to_path_buf()
-        } else {
-            specific_path.clone()
-        };
-        let set = dag.regeneration_set(&[relative.clone()]);
-        if set.is_empty() {
-            anyhow::bail!(
-                "Prompt {} not found in DAG.\n\nAvailable prompts:\n{}",
-                relative.display(),
-                dag.order()
-                    .iter()
-                    .map(|p| format!("  {}", p.display()))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            );
-        }
-        set
-    } else {
-        dag.order().to_vec()
-    };
+use crate::core::tokens::estimate_tokens;
+use crate::providers::{GenerationRequest, PromptCacheTokens};
+use crate::providers::setup::build_registry;
+
+/// What `lit regenerate` asks on the terminal, on top of [`RegenerateOptions`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalOptions {
+    /// Approve, retry or skip each fresh output (`--interactive`)
+    pub interactive: bool,
+    /// Settle patch conflicts region by region (`--interactive-conflicts`)
+    pub interactive_conflicts: bool,
+    /// Don't ask before regenerating a large set (`--yes`)
+    pub yes: bool,
+    /// Ask for API keys that are neither in the environment nor the keychain
+    pub ask_keys: bool,
+}
 
-    eprintln!("{}", style::regen_header(regeneration_set.len(), dag.len()));
-
-    // Resolve API key
-    let api_key = config.resolve_api_key().context(
-        "Failed to resolve API key.\n\
-         Hint: Set the environment variable specified in lit.toml [model.api] key_env,\n\
-         e.g.: export LIT_API_KEY=sk-ant-..."
-    )?;
-
-    // Create provider
-    let provider: Box<dyn crate::providers::LlmProvider> = match config.model.provider.as_str() {
-        "anthropic" => Box::new(AnthropicProvider::new(api_key)),
-        "openai" => Box::new(OpenAiProvider::new(api_key)),
-        other => anyhow::bail!(
-            "Provider '{}' is not supported.\n\
-             Hint: Supported providers: anthropic, openai",
-            other
-        ),
-    };
+impl TerminalOptions {
+    /// For commands that regenerate as one of their steps
+    pub const UNATTENDED: Self = Self { interactive: false, interactive_conflicts: false, yes: true, ask_keys: false };
+}
 
-    // Write static files first
-    let code_lock_dir = root.join("code.lock");
-    let mut static_files_written = 0;
-    for sf in &config.r#static {
-        let full_path = code_lock_dir.join(&sf.path);
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent).with_context(|| {
-                format!("Failed to create directory {}", parent.display())
-            })?;
+/// Regenerate `options.prompts` and their dependents (every prompt if it is
+/// empty) through [`Lit::regenerate`], with the terminal parts around it: the
+/// confirmation, interactive review and conflict resolution, and the summary.
+///
+/// With `output_dir` (relative to the working directory), files are written
+/// there instead of the code directory, which still provides context; the
+/// manifest, patches and interfaces are left as they are, so the run is a
+/// detached preview. With `only`, just the given prompts are regenerated;
+/// dependents whose inputs changed are left stale.
+///
+/// A set larger than `[generation] confirm_above` that needs fresh LLM calls
+/// is listed with its estimated cost and only run once confirmed, unless
+/// `yes` or stdin is not a terminal.
+pub async fn run(mut options: RegenerateOptions, terminal: TerminalOptions) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let lit = Lit::open(&cwd)?;
+    let (config, root) = (lit.config(), lit.root());
+    let _interrupts = InterruptGuard::enter();
+
+    if let Some(limit) = config.generation.confirm_above()
+        && !terminal.yes
+        && std::io::stdin().is_terminal()
+    {
+        ensure_prompts(config, root)?;
+        let (prompts_map, dag) = load_prompts(config, root)?;
+        let selection = select_prompts(root, &dag, &prompts_map, &options.prompts, options.only, &options.tags)?;
+        if selection.prompts.len() > limit
+            && !confirm_regeneration(config, root, &dag, &prompts_map, &selection.prompts, options.no_cache)?
+        {
+            eprintln!("{}", "Regeneration cancelled.".dimmed());
+            return Ok(());
         }
-        std::fs::write(&full_path, &sf.content).with_context(|| {
-            format!("Failed to write static file {}", full_path.display())
-        })?;
-        static_files_written += 1;
     }
-    if static_files_written > 0 {
-        eprintln!("  Static files written: {}", static_files_written.to_string().dimmed());
-    }
-
-    // Load existing code from code.lock/ for context
-    let existing_code = load_existing_code(&code_lock_dir);
-
-    // Initialize cache
-    let cache = if no_cache {
-        eprintln!("  {}", "Cache disabled (--no-cache)".dimmed());
-        None
-    } else {
-        let cache_dir = root.join(".lit").join("cache");
-        let c = Cache::new(cache_dir);
-        c.init().context("Failed to initialize cache directory")?;
-        Some(c)
-    };
-
-    // Create generator and run pipeline
-    let generator = Generator::new(provider, config.clone());
-    let result = generator
-        .run_pipeline(
-            &dag,
-            &prompts_map,
-            &regeneration_set,
-            &existing_code,
-            cache.as_ref(),
-        )
-        .await?;
-
-    // Load patch store
-    let patch_store = if no_patches {
-        eprintln!("  {}", "Patches disabled (--no-patches)".dimmed());
-        None
-    } else {
-        let ps = PatchStore::new(root.join(".lit").join("patches"));
-        let _ = ps.init();
-        Some(ps)
-    };
-
-    // Write generated files to code.lock/, applying patches
-    let mut files_written = 0;
-    let mut patches_applied = 0;
-    let mut patches_conflicted = 0;
-    for output in &result.outputs {
-        for (file_path, content) in &output.files {
-            let mut final_content = content.clone();
-
-            // Check if there's a saved patch for this file
-            if let Some(ref ps) = patch_store {
-                if let Some(stored_patch) = ps.load_patch(file_path) {
-                    match ps.apply_patch(
-                        &stored_patch.original_content,
-                        content,
-                        &stored_patch.manual_content,
-                    ) {
-                        PatchResult::Applied(merged) => {
-                            eprintln!("{}", style::patch_applied(&file_path.display().to_string()));
-                            final_content = merged;
-                            patches_applied += 1;
-
-                            if let Err(e) = ps.save_patch(
-                                file_path,
-                                content,
-                                &final_content,
-                            ) {
-                                eprintln!(
-                                    "    {}", style::warning(&format!("Failed to update patch: {}", e))
-                                );
-                            }
-                        }
-                        PatchResult::Conflict(conflict) => {
-                            eprintln!("{}", style::patch_conflict(&file_path.display().to_string()));
-                            eprintln!(
-                                "      {}",
-                                "Wrote conflict markers — please resolve manually".dimmed()
-                            );
-                            final_content = conflict;
-                            patches_conflicted += 1;
-                        }
-                    }
-                }
-            }
 
-            let full_path = code_lock_dir.join(file_path);
-            if let Some(parent) = full_path.parent() {
-                std::fs::create_dir_all(parent).with_context(|| {
-                    format!("Failed to create directory {}", parent.display())
-                })?;
-            }
-            std::fs::write(&full_path, &final_content).with_context(|| {
-                format!("Failed to write {}", full_path.display())
-            })?;
-            files_written += 1;
-        }
+    options.output_dir = options.output_dir.map(|dir| cwd.join(dir));
+    if terminal.ask_keys && options.provider.is_none() {
+        options.provider = Some(Box::new(build_registry(config, true)));
     }
-
-    // Build per-prompt records and compute costs
-    let pricing_override = config.model.pricing.as_ref().map(|p| {
-        crate::core::generation_record::ModelPricing::new(p.input_per_million, p.output_per_million)
-    });
-    let mut prompt_records = Vec::new();
-    let mut total_cost = 0.0;
-    for output in &result.outputs {
-        let cost = estimate_cost(
-            &output.model,
-            output.tokens_in,
-            output.tokens_out,
-            pricing_override.as_ref(),
-        );
-        total_cost += cost;
-
-        prompt_records.push(PromptRecord {
-            prompt_path: output.prompt_path.clone(),
-            output_files: output.files.keys().cloned().collect(),
-            input_hash: output.input_hash.clone(),
-            from_cache: output.from_cache,
-            tokens_in: output.tokens_in,
-            tokens_out: output.tokens_out,
-            duration_ms: output.duration_ms,
-            model: output.model.clone(),
-            cost_usd: cost,
-        });
+    if terminal.interactive_conflicts {
+        options.resolve_conflicts = Some(&resolve_conflicts);
     }
-
-    // Write generation record
-    let generation_record = GenerationRecord {
-        timestamp: Utc::now(),
-        project: config.project.name.clone(),
-        model: config.model.model.clone(),
-        temperature: config.model.temperature,
-        seed: config.model.seed,
-        language: config.language.default.clone(),
-        framework: config.framework.as_ref().map(|fw| fw.name.clone()),
-        prompts: prompt_records,
-        summary: GenerationSummary {
-            total_prompts: result.outputs.len() + result.skipped.len(),
-            cache_hits: result.cache_hits,
-            cache_misses: result.cache_misses,
-            skipped: result.skipped.len(),
-            total_tokens_in: result.total_tokens_in,
-            total_tokens_out: result.total_tokens_out,
-            total_cost_usd: total_cost,
-            total_duration_ms: result.total_duration_ms,
-            total_files_written: files_written,
-            patches_applied,
-            patches_conflicted,
-        },
-    };
-
-    let generations_dir = root.join(".lit").join("generations");
-    if let Err(e) = generation_record.write(&generations_dir) {
-        eprintln!("  {}", style::warning(&format!("Failed to write generation record: {}", e)));
+    let interactive = terminal.interactive;
+    if interactive {
+        progress::line(&format!("  {}", "Interactive review enabled (cached results are reused as-is)".dimmed()));
+        options.reviewer = Some(Box::new(InteractiveReviewer::resume_or_start(&root.join(".lit"))?));
+    }
+    let report = lit.regenerate(options).await?;
+    if interactive {
+        SessionStore::new(&root.join(".lit")).clear(REVIEW_SESSION)?;
     }
 
-    // Summary
+    let summary = &report.record.summary;
     eprintln!();
     eprintln!("{}", style::header("Generation complete"));
     eprintln!(
         "  {:<20} {}",
         "Prompts generated:".dimmed(),
-        result.outputs.len().to_string().bold()
+        (report.generated.len() + report.cached.len()).to_string().bold()
     );
     eprintln!(
         "  {:<20} {}",
         "Prompts skipped:".dimmed(),
-        result.skipped.len().to_string().dimmed()
+        report.skipped.len().to_string().dimmed()
     );
-    if result.cache_hits > 0 || result.cache_misses > 0 {
+    if summary.cache_hits > 0 || summary.cache_misses > 0 {
         eprintln!(
             "  {:<20} {} hit(s), {} miss(es)",
             "Cache:".dimmed(),
-            result.cache_hits.to_string().green(),
-            result.cache_misses.to_string().yellow()
+            summary.cache_hits.to_string().green(),
+            summary.cache_misses.to_string().yellow()
         );
     }
     eprintln!(
         "  {:<20} {}",
         "Files written:".dimmed(),
-        files_written.to_string().bold()
+        summary.total_files_written.to_string().bold()
     );
-    if patches_applied > 0 || patches_conflicted > 0 {
+    if report.written_to != config.code_dir(root) {
+        eprintln!("  {:<20} {}", "Written to:".dimmed(), report.written_to.display());
+    }
+    if !report.left_stale.is_empty() {
+        eprintln!(
+            "  {:<20} {}",
+            "Left stale:".dimmed(),
+            report.left_stale.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ").yellow()
+        );
+        eprintln!("  {}", style::hint("Run `lit regenerate` to bring the dependents up to date."));
+    }
+    if summary.patches_applied > 0 || summary.patches_conflicted > 0 {
         eprintln!(
             "  {:<20} {} applied, {} conflict(s)",
             "Patches:".dimmed(),
-            patches_applied.to_string().green(),
-            if patches_conflicted > 0 {
-                patches_conflicted.to_string().red()
+            summary.patches_applied.to_string().green(),
+            if summary.patches_conflicted > 0 {
+                summary.patches_conflicted.to_string().red()
             } else {
-                patches_conflicted.to_string().dimmed()
+                summary.patches_conflicted.to_string().dimmed()
             }
         );
     }
     eprintln!(
-        "  {:<20} {} in / {} out",
+        "  {:<20} {} in / {} out{}",
         "Tokens:".dimmed(),
-        format_tokens(result.total_tokens_in).dimmed(),
-        format_tokens(result.total_tokens_out).dimmed()
+        format_tokens(summary.total_tokens_in).dimmed(),
+        format_tokens(summary.total_tokens_out).dimmed(),
+        format_prompt_cache(&summary.total_prompt_cache).dimmed()
     );
     eprintln!(
         "  {:<20} {}",
         "Cost:".dimmed(),
-        style::cost(&format_cost(total_cost))
+        style::cost(&format_cost(summary.total_cost_usd))
     );
     eprintln!(
         "  {:<20} {}",
         "Time:".dimmed(),
-        format!("{:.1}s", result.total_duration_ms as f64 / 1000.0).dimmed()
+        format!("{:.1}s", summary.total_duration_ms as f64 / 1000.0).dimmed()
     );
 
+    // Conflicts scroll by quickly; leave a report that's hard to miss
+    if let Some(path) = &report.conflict_report {
+        eprintln!();
+        eprintln!(
+            "{}",
+            style::warning(&format!(
+                "{} patch conflict(s) — see {}",
+                report.conflicts.len(),
+                path.strip_prefix(root).unwrap_or(path).display()
+            ))
+        );
+    }
     Ok(())
 }
 
-/// Load existing files from code.lock/ directory for use as context
-fn load_existing_code(code_lock_dir: &std::path::Path) -> HashMap<PathBuf, String> {
-    let mut code = HashMap::new();
+// ---------- Interactive review ----------
+
+/// Session name for `lit regenerate --interactive`
+const REVIEW_SESSION: &str = "regenerate-review";
+
+/// Progress of an interactive review, saved after every step so the review
+/// can resume after a dropped terminal. Approved outputs are in the cache, so
+/// only skips and the output awaiting a decision need remembering.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReviewSession {
+    started_at: DateTime<Utc>,
+    /// Prompt → decision, for the input hash it was made on
+    decisions: BTreeMap<PathBuf, (String, ReviewDecision)>,
+    /// Output shown for review when the session was interrupted
+    pending: Option<PendingReview>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingReview {
+    prompt: PathBuf,
+    input_hash: String,
+    files: HashMap<PathBuf, String>,
+    tokens_in: u64,
+    tokens_out: u64,
+    #[serde(default)]
+    prompt_cache: PromptCacheTokens,
+    model: String,
+    #[serde(default)]
+    batch: bool,
+}
+
+/// Terminal reviewer for `lit regenerate --interactive`
+struct InteractiveReviewer {
+    sessions: SessionStore,
+    session: Mutex<ReviewSession>,
+}
+
+impl InteractiveReviewer {
+    /// Offer to resume an interrupted review, or start a new one
+    fn resume_or_start(lit_dir: &Path) -> Result<Self> {
+        let sessions = SessionStore::new(lit_dir);
+        let mut session = sessions
+            .load::<ReviewSession>(REVIEW_SESSION)
+            .filter(|s| !s.decisions.is_empty() || s.pending.is_some());
+        if let Some(ref earlier) = session {
+            let question = format!(
+                "Resume the interactive review started {} ({} prompt(s) reviewed{})?",
+                earlier.started_at.format("%Y-%m-%d %H:%M UTC"),
+                earlier.decisions.len(),
+                if earlier.pending.is_some() { ", one awaiting a decision" } else { "" }
+            );
+            if !confirm(&question, true)? {
+                session = None;
+            }
+        }
+        let session = session.unwrap_or_else(|| ReviewSession {
+            started_at: Utc::now(),
+            decisions: BTreeMap::new(),
+            pending: None,
+        });
+        Ok(Self {
+            sessions,
+            session: Mutex::new(session),
+        })
+    }
+
+    fn save(&self, session: &ReviewSession) {
+        if let Err(e) = self.sessions.save(REVIEW_SESSION, session) {
+            eprintln!("    {}", style::warning(&format!("Failed to save review session: {:#}", e)));
+        }
+    }
+}
+
+impl OutputReviewer for InteractiveReviewer {
+    fn preview(&self, _prompt: &Prompt, request: &GenerationRequest) {
+        eprintln!(
+            "    {} {}, ~{} input tokens (system {}, context {}, prompt {})",
+            "Request:".dimmed(),
+            request.model,
+            format_tokens(estimate_request_tokens(request)),
+            format_tokens(estimate_tokens(&request.system_prompt)).dimmed(),
+            format_tokens(estimate_tokens(&request.context)).dimmed(),
+            format_tokens(estimate_tokens(&request.user_prompt)).dimmed()
+        );
+    }
+
+    fn review(
+        &self,
+        prompt: &Prompt,
+        output: &GenerationOutput,
+        current: &HashMap<PathBuf, String>,
+    ) -> Result<ReviewDecision> {
+        let mut paths: Vec<&PathBuf> = output.files.keys().collect();
+        paths.sort();
+
+        eprintln!();
+        for path in paths {
+            let new_content = &output.files[path];
+            match current.get(path) {
+                Some(old) if old == new_content => {
+                    eprintln!("  {} {}", path.display(), "(unchanged)".dimmed());
+                }
+                Some(old) => {
+                    eprintln!("{}", style::file_modified(&path.display().to_string()));
+                    print_diff(old, new_content);
+                }
+                None => {
+                    eprintln!("{}", style::file_new(&path.display().to_string()));
+                    print_diff("", new_content);
+                }
+            }
+        }
+        eprintln!();
+
+        // Keep the output until it is decided on, so a dropped terminal
+        // doesn't cost another generation
+        {
+            let mut session = self.session.lock().unwrap();
+            session.pending = Some(PendingReview {
+                prompt: prompt.path.clone(),
+                input_hash: output.input_hash.clone(),
+                files: output.files.clone(),
+                tokens_in: output.tokens_in,
+                tokens_out: output.tokens_out,
+                prompt_cache: output.prompt_cache,
+                model: output.model.clone(),
+                batch: output.batch,
+            });
+            self.save(&session);
+        }
+
+        let decision = loop {
+            eprint!(
+                "  {} [a]pprove / [r]etry / [s]kip: ",
+                prompt.path.display().to_string().bold()
+            );
+            std::io::Write::flush(&mut std::io::stderr())?;
 
-    if !code_lock_dir.exists() {
-        return code;
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                anyhow::bail!("Interactive review aborted (stdin closed)");
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "a" | "approve" | "y" | "yes" => break ReviewDecision::Approve,
+                "r" | "retry" => break ReviewDecision::Retry,
+                "s" | "skip" | "n" | "no" => break ReviewDecision::Skip,
+                _ => eprintln!("    {}", "Please answer a, r or s.".dimmed()),
+            }
+        };
+
+        let mut session = self.session.lock().unwrap();
+        session.pending = None;
+        if decision != ReviewDecision::Retry {
+            session
+                .decisions
+                .insert(prompt.path.clone(), (output.input_hash.clone(), decision));
+        }
+        self.save(&session);
+        Ok(decision)
+    }
+
+    fn earlier_decision(&self, prompt: &Prompt, input_hash: &str) -> Option<ReviewDecision> {
+        let session = self.session.lock().unwrap();
+        session
+            .decisions
+            .get(&prompt.path)
+            .filter(|(hash, _)| hash == input_hash)
+            .map(|(_, decision)| *decision)
     }
 
-    fn walk_dir(dir: &std::path::Path, base: &std::path::Path, out: &mut HashMap<PathBuf, String>) {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    walk_dir(&path, base, out);
-                } else if path.is_file() {
-                    if let Ok(content) = std::fs::read_to_string(&path) {
-                        if let Ok(relative) = path.strip_prefix(base) {
-                            out.insert(relative.to_path_buf(), content);
+    fn pending(&self, prompt: &Prompt, input_hash: &str) -> Option<GenerationOutput> {
+        let session = self.session.lock().unwrap();
+        let pending = session
+            .pending
+            .as_ref()
+            .filter(|p| p.prompt == prompt.path && p.input_hash == input_hash)?;
+        Some(GenerationOutput {
+            prompt_path: pending.prompt.clone(),
+            files: pending.files.clone(),
+            tokens_in: pending.tokens_in,
+            tokens_out: pending.tokens_out,
+            prompt_cache: pending.prompt_cache,
+            duration_ms: 0,
+            model: pending.model.clone(),
+            from_cache: false,
+            input_hash: pending.input_hash.clone(),
+            input_breakdown: None,
+            batch: pending.batch,
+        })
+    }
+}
+
+// ---------- Conflict resolution ----------
+
+/// Resolve each conflicting region of a patch merge on the terminal, for
+/// `lit regenerate --interactive-conflicts`
+fn resolve_conflicts(path: &Path, merge: &PatchMerge) -> Result<String> {
+    let total = merge.conflicts().count();
+    let mut index = 0;
+    eprintln!();
+    merge.resolve(|conflict| {
+        index += 1;
+        eprintln!(
+            "  {} {} ({}, conflict {} of {})",
+            "Patch conflict in".yellow(),
+            path.display().to_string().bold(),
+            conflict.location(),
+            index,
+            total
+        );
+        print_side_by_side(&conflict.manual, &conflict.generated);
+        loop {
+            eprint!("  {} [m]anual / [g]enerated / [e]dit: ", "Keep".bold());
+            std::io::Write::flush(&mut std::io::stderr())?;
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                anyhow::bail!("Conflict resolution aborted (stdin closed)");
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "m" | "manual" => return Ok(conflict.manual.clone()),
+                "g" | "generated" => return Ok(conflict.generated.clone()),
+                "e" | "edit" => match edit_conflict(path, &conflict.with_markers()) {
+                    Ok(mut edited) if !has_conflict_markers(&edited) => {
+                        if conflict.generated.ends_with('\n') && !edited.ends_with('\n') {
+                            edited.push('\n');
                         }
+                        return Ok(edited);
                     }
-                }
+                    Ok(_) => eprintln!(
+                        "    {}",
+                        style::warning("The edit still contains conflict markers")
+                    ),
+                    Err(e) => eprintln!("    {}", style::warning(&format!("{:#}", e))),
+                },
+                _ => eprintln!("    {}", "Please answer m, g or e.".dimmed()),
             }
         }
+    })
+}
+
+/// Print the two sides of a conflict in columns sized to the terminal
+fn print_side_by_side(manual: &str, generated: &str) {
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(100);
+    let width = (columns.saturating_sub(7) / 2).max(20);
+    let fit = |line: &str| -> String {
+        let line = line.replace('\t', "    ");
+        if line.chars().count() > width {
+            let cut: String = line.chars().take(width - 1).collect();
+            format!("{}…", cut)
+        } else {
+            format!("{:<width$}", line, width = width)
+        }
+    };
+
+    eprintln!(
+        "    {} │ {}",
+        fit("manual patch").yellow().bold(),
+        fit("generated").cyan().bold()
+    );
+    eprintln!("    {}┼{}", "─".repeat(width + 1), "─".repeat(width + 1));
+    let left: Vec<&str> = manual.lines().collect();
+    let right: Vec<&str> = generated.lines().collect();
+    for i in 0..left.len().max(right.len()) {
+        let l = left.get(i).copied().unwrap_or("");
+        let r = right.get(i).copied().unwrap_or("");
+        let (l, r) = (fit(l), fit(r));
+        if left.get(i) == right.get(i) {
+            eprintln!("    {} │ {}", l.dimmed(), r.dimmed());
+        } else {
+            eprintln!("    {} │ {}", l.yellow(), r.cyan());
+        }
     }
+    eprintln!();
+}
+
+/// Open a conflict, with markers, in `$VISUAL` / `$EDITOR` and return the
+/// saved text
+fn edit_conflict(path: &Path, text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("conflict");
+    let tmp = std::env::temp_dir().join(format!("lit-conflict-{}-{}", std::process::id(), name));
+    std::fs::write(&tmp, text).with_context(|| format!("Failed to write {}", tmp.display()))?;
+
+    let command = format!("{} {}", editor, shell_quote(&tmp.to_string_lossy()));
+    let status = shell(&command)
+        .status()
+        .with_context(|| format!("Failed to run editor `{}`", editor));
+    let edited = std::fs::read_to_string(&tmp);
+    let _ = std::fs::remove_file(&tmp);
+
+    let status = status?;
+    if !status.success() {
+        anyhow::bail!("Editor `{}` exited with {}", editor, status);
+    }
+    edited.with_context(|| format!("Failed to read {}", tmp.display()))
+}
+
+fn has_conflict_markers(text: &str) -> bool {
+    text.lines().any(|l| {
+        l.starts_with("<<<<<<< ") || l == "=======" || l.starts_with(">>>>>>> ")
+    })
+}
 
-    walk_dir(code_lock_dir, code_lock_dir, &mut code);
-    code
+/// List a large regeneration set with what comes from cache and the
+/// estimated cost of the rest, and ask whether to go ahead. Sets served
+/// entirely from cache go ahead without asking.
+fn confirm_regeneration(
+    config: &LitConfig,
+    root: &Path,
+    dag: &Dag,
+    prompts: &HashMap<PathBuf, Prompt>,
+    regeneration_set: &[PathBuf],
+    no_cache: bool,
+) -> Result<bool> {
+    let existing_code = load_code(config, root);
+    let cache = (!no_cache)
+        .then(|| Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone()));
+    let estimates = estimate_prompts(config, dag, prompts, &existing_code, cache.as_ref(), regeneration_set);
+    let fresh = estimates.iter().filter(|e| !e.cached).count();
+    if fresh == 0 {
+        return Ok(true);
+    }
+
+    let width = estimates.iter().map(|e| e.prompt_path.display().to_string().len()).max().unwrap_or(0);
+    eprintln!();
+    for e in &estimates {
+        let source = if e.cached {
+            "cached".green()
+        } else {
+            format!("~{}", format_cost(e.cost_usd)).yellow()
+        };
+        eprintln!("    {:<width$}  {}", e.prompt_path.display().to_string(), source, width = width);
+    }
+    eprintln!(
+        "  {} prompt(s): {} from cache, {} fresh LLM call(s), about {}",
+        estimates.len().to_string().bold(),
+        (estimates.len() - fresh).to_string().green(),
+        fresh.to_string().yellow(),
+        style::cost(&format_cost(estimates.iter().map(|e| e.cost_usd).sum()))
+    );
+    confirm("Regenerate?", false)
+}
+
+/// Ask a yes/no question on the terminal; an empty answer means `default`
+pub fn confirm(question: &str, default: bool) -> Result<bool> {
+    eprint!("  {} {}: ", question.bold(), if default { "[Y/n]" } else { "[y/N]" });
+    std::io::Write::flush(&mut std::io::stderr())?;
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        anyhow::bail!("Aborted (stdin closed)");
+    }
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "" => default,
+        answer => matches!(answer, "y" | "yes"),
+    })
+}
+
+/// Print a colored unified diff between two versions of a file
+pub fn print_diff(old: &str, new: &str) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        eprintln!("    {}", hunk.header().to_string().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.trim_end_matches('\n');
+            match change.tag() {
+                similar::ChangeTag::Insert => eprintln!("    {}", format!("+{}", line).green()),
+                similar::ChangeTag::Delete => eprintln!("    {}", format!("-{}", line).red()),
+                similar::ChangeTag::Equal => eprintln!("    {}", format!(" {}", line).dimmed()),
+            }
+        }
+    }
 }
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cli::RemoteCommands;
+use crate::core::config::LitConfig;
+use crate::core::repo::LitRepo;
+use crate::core::style;
+
+/// `lit remote add|list` — the git remotes `lit push` and `lit pull` use
+pub async fn run(action: Option<RemoteCommands>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (_config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?;
+
+    match action.unwrap_or(RemoteCommands::List) {
+        RemoteCommands::Add { name, url } => {
+            repo.add_remote(&name, &url)?;
+            eprintln!("{} Added remote {} ({})", "✓".green().bold(), name.bold(), url);
+            eprintln!("{}", style::hint("Run `lit push` to publish the current branch to it."));
+        }
+        RemoteCommands::List => {
+            let remotes = repo.remotes()?;
+            if remotes.is_empty() {
+                eprintln!("{}", "No remotes.".dimmed());
+                eprintln!("{}", style::hint("Add one with `lit remote add origin <url>`."));
+                return Ok(());
+            }
+            for remote in remotes {
+                match remote.push_url {
+                    Some(push_url) => {
+                        println!("{}\t{} (fetch)", remote.name, remote.url);
+                        println!("{}\t{} (push)", remote.name, push_url);
+                    }
+                    None => println!("{}\t{}", remote.name, remote.url),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use colored::Colorize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::api::RegenerateOptions;
+use crate::cli::regenerate::{self, TerminalOptions};
+use crate::core::config::LitConfig;
+use crate::core::freeze::FreezeList;
+use crate::core::generation_record::{GenerationRecord, prompt_cost_totals, prompt_history};
+use crate::core::http::{Request, Response, read_request};
+use crate::core::patch::PatchStore;
+use crate::core::project::{load_prompts, stale_prompts};
+use crate::core::prompt::TagFilter;
+use crate::core::style;
+
+/// The dashboard page; it renders everything from the `/api/*` endpoints
+const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+/// How long a client gets to send its whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `lit serve` — a local web dashboard: the DAG with stale and frozen
+/// prompts, per-prompt cost history, tracked patches with their diffs, and
+/// regeneration of a selected prompt and its dependents.
+///
+/// Listens on 127.0.0.1 only, and refuses requests addressed to another
+/// host or sent from another origin. Requests are handled one at a time,
+/// so a regeneration started from the browser finishes before anything
+/// else is answered; its progress is printed here, as with `lit regenerate`.
+pub async fn run(port: u16) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen on 127.0.0.1:{}: {}", port, e))?;
+
+    eprintln!(
+        "{}",
+        style::project_header(&config.project.name, &config.project.version)
+    );
+    let port = listener.local_addr()?.port();
+    eprintln!(
+        "  {:<12} {}",
+        "Dashboard:".dimmed(),
+        format!("http://127.0.0.1:{}/", port).bold()
+    );
+    eprintln!("{}", style::hint("Press Ctrl-C to stop."));
+    eprintln!();
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.split();
+        // A client that stops sending mid-request would otherwise hold up
+        // every request after it
+        let read = tokio::time::timeout(READ_TIMEOUT, read_request(&mut BufReader::new(reader))).await;
+        let response = match read {
+            Ok(Ok(Some(request))) => match request.check_local(port) {
+                Ok(()) => handle(&root, &request).await,
+                Err(refused) => refused,
+            },
+            Ok(Ok(None)) => continue,
+            Ok(Err(e)) => Response::error(400, &format!("{:#}", e)),
+            Err(_) => Response::error(408, "Timed out reading the request"),
+        };
+        // A browser that gave up on the request is not the server's problem
+        let _ = writer.write_all(&response.to_bytes()).await;
+    }
+}
+
+async fn handle(root: &Path, request: &Request) -> Response {
+    // Reload on every request so edits made meanwhile show up
+    let config = match LitConfig::from_file(&root.join("lit.toml")) {
+        Ok(config) => config,
+        Err(e) => return Response::error(500, &format!("{:#}", e)),
+    };
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => return Response::html(DASHBOARD_HTML),
+        ("GET", "/api/dag") => dag_json(&config, root),
+        ("GET", "/api/cost") => cost_json(root, request.query.get("prompt").map(Path::new)),
+        ("GET", "/api/patches") => patches_json(root),
+        ("POST", "/api/regenerate") => {
+            let prompts: Vec<PathBuf> = serde_json::from_slice::<Value>(&request.body)
+                .ok()
+                .and_then(|body| body["prompts"].as_array().cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|p| p.as_str().map(PathBuf::from))
+                .collect();
+            if prompts.is_empty() {
+                return Response::error(400, "Expected a JSON body like {\"prompts\": [\"prompts/a.prompt.md\"]}");
+            }
+            eprintln!("{} {}", "Dashboard:".cyan(), "regenerate requested".dimmed());
+            let options = RegenerateOptions { prompts: prompts.clone(), ..Default::default() };
+            regenerate::run(options, TerminalOptions::UNATTENDED)
+                .await
+                .map(|()| json!({ "regenerated": prompts }))
+        }
+        (_, "/" | "/api/dag" | "/api/cost" | "/api/patches" | "/api/regenerate") => {
+            return Response::error(405, "Method not allowed");
+        }
+        _ => return Response::error(404, &format!("No such page: {}", request.path)),
+    };
+    match result {
+        Ok(value) => Response::json(&value),
+        Err(e) => Response::error(500, &format!("{:#}", e)),
+    }
+}
+
+/// Prompts by DAG level, with their edges, outputs and state
+fn dag_json(config: &LitConfig, root: &Path) -> Result<Value> {
+    let (prompts, dag) = load_prompts(config, root)?;
+    let stale = stale_prompts(config, root, &TagFilter::default())?;
+    let frozen = FreezeList::load(&root.join(".lit"))?;
+
+    let levels: Vec<Vec<Value>> = dag
+        .levels()
+        .iter()
+        .map(|level| {
+            level
+                .iter()
+                .map(|path| {
+                    let node = &dag.nodes()[path];
+                    json!({
+                        "path": path,
+                        "imports": node.imports,
+                        "dependents": node.dependents,
+                        "downstream": dag.descendants(path).len(),
+                        "outputs": node.outputs,
+                        "tags": prompts[path].frontmatter.tags,
+                        "stale": stale.as_ref().is_none_or(|stale| stale.contains(path)),
+                        "frozen": frozen.is_frozen(path),
+                        "draft": node.draft,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    Ok(json!({
+        "project": config.project.name,
+        "version": config.project.version,
+        "levels": levels,
+    }))
+}
+
+/// Cost per prompt, or one prompt's history (oldest first) with `prompt`
+fn cost_json(root: &Path, prompt: Option<&Path>) -> Result<Value> {
+    let records = GenerationRecord::list(&root.join(".lit").join("generations"))?;
+    if let Some(prompt) = prompt {
+        let history: Vec<Value> = prompt_history(&records, prompt)
+            .into_iter()
+            .map(|(timestamp, p)| {
+                json!({
+                    "timestamp": timestamp,
+                    "model": p.model,
+                    "from_cache": p.from_cache,
+                    "tokens_in": p.tokens_in,
+                    "tokens_out": p.tokens_out,
+                    "cost_usd": p.cost_usd,
+                })
+            })
+            .collect();
+        return Ok(json!({ "prompt": prompt, "history": history }));
+    }
+    let totals: Vec<Value> = prompt_cost_totals(&records)
+        .into_iter()
+        .map(|t| {
+            json!({
+                "prompt": t.prompt_path,
+                "generations": t.generations,
+                "tokens_in": t.tokens_in,
+                "tokens_out": t.tokens_out,
+                "cost_usd": t.cost_usd,
+                "last_generated": t.last_generated,
+            })
+        })
+        .collect();
+    Ok(json!({ "records": records.len(), "totals": totals }))
+}
+
+/// Tracked patches with their diffs
+fn patches_json(root: &Path) -> Result<Value> {
+    let store = PatchStore::new(root.join(".lit").join("patches"));
+    let index = store.load_index()?;
+    let patches: Vec<Value> = index
+        .patches
+        .iter()
+        .map(|(path, entry)| {
+            let diff = store.load_patch(path).map(|stored| stored.diff);
+            json!({
+                "output": path,
+                "status": entry.status,
+                "updated_at": entry.updated_at,
+                "diff": diff,
+            })
+        })
+        .collect();
+    Ok(json!({ "patches": patches }))
+}
@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::cli::log::format_timestamp;
+use crate::core::config::LitConfig;
+use crate::core::paths::{normalize, relative_to};
+use crate::core::prompt::{Prompt, is_prompt_file};
+use crate::core::repo::{CommitInfo, LitRepo, TreeObject};
+use crate::core::style;
+
+/// `lit show <ref> [path]` — like `git show`: a prompt, generated file or
+/// directory as of a commit, without checking it out. Content goes to
+/// stdout so it can be piped or redirected; the commit line and, for
+/// prompts, a frontmatter summary go to stderr. `<ref>:<path>` takes the
+/// path from the repo root, a separate path argument from the current
+/// directory. A path that isn't in the commit is also looked up under
+/// code.lock/. Without a path, the commit and the prompts it changed.
+pub async fn run(spec: String, path: Option<PathBuf>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?;
+
+    let (reference, path) = match (spec.split_once(':'), path) {
+        (Some(_), Some(_)) => bail!("Give the path either as <ref>:<path> or as a separate argument, not both"),
+        (Some((reference, path)), None) => (reference.to_string(), Some(normalize(Path::new(path)))),
+        (None, path) => (spec, path.map(|p| relative_to(&cwd.join(p), &root))),
+    };
+    let reference = if reference.is_empty() {
+        "HEAD".to_string()
+    } else {
+        reference
+    };
+    let commit = repo.commit_info(&reference)?;
+    print_commit(&commit);
+
+    let Some(path) = path else {
+        return show_commit(&repo, &commit);
+    };
+    let path = if path == Path::new(".") {
+        PathBuf::new()
+    } else {
+        path
+    };
+
+    let code_path = config.project.code_dir.join(&path);
+    let (path, object) = match repo.object_at(&commit.hash, &path)? {
+        Some(object) => (path, object),
+        None => match repo.object_at(&commit.hash, &code_path)? {
+            Some(object) => (code_path, object),
+            None => bail!("{} does not exist in {}", path.display(), commit.short_hash),
+        },
+    };
+
+    match object {
+        TreeObject::Tree(entries) => {
+            let name = if path.as_os_str().is_empty() {
+                "./".to_string()
+            } else {
+                format!("{}/", path.display())
+            };
+            eprintln!("{}", style::header(&name));
+            for entry in entries {
+                println!("{}", entry);
+            }
+        }
+        TreeObject::Blob(content) => {
+            if is_prompt_file(&path)
+                && let Ok(raw) = std::str::from_utf8(&content)
+            {
+                print_frontmatter(&config, &path, raw);
+            }
+            eprintln!();
+            std::io::stdout().write_all(&content).context("Failed to write to stdout")?;
+        }
+    }
+    Ok(())
+}
+
+fn print_commit(commit: &CommitInfo) {
+    eprintln!(
+        "{} {} {} — {}",
+        commit.short_hash.yellow(),
+        format_timestamp(commit.timestamp).dimmed(),
+        commit.author.dimmed(),
+        commit.message
+    );
+}
+
+/// The prompts a commit added, changed or removed
+fn show_commit(repo: &LitRepo, commit: &CommitInfo) -> Result<()> {
+    let changed = match repo.prompts_changed_between(&format!("{}^", commit.hash), &commit.hash) {
+        Ok(changed) => changed,
+        // A root commit: everything in it is new
+        Err(_) => repo.prompt_files_at(&commit.hash)?.into_iter().map(|(path, _)| path).collect(),
+    };
+    if changed.is_empty() {
+        eprintln!("{}", "No prompt changes in this commit.".dimmed());
+        return Ok(());
+    }
+    eprintln!();
+    for path in &changed {
+        println!("{}", path.display());
+    }
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::hint(&format!("Run `lit show {} <path>` to see one of them.", commit.short_hash))
+    );
+    Ok(())
+}
+
+/// What a prompt's frontmatter declares, as parsed against today's lit.toml
+fn print_frontmatter(config: &LitConfig, path: &Path, raw: &str) {
+    let prompt = match Prompt::parse(raw, path.to_path_buf(), config) {
+        Ok(prompt) => prompt,
+        Err(e) => {
+            eprintln!("  {}", format!("Frontmatter doesn't parse against the current lit.toml: {:#}", e).dimmed());
+            return;
+        }
+    };
+    let list = |paths: &[PathBuf]| paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ");
+    let fm = &prompt.frontmatter;
+    eprintln!("  {:<10} {}", "Outputs:".dimmed(), list(&fm.outputs));
+    if !fm.imports.is_empty() {
+        eprintln!("  {:<10} {}", "Imports:".dimmed(), list(&fm.imports));
+    }
+    if let Some(model) = &fm.model {
+        let mut models = vec![format!("{}/{}", model.provider, model.model)];
+        models.extend(fm.fallback_models.iter().map(|route| format!("{}/{}", route.provider, route.model)));
+        eprintln!("  {:<10} {}", "Model:".dimmed(), models.join(" → "));
+    }
+    if let Some(language) = &fm.language {
+        eprintln!("  {:<10} {}", "Language:".dimmed(), language);
+    }
+    if !fm.tags.is_empty() {
+        eprintln!("  {:<10} {}", "Tags:".dimmed(), fm.tags.join(", "));
+    }
+    if !fm.tests.is_empty() {
+        eprintln!("  {:<10} {}", "Tests:".dimmed(), fm.tests.len());
+    }
+    if fm.draft {
+        eprintln!("  {:<10} {}", "Draft:".dimmed(), "yes (never generated)".cyan());
+    }
+}
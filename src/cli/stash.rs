@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::cli::StashCommands;
+use crate::core::config::LitConfig;
+use crate::core::generator::change_summary;
+use crate::core::repo::LitRepo;
+use crate::core::stash::{Stash, StashEntry};
+use crate::core::style;
+
+/// `lit stash [push|list|show|pop|apply|drop]` — park uncommitted changes to
+/// prompts and .lit/patches/, leaving them as at HEAD, and bring them back
+/// later. Defaults to `push`.
+pub async fn run(action: Option<StashCommands>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let repo = LitRepo::open(&root)?.with_project_dirs(&config.project);
+    let lit_dir = root.join(".lit");
+    let mut stash = Stash::load(&lit_dir)?;
+
+    match action.unwrap_or(StashCommands::Push { message: None }) {
+        StashCommands::Push { message } => {
+            let paths: Vec<PathBuf> = repo
+                .changed_paths()?
+                .into_iter()
+                .filter(|path| repo.in_prompt_dir(path) || path.starts_with(".lit/patches"))
+                .collect();
+            if paths.is_empty() {
+                eprintln!("{}", "No prompt or patch changes to stash.".dimmed());
+                return Ok(());
+            }
+            let head = repo.head_commit();
+            let message = message.unwrap_or_else(|| match &head {
+                Some(head) => format!("WIP on {}: {}", head.short_hash, head.message),
+                None => "WIP".to_string(),
+            });
+            let mut files = BTreeMap::new();
+            for path in &paths {
+                let full = root.join(path);
+                let content = if full.exists() {
+                    Some(
+                        std::fs::read_to_string(&full)
+                            .with_context(|| format!("Failed to read {}", full.display()))?,
+                    )
+                } else {
+                    None
+                };
+                files.insert(path.clone(), content);
+            }
+            stash.push(StashEntry {
+                message: message.clone(),
+                created_at: chrono::Utc::now(),
+                base: head.map(|h| h.hash),
+                files,
+            });
+            // Save first: if restoring fails, the changes are still in the stash
+            stash.save(&lit_dir)?;
+            repo.restore_paths(&paths)?;
+            eprintln!(
+                "{} Stashed {} file(s) as stash@{{0}}: {}",
+                "✓".green().bold(),
+                paths.len(),
+                message
+            );
+        }
+        StashCommands::List => {
+            eprintln!("{}", style::header("Stash"));
+            if stash.entries.is_empty() {
+                eprintln!("  {}", "(empty)".dimmed());
+            }
+            for (i, entry) in stash.entries.iter().enumerate() {
+                eprintln!(
+                    "  {} {} {} {}",
+                    format!("stash@{{{}}}", i).yellow(),
+                    entry.created_at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+                    entry.message,
+                    format!("({} file(s))", entry.files.len()).dimmed()
+                );
+            }
+        }
+        StashCommands::Show { index } => {
+            let entry = stash.get(index)?;
+            eprintln!("{}", style::header(&format!("stash@{{{}}}: {}", index, entry.message)));
+            for (path, content) in &entry.files {
+                let head = repo.file_at("HEAD", path).ok().flatten();
+                match content {
+                    Some(content) => eprintln!("  {}", change_summary(path, head.as_deref(), content)),
+                    None => eprintln!("  {} {}", "deleted".red(), path.display()),
+                }
+            }
+        }
+        StashCommands::Pop { index } => {
+            apply(&root, &repo, stash.get(index)?)?;
+            let entry = stash.remove(index)?;
+            stash.save(&lit_dir)?;
+            eprintln!("{} Popped stash@{{{}}}: {}", "✓".green().bold(), index, entry.message);
+        }
+        StashCommands::Apply { index } => {
+            let entry = stash.get(index)?;
+            apply(&root, &repo, entry)?;
+            eprintln!("{} Applied stash@{{{}}}: {}", "✓".green().bold(), index, entry.message);
+        }
+        StashCommands::Drop { index } => {
+            let entry = stash.remove(index)?;
+            stash.save(&lit_dir)?;
+            eprintln!("{} Dropped stash@{{{}}}: {}", "✓".green().bold(), index, entry.message);
+        }
+    }
+    Ok(())
+}
+
+/// Write a stash entry's files back, refusing to overwrite uncommitted
+/// changes that differ from what was stashed
+fn apply(root: &Path, repo: &LitRepo, entry: &StashEntry) -> Result<()> {
+    let changed = repo.changed_paths()?;
+    let clobbered: Vec<&PathBuf> = entry
+        .files
+        .iter()
+        .filter(|(path, content)| {
+            changed.contains(path) && std::fs::read_to_string(root.join(path)).ok() != **content
+        })
+        .map(|(path, _)| path)
+        .collect();
+    if !clobbered.is_empty() {
+        bail!(
+            "Applying the stash would overwrite uncommitted changes to {} file(s):\n{}\n\
+             Hint: Commit or stash them first.",
+            clobbered.len(),
+            clobbered.iter().map(|p| format!("  {}", p.display())).collect::<Vec<_>>().join("\n")
+        );
+    }
+    if let (Some(base), Some(head)) = (&entry.base, repo.head_commit())
+        && *base != head.hash
+    {
+        eprintln!(
+            "{}",
+            style::warning(&format!(
+                "Stashed on {}, applying on {}",
+                &base[..7.min(base.len())],
+                head.short_hash
+            ))
+        );
+    }
+
+    for (path, content) in &entry.files {
+        let full = root.join(path);
+        match content {
+            Some(content) => {
+                if let Some(parent) = full.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&full, content).with_context(|| format!("Failed to write {}", full.display()))?;
+            }
+            None if full.exists() => {
+                std::fs::remove_file(&full).with_context(|| format!("Failed to remove {}", full.display()))?;
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
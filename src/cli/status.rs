@@ -1,12 +1,16 @@
+use std::path::Path;
+
 use anyhow::Result;
 use colored::Colorize;
 
 use crate::core::config::LitConfig;
-use crate::core::prompt::discover_prompts;
+use crate::core::manifest::Manifest;
+use crate::core::project::stale_prompts;
+use crate::core::prompt::{Prompt, TagFilter, discover_project_prompts};
 use crate::core::repo::LitRepo;
 use crate::core::style;
 
-pub async fn run() -> Result<()> {
+pub async fn run(tags: TagFilter) -> Result<()> {
     let cwd = std::env::current_dir()?;
     let (config, root) = LitConfig::find_and_load(&cwd)?;
 
@@ -14,10 +18,13 @@ pub async fn run() -> Result<()> {
 
     // Try to open git repo for status
     let repo = match LitRepo::open(&root) {
-        Ok(r) => r,
+        Ok(r) => r.with_project_dirs(&config.project),
         Err(_) => {
             eprintln!("  {}", "(no git repository — run `lit init` first)".dimmed());
-            return show_prompts_only(&root);
+            show_prompts_only(&config, &root)?;
+            show_staleness(&config, &root, &tags);
+            show_high_variance(&config, &root, &tags);
+            return Ok(());
         }
     };
 
@@ -37,17 +44,28 @@ pub async fn run() -> Result<()> {
     }
 
     // Show prompt count
-    let prompts_dir = root.join("prompts");
-    if prompts_dir.exists() {
-        if let Ok(paths) = discover_prompts(&prompts_dir) {
-            eprintln!("  Prompts: {}", paths.len().to_string().bold());
-        }
+    if let Ok(paths) = discover_project_prompts(&config, &root) {
+        eprintln!("  Prompts: {}", paths.len().to_string().bold());
     }
 
-    let _ = &config; // used for display above
+    if let Ok(missing) = repo.missing_gitignore_entries()
+        && !missing.is_empty()
+    {
+        eprintln!(
+            "  {}",
+            style::warning(&format!(
+                ".gitignore is missing {} lit entr{} — run `lit gitignore sync`",
+                missing.len(),
+                if missing.len() == 1 { "y" } else { "ies" }
+            ))
+        );
+    }
 
     eprintln!();
 
+    show_staleness(&config, &root, &tags);
+    show_high_variance(&config, &root, &tags);
+
     if !status.has_changes() {
         eprintln!("{}", "Nothing to commit (working tree clean).".dimmed());
         return Ok(());
@@ -105,14 +123,12 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
-fn show_prompts_only(root: &std::path::Path) -> Result<()> {
-    let prompts_dir = root.join("prompts");
-    if !prompts_dir.exists() {
-        eprintln!("{}", "No prompts/ directory found.".dimmed());
+fn show_prompts_only(config: &LitConfig, root: &Path) -> Result<()> {
+    let paths = discover_project_prompts(config, root)?;
+    if paths.is_empty() {
+        eprintln!("{}", format!("No prompts found in {}", config.project.prompt_dirs_display()).dimmed());
         return Ok(());
     }
-
-    let paths = discover_prompts(&prompts_dir)?;
     eprintln!("  Prompts: {}", paths.len().to_string().bold());
     for p in &paths {
         eprintln!("    {}", p.display());
@@ -120,3 +136,65 @@ fn show_prompts_only(root: &std::path::Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Report prompts whose code.lock/ output no longer matches their inputs
+fn show_staleness(config: &LitConfig, root: &Path, tags: &TagFilter) {
+    match stale_prompts(config, root, tags) {
+        Ok(Some(stale)) if stale.is_empty() => {}
+        Ok(Some(stale)) => {
+            eprintln!(
+                "{}",
+                style::warning(&format!(
+                    "code.lock is stale for {} prompt{} — run `lit regenerate`",
+                    stale.len(),
+                    if stale.len() == 1 { "" } else { "s" }
+                ))
+            );
+            for p in &stale {
+                eprintln!("{}", style::file_modified(&p.display().to_string()));
+            }
+            eprintln!();
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", style::warning(&format!("Could not check staleness: {:#}", e)));
+            eprintln!();
+        }
+    }
+}
+
+/// Report prompts whose last `lit sweep` found their output varies a lot
+/// between runs, unless they have been reworded since
+fn show_high_variance(config: &LitConfig, root: &Path, tags: &TagFilter) {
+    let Ok(Some(manifest)) = Manifest::load(&root.join(".lit")) else {
+        return;
+    };
+    let mut flagged = Vec::new();
+    for path in manifest.stability.keys() {
+        let Ok(prompt) = Prompt::from_file(path, root, config) else {
+            continue;
+        };
+        if let Some(stability) = manifest.current_stability(path, &prompt.raw)
+            && stability.is_high_variance()
+            && tags.matches(&prompt)
+        {
+            flagged.push((path, stability.churn));
+        }
+    }
+    if flagged.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{}",
+        style::warning(&format!(
+            "{} high-variance prompt{} — output churns between runs; consider tightening the wording",
+            flagged.len(),
+            if flagged.len() == 1 { "" } else { "s" }
+        ))
+    );
+    for (path, churn) in flagged {
+        eprintln!("{} {}", style::file_modified(&path.display().to_string()), format!("({:.1}% churn)", churn).dimmed());
+    }
+    eprintln!();
+}
+
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::context::dedupe_context;
+use crate::core::determinism::churn;
+use crate::core::generation_record::{estimate_cost, format_cost, format_tokens};
+use crate::core::generator::{build_request, gather_context, parse_response_in, prune_context_offline};
+use crate::core::manifest::{HIGH_VARIANCE_CHURN, Manifest, Stability, content_hash};
+use crate::core::paths::relative_to;
+use crate::core::project::{load_code, load_prompts};
+use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
+
+/// `lit sweep <prompts...>` — generate each prompt once per seed and
+/// temperature, measure how much the output churns between runs, and store
+/// the score in `.lit/manifest.json` so `lit status` can flag high-variance
+/// prompts.
+///
+/// Context comes from code.lock/, as in `lit verify-determinism`. Nothing
+/// is cached or written to code.lock/.
+pub async fn run(paths: Vec<PathBuf>, seeds: Vec<u64>, temperatures: Vec<f64>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let (prompts, dag) = load_prompts(&config, &root)?;
+
+    let mut selected = Vec::new();
+    for path in &paths {
+        let path = cwd.join(path);
+        let path = relative_to(&path, &root);
+        if !prompts.contains_key(&path) {
+            bail!("Prompt {} not found in DAG", path.display());
+        }
+        selected.push(path);
+    }
+    let selected: Vec<&PathBuf> = dag.order().iter().filter(|p| selected.contains(p)).collect();
+    if let Some(t) = temperatures.iter().find(|t| !(0.0..=2.0).contains(*t)) {
+        bail!("Invalid temperature {}. Must be between 0.0 and 2.0", t);
+    }
+    // One run per seed at each temperature; no temperatures means each
+    // prompt's own
+    let mut settings: Vec<(u64, Option<f64>)> = Vec::new();
+    for seed in &seeds {
+        if temperatures.is_empty() {
+            settings.push((*seed, None));
+        }
+        for temperature in &temperatures {
+            settings.push((*seed, Some(*temperature)));
+        }
+    }
+    if settings.len() < 2 {
+        bail!("A sweep needs at least 2 runs; pass more --seeds or --temperatures");
+    }
+    let lit_dir = root.join(".lit");
+    let Some(mut manifest) = Manifest::load(&lit_dir)? else {
+        bail!("No .lit/manifest.json yet.\nHint: Run `lit regenerate` first; sweeps use code.lock/ as context.");
+    };
+
+    eprintln!("{}", style::header("Seed sweep"));
+    eprintln!("  {:<14} {}", "Prompts:".dimmed(), selected.len().to_string().bold());
+    eprintln!(
+        "  {:<14} {}",
+        "Seeds:".dimmed(),
+        seeds.iter().map(u64::to_string).collect::<Vec<_>>().join(", ")
+    );
+    eprintln!(
+        "  {:<14} {}",
+        "Temperatures:".dimmed(),
+        if temperatures.is_empty() {
+            "(each prompt's own)".to_string()
+        } else {
+            temperatures.iter().map(f64::to_string).collect::<Vec<_>>().join(", ")
+        }
+    );
+    eprintln!();
+
+    let registry = build_registry(&config, std::io::stdin().is_terminal());
+    let existing_code = load_code(&config, &root);
+    let today = chrono::Utc::now().date_naive();
+    let mut total_in = 0;
+    let mut total_out = 0;
+    let mut total_cost = 0.0;
+    let mut high_variance = 0;
+
+    for (i, prompt_path) in selected.iter().enumerate() {
+        let prompt = &prompts[*prompt_path];
+        let (context, _) = dedupe_context(gather_context(prompt, &prompts, &existing_code));
+        let context = prune_context_offline(&config, context);
+        let base_request = build_request(&config, prompt, &context);
+
+        eprintln!(
+            "  {} {} {}",
+            "Generating".cyan(),
+            prompt_path.display().to_string().bold(),
+            style::progress(i + 1, selected.len())
+        );
+        let mut outputs: Vec<HashMap<PathBuf, String>> = Vec::new();
+        let mut failure = None;
+        for (seed, temperature) in &settings {
+            let mut request = base_request.clone();
+            request.seed = Some(*seed);
+            if let Some(temperature) = temperature {
+                request.temperature = *temperature;
+            }
+            let response = match registry.generate(request.clone()).await {
+                Ok(response) => response,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            };
+            total_in += response.tokens_in;
+            total_out += response.tokens_out;
+            total_cost += estimate_cost(
+                &response.model,
+                response.tokens_in,
+                response.tokens_out,
+                response.prompt_cache,
+                config.model_pricing(&response.model, today).as_ref(),
+            );
+            match parse_response_in(
+                &response.content,
+                &prompt.frontmatter.outputs,
+                prompt.frontmatter.module.as_deref(),
+                request.json,
+            ) {
+                Ok(files) => outputs.push(files),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if let Some(e) = failure {
+            eprintln!(
+                "    {} {}",
+                "!".yellow().bold(),
+                format!("{:#}", e).lines().next().unwrap_or("").dimmed()
+            );
+            continue;
+        }
+
+        let churn = churn(&outputs);
+        let stability = Stability {
+            prompt_hash: content_hash(&prompt.raw),
+            runs: outputs.len(),
+            churn: churn.overall,
+            files: churn.files,
+            measured_at: chrono::Utc::now(),
+        };
+        let verdict = if stability.is_high_variance() {
+            high_variance += 1;
+            "high variance".red().bold()
+        } else {
+            "stable".green().bold()
+        };
+        eprintln!("    {:.1}% churn — {}", stability.churn, verdict);
+        for (file, churn) in stability.files.iter().filter(|(_, churn)| **churn > 0.0) {
+            eprintln!("      {:<40} {:.1}%", file.display(), churn);
+        }
+        manifest.stability.insert((*prompt_path).clone(), stability);
+    }
+
+    manifest.save(&lit_dir)?;
+    eprintln!();
+    eprintln!(
+        "  {:<14} {} in / {} out, {}",
+        "Spent:".dimmed(),
+        format_tokens(total_in),
+        format_tokens(total_out),
+        style::cost(&format_cost(total_cost))
+    );
+    if high_variance > 0 {
+        eprintln!(
+            "{}",
+            style::hint(&format!(
+                "Prompts above {}% churn leave a lot to the model; tighten their wording and sweep again.",
+                HIGH_VARIANCE_CHURN
+            ))
+        );
+    }
+    Ok(())
+}
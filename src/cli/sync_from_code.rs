@@ -0,0 +1,190 @@
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::regenerate::{confirm, print_diff};
+use crate::core::cache::Cache;
+use crate::core::config::LitConfig;
+use crate::core::generator::{build_sync_request, strip_markdown_fences};
+use crate::core::header;
+use crate::core::manifest::{Manifest, content_hash};
+use crate::core::patch::PatchStore;
+use crate::core::paths::{normalize, relative_to};
+use crate::core::project::load_prompts;
+use crate::core::prompt::Prompt;
+use crate::core::session::SessionStore;
+use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
+
+/// `lit sync-from-code <file>` — fold a hand-edit of a generated file back
+/// into the prompt that owns it.
+///
+/// The edit is diffed against the file as generated (from the cache entry
+/// recorded in `.lit/manifest.json`), the model proposes an updated prompt
+/// body, and once accepted the prompt is rewritten and any patch for the
+/// file is dropped, since the prompt now carries the change.
+pub async fn run(file: PathBuf, yes: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+
+    let output = output_path(&cwd, &root, &config, &file);
+    let mut edited = std::fs::read_to_string(config.code_dir(&root).join(&output))
+        .with_context(|| format!("Failed to read code.lock/{}", output.display()))?;
+    if let Some(template) = &config.generation.file_header {
+        edited = header::strip(template, &output, &edited);
+    }
+
+    // Locate the owning prompt and the content it generated
+    let manifest = Manifest::load(&root.join(".lit"))?.context(
+        "No .lit/manifest.json found.\n\
+         Hint: Run `lit regenerate` first so lit knows which prompt owns each file.",
+    )?;
+    let entry = manifest.owner(&output).with_context(|| {
+        format!(
+            "{} is not a generated file (no entry in .lit/manifest.json)",
+            output.display()
+        )
+    })?;
+    let cache = Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone());
+    let generated = cache
+        .get(&entry.input_hash)
+        .and_then(|cached| cached.files.get(&output).cloned())
+        .with_context(|| {
+            format!(
+                "The generated version of {} is no longer in the cache.\n\
+                 Hint: Run `lit regenerate {}` to rebuild it, then save your edit with `lit patch save`.",
+                output.display(),
+                entry.prompt.display()
+            )
+        })?;
+
+    if generated == edited {
+        eprintln!("{} has no hand-edits.", output.display());
+        return Ok(());
+    }
+
+    let (prompts, dag) = load_prompts(&config, &root)?;
+    let prompt = prompts
+        .get(&entry.prompt)
+        .with_context(|| format!("Owning prompt {} no longer exists", entry.prompt.display()))?;
+
+    eprintln!("{}", style::header("Sync from code"));
+    eprintln!("  {:<8} {}", "File:".dimmed(), output.display());
+    eprintln!("  {:<8} {}", "Prompt:".dimmed(), prompt.path.display().to_string().bold());
+    eprintln!();
+    print_diff(&generated, &edited);
+
+    // A proposal for this same edit and prompt from an interrupted run can be
+    // reused instead of asking the model again
+    let sessions = SessionStore::new(&root.join(".lit"));
+    let key = SyncSession {
+        output: output.clone(),
+        prompt_hash: content_hash(&prompt.raw),
+        edited_hash: content_hash(&edited),
+        proposed: String::new(),
+        created_at: Utc::now(),
+    };
+    let earlier = sessions
+        .load::<SyncSession>(SYNC_SESSION)
+        .filter(|s| {
+            s.output == key.output
+                && s.prompt_hash == key.prompt_hash
+                && s.edited_hash == key.edited_hash
+        });
+    eprintln!();
+    let resumed = match earlier {
+        Some(earlier)
+            if confirm(
+                &format!(
+                    "Reuse the prompt update proposed {}?",
+                    earlier.created_at.format("%Y-%m-%d %H:%M UTC")
+                ),
+                true,
+            )? =>
+        {
+            Some(earlier.proposed)
+        }
+        _ => None,
+    };
+
+    let updated = match resumed {
+        Some(updated) => updated,
+        None => {
+            // Ask the model for a prompt body that produces the edit
+            let request = build_sync_request(&config, prompt, &output, &generated, &edited);
+            eprintln!("  {} {}", "Updating prompt with".cyan(), request.model.dimmed());
+            let response = build_registry(&config, std::io::stdin().is_terminal()).generate(request).await?;
+            let body = strip_markdown_fences(&response.content);
+            let updated = prompt.with_body(&format!("{}\n", body.trim_end()));
+            Prompt::parse(&updated, prompt.path.clone(), &config)
+                .context("The proposed prompt does not parse")?;
+            let session = SyncSession { proposed: updated.clone(), ..key };
+            if let Err(e) = sessions.save(SYNC_SESSION, &session) {
+                eprintln!("  {}", style::warning(&format!("Failed to save session: {:#}", e)));
+            }
+            updated
+        }
+    };
+
+    eprintln!();
+    eprintln!("{}", style::file_modified(&prompt.path.display().to_string()));
+    print_diff(&prompt.raw, &updated);
+    eprintln!();
+
+    if !yes && !confirm(&format!("Write {}?", prompt.path.display()), false)? {
+        sessions.clear(SYNC_SESSION)?;
+        eprintln!("{}", "Prompt left unchanged.".dimmed());
+        return Ok(());
+    }
+
+    std::fs::write(root.join(&prompt.path), &updated)
+        .with_context(|| format!("Failed to write {}", prompt.path.display()))?;
+    eprintln!("{}", style::success(&format!("Updated {}", prompt.path.display())));
+    sessions.clear(SYNC_SESSION)?;
+
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    if patch_store.has_patch(&output) {
+        patch_store.drop_patch(&output)?;
+        eprintln!("  Dropped the patch for {} (now carried by the prompt)", output.display());
+    }
+
+    let regenerate = dag.regeneration_set(std::slice::from_ref(&prompt.path));
+    eprintln!();
+    eprintln!(
+        "{}",
+        style::hint(&format!(
+            "Run `lit regenerate {}` to check the prompt reproduces your edit ({} prompt(s) affected).",
+            prompt.path.display(),
+            regenerate.len()
+        ))
+    );
+    Ok(())
+}
+
+/// Resolve a file argument to a path relative to the code directory
+fn output_path(cwd: &Path, root: &Path, config: &LitConfig, file: &Path) -> PathBuf {
+    let full = if file.is_absolute() { file.to_path_buf() } else { cwd.join(file) };
+    match full.strip_prefix(config.code_dir(root)) {
+        Ok(rel) => normalize(rel),
+        Err(_) => relative_to(file, &config.project.code_dir),
+    }
+}
+
+/// Session name for `lit sync-from-code`
+const SYNC_SESSION: &str = "sync-from-code";
+
+/// A proposed prompt update awaiting confirmation, keyed by the file, the
+/// prompt and the edit it was proposed for
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncSession {
+    output: PathBuf,
+    prompt_hash: String,
+    edited_hash: String,
+    proposed: String,
+    created_at: DateTime<Utc>,
+}
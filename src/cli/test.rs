@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::core::assertions::run_tests;
+use crate::core::config::LitConfig;
+use crate::core::paths::relative_to;
+use crate::core::project::load_prompts;
+use crate::core::prompt::TagFilter;
+use crate::core::style;
+
+/// `lit test [prompts...]` — check the code in code.lock/ against each
+/// prompt's tests (frontmatter `tests:` and `<name>.test.yaml`). Fails if
+/// any test fails, so prompt regressions can be caught in CI.
+pub async fn run(paths: Vec<PathBuf>, tags: TagFilter) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let (prompts, dag) = load_prompts(&config, &root)?;
+
+    let mut selected = Vec::new();
+    for path in &paths {
+        let path = cwd.join(path);
+        let path = relative_to(&path, &root);
+        if !prompts.contains_key(&path) {
+            bail!("Prompt {} not found in DAG", path.display());
+        }
+        selected.push(path);
+    }
+    let tested = dag
+        .order()
+        .iter()
+        .filter(|path| selected.is_empty() || selected.contains(path))
+        .map(|path| &prompts[path])
+        .filter(|prompt| tags.matches(prompt));
+    let outcomes = run_tests(&root, tested, &config.code_dir(&root))?;
+
+    eprintln!("{}", style::header("Test"));
+    if outcomes.is_empty() {
+        eprintln!("  {}", "(no prompts have tests)".dimmed());
+        eprintln!(
+            "{}",
+            style::hint("Add `tests:` to a prompt's frontmatter, or a <name>.test.yaml next to it.")
+        );
+        return Ok(());
+    }
+    let mut current = None;
+    let mut prompt_count = 0;
+    for outcome in &outcomes {
+        if current != Some(&outcome.prompt) {
+            eprintln!("  {}", outcome.prompt.display().to_string().bold());
+            current = Some(&outcome.prompt);
+            prompt_count += 1;
+        }
+        match &outcome.failure {
+            None => eprintln!("    {} {}", "✓".green().bold(), outcome.description),
+            Some(reason) => eprintln!(
+                "    {} {} {}",
+                "✗".red().bold(),
+                outcome.description,
+                format!("({})", reason).dimmed()
+            ),
+        }
+    }
+
+    eprintln!();
+    let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+    if failed > 0 {
+        bail!("{} of {} test(s) failed in {} prompt(s)", failed, outcomes.len(), prompt_count);
+    }
+    eprintln!("{}", style::success(&format!("{} test(s) passed in {} prompt(s)", outcomes.len(), prompt_count)));
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::core::config::LitConfig;
+use crate::core::context::dedupe_context;
+use crate::core::determinism::{RunComparison, compare_runs, sample_evenly};
+use crate::core::generation_record::{build_record, format_cost, format_tokens, report_budget, write_record};
+use crate::core::generator::{
+    PipelineResult, build_request, gather_context, parse_response_in, prune_context_offline,
+};
+use crate::core::paths::relative_to;
+use crate::core::project::{load_code, load_prompts};
+use crate::core::prompt::TagFilter;
+use crate::core::style;
+use crate::providers::LlmProvider;
+use crate::providers::setup::build_registry;
+
+/// `lit verify-determinism` — generate prompts several times with the
+/// configured seed at temperature 0 and report which ones produce
+/// different code from run to run.
+///
+/// Context comes from code.lock/, as in `lit cost --estimate`. Nothing is
+/// cached or written to code.lock/; every run is a fresh LLM call, and the
+/// calls are kept in a generation record tagged `verify-determinism` so
+/// budgets count them while `lit cost` totals leave them out.
+pub async fn run(paths: Vec<PathBuf>, runs: usize, sample: Option<usize>, tags: TagFilter) -> Result<()> {
+    if runs < 2 {
+        anyhow::bail!("--runs must be at least 2 to compare outputs");
+    }
+    let cwd = std::env::current_dir()?;
+    let (config, root) = LitConfig::find_and_load(&cwd)?;
+    let (prompts, dag) = load_prompts(&config, &root)?;
+
+    let mut selected: Vec<PathBuf> = Vec::new();
+    for path in &paths {
+        let relative = relative_to(path, &root);
+        if !prompts.contains_key(&relative) {
+            anyhow::bail!("Prompt not found: {}", relative.display());
+        }
+        selected.push(relative);
+    }
+    let mut selected: Vec<PathBuf> = dag
+        .order()
+        .iter()
+        .filter(|p| (selected.is_empty() || selected.contains(p)) && tags.matches(&prompts[*p]))
+        .cloned()
+        .collect();
+    if let Some(n) = sample {
+        selected = sample_evenly(&selected, n);
+    }
+    if selected.is_empty() {
+        eprintln!("{}", "No prompts selected.".dimmed());
+        return Ok(());
+    }
+
+    eprintln!("{}", style::header("Determinism check"));
+    eprintln!("  {:<12} {}", "Prompts:".dimmed(), selected.len().to_string().bold());
+    eprintln!("  {:<12} {} per prompt, temperature 0", "Runs:".dimmed(), runs);
+    eprintln!(
+        "  {:<12} {}",
+        "Seed:".dimmed(),
+        config.model.seed.map(|s| s.to_string()).unwrap_or_else(|| "(none)".to_string())
+    );
+    eprintln!();
+
+    let registry = build_registry(&config, std::io::stdin().is_terminal());
+    let existing_code = load_code(&config, &root);
+    let mut calls = Vec::new();
+    let mut results: Vec<(PathBuf, Result<RunComparison>)> = Vec::new();
+
+    for (i, prompt_path) in selected.iter().enumerate() {
+        let prompt = &prompts[prompt_path];
+        let (context, _) = dedupe_context(gather_context(prompt, &prompts, &existing_code));
+        let context = prune_context_offline(&config, context);
+        let mut request = build_request(&config, prompt, &context);
+        request.temperature = 0.0;
+
+        eprintln!(
+            "  {} {} {}",
+            "Generating".cyan(),
+            prompt_path.display().to_string().bold(),
+            style::progress(i + 1, selected.len())
+        );
+        let mut outputs: Vec<HashMap<PathBuf, String>> = Vec::new();
+        let mut failure = None;
+        for _ in 0..runs {
+            let started = Instant::now();
+            let response = match registry.generate(request.clone()).await {
+                Ok(response) => response,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            };
+            let content = response.content.clone();
+            calls.push((prompt_path.clone(), response, started.elapsed().as_millis() as u64));
+            match parse_response_in(
+                &content,
+                &prompt.frontmatter.outputs,
+                prompt.frontmatter.module.as_deref(),
+                request.json,
+            ) {
+                Ok(files) => outputs.push(files),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        let result = match failure {
+            Some(e) => Err(e),
+            None => Ok(compare_runs(&outputs)),
+        };
+        results.push((prompt_path.clone(), result));
+    }
+
+    let (mut record, total_cost) = build_record(&config, &PipelineResult::of_calls(&calls), 0, 0, 0);
+    record.command = Some("verify-determinism".to_string());
+    if !calls.is_empty() {
+        write_record(&root, &record);
+    }
+
+    eprintln!();
+    eprintln!("{}", style::section("Results:"));
+    let mut deterministic = 0;
+    for (prompt_path, result) in &results {
+        match result {
+            Ok(comparison) if comparison.is_deterministic() => {
+                deterministic += 1;
+                eprintln!("  {} {}", "✓".green().bold(), prompt_path.display());
+            }
+            Ok(comparison) => {
+                eprintln!(
+                    "  {} {} {}",
+                    "✗".red().bold(),
+                    prompt_path.display().to_string().bold(),
+                    format!(
+                        "({} of {} run(s) differ from the first, up to {} line(s))",
+                        comparison.differing_runs,
+                        runs - 1,
+                        comparison.max_changed_lines
+                    )
+                    .dimmed()
+                );
+                for (file, lines) in &comparison.files {
+                    eprintln!("      {:<40} {} line(s)", file.display(), lines);
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "  {} {} {}",
+                    "!".yellow().bold(),
+                    prompt_path.display(),
+                    format!("{:#}", e).lines().next().unwrap_or("").dimmed()
+                );
+            }
+        }
+    }
+
+    eprintln!();
+    eprintln!(
+        "  {} of {} prompt(s) deterministic",
+        deterministic.to_string().bold(),
+        results.len()
+    );
+    eprintln!(
+        "  {:<12} {} in / {} out, {}",
+        "Spent:".dimmed(),
+        format_tokens(record.summary.total_tokens_in),
+        format_tokens(record.summary.total_tokens_out),
+        style::cost(&format_cost(total_cost))
+    );
+    if deterministic < results.len() {
+        eprintln!(
+            "{}",
+            style::hint("Non-deterministic prompts may change on every `lit regenerate --no-cache`; tighten their wording or pin a seed.")
+        );
+    }
+    report_budget(&config, &root, Some(total_cost), true).await;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cli::{Commands, run_command};
+use crate::core::style;
+use crate::core::workspace::{WORKSPACE_FILE, Workspace};
+
+/// Run a command once in every workspace member, in the order listed in
+/// lit-workspace.toml.
+///
+/// Members are isolated: each run sees only that member's lit.toml,
+/// prompts and code.lock/. A failing member doesn't stop the others; the
+/// command fails at the end if any member did.
+pub async fn run(workspace: Workspace, command: Commands, wait: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    eprintln!(
+        "{}",
+        style::header(&format!(
+            "Workspace ({} member(s), {})",
+            workspace.members.len(),
+            WORKSPACE_FILE
+        ))
+    );
+
+    let mut failed = Vec::new();
+    for (member, dir) in workspace.members.iter().zip(workspace.member_dirs()) {
+        eprintln!();
+        eprintln!("{} {}", "──".dimmed(), member.display().to_string().bold().cyan());
+        std::env::set_current_dir(&dir)?;
+        let result = run_command(command.clone(), wait).await;
+        std::env::set_current_dir(&cwd)?;
+        if let Err(e) = result {
+            eprintln!("{}", style::error(&format!("{:#}", e)));
+            failed.push(member.display().to_string());
+        }
+    }
+
+    eprintln!();
+    if !failed.is_empty() {
+        anyhow::bail!("Failed in {} member(s): {}", failed.len(), failed.join(", "));
+    }
+    eprintln!(
+        "{}",
+        style::success(&format!("Done in {} member(s)", workspace.members.len()))
+    );
+    Ok(())
+}
@@ -0,0 +1,307 @@
+//! Assertions about generated code, checked by `lit test`.
+//!
+//! A prompt declares them under `tests:` in its frontmatter, or in a sibling
+//! `<name>.test.yaml` next to `<name>.prompt.md`. The sibling file keeps the
+//! prompt's input hash (and so its cache entry) unchanged when tests are
+//! edited; frontmatter tests are part of the prompt like everything else.
+//!
+//! ```yaml
+//! - defines: User
+//! - imports: sqlalchemy
+//! - file: tests/test_user.py
+//!   count: { pattern: "def test_", min: 3 }
+//! - not_contains: TODO
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::core::prompt::Prompt;
+
+/// Suffix of a prompt's sibling test file, replacing `.prompt.md`
+pub const TEST_FILE_SUFFIX: &str = ".test.yaml";
+
+/// One assertion about a generated file
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputTest {
+    /// Output file to check (relative to code.lock/); may be left out when
+    /// the prompt has a single output
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    #[serde(flatten)]
+    pub check: Check,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The file contains this text
+    Contains(String),
+    /// The file does not contain this text
+    NotContains(String),
+    /// A regular expression matches somewhere in the file
+    Matches(String),
+    /// A class, struct, enum, trait, interface, type or function of this name
+    Defines(String),
+    /// An import, `use`, `require` or `#include` line mentions this module
+    Imports(String),
+    /// A regular expression matches between `min` and `max` times
+    Count {
+        pattern: String,
+        #[serde(default)]
+        min: Option<usize>,
+        #[serde(default)]
+        max: Option<usize>,
+    },
+}
+
+impl Check {
+    /// The regular expression behind the check
+    fn regex(&self) -> Result<Regex> {
+        let pattern = match self {
+            Check::Contains(text) | Check::NotContains(text) => regex::escape(text),
+            Check::Matches(pattern) | Check::Count { pattern, .. } => pattern.clone(),
+            Check::Defines(name) => format!(
+                r"\b(?:class|struct|enum|trait|interface|type|def|fn|function)\s+{}\b",
+                regex::escape(name)
+            ),
+            Check::Imports(module) => format!(
+                r#"(?m)^\s*(?:import|from|use|require|#include|export)\b.*\b{}\b|require\(\s*['"]{}['"]"#,
+                regex::escape(module),
+                regex::escape(module)
+            ),
+        };
+        Regex::new(&pattern).with_context(|| format!("Invalid pattern '{}'", pattern))
+    }
+}
+
+impl OutputTest {
+    /// Reject patterns that don't compile, so mistakes show up when the
+    /// prompt is parsed rather than when it is tested
+    pub fn validate(&self) -> Result<()> {
+        self.check.regex()?;
+        if let Check::Count { min: Some(min), max: Some(max), .. } = self.check
+            && min > max
+        {
+            bail!("count has min {} above max {}", min, max);
+        }
+        Ok(())
+    }
+
+    /// The output file this test checks
+    pub fn target(&self, prompt: &Prompt) -> Result<PathBuf> {
+        match (&self.file, prompt.frontmatter.outputs.as_slice()) {
+            (Some(file), _) => Ok(file.clone()),
+            (None, [output]) => Ok(output.clone()),
+            (None, _) => bail!(
+                "A test for {} names no file, and the prompt has {} outputs.\n\
+                 Hint: Add `file:` to the test.",
+                prompt.path.display(),
+                prompt.frontmatter.outputs.len()
+            ),
+        }
+    }
+
+    /// `defines User`, `has at least 3 matches of /def test_/`, ...
+    pub fn describe(&self) -> String {
+        match &self.check {
+            Check::Contains(text) => format!("contains {:?}", text),
+            Check::NotContains(text) => format!("does not contain {:?}", text),
+            Check::Matches(pattern) => format!("matches /{}/", pattern),
+            Check::Defines(name) => format!("defines {}", name),
+            Check::Imports(module) => format!("imports {}", module),
+            Check::Count { pattern, min, max } => match (min, max) {
+                (Some(min), Some(max)) => format!("has {}–{} matches of /{}/", min, max, pattern),
+                (Some(min), None) => format!("has at least {} matches of /{}/", min, pattern),
+                (None, Some(max)) => format!("has at most {} matches of /{}/", max, pattern),
+                (None, None) => format!("mentions /{}/", pattern),
+            },
+        }
+    }
+
+    /// Check `content`, explaining a failure
+    pub fn run(&self, content: &str) -> std::result::Result<(), String> {
+        let regex = self.check.regex().map_err(|e| format!("{:#}", e))?;
+        match &self.check {
+            Check::NotContains(_) => match regex.find(content) {
+                Some(found) => Err(format!("found on line {}", content[..found.start()].matches('\n').count() + 1)),
+                None => Ok(()),
+            },
+            Check::Count { min, max, .. } => {
+                let found = regex.find_iter(content).count();
+                if min.is_some_and(|min| found < min) || max.is_some_and(|max| found > max) {
+                    return Err(format!("found {}", found));
+                }
+                Ok(())
+            }
+            _ if regex.is_match(content) => Ok(()),
+            _ => Err("not found".to_string()),
+        }
+    }
+}
+
+/// Where a prompt's sibling test file lives: `prompts/models/user.test.yaml`
+/// for `prompts/models/user.prompt.md`
+pub fn test_file_path(prompt_path: &Path) -> PathBuf {
+    let name = prompt_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let stem = name.strip_suffix(".prompt.md").unwrap_or(name);
+    prompt_path.with_file_name(format!("{}{}", stem, TEST_FILE_SUFFIX))
+}
+
+/// A prompt's tests: its frontmatter `tests:`, then its sibling test file's
+pub fn load_tests(root: &Path, prompt: &Prompt) -> Result<Vec<OutputTest>> {
+    let mut tests = prompt.frontmatter.tests.clone();
+    let path = root.join(test_file_path(&prompt.path));
+    if path.exists() {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let sibling: Vec<OutputTest> = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        for test in &sibling {
+            test.validate().with_context(|| format!("In {}", path.display()))?;
+        }
+        tests.extend(sibling);
+    }
+    Ok(tests)
+}
+
+/// One test run against a directory of generated code
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub prompt: PathBuf,
+    /// `src/models/user.py defines User`
+    pub description: String,
+    /// Why the test failed, if it did
+    pub failure: Option<String>,
+}
+
+/// Run the tests of `prompts` against the generated code in `code_dir`
+/// (code.lock/, or any directory laid out like it)
+pub fn run_tests<'a>(
+    root: &Path,
+    prompts: impl IntoIterator<Item = &'a Prompt>,
+    code_dir: &Path,
+) -> Result<Vec<TestOutcome>> {
+    let mut outcomes = Vec::new();
+    for prompt in prompts {
+        for test in load_tests(root, prompt)? {
+            let file = test.target(prompt)?;
+            let failure = match std::fs::read_to_string(code_dir.join(&file)) {
+                Ok(content) => test.run(&content).err(),
+                Err(_) => Some("file missing".to_string()),
+            };
+            outcomes.push(TestOutcome {
+                prompt: prompt.path.clone(),
+                description: format!("{} {}", file.display(), test.describe()),
+                failure,
+            });
+        }
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_PY: &str = "from sqlalchemy import Column\nimport os\n\nclass User(Base):\n    id = Column()\n";
+
+    fn parse(yaml: &str) -> Vec<OutputTest> {
+        let tests: Vec<OutputTest> = serde_yaml::from_str(yaml).unwrap();
+        tests.iter().for_each(|t| t.validate().unwrap());
+        tests
+    }
+
+    #[test]
+    fn test_checks() {
+        let tests = parse(
+            "- defines: User\n\
+             - imports: sqlalchemy\n\
+             - contains: \"id = Column\"\n\
+             - not_contains: TODO\n\
+             - file: src/models/user.py\n  count: { pattern: \"Column\", min: 2, max: 3 }\n",
+        );
+        assert_eq!(tests[4].file.as_deref(), Some(Path::new("src/models/user.py")));
+        for test in &tests {
+            assert_eq!(test.run(USER_PY), Ok(()), "{}", test.describe());
+        }
+
+        let failing = parse(
+            "- defines: Item\n\
+             - imports: pydantic\n\
+             - not_contains: \"import os\"\n\
+             - count: { pattern: \"def test_\", min: 3 }\n",
+        );
+        assert_eq!(failing[0].run(USER_PY), Err("not found".to_string()));
+        assert!(failing[1].run(USER_PY).is_err());
+        assert_eq!(failing[2].run(USER_PY), Err("found on line 2".to_string()));
+        assert_eq!(failing[3].run(USER_PY), Err("found 0".to_string()));
+        assert_eq!(failing[3].describe(), "has at least 3 matches of /def test_/");
+
+        // A module name inside another doesn't count as importing it
+        assert!(parse("- imports: sql\n")[0].run(USER_PY).is_err());
+        assert_eq!(parse("- imports: express\n")[0].run("const app = require('express');\n"), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_tests() {
+        let bad: Vec<OutputTest> = serde_yaml::from_str("- matches: \"(unclosed\"\n").unwrap();
+        assert!(bad[0].validate().is_err());
+        let bad: Vec<OutputTest> = serde_yaml::from_str("- count: { pattern: x, min: 3, max: 1 }\n").unwrap();
+        assert!(bad[0].validate().is_err());
+        assert!(serde_yaml::from_str::<Vec<OutputTest>>("- looks_like: User\n").is_err());
+    }
+
+    #[test]
+    fn test_run_tests_against_code_dir() {
+        let config = crate::core::config::LitConfig::from_str(
+            "[project]\nname = \"test\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n\
+             [language]\ndefault = \"python\"\nversion = \"3.12\"\n\n\
+             [model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5\"\ntemperature = 0.0\n",
+        )
+        .unwrap();
+        let user = Prompt::parse(
+            "---\noutputs:\n  - src/user.py\ntests:\n  - defines: User\n  - not_contains: TODO\n---\n\n# User\n",
+            PathBuf::from("prompts/user.prompt.md"),
+            &config,
+        )
+        .unwrap();
+        let item = Prompt::parse(
+            "---\noutputs:\n  - src/item.py\n---\n\n# Item\n",
+            PathBuf::from("prompts/item.prompt.md"),
+            &config,
+        )
+        .unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("prompts")).unwrap();
+        std::fs::write(root.path().join("prompts/item.test.yaml"), "- defines: Item\n").unwrap();
+        let code_dir = root.path().join(".lit/eval/a");
+        std::fs::create_dir_all(code_dir.join("src")).unwrap();
+        std::fs::write(code_dir.join("src/user.py"), "class User:\n    pass  # TODO\n").unwrap();
+
+        let outcomes = run_tests(root.path(), [&user, &item], &code_dir).unwrap();
+        let summary: Vec<(&Path, &str, Option<&str>)> = outcomes
+            .iter()
+            .map(|o| (o.prompt.as_path(), o.description.as_str(), o.failure.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Path::new("prompts/user.prompt.md"), "src/user.py defines User", None),
+                (Path::new("prompts/user.prompt.md"), "src/user.py does not contain \"TODO\"", Some("found on line 2")),
+                (Path::new("prompts/item.prompt.md"), "src/item.py defines Item", Some("file missing")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_test_file_path() {
+        assert_eq!(
+            test_file_path(Path::new("prompts/models/user.prompt.md")),
+            PathBuf::from("prompts/models/user.test.yaml")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Crash-safe file writes.
+//!
+//! [`write`] puts the new content in a temporary file next to the target and
+//! renames it over the target, so a crash or full disk leaves either the old
+//! file or the new one — never a truncated mix of both.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Numbers the temporary files of one process
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Replace `path` with `contents` atomically. An existing file's permissions
+/// are kept, as `std::fs::write` would.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let tmp = temp_path(path);
+    let result = write_temp(&tmp, path, contents.as_ref()).and_then(|()| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn write_temp(tmp: &Path, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(tmp)?;
+    file.write_all(contents)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()
+}
+
+/// `dir/.name.tmp-<pid>-<n>`: hidden, in the same directory (so the rename
+/// stays on one filesystem), and distinct per process and per write, so
+/// concurrent writes to one target don't share a temp file
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let n = NEXT_TEMP.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.tmp-{}-{}", name, std::process::id(), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        write(&path, "first").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        write(&path, b"second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+
+        // Only the target is left behind
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, vec!["entry.json"]);
+
+        // A write that can't happen leaves no temp file either
+        assert!(write(&dir.path().join("missing/entry.json"), "x").is_err());
+        assert!(!dir.path().join("missing").exists());
+    }
+
+    #[test]
+    fn test_concurrent_writes_to_one_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        assert_ne!(temp_path(&path), temp_path(&path));
+
+        let contents: Vec<String> = (0..8).map(|i| format!("{{\"writer\": {}}}", i).repeat(1000)).collect();
+        std::thread::scope(|scope| {
+            for content in &contents {
+                let path = &path;
+                scope.spawn(move || write(path, content).unwrap());
+            }
+        });
+        // One writer's content, whole
+        assert!(contents.contains(&std::fs::read_to_string(&path).unwrap()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.sh");
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        write(&path, "#!/bin/sh\necho hi\n").unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+    }
+}
@@ -2,9 +2,17 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use crate::core::atomic;
+use crate::core::config::{CacheConfig, CacheFormat};
+use crate::core::error::LitError;
+use crate::core::generation_record::GenerationRecord;
+use crate::core::generator::PARSE_VERSION;
+use crate::core::manifest::{Manifest, content_hash};
+
 /// Cached generation output for a single prompt
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedGeneration {
@@ -12,26 +20,52 @@ pub struct CachedGeneration {
     pub files: HashMap<PathBuf, String>,
     pub tokens_in: u64,
     pub tokens_out: u64,
+    /// When this entry was generated (None for entries written by older versions)
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Model that produced this entry (None for entries written by older versions)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// [`PARSE_VERSION`] of the parser that split the response into `files`
+    /// (0 for entries written by older versions)
+    #[serde(default)]
+    pub parse_version: u32,
 }
 
+/// zstd level for binary cache entries (the library default)
+const ZSTD_LEVEL: i32 = 3;
+
 /// Input-hash cache for skipping unchanged prompt generations.
 ///
-/// Cache entries are stored as JSON files in `.lit/cache/<hash>.json`.
-/// The cache is local-only (gitignored) — an optimization, not required for correctness.
+/// Cache entries are stored in `.lit/cache/` as `<hash>.json`, or as
+/// zstd-compressed MessagePack `<hash>.bin` with `[cache] format = "binary"`.
+/// Entries in the other format are still read, and rewritten in the
+/// configured one. The cache is local-only (gitignored) — an optimization,
+/// not required for correctness.
 pub struct Cache {
     cache_dir: PathBuf,
+    config: CacheConfig,
 }
 
 impl Cache {
     /// Create a new cache backed by the given directory.
     pub fn new(cache_dir: PathBuf) -> Self {
-        Self { cache_dir }
+        Self {
+            cache_dir,
+            config: CacheConfig::default(),
+        }
+    }
+
+    /// Apply the `[cache]` settings from lit.toml (expiry policy and format).
+    pub fn with_config(mut self, config: CacheConfig) -> Self {
+        self.config = config;
+        self
     }
 
     /// Ensure the cache directory exists.
     pub fn init(&self) -> Result<()> {
         std::fs::create_dir_all(&self.cache_dir)
-            .with_context(|| format!("Failed to create cache dir: {}", self.cache_dir.display()))
+            .with_context(|| LitError::cache("create cache dir", &self.cache_dir))
     }
 
     /// Compute an input hash for a prompt.
@@ -42,8 +76,13 @@ impl Cache {
     /// - The model config (model name, temperature, seed)
     /// - The language and framework
     /// - The system prompt version (so parser changes invalidate cache)
+    /// - Any project-level system prompt inputs (e.g. a custom template),
+    ///   as labeled `(name, content)` pairs
     ///
     /// This means if ANY upstream prompt changes, the hash cascades.
+    /// `system_inputs` is only mixed in when non-empty, so projects using the
+    /// built-in system prompt keep their existing cache entries.
+    #[allow(clippy::too_many_arguments)]
     pub fn compute_input_hash(
         prompt_content: &str,
         import_hashes: &[(&Path, &str)], // (import_path, import's input_hash)
@@ -52,6 +91,7 @@ impl Cache {
         seed: Option<u64>,
         language: &str,
         framework: Option<&str>,
+        system_inputs: &[(&str, &str)], // (label, content)
     ) -> String {
         let mut hasher = Sha256::new();
 
@@ -89,32 +129,150 @@ impl Cache {
             hasher.update(b"\n");
         }
 
+        if !system_inputs.is_empty() {
+            hasher.update(b"---system---\n");
+            let mut sorted_inputs: Vec<_> = system_inputs.to_vec();
+            sorted_inputs.sort_by_key(|(label, _)| *label);
+            for (label, content) in &sorted_inputs {
+                // Length-prefix the content so adjacent inputs can't collide
+                hasher.update(format!("{}:{}\n", label, content.len()).as_bytes());
+                hasher.update(content.as_bytes());
+                hasher.update(b"\n");
+            }
+        }
+
         format!("{:x}", hasher.finalize())
     }
 
     /// Look up a cached generation by input hash.
+    ///
+    /// An entry stored in the other format is migrated to the configured one.
     pub fn get(&self, input_hash: &str) -> Option<CachedGeneration> {
-        let path = self.cache_dir.join(format!("{}.json", input_hash));
-        let content = std::fs::read_to_string(&path).ok()?;
-        serde_json::from_str(&content).ok()
+        let format = self.config.format;
+        if let Some(entry) = self.read(input_hash, format) {
+            tracing::debug!(input_hash, "cache hit");
+            return Some(entry);
+        }
+
+        let other = match format {
+            CacheFormat::Json => CacheFormat::Binary,
+            CacheFormat::Binary => CacheFormat::Json,
+        };
+        let Some(entry) = self.read(input_hash, other) else {
+            tracing::debug!(input_hash, "cache miss");
+            return None;
+        };
+        if self.put(&entry).is_ok() {
+            let _ = std::fs::remove_file(self.entry_path(input_hash, other));
+        }
+        tracing::debug!(input_hash, from = ?other, to = ?format, "cache hit, migrated to the configured format");
+        Some(entry)
+    }
+
+    fn entry_path(&self, input_hash: &str, format: CacheFormat) -> PathBuf {
+        let ext = match format {
+            CacheFormat::Json => "json",
+            CacheFormat::Binary => "bin",
+        };
+        self.cache_dir.join(format!("{}.{}", input_hash, ext))
+    }
+
+    /// Look up a cached generation without migrating its format, for
+    /// read-only views such as `lit debug cache`
+    pub fn peek(&self, input_hash: &str) -> Option<CachedGeneration> {
+        self.read(input_hash, CacheFormat::Json)
+            .or_else(|| self.read(input_hash, CacheFormat::Binary))
+    }
+
+    /// The file holding an input hash's entry, in whichever format it is stored
+    pub fn entry_file(&self, input_hash: &str) -> Option<PathBuf> {
+        [self.config.format, CacheFormat::Json, CacheFormat::Binary]
+            .into_iter()
+            .map(|format| self.entry_path(input_hash, format))
+            .find(|path| path.is_file())
+    }
+
+    fn read(&self, input_hash: &str, format: CacheFormat) -> Option<CachedGeneration> {
+        let bytes = std::fs::read(self.entry_path(input_hash, format)).ok()?;
+        match format {
+            CacheFormat::Json => serde_json::from_slice(&bytes).ok(),
+            CacheFormat::Binary => {
+                let decoded = zstd::decode_all(bytes.as_slice()).ok()?;
+                rmp_serde::from_slice(&decoded).ok()
+            }
+        }
+    }
+
+    /// Check whether a cache entry is stale under the expiry policy.
+    ///
+    /// Returns a human-readable reason if the entry should be regenerated,
+    /// or None if it can be served from cache. Entries without a
+    /// `created_at` timestamp are considered stale when `expire_before` is set,
+    /// since their age is unknown.
+    pub fn stale_reason(&self, entry: &CachedGeneration) -> Option<String> {
+        if let Some(model) = entry.model.as_deref()
+            && let Some(deprecated) = self
+                .config
+                .deprecated_models
+                .iter()
+                .find(|d| model.starts_with(d.as_str()))
+        {
+            return Some(format!(
+                "generated by deprecated model {} (matches '{}')",
+                model, deprecated
+            ));
+        }
+
+        if let Some(cutoff) = self.config.expire_before {
+            match entry.created_at {
+                Some(created) if created.date_naive() >= cutoff => {}
+                Some(created) => {
+                    return Some(format!(
+                        "generated on {}, before cutoff {}",
+                        created.format("%Y-%m-%d"),
+                        cutoff
+                    ));
+                }
+                None => {
+                    return Some(format!(
+                        "no creation date recorded, cutoff is {}",
+                        cutoff
+                    ));
+                }
+            }
+        }
+
+        None
     }
 
     /// Store a generation result in the cache.
     pub fn put(&self, entry: &CachedGeneration) -> Result<()> {
-        let path = self.cache_dir.join(format!("{}.json", entry.input_hash));
-        let content = serde_json::to_string_pretty(entry)
-            .context("Failed to serialize cache entry")?;
-        std::fs::write(&path, content)
-            .with_context(|| format!("Failed to write cache entry: {}", path.display()))
+        let path = self.entry_path(&entry.input_hash, self.config.format);
+        let content = match self.config.format {
+            CacheFormat::Json => serde_json::to_vec_pretty(entry)
+                .context("Failed to serialize cache entry")?,
+            CacheFormat::Binary => {
+                let packed =
+                    rmp_serde::to_vec_named(entry).context("Failed to serialize cache entry")?;
+                zstd::encode_all(packed.as_slice(), ZSTD_LEVEL)
+                    .context("Failed to compress cache entry")?
+            }
+        };
+        atomic::write(&path, content)
+            .with_context(|| LitError::cache("write cache entry", &path))?;
+        tracing::debug!(input_hash = %entry.input_hash, files = entry.files.len(), "cache entry written");
+        Ok(())
     }
 
     /// Remove a cache entry.
     #[allow(dead_code)]
     pub fn remove(&self, input_hash: &str) -> Result<()> {
-        let path = self.cache_dir.join(format!("{}.json", input_hash));
-        if path.exists() {
-            std::fs::remove_file(&path)
-                .with_context(|| format!("Failed to remove cache entry: {}", path.display()))?;
+        for format in [CacheFormat::Json, CacheFormat::Binary] {
+            let path = self.entry_path(input_hash, format);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| LitError::cache("remove cache entry", &path))?;
+            }
         }
         Ok(())
     }
@@ -124,11 +282,76 @@ impl Cache {
     pub fn clear(&self) -> Result<()> {
         if self.cache_dir.exists() {
             std::fs::remove_dir_all(&self.cache_dir)
-                .with_context(|| format!("Failed to clear cache: {}", self.cache_dir.display()))?;
+                .with_context(|| LitError::cache("clear cache", &self.cache_dir))?;
             std::fs::create_dir_all(&self.cache_dir)?;
         }
         Ok(())
     }
+
+    /// Rebuild cache entries from code.lock/ for the input hashes recorded
+    /// in the manifest, e.g. in a fresh clone, so unchanged prompts are
+    /// cache hits instead of LLM calls.
+    ///
+    /// `code` is code.lock/'s content and `originals` the generated content
+    /// of patched files. An input hash is only cached when every one of its
+    /// outputs is still exactly as the manifest recorded it. Token counts
+    /// and model come from the generation records when they mention the
+    /// hash. Returns the number of entries written.
+    pub fn warm_from_manifest(
+        &self,
+        manifest: &Manifest,
+        code: &HashMap<PathBuf, String>,
+        originals: &HashMap<PathBuf, String>,
+        records: &[GenerationRecord],
+    ) -> Result<usize> {
+        let mut groups: HashMap<&str, Option<HashMap<PathBuf, String>>> = HashMap::new();
+        for (path, entry) in &manifest.outputs {
+            let content = code
+                .get(path)
+                .filter(|content| content_hash(content) == entry.content_hash)
+                .and_then(|content| match entry.patch {
+                    Some(_) => originals.get(path),
+                    None => Some(content),
+                });
+            let files = groups.entry(&entry.input_hash).or_insert_with(|| Some(HashMap::new()));
+            match (files.as_mut(), content) {
+                (Some(files), Some(content)) => {
+                    files.insert(path.clone(), content.clone());
+                }
+                _ => *files = None,
+            }
+        }
+
+        self.init()?;
+        let mut written = 0;
+        for (input_hash, files) in groups {
+            let Some(files) = files else {
+                continue;
+            };
+            if self.get(input_hash).is_some() {
+                continue;
+            }
+            // Records are newest first
+            let record = records.iter().find_map(|r| {
+                r.prompts
+                    .iter()
+                    .find(|p| p.input_hash == input_hash && !p.from_cache)
+                    .map(|p| (r.timestamp, p))
+            });
+            self.put(&CachedGeneration {
+                input_hash: input_hash.to_string(),
+                files,
+                tokens_in: record.map_or(0, |(_, p)| p.tokens_in),
+                tokens_out: record.map_or(0, |(_, p)| p.tokens_out),
+                created_at: record.map(|(timestamp, _)| timestamp),
+                model: record.map(|(_, p)| p.model.clone()),
+                // Taken from code.lock/ as-is; there is no response to re-parse
+                parse_version: PARSE_VERSION,
+            })?;
+            written += 1;
+        }
+        Ok(written)
+    }
 }
 
 #[cfg(test)]
@@ -146,6 +369,7 @@ mod tests {
             Some(42),
             "python",
             Some("fastapi"),
+            &[],
         );
         let h2 = Cache::compute_input_hash(
             "prompt content",
@@ -155,6 +379,7 @@ mod tests {
             Some(42),
             "python",
             Some("fastapi"),
+            &[],
         );
         assert_eq!(h1, h2);
     }
@@ -169,6 +394,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         let h2 = Cache::compute_input_hash(
             "prompt v2",
@@ -178,6 +404,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         assert_ne!(h1, h2);
     }
@@ -192,6 +419,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         let h2 = Cache::compute_input_hash(
             "prompt",
@@ -201,6 +429,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         assert_ne!(h1, h2);
     }
@@ -215,6 +444,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         let h2 = Cache::compute_input_hash(
             "prompt",
@@ -224,24 +454,40 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         assert_ne!(h1, h2);
     }
 
     #[test]
     fn test_changed_temperature_different_hash() {
-        let h1 = Cache::compute_input_hash("p", &[], "m", 0.0, None, "py", None);
-        let h2 = Cache::compute_input_hash("p", &[], "m", 0.5, None, "py", None);
+        let h1 = Cache::compute_input_hash("p", &[], "m", 0.0, None, "py", None, &[]);
+        let h2 = Cache::compute_input_hash("p", &[], "m", 0.5, None, "py", None, &[]);
         assert_ne!(h1, h2);
     }
 
     #[test]
     fn test_changed_seed_different_hash() {
-        let h1 = Cache::compute_input_hash("p", &[], "m", 0.0, Some(42), "py", None);
-        let h2 = Cache::compute_input_hash("p", &[], "m", 0.0, Some(99), "py", None);
+        let h1 = Cache::compute_input_hash("p", &[], "m", 0.0, Some(42), "py", None, &[]);
+        let h2 = Cache::compute_input_hash("p", &[], "m", 0.0, Some(99), "py", None, &[]);
         assert_ne!(h1, h2);
     }
 
+    #[test]
+    fn test_system_inputs_change_hash() {
+        let plain = Cache::compute_input_hash("p", &[], "m", 0.0, None, "py", None, &[]);
+        let t1 = Cache::compute_input_hash(
+            "p", &[], "m", 0.0, None, "py", None,
+            &[("system_prompt_template", "You write {language}.")],
+        );
+        let t2 = Cache::compute_input_hash(
+            "p", &[], "m", 0.0, None, "py", None,
+            &[("system_prompt_template", "You write careful {language}.")],
+        );
+        assert_ne!(plain, t1);
+        assert_ne!(t1, t2);
+    }
+
     #[test]
     fn test_import_order_does_not_matter() {
         let h1 = Cache::compute_input_hash(
@@ -255,6 +501,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         let h2 = Cache::compute_input_hash(
             "prompt",
@@ -267,6 +514,7 @@ mod tests {
             None,
             "python",
             None,
+            &[],
         );
         assert_eq!(h1, h2, "Import order should not affect hash");
     }
@@ -285,6 +533,9 @@ mod tests {
             files: files.clone(),
             tokens_in: 100,
             tokens_out: 200,
+            created_at: None,
+            model: None,
+            parse_version: PARSE_VERSION,
         };
 
         cache.put(&entry).unwrap();
@@ -316,6 +567,9 @@ mod tests {
             files: HashMap::new(),
             tokens_in: 0,
             tokens_out: 0,
+            created_at: None,
+            model: None,
+            parse_version: PARSE_VERSION,
         };
         cache.put(&entry).unwrap();
         assert!(cache.get("abc123").is_some());
@@ -323,4 +577,128 @@ mod tests {
         cache.clear().unwrap();
         assert!(cache.get("abc123").is_none());
     }
+
+    #[test]
+    fn test_warm_from_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf());
+        let now = Utc::now();
+
+        let user = PathBuf::from("src/user.py");
+        let base = PathBuf::from("src/base.py");
+        let api = PathBuf::from("src/api.py");
+        let code = HashMap::from([
+            (user.clone(), "class User: ...\n".to_string()),
+            (base.clone(), "class Base: ...  # patched\n".to_string()),
+            (api.clone(), "edited by hand\n".to_string()),
+        ]);
+        let originals = HashMap::from([(base.clone(), "class Base: ...\n".to_string())]);
+        let mut manifest = Manifest::default();
+        manifest.record(Path::new("prompts/user.prompt.md"), "user-hash", now, [(&user, code[&user].as_str(), None)]);
+        manifest.record(
+            Path::new("prompts/base.prompt.md"),
+            "base-hash",
+            now,
+            [(&base, code[&base].as_str(), Some(crate::core::patch::PatchStatus::Active))],
+        );
+        manifest.record(Path::new("prompts/api.prompt.md"), "api-hash", now, [(&api, "generated\n", None)]);
+
+        assert_eq!(cache.warm_from_manifest(&manifest, &code, &originals, &[]).unwrap(), 2);
+        assert_eq!(cache.get("user-hash").unwrap().files[&user], "class User: ...\n");
+        assert_eq!(cache.get("base-hash").unwrap().files[&base], "class Base: ...\n");
+        assert!(cache.get("api-hash").is_none(), "hand-edited output must not be cached");
+
+        // Existing entries are left alone
+        assert_eq!(cache.warm_from_manifest(&manifest, &code, &originals, &[]).unwrap(), 0);
+    }
+
+    fn entry_from(model: &str, created_at: Option<DateTime<Utc>>) -> CachedGeneration {
+        CachedGeneration {
+            input_hash: "abc123".to_string(),
+            files: HashMap::new(),
+            tokens_in: 0,
+            tokens_out: 0,
+            created_at,
+            model: Some(model.to_string()),
+            parse_version: PARSE_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_binary_format_migrates_json_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut entry = entry_from("claude-sonnet-4-5-20250929", Some(Utc::now()));
+        entry.input_hash = "abc".to_string();
+        entry.files.insert(PathBuf::from("src/user.py"), "class User: ...\n".repeat(50));
+
+        let json = Cache::new(dir.path().to_path_buf());
+        json.put(&entry).unwrap();
+        assert!(dir.path().join("abc.json").exists());
+        assert!(json.entry_file("nope").is_none());
+
+        let binary = Cache::new(dir.path().to_path_buf()).with_config(CacheConfig {
+            format: CacheFormat::Binary,
+            ..Default::default()
+        });
+        let migrated = binary.get("abc").unwrap();
+        assert_eq!(migrated.files, entry.files);
+        assert_eq!(migrated.created_at, entry.created_at);
+        assert!(!dir.path().join("abc.json").exists());
+        // Found in either format, and peeked at without migrating
+        assert_eq!(json.entry_file("abc"), Some(dir.path().join("abc.bin")));
+        assert_eq!(json.peek("abc").unwrap().files, entry.files);
+        assert!(dir.path().join("abc.bin").exists());
+        let bin_size = std::fs::metadata(dir.path().join("abc.bin")).unwrap().len();
+        assert!(bin_size < serde_json::to_vec_pretty(&entry).unwrap().len() as u64);
+
+        // Read back as binary, and migrated back when switching to JSON
+        assert_eq!(binary.get("abc").unwrap().files, entry.files);
+        assert_eq!(json.get("abc").unwrap().files, entry.files);
+        assert!(dir.path().join("abc.json").exists());
+        assert!(!dir.path().join("abc.bin").exists());
+
+        json.remove("abc").unwrap();
+        assert!(binary.get("abc").is_none());
+    }
+
+    #[test]
+    fn test_no_expiry_policy_never_stale() {
+        let cache = Cache::new(PathBuf::from(".lit/cache"));
+        let entry = entry_from("claude-3-opus-20240229", None);
+        assert!(cache.stale_reason(&entry).is_none());
+    }
+
+    #[test]
+    fn test_deprecated_model_is_stale() {
+        let cache = Cache::new(PathBuf::from(".lit/cache")).with_config(CacheConfig {
+            expire_before: None,
+            deprecated_models: vec!["claude-3-opus".to_string()],
+            ..Default::default()
+        });
+
+        let old = entry_from("claude-3-opus-20240229", Some(Utc::now()));
+        let reason = cache.stale_reason(&old).unwrap();
+        assert!(reason.contains("deprecated model"), "got: {}", reason);
+
+        let current = entry_from("claude-sonnet-4-5-20250929", Some(Utc::now()));
+        assert!(cache.stale_reason(&current).is_none());
+    }
+
+    #[test]
+    fn test_expire_before_cutoff() {
+        let cutoff = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let cache = Cache::new(PathBuf::from(".lit/cache")).with_config(CacheConfig {
+            expire_before: Some(cutoff),
+            deprecated_models: Vec::new(),
+            ..Default::default()
+        });
+
+        let before = "2025-05-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let after = "2025-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert!(cache.stale_reason(&entry_from("m", Some(before))).is_some());
+        assert!(cache.stale_reason(&entry_from("m", Some(after))).is_none());
+        // Unknown age is treated as stale once a cutoff is configured
+        assert!(cache.stale_reason(&entry_from("m", None)).is_some());
+    }
 }
@@ -1,23 +1,98 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
-use serde::Deserialize;
+use chrono::NaiveDate;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::core::error::LitError;
+use crate::core::generation_record::{ModelPricing, PricingTable, user_pricing_file};
+use crate::core::header::HEADER_PLACEHOLDERS;
+use crate::core::keychain;
+use crate::core::lint::RULES as LINT_RULES;
+use crate::core::migrate::{FIRST_SCHEMA, check_schema};
+use crate::core::paths::to_slash;
 
 /// Valid mapping modes for prompt → code file mapping
 const VALID_MAPPING_MODES: &[&str] = &["direct", "manifest", "modular", "inferred"];
 
-/// A static file entry: path → content (written as-is, no LLM needed)
+/// LLM providers lit can call
+pub const VALID_PROVIDERS: &[&str] = &["anthropic", "openai", "azure", "openrouter"];
+
+/// Code hosts `lit describe --post` can open pull requests on
+pub const FORGE_PROVIDERS: &[&str] = &["github", "gitlab"];
+
+/// A static file entry: path → content (written as-is, no LLM needed).
+/// With `source`, the file is copied byte for byte from that path in the
+/// repo instead, so it may be binary (images, fonts, ...).
 #[derive(Debug, Clone, Deserialize)]
 pub struct StaticFile {
     pub path: String,
     #[serde(default = "default_static_content")]
     pub content: String,
+    /// File to copy, relative to the repo root
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    #[serde(default)]
+    pub mode: Option<FileMode>,
 }
 
 fn default_static_content() -> String {
     String::new()
 }
 
+impl StaticFile {
+    /// The bytes to write: `content`, or the contents of `source`
+    pub fn read(&self, root: &Path) -> Result<Vec<u8>> {
+        match &self.source {
+            Some(source) => std::fs::read(root.join(source))
+                .with_context(|| format!("Failed to read [[static]] source {}", source.display())),
+            None => Ok(self.content.clone().into_bytes()),
+        }
+    }
+}
+
+/// Unix permission bits for a written file, e.g. `0755` for a script.
+/// Written in octal: a string (`"0755"`, or bare `0755` in YAML, which
+/// reads it as a string) or an octal integer (`0o755`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMode(pub u32);
+
+impl<'de> Deserialize<'de> for FileMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawMode {
+            Bits(u32),
+            Octal(String),
+        }
+        let bits = match RawMode::deserialize(deserializer)? {
+            RawMode::Bits(bits) => bits,
+            RawMode::Octal(text) => {
+                let digits = text.strip_prefix("0o").unwrap_or(&text);
+                u32::from_str_radix(digits, 8)
+                    .map_err(|_| D::Error::custom(format!("invalid file mode '{}', expected octal like 0755", text)))?
+            }
+        };
+        // A decimal 755 lands here too, as 0o1363
+        if bits > 0o777 {
+            return Err(D::Error::custom(format!(
+                "invalid file mode {}, expected octal permission bits like 0755",
+                bits
+            )));
+        }
+        Ok(FileMode(bits))
+    }
+}
+
+/// Whether `path` is relative and made only of plain names, so joining it
+/// onto a directory can never leave that directory
+pub fn is_contained_path(path: &Path) -> bool {
+    !path.as_os_str().is_empty()
+        && path.components().all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
 /// Project configuration from lit.toml
 #[derive(Debug, Clone, Deserialize)]
 pub struct LitConfig {
@@ -27,6 +102,41 @@ pub struct LitConfig {
     pub model: ModelConfig,
     #[serde(default)]
     pub r#static: Vec<StaticFile>,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub generation: GenerationConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub commit: CommitConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub describe: DescribeConfig,
+    #[serde(default)]
+    pub forge: ForgeConfig,
+    /// Extra providers (by name) for per-prompt model chains, each with its
+    /// own API key. The `[model]` provider uses `[model.api]`.
+    #[serde(default)]
+    pub providers: BTreeMap<String, ApiConfig>,
+    /// Formatter commands by file extension, run on freshly generated files
+    #[serde(default)]
+    pub format: BTreeMap<String, String>,
+    /// Output templates by prompt directory, used in `inferred` mapping mode
+    #[serde(default)]
+    pub layout: BTreeMap<String, String>,
+    /// Per-model pricing from pricing files, loaded by `LitConfig::from_file`
+    #[serde(skip)]
+    pub pricing_table: PricingTable,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -34,15 +144,104 @@ pub struct ProjectConfig {
     pub name: String,
     pub version: String,
     pub mapping: String,
+    /// Format version of lit.toml and the prompt files; 1 when absent
+    #[serde(default = "default_schema")]
+    pub schema: u32,
+    /// Directory generated code is written to, relative to the repo root
+    #[serde(default = "default_code_dir")]
+    pub code_dir: PathBuf,
+    /// Directories searched for prompts, relative to the repo root
+    #[serde(default = "default_prompt_dirs")]
+    pub prompt_dirs: Vec<PathBuf>,
+    /// Globs, relative to the repo root, of prompts that are not part of
+    /// the project (work in progress, templates, archived prompts)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_schema() -> u32 {
+    FIRST_SCHEMA
+}
+
+/// Where generated code goes unless `[project] code_dir` says otherwise
+pub const DEFAULT_CODE_DIR: &str = "code.lock";
+
+fn default_code_dir() -> PathBuf {
+    PathBuf::from(DEFAULT_CODE_DIR)
+}
+
+/// Where prompts live unless `[project] prompt_dirs` says otherwise
+pub const DEFAULT_PROMPT_DIR: &str = "prompts";
+
+fn default_prompt_dirs() -> Vec<PathBuf> {
+    vec![PathBuf::from(DEFAULT_PROMPT_DIR)]
+}
+
+impl ProjectConfig {
+    /// Whether `path` (relative to the repo root) is inside a prompt directory
+    pub fn in_prompt_dir(&self, path: &Path) -> bool {
+        self.prompt_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Whether `path` (relative to the repo root) matches an `exclude` glob,
+    /// itself or through one of its directories
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        path.ancestors().any(|p| {
+            let p = to_slash(p);
+            self.exclude.iter().any(|pattern| glob_match(pattern, &p))
+        })
+    }
+
+    /// The prompt directories for messages, e.g. `prompts/, specs/`
+    pub fn prompt_dirs_display(&self) -> String {
+        self.prompt_dirs
+            .iter()
+            .map(|dir| format!("{}/", dir.display()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct LanguageConfig {
     pub default: String,
     pub version: String,
+    #[serde(default)]
+    pub profiles: Vec<LanguageProfile>,
 }
 
+/// Per-path language/framework for multi-language projects.
+///
+/// `paths` are globs matched against the prompt path relative to the repo root
+/// (`*` within a path segment, `**` across segments). The first matching profile
+/// wins; a profile without `framework` means no framework, not the global one.
+///
+/// ```toml
+/// [[language.profiles]]
+/// paths = ["prompts/frontend/**"]
+/// language = "typescript"
+/// version = "5.6"
+/// framework = { name = "react", version = "19" }
+/// ```
 #[derive(Debug, Clone, Deserialize)]
+pub struct LanguageProfile {
+    pub paths: Vec<String>,
+    pub language: String,
+    pub version: String,
+    pub framework: Option<FrameworkConfig>,
+}
+
+/// Language settings resolved for a single prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedLanguage {
+    pub language: String,
+    pub version: String,
+    pub framework: Option<FrameworkConfig>,
+    /// Index into `language.profiles` of the matching profile, if any
+    pub profile: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct FrameworkConfig {
     pub name: String,
     pub version: String,
@@ -56,6 +255,77 @@ pub struct ModelConfig {
     pub seed: Option<u64>,
     pub api: Option<ApiConfig>,
     pub pricing: Option<PricingConfig>,
+    /// Context window in tokens, overriding the built-in table
+    pub context_window: Option<u64>,
+    /// Models for classes of prompts, under `[model.tiers.<name>]`
+    #[serde(default)]
+    pub tiers: BTreeMap<String, ModelTier>,
+    /// Client-side pacing under the account's rate limits
+    #[serde(default)]
+    pub limits: RateLimits,
+}
+
+/// Ceilings for requests to a provider, under `[model.limits]` or
+/// `[providers.<name>.limits]`. Requests wait until they fit in the last
+/// minute's budget rather than being sent and throttled. Unset means no
+/// pacing.
+///
+/// ```toml
+/// [model.limits]
+/// requests_per_minute = 50
+/// tokens_per_minute = 40000   # input and output tokens together
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u64>,
+}
+
+impl RateLimits {
+    pub fn is_empty(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+}
+
+/// A model for a class of prompts — a cheap one for scaffolding, an
+/// expensive one for complex modules. A prompt is in a tier if its `tier:`
+/// frontmatter key names it, or else if its path matches one of `paths`
+/// (globs, as in `[[language.profiles]]`). A prompt's own `model:` still wins.
+///
+/// ```toml
+/// [model.tiers.scaffold]
+/// model = "claude-haiku-4-5"
+/// paths = ["prompts/**/__init__.prompt.md", "prompts/config/**"]
+///
+/// [model.tiers.complex]
+/// model = "claude-opus-4-6"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelTier {
+    pub model: String,
+    /// Defaults to the provider the model name belongs to
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl ModelTier {
+    /// The tier's model and the provider that serves it
+    pub fn route(&self, config: &LitConfig) -> ModelRoute {
+        ModelRoute {
+            provider: self.provider.clone().unwrap_or_else(|| config.provider_for_model(&self.model)),
+            model: self.model.clone(),
+        }
+    }
+}
+
+/// A model and the provider that serves it, as listed in a prompt's
+/// `model:` chain
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ModelRoute {
+    pub provider: String,
+    pub model: String,
 }
 
 /// Optional per-million-token pricing override.
@@ -74,9 +344,409 @@ pub struct PricingConfig {
     pub output_per_million: f64,
 }
 
+/// API access for a provider, under `[model.api]` or `[providers.<name>]`.
+///
+/// ```toml
+/// [model.api]
+/// key_env = "AZURE_OPENAI_API_KEY"
+/// base_url = "https://my-resource.openai.azure.com"   # required for azure
+/// api_version = "2024-10-21"                          # azure only
+/// headers = { "X-Title" = "my-project" }              # extra request headers
+/// proxy_url = "http://proxy.corp.example:8080"        # else HTTPS_PROXY etc. apply
+/// ca_bundle = "certs/corp-root.pem"                   # extra CAs, relative to the repo root
+/// timeout_secs = 300                                  # per request (default: none)
+///
+/// [providers.openai.limits]                           # pacing, as [model.limits]
+/// requests_per_minute = 500
+/// ```
 #[derive(Debug, Clone, Deserialize)]
 pub struct ApiConfig {
     pub key_env: String,
+    /// API base URL, replacing the provider's default
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Azure OpenAI `api-version` query parameter
+    #[serde(default)]
+    pub api_version: Option<String>,
+    /// Extra headers sent with every request (e.g. OpenRouter's
+    /// `HTTP-Referer` and `X-Title`)
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Proxy every request to this provider goes through. When unset, the
+    /// `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` environment variables apply.
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// PEM file of CA certificates to trust besides the system's, e.g. for
+    /// a proxy that re-signs TLS traffic. Made absolute by `LitConfig::from_file`.
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// Seconds before a request is abandoned (default: no timeout)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Client-side pacing for a `[providers.*]` entry; the `[model]`
+    /// provider's is `[model.limits]`
+    #[serde(default)]
+    pub limits: RateLimits,
+}
+
+/// Optional generation settings.
+///
+/// `system_prompt_template` replaces the built-in preamble of the system prompt.
+/// It may use the placeholders `{language}`, `{language_version}`, `{framework}`
+/// and `{outputs}`. The output file format instructions are always appended by
+/// lit, since the response parser depends on them.
+///
+/// `conventions` points at a project-wide style guide (relative to the repo
+/// root) whose content is included in the system prompt of every generation.
+/// When unset, `conventions.md` at the repo root is used if it exists.
+///
+/// `file_header` is prepended to every generated file as a comment, in the
+/// file's comment syntax. It may use `{prompt}`, `{model}`, `{timestamp}`
+/// and `{input_hash}`.
+///
+/// ```toml
+/// [generation]
+/// system_prompt_template = """
+/// You are a senior {language} {language_version} engineer using {framework}.
+/// Write small, well-tested modules.
+/// """
+/// conventions = "docs/conventions.md"
+/// context_strategy = "signatures-only"
+/// file_header = "Generated by lit from {prompt}. Edit the prompt, not this file."
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GenerationConfig {
+    pub system_prompt_template: Option<String>,
+    pub conventions: Option<PathBuf>,
+    /// Content of the conventions file, loaded by `LitConfig::from_file`
+    #[serde(skip)]
+    pub conventions_content: Option<String>,
+    /// How imported prompts' code is passed as context
+    #[serde(default)]
+    pub context_strategy: ContextStrategy,
+    /// Model used by the `summary` context strategy (defaults to a cheap
+    /// model from the configured provider)
+    pub summary_model: Option<String>,
+    /// Extract each generated file's public interface into `.lit/interfaces/`
+    /// and give downstream prompts those instead of full file bodies
+    #[serde(default)]
+    pub interfaces: bool,
+    /// How the model is asked to lay out the generated files
+    #[serde(default)]
+    pub response_format: ResponseFormat,
+    /// `lit regenerate` asks before generating more prompts than this when
+    /// some need fresh LLM calls (default 10, 0 never asks)
+    pub confirm_above: Option<usize>,
+    /// Provenance comment prepended to each generated file
+    pub file_header: Option<String>,
+    /// Cascade on upstream input changes even when the regenerated output is
+    /// identical (set by `lit regenerate --no-prune`)
+    #[serde(skip)]
+    pub no_prune: bool,
+}
+
+/// Regeneration sets up to this size run without asking
+const DEFAULT_CONFIRM_ABOVE: usize = 10;
+
+impl GenerationConfig {
+    /// Largest regeneration set that runs without confirmation, if any asks
+    pub fn confirm_above(&self) -> Option<usize> {
+        match self.confirm_above.unwrap_or(DEFAULT_CONFIRM_ABOVE) {
+            0 => None,
+            n => Some(n),
+        }
+    }
+}
+
+/// How the generated code of imported prompts is included in a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContextStrategy {
+    /// Every imported output file verbatim
+    #[default]
+    Full,
+    /// Public interfaces only (class/def lines, exports, pub items)
+    SignaturesOnly,
+    /// A condensed description written by a cheap model
+    Summary,
+}
+
+impl ContextStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContextStrategy::Full => "full",
+            ContextStrategy::SignaturesOnly => "signatures-only",
+            ContextStrategy::Summary => "summary",
+        }
+    }
+}
+
+/// How a response carries the generated files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseFormat {
+    /// `=== FILE: path ===` sections
+    #[default]
+    Delimited,
+    /// A JSON object with a `files` array of `{path, content}`, using the
+    /// provider's JSON mode; providers without one use `Delimited`
+    Json,
+}
+
+/// Placeholders available in `[layout]` output templates
+const LAYOUT_PLACEHOLDERS: &[&str] = &["{name}", "{path}", "{ext}"];
+
+/// Repo pricing file, under `.lit/`
+pub const PRICING_FILE: &str = "pricing.toml";
+
+/// Default conventions file, picked up from the repo root when present
+const DEFAULT_CONVENTIONS_FILE: &str = "conventions.md";
+
+/// Optional cache expiry policy and storage format.
+///
+/// Cache entries created before `expire_before`, or generated by a model
+/// listed in `deprecated_models`, are treated as stale and regenerated
+/// instead of being served from cache.
+///
+/// ```toml
+/// [cache]
+/// expire_before = "2025-06-01"
+/// deprecated_models = ["claude-3-opus", "gpt-4-0613"]
+/// format = "binary"
+/// ```
+///
+/// A deprecated model name matches any model that starts with it, so
+/// `claude-3-opus` also covers dated snapshots like `claude-3-opus-20240229`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CacheConfig {
+    pub expire_before: Option<NaiveDate>,
+    #[serde(default)]
+    pub deprecated_models: Vec<String>,
+    #[serde(default)]
+    pub format: CacheFormat,
+}
+
+/// On-disk format of `.lit/cache/` entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheFormat {
+    /// Pretty-printed JSON (`<hash>.json`)
+    #[default]
+    Json,
+    /// zstd-compressed MessagePack (`<hash>.bin`), smaller and faster to
+    /// read for projects with large outputs
+    Binary,
+}
+
+/// `lit commit` defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommitConfig {
+    /// Regenerate changed prompts before committing (as `lit commit --regenerate`)
+    #[serde(default)]
+    pub regenerate: bool,
+}
+
+/// Extra records kept for debugging and auditing
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggingConfig {
+    /// Save the exact request and raw response of every generation under
+    /// `.lit/transcripts/` (view with `lit debug transcript <prompt>`)
+    #[serde(default)]
+    pub transcripts: bool,
+}
+
+/// Shell commands run at lifecycle points, before any `.lit/hooks/` script
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HooksConfig {
+    /// Run before `lit regenerate` generates anything (e.g. lint prompts)
+    #[serde(default)]
+    pub pre_generate: Vec<String>,
+    /// Run after each file is written to code.lock/; `{file}` is replaced
+    /// with the file's path (e.g. a formatter)
+    #[serde(default)]
+    pub post_write: Vec<String>,
+    /// Run before `lit commit` stages anything (e.g. tests)
+    #[serde(default)]
+    pub pre_commit: Vec<String>,
+}
+
+/// Prompt file size limits, checked when prompts are parsed so that an
+/// accidentally pasted log doesn't get sent to the provider. 0 disables a limit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    /// Largest prompt file accepted, in bytes
+    #[serde(default = "default_max_prompt_bytes")]
+    pub max_prompt_bytes: u64,
+    /// Largest prompt file accepted, in estimated tokens
+    #[serde(default = "default_max_prompt_tokens")]
+    pub max_prompt_tokens: u64,
+    /// Prompts above this many estimated tokens get a warning on regenerate
+    #[serde(default = "default_warn_prompt_tokens")]
+    pub warn_prompt_tokens: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_prompt_bytes: default_max_prompt_bytes(),
+            max_prompt_tokens: default_max_prompt_tokens(),
+            warn_prompt_tokens: default_warn_prompt_tokens(),
+        }
+    }
+}
+
+fn default_max_prompt_bytes() -> u64 {
+    256 * 1024
+}
+
+fn default_max_prompt_tokens() -> u64 {
+    50_000
+}
+
+fn default_warn_prompt_tokens() -> u64 {
+    10_000
+}
+
+/// `lit lint` settings.
+///
+/// ```toml
+/// [lint]
+/// min_body_words = 30
+///
+/// [lint.rules]
+/// unused-import = "off"
+/// duplicate-heading = "error"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct LintConfig {
+    /// Prompt bodies with fewer words are flagged by `vague-body` (0 disables)
+    #[serde(default = "default_min_body_words")]
+    pub min_body_words: usize,
+    /// Level per rule name, overriding the rule's default
+    #[serde(default)]
+    pub rules: BTreeMap<String, LintLevel>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            min_body_words: default_min_body_words(),
+            rules: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_min_body_words() -> usize {
+    20
+}
+
+/// How a lint rule's findings are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Off,
+    Warn,
+    Error,
+}
+
+/// Checks on generated code before it is written to code.lock/
+///
+/// ```toml
+/// [security]
+/// secrets = "error"
+/// allow = ["^sk_test_"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityConfig {
+    /// What a likely secret (API key, private key block, hardcoded
+    /// password) in generated output does: "off", "warn" or "error", which
+    /// fails the generation
+    #[serde(default = "default_secrets_level")]
+    pub secrets: LintLevel,
+    /// Regexes for values that are not secrets (test keys, fixtures)
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            secrets: default_secrets_level(),
+            allow: Vec::new(),
+        }
+    }
+}
+
+fn default_secrets_level() -> LintLevel {
+    LintLevel::Warn
+}
+
+/// `lit describe` settings.
+///
+/// ```toml
+/// [describe]
+/// base = "develop"
+/// template = ".lit/pr-template.md"
+/// model = "claude-haiku-4-5"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DescribeConfig {
+    /// Branch pull requests merge into (default "main")
+    pub base: Option<String>,
+    /// Markdown file with the description layout, using the placeholders in
+    /// [`DESCRIBE_PLACEHOLDERS`](crate::core::describe::DESCRIBE_PLACEHOLDERS)
+    pub template: Option<PathBuf>,
+    /// Model for `lit describe --llm` (defaults to the summary model)
+    pub model: Option<String>,
+}
+
+/// The GitHub or GitLab project `lit describe --post` opens pull requests
+/// on. Both are detected from the remote URL for github.com and gitlab.com.
+///
+/// ```toml
+/// [forge]
+/// provider = "gitlab"
+/// repo = "acme/shop"
+/// api_url = "https://gitlab.acme.dev/api/v4"
+/// token_env = "GITLAB_TOKEN"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ForgeConfig {
+    /// "github" or "gitlab"
+    pub provider: Option<String>,
+    /// `owner/name` (GitLab: the full project path)
+    pub repo: Option<String>,
+    /// API root, for GitHub Enterprise or self-hosted GitLab
+    pub api_url: Option<String>,
+    /// Environment variable holding the API token (default `GITHUB_TOKEN`
+    /// or `GITLAB_TOKEN`)
+    pub token_env: Option<String>,
+}
+
+/// Spending limits, checked against generation records
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub alerts: BudgetAlertsConfig,
+}
+
+/// Optional cost alert thresholds.
+///
+/// When a single `lit regenerate` run, or the total across the last seven
+/// days, costs more than its threshold, regenerate and commit print a
+/// warning, and regenerate posts the alert to `webhook` if one is set.
+///
+/// ```toml
+/// [budget.alerts]
+/// warn_at_usd_per_run = 2.0
+/// warn_at_usd_per_week = 25.0
+/// webhook = "https://hooks.example.com/lit"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BudgetAlertsConfig {
+    pub warn_at_usd_per_run: Option<f64>,
+    pub warn_at_usd_per_week: Option<f64>,
+    pub webhook: Option<String>,
 }
 
 impl LitConfig {
@@ -85,85 +755,644 @@ impl LitConfig {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        Self::from_str(&content)
+        let mut config = Self::from_str(&content)?;
+        let root = path.parent().unwrap_or_else(|| Path::new("."));
+        config.load_conventions(root)?;
+        for api in config.model.api.iter_mut().chain(config.providers.values_mut()) {
+            if let Some(bundle) = api.ca_bundle.as_mut().filter(|b| b.is_relative()) {
+                *bundle = root.join(&*bundle);
+            }
+        }
+
+        let mut pricing_files = vec![root.join(".lit").join(PRICING_FILE)];
+        pricing_files.extend(user_pricing_file());
+        config.pricing_table = PricingTable::load(&pricing_files)?;
+        Ok(config)
+    }
+
+    /// Parse and validate configuration from a TOML string
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(content: &str) -> Result<Self> {
+        let config: LitConfig = toml::from_str(content)
+            .context(LitError::InvalidConfig { message: "Failed to parse lit.toml".to_string() })?;
+
+        config
+            .validate()
+            .map_err(|e| LitError::InvalidConfig { message: format!("{:#}", e) })?;
+        Ok(config)
+    }
+
+    /// The directory generated code is written to (`[project] code_dir`)
+    pub fn code_dir(&self, root: &Path) -> PathBuf {
+        root.join(&self.project.code_dir)
+    }
+
+    /// Find and load lit.toml by walking up from the given directory
+    pub fn find_and_load(start_dir: &Path) -> Result<(Self, PathBuf)> {
+        let root = Self::find_root(start_dir)?;
+        let config = Self::from_file(&root.join("lit.toml"))?;
+        Ok((config, root))
+    }
+
+    /// Find the repo root (the directory holding lit.toml) without loading
+    /// the config, for commands that must work on configs lit can't load
+    pub fn find_root(start_dir: &Path) -> Result<PathBuf> {
+        let mut current = start_dir.to_path_buf();
+        loop {
+            if current.join("lit.toml").exists() {
+                return Ok(current);
+            }
+            if !current.pop() {
+                return Err(LitError::NotARepository { start: start_dir.to_path_buf() }.into());
+            }
+        }
+    }
+
+    /// Validate the configuration
+    fn validate(&self) -> Result<()> {
+        // Validate mapping mode
+        if !VALID_MAPPING_MODES.contains(&self.project.mapping.as_str()) {
+            bail!(
+                "Invalid mapping mode '{}' in lit.toml. Must be one of: {}",
+                self.project.mapping,
+                VALID_MAPPING_MODES.join(", ")
+            );
+        }
+
+        check_schema(self.project.schema, "lit.toml")?;
+
+        // Prompts and generated code live in separate directories, away
+        // from lit's and git's own
+        if self.project.prompt_dirs.is_empty() {
+            bail!("prompt_dirs in lit.toml must list at least one directory");
+        }
+        if let Some(dir) = self.project.prompt_dirs.iter().find(|dir| {
+            !is_contained_path(dir) || dir.starts_with(".lit") || dir.starts_with(".git")
+        }) {
+            bail!(
+                "Invalid prompt directory '{}' in lit.toml. Must be a relative directory outside .lit/ and .git/",
+                dir.display()
+            );
+        }
+        let code_dir = &self.project.code_dir;
+        if !is_contained_path(code_dir)
+            || self.project.in_prompt_dir(code_dir)
+            || self.project.prompt_dirs.iter().any(|dir| dir.starts_with(code_dir))
+            || code_dir.starts_with(".lit")
+            || code_dir.starts_with(".git")
+        {
+            bail!(
+                "Invalid code_dir '{}' in lit.toml. Must be a relative directory outside {}, .lit/ and .git/",
+                code_dir.display(),
+                self.project.prompt_dirs_display()
+            );
+        }
+
+        if let Some(sf) = self.r#static.iter().find(|sf| !is_contained_path(Path::new(&sf.path))) {
+            bail!(
+                "Invalid [[static]] path '{}' in lit.toml. Static files are written inside {}/",
+                sf.path,
+                code_dir.display()
+            );
+        }
+        for sf in &self.r#static {
+            let Some(ref source) = sf.source else { continue };
+            if !sf.content.is_empty() {
+                bail!("[[static]] '{}' in lit.toml sets both content and source; use one", sf.path);
+            }
+            if !is_contained_path(source) {
+                bail!(
+                    "Invalid [[static]] source '{}' in lit.toml. Must be a relative path inside the repo",
+                    source.display()
+                );
+            }
+        }
+
+        // Validate temperature range
+        if self.model.temperature < 0.0 || self.model.temperature > 2.0 {
+            bail!(
+                "Invalid temperature {} in lit.toml. Must be between 0.0 and 2.0",
+                self.model.temperature
+            );
+        }
+
+        // Validate provider
+        if !VALID_PROVIDERS.contains(&self.model.provider.as_str()) {
+            bail!(
+                "Invalid model provider '{}' in lit.toml. Must be one of: {}",
+                self.model.provider,
+                VALID_PROVIDERS.join(", ")
+            );
+        }
+        if self.model.api.as_ref().is_some_and(|api| !api.limits.is_empty()) {
+            bail!("Rate limits go under [model.limits] in lit.toml, not [model.api.limits]");
+        }
+        let limits = std::iter::once(("model".to_string(), &self.model.limits))
+            .chain(self.providers.iter().map(|(name, api)| (format!("providers.{}", name), &api.limits)));
+        for (section, limits) in limits {
+            if limits.requests_per_minute == Some(0) || limits.tokens_per_minute == Some(0) {
+                bail!("[{}.limits] in lit.toml must be at least 1 (leave a limit out to not pace by it)", section);
+            }
+        }
+        for (name, tier) in &self.model.tiers {
+            if tier.model.trim().is_empty() {
+                bail!("[model.tiers.{}] in lit.toml needs a model", name);
+            }
+            if let Some(provider) = tier.provider.as_deref().filter(|p| !VALID_PROVIDERS.contains(p)) {
+                bail!(
+                    "Invalid provider '{}' in [model.tiers.{}] in lit.toml. Must be one of: {}",
+                    provider,
+                    name,
+                    VALID_PROVIDERS.join(", ")
+                );
+            }
+        }
+        for name in self.providers.keys() {
+            if !VALID_PROVIDERS.contains(&name.as_str()) {
+                bail!(
+                    "Invalid provider [providers.{}] in lit.toml. Must be one of: {}",
+                    name,
+                    VALID_PROVIDERS.join(", ")
+                );
+            }
+        }
+        for provider in VALID_PROVIDERS {
+            let api = self.api_config(provider);
+            let base_url = api.and_then(|api| api.base_url.as_ref());
+            if let Some(url) = base_url
+                && !(url.starts_with("https://") || url.starts_with("http://"))
+            {
+                bail!(
+                    "Invalid base_url '{}' for {} in lit.toml. Must be an http(s) URL",
+                    url,
+                    provider
+                );
+            }
+            if *provider == "anthropic"
+                && api.is_some_and(|api| base_url.is_some() || !api.headers.is_empty())
+            {
+                bail!(
+                    "base_url and headers are not supported for the anthropic provider in lit.toml"
+                );
+            }
+            if let Some(url) = api.and_then(|api| api.proxy_url.as_ref())
+                && !(url.starts_with("https://") || url.starts_with("http://"))
+            {
+                bail!(
+                    "Invalid proxy_url '{}' for {} in lit.toml. Must be an http(s) URL",
+                    url,
+                    provider
+                );
+            }
+            if api.is_some_and(|api| api.timeout_secs == Some(0)) {
+                bail!("timeout_secs for {} in lit.toml must be at least 1", provider);
+            }
+            if *provider == "azure" && self.has_provider(provider) && base_url.is_none() {
+                bail!(
+                    "Provider 'azure' needs base_url in lit.toml, e.g. \"https://<resource>.openai.azure.com\""
+                );
+            }
+        }
+
+        if self.generation.interfaces && self.generation.context_strategy != ContextStrategy::Full {
+            bail!(
+                "generation.interfaces cannot be combined with context_strategy = \"{}\" in lit.toml",
+                self.generation.context_strategy.as_str()
+            );
+        }
+
+        if self.project.mapping == "inferred" && self.layout.is_empty() {
+            bail!(
+                "mapping = \"inferred\" requires a [layout] section in lit.toml.\n\
+                 Hint: Map prompt directories to output locations, e.g. \"prompts/models\" = \"src/models/\""
+            );
+        }
+        for (dir, template) in &self.layout {
+            let mut rest = template.clone();
+            for placeholder in LAYOUT_PLACEHOLDERS {
+                rest = rest.replace(placeholder, "");
+            }
+            if template.is_empty() || rest.contains(['{', '}']) {
+                bail!(
+                    "Invalid [layout] template '{}' for \"{}\" in lit.toml. \
+                     Available placeholders: {}",
+                    template,
+                    dir,
+                    LAYOUT_PLACEHOLDERS.join(", ")
+                );
+            }
+        }
+
+        if let Some(template) = &self.generation.file_header {
+            let mut rest = template.clone();
+            for placeholder in HEADER_PLACEHOLDERS {
+                rest = rest.replace(placeholder, "");
+            }
+            if rest.contains(['{', '}']) {
+                bail!(
+                    "Invalid [generation] file_header '{}' in lit.toml. Available placeholders: {}",
+                    template,
+                    HEADER_PLACEHOLDERS.join(", ")
+                );
+            }
+        }
+
+        for pattern in &self.security.allow {
+            if let Err(e) = regex::Regex::new(pattern) {
+                bail!("Invalid [security] allow pattern '{}' in lit.toml: {}", pattern, e);
+            }
+        }
+
+        if let Some(ref provider) = self.forge.provider
+            && !FORGE_PROVIDERS.contains(&provider.as_str())
+        {
+            bail!(
+                "Invalid [forge] provider '{}' in lit.toml. Must be one of: {}",
+                provider,
+                FORGE_PROVIDERS.join(", ")
+            );
+        }
+        if let Some(ref repo) = self.forge.repo
+            && !repo.trim_matches('/').contains('/')
+        {
+            bail!("Invalid [forge] repo '{}' in lit.toml. Expected owner/name", repo);
+        }
+        if let Some(ref url) = self.forge.api_url
+            && !(url.starts_with("https://") || url.starts_with("http://"))
+        {
+            bail!("Invalid [forge] api_url '{}' in lit.toml. Must be an http(s) URL", url);
+        }
+
+        let alerts = &self.budget.alerts;
+        for (key, threshold) in [
+            ("warn_at_usd_per_run", alerts.warn_at_usd_per_run),
+            ("warn_at_usd_per_week", alerts.warn_at_usd_per_week),
+        ] {
+            if let Some(usd) = threshold
+                && (usd.is_nan() || usd <= 0.0)
+            {
+                bail!("Invalid budget.alerts.{} = {} in lit.toml. Must be greater than 0", key, usd);
+            }
+        }
+        if let Some(ref url) = alerts.webhook
+            && !(url.starts_with("https://") || url.starts_with("http://"))
+        {
+            bail!("Invalid budget.alerts.webhook '{}' in lit.toml. Must be an http(s) URL", url);
+        }
+
+        for (ext, command) in &self.format {
+            if ext.is_empty() || ext.starts_with('.') {
+                bail!(
+                    "Invalid [format] extension \"{}\" in lit.toml. Use the extension without a dot, e.g. \"py\"",
+                    ext
+                );
+            }
+            if command.trim().is_empty() {
+                bail!("[format] command for \"{}\" in lit.toml is empty", ext);
+            }
+        }
+
+        let limits = &self.limits;
+        if limits.warn_prompt_tokens > 0
+            && limits.max_prompt_tokens > 0
+            && limits.warn_prompt_tokens >= limits.max_prompt_tokens
+        {
+            bail!(
+                "Invalid limits.warn_prompt_tokens = {} in lit.toml. Must be below max_prompt_tokens ({})",
+                limits.warn_prompt_tokens,
+                limits.max_prompt_tokens
+            );
+        }
+
+        for rule in self.lint.rules.keys() {
+            if !LINT_RULES.iter().any(|r| r.name == rule) {
+                bail!(
+                    "Unknown lint rule '{}' in lit.toml. Must be one of: {}",
+                    rule,
+                    LINT_RULES.iter().map(|r| r.name).collect::<Vec<_>>().join(", ")
+                );
+            }
+        }
+
+        for (i, profile) in self.language.profiles.iter().enumerate() {
+            if profile.paths.is_empty() {
+                bail!(
+                    "[[language.profiles]] entry {} ({}) in lit.toml has no paths",
+                    i + 1,
+                    profile.language
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve language, version and framework for a prompt.
+    ///
+    /// A matching `[[language.profiles]]` entry replaces the global defaults;
+    /// a `language:` frontmatter override still wins for the language name.
+    pub fn resolve_language(&self, prompt_path: &Path, language_override: Option<&str>) -> ResolvedLanguage {
+        let path = to_slash(prompt_path);
+        let matched = self
+            .language
+            .profiles
+            .iter()
+            .position(|p| p.paths.iter().any(|pattern| glob_match(pattern, &path)));
+
+        let mut resolved = match matched {
+            Some(i) => {
+                let profile = &self.language.profiles[i];
+                ResolvedLanguage {
+                    language: profile.language.clone(),
+                    version: profile.version.clone(),
+                    framework: profile.framework.clone(),
+                    profile: Some(i),
+                }
+            }
+            None => ResolvedLanguage {
+                language: self.language.default.clone(),
+                version: self.language.version.clone(),
+                framework: self.framework.clone(),
+                profile: None,
+            },
+        };
+
+        if let Some(language) = language_override {
+            resolved.language = language.to_string();
+        }
+        resolved
+    }
+
+    /// Derive a prompt's output path from `[layout]` (for `inferred` mapping).
+    ///
+    /// Keys are prompt directories and the longest one containing the prompt
+    /// wins. Values are output templates with `{name}` (file name without
+    /// `.prompt.md`), `{path}` (path below the key directory, likewise) and
+    /// `{ext}` (file extension of the prompt's language); a value ending in
+    /// `/` is shorthand for `<dir>/{path}.{ext}`.
+    ///
+    /// ```toml
+    /// [layout]
+    /// "prompts/models" = "src/models/"          # prompts/models/user.prompt.md → src/models/user.py
+    /// "prompts/api" = "src/routes/{name}_routes.{ext}"
+    /// ```
+    pub fn infer_output(&self, prompt_path: &Path, language_override: Option<&str>) -> Option<PathBuf> {
+        let (dir, template) = self
+            .layout
+            .iter()
+            .filter(|(dir, _)| prompt_path.starts_with(dir.as_str()))
+            .max_by_key(|(dir, _)| Path::new(dir.as_str()).components().count())?;
+
+        let relative = to_slash(prompt_path.strip_prefix(dir.as_str()).ok()?);
+        let path = relative.strip_suffix(".prompt.md").unwrap_or(&relative);
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let language = self.resolve_language(prompt_path, language_override).language;
+
+        let template = if template.ends_with('/') {
+            format!("{}{{path}}.{{ext}}", template)
+        } else {
+            template.clone()
+        };
+        Some(PathBuf::from(
+            template
+                .replace("{name}", name)
+                .replace("{path}", path)
+                .replace("{ext}", language_extension(&language)),
+        ))
+    }
+
+    /// Load the conventions file (if any) relative to the repo root.
+    ///
+    /// An explicitly configured path must exist; the default `conventions.md`
+    /// is optional.
+    fn load_conventions(&mut self, root: &Path) -> Result<()> {
+        let path = match self.generation.conventions {
+            Some(ref configured) => {
+                let path = root.join(configured);
+                if !path.is_file() {
+                    bail!(
+                        "Conventions file {} (from [generation] conventions in lit.toml) not found",
+                        configured.display()
+                    );
+                }
+                path
+            }
+            None => {
+                let path = root.join(DEFAULT_CONVENTIONS_FILE);
+                if !path.is_file() {
+                    return Ok(());
+                }
+                path
+            }
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read conventions file: {}", path.display()))?;
+        self.generation.conventions_content = Some(content);
+        Ok(())
+    }
+
+    /// Pricing for `model` on `date`: `[model.pricing]` if set, then the
+    /// pricing files. None means the built-in table applies.
+    pub fn model_pricing(&self, model: &str, date: NaiveDate) -> Option<ModelPricing> {
+        match self.model.pricing {
+            Some(ref p) => Some(ModelPricing::new(p.input_per_million, p.output_per_million)),
+            None => self.pricing_table.lookup(model, date),
+        }
+    }
+
+    /// Resolve the API key from the environment variable specified in
+    /// config, falling back to the OS keychain (see `lit auth login`)
+    pub fn resolve_api_key(&self) -> Result<String> {
+        let key_env = self
+            .model
+            .api
+            .as_ref()
+            .map(|api| api.key_env.as_str())
+            .unwrap_or("LIT_API_KEY");
+
+        if let Ok(key) = std::env::var(key_env) {
+            return Ok(key);
+        }
+        keychain::load(&self.model.provider).with_context(|| {
+            format!(
+                "API key not found. Set the {} environment variable or store the key in the OS keychain.\n\
+                 Hint: export {}=your-api-key, or run `lit auth login {}`",
+                key_env, key_env, self.model.provider
+            )
+        })
     }
 
-    /// Parse and validate configuration from a TOML string
-    pub fn from_str(content: &str) -> Result<Self> {
-        let config: LitConfig =
-            toml::from_str(content).context("Failed to parse lit.toml")?;
+    /// Resolve the API key for any configured provider: `[model.api]` for
+    /// the project provider, `[providers.<name>]` for the others
+    pub fn resolve_provider_key(&self, provider: &str) -> Result<String> {
+        if provider == self.model.provider {
+            return self.resolve_api_key();
+        }
+        let Some(api) = self.api_config(provider) else {
+            bail!(
+                "Provider '{}' is not configured.\n\
+                 Hint: Add [providers.{}] with key_env to lit.toml.",
+                provider,
+                provider
+            );
+        };
+        if let Ok(key) = std::env::var(&api.key_env) {
+            return Ok(key);
+        }
+        keychain::load(provider).with_context(|| {
+            format!(
+                "API key for {} not found. Set the {} environment variable or store the key in the OS keychain.\n\
+                 Hint: export {}=your-api-key, or run `lit auth login {}`",
+                provider, api.key_env, api.key_env, provider
+            )
+        })
+    }
 
-        config.validate()?;
-        Ok(config)
+    /// API settings for a provider: `[model.api]` for the project provider,
+    /// `[providers.<name>]` for the others
+    pub fn api_config(&self, provider: &str) -> Option<&ApiConfig> {
+        if provider == self.model.provider {
+            self.model.api.as_ref()
+        } else {
+            self.providers.get(provider)
+        }
     }
 
-    /// Find and load lit.toml by walking up from the given directory
-    pub fn find_and_load(start_dir: &Path) -> Result<(Self, PathBuf)> {
-        let mut current = start_dir.to_path_buf();
-        loop {
-            let config_path = current.join("lit.toml");
-            if config_path.exists() {
-                let config = Self::from_file(&config_path)?;
-                return Ok((config, current));
-            }
-            if !current.pop() {
-                bail!(
-                    "Not a lit repository: lit.toml not found in {} or any parent directory",
-                    start_dir.display()
-                );
-            }
+    /// Pacing for requests to `provider`: `[model.limits]` for the `[model]`
+    /// provider, else its `[providers.<name>.limits]`
+    pub fn rate_limits(&self, provider: &str) -> RateLimits {
+        if provider == self.model.provider {
+            self.model.limits.clone()
+        } else {
+            self.providers.get(provider).map(|api| api.limits.clone()).unwrap_or_default()
         }
     }
 
-    /// Validate the configuration
-    fn validate(&self) -> Result<()> {
-        // Validate mapping mode
-        if !VALID_MAPPING_MODES.contains(&self.project.mapping.as_str()) {
-            bail!(
-                "Invalid mapping mode '{}' in lit.toml. Must be one of: {}",
-                self.project.mapping,
-                VALID_MAPPING_MODES.join(", ")
-            );
+    /// The `[model.tiers]` entry for a prompt: the one its `tier:` frontmatter
+    /// key names, else the one whose `paths` match it. A path matching
+    /// several tiers is an error, since their order in lit.toml is not kept.
+    pub fn resolve_tier(&self, prompt_path: &Path, tier: Option<&str>) -> Result<Option<&ModelTier>> {
+        if let Some(name) = tier {
+            return match self.model.tiers.get(name) {
+                Some(tier) => Ok(Some(tier)),
+                None => bail!(
+                    "Prompt {} has tier '{}', which is not in [model.tiers] in lit.toml{}",
+                    prompt_path.display(),
+                    name,
+                    if self.model.tiers.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" (tiers: {})", self.model.tiers.keys().cloned().collect::<Vec<_>>().join(", "))
+                    }
+                ),
+            };
         }
 
-        // Validate temperature range
-        if self.model.temperature < 0.0 || self.model.temperature > 2.0 {
+        let path = to_slash(prompt_path);
+        let mut matched = self
+            .model
+            .tiers
+            .iter()
+            .filter(|(_, tier)| tier.paths.iter().any(|pattern| glob_match(pattern, &path)));
+        let Some((first, tier)) = matched.next() else {
+            return Ok(None);
+        };
+        if let Some((second, _)) = matched.next() {
             bail!(
-                "Invalid temperature {} in lit.toml. Must be between 0.0 and 2.0",
-                self.model.temperature
+                "Prompt {} matches the paths of tiers '{}' and '{}' in lit.toml.\n\
+                 Hint: Set `tier:` in its frontmatter to pick one.",
+                prompt_path.display(),
+                first,
+                second
             );
         }
+        Ok(Some(tier))
+    }
 
-        // Validate provider
-        let valid_providers = &["anthropic", "openai"];
-        if !valid_providers.contains(&self.model.provider.as_str()) {
-            bail!(
-                "Invalid model provider '{}' in lit.toml. Must be one of: {}",
-                self.model.provider,
-                valid_providers.join(", ")
-            );
+    /// Whether requests can be sent to `provider`
+    pub fn has_provider(&self, provider: &str) -> bool {
+        provider == self.model.provider || self.providers.contains_key(provider)
+    }
+
+    /// The provider serving a model named in a prompt's `model:` chain: the
+    /// project provider when it serves the model's family (or the family is
+    /// unknown), else the family's own provider
+    pub fn provider_for_model(&self, model: &str) -> String {
+        match model_family(model) {
+            Some(family) if !provider_serves(&self.model.provider, family) => family.to_string(),
+            _ => self.model.provider.clone(),
         }
+    }
+}
 
-        Ok(())
+/// The provider a model name belongs to, for well-known families
+pub fn model_family(model: &str) -> Option<&'static str> {
+    if model.starts_with("claude-") {
+        Some("anthropic")
+    } else if ["gpt-", "chatgpt-", "o1", "o3", "o4"].iter().any(|p| model.starts_with(p)) {
+        Some("openai")
+    } else {
+        None
     }
+}
 
-    /// Resolve the API key from the environment variable specified in config
-    pub fn resolve_api_key(&self) -> Result<String> {
-        let key_env = self
-            .model
-            .api
-            .as_ref()
-            .map(|api| api.key_env.as_str())
-            .unwrap_or("LIT_API_KEY");
+/// Whether `provider` can serve models of `family`: Azure hosts OpenAI
+/// models, and OpenRouter proxies every family
+pub fn provider_serves(provider: &str, family: &str) -> bool {
+    provider == family || provider == "openrouter" || (provider == "azure" && family == "openai")
+}
 
-        std::env::var(key_env).with_context(|| {
-            format!(
-                "API key not found. Set the {} environment variable.\n\
-                 Hint: export {}=your-api-key",
-                key_env, key_env
-            )
-        })
+/// File extension used for generated files in a language
+pub fn language_extension(language: &str) -> &str {
+    match language {
+        "python" => "py",
+        "typescript" => "ts",
+        "javascript" => "js",
+        "rust" => "rs",
+        "go" => "go",
+        "ruby" => "rb",
+        "kotlin" => "kt",
+        "csharp" | "c#" => "cs",
+        "cpp" | "c++" => "cpp",
+        other => other,
+    }
+}
+
+/// Entry file of a generated module directory (`modular` mapping mode)
+pub fn module_entry_file(language: &str, module_name: &str) -> String {
+    match language {
+        "python" => "__init__.py".to_string(),
+        "rust" => "mod.rs".to_string(),
+        "go" => format!("{}.go", module_name),
+        other => format!("index.{}", language_extension(other)),
+    }
+}
+
+/// Match a `/`-separated path against a glob pattern.
+///
+/// `*` and `?` never cross a `/`; `**` matches any number of characters
+/// including `/`, and `**/` also matches zero directories.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(p: &[u8], s: &[u8]) -> bool {
+        match p.first() {
+            None => s.is_empty(),
+            Some(b'*') if p.get(1) == Some(&b'*') => {
+                if p.get(2) == Some(&b'/') {
+                    let rest = &p[3..];
+                    (0..=s.len()).any(|i| (i == 0 || s[i - 1] == b'/') && matches(rest, &s[i..]))
+                } else {
+                    (0..=s.len()).any(|i| matches(&p[2..], &s[i..]))
+                }
+            }
+            Some(b'*') => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != b'/')
+                .any(|i| matches(&p[1..], &s[i..])),
+            Some(b'?') => s.first().is_some_and(|&c| c != b'/') && matches(&p[1..], &s[1..]),
+            Some(&c) => s.first() == Some(&c) && matches(&p[1..], &s[1..]),
+        }
     }
+    matches(pattern.as_bytes(), path.as_bytes())
 }
 
 #[cfg(test)]
@@ -368,6 +1597,9 @@ version = "3.12"
 provider = "anthropic"
 model = "claude-sonnet-4-5-20250929"
 temperature = 0.0
+
+[layout]
+"prompts" = "src/"
 "#
             );
             let config = LitConfig::from_str(&toml);
@@ -375,6 +1607,67 @@ temperature = 0.0
         }
     }
 
+    #[test]
+    fn test_layout_validation() {
+        let inferred = VALID_CONFIG.replace("mapping = \"manifest\"", "mapping = \"inferred\"");
+        let err = LitConfig::from_str(&inferred).unwrap_err();
+        assert!(err.to_string().contains("requires a [layout] section"));
+
+        let bad = format!("{}\n[layout]\n\"prompts\" = \"src/{{stem}}.py\"\n", inferred);
+        let err = LitConfig::from_str(&bad).unwrap_err();
+        assert!(err.to_string().contains("Invalid [layout] template"));
+
+        let good = format!("{}\n[layout]\n\"prompts/models\" = \"src/models/\"\n", inferred);
+        let config = LitConfig::from_str(&good).unwrap();
+        assert_eq!(
+            config.infer_output(Path::new("prompts/models/v1/user.prompt.md"), None),
+            Some(PathBuf::from("src/models/v1/user.py"))
+        );
+        assert_eq!(config.infer_output(Path::new("prompts/api/items.prompt.md"), None), None);
+        // Directory keys match whole path components
+        assert_eq!(config.infer_output(Path::new("prompts/models2/x.prompt.md"), None), None);
+    }
+
+    #[test]
+    fn test_code_dir() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.code_dir(Path::new("/repo")), PathBuf::from("/repo/code.lock"));
+
+        let custom = VALID_CONFIG.replace("[project]\n", "[project]\ncode_dir = \"generated/app\"\n");
+        let config = LitConfig::from_str(&custom).unwrap();
+        assert_eq!(config.code_dir(Path::new("/repo")), PathBuf::from("/repo/generated/app"));
+
+        for bad in ["/abs", "../up", "prompts/gen", ".lit", ""] {
+            let invalid = VALID_CONFIG.replace("[project]\n", &format!("[project]\ncode_dir = \"{}\"\n", bad));
+            let err = LitConfig::from_str(&invalid).unwrap_err();
+            assert!(err.to_string().contains("Invalid code_dir"), "{}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn test_prompt_dirs() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.project.prompt_dirs, vec![PathBuf::from("prompts")]);
+
+        let custom = VALID_CONFIG.replace("[project]\n", "[project]\nprompt_dirs = [\"prompts\", \"specs\"]\n");
+        let config = LitConfig::from_str(&custom).unwrap();
+        assert!(config.project.in_prompt_dir(Path::new("specs/api.prompt.md")));
+        assert!(!config.project.in_prompt_dir(Path::new("specsheet/api.prompt.md")));
+        assert_eq!(config.project.prompt_dirs_display(), "prompts/, specs/");
+
+        let custom = VALID_CONFIG.replace("[project]\n", "[project]\nexclude = [\"prompts/wip\", \"**/_*.prompt.md\"]\n");
+        let config = LitConfig::from_str(&custom).unwrap();
+        assert!(config.project.is_excluded(Path::new("prompts/wip/draft.prompt.md")));
+        assert!(config.project.is_excluded(Path::new("prompts/api/_template.prompt.md")));
+        assert!(!config.project.is_excluded(Path::new("prompts/wipe.prompt.md")));
+        assert!(!config.project.is_excluded(Path::new("prompts/api/users.prompt.md")));
+
+        for bad in ["[]", "[\"../specs\"]", "[\".lit/prompts\"]", "[\"code.lock/specs\"]"] {
+            let invalid = VALID_CONFIG.replace("[project]\n", &format!("[project]\nprompt_dirs = {}\n", bad));
+            assert!(LitConfig::from_str(&invalid).is_err(), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_static_files_parsing() {
         let toml = r##"
@@ -405,6 +1698,29 @@ content = "# Config package"
         assert_eq!(config.r#static[0].content, "");
         assert_eq!(config.r#static[1].path, "src/config/__init__.py");
         assert_eq!(config.r#static[1].content, "# Config package");
+        assert!(config.r#static[1].source.is_none());
+
+        let binary = format!(
+            "{}\n[[static]]\npath = \"static/logo.png\"\nsource = \"assets/logo.png\"\n\n\
+             [[static]]\npath = \"bin/run.sh\"\ncontent = \"#!/bin/sh\"\nmode = \"0755\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&binary).unwrap();
+        assert_eq!(config.r#static[0].source.as_deref(), Some(Path::new("assets/logo.png")));
+        assert_eq!(config.r#static[1].mode, Some(FileMode(0o755)));
+
+        let both = format!("{}\n[[static]]\npath = \"a.png\"\ncontent = \"x\"\nsource = \"a.png\"\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&both).unwrap_err().to_string().contains("both content and source"));
+        let outside = format!("{}\n[[static]]\npath = \"a.png\"\nsource = \"../a.png\"\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&outside).unwrap_err().to_string().contains("Invalid [[static]] source"));
+        for (mode, ok) in [("0o755", true), ("\"644\"", true), ("755", false), ("\"rwx\"", false)] {
+            let toml = format!("{}\n[[static]]\npath = \"a.sh\"\nmode = {}\n", VALID_CONFIG, mode);
+            assert_eq!(LitConfig::from_str(&toml).is_ok(), ok, "mode = {}", mode);
+        }
+
+        let escaping = format!("{}\n[[static]]\npath = \"../outside.py\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&escaping).unwrap_err();
+        assert!(err.to_string().contains("Invalid [[static]] path"), "got: {}", err);
     }
 
     #[test]
@@ -441,6 +1757,469 @@ output_per_million = 25.0
         assert_eq!(pricing.output_per_million, 25.0);
     }
 
+    #[test]
+    fn test_cache_expiry_config() {
+        let toml = r#"
+[project]
+name = "test"
+version = "0.1.0"
+mapping = "manifest"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+
+[cache]
+expire_before = "2025-06-01"
+deprecated_models = ["claude-3-opus"]
+"#;
+        let config = LitConfig::from_str(toml).unwrap();
+        assert_eq!(
+            config.cache.expire_before,
+            Some(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap())
+        );
+        assert_eq!(config.cache.deprecated_models, vec!["claude-3-opus"]);
+    }
+
+    #[test]
+    fn test_no_cache_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert!(config.cache.expire_before.is_none());
+        assert!(config.cache.deprecated_models.is_empty());
+    }
+
+    #[test]
+    fn test_system_prompt_template_config() {
+        let toml = format!(
+            "{}\n[generation]\nsystem_prompt_template = \"Write {{language}} code.\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(
+            config.generation.system_prompt_template.as_deref(),
+            Some("Write {language} code.")
+        );
+
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert!(config.generation.system_prompt_template.is_none());
+    }
+
+    #[test]
+    fn test_context_strategy_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.generation.context_strategy, ContextStrategy::Full);
+
+        let toml = format!(
+            "{}\n[generation]\ncontext_strategy = \"summary\"\nsummary_model = \"claude-haiku-4-5\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.generation.context_strategy, ContextStrategy::Summary);
+        assert_eq!(config.generation.summary_model.as_deref(), Some("claude-haiku-4-5"));
+
+        let toml = format!("{}\n[generation]\ncontext_strategy = \"everything\"\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).is_err());
+    }
+
+    #[test]
+    fn test_interfaces_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert!(!config.generation.interfaces);
+
+        let toml = format!("{}\n[generation]\ninterfaces = true\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).unwrap().generation.interfaces);
+
+        let toml = format!(
+            "{}\n[generation]\ninterfaces = true\ncontext_strategy = \"summary\"\n",
+            VALID_CONFIG
+        );
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("interfaces"));
+    }
+
+    #[test]
+    fn test_confirm_above_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.generation.confirm_above(), Some(10));
+
+        let toml = format!("{}\n[generation]\nconfirm_above = 25\n", VALID_CONFIG);
+        assert_eq!(LitConfig::from_str(&toml).unwrap().generation.confirm_above(), Some(25));
+
+        let toml = format!("{}\n[generation]\nconfirm_above = 0\n", VALID_CONFIG);
+        assert_eq!(LitConfig::from_str(&toml).unwrap().generation.confirm_above(), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("prompts/frontend/**", "prompts/frontend/app.prompt.md"));
+        assert!(glob_match("prompts/frontend/**", "prompts/frontend/a/b.prompt.md"));
+        assert!(glob_match("prompts/**/*.prompt.md", "prompts/x.prompt.md"));
+        assert!(glob_match("prompts/**/*.prompt.md", "prompts/a/b/x.prompt.md"));
+        assert!(glob_match("prompts/api/*.prompt.md", "prompts/api/users.prompt.md"));
+        assert!(!glob_match("prompts/api/*.prompt.md", "prompts/api/v2/users.prompt.md"));
+        assert!(glob_match("prompts/?.prompt.md", "prompts/a.prompt.md"));
+        assert!(!glob_match("prompts/backend/**", "prompts/frontend/app.prompt.md"));
+    }
+
+    #[test]
+    fn test_language_profiles_resolution() {
+        let toml = format!(
+            r#"{}
+[[language.profiles]]
+paths = ["prompts/frontend/**"]
+language = "typescript"
+version = "5.6"
+framework = {{ name = "react", version = "19" }}
+
+[[language.profiles]]
+paths = ["prompts/scripts/*.prompt.md"]
+language = "bash"
+version = "5"
+"#,
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.language.profiles.len(), 2);
+
+        let fe = config.resolve_language(Path::new("prompts/frontend/app.prompt.md"), None);
+        assert_eq!(fe.language, "typescript");
+        assert_eq!(fe.version, "5.6");
+        assert_eq!(fe.framework.as_ref().unwrap().name, "react");
+        assert_eq!(fe.profile, Some(0));
+
+        let script = config.resolve_language(Path::new("prompts/scripts/deploy.prompt.md"), None);
+        assert_eq!(script.language, "bash");
+        assert!(script.framework.is_none());
+
+        let backend = config.resolve_language(Path::new("prompts/models/user.prompt.md"), None);
+        assert_eq!(backend.language, "python");
+        assert_eq!(backend.framework.as_ref().unwrap().name, "fastapi");
+        assert_eq!(backend.profile, None);
+
+        // Frontmatter override still wins for the language name
+        let overridden =
+            config.resolve_language(Path::new("prompts/frontend/app.prompt.md"), Some("javascript"));
+        assert_eq!(overridden.language, "javascript");
+        assert_eq!(overridden.version, "5.6");
+    }
+
+    #[test]
+    fn test_language_profile_requires_paths() {
+        let toml = format!(
+            "{}\n[[language.profiles]]\npaths = []\nlanguage = \"go\"\nversion = \"1.23\"\n",
+            VALID_CONFIG
+        );
+        let err = LitConfig::from_str(&toml).unwrap_err();
+        assert!(err.to_string().contains("no paths"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_commit_config() {
+        assert!(!LitConfig::from_str(VALID_CONFIG).unwrap().commit.regenerate);
+        let toml = format!("{}\n[commit]\nregenerate = true\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).unwrap().commit.regenerate);
+    }
+
+    #[test]
+    fn test_providers_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert!(config.providers.is_empty());
+        assert!(config.has_provider("anthropic"));
+        assert!(!config.has_provider("openai"));
+        assert_eq!(config.provider_for_model("gpt-4o"), "openai");
+        assert_eq!(config.provider_for_model("my-finetune"), "anthropic");
+        assert!(config.resolve_provider_key("openai").is_err());
+
+        let toml = format!("{}\n[providers.openai]\nkey_env = \"LIT_TEST_OPENAI_KEY\"\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert!(config.has_provider("openai"));
+        unsafe { std::env::set_var("LIT_TEST_OPENAI_KEY", "sk-test") };
+        assert_eq!(config.resolve_provider_key("openai").unwrap(), "sk-test");
+
+        let toml = format!("{}\n[providers.mystery]\nkey_env = \"X\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("[providers.mystery]"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_resolve_provider_key_order() {
+        keychain::memory::install();
+        let toml = format!("{}\n[providers.openrouter]\nkey_env = \"LIT_TEST_OPENROUTER_KEY\"\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        unsafe { std::env::remove_var("LIT_TEST_OPENROUTER_KEY") };
+        let err = config.resolve_provider_key("openrouter").unwrap_err().to_string();
+        assert!(err.contains("LIT_TEST_OPENROUTER_KEY") && err.contains("lit auth login openrouter"), "got: {}", err);
+
+        // The keychain is the fallback, the environment variable wins over it
+        keychain::store("openrouter", "sk-keychain").unwrap();
+        assert_eq!(config.resolve_provider_key("openrouter").unwrap(), "sk-keychain");
+        unsafe { std::env::set_var("LIT_TEST_OPENROUTER_KEY", "sk-env") };
+        assert_eq!(config.resolve_provider_key("openrouter").unwrap(), "sk-env");
+        unsafe { std::env::remove_var("LIT_TEST_OPENROUTER_KEY") };
+        keychain::delete("openrouter").unwrap();
+    }
+
+    #[test]
+    fn test_model_tiers() {
+        let toml = format!(
+            "{}\n[model.tiers.scaffold]\nmodel = \"claude-haiku-4-5\"\npaths = [\"prompts/**/__init__.prompt.md\"]\n\n\
+             [model.tiers.complex]\nmodel = \"gpt-4o\"\npaths = [\"prompts/api/**\"]\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        let tier = |path: &str, name: Option<&str>| {
+            config.resolve_tier(Path::new(path), name).map(|t| t.map(|t| t.model.clone()))
+        };
+        assert_eq!(tier("prompts/models/__init__.prompt.md", None).unwrap().as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(tier("prompts/api/users.prompt.md", None).unwrap().as_deref(), Some("gpt-4o"));
+        assert_eq!(tier("prompts/models/user.prompt.md", None).unwrap(), None);
+        assert_eq!(tier("prompts/models/user.prompt.md", Some("complex")).unwrap().as_deref(), Some("gpt-4o"));
+        assert!(tier("prompts/models/user.prompt.md", Some("mystery")).unwrap_err().to_string().contains("scaffold"));
+        // Both tiers' globs match: the prompt has to pick
+        assert!(tier("prompts/api/__init__.prompt.md", None).unwrap_err().to_string().contains("tier:"));
+        assert_eq!(config.model.tiers["complex"].route(&config).provider, "openai");
+
+        let toml = format!("{}\n[model.tiers.cheap]\nmodel = \"x\"\nprovider = \"mystery\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("[model.tiers.cheap]"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_azure_and_openrouter_config() {
+        let base = VALID_CONFIG.replace("provider = \"anthropic\"", "provider = \"azure\"");
+        let err = LitConfig::from_str(&base).unwrap_err().to_string();
+        assert!(err.contains("needs base_url"), "got: {}", err);
+
+        let toml = base.replace(
+            "key_env = \"LIT_API_KEY\"",
+            "key_env = \"AZURE_KEY\"\nbase_url = \"https://acme.openai.azure.com\"\napi_version = \"2024-10-21\"",
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        let api = config.api_config("azure").unwrap();
+        assert_eq!(api.api_version.as_deref(), Some("2024-10-21"));
+        // Azure serves OpenAI models itself
+        assert_eq!(config.provider_for_model("gpt-4o"), "azure");
+        assert_eq!(config.provider_for_model("claude-opus-4-6"), "anthropic");
+
+        let toml = format!(
+            "{}\n[providers.openrouter]\nkey_env = \"OPENROUTER_KEY\"\nbase_url = \"ftp://nope\"\n",
+            VALID_CONFIG
+        );
+        assert!(
+            LitConfig::from_str(&toml)
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid base_url")
+        );
+        let toml = format!(
+            "{}\n[providers.openrouter]\nkey_env = \"OPENROUTER_KEY\"\nheaders = {{ \"X-Title\" = \"demo\" }}\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(
+            config.api_config("openrouter").unwrap().headers["X-Title"],
+            "demo"
+        );
+    }
+
+    #[test]
+    fn test_rate_limits_config() {
+        assert!(LitConfig::from_str(VALID_CONFIG).unwrap().model.limits.is_empty());
+        let toml = format!("{}\n[model.limits]\nrequests_per_minute = 50\ntokens_per_minute = 40000\n", VALID_CONFIG);
+        let limits = LitConfig::from_str(&toml).unwrap().model.limits;
+        assert_eq!(limits, RateLimits { requests_per_minute: Some(50), tokens_per_minute: Some(40000) });
+        let err = LitConfig::from_str(&toml.replace("= 50", "= 0")).unwrap_err().to_string();
+        assert!(err.contains("[model.limits]"), "got: {}", err);
+
+        let toml = format!(
+            "{}\n[providers.openai]\nkey_env = \"OPENAI_API_KEY\"\n\n[providers.openai.limits]\nrequests_per_minute = 500\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.rate_limits("openai").requests_per_minute, Some(500));
+        assert!(config.rate_limits(&config.model.provider.clone()).is_empty());
+        let err = LitConfig::from_str(&toml.replace("= 500", "= 0")).unwrap_err().to_string();
+        assert!(err.contains("[providers.openai.limits]"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_api_network_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = VALID_CONFIG.replace(
+            "key_env = \"LIT_API_KEY\"",
+            "key_env = \"LIT_API_KEY\"\nproxy_url = \"http://proxy.corp:8080\"\nca_bundle = \"certs/corp.pem\"\ntimeout_secs = 120",
+        );
+        std::fs::write(dir.path().join("lit.toml"), &toml).unwrap();
+        let config = LitConfig::from_file(&dir.path().join("lit.toml")).unwrap();
+        let api = config.api_config("anthropic").unwrap();
+        assert_eq!(api.proxy_url.as_deref(), Some("http://proxy.corp:8080"));
+        assert_eq!(api.ca_bundle.as_deref(), Some(dir.path().join("certs/corp.pem").as_path()));
+        assert_eq!(api.timeout_secs, Some(120));
+
+        let err = LitConfig::from_str(&toml.replace("http://proxy", "proxy")).unwrap_err().to_string();
+        assert!(err.contains("Invalid proxy_url"), "got: {}", err);
+        let err = LitConfig::from_str(&toml.replace("= 120", "= 0")).unwrap_err().to_string();
+        assert!(err.contains("timeout_secs"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_lint_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.lint.min_body_words, 20);
+        assert!(config.lint.rules.is_empty());
+
+        let toml = format!(
+            "{}\n[lint]\nmin_body_words = 5\n\n[lint.rules]\nunused-import = \"off\"\nvague-body = \"error\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.lint.min_body_words, 5);
+        assert_eq!(config.lint.rules["unused-import"], LintLevel::Off);
+        assert_eq!(config.lint.rules["vague-body"], LintLevel::Error);
+
+        let toml = format!("{}\n[lint.rules]\nno-such-rule = \"warn\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("Unknown lint rule 'no-such-rule'"), "got: {}", err);
+
+        let toml = format!("{}\n[lint.rules]\nvague-body = \"loud\"\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).is_err());
+    }
+
+    #[test]
+    fn test_security_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.security.secrets, LintLevel::Warn);
+
+        let toml = format!("{}\n[security]\nsecrets = \"error\"\nallow = [\"^sk_test_\"]\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.security.secrets, LintLevel::Error);
+        assert_eq!(config.security.allow, vec!["^sk_test_"]);
+
+        let toml = format!("{}\n[security]\nallow = [\"(unclosed\"]\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("Invalid [security] allow pattern"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_file_header_config() {
+        let toml = format!("{}\n[generation]\nfile_header = \"From {{prompt}} by {{model}}\"\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.generation.file_header.as_deref(), Some("From {prompt} by {model}"));
+
+        let toml = format!("{}\n[generation]\nfile_header = \"From {{author}}\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+        assert!(err.contains("Invalid [generation] file_header"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_limits_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.limits.max_prompt_bytes, 256 * 1024);
+        assert_eq!(config.limits.warn_prompt_tokens, 10_000);
+
+        let toml = format!("{}\n[limits]\nmax_prompt_tokens = 0\nwarn_prompt_tokens = 20000\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.limits.max_prompt_tokens, 0);
+        assert_eq!(config.limits.max_prompt_bytes, 256 * 1024);
+
+        let toml = format!("{}\n[limits]\nmax_prompt_tokens = 5000\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err();
+        assert!(err.to_string().contains("warn_prompt_tokens"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_format_config() {
+        let toml = format!("{}\n[format]\npy = \"black -q -\"\n", VALID_CONFIG);
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.format["py"], "black -q -");
+
+        let toml = format!("{}\n[format]\n\".py\" = \"black -q -\"\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).is_err());
+    }
+
+    #[test]
+    fn test_describe_and_forge_config() {
+        let config = LitConfig::from_str(VALID_CONFIG).unwrap();
+        assert!(config.describe.base.is_none());
+        assert!(config.forge.provider.is_none());
+
+        let toml = format!(
+            "{}\n[describe]\nbase = \"develop\"\n[forge]\nprovider = \"gitlab\"\nrepo = \"acme/shop\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.describe.base.as_deref(), Some("develop"));
+        assert_eq!(config.forge.provider.as_deref(), Some("gitlab"));
+
+        for (forge, error) in [
+            ("provider = \"bitbucket\"", "Invalid [forge] provider"),
+            ("repo = \"shop\"", "Invalid [forge] repo"),
+            ("api_url = \"gitlab.acme.dev\"", "Invalid [forge] api_url"),
+        ] {
+            let toml = format!("{}\n[forge]\n{}\n", VALID_CONFIG, forge);
+            let err = LitConfig::from_str(&toml).unwrap_err().to_string();
+            assert!(err.contains(error), "got: {}", err);
+        }
+    }
+
+    #[test]
+    fn test_budget_alerts_config() {
+        let toml = format!(
+            "{}\n[budget.alerts]\nwarn_at_usd_per_run = 2.0\nwebhook = \"https://hooks.example.com/lit\"\n",
+            VALID_CONFIG
+        );
+        let config = LitConfig::from_str(&toml).unwrap();
+        assert_eq!(config.budget.alerts.warn_at_usd_per_run, Some(2.0));
+        assert_eq!(config.budget.alerts.warn_at_usd_per_week, None);
+
+        let toml = format!("{}\n[budget.alerts]\nwarn_at_usd_per_week = 0\n", VALID_CONFIG);
+        assert!(LitConfig::from_str(&toml).is_err());
+
+        let toml = format!("{}\n[budget.alerts]\nwebhook = \"hooks.example.com\"\n", VALID_CONFIG);
+        let err = LitConfig::from_str(&toml).unwrap_err();
+        assert!(err.to_string().contains("http(s) URL"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_conventions_default_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lit.toml"), VALID_CONFIG).unwrap();
+
+        // No conventions.md — nothing loaded
+        let config = LitConfig::from_file(&dir.path().join("lit.toml")).unwrap();
+        assert!(config.generation.conventions_content.is_none());
+
+        std::fs::write(dir.path().join("conventions.md"), "Use snake_case.\n").unwrap();
+        let config = LitConfig::from_file(&dir.path().join("lit.toml")).unwrap();
+        assert_eq!(
+            config.generation.conventions_content.as_deref(),
+            Some("Use snake_case.\n")
+        );
+    }
+
+    #[test]
+    fn test_conventions_configured_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = format!("{}\n[generation]\nconventions = \"docs/style.md\"\n", VALID_CONFIG);
+        std::fs::write(dir.path().join("lit.toml"), toml).unwrap();
+
+        let err = LitConfig::from_file(&dir.path().join("lit.toml")).unwrap_err();
+        assert!(err.to_string().contains("docs/style.md"), "got: {}", err);
+
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/style.md"), "Docstrings everywhere.\n").unwrap();
+        let config = LitConfig::from_file(&dir.path().join("lit.toml")).unwrap();
+        assert_eq!(
+            config.generation.conventions_content.as_deref(),
+            Some("Docstrings everywhere.\n")
+        );
+    }
+
     #[test]
     fn test_no_pricing_override() {
         // Existing configs without [model.pricing] should still parse fine
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::core::atomic;
+
+/// Drop context files whose content is identical to another file's.
+///
+/// The first path in sorted order is kept. Returns the deduplicated context
+/// and the `(dropped, kept)` pairs.
+pub fn dedupe_context(
+    context: HashMap<PathBuf, String>,
+) -> (HashMap<PathBuf, String>, Vec<(PathBuf, PathBuf)>) {
+    let mut entries: Vec<_> = context.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut seen: HashMap<String, PathBuf> = HashMap::new();
+    let mut kept = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (path, content) in entries {
+        let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+        match seen.get(&hash) {
+            Some(original) => duplicates.push((path, original.clone())),
+            None => {
+                seen.insert(hash, path.clone());
+                kept.insert(path, content);
+            }
+        }
+    }
+    (kept, duplicates)
+}
+
+/// Location of a generated file's interface artifact:
+/// `<interfaces_dir>/<output-path>.txt`
+pub fn interface_path(interfaces_dir: &Path, output_path: &Path) -> PathBuf {
+    let mut name = output_path.as_os_str().to_os_string();
+    name.push(".txt");
+    interfaces_dir.join(name)
+}
+
+/// Extract a generated file's interface and write it to `interfaces_dir`.
+pub fn write_interface(interfaces_dir: &Path, output_path: &Path, content: &str) -> Result<()> {
+    let path = interface_path(interfaces_dir, output_path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    atomic::write(&path, extract_signatures(output_path, content))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reduce a generated file to its public interface.
+///
+/// Used by the `signatures-only` context strategy so that downstream prompts
+/// see what they can call without the implementation. Supports Python,
+/// TypeScript/JavaScript, Rust and Go; other files are returned unchanged.
+pub fn extract_signatures(path: &Path, content: &str) -> String {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let lines = match ext {
+        "py" => python_signatures(content),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" => brace_signatures(content, typescript_item),
+        "rs" => brace_signatures(content, rust_item),
+        "go" => brace_signatures(content, go_item),
+        _ => return content.to_string(),
+    };
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+// ---------- Python ----------
+
+fn python_signatures(content: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut decorators: Vec<&str> = Vec::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let top_level = trimmed.len() == line.len();
+
+        if top_level && (trimmed.starts_with("import ") || trimmed.starts_with("from ")) {
+            out.push(line.to_string());
+        } else if trimmed.starts_with('@') {
+            decorators.push(line);
+            continue;
+        } else if let Some(name) = python_def_name(trimmed) {
+            if is_public_python_name(name) {
+                out.extend(decorators.iter().map(|d| d.to_string()));
+                // Signatures may span several lines; stop at the closing colon
+                let mut signature = line.to_string();
+                while !signature.trim_end().ends_with(':') {
+                    match lines.next() {
+                        Some(next) => {
+                            signature.push('\n');
+                            signature.push_str(next);
+                        }
+                        None => break,
+                    }
+                }
+                if !trimmed.starts_with("class ") {
+                    signature.push_str(" ...");
+                }
+                out.push(signature);
+            }
+        } else if !top_level && is_python_annotated_attribute(trimmed) {
+            // Class fields (dataclasses, pydantic models) are part of the interface
+            out.push(line.to_string());
+        }
+        decorators.clear();
+    }
+    out
+}
+
+fn python_def_name(trimmed: &str) -> Option<&str> {
+    let rest = trimmed
+        .strip_prefix("def ")
+        .or_else(|| trimmed.strip_prefix("async def "))
+        .or_else(|| trimmed.strip_prefix("class "))?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+fn is_public_python_name(name: &str) -> bool {
+    !name.starts_with('_') || (name.starts_with("__") && name.ends_with("__"))
+}
+
+/// `name: Type` or `name: Type = default` inside a class body
+fn is_python_annotated_attribute(trimmed: &str) -> bool {
+    let Some((name, rest)) = trimmed.split_once(':') else {
+        return false;
+    };
+    let name = name.trim_end();
+    !name.is_empty()
+        && !name.starts_with('_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !rest.trim().is_empty()
+        && !trimmed.trim_end().ends_with(':')
+}
+
+// ---------- Brace languages ----------
+
+/// How a matched declaration is kept
+enum Item {
+    /// Keep the whole brace-delimited block (types, interfaces)
+    Block,
+    /// Keep the signature and elide the body (functions)
+    Signature,
+    /// Keep the line as-is
+    Line,
+    /// Drop the declaration and its body (private items)
+    Skip,
+}
+
+fn brace_signatures(content: &str, classify: fn(&str) -> Option<Item>) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let Some(item) = classify(line) else {
+            continue;
+        };
+        match item {
+            Item::Line => out.push(line.to_string()),
+            Item::Block => {
+                out.push(line.to_string());
+                let mut depth = brace_delta(line);
+                while depth > 0 {
+                    let Some(next) = lines.next() else { break };
+                    depth += brace_delta(next);
+                    out.push(next.to_string());
+                }
+            }
+            Item::Signature | Item::Skip => {
+                let mut signature = line.to_string();
+                while !signature.contains('{') && !signature.trim_end().ends_with(';') {
+                    match lines.next() {
+                        Some(next) => {
+                            signature.push('\n');
+                            signature.push_str(next);
+                        }
+                        None => break,
+                    }
+                }
+                let mut depth = brace_delta(&signature);
+                if let Some(idx) = signature.find('{') {
+                    signature.truncate(idx);
+                    signature = format!("{}{{ ... }}", signature);
+                }
+                // Skip the body
+                while depth > 0 {
+                    let Some(next) = lines.next() else { break };
+                    depth += brace_delta(next);
+                }
+                if matches!(item, Item::Signature) {
+                    out.push(signature);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn brace_delta(line: &str) -> i32 {
+    line.chars().fold(0, |acc, c| match c {
+        '{' => acc + 1,
+        '}' => acc - 1,
+        _ => acc,
+    })
+}
+
+fn typescript_item(line: &str) -> Option<Item> {
+    if line.starts_with("import ") {
+        return Some(Item::Line);
+    }
+    let rest = line.strip_prefix("export ")?;
+    let rest = rest.strip_prefix("default ").unwrap_or(rest);
+    if rest.starts_with("interface ") || rest.starts_with("type ") || rest.starts_with("enum ") {
+        Some(Item::Block)
+    } else if rest.starts_with("function ")
+        || rest.starts_with("async function ")
+        || rest.starts_with("class ")
+    {
+        Some(Item::Signature)
+    } else {
+        Some(Item::Line)
+    }
+}
+
+fn rust_item(line: &str) -> Option<Item> {
+    let trimmed = line.trim_start();
+    if line.starts_with("impl") || (line.starts_with('}') && line.trim() == "}") {
+        // impl headers and their closing braces frame the pub methods
+        return Some(Item::Line);
+    }
+    let Some(rest) = trimmed.strip_prefix("pub ") else {
+        let private_item = ["fn ", "async fn ", "struct ", "enum ", "trait ", "mod "]
+            .iter()
+            .any(|kw| trimmed.starts_with(kw));
+        return private_item.then_some(Item::Skip);
+    };
+    if rest.starts_with("struct ")
+        || rest.starts_with("enum ")
+        || rest.starts_with("trait ")
+        || rest.starts_with("type ")
+        || rest.starts_with("const ")
+    {
+        Some(Item::Block)
+    } else if rest.starts_with("fn ") || rest.starts_with("async fn ") {
+        Some(Item::Signature)
+    } else {
+        Some(Item::Line)
+    }
+}
+
+fn go_item(line: &str) -> Option<Item> {
+    if let Some(rest) = line.strip_prefix("type ") {
+        return rest.starts_with(char::is_uppercase).then_some(Item::Block);
+    }
+    let rest = line.strip_prefix("func ")?;
+    // Skip a method receiver: func (s *Server) Name(...)
+    let rest = match rest.strip_prefix('(') {
+        Some(r) => r.split_once(')').map(|(_, name)| name.trim_start())?,
+        None => rest,
+    };
+    rest.starts_with(char::is_uppercase).then_some(Item::Signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_signatures() {
+        let code = r#"from pydantic import BaseModel
+import os
+
+class User(BaseModel):
+    id: int
+    name: str = "anon"
+
+    def greet(self, other: str) -> str:
+        return f"hi {other}"
+
+    def _secret(self):
+        return os.environ["X"]
+
+@app.get("/users")
+async def list_users(
+    limit: int = 10,
+) -> list[User]:
+    users = []
+    return users
+
+def _helper():
+    pass
+"#;
+        let sig = extract_signatures(Path::new("src/models.py"), code);
+        assert!(sig.contains("from pydantic import BaseModel"));
+        assert!(sig.contains("class User(BaseModel):"));
+        assert!(sig.contains("    id: int"));
+        assert!(sig.contains("    name: str = \"anon\""));
+        assert!(sig.contains("    def greet(self, other: str) -> str: ..."));
+        assert!(sig.contains("@app.get(\"/users\")\nasync def list_users(\n    limit: int = 10,\n) -> list[User]: ..."));
+        assert!(!sig.contains("_secret"));
+        assert!(!sig.contains("_helper"));
+        assert!(!sig.contains("return"));
+    }
+
+    #[test]
+    fn test_typescript_signatures() {
+        let code = r#"import { api } from "./api";
+
+export interface User {
+  id: number;
+  name: string;
+}
+
+export async function fetchUser(id: number): Promise<User> {
+  const res = await api.get(`/users/${id}`);
+  return res.data;
+}
+
+function internal() {
+  return 1;
+}
+
+export const MAX_USERS = 100;
+"#;
+        let sig = extract_signatures(Path::new("src/user.ts"), code);
+        assert!(sig.contains("export interface User {\n  id: number;\n  name: string;\n}"));
+        assert!(sig.contains("export async function fetchUser(id: number): Promise<User> { ... }"));
+        assert!(sig.contains("export const MAX_USERS = 100;"));
+        assert!(!sig.contains("internal"));
+        assert!(!sig.contains("api.get"));
+    }
+
+    #[test]
+    fn test_rust_signatures() {
+        let code = r#"pub struct Counter {
+    pub count: u32,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self { count: 0 }
+    }
+
+    fn bump(&mut self) {
+        self.count += 1;
+    }
+}
+"#;
+        let code = format!("{}\nfn private() {{\n    todo!()\n}}\n", code);
+        let sig = extract_signatures(Path::new("src/counter.rs"), &code);
+        assert!(sig.contains("pub struct Counter {\n    pub count: u32,\n}"));
+        assert!(sig.contains("impl Counter {"));
+        assert!(sig.contains("    pub fn new() -> Self { ... }"));
+        assert!(!sig.contains("bump"));
+        assert!(!sig.contains("Self { count: 0 }"));
+        assert!(!sig.contains("todo!"));
+        assert!(sig.trim_end().ends_with("impl Counter {\n    pub fn new() -> Self { ... }\n}"));
+    }
+
+    #[test]
+    fn test_go_signatures() {
+        let code = r#"package server
+
+type Server struct {
+	Addr string
+}
+
+func (s *Server) Start() error {
+	return nil
+}
+
+func helper() {}
+"#;
+        let sig = extract_signatures(Path::new("server.go"), code);
+        assert!(sig.contains("type Server struct {\n\tAddr string\n}"));
+        assert!(sig.contains("func (s *Server) Start() error { ... }"));
+        assert!(!sig.contains("helper"));
+    }
+
+    #[test]
+    fn test_dedupe_context() {
+        let context: HashMap<PathBuf, String> = [
+            ("src/models/user.py", "class User: ...\n"),
+            ("src/api/user.py", "class User: ...\n"),
+            ("src/db.py", "engine = None\n"),
+        ]
+        .into_iter()
+        .map(|(p, c)| (PathBuf::from(p), c.to_string()))
+        .collect();
+
+        let (kept, duplicates) = dedupe_context(context);
+        assert_eq!(kept.len(), 2);
+        assert!(kept.contains_key(Path::new("src/api/user.py")));
+        assert!(kept.contains_key(Path::new("src/db.py")));
+        assert_eq!(
+            duplicates,
+            vec![(PathBuf::from("src/models/user.py"), PathBuf::from("src/api/user.py"))]
+        );
+    }
+
+    #[test]
+    fn test_write_interface() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = Path::new("src/models/user.py");
+        write_interface(dir.path(), output, "def get_user(id):\n    return db.find(id)\n").unwrap();
+
+        let path = interface_path(dir.path(), output);
+        assert_eq!(path, dir.path().join("src/models/user.py.txt"));
+        let interface = std::fs::read_to_string(path).unwrap();
+        assert!(interface.contains("def get_user(id):"));
+        assert!(!interface.contains("db.find"));
+    }
+
+    #[test]
+    fn test_unknown_extension_unchanged() {
+        let code = "key: value\n";
+        assert_eq!(extract_signatures(Path::new("config.yaml"), code), code);
+    }
+}
@@ -0,0 +1,234 @@
+//! Credentials for git remotes (`lit push`, `lit pull`, `lit clone`).
+//!
+//! libgit2 asks for credentials through a callback, once per attempt, until
+//! one is accepted or the callback gives up. Each source is offered once,
+//! in order:
+//!
+//! - SSH remotes: ssh-agent, then `$LIT_GIT_SSH_KEY` (with
+//!   `$LIT_GIT_SSH_PASSPHRASE`), then `~/.ssh/id_ed25519`, `id_ecdsa` and
+//!   `id_rsa`
+//! - HTTPS remotes: `$LIT_GIT_TOKEN` (user `$LIT_GIT_USERNAME`, the URL's
+//!   user, or `x-access-token`), then git's configured credential helper
+//!
+//! so remotes work in CI and containers without a global credential helper.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use git2::{Cred, CredentialType, RemoteCallbacks};
+
+/// Access token for HTTPS remotes
+pub const TOKEN_ENV: &str = "LIT_GIT_TOKEN";
+/// User name sent with `LIT_GIT_TOKEN`
+pub const USERNAME_ENV: &str = "LIT_GIT_USERNAME";
+/// Private key for SSH remotes
+pub const SSH_KEY_ENV: &str = "LIT_GIT_SSH_KEY";
+/// Passphrase of `LIT_GIT_SSH_KEY`
+pub const SSH_PASSPHRASE_ENV: &str = "LIT_GIT_SSH_PASSPHRASE";
+
+/// Default key files under `~/.ssh/`, most preferred first
+const DEFAULT_SSH_KEYS: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Where credentials can come from
+#[derive(Debug, Default)]
+struct Sources {
+    token: Option<String>,
+    username: Option<String>,
+    ssh_agent: bool,
+    ssh_keys: Vec<PathBuf>,
+    ssh_passphrase: Option<String>,
+}
+
+impl Sources {
+    fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        let mut ssh_keys: Vec<PathBuf> = var(SSH_KEY_ENV).map(PathBuf::from).into_iter().collect();
+        if let Some(home) = std::env::var_os("HOME") {
+            let dir = PathBuf::from(home).join(".ssh");
+            ssh_keys.extend(DEFAULT_SSH_KEYS.iter().map(|key| dir.join(key)).filter(|key| key.is_file()));
+        }
+        Self {
+            token: var(TOKEN_ENV),
+            username: var(USERNAME_ENV),
+            ssh_agent: std::env::var_os("SSH_AUTH_SOCK").is_some(),
+            ssh_keys,
+            ssh_passphrase: var(SSH_PASSPHRASE_ENV),
+        }
+    }
+}
+
+/// A credential to offer, before it is turned into a libgit2 [`Cred`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Attempt {
+    Username(String),
+    SshAgent(String),
+    SshKey(String, PathBuf),
+    Token(String),
+    CredentialHelper,
+    Default,
+}
+
+/// The sources not yet offered for one remote operation
+#[derive(Debug)]
+struct Attempts {
+    sources: Sources,
+    ssh_keys: VecDeque<PathBuf>,
+    username_tried: bool,
+    agent_tried: bool,
+    token_tried: bool,
+    helper_tried: bool,
+    default_tried: bool,
+}
+
+impl Attempts {
+    fn new(sources: Sources) -> Self {
+        let ssh_keys = sources.ssh_keys.iter().cloned().collect();
+        Self {
+            sources,
+            ssh_keys,
+            username_tried: false,
+            agent_tried: false,
+            token_tried: false,
+            helper_tried: false,
+            default_tried: false,
+        }
+    }
+
+    /// The next credential of a type the remote accepts, or None when every
+    /// source has been offered
+    fn next(&mut self, username: Option<&str>, allowed: CredentialType) -> Option<Attempt> {
+        if allowed.contains(CredentialType::USERNAME) && !self.username_tried {
+            self.username_tried = true;
+            return Some(Attempt::Username(username.unwrap_or("git").to_string()));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username.unwrap_or("git").to_string();
+            if !self.agent_tried {
+                self.agent_tried = true;
+                if self.sources.ssh_agent {
+                    return Some(Attempt::SshAgent(user));
+                }
+            }
+            if let Some(key) = self.ssh_keys.pop_front() {
+                return Some(Attempt::SshKey(user, key));
+            }
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !self.token_tried {
+                self.token_tried = true;
+                if self.sources.token.is_some() {
+                    let user = self.sources.username.as_deref().or(username).unwrap_or("x-access-token");
+                    return Some(Attempt::Token(user.to_string()));
+                }
+            }
+            if !self.helper_tried {
+                self.helper_tried = true;
+                return Some(Attempt::CredentialHelper);
+            }
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !self.default_tried {
+            self.default_tried = true;
+            return Some(Attempt::Default);
+        }
+        None
+    }
+
+    fn credential(&mut self, url: &str, username: Option<&str>, allowed: CredentialType) -> Result<Cred, git2::Error> {
+        loop {
+            let attempt = self.next(username, allowed).ok_or_else(|| {
+                git2::Error::new(
+                    git2::ErrorCode::Auth,
+                    git2::ErrorClass::Net,
+                    format!("no credentials accepted for {}", url),
+                )
+            })?;
+            let cred = match attempt {
+                Attempt::Username(user) => Cred::username(&user),
+                Attempt::SshAgent(user) => Cred::ssh_key_from_agent(&user),
+                Attempt::SshKey(user, key) => {
+                    Cred::ssh_key(&user, None, &key, self.sources.ssh_passphrase.as_deref())
+                }
+                Attempt::Token(user) => Cred::userpass_plaintext(&user, self.sources.token.as_deref().unwrap_or("")),
+                Attempt::CredentialHelper => {
+                    git2::Config::open_default().and_then(|config| Cred::credential_helper(&config, url, username))
+                }
+                Attempt::Default => Cred::default(),
+            };
+            // A source that can't produce a credential (no helper configured,
+            // say) just moves on to the next
+            if let Ok(cred) = cred {
+                return Ok(cred);
+            }
+        }
+    }
+}
+
+/// Callbacks answering libgit2's credential requests from the environment
+pub fn callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut attempts = Attempts::new(Sources::from_env());
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| attempts.credential(url, username, allowed));
+    callbacks
+}
+
+/// Hint for a remote that rejected every credential offered
+pub fn auth_hint(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        format!(
+            "Hint: Set ${} to an access token for {} (and ${} if the host needs a user name), \
+             or configure a git credential helper.",
+            TOKEN_ENV, url, USERNAME_ENV
+        )
+    } else {
+        format!(
+            "Hint: Add your key to ssh-agent, or set ${} to a private key {} accepts \
+             (with ${} if it has a passphrase).",
+            SSH_KEY_ENV, url, SSH_PASSPHRASE_ENV
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_attempts_in_order() {
+        let mut attempts = Attempts::new(Sources {
+            ssh_agent: true,
+            ssh_keys: vec![PathBuf::from("/keys/deploy"), PathBuf::from("/home/me/.ssh/id_ed25519")],
+            ..Default::default()
+        });
+        let ssh = CredentialType::SSH_KEY;
+        assert_eq!(attempts.next(Some("git"), CredentialType::USERNAME), Some(Attempt::Username("git".to_string())));
+        assert_eq!(attempts.next(Some("git"), ssh), Some(Attempt::SshAgent("git".to_string())));
+        assert_eq!(attempts.next(Some("git"), ssh), Some(Attempt::SshKey("git".to_string(), "/keys/deploy".into())));
+        assert_eq!(
+            attempts.next(None, ssh),
+            Some(Attempt::SshKey("git".to_string(), "/home/me/.ssh/id_ed25519".into()))
+        );
+        // Everything offered: give up instead of looping
+        assert_eq!(attempts.next(Some("git"), ssh), None);
+        assert_eq!(attempts.next(Some("git"), CredentialType::USERNAME), None);
+    }
+
+    #[test]
+    fn test_https_attempts_in_order() {
+        let https = CredentialType::USER_PASS_PLAINTEXT;
+        let mut attempts = Attempts::new(Sources { token: Some("t0k3n".to_string()), ..Default::default() });
+        assert_eq!(attempts.next(None, https), Some(Attempt::Token("x-access-token".to_string())));
+        assert_eq!(attempts.next(None, https), Some(Attempt::CredentialHelper));
+        assert_eq!(attempts.next(None, https), None);
+
+        // A configured user name wins over the URL's; without a token only
+        // the credential helper is asked
+        let mut attempts = Attempts::new(Sources {
+            token: Some("t0k3n".to_string()),
+            username: Some("ci-bot".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(attempts.next(Some("me"), https), Some(Attempt::Token("ci-bot".to_string())));
+        let mut attempts = Attempts::new(Sources::default());
+        assert_eq!(attempts.next(Some("me"), https), Some(Attempt::CredentialHelper));
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Result, bail};
 
+use crate::core::error::LitError;
 use crate::core::prompt::Prompt;
 
 // ---------- Public types ----------
@@ -17,8 +18,9 @@ pub struct DagNode {
     /// Prompts that depend on this node (reverse edges)
     pub dependents: Vec<PathBuf>,
     /// Output files this prompt produces
-    #[allow(dead_code)]
     pub outputs: Vec<PathBuf>,
+    /// Marked `draft: true`, so never generated
+    pub draft: bool,
 }
 
 /// The dependency DAG for prompt resolution
@@ -30,6 +32,42 @@ pub struct Dag {
     order: Vec<PathBuf>,
 }
 
+/// A change of which prompt owns an output file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnershipChange {
+    pub output: PathBuf,
+    /// Owner in the base DAG (None if the output is new)
+    pub before: Option<PathBuf>,
+    /// Owner in the new DAG (None if the output was removed)
+    pub after: Option<PathBuf>,
+}
+
+/// Structural differences between two DAGs (e.g. two git refs)
+#[derive(Debug, Clone, Default)]
+pub struct DagDiff {
+    pub added_prompts: Vec<PathBuf>,
+    pub removed_prompts: Vec<PathBuf>,
+    /// Added `(prompt, import)` edges
+    pub added_edges: Vec<(PathBuf, PathBuf)>,
+    /// Removed `(prompt, import)` edges
+    pub removed_edges: Vec<(PathBuf, PathBuf)>,
+    pub ownership_changes: Vec<OwnershipChange>,
+    /// Prompts in both DAGs whose number of transitive dependents changed:
+    /// `(prompt, before, after)`
+    pub cascade_changes: Vec<(PathBuf, usize, usize)>,
+}
+
+impl DagDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_prompts.is_empty()
+            && self.removed_prompts.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.ownership_changes.is_empty()
+            && self.cascade_changes.is_empty()
+    }
+}
+
 // ---------- Implementation ----------
 
 impl Dag {
@@ -49,6 +87,7 @@ impl Dag {
                 imports: prompt.frontmatter.imports.clone(),
                 dependents: Vec::new(),
                 outputs: prompt.frontmatter.outputs.clone(),
+                draft: prompt.frontmatter.draft,
             };
             nodes.insert(prompt.path.clone(), node);
         }
@@ -74,14 +113,7 @@ impl Dag {
             }
         }
         if !missing_imports.is_empty() {
-            let details: Vec<String> = missing_imports
-                .iter()
-                .map(|(from, to)| format!("  {} imports {} (not found)", from.display(), to.display()))
-                .collect();
-            bail!(
-                "Missing imports:\n{}",
-                details.join("\n")
-            );
+            return Err(LitError::MissingImports { imports: missing_imports }.into());
         }
 
         // Validate no output conflicts
@@ -163,6 +195,257 @@ impl Dag {
             .collect()
     }
 
+    /// All prompts `path` transitively imports, in topological order.
+    pub fn ancestors(&self, path: &Path) -> Vec<PathBuf> {
+        self.reachable(path, |node| &node.imports)
+    }
+
+    /// All prompts that transitively import `path`, in topological order.
+    pub fn descendants(&self, path: &Path) -> Vec<PathBuf> {
+        self.reachable(path, |node| &node.dependents)
+    }
+
+    /// Length of the longest import chain from a root to `path`
+    /// (roots have depth 0). None if `path` is not in the DAG.
+    pub fn depth(&self, path: &Path) -> Option<usize> {
+        self.nodes.get(path)?;
+
+        // Topological order guarantees imports are resolved first
+        let mut depths: HashMap<&PathBuf, usize> = HashMap::new();
+        for p in &self.order {
+            let depth = self.nodes[p]
+                .imports
+                .iter()
+                .filter_map(|i| depths.get(i))
+                .map(|d| d + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(p, depth);
+            if p == path {
+                return Some(depth);
+            }
+        }
+        None
+    }
+
+    /// Prompts grouped by depth, each group in topological order. Prompts
+    /// in one level don't import each other, so a level can be generated
+    /// all at once once the levels before it are done.
+    pub fn levels(&self) -> Vec<Vec<PathBuf>> {
+        let mut depths: HashMap<&PathBuf, usize> = HashMap::new();
+        let mut levels: Vec<Vec<PathBuf>> = Vec::new();
+        for p in &self.order {
+            let depth = self.nodes[p]
+                .imports
+                .iter()
+                .filter_map(|i| depths.get(i))
+                .map(|d| d + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(p, depth);
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(p.clone());
+        }
+        levels
+    }
+
+    /// The DAG restricted to `paths`, keeping only edges between them.
+    /// Unknown paths are ignored.
+    pub fn subgraph(&self, paths: &[PathBuf]) -> Dag {
+        let keep: HashSet<&PathBuf> = paths.iter().filter(|p| self.nodes.contains_key(*p)).collect();
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|(path, _)| keep.contains(path))
+            .map(|(path, node)| {
+                let mut node = node.clone();
+                node.imports.retain(|i| keep.contains(i));
+                node.dependents.retain(|d| keep.contains(d));
+                (path.clone(), node)
+            })
+            .collect();
+        let order = self.order.iter().filter(|p| keep.contains(p)).cloned().collect();
+        Dag { nodes, order }
+    }
+
+    /// Compare this DAG against `base`: what changed going from `base` to `self`.
+    pub fn diff(&self, base: &Dag) -> DagDiff {
+        let sorted = |mut v: Vec<PathBuf>| {
+            v.sort();
+            v
+        };
+        let edges = |dag: &Dag| -> HashSet<(PathBuf, PathBuf)> {
+            dag.nodes
+                .values()
+                .flat_map(|n| n.imports.iter().map(|i| (n.prompt_path.clone(), i.clone())))
+                .collect()
+        };
+        let owners = |dag: &Dag| -> HashMap<PathBuf, PathBuf> {
+            dag.nodes
+                .values()
+                .flat_map(|n| n.outputs.iter().map(|o| (o.clone(), n.prompt_path.clone())))
+                .collect()
+        };
+
+        let (old_edges, new_edges) = (edges(base), edges(self));
+        let mut added_edges: Vec<_> = new_edges.difference(&old_edges).cloned().collect();
+        let mut removed_edges: Vec<_> = old_edges.difference(&new_edges).cloned().collect();
+        added_edges.sort();
+        removed_edges.sort();
+
+        let (old_owners, new_owners) = (owners(base), owners(self));
+        let all_outputs: HashSet<&PathBuf> = old_owners.keys().chain(new_owners.keys()).collect();
+        let mut ownership_changes: Vec<OwnershipChange> = all_outputs
+            .into_iter()
+            .filter(|o| old_owners.get(*o) != new_owners.get(*o))
+            .map(|o| OwnershipChange {
+                output: o.clone(),
+                before: old_owners.get(o).cloned(),
+                after: new_owners.get(o).cloned(),
+            })
+            .collect();
+        ownership_changes.sort_by(|a, b| a.output.cmp(&b.output));
+
+        let cascade_changes = self
+            .order
+            .iter()
+            .filter(|p| base.nodes.contains_key(*p))
+            .filter_map(|p| {
+                let before = base.descendants(p).len();
+                let after = self.descendants(p).len();
+                (before != after).then(|| (p.clone(), before, after))
+            })
+            .collect();
+
+        DagDiff {
+            added_prompts: sorted(
+                self.nodes.keys().filter(|p| !base.nodes.contains_key(*p)).cloned().collect(),
+            ),
+            removed_prompts: sorted(
+                base.nodes.keys().filter(|p| !self.nodes.contains_key(*p)).cloned().collect(),
+            ),
+            added_edges,
+            removed_edges,
+            ownership_changes,
+            cascade_changes,
+        }
+    }
+
+    /// Render as a Graphviz DOT digraph.
+    ///
+    /// Edges point from an import to the prompts that use it; output files are
+    /// drawn as secondary nodes. Prompts in `changed` and their cascade (the
+    /// regeneration set) are highlighted, and drafts are dashed.
+    pub fn to_dot(&self, changed: &[PathBuf]) -> String {
+        let (direct, cascade) = self.highlight_sets(changed);
+        let quote = |p: &PathBuf| format!("\"{}\"", p.display().to_string().replace('"', "\\\""));
+
+        let mut out = String::from("digraph lit {\n    rankdir=LR;\n    node [shape=box];\n\n");
+        for path in &self.order {
+            let node = &self.nodes[path];
+            let style = if node.draft {
+                " [style=dashed, color=gray, fontcolor=gray, xlabel=\"draft\"]"
+            } else if direct.contains(path) {
+                " [style=filled, fillcolor=\"#f4a261\"]"
+            } else if cascade.contains(path) {
+                " [style=filled, fillcolor=\"#ffe8a3\"]"
+            } else {
+                ""
+            };
+            out.push_str(&format!("    {}{};\n", quote(path), style));
+            for output in &node.outputs {
+                out.push_str(&format!(
+                    "    {} [shape=note, color=gray, fontcolor=gray];\n    {} -> {} [style=dashed, color=gray];\n",
+                    quote(output),
+                    quote(path),
+                    quote(output)
+                ));
+            }
+        }
+        out.push('\n');
+        for path in &self.order {
+            for import in &self.nodes[path].imports {
+                out.push_str(&format!("    {} -> {};\n", quote(import), quote(path)));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart inside a ```mermaid fence, ready to
+    /// drop into Markdown. Same layout and highlighting as [`Dag::to_dot`].
+    pub fn to_mermaid(&self, changed: &[PathBuf]) -> String {
+        let (direct, cascade) = self.highlight_sets(changed);
+        let ids: HashMap<&PathBuf, String> = self
+            .order
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p, format!("p{}", i)))
+            .collect();
+        let label = |p: &PathBuf| p.display().to_string().replace('"', "#quot;");
+
+        let mut out = String::from("```mermaid\nflowchart LR\n");
+        let mut output_index = 0;
+        let mut output_edges = Vec::new();
+        for path in &self.order {
+            let id = &ids[path];
+            out.push_str(&format!("    {}[\"{}\"]\n", id, label(path)));
+            for output in &self.nodes[path].outputs {
+                let output_id = format!("o{}", output_index);
+                output_index += 1;
+                out.push_str(&format!("    {}([\"{}\"]):::output\n", output_id, label(output)));
+                output_edges.push(format!("    {} -.-> {}\n", id, output_id));
+            }
+        }
+        for path in &self.order {
+            for import in &self.nodes[path].imports {
+                out.push_str(&format!("    {} --> {}\n", ids[import], ids[path]));
+            }
+        }
+        for edge in output_edges {
+            out.push_str(&edge);
+        }
+
+        out.push_str("    classDef output fill:#f6f6f6,stroke:#bbb,color:#777\n");
+        let drafts: HashSet<PathBuf> = self.order.iter().filter(|p| self.nodes[*p].draft).cloned().collect();
+        for (class, set, style) in [
+            ("changed", &direct, "fill:#f4a261,stroke:#333"),
+            ("cascade", &cascade, "fill:#ffe8a3,stroke:#333"),
+            ("draft", &drafts, "stroke-dasharray:4 4,color:#777"),
+        ] {
+            let members: Vec<&str> = self
+                .order
+                .iter()
+                .filter(|p| set.contains(*p))
+                .map(|p| ids[p].as_str())
+                .collect();
+            if !members.is_empty() {
+                out.push_str(&format!("    classDef {} {}\n", class, style));
+                out.push_str(&format!("    class {} {}\n", members.join(","), class));
+            }
+        }
+        out.push_str("```\n");
+        out
+    }
+
+    /// Split the regeneration set for `changed` into the changed prompts
+    /// themselves and their downstream cascade.
+    fn highlight_sets(&self, changed: &[PathBuf]) -> (HashSet<PathBuf>, HashSet<PathBuf>) {
+        let direct: HashSet<PathBuf> = changed
+            .iter()
+            .filter(|p| self.nodes.contains_key(*p))
+            .cloned()
+            .collect();
+        let cascade = self
+            .regeneration_set(changed)
+            .into_iter()
+            .filter(|p| !direct.contains(p))
+            .collect();
+        (direct, cascade)
+    }
+
     /// Get the total number of prompts in the DAG.
     pub fn len(&self) -> usize {
         self.nodes.len()
@@ -176,6 +459,23 @@ impl Dag {
 
     // ---------- Internal ----------
 
+    /// Nodes reachable from `start` along `edges`, excluding `start`,
+    /// in topological order.
+    fn reachable(&self, start: &Path, edges: fn(&DagNode) -> &Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut queue: VecDeque<PathBuf> = VecDeque::from([start.to_path_buf()]);
+        while let Some(current) = queue.pop_front() {
+            if let Some(node) = self.nodes.get(&current) {
+                for next in edges(node) {
+                    if seen.insert(next.clone()) {
+                        queue.push_back(next.clone());
+                    }
+                }
+            }
+        }
+        self.order.iter().filter(|p| seen.contains(*p)).cloned().collect()
+    }
+
     /// Kahn's algorithm for topological sort.
     /// Returns an error if a cycle is detected.
     fn topological_sort(nodes: &HashMap<PathBuf, DagNode>) -> Result<Vec<PathBuf>> {
@@ -222,32 +522,31 @@ impl Dag {
 
         // If we didn't process all nodes, there's a cycle
         if order.len() != nodes.len() {
-            let in_cycle: Vec<String> = nodes
+            let in_cycle: Vec<PathBuf> = nodes
                 .keys()
                 .filter(|p| !order.contains(p))
-                .map(|p| p.display().to_string())
+                .cloned()
                 .collect();
 
             // Try to find the actual cycle path for a better error message
             let cycle_path = Self::find_cycle(nodes, &order);
 
-            bail!(
-                "Circular dependency detected. Prompts involved: [{}]{}",
-                in_cycle.join(", "),
-                cycle_path
-                    .map(|c| format!("\n  Cycle: {}", c))
-                    .unwrap_or_default()
-            );
+            return Err(LitError::DagCycle {
+                prompts: in_cycle,
+                cycle: cycle_path.unwrap_or_default(),
+            }
+            .into());
         }
 
         Ok(order)
     }
 
-    /// Try to find and report a specific cycle path for better error messages.
+    /// Try to find a specific cycle path for better error messages, with
+    /// its first prompt repeated at the end.
     fn find_cycle(
         nodes: &HashMap<PathBuf, DagNode>,
         sorted: &[PathBuf],
-    ) -> Option<String> {
+    ) -> Option<Vec<PathBuf>> {
         let sorted_set: HashSet<&PathBuf> = sorted.iter().collect();
 
         // Find nodes not in sorted output (they're in cycles)
@@ -268,19 +567,13 @@ impl Dag {
             visited: &mut HashSet<&'a PathBuf>,
             path: &mut Vec<&'a PathBuf>,
             sorted_set: &HashSet<&PathBuf>,
-        ) -> Option<String> {
+        ) -> Option<Vec<PathBuf>> {
             if path.contains(&current) {
                 // Found the cycle — extract it
                 let cycle_start = path.iter().position(|p| *p == current).unwrap();
-                let cycle: Vec<String> = path[cycle_start..]
-                    .iter()
-                    .map(|p| p.display().to_string())
-                    .collect();
-                return Some(format!(
-                    "{} → {}",
-                    cycle.join(" → "),
-                    current.display()
-                ));
+                let mut cycle: Vec<PathBuf> = path[cycle_start..].iter().map(|p| (*p).clone()).collect();
+                cycle.push(current.clone());
+                return Some(cycle);
             }
 
             if visited.contains(current) || sorted_set.contains(current) {
@@ -318,25 +611,37 @@ impl Dag {
             }
         }
 
-        let conflicts: Vec<String> = output_owners
-            .iter()
+        let conflicts: Vec<(PathBuf, Vec<PathBuf>)> = output_owners
+            .into_iter()
             .filter(|(_, owners)| owners.len() > 1)
-            .map(|(output, owners)| {
-                let owner_strs: Vec<String> =
-                    owners.iter().map(|p| p.display().to_string()).collect();
-                format!(
-                    "  {} claimed by: [{}]",
-                    output.display(),
-                    owner_strs.join(", ")
-                )
-            })
             .collect();
 
         if !conflicts.is_empty() {
-            bail!(
-                "Output file conflicts:\n{}",
-                conflicts.join("\n")
-            );
+            return Err(LitError::OutputConflict { conflicts }.into());
+        }
+
+        // A module directory owns everything inside it, so modules can't nest
+        let modules: Vec<(&PathBuf, &PathBuf)> = prompts
+            .iter()
+            .filter_map(|p| p.frontmatter.module.as_ref().map(|m| (m, &p.path)))
+            .collect();
+        let mut overlaps = Vec::new();
+        for (i, (a, a_owner)) in modules.iter().enumerate() {
+            for (b, b_owner) in &modules[i + 1..] {
+                if a.starts_with(b) || b.starts_with(a) {
+                    overlaps.push(format!(
+                        "  {}/ ({}) overlaps {}/ ({})",
+                        a.display(),
+                        a_owner.display(),
+                        b.display(),
+                        b_owner.display()
+                    ));
+                }
+            }
+        }
+        if !overlaps.is_empty() {
+            overlaps.sort();
+            bail!("Module directory conflicts:\n{}", overlaps.join("\n"));
         }
 
         Ok(())
@@ -486,6 +791,13 @@ temperature = 0.0
         let c_pos = order.iter().position(|p| p == &PathBuf::from("prompts/c.prompt.md")).unwrap();
         assert!(b_pos > 0 && b_pos < 3);
         assert!(c_pos > 0 && c_pos < 3);
+
+        // B and C share a level
+        let levels = dag.levels();
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0], vec![PathBuf::from("prompts/a.prompt.md")]);
+        assert_eq!(levels[1].len(), 2);
+        assert_eq!(levels[2], vec![PathBuf::from("prompts/d.prompt.md")]);
     }
 
     #[test]
@@ -511,6 +823,14 @@ temperature = 0.0
             "Expected cycle error, got: {}",
             msg
         );
+        match LitError::of(&err) {
+            Some(LitError::DagCycle { prompts, cycle }) => {
+                assert_eq!(prompts.len(), 2);
+                assert_eq!(cycle.len(), 3);
+                assert_eq!(cycle.first(), cycle.last());
+            }
+            other => panic!("Expected DagCycle, got: {:?}", other),
+        }
     }
 
     #[test]
@@ -716,6 +1036,41 @@ temperature = 0.0
             "Expected shared.py in error, got: {}",
             err
         );
+        assert!(matches!(
+            LitError::of(&err),
+            Some(LitError::OutputConflict { conflicts }) if conflicts[0].0 == Path::new("src/shared.py")
+        ));
+    }
+
+    #[test]
+    fn test_module_overlap_detection() {
+        let config = LitConfig::from_str(
+            r#"
+[project]
+name = "test"
+version = "0.1.0"
+mapping = "modular"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+"#,
+        )
+        .unwrap();
+        let module = |path: &str| Prompt::parse("---\n---\n", PathBuf::from(path), &config).unwrap();
+
+        let dag = Dag::build(&[module("prompts/models.prompt.md"), module("prompts/api.prompt.md")]);
+        assert!(dag.is_ok());
+
+        // models/ would contain models/user/
+        let err = Dag::build(&[module("prompts/models.prompt.md"), module("prompts/models/user.prompt.md")])
+            .unwrap_err();
+        assert!(err.to_string().contains("Module directory conflicts"), "got: {}", err);
     }
 
     #[test]
@@ -738,6 +1093,12 @@ temperature = 0.0
             "Expected nonexistent in error, got: {}",
             err
         );
+        assert_eq!(
+            LitError::of(&err),
+            Some(&LitError::MissingImports {
+                imports: vec![(PathBuf::from("prompts/a.prompt.md"), PathBuf::from("prompts/nonexistent.prompt.md"))],
+            })
+        );
     }
 
     #[test]
@@ -787,6 +1148,105 @@ temperature = 0.0
         assert!(display.contains("prompts/b.prompt.md"));
     }
 
+    #[test]
+    fn test_ancestors_descendants_depth() {
+        // Diamond A → {B, C} → D, plus independent E
+        let a = make_prompt("prompts/a.prompt.md", &["src/a.py"], &[]);
+        let b = make_prompt("prompts/b.prompt.md", &["src/b.py"], &["prompts/a.prompt.md"]);
+        let c = make_prompt("prompts/c.prompt.md", &["src/c.py"], &["prompts/a.prompt.md"]);
+        let d = make_prompt(
+            "prompts/d.prompt.md",
+            &["src/d.py"],
+            &["prompts/b.prompt.md", "prompts/c.prompt.md"],
+        );
+        let e = make_prompt("prompts/e.prompt.md", &["src/e.py"], &[]);
+        let dag = Dag::build(&[a, b, c, d, e]).unwrap();
+        let p = |name: &str| PathBuf::from(format!("prompts/{}.prompt.md", name));
+
+        assert_eq!(dag.ancestors(&p("d")), vec![p("a"), p("b"), p("c")]);
+        assert!(dag.ancestors(&p("a")).is_empty());
+        assert_eq!(dag.descendants(&p("a")), vec![p("b"), p("c"), p("d")]);
+        assert!(dag.descendants(&p("e")).is_empty());
+
+        assert_eq!(dag.depth(&p("a")), Some(0));
+        assert_eq!(dag.depth(&p("b")), Some(1));
+        assert_eq!(dag.depth(&p("d")), Some(2));
+        assert_eq!(dag.depth(&p("missing")), None);
+
+        let sub = dag.subgraph(&[p("b"), p("d"), p("missing")]);
+        assert_eq!(sub.order(), &[p("b"), p("d")]);
+        assert_eq!(sub.get(&p("d")).unwrap().imports, vec![p("b")]);
+        assert!(sub.get(&p("b")).unwrap().imports.is_empty());
+    }
+
+    #[test]
+    fn test_dag_diff() {
+        let a = make_prompt("prompts/a.prompt.md", &["src/a.py"], &[]);
+        let b = make_prompt("prompts/b.prompt.md", &["src/b.py", "src/shared.py"], &["prompts/a.prompt.md"]);
+        let c = make_prompt("prompts/c.prompt.md", &["src/c.py"], &[]);
+        let base = Dag::build(&[a.clone(), b, c]).unwrap();
+
+        // b hands src/shared.py to a new prompt d, and c now imports a
+        let b2 = make_prompt("prompts/b.prompt.md", &["src/b.py"], &["prompts/a.prompt.md"]);
+        let c2 = make_prompt("prompts/c.prompt.md", &["src/c.py"], &["prompts/a.prompt.md"]);
+        let d = make_prompt("prompts/d.prompt.md", &["src/shared.py"], &[]);
+        let head = Dag::build(&[a, b2, c2, d]).unwrap();
+        let p = |name: &str| PathBuf::from(format!("prompts/{}.prompt.md", name));
+
+        let diff = head.diff(&base);
+        assert_eq!(diff.added_prompts, vec![p("d")]);
+        assert!(diff.removed_prompts.is_empty());
+        assert_eq!(diff.added_edges, vec![(p("c"), p("a"))]);
+        assert!(diff.removed_edges.is_empty());
+        assert_eq!(
+            diff.ownership_changes,
+            vec![OwnershipChange {
+                output: PathBuf::from("src/shared.py"),
+                before: Some(p("b")),
+                after: Some(p("d")),
+            }]
+        );
+        assert_eq!(diff.cascade_changes, vec![(p("a"), 1, 2)]);
+
+        assert!(head.diff(&head).is_empty());
+    }
+
+    #[test]
+    fn test_graph_export() {
+        let a = make_prompt("prompts/a.prompt.md", &["src/a.py"], &[]);
+        let b = make_prompt(
+            "prompts/b.prompt.md",
+            &["src/b.py"],
+            &["prompts/a.prompt.md"],
+        );
+        let c = make_prompt("prompts/c.prompt.md", &["src/c.py"], &[]);
+        let mut d = make_prompt("prompts/d.prompt.md", &["src/d.py"], &[]);
+        d.frontmatter.draft = true;
+        let dag = Dag::build(&[a, b, c, d]).unwrap();
+        let changed = [PathBuf::from("prompts/a.prompt.md")];
+
+        let dot = dag.to_dot(&changed);
+        assert!(dot.starts_with("digraph lit {"));
+        assert!(dot.contains("\"prompts/a.prompt.md\" -> \"prompts/b.prompt.md\";"));
+        assert!(dot.contains("\"prompts/a.prompt.md\" -> \"src/a.py\" [style=dashed"));
+        assert!(dot.contains("\"prompts/a.prompt.md\" [style=filled, fillcolor=\"#f4a261\"]"));
+        assert!(dot.contains("\"prompts/b.prompt.md\" [style=filled, fillcolor=\"#ffe8a3\"]"));
+        assert!(dot.contains("    \"prompts/c.prompt.md\";"));
+        assert!(dot.contains("\"prompts/d.prompt.md\" [style=dashed, color=gray"));
+
+        let mermaid = dag.to_mermaid(&changed);
+        assert!(mermaid.starts_with("```mermaid\nflowchart LR\n"));
+        assert!(mermaid.contains("p0[\"prompts/a.prompt.md\"]"));
+        assert!(mermaid.contains("p0 --> p3"));
+        assert!(mermaid.contains("p0 -.-> o0"));
+        assert!(mermaid.contains("class p0 changed"));
+        assert!(mermaid.contains("class p3 cascade"));
+        assert!(mermaid.contains("class p2 draft"));
+
+        // Nothing changed: only drafts are marked
+        assert!(!dag.to_mermaid(&[]).contains("changed"));
+    }
+
     #[test]
     fn test_multiple_changes() {
         // A, B (independent), C depends on A, D depends on B
@@ -0,0 +1,288 @@
+//! Pull request descriptions (`lit describe`).
+//!
+//! A [`BranchSummary`] collects what a branch did relative to its base:
+//! prompts changed, code.lock/ files they regenerated, what the committed
+//! generation runs cost and which manual patches were added. [`render`]
+//! lays it out as Markdown through a template whose placeholders each
+//! expand to one section, so teams can reorder or drop sections without
+//! touching the facts.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+use crate::core::generation_record::{GenerationRecord, format_cost};
+use crate::core::repo::CommitInfo;
+
+/// Placeholders available in a `[describe] template`
+pub const DESCRIBE_PLACEHOLDERS: &[&str] =
+    &["{title}", "{summary}", "{commits}", "{prompts}", "{impact}", "{cost}", "{patches}"];
+
+/// Layout used without a `[describe] template`
+pub const DEFAULT_TEMPLATE: &str = "{summary}
+
+### Prompt changes
+
+{prompts}
+
+### Regenerated code
+
+{impact}
+
+### Generation cost
+
+{cost}
+
+### Manual patches
+
+{patches}
+";
+
+/// System prompt for `lit describe --llm`
+pub const DESCRIBE_SYSTEM_PROMPT: &str = "You write the overview paragraph of a pull request \
+description for a project whose code is generated from prompt files. You are given the branch's \
+commits, which prompts changed and their diff, and which generated files were regenerated. In 2-4 \
+sentences of plain prose, say what the change does for the software and why a reviewer should care. \
+Don't list files, costs or tokens (those sections follow yours), don't use headings, and don't \
+speculate beyond the diff.";
+
+/// Prompt diffs longer than this are cut before being sent to the model
+const MAX_DIFF_CHARS: usize = 24_000;
+
+/// What a branch changed relative to its base
+#[derive(Debug, Clone, Default)]
+pub struct BranchSummary {
+    /// Commits on the branch, newest first
+    pub commits: Vec<CommitInfo>,
+    pub prompts_new: Vec<PathBuf>,
+    pub prompts_modified: Vec<PathBuf>,
+    pub prompts_deleted: Vec<PathBuf>,
+    /// Insertions and deletions per changed prompt
+    pub prompt_stats: BTreeMap<PathBuf, (usize, usize)>,
+    /// code.lock/ files added or changed
+    pub code_changed: Vec<PathBuf>,
+    /// Generation records committed on the branch
+    pub records: Vec<GenerationRecord>,
+    /// Output files that gained a manual patch
+    pub patches_added: Vec<PathBuf>,
+}
+
+impl BranchSummary {
+    pub fn prompt_count(&self) -> usize {
+        self.prompts_new.len() + self.prompts_modified.len() + self.prompts_deleted.len()
+    }
+
+    /// Pull request title: the commit subject for a single commit, else a
+    /// count of the prompt changes
+    pub fn title(&self) -> String {
+        match (self.commits.as_slice(), self.prompt_count()) {
+            ([only], _) => only.message.clone(),
+            (_, 0) => format!("{} commit(s)", self.commits.len()),
+            (_, 1) => {
+                let path = self.prompts_new.iter().chain(&self.prompts_modified).chain(&self.prompts_deleted).next();
+                format!("Update {}", path.map(|p| p.display().to_string()).unwrap_or_default())
+            }
+            (_, n) => format!("Update {} prompts", n),
+        }
+    }
+}
+
+fn bullet(path: &std::path::Path, note: &str) -> String {
+    if note.is_empty() {
+        format!("- `{}`\n", path.display())
+    } else {
+        format!("- `{}` {}\n", path.display(), note)
+    }
+}
+
+fn commits_section(summary: &BranchSummary) -> String {
+    summary
+        .commits
+        .iter()
+        .map(|c| format!("- {} {}\n", c.short_hash, c.message))
+        .collect::<String>()
+}
+
+fn prompts_section(summary: &BranchSummary) -> String {
+    let stat = |path: &PathBuf| {
+        summary.prompt_stats.get(path).map(|(ins, del)| format!("(+{} −{})", ins, del)).unwrap_or_default()
+    };
+    let mut out = String::new();
+    for path in &summary.prompts_new {
+        out.push_str(&bullet(path, format!("new {}", stat(path)).trim_end()));
+    }
+    for path in &summary.prompts_modified {
+        out.push_str(&bullet(path, &stat(path)));
+    }
+    for path in &summary.prompts_deleted {
+        out.push_str(&bullet(path, "deleted"));
+    }
+    out
+}
+
+fn impact_section(summary: &BranchSummary) -> String {
+    let mut out: String = summary.code_changed.iter().map(|path| bullet(path, "")).collect();
+    // Cache hits reused code generated before the branch
+    let generated = summary.records.iter().flat_map(|r| &r.prompts).filter(|p| !p.from_cache).count();
+    if generated > 0 {
+        out.push_str(&format!(
+            "\n{} file(s) changed by {} fresh generation(s).\n",
+            summary.code_changed.len(),
+            generated
+        ));
+    }
+    out
+}
+
+fn cost_section(summary: &BranchSummary) -> String {
+    if summary.records.is_empty() {
+        return String::new();
+    }
+    let tokens_in: u64 = summary.records.iter().map(|r| r.summary.total_tokens_in).sum();
+    let tokens_out: u64 = summary.records.iter().map(|r| r.summary.total_tokens_out).sum();
+    let cost: f64 = summary.records.iter().map(|r| r.summary.total_cost_usd).sum();
+    let mut models: Vec<&str> = summary.records.iter().map(|r| r.model.as_str()).collect();
+    models.sort();
+    models.dedup();
+    format!(
+        "{} across {} generation run(s) ({} tokens in, {} out; {}).\n",
+        format_cost(cost),
+        summary.records.len(),
+        tokens_in,
+        tokens_out,
+        models.join(", ")
+    )
+}
+
+fn patches_section(summary: &BranchSummary) -> String {
+    summary.patches_added.iter().map(|path| bullet(path, "")).collect()
+}
+
+/// The description, laid out by `template`. `overview` (from
+/// `lit describe --llm`) fills `{summary}`; without one it lists the
+/// commits. Empty sections read "None."
+pub fn render(template: &str, summary: &BranchSummary, overview: Option<&str>) -> String {
+    let none = |section: String| {
+        if section.trim().is_empty() {
+            "None.".to_string()
+        } else {
+            section.trim_end().to_string()
+        }
+    };
+    let overview = match overview {
+        Some(text) => text.trim().to_string(),
+        None => none(commits_section(summary)),
+    };
+    let mut out = template.to_string();
+    for (placeholder, value) in [
+        ("{title}", summary.title()),
+        ("{summary}", overview),
+        ("{commits}", none(commits_section(summary))),
+        ("{prompts}", none(prompts_section(summary))),
+        ("{impact}", none(impact_section(summary))),
+        ("{cost}", none(cost_section(summary))),
+        ("{patches}", none(patches_section(summary))),
+    ] {
+        out = out.replace(placeholder, &value);
+    }
+    format!("{}\n", out.trim_end())
+}
+
+/// `{name}` placeholders in a template that [`render`] doesn't fill
+pub fn unknown_placeholders(template: &str) -> Vec<String> {
+    let placeholder = Regex::new(r"\{[a-z_]+\}").expect("valid regex");
+    placeholder
+        .find_iter(template)
+        .map(|m| m.as_str().to_string())
+        .filter(|name| !DESCRIBE_PLACEHOLDERS.contains(&name.as_str()))
+        .collect()
+}
+
+/// User prompt for `lit describe --llm`: the facts, and the prompt diff
+pub fn overview_request(summary: &BranchSummary, prompt_diff: &str) -> String {
+    let mut diff = prompt_diff.to_string();
+    if diff.len() > MAX_DIFF_CHARS {
+        let mut cut = MAX_DIFF_CHARS;
+        while !diff.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        diff.truncate(cut);
+        diff.push_str("\n[diff truncated]\n");
+    }
+    format!(
+        "Commits:\n{}\nPrompt changes:\n{}\nRegenerated files:\n{}\nPrompt diff:\n```diff\n{}```\n",
+        commits_section(summary),
+        prompts_section(summary),
+        summary.code_changed.iter().map(|p| format!("- {}\n", p.display())).collect::<String>(),
+        diff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(short_hash: &str, message: &str) -> CommitInfo {
+        CommitInfo {
+            hash: short_hash.to_string(),
+            short_hash: short_hash.to_string(),
+            message: message.to_string(),
+            author: "Dev".to_string(),
+            timestamp: 0,
+        }
+    }
+
+    fn summary() -> BranchSummary {
+        BranchSummary {
+            commits: vec![commit("bbb2222", "Add orders"), commit("aaa1111", "Rename user fields")],
+            prompts_new: vec![PathBuf::from("prompts/orders.prompt.md")],
+            prompts_modified: vec![PathBuf::from("prompts/user.prompt.md")],
+            prompt_stats: BTreeMap::from([
+                (PathBuf::from("prompts/orders.prompt.md"), (12, 0)),
+                (PathBuf::from("prompts/user.prompt.md"), (3, 1)),
+            ]),
+            code_changed: vec![PathBuf::from("code.lock/src/orders.py"), PathBuf::from("code.lock/src/user.py")],
+            patches_added: vec![PathBuf::from("src/user.py")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_default_template() {
+        let body = render(DEFAULT_TEMPLATE, &summary(), None);
+        assert!(body.starts_with("- bbb2222 Add orders\n- aaa1111 Rename user fields\n\n### Prompt changes"), "{}", body);
+        assert!(body.contains("- `prompts/orders.prompt.md` new (+12 −0)\n- `prompts/user.prompt.md` (+3 −1)\n"));
+        assert!(body.contains("- `code.lock/src/user.py`\n"));
+        // No generation records committed on the branch
+        assert!(body.contains("### Generation cost\n\nNone.\n"));
+        assert!(body.ends_with("### Manual patches\n\n- `src/user.py`\n"));
+
+        let body = render(DEFAULT_TEMPLATE, &summary(), Some("Adds an orders model.\n"));
+        assert!(body.starts_with("Adds an orders model.\n\n### Prompt changes"));
+    }
+
+    #[test]
+    fn test_render_custom_template_and_title() {
+        let mut summary = summary();
+        assert_eq!(summary.title(), "Update 2 prompts");
+        let body = render("## {title}\n\n{prompts}\n", &summary, None);
+        assert!(body.starts_with("## Update 2 prompts\n\n- `prompts/orders.prompt.md`"));
+        assert_eq!(unknown_placeholders("{title}\n{reviewers}\n```json\n{\"a\": 1}\n```"), vec!["{reviewers}"]);
+
+        summary.commits.truncate(1);
+        assert_eq!(summary.title(), "Add orders");
+        summary.prompts_new.clear();
+        summary.commits.push(commit("ccc3333", "Tweak"));
+        assert_eq!(summary.title(), "Update prompts/user.prompt.md");
+    }
+
+    #[test]
+    fn test_overview_request_truncates_diff() {
+        let diff = "+x\n".repeat(MAX_DIFF_CHARS);
+        let request = overview_request(&summary(), &diff);
+        assert!(request.contains("- `prompts/user.prompt.md` (+3 −1)"), "{}", request);
+        assert!(request.contains("[diff truncated]"));
+        assert!(request.len() < MAX_DIFF_CHARS + 1_000);
+    }
+}
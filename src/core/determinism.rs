@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// How far repeated generations of one prompt drifted from the first run
+#[derive(Debug, Default, PartialEq)]
+pub struct RunComparison {
+    /// Runs whose output differs from the first run
+    pub differing_runs: usize,
+    /// Most lines changed (inserted + deleted) between the first run and any other
+    pub max_changed_lines: usize,
+    /// Most lines changed per file, for files that ever differed
+    pub files: BTreeMap<PathBuf, usize>,
+}
+
+impl RunComparison {
+    pub fn is_deterministic(&self) -> bool {
+        self.differing_runs == 0
+    }
+}
+
+/// Lines inserted plus lines deleted to get from `old` to `new`
+pub fn changed_lines(old: &str, new: &str) -> usize {
+    similar::TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .filter(|change| change.tag() != similar::ChangeTag::Equal)
+        .count()
+}
+
+/// Compare every run against the first. A file missing from a run counts
+/// as every line of it changed.
+pub fn compare_runs(runs: &[HashMap<PathBuf, String>]) -> RunComparison {
+    let mut comparison = RunComparison::default();
+    let Some((first, rest)) = runs.split_first() else {
+        return comparison;
+    };
+    for run in rest {
+        let mut run_changed = 0;
+        let paths = first.keys().chain(run.keys().filter(|p| !first.contains_key(*p)));
+        for path in paths {
+            let old = first.get(path).map_or("", String::as_str);
+            let new = run.get(path).map_or("", String::as_str);
+            let changed = changed_lines(old, new);
+            if changed > 0 {
+                let max = comparison.files.entry(path.clone()).or_default();
+                *max = (*max).max(changed);
+                run_changed += changed;
+            }
+        }
+        if run_changed > 0 {
+            comparison.differing_runs += 1;
+            comparison.max_changed_lines = comparison.max_changed_lines.max(run_changed);
+        }
+    }
+    comparison
+}
+
+/// Line churn across runs, in percent: lines changed between the first run
+/// and each other run, over the lines of both. 0% means every run matched
+/// the first; 100% means they had nothing in common.
+#[derive(Debug, Default, PartialEq)]
+pub struct Churn {
+    pub overall: f64,
+    pub files: BTreeMap<PathBuf, f64>,
+}
+
+pub fn churn(runs: &[HashMap<PathBuf, String>]) -> Churn {
+    let Some((first, rest)) = runs.split_first() else {
+        return Churn::default();
+    };
+    // (changed, total) lines per file, summed over runs
+    let mut counts: BTreeMap<PathBuf, (usize, usize)> = BTreeMap::new();
+    for run in rest {
+        let paths = first.keys().chain(run.keys().filter(|p| !first.contains_key(*p)));
+        for path in paths {
+            let old = first.get(path).map_or("", String::as_str);
+            let new = run.get(path).map_or("", String::as_str);
+            let (changed, total) = counts.entry(path.clone()).or_default();
+            *changed += changed_lines(old, new);
+            *total += old.lines().count() + new.lines().count();
+        }
+    }
+    let percent = |(changed, total): (usize, usize)| match total {
+        0 => 0.0,
+        total => 100.0 * changed as f64 / total as f64,
+    };
+    let sum = counts.values().fold((0, 0), |(c, t), (changed, total)| (c + changed, t + total));
+    Churn {
+        overall: percent(sum),
+        files: counts.into_iter().map(|(path, counts)| (path, percent(counts))).collect(),
+    }
+}
+
+/// Pick `n` items spread evenly through `items`, keeping their order
+pub fn sample_evenly<T: Clone>(items: &[T], n: usize) -> Vec<T> {
+    if n >= items.len() {
+        return items.to_vec();
+    }
+    (0..n).map(|i| items[i * items.len() / n].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn run(files: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+        files.iter().map(|(p, c)| (PathBuf::from(p), c.to_string())).collect()
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let same = [run(&[("a.py", "x = 1\ny = 2\n")]), run(&[("a.py", "x = 1\ny = 2\n")])];
+        assert!(compare_runs(&same).is_deterministic());
+        assert!(compare_runs(&[]).is_deterministic());
+
+        let drifted = [
+            run(&[("a.py", "x = 1\ny = 2\n"), ("b.py", "pass\n")]),
+            run(&[("a.py", "x = 1\ny = 3\n"), ("b.py", "pass\n")]),
+            run(&[("a.py", "x = 1\ny = 2\n"), ("b.py", "pass\n")]),
+            run(&[("a.py", "x = 1\ny = 2\n"), ("c.py", "z = 1\n")]),
+        ];
+        let comparison = compare_runs(&drifted);
+        assert_eq!(comparison.differing_runs, 2);
+        assert_eq!(comparison.max_changed_lines, 2);
+        assert_eq!(
+            comparison.files,
+            BTreeMap::from([
+                (PathBuf::from("a.py"), 2),
+                (PathBuf::from("b.py"), 1),
+                (PathBuf::from("c.py"), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_churn() {
+        let same = [run(&[("a.py", "x = 1\ny = 2\n")]), run(&[("a.py", "x = 1\ny = 2\n")])];
+        assert_eq!(churn(&same).overall, 0.0);
+        assert_eq!(churn(&same[..1]), Churn { overall: 0.0, files: BTreeMap::from([]) });
+
+        let drifted = [
+            run(&[("a.py", "x = 1\ny = 2\n"), ("b.py", "pass\n")]),
+            run(&[("a.py", "x = 1\ny = 3\n"), ("b.py", "pass\n")]),
+            run(&[("a.py", "x = 1\ny = 2\n"), ("c.py", "z = 1\n")]),
+        ];
+        let churn = churn(&drifted);
+        // a.py: 2 of 4 lines, then 0 of 4; b.py: 0 of 2, then 1 of 1; c.py: 1 of 1
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(churn.files[Path::new("a.py")], 25.0));
+        assert!(close(churn.files[Path::new("b.py")], 100.0 / 3.0));
+        assert!(close(churn.files[Path::new("c.py")], 100.0));
+        assert!(close(churn.overall, 100.0 / 3.0));
+    }
+
+    #[test]
+    fn test_sample_evenly() {
+        let items: Vec<u32> = (0..10).collect();
+        assert_eq!(sample_evenly(&items, 3), vec![0, 3, 6]);
+        assert_eq!(sample_evenly(&items, 20), items);
+        assert!(sample_evenly(&items, 0).is_empty());
+    }
+}
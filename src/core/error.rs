@@ -0,0 +1,151 @@
+//! Failure kinds for code embedding lit as a library.
+//!
+//! Core functions return `anyhow::Result`, as the CLI does. Failures a
+//! caller may want to handle are raised as (or wrapped in) a [`LitError`],
+//! which [`LitError::of`] finds again without string-matching messages:
+//!
+//! ```ignore
+//! match Dag::build(&prompts) {
+//!     Err(e) => match LitError::of(&e) {
+//!         Some(LitError::DagCycle { cycle, .. }) => highlight(cycle),
+//!         _ => return Err(e),
+//!     },
+//!     Ok(dag) => ...,
+//! }
+//! ```
+//!
+//! Provider failures are [`ProviderError`](crate::providers::ProviderError)s,
+//! found the same way with `downcast_ref` and classified by
+//! [`ProviderError::kind`](crate::providers::ProviderError::kind).
+
+use std::path::{Path, PathBuf};
+
+use crate::core::secrets::SecretFinding;
+
+/// A failure kind callers can match on. The message is the one the CLI
+/// shows; the fields carry the same details in structured form.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LitError {
+    /// No lit.toml in `start` or any parent directory
+    #[error("Not a lit repository: lit.toml not found in {} or any parent directory", start.display())]
+    NotARepository { start: PathBuf },
+    /// lit.toml doesn't parse or fails validation
+    #[error("{message}")]
+    InvalidConfig { message: String },
+    /// A prompt file's frontmatter doesn't parse; the cause follows in the chain
+    #[error("Failed to parse {part} in {}", path.display())]
+    Parse { path: PathBuf, part: String },
+    /// Prompts import prompts that don't exist, as `(prompt, import)`
+    #[error("Missing imports:\n{}", list_edges(imports, "not found"))]
+    MissingImports { imports: Vec<(PathBuf, PathBuf)> },
+    /// Prompts left to regenerate import draft prompts, as `(prompt, import)`
+    #[error(
+        "Prompts import draft prompts, which are never generated:\n{}\n\
+         Hint: Remove `draft: true` once the draft is ready, or mark its dependents as drafts too.",
+        list_edges(imports, "draft")
+    )]
+    DraftImports { imports: Vec<(PathBuf, PathBuf)> },
+    /// The imports form a cycle. `prompts` are those that couldn't be
+    /// ordered; `cycle` is one cycle through them, first prompt repeated at
+    /// the end, when it could be traced.
+    #[error("Circular dependency detected. Prompts involved: [{}]{}", list_paths(prompts, ", "), cycle_line(cycle))]
+    DagCycle { prompts: Vec<PathBuf>, cycle: Vec<PathBuf> },
+    /// Output files claimed by more than one prompt, with their claimants
+    #[error("Output file conflicts:\n{}", list_conflicts(conflicts))]
+    OutputConflict { conflicts: Vec<(PathBuf, Vec<PathBuf>)> },
+    /// Generated code for `prompt` looks like it contains secrets, under
+    /// `[security] secrets = "error"`
+    #[error(
+        "Generated code for {} looks like it contains secrets:\n{}\n\
+         Hint: Have the prompt read credentials from the environment, or list test values in [security] allow.",
+        prompt.display(),
+        list_findings(findings)
+    )]
+    SecretsFound { prompt: PathBuf, findings: Vec<SecretFinding> },
+    /// Reading or writing the generation cache failed; the cause follows
+    #[error("Failed to {action}: {}", path.display())]
+    Cache { action: String, path: PathBuf },
+    /// A commit would record code.lock/ behind the prompts it comes from
+    #[error(
+        "code.lock is stale for {} prompt(s):\n  {}\n\
+         Hint: Run `lit regenerate` first, or pass --allow-stale to commit anyway.",
+        prompts.len(),
+        list_paths(prompts, "\n  ")
+    )]
+    Stale { prompts: Vec<PathBuf> },
+}
+
+impl LitError {
+    /// The `LitError` an error was raised as or wrapped in, if any
+    pub fn of(err: &anyhow::Error) -> Option<&LitError> {
+        err.downcast_ref::<LitError>()
+    }
+
+    pub fn cache(action: &str, path: &Path) -> Self {
+        LitError::Cache { action: action.to_string(), path: path.to_path_buf() }
+    }
+}
+
+fn list_paths(paths: &[PathBuf], separator: &str) -> String {
+    paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(separator)
+}
+
+fn list_edges(edges: &[(PathBuf, PathBuf)], note: &str) -> String {
+    edges
+        .iter()
+        .map(|(from, to)| format!("  {} imports {} ({})", from.display(), to.display(), note))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cycle_line(cycle: &[PathBuf]) -> String {
+    if cycle.is_empty() {
+        String::new()
+    } else {
+        format!("\n  Cycle: {}", list_paths(cycle, " → "))
+    }
+}
+
+fn list_findings(findings: &[SecretFinding]) -> String {
+    findings.iter().map(|f| format!("  {}", f)).collect::<Vec<_>>().join("\n")
+}
+
+fn list_conflicts(conflicts: &[(PathBuf, Vec<PathBuf>)]) -> String {
+    conflicts
+        .iter()
+        .map(|(output, owners)| format!("  {} claimed by: [{}]", output.display(), list_paths(owners, ", ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_found_through_context() {
+        let err = anyhow::Error::new(LitError::DagCycle {
+            prompts: vec![PathBuf::from("prompts/a.prompt.md"), PathBuf::from("prompts/b.prompt.md")],
+            cycle: vec![
+                PathBuf::from("prompts/a.prompt.md"),
+                PathBuf::from("prompts/b.prompt.md"),
+                PathBuf::from("prompts/a.prompt.md"),
+            ],
+        });
+        assert_eq!(
+            err.to_string(),
+            "Circular dependency detected. Prompts involved: [prompts/a.prompt.md, prompts/b.prompt.md]\n  \
+             Cycle: prompts/a.prompt.md → prompts/b.prompt.md → prompts/a.prompt.md"
+        );
+        let err = Err::<(), _>(err).context("Invalid prompt DAG at HEAD").unwrap_err();
+        assert!(matches!(LitError::of(&err), Some(LitError::DagCycle { cycle, .. }) if cycle.len() == 3));
+
+        // Used as context, the kind wraps the underlying cause
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err = Err::<(), _>(io).context(LitError::cache("write cache entry", Path::new(".lit/cache/abc.bin"))).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to write cache entry: .lit/cache/abc.bin");
+        assert!(matches!(LitError::of(&err), Some(LitError::Cache { .. })));
+        assert!(LitError::of(&anyhow::anyhow!("something else")).is_none());
+    }
+}
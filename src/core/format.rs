@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{Context, Result, bail};
+
+use crate::core::hooks::{shell, shell_quote};
+
+/// Formats generated files with the `[format]` commands from lit.toml.
+///
+/// Commands are keyed by file extension, read the file on stdin and write the
+/// formatted file to stdout (e.g. `black -q -`, `prettier --stdin-filepath
+/// {file}`). `{file}` is replaced with the output path, relative to code.lock/.
+/// Commands run through the shell from the repo root.
+pub struct Formatter {
+    root: PathBuf,
+    commands: BTreeMap<String, String>,
+}
+
+impl Formatter {
+    pub fn new(root: &Path, commands: BTreeMap<String, String>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            commands,
+        }
+    }
+
+    /// The formatter command for `path`, by extension
+    pub fn command_for(&self, path: &Path) -> Option<&str> {
+        let ext = path.extension()?.to_str()?;
+        self.commands.get(ext).map(String::as_str)
+    }
+
+    /// Format one file. Files without a formatter are returned unchanged.
+    pub fn format(&self, path: &Path, content: &str) -> Result<String> {
+        let Some(command) = self.command_for(path) else {
+            return Ok(content.to_string());
+        };
+        let command = command.replace("{file}", &shell_quote(&path.to_string_lossy()));
+
+        let mut child = shell(&command)
+            .current_dir(&self.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run formatter `{}`", command))?;
+
+        // Write stdin from a thread so a formatter that streams its output
+        // can't deadlock against a full stdout pipe
+        let mut stdin = child.stdin.take().context("Failed to open formatter stdin")?;
+        let input = content.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("Failed to run formatter `{}`", command))?;
+        let _ = writer.join();
+
+        if !output.status.success() {
+            bail!(
+                "Formatter `{}` failed on {} ({})\n{}",
+                command,
+                path.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            );
+        }
+        let formatted = String::from_utf8(output.stdout)
+            .with_context(|| format!("Formatter `{}` wrote invalid UTF-8", command))?;
+        if formatted.trim().is_empty() && !content.trim().is_empty() {
+            bail!(
+                "Formatter `{}` produced no output for {} (does it write to stdout?)",
+                command,
+                path.display()
+            );
+        }
+        Ok(formatted)
+    }
+
+    /// Format every file in place. A file whose formatter fails keeps its
+    /// generated content; the failures are returned as messages.
+    pub fn format_all(&self, files: &mut HashMap<PathBuf, String>) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (path, content) in files.iter_mut() {
+            match self.format(path, content) {
+                Ok(formatted) => *content = formatted,
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+        errors.sort();
+        errors
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let formatter = Formatter::new(
+            dir.path(),
+            BTreeMap::from([
+                ("py".to_string(), "tr a-z A-Z".to_string()),
+                ("ts".to_string(), "echo {file}; cat".to_string()),
+                ("rs".to_string(), "echo 'syntax error' >&2; exit 1".to_string()),
+            ]),
+        );
+
+        let mut files = HashMap::from([
+            (PathBuf::from("src/user.py"), "x = 1\n".to_string()),
+            (PathBuf::from("web/app.ts"), "let x = 1;\n".to_string()),
+            (PathBuf::from("README.md"), "# readme\n".to_string()),
+            (PathBuf::from("src/lib.rs"), "fn main() {\n".to_string()),
+        ]);
+        let errors = formatter.format_all(&mut files);
+
+        assert_eq!(files[Path::new("src/user.py")], "X = 1\n");
+        assert_eq!(files[Path::new("web/app.ts")], "web/app.ts\nlet x = 1;\n");
+        assert_eq!(files[Path::new("README.md")], "# readme\n");
+        assert_eq!(files[Path::new("src/lib.rs")], "fn main() {\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("syntax error"), "got: {}", errors[0]);
+    }
+}
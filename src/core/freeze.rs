@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic;
+
+/// Frozen prompts, under `.lit/` (committed)
+pub const FREEZE_FILE: &str = "freeze.json";
+
+/// Prompts pinned to their code.lock/ content with `lit freeze`.
+///
+/// `lit regenerate` never calls the LLM for a frozen prompt, not even with
+/// `--all` or `--no-cache`; its current code is reused as-is and still
+/// serves as context for its dependents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FreezeList {
+    #[serde(default)]
+    pub prompts: BTreeSet<PathBuf>,
+}
+
+impl FreezeList {
+    /// Load `.lit/freeze.json`; empty when nothing has been frozen
+    pub fn load(lit_dir: &Path) -> Result<Self> {
+        let path = lit_dir.join(FREEZE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write `.lit/freeze.json`, removing it once nothing is frozen
+    pub fn save(&self, lit_dir: &Path) -> Result<()> {
+        let path = lit_dir.join(FREEZE_FILE);
+        if self.prompts.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            return Ok(());
+        }
+        std::fs::create_dir_all(lit_dir)
+            .with_context(|| format!("Failed to create {}", lit_dir.display()))?;
+        atomic::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn is_frozen(&self, prompt: &Path) -> bool {
+        self.prompts.contains(prompt)
+    }
+
+    /// Freeze a prompt; false if it already was
+    pub fn freeze(&mut self, prompt: &Path) -> bool {
+        self.prompts.insert(prompt.to_path_buf())
+    }
+
+    /// Unfreeze a prompt; false if it wasn't frozen
+    pub fn unfreeze(&mut self, prompt: &Path) -> bool {
+        self.prompts.remove(prompt)
+    }
+
+    /// Carry a frozen prompt over to its new path after a move
+    pub fn rename(&mut self, from: &Path, to: &Path) -> bool {
+        self.unfreeze(from) && self.freeze(to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeze_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut list = FreezeList::load(dir.path()).unwrap();
+        assert!(list.prompts.is_empty());
+
+        let user = Path::new("prompts/user.prompt.md");
+        assert!(list.freeze(user));
+        assert!(!list.freeze(user));
+        list.save(dir.path()).unwrap();
+
+        let mut list = FreezeList::load(dir.path()).unwrap();
+        assert!(list.is_frozen(user));
+        let account = Path::new("prompts/account.prompt.md");
+        assert!(list.rename(user, account));
+        assert!(!list.is_frozen(user) && list.is_frozen(account));
+
+        assert!(list.unfreeze(account));
+        assert!(!list.unfreeze(account));
+        list.save(dir.path()).unwrap();
+        assert!(!dir.path().join(FREEZE_FILE).exists());
+    }
+}
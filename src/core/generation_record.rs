@@ -1,9 +1,17 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::core::atomic;
+use crate::core::config::{BudgetAlertsConfig, LitConfig};
+use crate::core::error::LitError;
+use crate::core::generator::PipelineResult;
+use crate::core::style;
+use crate::providers::{PromptCacheTokens, ProviderError, service_client};
+
 /// A generation record captures the full metadata for a single `lit regenerate` run.
 ///
 /// Stored as JSON in `.lit/generations/<timestamp>.json`.
@@ -30,6 +38,16 @@ pub struct GenerationRecord {
 
     /// Aggregate statistics
     pub summary: GenerationSummary,
+
+    /// Prompts whose generation failed (empty for successful runs)
+    #[serde(default)]
+    pub failures: Vec<GenerationFailure>,
+
+    /// Command that made these calls when it was not a generation run
+    /// (e.g. `verify-determinism`); such records count toward budgets but
+    /// are left out of generation totals and prompt history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
 }
 
 /// Metadata for a single prompt's generation
@@ -53,6 +71,14 @@ pub struct PromptRecord {
     /// Tokens produced (0 if from cache)
     pub tokens_out: u64,
 
+    /// Part of `tokens_in` read from or written to the provider's prompt cache
+    #[serde(default)]
+    pub prompt_cache: PromptCacheTokens,
+
+    /// Whether the generation ran in a batch job, billed at [`BATCH_PRICE_RATE`]
+    #[serde(default)]
+    pub batch: bool,
+
     /// Generation time in milliseconds (0 if from cache)
     pub duration_ms: u64,
 
@@ -61,10 +87,31 @@ pub struct PromptRecord {
 
     /// Estimated cost in USD
     pub cost_usd: f64,
+
+    /// Estimated input tokens per request component (None if from cache)
+    #[serde(default)]
+    pub input_breakdown: Option<InputBreakdown>,
+}
+
+/// Estimated input tokens attributable to each part of a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputBreakdown {
+    /// System prompt (preamble, conventions, output format)
+    pub system: u64,
+    /// Imported code
+    pub context: u64,
+    /// Prompt body
+    pub prompt: u64,
+}
+
+impl InputBreakdown {
+    pub fn total(&self) -> u64 {
+        self.system + self.context + self.prompt
+    }
 }
 
 /// Aggregate statistics for a generation run
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GenerationSummary {
     /// Total prompts processed
     pub total_prompts: usize,
@@ -84,6 +131,10 @@ pub struct GenerationSummary {
     /// Total output tokens
     pub total_tokens_out: u64,
 
+    /// Part of `total_tokens_in` read from or written to the provider's prompt cache
+    #[serde(default)]
+    pub total_prompt_cache: PromptCacheTokens,
+
     /// Total estimated cost in USD
     pub total_cost_usd: f64,
 
@@ -100,6 +151,74 @@ pub struct GenerationSummary {
     pub patches_conflicted: usize,
 }
 
+/// A failed prompt generation, with the provider's error classified
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationFailure {
+    /// Prompt file path (relative to repo root)
+    pub prompt_path: PathBuf,
+
+    /// Provider that was called
+    pub provider: String,
+
+    /// HTTP status returned by the provider, if any
+    pub status: Option<u16>,
+
+    /// Error category (API error type, "network", "invalid_response", ...)
+    pub error_type: String,
+
+    /// Whether the error was considered retriable
+    pub retriable: bool,
+
+    /// Number of attempts made before giving up
+    pub attempts: u32,
+
+    /// Full error message
+    pub message: String,
+}
+
+impl GenerationFailure {
+    /// Build a failure record from a generation error.
+    ///
+    /// Structured `ProviderError`s keep their classification; output held
+    /// back for secrets is `secrets_found`, and anything else (e.g. an
+    /// unparseable response) is recorded as a non-retriable
+    /// `invalid_response`.
+    pub fn from_error(prompt_path: PathBuf, provider: &str, err: &anyhow::Error) -> Self {
+        let message = format!("{:#}", err);
+        if let Some(LitError::SecretsFound { .. }) = LitError::of(err) {
+            return Self {
+                prompt_path,
+                provider: provider.to_string(),
+                status: None,
+                error_type: "secrets_found".to_string(),
+                retriable: false,
+                attempts: 1,
+                message,
+            };
+        }
+        match err.downcast_ref::<ProviderError>() {
+            Some(pe) => Self {
+                prompt_path,
+                provider: pe.provider.clone(),
+                status: pe.status,
+                error_type: pe.error_type.clone(),
+                retriable: pe.retriable,
+                attempts: pe.attempts,
+                message,
+            },
+            None => Self {
+                prompt_path,
+                provider: provider.to_string(),
+                status: None,
+                error_type: "invalid_response".to_string(),
+                retriable: false,
+                attempts: 1,
+                message,
+            },
+        }
+    }
+}
+
 /// Known model pricing (per million tokens, in USD)
 #[derive(Debug, Clone)]
 pub struct ModelPricing {
@@ -116,7 +235,82 @@ impl ModelPricing {
     }
 }
 
+/// A pricing file entry: prices for models whose name starts with `model`,
+/// in effect from `effective` (or always, if unset).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PricingEntry {
+    pub model: String,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    pub effective: Option<NaiveDate>,
+}
+
+/// User-maintained pricing that overrides the built-in table.
+///
+/// Loaded from `.lit/pricing.toml` in the repo and `lit/pricing.toml` in the
+/// user's config directory (repo entries win):
+///
+/// ```toml
+/// [[models]]
+/// model = "claude-sonnet-4-5"
+/// input_per_million = 3.0
+/// output_per_million = 15.0
+/// effective = "2025-09-29"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PricingTable {
+    #[serde(default)]
+    pub models: Vec<PricingEntry>,
+}
+
+impl PricingTable {
+    /// Load and merge pricing files, earlier files taking precedence.
+    /// Missing files are skipped.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut table = PricingTable::default();
+        for path in paths {
+            if !path.is_file() {
+                continue;
+            }
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read pricing file: {}", path.display()))?;
+            let file: PricingTable = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse pricing file: {}", path.display()))?;
+            table.models.extend(file.models);
+        }
+        Ok(table)
+    }
+
+    /// Price for `model` on `date`: the longest matching model prefix, then
+    /// the latest entry already in effect. Ties go to the earlier file.
+    pub fn lookup(&self, model: &str, date: NaiveDate) -> Option<ModelPricing> {
+        self.models
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| model.starts_with(e.model.as_str()))
+            .filter(|(_, e)| e.effective.is_none_or(|d| d <= date))
+            .max_by_key(|(i, e)| (e.model.len(), e.effective, std::cmp::Reverse(*i)))
+            .map(|(_, e)| ModelPricing::new(e.input_per_million, e.output_per_million))
+    }
+}
+
+/// The user-level pricing file: `$XDG_CONFIG_HOME/lit/pricing.toml`, or
+/// `~/.config/lit/pricing.toml`.
+pub fn user_pricing_file() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config_dir.join("lit").join("pricing.toml"))
+}
+
 impl GenerationRecord {
+    /// Whether this record is a generation run rather than calls made by
+    /// another command (see [`GenerationRecord::command`])
+    pub fn is_generation(&self) -> bool {
+        self.command.is_none()
+    }
+
     /// Write a generation record to disk.
     ///
     /// Records are stored at `.lit/generations/<timestamp>.json`.
@@ -134,7 +328,7 @@ impl GenerationRecord {
         let json =
             serde_json::to_string_pretty(self).context("Failed to serialize generation record")?;
 
-        std::fs::write(&path, json)
+        atomic::write(&path, json)
             .with_context(|| format!("Failed to write generation record: {}", path.display()))
     }
 
@@ -180,7 +374,7 @@ impl GenerationRecord {
         }
 
         // Sort newest first
-        records.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        records.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
 
         Ok(records)
     }
@@ -193,29 +387,47 @@ impl GenerationRecord {
     }
 }
 
+/// Price of prompt cache reads, relative to the input price
+pub const PROMPT_CACHE_READ_RATE: f64 = 0.1;
+
+/// Price of prompt cache writes, relative to the input price
+pub const PROMPT_CACHE_WRITE_RATE: f64 = 1.25;
+
+/// Price of requests run through a provider's batch API, relative to the
+/// usual price
+pub const BATCH_PRICE_RATE: f64 = 0.5;
+
 /// Estimate the cost of a generation based on model and token counts.
 ///
+/// `prompt_cache` is the part of `tokens_in` read from or written to the
+/// provider's prompt cache, billed at [`PROMPT_CACHE_READ_RATE`] and
+/// [`PROMPT_CACHE_WRITE_RATE`] times the input price.
+///
 /// If `pricing_override` is provided (from `[model.pricing]` in lit.toml),
 /// it takes precedence over the built-in pricing table.
 pub fn estimate_cost(
     model: &str,
     tokens_in: u64,
     tokens_out: u64,
+    prompt_cache: PromptCacheTokens,
     pricing_override: Option<&ModelPricing>,
 ) -> f64 {
     let pricing = match pricing_override {
         Some(p) => p.clone(),
         None => get_model_pricing(model),
     };
-    let input_cost = (tokens_in as f64 / 1_000_000.0) * pricing.input_per_million;
+    let uncached = tokens_in.saturating_sub(prompt_cache.read + prompt_cache.write) as f64
+        + prompt_cache.read as f64 * PROMPT_CACHE_READ_RATE
+        + prompt_cache.write as f64 * PROMPT_CACHE_WRITE_RATE;
+    let input_cost = (uncached / 1_000_000.0) * pricing.input_per_million;
     let output_cost = (tokens_out as f64 / 1_000_000.0) * pricing.output_per_million;
     input_cost + output_cost
 }
 
 /// Get pricing for a known model. Falls back to conservative defaults for unknown models.
 ///
-/// Pricing as of February 2026. Override in lit.toml with `[model.pricing]` if these
-/// become stale:
+/// Pricing as of February 2026. Override in lit.toml with `[model.pricing]`, or
+/// per model with a [`PricingTable`] file, if these become stale:
 ///
 /// ```toml
 /// [model.pricing]
@@ -281,6 +493,281 @@ pub fn get_model_pricing(model: &str) -> ModelPricing {
     }
 }
 
+/// A `[budget.alerts]` threshold that was exceeded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetAlert {
+    /// "run" or "week"
+    pub period: &'static str,
+    pub spent_usd: f64,
+    pub threshold_usd: f64,
+}
+
+impl BudgetAlert {
+    pub fn message(&self) -> String {
+        match self.period {
+            "run" => format!(
+                "This run cost {}, over the {} per-run budget",
+                format_cost(self.spent_usd),
+                format_cost(self.threshold_usd)
+            ),
+            _ => format!(
+                "Spent {} in the last 7 days, over the {} weekly budget",
+                format_cost(self.spent_usd),
+                format_cost(self.threshold_usd)
+            ),
+        }
+    }
+}
+
+/// Check `[budget.alerts]` thresholds.
+///
+/// `run_cost` is the cost of the run being reported (if any); the weekly
+/// total sums every record from the seven days before `now`.
+pub fn check_budget(
+    alerts: &BudgetAlertsConfig,
+    run_cost: Option<f64>,
+    records: &[GenerationRecord],
+    now: DateTime<Utc>,
+) -> Vec<BudgetAlert> {
+    let mut exceeded = Vec::new();
+    if let (Some(threshold_usd), Some(spent_usd)) = (alerts.warn_at_usd_per_run, run_cost)
+        && spent_usd > threshold_usd
+    {
+        exceeded.push(BudgetAlert { period: "run", spent_usd, threshold_usd });
+    }
+    if let Some(threshold_usd) = alerts.warn_at_usd_per_week {
+        let week_start = now - chrono::Duration::days(7);
+        let spent_usd: f64 = records
+            .iter()
+            .filter(|r| r.timestamp > week_start && r.timestamp <= now)
+            .map(|r| r.summary.total_cost_usd)
+            .sum();
+        if spent_usd > threshold_usd {
+            exceeded.push(BudgetAlert { period: "week", spent_usd, threshold_usd });
+        }
+    }
+    exceeded
+}
+
+/// Warn about exceeded `[budget.alerts]` thresholds, posting them to the
+/// configured webhook when `notify` is set. Returns the alert messages.
+pub async fn report_budget(
+    config: &LitConfig,
+    root: &Path,
+    run_cost: Option<f64>,
+    notify: bool,
+) -> Vec<String> {
+    let alerts = &config.budget.alerts;
+    if alerts.warn_at_usd_per_run.is_none() && alerts.warn_at_usd_per_week.is_none() {
+        return Vec::new();
+    }
+    let records = GenerationRecord::list(&root.join(".lit").join("generations")).unwrap_or_default();
+    let exceeded = check_budget(alerts, run_cost, &records, Utc::now());
+    if exceeded.is_empty() {
+        return Vec::new();
+    }
+
+    eprintln!();
+    eprintln!("{}", style::header("Budget alert"));
+    for alert in &exceeded {
+        eprintln!("  {}", style::warning(&alert.message()));
+    }
+
+    if notify
+        && let Some(ref url) = alerts.webhook
+    {
+        let text = exceeded.iter().map(|a| a.message()).collect::<Vec<_>>().join("\n");
+        let payload = serde_json::json!({
+            "project": config.project.name,
+            "text": format!("lit budget alert for {}: {}", config.project.name, text),
+            "alerts": exceeded,
+        });
+        let sent = match service_client() {
+            Ok(client) => client
+                .post(url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            eprintln!("  {}", style::warning(&format!("Failed to send budget webhook: {}", e)));
+        }
+    }
+    exceeded.iter().map(|alert| alert.message()).collect()
+}
+
+/// Cumulative spend on one prompt across generation records
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptCostTotal {
+    pub prompt_path: PathBuf,
+    /// Fresh generations (cache hits cost nothing and aren't counted)
+    pub generations: usize,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub cost_usd: f64,
+    pub last_generated: Option<DateTime<Utc>>,
+}
+
+/// Every prompt's cumulative spend, most expensive first
+pub fn prompt_cost_totals(records: &[GenerationRecord]) -> Vec<PromptCostTotal> {
+    let mut totals: BTreeMap<&Path, PromptCostTotal> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.is_generation()) {
+        for p in &record.prompts {
+            let total = totals.entry(&p.prompt_path).or_insert_with(|| PromptCostTotal {
+                prompt_path: p.prompt_path.clone(),
+                generations: 0,
+                tokens_in: 0,
+                tokens_out: 0,
+                cost_usd: 0.0,
+                last_generated: None,
+            });
+            if p.from_cache {
+                continue;
+            }
+            total.generations += 1;
+            total.tokens_in += p.tokens_in;
+            total.tokens_out += p.tokens_out;
+            total.cost_usd += p.cost_usd;
+            total.last_generated = total.last_generated.max(Some(record.timestamp));
+        }
+    }
+    let mut totals: Vec<PromptCostTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    totals
+}
+
+/// Totals across generation records, as `lit cost` shows them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostSummary {
+    pub generations: usize,
+    pub total_cost_usd: f64,
+    pub total_tokens_in: u64,
+    pub total_tokens_out: u64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub first_run: Option<DateTime<Utc>>,
+    pub latest_run: Option<DateTime<Utc>>,
+    /// Spend recorded by other commands (e.g. `lit verify-determinism`),
+    /// not included in the totals above
+    pub other_cost_usd: f64,
+}
+
+impl CostSummary {
+    pub fn of(records: &[GenerationRecord]) -> Self {
+        let (generations, others): (Vec<&GenerationRecord>, Vec<&GenerationRecord>) =
+            records.iter().partition(|r| r.is_generation());
+        Self {
+            generations: generations.len(),
+            total_cost_usd: generations.iter().map(|r| r.summary.total_cost_usd).sum(),
+            total_tokens_in: generations.iter().map(|r| r.summary.total_tokens_in).sum(),
+            total_tokens_out: generations.iter().map(|r| r.summary.total_tokens_out).sum(),
+            cache_hits: generations.iter().map(|r| r.summary.cache_hits).sum(),
+            cache_misses: generations.iter().map(|r| r.summary.cache_misses).sum(),
+            first_run: generations.iter().map(|r| r.timestamp).min(),
+            latest_run: generations.iter().map(|r| r.timestamp).max(),
+            other_cost_usd: others.iter().map(|r| r.summary.total_cost_usd).sum(),
+        }
+    }
+}
+
+/// One prompt's entries across generation records, oldest first
+pub fn prompt_history<'a>(
+    records: &'a [GenerationRecord],
+    prompt: &Path,
+) -> Vec<(DateTime<Utc>, &'a PromptRecord)> {
+    let mut history: Vec<_> = records
+        .iter()
+        .filter(|r| r.is_generation())
+        .flat_map(|r| {
+            r.prompts
+                .iter()
+                .filter(|p| p.prompt_path == prompt)
+                .map(move |p| (r.timestamp, p))
+        })
+        .collect();
+    history.sort_by_key(|(timestamp, _)| *timestamp);
+    history
+}
+
+/// One prompt's entry in a generation record, flattened for export
+/// (`lit cost --export`)
+#[derive(Debug, Clone, Serialize)]
+pub struct CostRow {
+    pub timestamp: DateTime<Utc>,
+    pub prompt: PathBuf,
+    pub model: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub prompt_cache_read: u64,
+    pub prompt_cache_write: u64,
+    pub cost_usd: f64,
+    pub cache_hit: bool,
+    pub batch: bool,
+    pub duration_ms: u64,
+}
+
+/// Column order of [`cost_rows_csv`]
+pub const COST_CSV_HEADER: &str = "timestamp,prompt,model,tokens_in,tokens_out,prompt_cache_read,prompt_cache_write,cost_usd,cache_hit,batch,duration_ms";
+
+/// Every prompt entry of every record, oldest first
+pub fn cost_rows(records: &[GenerationRecord]) -> Vec<CostRow> {
+    let mut rows: Vec<CostRow> = records
+        .iter()
+        .flat_map(|r| {
+            r.prompts.iter().map(move |p| CostRow {
+                timestamp: r.timestamp,
+                prompt: p.prompt_path.clone(),
+                model: p.model.clone(),
+                tokens_in: p.tokens_in,
+                tokens_out: p.tokens_out,
+                prompt_cache_read: p.prompt_cache.read,
+                prompt_cache_write: p.prompt_cache.write,
+                cost_usd: p.cost_usd,
+                cache_hit: p.from_cache,
+                batch: p.batch,
+                duration_ms: p.duration_ms,
+            })
+        })
+        .collect();
+    rows.sort_by_key(|row| row.timestamp);
+    rows
+}
+
+/// Render rows as CSV with a header line (RFC 4180 quoting)
+pub fn cost_rows_csv(rows: &[CostRow]) -> String {
+    let mut out = format!("{}\n", COST_CSV_HEADER);
+    for row in rows {
+        let fields = [
+            row.timestamp.to_rfc3339(),
+            row.prompt.display().to_string(),
+            row.model.clone(),
+            row.tokens_in.to_string(),
+            row.tokens_out.to_string(),
+            row.prompt_cache_read.to_string(),
+            row.prompt_cache_write.to_string(),
+            format!("{:.6}", row.cost_usd),
+            row.cache_hit.to_string(),
+            row.batch.to_string(),
+            row.duration_ms.to_string(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 /// Format a cost in USD for display.
 pub fn format_cost(cost_usd: f64) -> String {
     if cost_usd < 0.001 {
@@ -292,6 +779,19 @@ pub fn format_cost(cost_usd: f64) -> String {
     }
 }
 
+/// Describe prompt cache usage for display after a token count (e.g.
+/// " (3,000 cache read, 120 cache write)"), or "" when the cache wasn't used
+pub fn format_prompt_cache(cache: &PromptCacheTokens) -> String {
+    if *cache == PromptCacheTokens::default() {
+        return String::new();
+    }
+    format!(
+        " ({} cache read, {} cache write)",
+        format_tokens(cache.read),
+        format_tokens(cache.write)
+    )
+}
+
 /// Format a token count for display (e.g., 1234 → "1,234", 1234567 → "1.2M").
 pub fn format_tokens(tokens: u64) -> String {
     if tokens >= 1_000_000 {
@@ -312,6 +812,84 @@ pub fn format_tokens(tokens: u64) -> String {
     }
 }
 
+/// Build the generation record for a pipeline run, returning it with the total cost
+pub fn build_record(
+    config: &LitConfig,
+    result: &PipelineResult,
+    files_written: usize,
+    patches_applied: usize,
+    patches_conflicted: usize,
+) -> (GenerationRecord, f64) {
+    let today = chrono::Utc::now().date_naive();
+    let mut prompt_records = Vec::new();
+    let mut total_cost = 0.0;
+    for output in &result.outputs {
+        let mut cost = estimate_cost(
+            &output.model,
+            output.tokens_in,
+            output.tokens_out,
+            output.prompt_cache,
+            config.model_pricing(&output.model, today).as_ref(),
+        );
+        if output.batch {
+            cost *= BATCH_PRICE_RATE;
+        }
+        total_cost += cost;
+
+        prompt_records.push(PromptRecord {
+            prompt_path: output.prompt_path.clone(),
+            output_files: output.files.keys().cloned().collect(),
+            input_hash: output.input_hash.clone(),
+            from_cache: output.from_cache,
+            tokens_in: output.tokens_in,
+            tokens_out: output.tokens_out,
+            prompt_cache: output.prompt_cache,
+            batch: output.batch,
+            duration_ms: output.duration_ms,
+            model: output.model.clone(),
+            cost_usd: cost,
+            input_breakdown: output.input_breakdown,
+        });
+    }
+
+    let record = GenerationRecord {
+        timestamp: Utc::now(),
+        project: config.project.name.clone(),
+        model: config.model.model.clone(),
+        temperature: config.model.temperature,
+        seed: config.model.seed,
+        language: config.language.default.clone(),
+        framework: config.framework.as_ref().map(|fw| fw.name.clone()),
+        prompts: prompt_records,
+        summary: GenerationSummary {
+            total_prompts: result.outputs.len() + result.skipped.len() + result.failures.len(),
+            cache_hits: result.cache_hits,
+            cache_misses: result.cache_misses,
+            skipped: result.skipped.len(),
+            total_tokens_in: result.total_tokens_in,
+            total_tokens_out: result.total_tokens_out,
+            total_prompt_cache: result.total_prompt_cache,
+            total_cost_usd: total_cost,
+            total_duration_ms: result.total_duration_ms,
+            total_files_written: files_written,
+            patches_applied,
+            patches_conflicted,
+        },
+        failures: result.failures.clone(),
+        command: None,
+    };
+
+    (record, total_cost)
+}
+
+/// Write a generation record to .lit/generations/ (warn on failure)
+pub fn write_record(root: &Path, record: &GenerationRecord) {
+    let generations_dir = root.join(".lit").join("generations");
+    if let Err(e) = record.write(&generations_dir) {
+        eprintln!("  {}", style::warning(&format!("Failed to write generation record: {}", e)));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,9 +911,12 @@ mod tests {
                     from_cache: false,
                     tokens_in: 500,
                     tokens_out: 1200,
+                    prompt_cache: PromptCacheTokens::default(),
+                    batch: false,
                     duration_ms: 3500,
                     model: "claude-sonnet-4-5-20250929".to_string(),
                     cost_usd: 0.0195,
+                    input_breakdown: Some(InputBreakdown { system: 120, context: 300, prompt: 80 }),
                 },
                 PromptRecord {
                     prompt_path: PathBuf::from("prompts/schemas/user.prompt.md"),
@@ -344,9 +925,12 @@ mod tests {
                     from_cache: true,
                     tokens_in: 0,
                     tokens_out: 0,
+                    prompt_cache: PromptCacheTokens::default(),
+                    batch: false,
                     duration_ms: 0,
                     model: "claude-sonnet-4-5-20250929".to_string(),
                     cost_usd: 0.0,
+                    input_breakdown: None,
                 },
             ],
             summary: GenerationSummary {
@@ -356,12 +940,15 @@ mod tests {
                 skipped: 0,
                 total_tokens_in: 500,
                 total_tokens_out: 1200,
+                total_prompt_cache: PromptCacheTokens::default(),
                 total_cost_usd: 0.0195,
                 total_duration_ms: 3500,
                 total_files_written: 2,
                 patches_applied: 0,
                 patches_conflicted: 0,
             },
+            failures: Vec::new(),
+            command: None,
         }
     }
 
@@ -377,6 +964,8 @@ mod tests {
         assert_eq!(deserialized.summary.cache_hits, 1);
         assert_eq!(deserialized.summary.total_tokens_in, 500);
         assert_eq!(deserialized.summary.total_cost_usd, 0.0195);
+        assert_eq!(deserialized.prompts[0].input_breakdown.unwrap().total(), 500);
+        assert!(deserialized.prompts[1].input_breakdown.is_none());
     }
 
     #[test]
@@ -460,7 +1049,7 @@ mod tests {
     #[test]
     fn test_estimate_cost_sonnet() {
         // 1000 input tokens + 2000 output tokens with Sonnet pricing
-        let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, None);
+        let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, Default::default(), None);
         // Input: 1000/1M * $3.0 = $0.003
         // Output: 2000/1M * $15.0 = $0.03
         // Total: $0.033
@@ -469,7 +1058,7 @@ mod tests {
 
     #[test]
     fn test_estimate_cost_haiku() {
-        let cost = estimate_cost("claude-3-5-haiku-20241022", 1000, 2000, None);
+        let cost = estimate_cost("claude-3-5-haiku-20241022", 1000, 2000, Default::default(), None);
         // Input: 1000/1M * $0.80 = $0.0008
         // Output: 2000/1M * $4.0 = $0.008
         // Total: $0.0088
@@ -479,8 +1068,8 @@ mod tests {
     #[test]
     fn test_estimate_cost_unknown_model() {
         // Unknown models use Sonnet-tier defaults
-        let cost = estimate_cost("some-future-model", 1000, 2000, None);
-        let sonnet_cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, None);
+        let cost = estimate_cost("some-future-model", 1000, 2000, Default::default(), None);
+        let sonnet_cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, Default::default(), None);
         assert_eq!(cost, sonnet_cost);
     }
 
@@ -488,24 +1077,183 @@ mod tests {
     fn test_estimate_cost_with_override() {
         // Custom pricing should override built-in defaults
         let custom = ModelPricing::new(10.0, 50.0);
-        let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, Some(&custom));
+        let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 2000, Default::default(), Some(&custom));
         // Input: 1000/1M * $10.0 = $0.01
         // Output: 2000/1M * $50.0 = $0.10
         // Total: $0.11
         assert!((cost - 0.11).abs() < 0.0001, "Expected ~$0.11, got {}", cost);
     }
 
+    #[test]
+    fn test_estimate_cost_prompt_cache() {
+        let custom = ModelPricing::new(10.0, 0.0);
+        let cache = PromptCacheTokens { read: 800_000, write: 100_000 };
+        let cost = estimate_cost("claude-sonnet-4-5-20250929", 1_000_000, 0, cache, Some(&custom));
+        // Uncached: 100k * $10/M = $1.00
+        // Reads: 800k * $1/M = $0.80
+        // Writes: 100k * $12.50/M = $1.25
+        assert!((cost - 3.05).abs() < 0.0001, "Expected ~$3.05, got {}", cost);
+    }
+
+    #[test]
+    fn test_pricing_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo_file = dir.path().join("repo.toml");
+        let user_file = dir.path().join("user.toml");
+        std::fs::write(
+            &repo_file,
+            r#"
+[[models]]
+model = "claude-sonnet-4-5"
+input_per_million = 2.0
+output_per_million = 10.0
+effective = "2026-03-01"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &user_file,
+            r#"
+[[models]]
+model = "claude-sonnet"
+input_per_million = 4.0
+output_per_million = 20.0
+
+[[models]]
+model = "claude-sonnet-4-5"
+input_per_million = 3.5
+output_per_million = 17.5
+"#,
+        )
+        .unwrap();
+        let missing = dir.path().join("missing.toml");
+        let table = PricingTable::load(&[repo_file, user_file, missing]).unwrap();
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        // Longest prefix, then the latest entry in effect
+        let p = table.lookup("claude-sonnet-4-5-20250929", date("2026-04-01")).unwrap();
+        assert_eq!(p.input_per_million, 2.0);
+        let p = table.lookup("claude-sonnet-4-5-20250929", date("2026-02-01")).unwrap();
+        assert_eq!(p.input_per_million, 3.5);
+        let p = table.lookup("claude-sonnet-4-20250514", date("2026-04-01")).unwrap();
+        assert_eq!(p.input_per_million, 4.0);
+        assert!(table.lookup("gpt-4o", date("2026-04-01")).is_none());
+    }
+
     #[test]
     fn test_estimate_cost_opus_tiers() {
         // Opus 4.5/4.6 should be $5/$25, not $15/$75
-        let cost_new = estimate_cost("claude-opus-4-5-20260101", 1_000_000, 0, None);
+        let cost_new = estimate_cost("claude-opus-4-5-20260101", 1_000_000, 0, Default::default(), None);
         assert!((cost_new - 5.0).abs() < 0.01, "Opus 4.5 input should be $5/MTok, got {}", cost_new);
 
         // Opus 4/4.1 should still be $15/$75
-        let cost_old = estimate_cost("claude-opus-4-20250514", 1_000_000, 0, None);
+        let cost_old = estimate_cost("claude-opus-4-20250514", 1_000_000, 0, Default::default(), None);
         assert!((cost_old - 15.0).abs() < 0.01, "Opus 4 input should be $15/MTok, got {}", cost_old);
     }
 
+    #[test]
+    fn test_prompt_cost_totals_and_history() {
+        let older = sample_record();
+        let mut newer = sample_record();
+        newer.timestamp = older.timestamp + chrono::Duration::hours(1);
+        newer.prompts[0].cost_usd = 0.03;
+        newer.prompts[1].from_cache = false;
+        newer.prompts[1].cost_usd = 0.01;
+        let records = vec![newer.clone(), older.clone()];
+
+        let totals = prompt_cost_totals(&records);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].prompt_path, PathBuf::from("prompts/models/user.prompt.md"));
+        assert_eq!(totals[0].generations, 2);
+        assert_eq!(totals[0].tokens_out, 2400);
+        assert!((totals[0].cost_usd - 0.0495).abs() < 1e-9);
+        assert_eq!(totals[0].last_generated, Some(newer.timestamp));
+        assert_eq!(totals[1].generations, 1);
+
+        let history = prompt_history(&records, Path::new("prompts/models/user.prompt.md"));
+        let timestamps: Vec<_> = history.iter().map(|(t, _)| *t).collect();
+        assert_eq!(timestamps, vec![older.timestamp, newer.timestamp]);
+        assert!(prompt_history(&records, Path::new("prompts/none.prompt.md")).is_empty());
+
+        let summary = CostSummary::of(&records);
+        assert_eq!(summary.generations, 2);
+        assert_eq!(summary.total_tokens_in, 2 * older.summary.total_tokens_in);
+        assert_eq!(summary.first_run, Some(older.timestamp));
+        assert_eq!(summary.latest_run, Some(newer.timestamp));
+        assert_eq!(CostSummary::of(&[]), CostSummary::default());
+    }
+
+    #[test]
+    fn test_command_records_left_out_of_generation_totals() {
+        let generation = sample_record();
+        let mut check = sample_record();
+        check.command = Some("verify-determinism".to_string());
+        check.summary.total_cost_usd = 0.5;
+        let records = vec![generation.clone(), check];
+
+        let summary = CostSummary::of(&records);
+        assert_eq!(summary.generations, 1);
+        assert_eq!(summary.total_cost_usd, generation.summary.total_cost_usd);
+        assert_eq!(summary.other_cost_usd, 0.5);
+        assert_eq!(prompt_cost_totals(&records)[0].generations, 1);
+        assert_eq!(prompt_history(&records, Path::new("prompts/models/user.prompt.md")).len(), 1);
+
+        let json = serde_json::to_string(&generation).unwrap();
+        assert!(!json.contains("\"command\""));
+        let parsed: GenerationRecord = serde_json::from_str(&serde_json::to_string(&records[1]).unwrap()).unwrap();
+        assert_eq!(parsed.command.as_deref(), Some("verify-determinism"));
+    }
+
+    #[test]
+    fn test_cost_rows_csv() {
+        let mut record = sample_record();
+        record.prompts[0].prompt_path = PathBuf::from("prompts/a, \"b\".prompt.md");
+        let rows = cost_rows(&[record]);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[1].cache_hit);
+
+        let csv = cost_rows_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], COST_CSV_HEADER);
+        assert!(
+            lines[1].contains(",\"prompts/a, \"\"b\"\".prompt.md\",claude-sonnet-4-5-20250929,500,1200,0,0,0.019500,false,false,3500"),
+            "got: {}",
+            lines[1]
+        );
+        assert!(lines[2].ends_with(",0.000000,true,false,0"), "got: {}", lines[2]);
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let now = Utc::now();
+        let mut records = Vec::new();
+        for days_ago in [1, 3, 10] {
+            let mut record = sample_record();
+            record.timestamp = now - chrono::Duration::days(days_ago);
+            record.summary.total_cost_usd = 4.0;
+            records.push(record);
+        }
+
+        let alerts = BudgetAlertsConfig {
+            warn_at_usd_per_run: Some(5.0),
+            warn_at_usd_per_week: Some(7.5),
+            webhook: None,
+        };
+        // The record from 10 days ago falls outside the week
+        let exceeded = check_budget(&alerts, Some(4.0), &records, now);
+        assert_eq!(
+            exceeded,
+            vec![BudgetAlert { period: "week", spent_usd: 8.0, threshold_usd: 7.5 }]
+        );
+        assert!(exceeded[0].message().contains("$8.00"));
+
+        let exceeded = check_budget(&alerts, Some(6.0), &records[2..], now);
+        assert_eq!(exceeded.len(), 1);
+        assert_eq!(exceeded[0].period, "run");
+
+        assert!(check_budget(&BudgetAlertsConfig::default(), Some(100.0), &records, now).is_empty());
+    }
+
     #[test]
     fn test_format_cost() {
         assert_eq!(format_cost(0.0), "$0.0000");
@@ -525,6 +1273,43 @@ mod tests {
         assert_eq!(format_tokens(1234567), "1.2M");
     }
 
+    #[test]
+    fn test_failure_from_provider_error() {
+        let err = anyhow::Error::new(
+            ProviderError::new("anthropic", "overloaded_error", "overloaded".to_string())
+                .with_status(529)
+                .retriable(true),
+        )
+        .context("Failed to generate code for prompts/a.prompt.md");
+
+        let failure =
+            GenerationFailure::from_error(PathBuf::from("prompts/a.prompt.md"), "anthropic", &err);
+        assert_eq!(failure.status, Some(529));
+        assert_eq!(failure.error_type, "overloaded_error");
+        assert!(failure.retriable);
+        assert_eq!(failure.attempts, 1);
+        assert!(failure.message.contains("overloaded"));
+    }
+
+    #[test]
+    fn test_failure_from_other_error() {
+        let err = anyhow::anyhow!("no file delimiters");
+        let failure =
+            GenerationFailure::from_error(PathBuf::from("prompts/a.prompt.md"), "openai", &err);
+        assert_eq!(failure.provider, "openai");
+        assert_eq!(failure.status, None);
+        assert_eq!(failure.error_type, "invalid_response");
+        assert!(!failure.retriable);
+    }
+
+    #[test]
+    fn test_record_without_failures_field_parses() {
+        let mut json: serde_json::Value = serde_json::to_value(sample_record()).unwrap();
+        json.as_object_mut().unwrap().remove("failures");
+        let record: GenerationRecord = serde_json::from_value(json).unwrap();
+        assert!(record.failures.is_empty());
+    }
+
     #[test]
     fn test_prompt_record_cached_zero_cost() {
         let record = sample_record();
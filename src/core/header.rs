@@ -0,0 +1,170 @@
+//! Provenance headers on generated files (`[generation] file_header`).
+//!
+//! The header template is rendered per file, commented in the style of the
+//! file's language and prepended when lit writes code.lock/. Everything that
+//! reads code.lock/ back as generated code — patch detection, staleness,
+//! context for dependents — strips it first, so a header never shows up as a
+//! hand-edit. Files without a comment syntax (JSON, unknown extensions) get
+//! no header.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+/// Placeholders available in `[generation] file_header`
+pub const HEADER_PLACEHOLDERS: &[&str] = &["{prompt}", "{model}", "{timestamp}", "{input_hash}"];
+
+/// What a header says about the file it heads
+pub struct HeaderValues<'a> {
+    pub prompt: &'a Path,
+    pub model: &'a str,
+    pub timestamp: DateTime<Utc>,
+    pub input_hash: &'a str,
+}
+
+enum Comment {
+    Line(&'static str),
+    Block(&'static str, &'static str),
+}
+
+fn comment_style(path: &Path) -> Option<Comment> {
+    match path.file_name()?.to_str()? {
+        "Dockerfile" | "Makefile" => return Some(Comment::Line("#")),
+        _ => {}
+    }
+    let style = match path.extension()?.to_str()? {
+        "py" | "rb" | "sh" | "bash" | "zsh" | "pl" | "r" | "R" | "toml" | "yaml" | "yml" | "ex" | "exs"
+        | "jl" | "nim" | "ps1" => Comment::Line("#"),
+        "rs" | "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" | "go" | "java" | "kt" | "kts" | "swift" | "c"
+        | "h" | "cc" | "cpp" | "hpp" | "cs" | "scala" | "dart" | "php" | "zig" | "proto" | "groovy" => {
+            Comment::Line("//")
+        }
+        "sql" | "lua" | "hs" | "elm" => Comment::Line("--"),
+        "html" | "htm" | "xml" | "svg" | "vue" | "svelte" | "md" => Comment::Block("<!--", "-->"),
+        "css" | "scss" | "less" => Comment::Block("/*", "*/"),
+        _ => return None,
+    };
+    Some(style)
+}
+
+/// `text` as a comment block, ending with a blank line
+fn commented(text: &str, style: &Comment) -> String {
+    let lines = text.trim_end().lines();
+    let mut out = String::new();
+    match style {
+        Comment::Line(prefix) => {
+            for line in lines {
+                match line.trim_end() {
+                    "" => out.push_str(prefix),
+                    line => out.push_str(&format!("{} {}", prefix, line)),
+                }
+                out.push('\n');
+            }
+        }
+        Comment::Block(open, close) => {
+            out.push_str(open);
+            out.push('\n');
+            for line in lines {
+                out.push_str(line.trim_end());
+                out.push('\n');
+            }
+            out.push_str(close);
+            out.push('\n');
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// A leading `#!` line, which has to stay first
+fn split_shebang(content: &str) -> (&str, &str) {
+    if content.starts_with("#!") {
+        content.split_at(content.find('\n').map_or(content.len(), |i| i + 1))
+    } else {
+        ("", content)
+    }
+}
+
+/// `content` with the rendered header in front
+pub fn prepend(template: &str, path: &Path, content: &str, values: &HeaderValues) -> String {
+    let Some(style) = comment_style(path) else {
+        return content.to_string();
+    };
+    let text = template
+        .replace("{prompt}", &values.prompt.display().to_string())
+        .replace("{model}", values.model)
+        .replace("{timestamp}", &values.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .replace("{input_hash}", values.input_hash);
+    let (shebang, rest) = split_shebang(content);
+    format!("{}{}{}", shebang, commented(&text, &style), rest)
+}
+
+/// `content` without a header rendered from `template`, if it starts with
+/// one
+pub fn strip(template: &str, path: &Path, content: &str) -> String {
+    let Some(style) = comment_style(path) else {
+        return content.to_string();
+    };
+    let mut pattern = regex::escape(&commented(template, &style));
+    for placeholder in HEADER_PLACEHOLDERS {
+        pattern = pattern.replace(&regex::escape(placeholder), "[^\n]*");
+    }
+    let Ok(header) = Regex::new(&format!("^{}", pattern)) else {
+        return content.to_string();
+    };
+    let (shebang, rest) = split_shebang(content);
+    match header.find(rest) {
+        Some(found) => format!("{}{}", shebang, &rest[found.end()..]),
+        None => content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "Generated by lit from {prompt} ({model}) — do not edit.\ninput: {input_hash}\n";
+
+    fn values() -> HeaderValues<'static> {
+        HeaderValues {
+            prompt: Path::new("prompts/app.prompt.md"),
+            model: "claude-sonnet-4-5",
+            timestamp: "2025-06-01T12:00:00Z".parse().unwrap(),
+            input_hash: "abc123",
+        }
+    }
+
+    #[test]
+    fn test_prepend_by_language() {
+        let python = prepend(TEMPLATE, Path::new("src/app.py"), "x = 1\n", &values());
+        assert_eq!(
+            python,
+            "# Generated by lit from prompts/app.prompt.md (claude-sonnet-4-5) — do not edit.\n\
+             # input: abc123\n\nx = 1\n"
+        );
+        let rust = prepend("{prompt} at {timestamp}", Path::new("src/lib.rs"), "", &values());
+        assert_eq!(rust, "// prompts/app.prompt.md at 2025-06-01T12:00:00Z\n\n");
+        let html = prepend("{prompt}", Path::new("index.html"), "<p>\n", &values());
+        assert_eq!(html, "<!--\nprompts/app.prompt.md\n-->\n\n<p>\n");
+        let script = prepend("{prompt}", Path::new("run.sh"), "#!/bin/sh\necho hi\n", &values());
+        assert_eq!(script, "#!/bin/sh\n# prompts/app.prompt.md\n\necho hi\n");
+        // No comment syntax, no header
+        assert_eq!(prepend(TEMPLATE, Path::new("data.json"), "{}\n", &values()), "{}\n");
+    }
+
+    #[test]
+    fn test_strip_round_trips() {
+        for (path, content) in [("src/app.py", "x = 1\n"), ("index.html", "<p>\n"), ("run.sh", "#!/bin/sh\necho hi\n")] {
+            let path = Path::new(path);
+            let headed = prepend(TEMPLATE, path, content, &values());
+            assert_eq!(strip(TEMPLATE, path, &headed), content);
+            // Files without the header (e.g. written before it was configured)
+            // are left alone
+            assert_eq!(strip(TEMPLATE, path, content), content);
+        }
+        // A hand-edit below the header survives
+        let headed = prepend(TEMPLATE, Path::new("src/app.py"), "x = 1\n", &values());
+        assert_eq!(strip(TEMPLATE, Path::new("src/app.py"), &format!("{}y = 2\n", headed)), "x = 1\ny = 2\n");
+    }
+}
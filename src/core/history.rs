@@ -0,0 +1,187 @@
+//! Prompt-scoped history for `lit log --prompt`: what each edit changed in
+//! a prompt's frontmatter and body, and what regenerating it cost.
+
+use std::fmt;
+use std::path::Path;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::core::generation_record::{GenerationRecord, PromptRecord};
+use crate::core::migrate::{frontmatter_value, split_raw};
+
+/// A top-level frontmatter key that an edit added, removed or changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrontmatterChange {
+    Added { key: String, value: String },
+    Removed { key: String, value: String },
+    Changed { key: String, from: String, to: String },
+}
+
+impl fmt::Display for FrontmatterChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontmatterChange::Added { key, value } => write!(f, "+ {}: {}", key, value),
+            FrontmatterChange::Removed { key, value } => write!(f, "- {}: {}", key, value),
+            FrontmatterChange::Changed { key, from, to } => write!(f, "~ {}: {} → {}", key, from, to),
+        }
+    }
+}
+
+/// Frontmatter keys that differ between two versions of a prompt file
+/// (`None` for a version where the file didn't exist). Frontmatter that
+/// doesn't parse counts as empty.
+pub fn frontmatter_changes(before: Option<&str>, after: Option<&str>) -> Vec<FrontmatterChange> {
+    let mapping = |raw: Option<&str>| match raw.map(frontmatter_value) {
+        Some(Ok(Value::Mapping(mapping))) => mapping,
+        _ => Mapping::new(),
+    };
+    let (before, after) = (mapping(before), mapping(after));
+    let mut changes = Vec::new();
+    for (key, value) in &after {
+        match before.get(key) {
+            None => changes.push(FrontmatterChange::Added { key: render(key), value: render(value) }),
+            Some(old) if old != value => changes.push(FrontmatterChange::Changed {
+                key: render(key),
+                from: render(old),
+                to: render(value),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in &before {
+        if !after.contains_key(key) {
+            changes.push(FrontmatterChange::Removed { key: render(key), value: render(value) });
+        }
+    }
+    changes
+}
+
+/// A YAML value on one line: strings bare, everything else as JSON
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// A prompt file's body: everything after the frontmatter
+pub fn body(raw: &str) -> &str {
+    match split_raw(raw) {
+        Ok((_, _, rest)) => rest.split_once('\n').map_or("", |(_, body)| body),
+        Err(_) => raw,
+    }
+}
+
+/// Lines added and removed in the body between two versions of a prompt
+pub fn body_changes(before: Option<&str>, after: Option<&str>) -> (usize, usize) {
+    let (before, after) = (before.map_or("", body), after.map_or("", body));
+    let (mut added, mut removed) = (0, 0);
+    for change in similar::TextDiff::from_lines(before, after).iter_all_changes() {
+        match change.tag() {
+            similar::ChangeTag::Insert => added += 1,
+            similar::ChangeTag::Delete => removed += 1,
+            similar::ChangeTag::Equal => {}
+        }
+    }
+    (added, removed)
+}
+
+/// Fresh (uncached) generations of `prompt` recorded after `since` and up to
+/// `until` (Unix seconds; `None` leaves that end open), oldest first.
+///
+/// Prompts are usually regenerated before the commit that records the edit,
+/// so a commit's generations are the ones between it and the previous
+/// commit that touched the prompt.
+pub fn fresh_generations<'a>(
+    records: &'a [GenerationRecord],
+    prompt: &Path,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Vec<&'a PromptRecord> {
+    let mut records: Vec<&GenerationRecord> = records
+        .iter()
+        .filter(|r| {
+            let t = r.timestamp.timestamp();
+            r.is_generation() && since.is_none_or(|since| t > since) && until.is_none_or(|until| t <= until)
+        })
+        .collect();
+    records.sort_by_key(|r| r.timestamp);
+    records
+        .into_iter()
+        .flat_map(|r| &r.prompts)
+        .filter(|p| p.prompt_path == prompt && !p.from_cache)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+
+    use crate::core::generation_record::GenerationSummary;
+
+    const V1: &str = "---\noutputs:\n  - src/user.py\nmodel: claude-sonnet\ntier: fast\n---\nDefine a User.\n";
+    const V2: &str = "---\noutputs:\n  - src/user.py\n  - src/schema.py\nmodel: gpt-4o\n---\nDefine a User.\nAdd a schema.\n";
+
+    #[test]
+    fn test_frontmatter_changes() {
+        let changes: Vec<String> = frontmatter_changes(Some(V1), Some(V2)).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                r#"~ outputs: ["src/user.py"] → ["src/user.py","src/schema.py"]"#,
+                "~ model: claude-sonnet → gpt-4o",
+                "- tier: fast",
+            ]
+        );
+        assert!(frontmatter_changes(Some(V1), Some(V1)).is_empty());
+        assert_eq!(frontmatter_changes(None, Some(V1)).len(), 3);
+    }
+
+    #[test]
+    fn test_body_changes() {
+        assert_eq!(body(V2), "Define a User.\nAdd a schema.\n");
+        assert_eq!(body_changes(Some(V1), Some(V2)), (1, 0));
+        assert_eq!(body_changes(Some(V2), None), (0, 2));
+    }
+
+    #[test]
+    fn test_fresh_generations() {
+        let user = PathBuf::from("prompts/user.prompt.md");
+        let record = |secs: i64, from_cache: bool| GenerationRecord {
+            timestamp: Utc.timestamp_opt(secs, 0).unwrap(),
+            project: "demo".to_string(),
+            model: "m".to_string(),
+            temperature: 0.0,
+            seed: None,
+            language: "python".to_string(),
+            framework: None,
+            prompts: vec![PromptRecord {
+                prompt_path: user.clone(),
+                output_files: Vec::new(),
+                input_hash: secs.to_string(),
+                from_cache,
+                tokens_in: 0,
+                tokens_out: 0,
+                prompt_cache: Default::default(),
+                batch: false,
+                duration_ms: 0,
+                model: "m".to_string(),
+                cost_usd: 0.0,
+                input_breakdown: None,
+            }],
+            summary: GenerationSummary::default(),
+            failures: Vec::new(),
+            command: None,
+        };
+        let records = [record(300, false), record(100, false), record(200, true), record(150, false)];
+        let found: Vec<&str> = fresh_generations(&records, &user, Some(100), Some(300))
+            .iter()
+            .map(|p| p.input_hash.as_str())
+            .collect();
+        assert_eq!(found, vec!["150", "300"]);
+        assert_eq!(fresh_generations(&records, &user, None, Some(100)).len(), 1);
+        assert!(fresh_generations(&records, Path::new("prompts/other.prompt.md"), None, None).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::core::config::HooksConfig;
+
+/// Directory of hook scripts, under `.lit/`
+pub const HOOKS_DIR: &str = "hooks";
+
+/// A point in the lit lifecycle where hooks run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPoint {
+    /// Before `lit regenerate` generates anything
+    PreGenerate,
+    /// After each file is written to code.lock/
+    PostWrite,
+    /// Before `lit commit` stages and commits
+    PreCommit,
+}
+
+impl HookPoint {
+    /// Name of the hook script in `.lit/hooks/`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookPoint::PreGenerate => "pre-generate",
+            HookPoint::PostWrite => "post-write",
+            HookPoint::PreCommit => "pre-commit",
+        }
+    }
+}
+
+/// Runs `[hooks]` commands and `.lit/hooks/` scripts.
+///
+/// Commands run through the shell from the repo root, with `LIT_ROOT` and
+/// `LIT_HOOK` set. Post-write hooks also get the written file (relative to
+/// the repo root) as `{file}` in commands, as the first argument to
+/// scripts, and as `LIT_FILE`. A hook that exits non-zero aborts the
+/// operation, with its output in the error.
+pub struct Hooks {
+    root: PathBuf,
+    config: HooksConfig,
+}
+
+impl Hooks {
+    pub fn new(root: &Path, config: HooksConfig) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            config,
+        }
+    }
+
+    /// Whether any hook is registered for `point`
+    pub fn has(&self, point: HookPoint) -> bool {
+        !self.commands(point).is_empty() || self.script(point).is_some()
+    }
+
+    /// Run every hook registered for `point`: configured commands first, then
+    /// the `.lit/hooks/` script. `file` is the written file for post-write hooks.
+    pub fn run(&self, point: HookPoint, file: Option<&Path>) -> Result<()> {
+        for command in self.commands(point) {
+            let command = match file {
+                Some(f) => command.replace("{file}", &shell_quote(&f.to_string_lossy())),
+                None => command.clone(),
+            };
+            let mut cmd = shell(&command);
+            self.execute(point, &command, &mut cmd, file)?;
+        }
+
+        if let Some(script) = self.script(point) {
+            let mut cmd = Command::new(&script);
+            if let Some(f) = file {
+                cmd.arg(f);
+            }
+            let label = format!(".lit/{}/{}", HOOKS_DIR, point.as_str());
+            self.execute(point, &label, &mut cmd, file)?;
+        }
+        Ok(())
+    }
+
+    fn commands(&self, point: HookPoint) -> &[String] {
+        match point {
+            HookPoint::PreGenerate => &self.config.pre_generate,
+            HookPoint::PostWrite => &self.config.post_write,
+            HookPoint::PreCommit => &self.config.pre_commit,
+        }
+    }
+
+    fn script(&self, point: HookPoint) -> Option<PathBuf> {
+        let path = self.root.join(".lit").join(HOOKS_DIR).join(point.as_str());
+        path.is_file().then_some(path)
+    }
+
+    fn execute(&self, point: HookPoint, label: &str, cmd: &mut Command, file: Option<&Path>) -> Result<()> {
+        if point != HookPoint::PostWrite {
+            eprintln!("  {} {}", format!("{} hook:", point.as_str()).dimmed(), label);
+        }
+        cmd.current_dir(&self.root)
+            .env("LIT_ROOT", &self.root)
+            .env("LIT_HOOK", point.as_str());
+        if let Some(f) = file {
+            cmd.env("LIT_FILE", f);
+        }
+
+        let output = cmd
+            .output()
+            .with_context(|| format!("Failed to run {} hook `{}`", point.as_str(), label))?;
+        if !output.status.success() {
+            let mut details = String::from_utf8_lossy(&output.stdout).into_owned();
+            details.push_str(&String::from_utf8_lossy(&output.stderr));
+            bail!(
+                "{} hook `{}` failed ({})\n{}",
+                point.as_str(),
+                label,
+                output.status,
+                details.trim_end()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Quote a path for substitution into a shell command
+pub(crate) fn shell_quote(s: &str) -> String {
+    if cfg!(unix) {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    } else {
+        format!("\"{}\"", s)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_run_and_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = Hooks::new(
+            dir.path(),
+            HooksConfig {
+                pre_generate: vec!["echo ok > pre.txt".to_string()],
+                post_write: vec!["echo \"$LIT_HOOK\" >> {file}".to_string()],
+                pre_commit: vec!["echo 'tests failed' >&2; exit 3".to_string()],
+            },
+        );
+
+        hooks.run(HookPoint::PreGenerate, None).unwrap();
+        assert!(dir.path().join("pre.txt").exists());
+
+        std::fs::write(dir.path().join("it's.py"), "x = 1\n").unwrap();
+        hooks.run(HookPoint::PostWrite, Some(Path::new("it's.py"))).unwrap();
+        let written = std::fs::read_to_string(dir.path().join("it's.py")).unwrap();
+        assert_eq!(written, "x = 1\npost-write\n");
+
+        let err = hooks.run(HookPoint::PreCommit, None).unwrap_err().to_string();
+        assert!(err.contains("pre-commit hook"), "got: {}", err);
+        assert!(err.contains("tests failed"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_hook_scripts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = Hooks::new(dir.path(), HooksConfig::default());
+        assert!(!hooks.has(HookPoint::PostWrite));
+
+        let hooks_dir = dir.path().join(".lit").join(HOOKS_DIR);
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        let script = hooks_dir.join("post-write");
+        std::fs::write(&script, "#!/bin/sh\necho \"$1\" > last.txt\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(hooks.has(HookPoint::PostWrite));
+        hooks.run(HookPoint::PostWrite, Some(Path::new("code.lock/a.py"))).unwrap();
+        let last = std::fs::read_to_string(dir.path().join("last.txt")).unwrap();
+        assert_eq!(last.trim(), "code.lock/a.py");
+    }
+}
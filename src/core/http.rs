@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Largest request body accepted by [`read_request`]
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// An HTTP/1.1 request, as much of it as the `lit serve` dashboard needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string, e.g. `/api/cost`
+    pub path: String,
+    /// Decoded query parameters
+    pub query: BTreeMap<String, String>,
+    /// Headers by lowercased name
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// A complete response; connections are closed after each one
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+/// Read one request; None if the client closed the connection first
+pub async fn read_request(reader: &mut (impl AsyncBufRead + Unpin)) -> Result<Option<Request>> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line: {}", request_line.trim_end());
+    };
+
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let length: usize = match headers.get("content-length") {
+        Some(value) => value.parse().context("Invalid Content-Length header")?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        bail!("Request body of {} bytes is too large", length);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+
+    Ok(Some(Request {
+        method: method.to_string(),
+        path: percent_decode(path),
+        query,
+        headers,
+        body,
+    }))
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Refuse requests a web page on another site could have made to a
+    /// server on `port` of this machine: a `Host` other than 127.0.0.1 or
+    /// localhost (DNS rebinding), a foreign `Origin`, and POSTs that aren't
+    /// JSON (which browsers only send cross-site after a preflight the
+    /// server never answers)
+    pub fn check_local(&self, port: u16) -> std::result::Result<(), Response> {
+        let local = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+        if !self.header("Host").is_some_and(|host| local.iter().any(|l| host.eq_ignore_ascii_case(l))) {
+            return Err(Response::error(403, "Requests must be addressed to 127.0.0.1 or localhost"));
+        }
+        if let Some(origin) = self.header("Origin")
+            && !local.iter().any(|l| origin.eq_ignore_ascii_case(&format!("http://{}", l)))
+        {
+            return Err(Response::error(403, &format!("Cross-origin requests from {} are not allowed", origin)));
+        }
+        let json = self
+            .header("Content-Type")
+            .is_some_and(|kind| kind.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json"));
+        if self.method == "POST" && !json {
+            return Err(Response::error(415, "POST bodies must be sent as application/json"));
+        }
+        Ok(())
+    }
+}
+
+/// Decode `%XX` escapes and `+` (as a space) in a URL component
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+            .flatten();
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl Response {
+    pub fn html(body: &str) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn json(value: &serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    /// A JSON `{ "error": message }` with the given status
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            ..Self::json(&serde_json::json!({ "error": message }))
+        }
+    }
+
+    /// The response as written to the socket
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            415 => "Unsupported Media Type",
+            _ => "Internal Server Error",
+        };
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len()
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /api/regenerate?prompt=prompts%2Fa.prompt.md&all HTTP/1.1\r\nHost: localhost\r\ncontent-length: 7\r\n\r\n{\"a\":1}";
+        let mut reader = tokio::io::BufReader::new(&raw[..]);
+        let request = read_request(&mut reader).await.unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/regenerate");
+        assert_eq!(request.query["prompt"], "prompts/a.prompt.md");
+        assert_eq!(request.query["all"], "");
+        assert_eq!(request.body, b"{\"a\":1}");
+
+        let mut empty = tokio::io::BufReader::new(&b""[..]);
+        assert_eq!(read_request(&mut empty).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_local() {
+        async fn status(raw: String) -> Option<u16> {
+            let request = read_request(&mut tokio::io::BufReader::new(raw.as_bytes())).await.unwrap().unwrap();
+            request.check_local(4848).err().map(|response| response.status)
+        }
+
+        assert_eq!(status("GET / HTTP/1.1\r\nHost: 127.0.0.1:4848\r\n\r\n".into()).await, None);
+        assert_eq!(status("GET / HTTP/1.1\r\nHost: evil.example:4848\r\n\r\n".into()).await, Some(403));
+        assert_eq!(status("GET / HTTP/1.1\r\n\r\n".into()).await, Some(403));
+
+        let post = |headers: &str| format!("POST /api/regenerate HTTP/1.1\r\nHost: localhost:4848\r\n{}\r\n", headers);
+        let json = "Content-Type: application/json; charset=utf-8\r\n";
+        assert_eq!(status(post(&format!("Origin: http://localhost:4848\r\n{}", json))).await, None);
+        assert_eq!(status(post("Content-Type: text/plain\r\n")).await, Some(415));
+        assert_eq!(status(post(&format!("Origin: https://evil.example\r\n{}", json))).await, Some(403));
+    }
+
+    #[test]
+    fn test_response_bytes() {
+        let response = Response::error(404, "no such prompt");
+        let text = String::from_utf8(response.to_bytes()).unwrap();
+        assert!(text.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text.contains("Content-Length: 26\r\n"));
+        assert!(text.ends_with("\r\n\r\n{\"error\":\"no such prompt\"}"));
+    }
+}
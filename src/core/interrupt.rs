@@ -0,0 +1,149 @@
+//! Cooperative Ctrl-C handling for long runs.
+//!
+//! Inside an [`InterruptGuard`] the first Ctrl-C only sets a flag: the
+//! pipeline stops before its next LLM call (abandoning one in flight),
+//! leaves code.lock/ untouched or finishes writing it, and records where it
+//! stopped. A second Ctrl-C, or one outside a guard, exits straight away.
+
+use std::future::Future;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use colored::Colorize;
+
+/// Exit code of a run stopped by Ctrl-C (128 + SIGINT, as shells report it)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// How often a cancellable wait checks for an interrupt
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static INSTALL: Once = Once::new();
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+// Tests run in parallel, so a simulated Ctrl-C only reaches its own thread
+// (a `#[tokio::test]` runtime runs every task on the test's thread)
+#[cfg(test)]
+thread_local! {
+    static SIMULATED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// The error a run returns when it stopped because of Ctrl-C; `main` turns
+/// it into [`EXIT_INTERRUPTED`]
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// While alive, Ctrl-C asks the run to stop instead of killing the process
+pub struct InterruptGuard;
+
+impl InterruptGuard {
+    pub fn enter() -> Self {
+        INSTALL.call_once(|| {
+            tokio::spawn(async {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    if GUARDS.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                        std::process::exit(EXIT_INTERRUPTED);
+                    }
+                    eprintln!();
+                    eprintln!(
+                        "{} {}",
+                        "Interrupted:".yellow().bold(),
+                        "stopping after the current step (Ctrl-C again to quit now)".dimmed()
+                    );
+                }
+            });
+        });
+        GUARDS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        // The last guard out resets the flag, so `lit serve` can run again
+        if GUARDS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Whether Ctrl-C was pressed inside a guard
+pub fn interrupted() -> bool {
+    #[cfg(test)]
+    if SIMULATED.get() {
+        return true;
+    }
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Ctrl-C as [`interrupted`] sees it on this thread, until dropped
+#[cfg(test)]
+pub(crate) struct SimulatedInterrupt;
+
+#[cfg(test)]
+impl SimulatedInterrupt {
+    pub(crate) fn press() -> Self {
+        SIMULATED.set(true);
+        Self
+    }
+}
+
+#[cfg(test)]
+impl Drop for SimulatedInterrupt {
+    fn drop(&mut self) {
+        SIMULATED.set(false);
+    }
+}
+
+/// Await `future` unless Ctrl-C is pressed first, in which case it is
+/// dropped (cancelling e.g. an HTTP request) and None is returned
+pub async fn until_interrupted<F: Future>(future: F) -> Option<F::Output> {
+    tokio::pin!(future);
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            output = &mut future => return Some(output),
+            _ = poll.tick() => {
+                if interrupted() {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_until_interrupted() {
+        let _guard = InterruptGuard::enter();
+        assert_eq!(until_interrupted(async { 42 }).await, Some(42));
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            "done"
+        };
+        assert_eq!(until_interrupted(slow).await, Some("done"));
+        assert!(!interrupted());
+    }
+
+    #[tokio::test]
+    async fn test_until_interrupted_drops_pending_future() {
+        let _guard = InterruptGuard::enter();
+        let ctrl_c = SimulatedInterrupt::press();
+        assert!(interrupted());
+        assert_eq!(until_interrupted(std::future::pending::<()>()).await, None);
+        drop(ctrl_c);
+        assert!(!interrupted());
+    }
+}
@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::core::config::LitConfig;
+use crate::core::generation_record::GenerationRecord;
+use crate::core::patch::PatchStore;
+use crate::core::prompt::Prompt;
+
+/// A generated artifact and its provenance
+#[derive(Debug, Clone, Serialize)]
+pub struct InventoryArtifact {
+    /// Output path (relative to code.lock/)
+    pub path: PathBuf,
+    /// Prompt that produces this file
+    pub prompt: PathBuf,
+    /// Model used in the most recent generation
+    pub model: Option<String>,
+    /// Input hash of the most recent generation
+    pub input_hash: Option<String>,
+    /// SHA-256 of the file in code.lock/ (None if not generated yet)
+    pub sha256: Option<String>,
+    /// Most recent generation run that produced this file
+    pub generated_at: Option<DateTime<Utc>>,
+    /// Oldest recorded generation run that produced this file
+    pub first_generated_at: Option<DateTime<Utc>>,
+    /// Whether the file carries manual patches
+    pub patched: bool,
+}
+
+/// Inventory of every AI-generated artifact in a lit project.
+///
+/// Built from prompts, generation records and code.lock/; exported by
+/// `lit inventory` as plain JSON or a CycloneDX BOM.
+#[derive(Debug, Clone, Serialize)]
+pub struct Inventory {
+    pub project: String,
+    pub version: String,
+    pub created_at: DateTime<Utc>,
+    pub artifacts: Vec<InventoryArtifact>,
+}
+
+impl Inventory {
+    /// Build the inventory. `records` must be sorted newest first
+    /// (as returned by `GenerationRecord::list`).
+    pub fn build(
+        config: &LitConfig,
+        prompts: &[Prompt],
+        records: &[GenerationRecord],
+        code_lock_dir: &Path,
+        patches: &PatchStore,
+    ) -> Self {
+        let mut artifacts = Vec::new();
+
+        for prompt in prompts {
+            for output in &prompt.frontmatter.outputs {
+                // Generation runs that produced this file from this prompt, newest first
+                let produced: Vec<_> = records
+                    .iter()
+                    .filter_map(|record| {
+                        record
+                            .prompts
+                            .iter()
+                            .find(|p| p.prompt_path == prompt.path && p.output_files.contains(output))
+                            .map(|p| (record.timestamp, p))
+                    })
+                    .collect();
+                let latest = produced.first();
+
+                let sha256 = std::fs::read(code_lock_dir.join(output))
+                    .ok()
+                    .map(|content| format!("{:x}", Sha256::digest(&content)));
+
+                artifacts.push(InventoryArtifact {
+                    path: output.clone(),
+                    prompt: prompt.path.clone(),
+                    model: latest.map(|(_, p)| p.model.clone()),
+                    input_hash: latest.map(|(_, p)| p.input_hash.clone()),
+                    sha256,
+                    generated_at: latest.map(|(ts, _)| *ts),
+                    first_generated_at: produced.last().map(|(ts, _)| *ts),
+                    patched: patches.has_patch(output),
+                });
+            }
+        }
+
+        artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            project: config.project.name.clone(),
+            version: config.project.version.clone(),
+            created_at: Utc::now(),
+            artifacts,
+        }
+    }
+
+    /// Render as a CycloneDX 1.5 JSON BOM, one `file` component per artifact
+    /// with lit provenance in `properties`.
+    pub fn to_cyclonedx(&self) -> serde_json::Value {
+        let components: Vec<serde_json::Value> = self
+            .artifacts
+            .iter()
+            .map(|a| {
+                let path = a.path.to_string_lossy();
+                let mut properties = vec![
+                    json!({ "name": "lit:ai-generated", "value": "true" }),
+                    json!({ "name": "lit:prompt", "value": a.prompt.to_string_lossy() }),
+                    json!({ "name": "lit:patched", "value": a.patched.to_string() }),
+                ];
+                let optional = [
+                    ("lit:model", a.model.clone()),
+                    ("lit:input-hash", a.input_hash.clone()),
+                    ("lit:generated-at", a.generated_at.map(|t| t.to_rfc3339())),
+                    ("lit:first-generated-at", a.first_generated_at.map(|t| t.to_rfc3339())),
+                ];
+                for (name, value) in optional {
+                    if let Some(value) = value {
+                        properties.push(json!({ "name": name, "value": value }));
+                    }
+                }
+
+                let mut component = json!({
+                    "type": "file",
+                    "bom-ref": path,
+                    "name": path,
+                    "properties": properties,
+                });
+                if let Some(ref sha) = a.sha256 {
+                    component["hashes"] = json!([{ "alg": "SHA-256", "content": sha }]);
+                }
+                component
+            })
+            .collect();
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "version": 1,
+            "metadata": {
+                "timestamp": self.created_at.to_rfc3339(),
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "lit",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }]
+                },
+                "component": {
+                    "type": "application",
+                    "bom-ref": self.project,
+                    "name": self.project,
+                    "version": self.version,
+                }
+            },
+            "components": components,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::generation_record::{GenerationSummary, PromptRecord};
+    use crate::providers::PromptCacheTokens;
+
+    const CONFIG: &str = r#"
+[project]
+name = "inv-app"
+version = "1.2.0"
+mapping = "manifest"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+"#;
+
+    fn record(timestamp: DateTime<Utc>, model: &str, hash: &str) -> GenerationRecord {
+        GenerationRecord {
+            timestamp,
+            project: "inv-app".to_string(),
+            model: model.to_string(),
+            temperature: 0.0,
+            seed: None,
+            language: "python".to_string(),
+            framework: None,
+            prompts: vec![PromptRecord {
+                prompt_path: PathBuf::from("prompts/user.prompt.md"),
+                output_files: vec![PathBuf::from("src/user.py")],
+                input_hash: hash.to_string(),
+                from_cache: false,
+                tokens_in: 10,
+                tokens_out: 10,
+                prompt_cache: PromptCacheTokens::default(),
+                batch: false,
+                duration_ms: 100,
+                model: model.to_string(),
+                cost_usd: 0.0,
+                input_breakdown: None,
+            }],
+            summary: GenerationSummary {
+                total_prompts: 1,
+                cache_hits: 0,
+                cache_misses: 1,
+                skipped: 0,
+                total_tokens_in: 10,
+                total_tokens_out: 10,
+                total_prompt_cache: PromptCacheTokens::default(),
+                total_cost_usd: 0.0,
+                total_duration_ms: 100,
+                total_files_written: 1,
+                patches_applied: 0,
+                patches_conflicted: 0,
+            },
+            failures: Vec::new(),
+            command: None,
+        }
+    }
+
+    fn build_inventory(dir: &Path) -> Inventory {
+        let config = LitConfig::from_str(CONFIG).unwrap();
+        let user = Prompt::parse(
+            "---\noutputs:\n  - src/user.py\n---\n\n# User\n",
+            PathBuf::from("prompts/user.prompt.md"),
+            &config,
+        )
+        .unwrap();
+        let api = Prompt::parse(
+            "---\noutputs:\n  - src/api.py\n---\n\n# API\n",
+            PathBuf::from("prompts/api.prompt.md"),
+            &config,
+        )
+        .unwrap();
+
+        let code_lock = dir.join("code.lock");
+        std::fs::create_dir_all(code_lock.join("src")).unwrap();
+        std::fs::write(code_lock.join("src/user.py"), "class User: ...\n").unwrap();
+
+        let patches = PatchStore::new(dir.join(".lit/patches"));
+        patches.save_patch(Path::new("src/user.py"), "a", "b").unwrap();
+
+        let now = Utc::now();
+        let records = vec![
+            record(now, "claude-sonnet-4-5-20250929", "newhash"),
+            record(now - chrono::Duration::days(3), "claude-3-5-sonnet-20241022", "oldhash"),
+        ];
+
+        Inventory::build(&config, &[user, api], &records, &code_lock, &patches)
+    }
+
+    #[test]
+    fn test_build_inventory() {
+        let dir = tempfile::tempdir().unwrap();
+        let inventory = build_inventory(dir.path());
+
+        assert_eq!(inventory.project, "inv-app");
+        assert_eq!(inventory.artifacts.len(), 2);
+
+        // Sorted by path: src/api.py, src/user.py
+        let api = &inventory.artifacts[0];
+        assert_eq!(api.path, PathBuf::from("src/api.py"));
+        assert!(api.model.is_none());
+        assert!(api.sha256.is_none());
+        assert!(!api.patched);
+
+        let user = &inventory.artifacts[1];
+        assert_eq!(user.prompt, PathBuf::from("prompts/user.prompt.md"));
+        assert_eq!(user.model.as_deref(), Some("claude-sonnet-4-5-20250929"));
+        assert_eq!(user.input_hash.as_deref(), Some("newhash"));
+        assert!(user.first_generated_at < user.generated_at);
+        assert_eq!(user.sha256.as_ref().unwrap().len(), 64);
+        assert!(user.patched);
+    }
+
+    #[test]
+    fn test_cyclonedx_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let bom = build_inventory(dir.path()).to_cyclonedx();
+
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["metadata"]["component"]["name"], "inv-app");
+        let components = bom["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+
+        let user = &components[1];
+        assert_eq!(user["type"], "file");
+        assert_eq!(user["name"], "src/user.py");
+        assert_eq!(user["hashes"][0]["alg"], "SHA-256");
+        let props = user["properties"].as_array().unwrap();
+        assert!(props.iter().any(|p| p["name"] == "lit:model"
+            && p["value"] == "claude-sonnet-4-5-20250929"));
+        assert!(props.iter().any(|p| p["name"] == "lit:ai-generated" && p["value"] == "true"));
+
+        // Never-generated artifacts carry no hash
+        assert!(components[0].get("hashes").is_none());
+    }
+}
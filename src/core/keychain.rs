@@ -0,0 +1,106 @@
+//! Provider API keys in the OS keychain (macOS Keychain, Secret Service on
+//! Linux, Windows Credential Manager), stored by `lit auth login` for setups
+//! where keys can't live in environment variables.
+
+use anyhow::{Context, Result};
+
+/// Keychain service name; each provider's key is stored under its name
+const SERVICE: &str = "lit";
+
+fn entry(provider: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, provider).context("Failed to open the OS keychain")
+}
+
+/// The stored key for a provider. None when there is none, or the keychain
+/// can't be reached (e.g. no Secret Service running), so key resolution can
+/// move on to its next source.
+pub fn load(provider: &str) -> Option<String> {
+    entry(provider).ok()?.get_password().ok()
+}
+
+/// Store (or replace) a provider's key
+pub fn store(provider: &str, key: &str) -> Result<()> {
+    entry(provider)?
+        .set_password(key)
+        .with_context(|| format!("Failed to store the {} API key in the OS keychain", provider))
+}
+
+/// Remove a provider's key; false if none was stored
+pub fn delete(provider: &str) -> Result<bool> {
+    match entry(provider)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove the {} API key from the OS keychain", provider)),
+    }
+}
+
+/// An in-process keychain for tests, shared by every entry (keyring's own
+/// mock keeps each password in its entry, so a stored key is never loaded)
+#[cfg(test)]
+pub(crate) mod memory {
+    use std::any::Any;
+    use std::collections::BTreeMap;
+    use std::sync::{Mutex, Once};
+
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+
+    static SECRETS: Mutex<BTreeMap<String, Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+    #[derive(Debug)]
+    struct Entry(String);
+
+    impl CredentialApi for Entry {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            SECRETS.lock().unwrap().insert(self.0.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            SECRETS.lock().unwrap().get(&self.0).cloned().ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            SECRETS.lock().unwrap().remove(&self.0).map(|_| ()).ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    struct Builder;
+
+    impl CredentialBuilderApi for Builder {
+        fn build(&self, _target: Option<&str>, service: &str, user: &str) -> keyring::Result<Box<Credential>> {
+            Ok(Box::new(Entry(format!("{}/{}", service, user))))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    /// Use the in-process keychain instead of the OS one for the rest of the
+    /// test run
+    pub fn install() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| keyring::set_default_credential_builder(Box::new(Builder)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_load_delete() {
+        memory::install();
+        assert_eq!(load("azure"), None);
+        store("azure", "sk-first").unwrap();
+        store("azure", "sk-second").unwrap();
+        assert_eq!(load("azure").as_deref(), Some("sk-second"));
+        assert!(delete("azure").unwrap());
+        assert!(!delete("azure").unwrap());
+        assert_eq!(load("azure"), None);
+    }
+}
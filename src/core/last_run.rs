@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic;
+use crate::core::dag::Dag;
+use crate::core::generation_record::{GenerationRecord, GenerationSummary};
+use crate::core::generator::PipelineResult;
+use crate::core::patch::PatchStatus;
+use crate::core::style;
+
+/// Summary of the most recent `lit regenerate`, under `.lit/`
+pub const LAST_RUN_FILE: &str = "last-run.json";
+
+/// Version of the `.lit/last-run.json` format, bumped on breaking changes
+pub const LAST_RUN_VERSION: u32 = 1;
+
+/// Machine-readable summary of a regeneration run, for CI and wrappers.
+///
+/// Overwritten by every `lit regenerate` that gets as far as generating,
+/// whether it succeeds or fails. Fields are only ever added within a version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastRun {
+    pub version: u32,
+    pub timestamp: DateTime<Utc>,
+    pub status: RunStatus,
+    /// Error message when `status` is `failed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub summary: GenerationSummary,
+    /// Every prompt in the project, in DAG order
+    pub prompts: Vec<PromptRun>,
+    /// Files written to code.lock/, sorted by path
+    pub files: Vec<WrittenFile>,
+    /// Warnings shown during the run (size limits, patch conflicts, budget alerts)
+    pub warnings: Vec<String>,
+}
+
+/// Overall outcome of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// Everything was generated and written
+    Success,
+    /// Files were written, but some patches conflicted
+    Conflicts,
+    /// Generation failed; code.lock/ was left untouched
+    Failed,
+    /// Stopped by Ctrl-C before generation finished; code.lock/ was left untouched
+    Interrupted,
+}
+
+/// What happened to one prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptStatus {
+    /// Freshly generated by the LLM
+    Generated,
+    /// Served from the cache
+    Cached,
+    /// In the regeneration set but rejected in interactive review
+    Rejected,
+    /// Not in the regeneration set
+    Unchanged,
+    /// Generation failed
+    Failed,
+    /// Not reached because an earlier prompt failed or the run was interrupted
+    Pending,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptRun {
+    pub prompt: PathBuf,
+    pub status: PromptStatus,
+    #[serde(default)]
+    pub outputs: Vec<PathBuf>,
+    #[serde(default)]
+    pub tokens_in: u64,
+    #[serde(default)]
+    pub tokens_out: u64,
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrittenFile {
+    /// Path relative to code.lock/
+    pub path: PathBuf,
+    /// Prompt that generated it (None for `[[static]]` files)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<PathBuf>,
+    /// Patch status, when a manual patch was re-applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<PatchStatus>,
+}
+
+impl LastRun {
+    /// Load `.lit/last-run.json`, if a run has been recorded
+    pub fn load(lit_dir: &Path) -> Result<Option<Self>> {
+        let path = lit_dir.join(LAST_RUN_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let run = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(run))
+    }
+
+    /// Write `.lit/last-run.json`
+    pub fn save(&self, lit_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(lit_dir)
+            .with_context(|| format!("Failed to create {}", lit_dir.display()))?;
+        let path = lit_dir.join(LAST_RUN_FILE);
+        atomic::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Number of prompts with `status`
+    pub fn count(&self, status: PromptStatus) -> usize {
+        self.prompts.iter().filter(|p| p.status == status).count()
+    }
+}
+
+/// Build the `.lit/last-run.json` summary of a pipeline run
+pub fn build_last_run(
+    dag: &Dag,
+    regeneration_set: &[PathBuf],
+    result: &PipelineResult,
+    record: &GenerationRecord,
+    files: &[WrittenFile],
+    warnings: Vec<String>,
+    error: Option<String>,
+) -> LastRun {
+    let prompts = dag
+        .order()
+        .iter()
+        .map(|path| {
+            if let Some(generated) = record.prompts.iter().find(|p| &p.prompt_path == path) {
+                let mut outputs = generated.output_files.clone();
+                outputs.sort();
+                return PromptRun {
+                    prompt: path.clone(),
+                    status: if generated.from_cache {
+                        PromptStatus::Cached
+                    } else {
+                        PromptStatus::Generated
+                    },
+                    outputs,
+                    tokens_in: generated.tokens_in,
+                    tokens_out: generated.tokens_out,
+                    cost_usd: generated.cost_usd,
+                };
+            }
+            let status = if result.failures.iter().any(|f| &f.prompt_path == path) {
+                PromptStatus::Failed
+            } else if !regeneration_set.contains(path) {
+                PromptStatus::Unchanged
+            } else if result.skipped.contains(path) {
+                PromptStatus::Rejected
+            } else {
+                PromptStatus::Pending
+            };
+            PromptRun {
+                prompt: path.clone(),
+                status,
+                outputs: Vec::new(),
+                tokens_in: 0,
+                tokens_out: 0,
+                cost_usd: 0.0,
+            }
+        })
+        .collect();
+
+    let status = if error.is_some() {
+        RunStatus::Failed
+    } else if result.interrupted {
+        RunStatus::Interrupted
+    } else if record.summary.patches_conflicted > 0 {
+        RunStatus::Conflicts
+    } else {
+        RunStatus::Success
+    };
+
+    LastRun {
+        version: LAST_RUN_VERSION,
+        timestamp: record.timestamp,
+        status,
+        error,
+        summary: record.summary.clone(),
+        prompts,
+        files: files.to_vec(),
+        warnings,
+    }
+}
+
+/// Write .lit/last-run.json (warn on failure)
+pub fn write_last_run(root: &Path, last_run: &LastRun) {
+    if let Err(e) = last_run.save(&root.join(".lit")) {
+        eprintln!("  {}", style::warning(&format!("Failed to write .lit/last-run.json: {}", e)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::PromptCacheTokens;
+
+    #[test]
+    fn test_last_run_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(LastRun::load(dir.path()).unwrap().is_none());
+
+        let run = LastRun {
+            version: LAST_RUN_VERSION,
+            timestamp: Utc::now(),
+            status: RunStatus::Conflicts,
+            error: None,
+            summary: GenerationSummary {
+                total_prompts: 2,
+                cache_hits: 1,
+                cache_misses: 1,
+                skipped: 0,
+                total_tokens_in: 100,
+                total_tokens_out: 50,
+                total_prompt_cache: PromptCacheTokens::default(),
+                total_cost_usd: 0.01,
+                total_duration_ms: 1200,
+                total_files_written: 2,
+                patches_applied: 0,
+                patches_conflicted: 1,
+            },
+            prompts: vec![
+                PromptRun {
+                    prompt: PathBuf::from("prompts/a.prompt.md"),
+                    status: PromptStatus::Cached,
+                    outputs: vec![PathBuf::from("src/a.py")],
+                    tokens_in: 0,
+                    tokens_out: 0,
+                    cost_usd: 0.0,
+                },
+                PromptRun {
+                    prompt: PathBuf::from("prompts/b.prompt.md"),
+                    status: PromptStatus::Generated,
+                    outputs: vec![PathBuf::from("src/b.py")],
+                    tokens_in: 100,
+                    tokens_out: 50,
+                    cost_usd: 0.01,
+                },
+            ],
+            files: vec![WrittenFile {
+                path: PathBuf::from("src/b.py"),
+                prompt: Some(PathBuf::from("prompts/b.prompt.md")),
+                patch: Some(PatchStatus::Conflicted),
+            }],
+            warnings: vec!["1 patch conflict(s)".to_string()],
+        };
+        run.save(dir.path()).unwrap();
+
+        let raw = std::fs::read_to_string(dir.path().join(LAST_RUN_FILE)).unwrap();
+        assert!(raw.contains("\"status\": \"conflicts\""), "got: {}", raw);
+        assert!(raw.contains("\"status\": \"cached\""), "got: {}", raw);
+        assert!(!raw.contains("\"error\""));
+
+        let loaded = LastRun::load(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded.status, RunStatus::Conflicts);
+        assert_eq!(loaded.count(PromptStatus::Generated), 1);
+        assert_eq!(loaded.files[0].patch, Some(PatchStatus::Conflicted));
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::{LintLevel, LitConfig, model_family, provider_serves};
+use crate::core::prompt::{Prompt, is_glob_import};
+
+/// A lint rule, configurable by name under `[lint.rules]` in lit.toml
+#[derive(Debug)]
+pub struct LintRule {
+    pub name: &'static str,
+    pub default: LintLevel,
+    pub description: &'static str,
+}
+
+/// Every lint rule, in the order findings are reported
+pub const RULES: &[LintRule] = &[
+    LintRule {
+        name: "undeclared-import",
+        default: LintLevel::Error,
+        description: "@import() in the body that is not declared in `imports:`",
+    },
+    LintRule {
+        name: "unused-import",
+        default: LintLevel::Warn,
+        description: "declared import never referenced with @import() in the body",
+    },
+    LintRule {
+        name: "vague-body",
+        default: LintLevel::Warn,
+        description: "body shorter than [lint] min_body_words",
+    },
+    LintRule {
+        name: "output-outside-code-lock",
+        default: LintLevel::Error,
+        description: "output path that starts with code.lock/ and so lands in code.lock/code.lock/",
+    },
+    LintRule {
+        name: "duplicate-heading",
+        default: LintLevel::Warn,
+        description: "the same markdown heading more than once in a body",
+    },
+    LintRule {
+        name: "conflicting-model",
+        default: LintLevel::Error,
+        description: "`model:` choice with an unconfigured provider or another provider's model",
+    },
+];
+
+/// Pseudo-rule for prompts that fail to parse; always an error
+pub const PARSE_RULE: &str = "parse";
+
+/// One problem found in a prompt
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub prompt: PathBuf,
+    pub rule: &'static str,
+    pub level: LintLevel,
+    pub message: String,
+}
+
+/// The configured level of a rule
+pub fn level(config: &LitConfig, rule: &LintRule) -> LintLevel {
+    config.lint.rules.get(rule.name).copied().unwrap_or(rule.default)
+}
+
+/// Run every enabled rule over the prompts. Findings are sorted by prompt,
+/// then in rule order.
+pub fn lint_prompts(prompts: &[Prompt], config: &LitConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for prompt in prompts {
+        for rule in RULES {
+            let level = level(config, rule);
+            if level == LintLevel::Off {
+                continue;
+            }
+            for message in check(rule.name, prompt, config) {
+                findings.push(Finding {
+                    prompt: prompt.path.clone(),
+                    rule: rule.name,
+                    level,
+                    message,
+                });
+            }
+        }
+    }
+    findings.sort_by(|a, b| a.prompt.cmp(&b.prompt));
+    findings
+}
+
+fn check(rule: &str, prompt: &Prompt, config: &LitConfig) -> Vec<String> {
+    match rule {
+        "undeclared-import" => prompt
+            .undeclared_body_imports()
+            .into_iter()
+            .map(|reference| {
+                format!(
+                    "@import({}) is not declared in `imports:`, so its code is not passed as context",
+                    reference
+                )
+            })
+            .collect(),
+        "unused-import" => {
+            let referenced = prompt.body_imports();
+            prompt
+                .frontmatter
+                .imports
+                .iter()
+                .filter(|import| !is_glob_import(import) && !referenced.contains(import))
+                .map(|import| {
+                    format!("imports {} but never references it with @import()", import.display())
+                })
+                .collect()
+        }
+        "vague-body" => {
+            let min = config.lint.min_body_words;
+            let words = count_words(&prompt.body);
+            if min > 0 && words < min {
+                vec![format!("body has {} word(s), fewer than min_body_words ({})", words, min)]
+            } else {
+                Vec::new()
+            }
+        }
+        "output-outside-code-lock" => prompt
+            .frontmatter
+            .outputs
+            .iter()
+            .filter_map(|output| {
+                outside_code_lock(output, &config.project.code_dir)
+                    .map(|why| format!("output {} {}", output.display(), why))
+            })
+            .collect(),
+        "duplicate-heading" => duplicate_headings(&prompt.body)
+            .into_iter()
+            .map(|(heading, count)| format!("heading \"{}\" appears {} times", heading, count))
+            .collect(),
+        "conflicting-model" => conflicting_model(prompt, config),
+        _ => Vec::new(),
+    }
+}
+
+/// Words in a body, not counting markup (heading markers, fences) and
+/// @import() references
+fn count_words(body: &str) -> usize {
+    body.split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphanumeric) && !w.starts_with("@import("))
+        .count()
+}
+
+/// Why an output path would not be written where it reads, if it wouldn't.
+/// Absolute and `..` outputs are rejected when the prompt is parsed.
+fn outside_code_lock(output: &Path, code_dir: &Path) -> Option<String> {
+    let dir = code_dir.display();
+    if output.starts_with(code_dir) {
+        return Some(format!("starts with {}/, so it is written to {}/{}/", dir, dir, dir));
+    }
+    None
+}
+
+/// Headings (with their `#` level) that appear more than once outside code
+/// fences, in first-appearance order
+fn duplicate_headings(body: &str) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut in_fence = false;
+    for (i, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        let text = trimmed[hashes..].trim();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') && !text.is_empty() {
+            let heading = format!("{} {}", "#".repeat(hashes), text);
+            counts.entry(heading).or_insert((i, 0)).1 += 1;
+        }
+    }
+    let mut duplicates: Vec<(usize, String, usize)> = counts
+        .into_iter()
+        .filter(|(_, (_, count))| *count > 1)
+        .map(|(heading, (first, count))| (first, heading, count))
+        .collect();
+    duplicates.sort();
+    duplicates.into_iter().map(|(_, heading, count)| (heading, count)).collect()
+}
+
+/// Model choices the configured providers can't honour: a provider with no
+/// API key configured, or a model name from another provider's family
+fn conflicting_model(prompt: &Prompt, config: &LitConfig) -> Vec<String> {
+    let Some(ref primary) = prompt.frontmatter.model else {
+        return Vec::new();
+    };
+    let routes = std::iter::once((primary.provider.as_str(), primary.model.as_str())).chain(
+        prompt
+            .frontmatter
+            .fallback_models
+            .iter()
+            .map(|r| (r.provider.as_str(), r.model.as_str())),
+    );
+    let mut messages = Vec::new();
+    for (provider, model) in routes {
+        if !config.has_provider(provider) {
+            messages.push(format!(
+                "model '{}' needs provider '{}', which is not configured in lit.toml",
+                model, provider
+            ));
+        } else if let Some(family) = model_family(model)
+            && !provider_serves(provider, family)
+        {
+            messages.push(format!(
+                "model '{}' is a {} model, but is sent to '{}'",
+                model, family, provider
+            ));
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(extra: &str) -> LitConfig {
+        LitConfig::from_str(&format!(
+            r#"
+[project]
+name = "test"
+version = "0.1.0"
+mapping = "manifest"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+{}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn prompt(path: &str, raw: &str, config: &LitConfig) -> Prompt {
+        Prompt::parse(raw, PathBuf::from(path), config).unwrap()
+    }
+
+    fn rules(findings: &[Finding]) -> Vec<(&str, LintLevel)> {
+        findings.iter().map(|f| (f.rule, f.level)).collect()
+    }
+
+    #[test]
+    fn test_lint_clean_prompt() {
+        let config = test_config("");
+        let p = prompt(
+            "prompts/user.prompt.md",
+            "---\noutputs:\n  - src/user.py\nimports:\n  - prompts/base.prompt.md\n---\n\
+             # User\n\nA user model that extends the Base class from @import(prompts/base.prompt.md) \
+             with a name, an email address and a creation timestamp, validated on construction.\n",
+            &config,
+        );
+        assert!(lint_prompts(&[p], &config).is_empty());
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        let config = test_config("");
+        let p = prompt(
+            "prompts/user.prompt.md",
+            "---\noutputs:\n  - code.lock/src/user.py\nimports:\n  - prompts/base.prompt.md\n\
+             model:\n  provider: anthropic\n  model: gpt-4o\n  temperature: 0.0\n---\n\
+             ## Fields\n\nUse @import(prompts/other.prompt.md).\n\n```\n## Fields\n```\n\n## Fields\n",
+            &config,
+        );
+        let findings = lint_prompts(&[p], &config);
+        assert_eq!(
+            rules(&findings),
+            vec![
+                ("undeclared-import", LintLevel::Error),
+                ("unused-import", LintLevel::Warn),
+                ("vague-body", LintLevel::Warn),
+                ("output-outside-code-lock", LintLevel::Error),
+                ("duplicate-heading", LintLevel::Warn),
+                ("conflicting-model", LintLevel::Error),
+            ]
+        );
+        assert!(findings[2].message.contains("4 word(s)"), "got: {}", findings[2].message);
+        assert!(findings[4].message.contains("\"## Fields\" appears 2 times"));
+        assert!(findings[5].message.contains("openai model"), "got: {}", findings[6].message);
+    }
+
+    #[test]
+    fn test_lint_rule_levels() {
+        let config = test_config(
+            "\n[lint]\nmin_body_words = 0\n\n[lint.rules]\nunused-import = \"off\"\nconflicting-model = \"warn\"\n",
+        );
+        let p = prompt(
+            "prompts/user.prompt.md",
+            "---\noutputs:\n  - src/user.py\nimports:\n  - prompts/base.prompt.md\n\
+             model:\n  provider: openai\n  model: gpt-4o\n  temperature: 0.0\n---\nShort.\n",
+            &config,
+        );
+        let findings = lint_prompts(&[p], &config);
+        assert_eq!(rules(&findings), vec![("conflicting-model", LintLevel::Warn)]);
+        assert!(findings[0].message.contains("provider 'openai'"), "got: {}", findings[0].message);
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs::{File, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+
+/// Repository lock file, under `.lit/` (not committed)
+pub const LOCK_FILE: &str = "lock";
+
+/// Lock files this process holds, so commands that run others (commit
+/// regenerating, pull regenerating) don't wait on themselves
+static HELD: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+fn held() -> MutexGuard<'static, BTreeSet<PathBuf>> {
+    HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Advisory lock on a lit repository, held by commands that write to
+/// code.lock/ or `.lit/` and released on drop.
+///
+/// The lock is an OS file lock on `.lit/lock`, so it goes away with the
+/// process even if lit is killed; the file itself stays behind and names
+/// the last holder for the "already running" message.
+pub struct RepoLock {
+    /// None for a nested acquire of a lock this process already holds
+    file: Option<File>,
+    path: PathBuf,
+}
+
+impl RepoLock {
+    /// Lock the repository for `command` (e.g. `regenerate`). If another
+    /// lit process holds it, wait for it with `wait`, else fail.
+    pub fn acquire(lit_dir: &Path, command: &str, wait: bool) -> Result<Self> {
+        std::fs::create_dir_all(lit_dir)
+            .with_context(|| format!("Failed to create {}", lit_dir.display()))?;
+        // The same repository reached through another path is the same lock
+        let path = lit_dir.canonicalize().unwrap_or_else(|_| lit_dir.to_path_buf()).join(LOCK_FILE);
+        if held().contains(&path) {
+            return Ok(Self { file: None, path });
+        }
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = read_holder(&mut file);
+                if !wait {
+                    bail!(
+                        "Another lit command is running in this repository ({})\n\
+                         Hint: Wait for it to finish, or pass --wait to run once it does.",
+                        holder
+                    );
+                }
+                eprintln!("Waiting for another lit command to finish ({})...", holder);
+                file.lock().with_context(|| format!("Failed to lock {}", path.display()))?;
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        writeln!(
+            file,
+            "lit {} (pid {}, started {})",
+            command,
+            std::process::id(),
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        )?;
+        held().insert(path.clone());
+        Ok(Self { file: Some(file), path })
+    }
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            let _ = file.unlock();
+            held().remove(&self.path);
+        }
+    }
+}
+
+/// Who holds the lock, as written by [`RepoLock::acquire`]
+fn read_holder(file: &mut File) -> String {
+    let mut holder = String::new();
+    let _ = file.read_to_string(&mut holder);
+    match holder.trim() {
+        "" => "unknown process".to_string(),
+        holder => holder.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let lit_dir = dir.path().join(".lit");
+        let lock = RepoLock::acquire(&lit_dir, "regenerate", false).unwrap();
+        let holder = std::fs::read_to_string(lit_dir.join(LOCK_FILE)).unwrap();
+        assert!(holder.starts_with(&format!("lit regenerate (pid {}", std::process::id())));
+
+        // Re-entrant within the process, e.g. commit running regenerate
+        let nested = RepoLock::acquire(&lit_dir, "commit", false).unwrap();
+        assert!(nested.file.is_none());
+        drop(nested);
+
+        // Holding one repository's lock doesn't stand in for another's
+        let other_dir = tempfile::tempdir().unwrap();
+        let other_lock = RepoLock::acquire(&other_dir.path().join(".lit"), "commit", false).unwrap();
+        assert!(other_lock.file.is_some());
+        drop(other_lock);
+
+        // Another handle on the file, as another process would have, can't lock it
+        let other = File::options().write(true).open(lit_dir.join(LOCK_FILE)).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+    }
+}
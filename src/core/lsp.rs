@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use crate::core::config::{LintLevel, LitConfig, glob_match};
+use crate::core::lint::{PARSE_RULE, lint_prompts};
+use crate::core::paths::{relative_to, to_slash};
+use crate::core::prompt::{Prompt, discover_project_prompts, is_glob_import, is_prompt_file, resolve_imports};
+
+// ---------- Framing ----------
+
+/// Read one `Content-Length`-framed JSON-RPC message; None at end of input
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = Some(value.trim().parse::<usize>().context("Invalid Content-Length header")?);
+        }
+    }
+    let Some(length) = length else {
+        bail!("Message without a Content-Length header");
+    };
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content).context("Invalid JSON-RPC message")?))
+}
+
+/// Write one `Content-Length`-framed JSON-RPC message
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let content = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The local path of a `file://` URI
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = path.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// The `file://` URI of an absolute path
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+// ---------- Analysis ----------
+
+/// A problem in a prompt file, for an editor to underline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Zero-based line in the prompt file
+    pub line: u32,
+    pub level: LintLevel,
+    /// Lint rule name, or `missing-import` / `output-conflict` / `parse`
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// The prompts of a project as an editor sees them: files on disk, with
+/// open documents taking the place of their saved content
+pub struct Project {
+    config: LitConfig,
+    /// Raw content per prompt path (relative to repo root)
+    raw: BTreeMap<PathBuf, String>,
+    prompts: Vec<Prompt>,
+    parse_errors: Vec<(PathBuf, String)>,
+}
+
+impl Project {
+    /// Load every prompt in the prompt directories, plus open prompt documents not
+    /// yet saved. `open` maps paths relative to `root` to buffer content.
+    pub fn load(config: &LitConfig, root: &Path, open: &HashMap<PathBuf, String>) -> Result<Self> {
+        let mut raw = BTreeMap::new();
+        for path in discover_project_prompts(config, root)? {
+            let relative = relative_to(&path, root);
+            if !open.contains_key(&relative)
+                && let Ok(content) = std::fs::read_to_string(&path)
+            {
+                raw.insert(relative, content);
+            }
+        }
+        for (path, content) in open {
+            if is_prompt_file(path) && config.project.in_prompt_dir(path) && !config.project.is_excluded(path) {
+                raw.insert(path.clone(), content.clone());
+            }
+        }
+
+        let mut prompts = Vec::new();
+        let mut parse_errors = Vec::new();
+        for (path, content) in &raw {
+            match Prompt::from_content(content, path.clone(), root, config) {
+                Ok(prompt) => prompts.push(prompt),
+                Err(e) => parse_errors.push((path.clone(), format!("{:#}", e))),
+            }
+        }
+        // An unmatched glob is reported per prompt by `diagnostics`
+        let _ = resolve_imports(&mut prompts);
+
+        Ok(Self {
+            config: config.clone(),
+            raw,
+            prompts,
+            parse_errors,
+        })
+    }
+
+    /// Diagnostics for every prompt, including an empty list for prompts
+    /// without problems
+    pub fn diagnostics(&self) -> BTreeMap<PathBuf, Vec<Diagnostic>> {
+        let mut diagnostics: BTreeMap<PathBuf, Vec<Diagnostic>> =
+            self.raw.keys().map(|path| (path.clone(), Vec::new())).collect();
+
+        for (path, message) in &self.parse_errors {
+            diagnostics.entry(path.clone()).or_default().push(Diagnostic {
+                line: 0,
+                level: LintLevel::Error,
+                rule: PARSE_RULE,
+                message: message.clone(),
+            });
+        }
+
+        for prompt in &self.prompts {
+            for import in &prompt.frontmatter.imports {
+                let found = if is_glob_import(import) {
+                    let pattern = to_slash(import);
+                    self.prompts.iter().any(|p| {
+                        p.path != prompt.path && glob_match(&pattern, &to_slash(&p.path))
+                    })
+                } else {
+                    self.prompts.iter().any(|p| &p.path == import)
+                };
+                if !found {
+                    diagnostics.entry(prompt.path.clone()).or_default().push(Diagnostic {
+                        line: self.line_of(&prompt.path, &import.to_string_lossy()),
+                        level: LintLevel::Error,
+                        rule: "missing-import",
+                        message: format!("imports {}, which is not a prompt in this project", import.display()),
+                    });
+                }
+            }
+        }
+
+        let mut owners: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+        for prompt in &self.prompts {
+            for output in &prompt.frontmatter.outputs {
+                owners.entry(output.as_path()).or_default().push(prompt.path.as_path());
+            }
+        }
+        for (output, claimants) in owners.iter().filter(|(_, claimants)| claimants.len() > 1) {
+            for claimant in claimants {
+                let others: Vec<String> = claimants
+                    .iter()
+                    .filter(|other| *other != claimant)
+                    .map(|other| other.display().to_string())
+                    .collect();
+                diagnostics.entry(claimant.to_path_buf()).or_default().push(Diagnostic {
+                    line: self.line_of(claimant, &output.to_string_lossy()),
+                    level: LintLevel::Error,
+                    rule: "output-conflict",
+                    message: format!("output {} is also claimed by {}", output.display(), others.join(", ")),
+                });
+            }
+        }
+
+        for finding in lint_prompts(&self.prompts, &self.config) {
+            diagnostics.entry(finding.prompt.clone()).or_default().push(Diagnostic {
+                line: 0,
+                level: finding.level,
+                rule: finding.rule,
+                message: finding.message,
+            });
+        }
+
+        diagnostics
+    }
+
+    /// Markdown describing a prompt: what it generates, what it imports and
+    /// what regenerating it touches downstream
+    pub fn hover(&self, path: &Path) -> Option<String> {
+        let prompt = self.prompts.iter().find(|p| p.path == path)?;
+        let list = |paths: &[PathBuf]| {
+            if paths.is_empty() {
+                "(none)".to_string()
+            } else {
+                paths.iter().map(|p| format!("`{}`", p.display())).collect::<Vec<_>>().join(", ")
+            }
+        };
+        let dependents: Vec<PathBuf> = self
+            .prompts
+            .iter()
+            .filter(|p| p.frontmatter.imports.iter().any(|i| i == path))
+            .map(|p| p.path.clone())
+            .collect();
+        let downstream = self.downstream(path);
+
+        let mut text = format!("**{}**\n\n", prompt.path.display());
+        text.push_str(&format!("- Outputs: {}\n", list(&prompt.frontmatter.outputs)));
+        text.push_str(&format!("- Imports: {}\n", list(&prompt.frontmatter.imports)));
+        text.push_str(&format!("- Imported by: {}\n", list(&dependents)));
+        if !prompt.includes.is_empty() {
+            let includes: Vec<PathBuf> = prompt.includes.keys().cloned().collect();
+            text.push_str(&format!("- Includes: {}\n", list(&includes)));
+        }
+        text.push_str(&format!(
+            "\nRegenerating it also regenerates {} downstream prompt(s).\n",
+            downstream.len()
+        ));
+        Some(text)
+    }
+
+    /// Whether `path` is a prompt that parsed
+    pub fn has_prompt(&self, path: &Path) -> bool {
+        self.prompts.iter().any(|p| p.path == path)
+    }
+
+    /// Every prompt that imports `path`, directly or transitively
+    fn downstream(&self, path: &Path) -> Vec<PathBuf> {
+        let mut found: Vec<PathBuf> = Vec::new();
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for prompt in &self.prompts {
+                if prompt.frontmatter.imports.contains(&current) && !found.contains(&prompt.path) {
+                    found.push(prompt.path.clone());
+                    pending.push(prompt.path.clone());
+                }
+            }
+        }
+        found.retain(|p| p != path);
+        found
+    }
+
+    /// First line of a prompt file mentioning `needle`, else 0
+    fn line_of(&self, path: &Path, needle: &str) -> u32 {
+        self.raw
+            .get(path)
+            .and_then(|raw| raw.lines().position(|line| line.contains(needle)))
+            .unwrap_or(0) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_framing() {
+        let message = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &message).unwrap();
+
+        let mut reader = std::io::Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message.clone()));
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/home/me/my project/prompts/a.prompt.md");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/me/my%20project/prompts/a.prompt.md");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    fn test_project_diagnostics_and_hover() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("prompts")).unwrap();
+        let write = |name: &str, imports: &str, output: &str| {
+            std::fs::write(
+                root.join("prompts").join(name),
+                format!(
+                    "---\noutputs:\n  - {}\nimports: [{}]\n---\n\n# Prompt\n\nBuild the thing described here in enough words.\n",
+                    output, imports
+                ),
+            )
+            .unwrap();
+        };
+        write("base.prompt.md", "", "base.py");
+        write("user.prompt.md", "prompts/base.prompt.md", "user.py");
+        write("api.prompt.md", "prompts/user.prompt.md", "user.py");
+
+        let config = LitConfig::from_str(
+            "[project]\nname = \"t\"\nversion = \"0.1.0\"\nmapping = \"manifest\"\n\n[language]\ndefault = \"python\"\nversion = \"3.12\"\n\n[model]\nprovider = \"anthropic\"\nmodel = \"claude-sonnet-4-5-20250929\"\ntemperature = 0.0\n\n[lint]\nmin_body_words = 0\n\n[lint.rules]\nunused-import = \"off\"\n",
+        )
+        .unwrap();
+
+        let project = Project::load(&config, root, &HashMap::new()).unwrap();
+        let diagnostics = project.diagnostics();
+        assert!(diagnostics[Path::new("prompts/base.prompt.md")].is_empty());
+        let api = &diagnostics[Path::new("prompts/api.prompt.md")];
+        assert_eq!(api.len(), 1);
+        assert_eq!(api[0].rule, "output-conflict");
+        assert_eq!(api[0].line, 2);
+
+        let hover = project.hover(Path::new("prompts/base.prompt.md")).unwrap();
+        assert!(hover.contains("`base.py`"));
+        assert!(hover.contains("Imported by: `prompts/user.prompt.md`"));
+        assert!(hover.contains("regenerates 2 downstream"));
+
+        // An open buffer replaces the file on disk
+        let open = HashMap::from([(
+            PathBuf::from("prompts/api.prompt.md"),
+            "---\noutputs:\n  - api.py\nimports:\n  - prompts/gone.prompt.md\n---\n\n# Api\n".to_string(),
+        )]);
+        let project = Project::load(&config, root, &open).unwrap();
+        let api = &project.diagnostics()[Path::new("prompts/api.prompt.md")];
+        assert_eq!(api.len(), 1);
+        assert_eq!((api[0].rule, api[0].line), ("missing-import", 4));
+    }
+}
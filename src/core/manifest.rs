@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::atomic;
+use crate::core::generator::HashComponents;
+use crate::core::patch::{PatchIndex, PatchStatus};
+use crate::core::prompt::Prompt;
+
+/// Output manifest, stored at `.lit/manifest.json`
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a single code.lock/ file was generated from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Prompt that generated the file
+    pub prompt: PathBuf,
+    /// Input hash of that prompt at generation time
+    pub input_hash: String,
+    /// SHA-256 of the file as written to code.lock/ (after patches)
+    pub content_hash: String,
+    pub generated_at: DateTime<Utc>,
+    /// Status of the file's manual patch, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<PatchStatus>,
+}
+
+/// Line churn above which a prompt is reported as high-variance, in percent
+pub const HIGH_VARIANCE_CHURN: f64 = 10.0;
+
+/// How much a prompt's output varied across a `lit sweep`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stability {
+    /// SHA-256 of the prompt file when it was measured; rewording the prompt
+    /// makes the score outdated
+    pub prompt_hash: String,
+    /// Generations compared
+    pub runs: usize,
+    /// Line churn between runs, in percent
+    pub churn: f64,
+    /// Churn per output file
+    #[serde(default)]
+    pub files: BTreeMap<PathBuf, f64>,
+    pub measured_at: DateTime<Utc>,
+}
+
+impl Stability {
+    pub fn is_high_variance(&self) -> bool {
+        self.churn > HIGH_VARIANCE_CHURN
+    }
+}
+
+/// Durable output ↔ prompt ↔ input-hash mapping, keyed by output path.
+///
+/// Written by `lit regenerate` and kept current by `lit commit`, so commands
+/// that run outside a pipeline (staleness in `lit status`, ownership, orphan
+/// detection) can tell where each code.lock/ file came from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub outputs: BTreeMap<PathBuf, ManifestEntry>,
+    /// Stability scores from `lit sweep`, keyed by prompt
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stability: BTreeMap<PathBuf, Stability>,
+    /// Hash components of each prompt's last recorded input hash, keyed by
+    /// prompt, so the next cache miss can say what changed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<PathBuf, HashComponents>,
+    /// Dependents `lit regenerate --only` left behind: stale whatever their
+    /// input hash says, until they are regenerated themselves
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub stale: BTreeSet<PathBuf>,
+}
+
+impl Manifest {
+    /// Load `.lit/manifest.json`, or `None` if no regeneration has recorded one yet.
+    pub fn load(lit_dir: &Path) -> Result<Option<Self>> {
+        let path = lit_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    pub fn save(&self, lit_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(lit_dir)
+            .with_context(|| format!("Failed to create {}", lit_dir.display()))?;
+        let path = lit_dir.join(MANIFEST_FILE);
+        let content = serde_json::to_string_pretty(self)?;
+        atomic::write(&path, content + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record a prompt's outputs as `(path, content, patch status)`, replacing
+    /// whatever it generated before, and clear its stale mark.
+    pub fn record<'a>(
+        &mut self,
+        prompt: &Path,
+        input_hash: &str,
+        generated_at: DateTime<Utc>,
+        files: impl IntoIterator<Item = (&'a PathBuf, &'a str, Option<PatchStatus>)>,
+    ) {
+        self.stale.remove(prompt);
+        self.outputs.retain(|_, entry| entry.prompt != prompt);
+        for (file, content, patch) in files {
+            self.outputs.insert(
+                file.clone(),
+                ManifestEntry {
+                    prompt: prompt.to_path_buf(),
+                    input_hash: input_hash.to_string(),
+                    content_hash: content_hash(content),
+                    generated_at,
+                    patch,
+                },
+            );
+        }
+    }
+
+    /// Record the hash components of a prompt's input hash
+    pub fn record_inputs(&mut self, prompt: &Path, components: HashComponents) {
+        self.inputs.insert(prompt.to_path_buf(), components);
+    }
+
+    /// Mark prompts stale until they are next recorded
+    pub fn mark_stale<'a>(&mut self, prompts: impl IntoIterator<Item = &'a PathBuf>) {
+        self.stale.extend(prompts.into_iter().cloned());
+    }
+
+    /// Drop entries for prompts that no longer exist.
+    pub fn retain_prompts(&mut self, prompts: &HashMap<PathBuf, Prompt>) {
+        self.stale.retain(|prompt| prompts.contains_key(prompt));
+        self.outputs.retain(|_, entry| prompts.contains_key(&entry.prompt));
+        self.stability.retain(|prompt, _| prompts.contains_key(prompt));
+        self.inputs.retain(|prompt, _| prompts.contains_key(prompt));
+    }
+
+    /// Refresh each entry's patch status from the patch index.
+    pub fn sync_patches(&mut self, index: &PatchIndex) {
+        for (path, entry) in &mut self.outputs {
+            entry.patch = index.patches.get(path).map(|p| p.status);
+        }
+    }
+
+    /// The entry for an output file, if lit generated it.
+    #[allow(dead_code)]
+    pub fn owner(&self, output: &Path) -> Option<&ManifestEntry> {
+        self.outputs.get(output)
+    }
+
+    /// Files in `code_lock_files` that no manifest entry accounts for.
+    #[allow(dead_code)]
+    pub fn orphans<'a>(&self, code_lock_files: impl IntoIterator<Item = &'a PathBuf>) -> Vec<PathBuf> {
+        let mut orphans: Vec<PathBuf> = code_lock_files
+            .into_iter()
+            .filter(|f| !self.outputs.contains_key(*f))
+            .cloned()
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    /// A prompt's stability score, unless the prompt (with content `raw`)
+    /// has changed since it was measured.
+    pub fn current_stability(&self, prompt: &Path, raw: &str) -> Option<&Stability> {
+        self.stability.get(prompt).filter(|s| s.prompt_hash == content_hash(raw))
+    }
+
+    /// Whether code.lock is behind for a prompt: it is marked stale, or one
+    /// of its outputs was never recorded or was generated from a different
+    /// input hash.
+    pub fn is_stale(&self, prompt: &Path, outputs: &[PathBuf], current_hash: &str) -> bool {
+        self.stale.contains(prompt)
+            || outputs.iter().any(|output| match self.outputs.get(output) {
+                Some(entry) => entry.prompt != prompt || entry.input_hash != current_hash,
+                None => true,
+            })
+    }
+}
+
+/// SHA-256 of a file's content, as recorded in the manifest
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::patch::PatchIndexEntry;
+
+    #[test]
+    fn test_manifest_staleness() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Manifest::load(dir.path()).unwrap().is_none());
+
+        let user = PathBuf::from("prompts/user.prompt.md");
+        let files = [PathBuf::from("src/user.py"), PathBuf::from("tests/test_user.py")];
+        let mut manifest = Manifest::default();
+        manifest.record(&user, "aaa", Utc::now(), files.iter().map(|f| (f, "x = 1\n", None)));
+        manifest.save(dir.path()).unwrap();
+
+        let manifest = Manifest::load(dir.path()).unwrap().unwrap();
+        assert!(!manifest.is_stale(&user, &files, "aaa"));
+        assert!(manifest.is_stale(&user, &files, "bbb"));
+        assert_eq!(manifest.owner(&files[0]).unwrap().content_hash, content_hash("x = 1\n"));
+
+        // A newly declared output has no hash of record
+        let mut more = files.to_vec();
+        more.push(PathBuf::from("src/user_schema.py"));
+        assert!(manifest.is_stale(&user, &more, "aaa"));
+        assert_eq!(manifest.orphans(&more), vec![PathBuf::from("src/user_schema.py")]);
+
+        // Re-recording replaces the prompt's previous outputs
+        let mut manifest = manifest;
+        manifest.record(&user, "bbb", Utc::now(), [(&files[0], "", None)]);
+        assert_eq!(manifest.outputs.len(), 1);
+        assert!(!manifest.is_stale(&user, &files[..1], "bbb"));
+
+        // A stale mark holds whatever the hash says, until the next record
+        manifest.mark_stale([&user]);
+        assert!(manifest.is_stale(&user, &files[..1], "bbb"));
+        manifest.record(&user, "bbb", Utc::now(), [(&files[0], "", None)]);
+        assert!(!manifest.is_stale(&user, &files[..1], "bbb"));
+    }
+
+    #[test]
+    fn test_manifest_stability() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = PathBuf::from("prompts/user.prompt.md");
+        let mut manifest = Manifest::default();
+        manifest.stability.insert(
+            user.clone(),
+            Stability {
+                prompt_hash: content_hash("Define a User.\n"),
+                runs: 3,
+                churn: 12.5,
+                files: BTreeMap::from([(PathBuf::from("src/user.py"), 12.5)]),
+                measured_at: Utc::now(),
+            },
+        );
+        manifest.save(dir.path()).unwrap();
+
+        let mut manifest = Manifest::load(dir.path()).unwrap().unwrap();
+        let stability = manifest.current_stability(&user, "Define a User.\n").unwrap();
+        assert!(stability.is_high_variance());
+        // Rewording the prompt makes the score outdated
+        assert!(manifest.current_stability(&user, "Define a User model.\n").is_none());
+
+        manifest.retain_prompts(&HashMap::new());
+        assert!(manifest.stability.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = PathBuf::from("prompts/user.prompt.md");
+        let components = HashComponents {
+            prompt: content_hash("Define a User.\n"),
+            imports: BTreeMap::from([(PathBuf::from("prompts/base.prompt.md"), "abc123".to_string())]),
+            model: "claude-sonnet-4-5-20250929, temperature 0, seed none".to_string(),
+            language: "python".to_string(),
+            system: BTreeMap::new(),
+        };
+        let mut manifest = Manifest::default();
+        manifest.record_inputs(&user, components.clone());
+        manifest.save(dir.path()).unwrap();
+
+        let mut manifest = Manifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.inputs[&user], components);
+        manifest.retain_prompts(&HashMap::new());
+        assert!(manifest.inputs.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_sync_patches() {
+        let user = PathBuf::from("prompts/user.prompt.md");
+        let file = PathBuf::from("src/user.py");
+        let mut manifest = Manifest::default();
+        manifest.record(&user, "aaa", Utc::now(), [(&file, "", Some(PatchStatus::Conflicted))]);
+
+        let mut index = PatchIndex::default();
+        manifest.sync_patches(&index);
+        assert_eq!(manifest.outputs[&file].patch, None);
+
+        index.patches.insert(
+            file.clone(),
+            PatchIndexEntry {
+                hash: String::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                status: PatchStatus::Active,
+            },
+        );
+        manifest.sync_patches(&index);
+        assert_eq!(manifest.outputs[&file].patch, Some(PatchStatus::Active));
+    }
+}
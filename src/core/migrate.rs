@@ -0,0 +1,623 @@
+use anyhow::{Context, Result, bail};
+use serde_yaml::{Mapping, Value};
+
+use crate::core::config::glob_match;
+
+/// A single frontmatter change: `key=value` sets, `Unset` removes.
+///
+/// Keys are dotted paths into the frontmatter (`model.provider`); values are
+/// parsed as YAML, so `0.2` is a number and `[a, b]` a list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontmatterEdit {
+    Set(Vec<String>, Value),
+    Unset(Vec<String>),
+}
+
+impl FrontmatterEdit {
+    /// Parse a `--set key=value` argument
+    pub fn parse_set(arg: &str) -> Result<Self> {
+        let Some((key, value)) = arg.split_once('=') else {
+            bail!("Invalid --set '{}': expected key=value", arg);
+        };
+        let value: Value = serde_yaml::from_str(value.trim())
+            .with_context(|| format!("Invalid value in --set '{}'", arg))?;
+        Ok(FrontmatterEdit::Set(parse_key(key)?, value))
+    }
+
+    /// Parse an `--unset key` argument
+    pub fn parse_unset(arg: &str) -> Result<Self> {
+        Ok(FrontmatterEdit::Unset(parse_key(arg)?))
+    }
+}
+
+/// A `--where` condition on a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// `key contains value` — list membership, or substring for strings
+    Contains(Vec<String>, String),
+    /// `key = value`
+    Equals(Vec<String>, String),
+    /// `key != value` (also true when the key is missing)
+    NotEquals(Vec<String>, String),
+    /// `key exists`
+    Exists(Vec<String>),
+    /// `path matches <glob>` — the prompt file path
+    PathMatches(String),
+}
+
+impl Filter {
+    /// Parse a `--where` expression
+    pub fn parse(expr: &str) -> Result<Self> {
+        let expr = expr.trim();
+        if let Some(key) = expr.strip_suffix(" exists") {
+            return Ok(Filter::Exists(parse_key(key)?));
+        }
+        if let Some((key, value)) = expr.split_once(" contains ") {
+            return Ok(Filter::Contains(parse_key(key)?, unquote(value)));
+        }
+        if let Some(("path", glob)) = expr.split_once(" matches ") {
+            return Ok(Filter::PathMatches(unquote(glob)));
+        }
+        if let Some((key, value)) = expr.split_once("!=") {
+            return Ok(Filter::NotEquals(parse_key(key)?, unquote(value)));
+        }
+        if let Some((key, value)) = expr.split_once('=') {
+            return Ok(Filter::Equals(parse_key(key)?, unquote(value)));
+        }
+        bail!(
+            "Invalid --where '{}'. Expected one of: `key contains value`, `key = value`, \
+             `key != value`, `key exists`, `path matches <glob>`",
+            expr
+        );
+    }
+
+    /// Whether a prompt (path and parsed frontmatter) satisfies the condition
+    pub fn matches(&self, prompt_path: &str, frontmatter: &Value) -> bool {
+        match self {
+            Filter::Contains(key, needle) => match lookup(frontmatter, key) {
+                // Aliased imports are `{ path, as }` mappings
+                Some(Value::Sequence(items)) => items.iter().any(|v| {
+                    scalar_eq(v, needle) || v.get("path").is_some_and(|p| scalar_eq(p, needle))
+                }),
+                Some(Value::String(s)) => s.contains(needle.as_str()),
+                _ => false,
+            },
+            Filter::Equals(key, value) => lookup(frontmatter, key).is_some_and(|v| scalar_eq(v, value)),
+            Filter::NotEquals(key, value) => !lookup(frontmatter, key).is_some_and(|v| scalar_eq(v, value)),
+            Filter::Exists(key) => lookup(frontmatter, key).is_some(),
+            Filter::PathMatches(glob) => glob_match(glob, prompt_path),
+        }
+    }
+}
+
+/// Split a prompt file into `(frontmatter YAML, rest)`, where `rest` starts
+/// at the closing `---` line and is kept byte-for-byte.
+pub fn split_raw(raw: &str) -> Result<(&str, &str, &str)> {
+    let start = raw.len() - raw.trim_start().len();
+    let after_open = raw[start..]
+        .strip_prefix("---")
+        .and_then(|r| r.find('\n').map(|i| start + 3 + i + 1))
+        .context("Prompt file must start with YAML frontmatter (---)")?;
+
+    let mut offset = after_open;
+    for line in raw[after_open..].split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Ok((&raw[..after_open], &raw[after_open..offset], &raw[offset..]));
+        }
+        offset += line.len();
+    }
+    bail!("Unterminated frontmatter: missing closing ---")
+}
+
+/// Parse a prompt file's frontmatter as a YAML mapping.
+pub fn frontmatter_value(raw: &str) -> Result<Value> {
+    let (_, yaml, _) = split_raw(raw)?;
+    let value: Value = serde_yaml::from_str(yaml).context("Failed to parse frontmatter YAML")?;
+    Ok(match value {
+        Value::Null => Value::Mapping(Mapping::new()),
+        v => v,
+    })
+}
+
+/// Apply edits to a prompt file's frontmatter.
+///
+/// Returns the new file contents, or None if nothing changed. The body and
+/// untouched top-level keys are kept byte-for-byte; changed keys are
+/// re-serialized (losing any comments inside them).
+pub fn apply_edits(raw: &str, edits: &[FrontmatterEdit]) -> Result<Option<String>> {
+    let original = frontmatter_value(raw)?;
+    let mut value = original.clone();
+
+    for edit in edits {
+        match edit {
+            FrontmatterEdit::Set(key, new) => set(&mut value, key, new.clone())?,
+            FrontmatterEdit::Unset(key) => unset(&mut value, key),
+        }
+    }
+
+    if value == original {
+        return Ok(None);
+    }
+    write_frontmatter(raw, &original, &value).map(Some)
+}
+
+/// Replace a prompt file's frontmatter with `value`, keeping the body and the
+/// text of top-level keys whose value is unchanged from `original`
+fn write_frontmatter(raw: &str, original: &Value, value: &Value) -> Result<String> {
+    let (head, yaml, rest) = split_raw(raw)?;
+    let segments = top_level_segments(yaml);
+    let mut out: String = segments
+        .iter()
+        .filter(|(key, _)| key.is_none())
+        .map(|(_, text)| text.as_str())
+        .collect();
+    let empty = Mapping::new();
+    let new_map = value.as_mapping().unwrap_or(&empty);
+    for (key, new) in new_map {
+        let name = key.as_str().unwrap_or_default();
+        let unchanged = segments
+            .iter()
+            .find(|(k, _)| k.as_deref() == Some(name))
+            .filter(|_| original.get(name) == Some(new));
+        match unchanged {
+            Some((_, text)) => out.push_str(text),
+            None => {
+                let single = Mapping::from_iter([(key.clone(), new.clone())]);
+                out.push_str(
+                    &serde_yaml::to_string(&single).context("Failed to serialize frontmatter")?,
+                );
+            }
+        }
+    }
+    Ok(format!("{}{}{}", head, out, rest))
+}
+
+/// Split frontmatter YAML into top-level `(key, text)` segments. Text before
+/// the first key (comments) has no key.
+fn top_level_segments(yaml: &str) -> Vec<(Option<String>, String)> {
+    let mut segments: Vec<(Option<String>, String)> = Vec::new();
+    for line in yaml.split_inclusive('\n') {
+        let starts_key = !line.starts_with([' ', '\t', '#', '-', '\n']) && line.contains(':');
+        if starts_key {
+            let key = line.split(':').next().unwrap_or_default().trim().trim_matches(['"', '\'']);
+            segments.push((Some(key.to_string()), String::new()));
+        } else if segments.is_empty() {
+            segments.push((None, String::new()));
+        }
+        if let Some((_, text)) = segments.last_mut() {
+            text.push_str(line);
+        }
+    }
+    segments
+}
+
+/// Rewrite references to a moved path: entries under the given top-level
+/// frontmatter key (e.g. `imports`) and, for imports, `@import()` references
+/// in the body.
+///
+/// Returns the new file contents, or None if nothing referenced `from`.
+/// Everything else is kept byte-for-byte.
+pub fn rewrite_path(raw: &str, key: &str, from: &str, to: &str) -> Result<Option<String>> {
+    let (head, yaml, rest) = split_raw(raw)?;
+    let frontmatter: String = top_level_segments(yaml)
+        .into_iter()
+        .map(|(k, text)| match k.as_deref() {
+            Some(k) if k == key => replace_path(&text, from, to),
+            _ => text,
+        })
+        .collect();
+
+    let body = if key == "imports" {
+        rewrite_body_imports(rest, from, to)
+    } else {
+        rest.to_string()
+    };
+
+    if frontmatter == yaml && body == rest {
+        return Ok(None);
+    }
+    Ok(Some(format!("{}{}{}", head, frontmatter, body)))
+}
+
+/// Replace `from` with `to` wherever it appears as a whole path
+fn replace_path(text: &str, from: &str, to: &str) -> String {
+    let is_path_char = |c: char| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-');
+    let mut out = String::with_capacity(text.len());
+    let mut remaining = text;
+    while let Some(pos) = remaining.find(from) {
+        let before = remaining[..pos].chars().next_back();
+        let after = remaining[pos + from.len()..].chars().next();
+        out.push_str(&remaining[..pos]);
+        if before.is_some_and(is_path_char) || after.is_some_and(is_path_char) {
+            out.push_str(from);
+        } else {
+            out.push_str(to);
+        }
+        remaining = &remaining[pos + from.len()..];
+    }
+    out.push_str(remaining);
+    out
+}
+
+/// Point `@import(from)` references at `to`
+fn rewrite_body_imports(body: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut remaining = body;
+    while let Some(start) = remaining.find("@import(") {
+        let after = &remaining[start + 8..];
+        let Some(end) = after.find(')') else { break };
+        out.push_str(&remaining[..start + 8]);
+        if after[..end].trim() == from {
+            out.push_str(to);
+        } else {
+            out.push_str(&after[..end]);
+        }
+        remaining = &after[end..];
+    }
+    out.push_str(remaining);
+    out
+}
+
+// ---------- Schema migrations ----------
+
+/// Schema of files without a `schema` field
+pub const FIRST_SCHEMA: u32 = 1;
+
+/// Schema this version of lit reads. Bump it, with a new entry in
+/// [`SCHEMA_MIGRATIONS`], whenever the prompt or lit.toml format changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A format change that upgrades prompt files and lit.toml from schema
+/// `to - 1` to `to`
+pub struct SchemaMigration {
+    pub to: u32,
+    pub description: &'static str,
+    /// Rewrite a prompt's frontmatter
+    pub prompt: fn(&mut Mapping) -> Result<()>,
+    /// Rewrite lit.toml
+    pub config: fn(&mut toml::Table) -> Result<()>,
+}
+
+/// Every schema migration, oldest first
+pub const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[];
+
+/// Reject a file (`lit.toml` or a prompt path) whose schema this lit can't
+/// read: newer than it knows, or older and in need of `lit migrate`
+pub fn check_schema(schema: u32, file: &str) -> Result<()> {
+    if schema < FIRST_SCHEMA {
+        bail!("Invalid schema {} in {}. Schemas start at {}", schema, file, FIRST_SCHEMA);
+    }
+    if schema > SCHEMA_VERSION {
+        bail!(
+            "{} uses schema {}, but this version of lit only reads schema {} and older.\n\
+             Hint: Upgrade lit.",
+            file,
+            schema,
+            SCHEMA_VERSION
+        );
+    }
+    if schema < SCHEMA_VERSION {
+        bail!(
+            "{} uses schema {}, but the current schema is {}.\n\
+             Hint: Run `lit migrate` to upgrade it.",
+            file,
+            schema,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// The migrations a file on `schema` still needs, failing if the file is
+/// newer than the last migration
+pub fn pending_migrations(schema: u32, migrations: &[SchemaMigration]) -> Result<&[SchemaMigration]> {
+    let latest = migrations.last().map_or(SCHEMA_VERSION, |m| m.to);
+    if schema > latest {
+        bail!(
+            "Schema {} is newer than this version of lit supports ({}).\nHint: Upgrade lit.",
+            schema,
+            latest
+        );
+    }
+    let first = migrations.iter().position(|m| m.to > schema).unwrap_or(migrations.len());
+    Ok(&migrations[first..])
+}
+
+/// A prompt file's schema (`schema:` in the frontmatter)
+pub fn prompt_schema(raw: &str) -> Result<u32> {
+    let value = frontmatter_value(raw)?;
+    schema_field(value.get("schema").and_then(Value::as_u64), "schema")
+}
+
+/// lit.toml's schema (`[project] schema`)
+pub fn config_schema(raw: &str) -> Result<u32> {
+    let table: toml::Table = toml::from_str(raw).context("Failed to parse lit.toml")?;
+    let schema = table.get("project").and_then(|p| p.get("schema"));
+    schema_field(
+        schema.and_then(toml::Value::as_integer).and_then(|s| u64::try_from(s).ok()),
+        "[project] schema",
+    )
+}
+
+fn schema_field(value: Option<u64>, name: &str) -> Result<u32> {
+    match value {
+        None => Ok(FIRST_SCHEMA),
+        Some(v) => u32::try_from(v).with_context(|| format!("Invalid {} {}", name, v)),
+    }
+}
+
+/// Upgrade a prompt file through `migrations`, stamping the new schema.
+///
+/// Returns None if the prompt is already current. Like [`apply_edits`],
+/// untouched top-level keys and the body are kept byte-for-byte.
+pub fn migrate_prompt(raw: &str, migrations: &[SchemaMigration]) -> Result<Option<String>> {
+    let pending = pending_migrations(prompt_schema(raw)?, migrations)?;
+    let Some(last) = pending.last() else {
+        return Ok(None);
+    };
+    let original = frontmatter_value(raw)?;
+    let mut value = original.clone();
+    let map = value.as_mapping_mut().context("Frontmatter is not a mapping")?;
+    for migration in pending {
+        (migration.prompt)(map).with_context(|| format!("Migration to schema {} failed", migration.to))?;
+    }
+    map.insert(Value::from("schema"), Value::from(last.to));
+    write_frontmatter(raw, &original, &value).map(Some)
+}
+
+/// Upgrade lit.toml through `migrations`, stamping the new schema.
+///
+/// Returns None if the config is already current. The file is re-serialized,
+/// so comments are lost when a migration applies.
+pub fn migrate_config(raw: &str, migrations: &[SchemaMigration]) -> Result<Option<String>> {
+    let pending = pending_migrations(config_schema(raw)?, migrations)?;
+    let Some(last) = pending.last() else {
+        return Ok(None);
+    };
+    let mut table: toml::Table = toml::from_str(raw).context("Failed to parse lit.toml")?;
+    for migration in pending {
+        (migration.config)(&mut table)
+            .with_context(|| format!("Migration to schema {} failed", migration.to))?;
+    }
+    let project = table
+        .entry("project")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .context("[project] in lit.toml is not a table")?;
+    project.insert("schema".to_string(), toml::Value::Integer(i64::from(last.to)));
+    toml::to_string_pretty(&table).context("Failed to serialize lit.toml").map(Some)
+}
+
+fn parse_key(key: &str) -> Result<Vec<String>> {
+    let parts: Vec<String> = key.trim().split('.').map(|p| p.trim().to_string()).collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        bail!("Invalid frontmatter key '{}'", key.trim());
+    }
+    Ok(parts)
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn lookup<'a>(value: &'a Value, key: &[String]) -> Option<&'a Value> {
+    key.iter().try_fold(value, |v, part| v.get(part.as_str()))
+}
+
+/// Compare a YAML scalar with a string from the command line
+fn scalar_eq(value: &Value, expected: &str) -> bool {
+    match value {
+        Value::String(s) => s == expected,
+        Value::Number(n) => n.to_string() == expected,
+        Value::Bool(b) => b.to_string() == expected,
+        _ => false,
+    }
+}
+
+fn set(value: &mut Value, key: &[String], new: Value) -> Result<()> {
+    let (last, parents) = key.split_last().expect("keys are never empty");
+    let mut current = value;
+    for part in parents {
+        let map = current
+            .as_mapping_mut()
+            .with_context(|| format!("Cannot set '{}': '{}' is not a mapping", key.join("."), part))?;
+        current = map
+            .entry(Value::String(part.clone()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+    let map = current
+        .as_mapping_mut()
+        .with_context(|| format!("Cannot set '{}': parent is not a mapping", key.join(".")))?;
+    map.insert(Value::String(last.clone()), new);
+    Ok(())
+}
+
+fn unset(value: &mut Value, key: &[String]) {
+    let (last, parents) = key.split_last().expect("keys are never empty");
+    let mut current = value;
+    for part in parents {
+        match current.get_mut(part.as_str()) {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+    if let Some(map) = current.as_mapping_mut() {
+        map.remove(last.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMPT: &str = "---\noutputs:\n  - src/api.py\nimports:\n  - prompts/models/base.prompt.md\n---\n\n# API\n\nBody stays as-is.\n";
+
+    #[test]
+    fn test_filters() {
+        let fm = frontmatter_value(PROMPT).unwrap();
+        let path = "prompts/api/users.prompt.md";
+        let check = |expr: &str| Filter::parse(expr).unwrap().matches(path, &fm);
+
+        assert!(check("imports contains prompts/models/base.prompt.md"));
+        assert!(!check("imports contains prompts/other.prompt.md"));
+        assert!(check("model.provider != openai"));
+        assert!(!check("model exists"));
+        assert!(check("outputs exists"));
+        assert!(check("path matches 'prompts/api/**'"));
+        assert!(!check("path matches prompts/models/*"));
+        assert!(Filter::parse("imports").is_err());
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let edits = vec![
+            FrontmatterEdit::parse_set("model.provider=anthropic").unwrap(),
+            FrontmatterEdit::parse_set("model.model=claude-sonnet-4-5-20250929").unwrap(),
+            FrontmatterEdit::parse_set("model.temperature=0.2").unwrap(),
+        ];
+        let updated = apply_edits(PROMPT, &edits).unwrap().unwrap();
+
+        // Untouched keys and the body keep their formatting
+        assert!(updated.starts_with("---\noutputs:\n  - src/api.py\nimports:\n  - prompts/models/base.prompt.md\nmodel:\n"));
+        assert!(updated.ends_with("---\n\n# API\n\nBody stays as-is.\n"));
+        let fm = frontmatter_value(&updated).unwrap();
+        assert_eq!(fm["model"]["provider"], Value::from("anthropic"));
+        assert_eq!(fm["model"]["temperature"], Value::from(0.2));
+        assert_eq!(fm["imports"][0], Value::from("prompts/models/base.prompt.md"));
+
+        // Re-applying is a no-op; unset removes the key
+        assert!(apply_edits(&updated, &edits).unwrap().is_none());
+        let removed = apply_edits(&updated, &[FrontmatterEdit::parse_unset("model").unwrap()])
+            .unwrap()
+            .unwrap();
+        assert!(!frontmatter_value(&removed).unwrap().as_mapping().unwrap().contains_key("model"));
+    }
+
+    #[test]
+    fn test_rewrite_path() {
+        let raw = "---\noutputs:\n  - src/api.py\nimports:\n  - prompts/models/user.prompt.md\n  - prompts/models/user.prompt.md.bak.prompt.md\n---\n\nUse @import(prompts/models/user.prompt.md) and @import( prompts/models/user.prompt.md ).\n";
+        let updated = rewrite_path(raw, "imports", "prompts/models/user.prompt.md", "prompts/models/account.prompt.md")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            updated,
+            "---\noutputs:\n  - src/api.py\nimports:\n  - prompts/models/account.prompt.md\n  - prompts/models/user.prompt.md.bak.prompt.md\n---\n\nUse @import(prompts/models/account.prompt.md) and @import(prompts/models/account.prompt.md).\n"
+        );
+
+        // Outputs are only rewritten under `outputs:`
+        let moved = rewrite_path(raw, "outputs", "src/api.py", "src/routes.py").unwrap().unwrap();
+        assert!(moved.contains("outputs:\n  - src/routes.py\n"));
+        assert!(moved.ends_with("@import( prompts/models/user.prompt.md ).\n"));
+
+        assert!(rewrite_path(raw, "imports", "prompts/other.prompt.md", "prompts/x.prompt.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_rejects_non_mapping_parent() {
+        let edit = FrontmatterEdit::parse_set("outputs.first=x").unwrap();
+        assert!(apply_edits(PROMPT, &[edit]).is_err());
+        assert!(FrontmatterEdit::parse_set("no-equals-sign").is_err());
+    }
+
+    #[test]
+    fn test_schema_migrations_are_sequential() {
+        let mut expected = FIRST_SCHEMA;
+        for migration in SCHEMA_MIGRATIONS {
+            expected += 1;
+            assert_eq!(migration.to, expected, "{}", migration.description);
+        }
+        assert_eq!(expected, SCHEMA_VERSION);
+    }
+
+    fn rename_model_name(map: &mut Mapping) -> Result<()> {
+        if let Some(Value::Mapping(model)) = map.get_mut("model")
+            && let Some(name) = model.remove("name")
+        {
+            model.insert(Value::from("model"), name);
+        }
+        Ok(())
+    }
+
+    fn rename_project_mode(table: &mut toml::Table) -> Result<()> {
+        if let Some(toml::Value::Table(project)) = table.get_mut("project")
+            && let Some(mode) = project.remove("mode")
+        {
+            project.insert("mapping".to_string(), mode);
+        }
+        Ok(())
+    }
+
+    fn noop_prompt(_: &mut Mapping) -> Result<()> {
+        Ok(())
+    }
+
+    fn noop_config(_: &mut toml::Table) -> Result<()> {
+        Ok(())
+    }
+
+    const TEST_MIGRATIONS: &[SchemaMigration] = &[
+        SchemaMigration {
+            to: 2,
+            description: "rename model.name and project.mode",
+            prompt: rename_model_name,
+            config: rename_project_mode,
+        },
+        SchemaMigration {
+            to: 3,
+            description: "no-op",
+            prompt: noop_prompt,
+            config: noop_config,
+        },
+    ];
+
+    #[test]
+    fn test_migrate_prompt() {
+        let raw = "---\noutputs:\n  - src/api.py   # the API\nmodel:\n  provider: anthropic\n  name: claude-x\n  temperature: 0.0\n---\n\nBody.\n";
+        let migrated = migrate_prompt(raw, TEST_MIGRATIONS).unwrap().unwrap();
+        assert!(migrated.contains("  - src/api.py   # the API\n"), "got: {}", migrated);
+        assert!(migrated.contains("  model: claude-x\n"), "got: {}", migrated);
+        assert!(migrated.contains("schema: 3\n"), "got: {}", migrated);
+        assert!(migrated.ends_with("---\n\nBody.\n"));
+        assert_eq!(prompt_schema(&migrated).unwrap(), 3);
+
+        // Current files are left alone; newer ones are refused
+        assert!(migrate_prompt(&migrated, TEST_MIGRATIONS).unwrap().is_none());
+        let newer = migrated.replace("schema: 3", "schema: 4");
+        assert!(migrate_prompt(&newer, TEST_MIGRATIONS).is_err());
+
+        // Only later migrations run on a partly migrated file
+        let partial = raw.replace("---\n\n", "schema: 2\n---\n\n");
+        let migrated = migrate_prompt(&partial, TEST_MIGRATIONS).unwrap().unwrap();
+        assert!(migrated.contains("  name: claude-x\n"), "got: {}", migrated);
+        assert!(migrated.contains("schema: 3\n"));
+    }
+
+    #[test]
+    fn test_migrate_config() {
+        let raw = "[project]\nname = \"demo\"\nversion = \"0.1.0\"\nmode = \"manifest\"\n";
+        assert_eq!(config_schema(raw).unwrap(), FIRST_SCHEMA);
+
+        let migrated = migrate_config(raw, TEST_MIGRATIONS).unwrap().unwrap();
+        assert!(migrated.contains("mapping = \"manifest\""), "got: {}", migrated);
+        assert!(!migrated.contains("mode ="));
+        assert_eq!(config_schema(&migrated).unwrap(), 3);
+        assert!(migrate_config(&migrated, TEST_MIGRATIONS).unwrap().is_none());
+
+        // With no migrations, everything is current
+        assert!(migrate_config(raw, SCHEMA_MIGRATIONS).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_check_schema() {
+        check_schema(SCHEMA_VERSION, "lit.toml").unwrap();
+        let err = check_schema(SCHEMA_VERSION + 1, "lit.toml").unwrap_err().to_string();
+        assert!(err.contains("Upgrade lit"), "got: {}", err);
+        assert!(check_schema(0, "lit.toml").is_err());
+    }
+}
@@ -1,10 +1,43 @@
+pub mod assertions;
+pub mod atomic;
 pub mod config;
+pub mod error;
 pub mod prompt;
 pub mod dag;
 pub mod generator;
 pub mod cache;
+pub mod context;
 pub mod patch;
 pub mod repo;
 pub mod generation_record;
+pub mod inventory;
+pub mod migrate;
+pub mod tokens;
+pub mod manifest;
+pub mod hooks;
+pub mod keychain;
+pub mod format;
+pub mod session;
+pub mod last_run;
+pub mod lint;
+pub mod workspace;
+pub mod freeze;
+pub mod stash;
+pub mod determinism;
+pub mod history;
+pub mod transcript;
+pub mod lsp;
+pub mod http;
+pub mod interrupt;
+pub mod progress;
+pub mod run_log;
+pub mod secrets;
+pub mod header;
+pub mod credentials;
+pub mod describe;
+pub mod forge;
+pub mod lock;
+pub mod paths;
+pub mod project;
 #[allow(dead_code)]
 pub mod style;
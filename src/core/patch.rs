@@ -1,10 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use similar::{ChangeTag, TextDiff};
 
+use crate::core::atomic;
+use crate::core::paths::{normalize, to_slash};
+
+/// Index of all patches, stored at `.lit/patches/index.json`
+const INDEX_FILE: &str = "index.json";
+
+/// Lock file guarding read-modify-write cycles on the index
+const INDEX_LOCK_FILE: &str = "index.lock";
+
 /// Info about a detected manual patch
 #[derive(Debug, Clone)]
 pub struct PatchInfo {
@@ -30,6 +43,47 @@ pub struct StoredPatch {
     pub diff: String,
 }
 
+/// Status of a tracked patch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchStatus {
+    /// Applies cleanly on regeneration
+    #[default]
+    Active,
+    /// Last regeneration produced conflict markers
+    Conflicted,
+}
+
+/// Index entry for a single patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchIndexEntry {
+    /// SHA-256 of the stored patch file (detects edits made outside lit)
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub status: PatchStatus,
+}
+
+/// Index of all tracked patches, keyed by output path.
+///
+/// Lets `lit patch list` avoid walking `.lit/patches/` and gives patches a
+/// place for metadata.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PatchIndex {
+    #[serde(default)]
+    pub patches: BTreeMap<PathBuf, PatchIndexEntry>,
+}
+
+/// Why an index entry no longer matches its patch file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StalePatch {
+    /// Indexed, but the patch file is gone
+    Missing(PathBuf),
+    /// The patch file was changed without going through lit
+    Modified(PathBuf),
+}
+
 /// Result of applying a patch to new generated content
 #[derive(Debug)]
 pub enum PatchResult {
@@ -39,9 +93,110 @@ pub enum PatchResult {
     Conflict(String),
 }
 
+/// Result of merging a patch hunk by hunk
+#[derive(Debug, Clone)]
+pub struct PatchMerge {
+    /// Merged content, with conflict markers around conflicting hunks
+    pub content: String,
+    /// Each hunk of the patch, in file order
+    pub hunks: Vec<HunkReport>,
+    /// The merged file as merged text and conflicting regions, in order
+    pub segments: Vec<MergeSegment>,
+}
+
+impl PatchMerge {
+    pub fn conflicted(&self) -> bool {
+        self.hunks.iter().any(|h| h.outcome == HunkOutcome::Conflict)
+    }
+
+    /// The conflicting regions, in file order
+    pub fn conflicts(&self) -> impl Iterator<Item = &MergeConflict> {
+        self.segments.iter().filter_map(|segment| match segment {
+            MergeSegment::Conflict(conflict) => Some(conflict),
+            MergeSegment::Clean(_) => None,
+        })
+    }
+
+    /// Rebuild the file with each conflict replaced by `resolve`'s text
+    pub fn resolve(&self, mut resolve: impl FnMut(&MergeConflict) -> Result<String>) -> Result<String> {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                MergeSegment::Clean(text) => out.push_str(text),
+                MergeSegment::Conflict(conflict) => out.push_str(&resolve(conflict)?),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Part of a merged file
+#[derive(Debug, Clone)]
+pub enum MergeSegment {
+    Clean(String),
+    Conflict(MergeConflict),
+}
+
+/// A region both the manual patch and the new generation changed differently
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    /// Lines of the originally generated file the region covers (0-based, end exclusive)
+    pub start: usize,
+    pub end: usize,
+    /// The region as patched by hand
+    pub manual: String,
+    /// The region as newly generated
+    pub generated: String,
+}
+
+impl MergeConflict {
+    /// Human-readable location in the originally generated file
+    pub fn location(&self) -> String {
+        describe_lines(self.start, self.end)
+    }
+
+    /// The region wrapped in `<<<<<<< manual-patch` / `>>>>>>> generated` markers
+    pub fn with_markers(&self) -> String {
+        let mut out = String::from("<<<<<<< manual-patch\n");
+        push_line_block(&mut out, &self.manual);
+        out.push_str("=======\n");
+        push_line_block(&mut out, &self.generated);
+        out.push_str(">>>>>>> generated\n");
+        out
+    }
+}
+
+/// What happened to one hunk of a patch during a merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkReport {
+    /// Lines of the originally generated file the hunk replaces (0-based,
+    /// end exclusive; empty for pure insertions)
+    pub start: usize,
+    pub end: usize,
+    pub outcome: HunkOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkOutcome {
+    /// Merged into the new content
+    Applied,
+    /// The new content already makes the same change
+    AlreadyPresent,
+    /// The new content changes the same lines differently; wrapped in markers
+    Conflict,
+}
+
+impl HunkReport {
+    /// Human-readable location, e.g. "lines 3-5" or "after line 2"
+    pub fn location(&self) -> String {
+        describe_lines(self.start, self.end)
+    }
+}
+
 /// Manages manual patches to code.lock/ files.
 ///
-/// Patches are stored as unified diffs in `.lit/patches/<output-path>.patch`.
+/// Patches are stored as unified diffs in `.lit/patches/<output-path>.patch`,
+/// with `.lit/patches/index.json` tracking their hashes, timestamps and status.
 /// They represent hand-edits to generated code that should survive regeneration.
 ///
 /// Design principle: Patches are temporary escape hatches. The long-term goal
@@ -76,35 +231,35 @@ impl PatchStore {
         let mut patches = Vec::new();
 
         for (path, gen_content) in generated {
-            if let Some(actual_content) = actual.get(path) {
-                if gen_content != actual_content {
-                    let diff = TextDiff::from_lines(gen_content, actual_content);
-                    let unified = diff
-                        .unified_diff()
-                        .context_radius(3)
-                        .header(
-                            &format!("a/{}", path.display()),
-                            &format!("b/{}", path.display()),
-                        )
-                        .to_string();
-
-                    let mut lines_added = 0;
-                    let mut lines_removed = 0;
-                    for change in diff.iter_all_changes() {
-                        match change.tag() {
-                            ChangeTag::Insert => lines_added += 1,
-                            ChangeTag::Delete => lines_removed += 1,
-                            ChangeTag::Equal => {}
-                        }
+            if let Some(actual_content) = actual.get(path)
+                && gen_content != actual_content
+            {
+                let diff = TextDiff::from_lines(gen_content, actual_content);
+                let unified = diff
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(
+                        &format!("a/{}", path.display()),
+                        &format!("b/{}", path.display()),
+                    )
+                    .to_string();
+
+                let mut lines_added = 0;
+                let mut lines_removed = 0;
+                for change in diff.iter_all_changes() {
+                    match change.tag() {
+                        ChangeTag::Insert => lines_added += 1,
+                        ChangeTag::Delete => lines_removed += 1,
+                        ChangeTag::Equal => {}
                     }
-
-                    patches.push(PatchInfo {
-                        output_path: path.clone(),
-                        diff: unified,
-                        lines_added,
-                        lines_removed,
-                    });
                 }
+
+                patches.push(PatchInfo {
+                    output_path: path.clone(),
+                    diff: unified,
+                    lines_added,
+                    lines_removed,
+                });
             }
         }
 
@@ -128,8 +283,8 @@ impl PatchStore {
             .unified_diff()
             .context_radius(3)
             .header(
-                &format!("a/{}", output_path.display()),
-                &format!("b/{}", output_path.display()),
+                &format!("a/{}", to_slash(output_path)),
+                &format!("b/{}", to_slash(output_path)),
             )
             .to_string();
 
@@ -140,14 +295,30 @@ impl PatchStore {
         };
 
         let patch_path = self.patch_file_path(output_path);
-        if let Some(parent) = patch_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create patch dir: {}", parent.display()))?;
-        }
-        let json = serde_json::to_string_pretty(&stored)
-            .context("Failed to serialize patch")?;
-        std::fs::write(&patch_path, json)
-            .with_context(|| format!("Failed to write patch: {}", patch_path.display()))
+        let json = prepare_patch_file(&patch_path, &stored)?;
+
+        self.update_index(|index| {
+            atomic::write(&patch_path, &json)
+                .with_context(|| format!("Failed to write patch: {}", patch_path.display()))?;
+
+            let now = Utc::now();
+            let created_at = index
+                .patches
+                .get(output_path)
+                .map(|e| e.created_at)
+                .unwrap_or(now);
+            index.patches.insert(
+                normalize(output_path),
+                PatchIndexEntry {
+                    hash: hash_content(json.as_bytes()),
+                    created_at,
+                    updated_at: now,
+                    status: PatchStatus::Active,
+                },
+            );
+            tracing::info!(output = %output_path.display(), "patch saved");
+            Ok(())
+        })
     }
 
     /// Load a saved patch for a given output path.
@@ -159,13 +330,16 @@ impl PatchStore {
 
     /// Apply a saved patch to newly generated content.
     ///
-    /// This is a line-based 3-way merge:
+    /// This is a line-based 3-way merge (diff3):
     /// - The "base" is the original generated content (from the saved patch's "before" side)
     /// - The "theirs" is the new LLM-generated content
     /// - The "ours" changes are the manual edits from the patch
     ///
-    /// Simple strategy: if the patch hunks apply cleanly to the new content,
-    /// return Applied. Otherwise return Conflict with markers.
+    /// Both sides are diffed against the base with patience diff, which
+    /// anchors hunks on unique lines rather than line numbers, so edits in
+    /// different parts of the file merge even when line counts shift. Only
+    /// regions both sides changed differently get conflict markers; the rest
+    /// of the file is merged.
     pub fn apply_patch(
         &self,
         original_generated: &str,
@@ -178,78 +352,87 @@ impl PatchStore {
             return PatchResult::Applied(manual_content.to_string());
         }
 
-        // 3-way merge: find what the user changed (original→manual),
-        // and what the LLM changed (original→new), then combine.
-        //
-        // Simple line-based approach:
-        // 1. Compute user edits: original → manual
-        // 2. Compute LLM edits: original → new
-        // 3. If no overlapping changes, apply both sets of edits
-        // 4. If overlapping, insert conflict markers
-
-        let original_lines: Vec<&str> = original_generated.lines().collect();
-        let manual_lines: Vec<&str> = manual_content.lines().collect();
-        let new_lines: Vec<&str> = new_generated.lines().collect();
-
-        // Collect line-level change ranges for user edits and LLM edits
-        let user_changes = collect_line_changes_from_strings(original_generated, manual_content);
-        let llm_changes = collect_line_changes_from_strings(original_generated, new_generated);
-
-        // Check for overlapping changes
-        let has_conflict = user_changes.iter().any(|uc| {
-            llm_changes.iter().any(|lc| ranges_overlap(uc, lc))
-        });
-
-        if has_conflict {
-            // Conflict: output with markers
-            let conflict_content = format!(
-                "<<<<<<< manual-patch\n\
-                 {}\
-                 =======\n\
-                 {}\
-                 >>>>>>> generated\n",
-                manual_content,
-                new_generated
-            );
-            PatchResult::Conflict(conflict_content)
+        let merge = self.merge_patch(original_generated, new_generated, manual_content);
+        tracing::debug!(conflicted = merge.conflicted(), "patch merged into new generation");
+        if merge.conflicted() {
+            PatchResult::Conflict(merge.content)
         } else {
-            // No overlapping changes — apply user edits to new generated content.
-            // The simplest correct approach: start from the new LLM output and
-            // replay user changes that don't conflict.
-            //
-            // Since there are no overlapping changes, we can apply user edits
-            // by finding the lines the user modified and applying those changes
-            // to the corresponding positions in the new content.
-            //
-            // Simplification: if user only made small edits and LLM made different
-            // small edits, apply the user's manual_content lines that differ from
-            // original, keeping the rest from new_generated.
-            let result = merge_non_conflicting(
-                &original_lines,
-                &manual_lines,
-                &new_lines,
-            );
-            PatchResult::Applied(result)
+            PatchResult::Applied(merge.content)
         }
     }
 
-    /// List all tracked patches.
+    /// Merge a patch into `current` hunk by hunk, as [`apply_patch`](Self::apply_patch)
+    /// does, reporting what happened to each hunk of the patch. Conflicting
+    /// hunks get markers while clean hunks still merge.
+    pub fn merge_patch(&self, original_generated: &str, current: &str, manual_content: &str) -> PatchMerge {
+        merge3(original_generated, manual_content, current)
+    }
+
+    /// List all tracked patches (from the index).
+    #[allow(dead_code)]
     pub fn list_patches(&self) -> Vec<PathBuf> {
-        let mut patches = Vec::new();
-        if self.patches_dir.exists() {
-            collect_patches_recursive(&self.patches_dir, &self.patches_dir, &mut patches);
+        match self.load_index() {
+            Ok(index) => index.patches.into_keys().collect(),
+            Err(_) => self.scan_patch_files(),
         }
-        patches.sort();
-        patches
+    }
+
+    /// Move a patch to a new output path, keeping its index metadata.
+    ///
+    /// Returns false if there was no patch for `from`.
+    pub fn rename_patch(&self, from: &Path, to: &Path) -> Result<bool> {
+        let Some(mut stored) = self.load_patch(from) else {
+            return Ok(false);
+        };
+        stored.diff = stored.diff.replacen(
+            &format!("a/{}", from.display()),
+            &format!("a/{}", to.display()),
+            1,
+        );
+        stored.diff = stored.diff.replacen(
+            &format!("b/{}", from.display()),
+            &format!("b/{}", to.display()),
+            1,
+        );
+
+        let from_path = self.patch_file_path(from);
+        let to_path = self.patch_file_path(to);
+        let json = prepare_patch_file(&to_path, &stored)?;
+        self.update_index(|index| {
+            atomic::write(&to_path, &json)
+                .with_context(|| format!("Failed to write patch: {}", to_path.display()))?;
+            std::fs::remove_file(&from_path)
+                .with_context(|| format!("Failed to remove patch: {}", from_path.display()))?;
+
+            let now = Utc::now();
+            let mut entry = index.patches.remove(from).unwrap_or(PatchIndexEntry {
+                hash: String::new(),
+                created_at: now,
+                updated_at: now,
+                status: PatchStatus::Active,
+            });
+            entry.hash = hash_content(json.as_bytes());
+            entry.updated_at = now;
+            index.patches.insert(normalize(to), entry);
+            Ok(())
+        })?;
+        if let Some(parent) = from_path.parent() {
+            let _ = cleanup_empty_dirs(parent, &self.patches_dir);
+        }
+        Ok(true)
     }
 
     /// Drop (delete) a saved patch.
     pub fn drop_patch(&self, output_path: &Path) -> Result<()> {
         let patch_path = self.patch_file_path(output_path);
-        if patch_path.exists() {
-            std::fs::remove_file(&patch_path)
-                .with_context(|| format!("Failed to remove patch: {}", patch_path.display()))?;
-        }
+        self.update_index(|index| {
+            if patch_path.exists() {
+                std::fs::remove_file(&patch_path)
+                    .with_context(|| format!("Failed to remove patch: {}", patch_path.display()))?;
+            }
+            index.patches.remove(output_path);
+            Ok(())
+        })?;
         // Clean up empty parent directories
         if let Some(parent) = patch_path.parent() {
             let _ = cleanup_empty_dirs(parent, &self.patches_dir);
@@ -257,21 +440,233 @@ impl PatchStore {
         Ok(())
     }
 
+    /// Record the status of a patch after regeneration.
+    pub fn set_status(&self, output_path: &Path, status: PatchStatus) -> Result<()> {
+        self.update_index(|index| {
+            if let Some(entry) = index.patches.get_mut(output_path) {
+                entry.status = status;
+                entry.updated_at = Utc::now();
+            }
+            Ok(())
+        })
+    }
+
+    /// Load the patch index.
+    ///
+    /// Repos created before the index existed get one built from the patch
+    /// files on disk (not persisted until the next write).
+    pub fn load_index(&self) -> Result<PatchIndex> {
+        let index_path = self.patches_dir.join(INDEX_FILE);
+        if !index_path.exists() {
+            return Ok(self.rebuild_index());
+        }
+        let content = std::fs::read_to_string(&index_path)
+            .with_context(|| format!("Failed to read patch index: {}", index_path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse patch index: {}", index_path.display()))
+    }
+
+    /// Find index entries whose patch file is missing or was modified outside lit.
+    pub fn stale_patches(&self) -> Result<Vec<StalePatch>> {
+        let index = self.load_index()?;
+        let mut stale = Vec::new();
+        for (path, entry) in &index.patches {
+            match std::fs::read(self.patch_file_path(path)) {
+                Err(_) => stale.push(StalePatch::Missing(path.clone())),
+                Ok(content) if hash_content(&content) != entry.hash => {
+                    stale.push(StalePatch::Modified(path.clone()))
+                }
+                Ok(_) => {}
+            }
+        }
+        Ok(stale)
+    }
+
+    /// Read-modify-write the index under the lock file, writing it atomically.
+    fn update_index(&self, f: impl FnOnce(&mut PatchIndex) -> Result<()>) -> Result<()> {
+        self.init()?;
+        let _lock = IndexLock::acquire(&self.patches_dir.join(INDEX_LOCK_FILE))?;
+
+        let mut index = self.load_index()?;
+        f(&mut index)?;
+
+        let index_path = self.patches_dir.join(INDEX_FILE);
+        let json = serde_json::to_string_pretty(&index).context("Failed to serialize patch index")?;
+        atomic::write(&index_path, json)
+            .with_context(|| format!("Failed to write patch index: {}", index_path.display()))
+    }
+
+    /// Build an index from the patch files on disk
+    fn rebuild_index(&self) -> PatchIndex {
+        let now = Utc::now();
+        let mut index = PatchIndex::default();
+        for path in self.scan_patch_files() {
+            if let Ok(content) = std::fs::read(self.patch_file_path(&path)) {
+                index.patches.insert(
+                    path,
+                    PatchIndexEntry {
+                        hash: hash_content(&content),
+                        created_at: now,
+                        updated_at: now,
+                        status: PatchStatus::Active,
+                    },
+                );
+            }
+        }
+        index
+    }
+
+    fn scan_patch_files(&self) -> Vec<PathBuf> {
+        let mut patches = Vec::new();
+        if self.patches_dir.exists() {
+            collect_patches_recursive(&self.patches_dir, &self.patches_dir, &mut patches);
+        }
+        patches.sort();
+        patches
+    }
+
     /// Check if a patch exists for a given output path.
     pub fn has_patch(&self, output_path: &Path) -> bool {
         self.patch_file_path(output_path).exists()
     }
 
-    // Internal: compute the path where a patch file is stored
-    fn patch_file_path(&self, output_path: &Path) -> PathBuf {
+    /// The path where the patch for an output file is stored
+    pub fn patch_file_path(&self, output_path: &Path) -> PathBuf {
         // e.g., output_path = "src/schemas/user.py"
         // patch file = ".lit/patches/src/schemas/user.py.patch"
-        let mut patch_name = output_path.as_os_str().to_os_string();
-        patch_name.push(".patch");
-        self.patches_dir.join(PathBuf::from(patch_name))
+        self.patches_dir.join(format!("{}.patch", to_slash(output_path)))
     }
 }
 
+/// Exclusive lock on the patch index, released on drop.
+///
+/// Like [`crate::core::lock::RepoLock`] it is an OS file lock, so a killed
+/// process doesn't leave the index locked; the file itself stays behind.
+struct IndexLock {
+    file: File,
+}
+
+impl IndexLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        let file = File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open patch index lock: {}", path.display()))?;
+        // Holders only rewrite the index, so waiting on one is brief
+        file.lock()
+            .with_context(|| format!("Failed to lock patch index: {}", path.display()))?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+// ---------- Conflict report ----------
+
+/// A patch that could not be merged into freshly generated code
+#[derive(Debug, Clone)]
+pub struct PatchConflict {
+    pub output_path: PathBuf,
+    /// Prompt that generates the file
+    pub prompt_path: PathBuf,
+    /// Generated content the patch was made against
+    pub original_generated: String,
+    pub manual_content: String,
+    pub new_generated: String,
+}
+
+/// Render a Markdown report of the patch conflicts from one regeneration run.
+pub fn conflict_report(conflicts: &[PatchConflict], timestamp: DateTime<Utc>) -> String {
+    let mut out = format!(
+        "# Patch conflicts ({})\n\n\
+         {} file(s) in code.lock/ contain conflict markers. Resolve them before committing.\n",
+        timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+        conflicts.len()
+    );
+
+    for conflict in conflicts {
+        let user_changes =
+            collect_line_changes_from_strings(&conflict.original_generated, &conflict.manual_content);
+        let llm_changes =
+            collect_line_changes_from_strings(&conflict.original_generated, &conflict.new_generated);
+
+        out.push_str(&format!(
+            "\n## {}\n\nPrompt: `{}`\n\n### Your patch\n\n```diff\n{}```\n\n### Regenerated\n\n```diff\n{}```\n",
+            conflict.output_path.display(),
+            conflict.prompt_path.display(),
+            conflicting_hunks(&conflict.original_generated, &conflict.manual_content, &llm_changes),
+            conflicting_hunks(&conflict.original_generated, &conflict.new_generated, &user_changes),
+        ));
+    }
+
+    out.push_str(
+        "\n## Resolving\n\n\
+         1. Edit each file above in code.lock/ and resolve the `<<<<<<< manual-patch` / \
+         `>>>>>>> generated` sections.\n\
+         2. Run `lit patch save` to record the resolution as the new patch.\n\
+         3. Or run `lit patch drop <file>` and `lit regenerate <prompt>` to keep the generated version.\n\
+         4. Commit only once no conflict markers remain.\n",
+    );
+    out
+}
+
+/// Write a conflict report to `<reports_dir>/conflicts-<timestamp>.md`.
+pub fn write_conflict_report(
+    reports_dir: &Path,
+    conflicts: &[PatchConflict],
+    timestamp: DateTime<Utc>,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(reports_dir)
+        .with_context(|| format!("Failed to create reports dir: {}", reports_dir.display()))?;
+    let path = reports_dir.join(format!("conflicts-{}.md", timestamp.format("%Y%m%d-%H%M%S")));
+    atomic::write(&path, conflict_report(conflicts, timestamp))
+        .with_context(|| format!("Failed to write conflict report: {}", path.display()))?;
+    Ok(path)
+}
+
+/// Unified-diff hunks of `original → modified` that overlap `other_changes`
+fn conflicting_hunks(original: &str, modified: &str, other_changes: &[LineChange]) -> String {
+    let diff = TextDiff::from_lines(original, modified);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(2);
+
+    let mut out = String::new();
+    for hunk in unified.iter_hunks() {
+        let overlaps = hunk
+            .ops()
+            .iter()
+            .filter(|op| op.tag() != similar::DiffTag::Equal)
+            .any(|op| {
+                let range = op.old_range();
+                let change = LineChange { start: range.start, end: range.end };
+                other_changes.iter().any(|other| ranges_overlap(&change, other))
+            });
+        if overlaps {
+            out.push_str(&hunk.to_string());
+        }
+    }
+    out
+}
+
+/// Create the patch file's parent directory and serialize the patch
+fn prepare_patch_file(patch_path: &Path, stored: &StoredPatch) -> Result<String> {
+    if let Some(parent) = patch_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create patch dir: {}", parent.display()))?;
+    }
+    serde_json::to_string_pretty(stored).context("Failed to serialize patch")
+}
+
+fn hash_content(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
 /// Represents a range of lines in the original that were changed
 #[derive(Debug)]
 struct LineChange {
@@ -333,112 +728,156 @@ fn collect_line_changes_from_strings(original: &str, modified: &str) -> Vec<Line
     changes
 }
 
-/// Merge non-conflicting edits from both user and LLM onto original.
-///
-/// For each line in the original:
-/// - If the user changed it (but LLM didn't) → use user's version
-/// - If the LLM changed it (but user didn't) → use LLM's version
-/// - If neither changed it → use original (or equivalently, new)
-fn merge_non_conflicting(
-    original: &[&str],
-    manual: &[&str],
-    new_gen: &[&str],
-) -> String {
-    // Simple LCS-based merge: walk through and pick changes from whichever side modified each line
-    // For now, use a simpler heuristic: the user's edits are applied on top of
-    // the new generated content.
-    //
-    // Since we verified no overlapping changes, we can:
-    // 1. Find lines user changed from original
-    // 2. Find where those lines map to in new_gen
-    // 3. Apply user's changes there
-    //
-    // Simplest correct approach for non-conflicting:
-    // Start with new_gen, then for each line in original that the user changed
-    // but the LLM kept the same, apply the user's change.
-
-    let mut result_lines: Vec<String> = Vec::new();
-
-    // Walk through the alignment of all three
-    let mut o_idx = 0;
-    let mut m_idx = 0;
-    let mut n_idx = 0;
-
-    while o_idx < original.len() || m_idx < manual.len() || n_idx < new_gen.len() {
-        let o_line = original.get(o_idx).copied();
-        let m_line = manual.get(m_idx).copied();
-        let n_line = new_gen.get(n_idx).copied();
-
-        match (o_line, m_line, n_line) {
-            (Some(o), Some(m), Some(n)) => {
-                if o == m && o == n {
-                    // All three agree — emit the line
-                    result_lines.push(n.to_string());
-                    o_idx += 1;
-                    m_idx += 1;
-                    n_idx += 1;
-                } else if o == n && o != m {
-                    // User changed, LLM didn't → use user's version
-                    result_lines.push(m.to_string());
-                    o_idx += 1;
-                    m_idx += 1;
-                    n_idx += 1;
-                } else if o == m && o != n {
-                    // LLM changed, user didn't → use LLM's version
-                    result_lines.push(n.to_string());
-                    o_idx += 1;
-                    m_idx += 1;
-                    n_idx += 1;
-                } else {
-                    // Both changed (shouldn't happen since we checked for conflicts)
-                    // Use the LLM version as fallback
-                    result_lines.push(n.to_string());
-                    o_idx += 1;
-                    m_idx += 1;
-                    n_idx += 1;
-                }
-            }
-            (None, Some(m), None) => {
-                // User added extra lines at the end
-                result_lines.push(m.to_string());
-                m_idx += 1;
-            }
-            (None, None, Some(n)) => {
-                // LLM added extra lines at the end
-                result_lines.push(n.to_string());
-                n_idx += 1;
-            }
-            (Some(_o), Some(m), None) => {
-                // Original and manual have lines, but new_gen is shorter
-                // User's version of the remaining lines
-                result_lines.push(m.to_string());
-                o_idx += 1;
-                m_idx += 1;
-            }
-            (Some(_o), None, Some(n)) => {
-                // Original and new have lines, but manual is shorter (user deleted)
-                // Since LLM kept these, and user deleted, user's edit wins
-                o_idx += 1;
-                n_idx += 1;
-                // Skip this line (user deleted it)
-                let _ = n; // suppress unused warning
+/// One side's replacement of `base[start..end]`
+#[derive(Debug)]
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: &'a [&'a str],
+}
+
+/// Whether two hunks touch the same base lines. Insertions at the same point
+/// also conflict, since their order is ambiguous.
+fn hunks_conflict(start: usize, end: usize, hunk: &Hunk) -> bool {
+    (start < hunk.end && hunk.start < end)
+        || (start == hunk.start && (start == end || hunk.start == hunk.end))
+}
+
+/// Hunks turning `base` into `side`, anchored by patience diff
+fn diff_hunks<'a>(base: &[&str], side: &'a [&'a str]) -> Vec<Hunk<'a>> {
+    let mut ranges: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    for op in similar::capture_diff_slices(similar::Algorithm::Patience, base, side) {
+        if op.tag() == similar::DiffTag::Equal {
+            continue;
+        }
+        let (old, new) = (op.old_range(), op.new_range());
+        match ranges.last_mut() {
+            // A delete followed by an insert at the same point is one replacement
+            Some((last_old, last_new)) if last_old.end == old.start && last_new.end == new.start => {
+                last_old.end = old.end;
+                last_new.end = new.end;
             }
-            (None, Some(m), Some(_n)) => {
-                // Both manual and new added lines past original
-                // Prefer manual since user explicitly added
-                result_lines.push(m.to_string());
-                m_idx += 1;
-                n_idx += 1;
+            _ => ranges.push((old, new)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(old, new)| Hunk { start: old.start, end: old.end, lines: &side[new] })
+        .collect()
+}
+
+/// Apply `hunks` (all within `start..end`) to `base[start..end]`
+fn apply_hunks(base: &[&str], start: usize, end: usize, hunks: &[&Hunk]) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for hunk in hunks {
+        out.extend(base[pos..hunk.start].iter().copied());
+        out.extend(hunk.lines.iter().copied());
+        pos = hunk.end;
+    }
+    out.extend(base[pos..end].iter().copied());
+    out
+}
+
+/// Three-way merge of `ours` (the manual edit) and `theirs` against `base`.
+///
+/// Conflicting regions are wrapped in `<<<<<<< manual-patch` /
+/// `>>>>>>> generated` markers; every hunk of `ours` is reported.
+fn merge3(base: &str, ours: &str, theirs: &str) -> PatchMerge {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let ours = diff_hunks(&base, &ours);
+    let theirs = diff_hunks(&base, &theirs);
+
+    let mut segments = Vec::new();
+    let mut reports = Vec::new();
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < ours.len() || j < theirs.len() {
+        // Start a region at the earliest remaining hunk, then grow it while
+        // hunks from either side conflict with it (a hunk always conflicts
+        // with its own range)
+        let (mut start, mut end) = match (ours.get(i), theirs.get(j)) {
+            (Some(a), Some(b)) if b.start < a.start => (b.start, b.end),
+            (Some(a), _) => (a.start, a.end),
+            (None, Some(b)) => (b.start, b.end),
+            (None, None) => unreachable!(),
+        };
+        let (first_i, first_j) = (i, j);
+        loop {
+            if let Some(a) = ours.get(i)
+                && hunks_conflict(start, end, a)
+            {
+                start = start.min(a.start);
+                end = end.max(a.end);
+                i += 1;
+            } else if let Some(b) = theirs.get(j)
+                && hunks_conflict(start, end, b)
+            {
+                start = start.min(b.start);
+                end = end.max(b.end);
+                j += 1;
+            } else {
+                break;
             }
-            _ => break,
         }
+
+        segments.push(MergeSegment::Clean(base[pos..start].concat()));
+        let our_hunks: Vec<&Hunk> = ours[first_i..i].iter().collect();
+        let their_hunks: Vec<&Hunk> = theirs[first_j..j].iter().collect();
+        let our_text = apply_hunks(&base, start, end, &our_hunks);
+        let their_text = apply_hunks(&base, start, end, &their_hunks);
+
+        let outcome = if our_hunks.is_empty() || their_hunks.is_empty() {
+            // Only one side changed this region
+            segments.push(MergeSegment::Clean(if our_hunks.is_empty() { their_text } else { our_text }));
+            HunkOutcome::Applied
+        } else if our_text == their_text {
+            // Both sides made the same change
+            segments.push(MergeSegment::Clean(our_text));
+            HunkOutcome::AlreadyPresent
+        } else {
+            segments.push(MergeSegment::Conflict(MergeConflict {
+                start,
+                end,
+                manual: our_text,
+                generated: their_text,
+            }));
+            HunkOutcome::Conflict
+        };
+        reports.extend(our_hunks.iter().map(|h| HunkReport { start: h.start, end: h.end, outcome }));
+        pos = end;
     }
+    segments.push(MergeSegment::Clean(base[pos..].concat()));
+    segments.retain(|segment| !matches!(segment, MergeSegment::Clean(text) if text.is_empty()));
+
+    let content = segments
+        .iter()
+        .map(|segment| match segment {
+            MergeSegment::Clean(text) => text.clone(),
+            MergeSegment::Conflict(conflict) => conflict.with_markers(),
+        })
+        .collect();
+    PatchMerge { content, hunks: reports, segments }
+}
+
+/// Describe base lines `start..end` (0-based) for humans, 1-based
+fn describe_lines(start: usize, end: usize) -> String {
+    match (start, end) {
+        (0, 0) => "at the top".to_string(),
+        (start, end) if start == end => format!("after line {}", start),
+        (start, end) if end == start + 1 => format!("line {}", end),
+        (start, end) => format!("lines {}-{}", start + 1, end),
+    }
+}
 
-    let mut result = result_lines.join("\n");
-    if !result.ends_with('\n') {
-        result.push('\n');
+/// Append `text`, ending it with a newline so a following marker starts a line
+fn push_line_block(out: &mut String, text: &str) {
+    out.push_str(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        out.push('\n');
     }
-    result
 }
 
 /// Recursively collect .patch files
@@ -452,7 +891,7 @@ fn collect_patches_recursive(dir: &Path, base: &Path, out: &mut Vec<PathBuf>) {
                 // Convert patch file path back to output path
                 // e.g., .lit/patches/src/schemas/user.py.patch → src/schemas/user.py
                 if let Ok(relative) = path.strip_prefix(base) {
-                    let rel_str = relative.to_string_lossy();
+                    let rel_str = to_slash(relative);
                     if let Some(stripped) = rel_str.strip_suffix(".patch") {
                         out.push(PathBuf::from(stripped));
                     }
@@ -485,6 +924,30 @@ fn cleanup_empty_dirs(dir: &Path, stop_at: &Path) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_conflict_report() {
+        let conflict = PatchConflict {
+            output_path: PathBuf::from("src/main.py"),
+            prompt_path: PathBuf::from("prompts/main.prompt.md"),
+            original_generated: "import os\nTIMEOUT = 10\nprint('hi')\n".to_string(),
+            manual_content: "import os\nTIMEOUT = 30\nprint('hi')\n".to_string(),
+            new_generated: "import os\nTIMEOUT = 5\nprint('hi')\n".to_string(),
+        };
+        let timestamp = Utc::now();
+        let report = conflict_report(std::slice::from_ref(&conflict), timestamp);
+
+        assert!(report.contains("## src/main.py"));
+        assert!(report.contains("Prompt: `prompts/main.prompt.md`"));
+        assert!(report.contains("+TIMEOUT = 30"));
+        assert!(report.contains("+TIMEOUT = 5"));
+        assert!(report.contains("lit patch save"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_conflict_report(dir.path(), &[conflict], timestamp).unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("conflicts-"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), report);
+    }
+
     #[test]
     fn test_detect_no_patches() {
         let mut generated = HashMap::new();
@@ -593,6 +1056,138 @@ mod tests {
         assert!(!store.has_patch(Path::new("src/user.py")));
     }
 
+    #[test]
+    fn test_index_tracks_save_and_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        store.save_patch(Path::new("src/a.py"), "old", "new").unwrap();
+        let first = store.load_index().unwrap().patches[Path::new("src/a.py")].clone();
+        assert_eq!(first.status, PatchStatus::Active);
+        assert!(dir.path().join(INDEX_FILE).exists());
+
+        // Re-saving keeps created_at but refreshes the hash
+        store.save_patch(Path::new("src/a.py"), "old", "newer").unwrap();
+        let second = store.load_index().unwrap().patches[Path::new("src/a.py")].clone();
+        assert_eq!(second.created_at, first.created_at);
+        assert_ne!(second.hash, first.hash);
+
+        store.drop_patch(Path::new("src/a.py")).unwrap();
+        assert!(store.load_index().unwrap().patches.is_empty());
+    }
+
+    #[test]
+    fn test_index_set_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        store.save_patch(Path::new("src/a.py"), "old", "new").unwrap();
+        store.set_status(Path::new("src/a.py"), PatchStatus::Conflicted).unwrap();
+        let index = store.load_index().unwrap();
+        assert_eq!(index.patches[Path::new("src/a.py")].status, PatchStatus::Conflicted);
+    }
+
+    #[test]
+    fn test_rename_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        store.save_patch(Path::new("src/a.py"), "old\n", "new\n").unwrap();
+        store.set_status(Path::new("src/a.py"), PatchStatus::Conflicted).unwrap();
+        let created_at = store.load_index().unwrap().patches[Path::new("src/a.py")].created_at;
+
+        assert!(store.rename_patch(Path::new("src/a.py"), Path::new("lib/b.py")).unwrap());
+        assert!(!store.has_patch(Path::new("src/a.py")));
+        assert!(!dir.path().join("src").exists());
+
+        let moved = store.load_patch(Path::new("lib/b.py")).unwrap();
+        assert_eq!(moved.manual_content, "new\n");
+        assert!(moved.diff.contains("b/lib/b.py"));
+        let index = store.load_index().unwrap();
+        let entry = &index.patches[Path::new("lib/b.py")];
+        assert_eq!(entry.created_at, created_at);
+        assert_eq!(entry.status, PatchStatus::Conflicted);
+        assert!(store.stale_patches().unwrap().is_empty());
+
+        assert!(!store.rename_patch(Path::new("src/a.py"), Path::new("c.py")).unwrap());
+    }
+
+    #[test]
+    fn test_stale_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        store.save_patch(Path::new("src/a.py"), "old", "new").unwrap();
+        store.save_patch(Path::new("src/b.py"), "old", "new").unwrap();
+        store.save_patch(Path::new("src/c.py"), "old", "new").unwrap();
+        assert!(store.stale_patches().unwrap().is_empty());
+
+        std::fs::remove_file(dir.path().join("src/a.py.patch")).unwrap();
+        std::fs::write(dir.path().join("src/b.py.patch"), "{}").unwrap();
+
+        let stale = store.stale_patches().unwrap();
+        assert_eq!(
+            stale,
+            vec![
+                StalePatch::Missing(PathBuf::from("src/a.py")),
+                StalePatch::Modified(PathBuf::from("src/b.py")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_rebuilt_for_legacy_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        store.save_patch(Path::new("src/a.py"), "old", "new").unwrap();
+        store.save_patch(Path::new("src/b.py"), "old", "new").unwrap();
+        std::fs::remove_file(dir.path().join(INDEX_FILE)).unwrap();
+
+        assert_eq!(store.list_patches().len(), 2);
+        assert!(store.stale_patches().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_index_lock_released_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+        // A lock file left by a killed process doesn't hold the lock
+        std::fs::write(dir.path().join(INDEX_LOCK_FILE), "").unwrap();
+        store.save_patch(Path::new("src/a.py"), "old", "new").unwrap();
+
+        let lock_path = dir.path().join(INDEX_LOCK_FILE);
+        let lock = IndexLock::acquire(&lock_path).unwrap();
+        let other = File::options().write(true).open(&lock_path).unwrap();
+        assert!(matches!(other.try_lock(), Err(std::fs::TryLockError::WouldBlock)));
+        drop(lock);
+        other.try_lock().unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_all_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let patches_dir = dir.path().to_path_buf();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let patches_dir = patches_dir.clone();
+                std::thread::spawn(move || {
+                    let store = PatchStore::new(patches_dir);
+                    store
+                        .save_patch(&PathBuf::from(format!("src/f{}.py", i)), "old", "new")
+                        .unwrap();
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let store = PatchStore::new(patches_dir);
+        assert_eq!(store.load_index().unwrap().patches.len(), 8);
+    }
+
     #[test]
     fn test_apply_patch_no_llm_change() {
         // LLM produces the same content as before — user's edits apply cleanly
@@ -651,6 +1246,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_apply_patch_survives_shifted_lines() {
+        // User edits the top; the LLM adds lines in the middle and edits the bottom
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        let original = "import os\n\ndef a():\n    return 1\n\ndef b():\n    return 2\n";
+        let manual = "import os\nimport sys\n\ndef a():\n    return 1\n\ndef b():\n    return 2\n";
+        let new_gen = "import os\n\ndef a():\n    return 1\n\ndef c():\n    return 3\n\ndef b():\n    return 20\n";
+
+        match store.apply_patch(original, new_gen, manual) {
+            PatchResult::Applied(content) => assert_eq!(
+                content,
+                "import os\nimport sys\n\ndef a():\n    return 1\n\ndef c():\n    return 3\n\ndef b():\n    return 20\n"
+            ),
+            PatchResult::Conflict(c) => panic!("Expected clean apply, got conflict:\n{}", c),
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_conflict_is_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        let original = "a\nb\nc\nd\ne\n";
+        let manual = "a\nB_user\nc\nd\ne\n";
+        let new_gen = "a\nB_llm\nc\nd\nE_llm";
+
+        match store.apply_patch(original, new_gen, manual) {
+            PatchResult::Conflict(content) => assert_eq!(
+                content,
+                "a\n<<<<<<< manual-patch\nB_user\n=======\nB_llm\n>>>>>>> generated\nc\nd\nE_llm"
+            ),
+            PatchResult::Applied(_) => panic!("Expected conflict, got clean apply"),
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_same_change_on_both_sides() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        let original = "a\nb\nc\n";
+        let manual = "a\nfixed\nc\n";
+        let new_gen = "x\na\nfixed\nc\n";
+
+        match store.apply_patch(original, new_gen, manual) {
+            PatchResult::Applied(content) => assert_eq!(content, "x\na\nfixed\nc\n"),
+            PatchResult::Conflict(c) => panic!("Expected clean apply, got conflict:\n{}", c),
+        }
+    }
+
+    #[test]
+    fn test_merge_patch_reports_hunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        let original = "a\nb\nc\nd\ne\nf\n";
+        let manual = "header\na\nB_user\nc\nd\nE_fix\nf\n";
+        let current = "a\nB_llm\nc\nd\nE_fix\nf\n";
+
+        let merge = store.merge_patch(original, current, manual);
+        assert!(merge.conflicted());
+        let outcomes: Vec<(String, HunkOutcome)> =
+            merge.hunks.iter().map(|h| (h.location(), h.outcome)).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("at the top".to_string(), HunkOutcome::Applied),
+                ("line 2".to_string(), HunkOutcome::Conflict),
+                ("line 5".to_string(), HunkOutcome::AlreadyPresent),
+            ]
+        );
+        assert!(merge.content.starts_with("header\na\n<<<<<<< manual-patch\nB_user\n"));
+        assert!(merge.content.ends_with(">>>>>>> generated\nc\nd\nE_fix\nf\n"));
+    }
+
+    #[test]
+    fn test_merge_patch_resolve_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+
+        let original = "a\nb\nc\nd\ne\n";
+        let manual = "a\nB_user\nc\nD_user\ne\n";
+        let current = "a\nB_llm\nc\nD_llm\ne\n";
+
+        let merge = store.merge_patch(original, current, manual);
+        let conflicts: Vec<&MergeConflict> = merge.conflicts().collect();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].location(), "line 2");
+        assert_eq!(conflicts[0].manual, "B_user\n");
+        assert_eq!(conflicts[0].generated, "B_llm\n");
+
+        let resolved = merge
+            .resolve(|c| Ok(if c.start == 1 { c.manual.clone() } else { c.generated.clone() }))
+            .unwrap();
+        assert_eq!(resolved, "a\nB_user\nc\nD_llm\ne\n");
+
+        let unresolved = merge.resolve(|c| Ok(c.with_markers())).unwrap();
+        assert_eq!(unresolved, merge.content);
+    }
+
     #[test]
     fn test_has_patch() {
         let dir = tempfile::tempdir().unwrap();
@@ -670,5 +1367,24 @@ mod tests {
             path,
             PathBuf::from(".lit/patches/src/schemas/user.py.patch")
         );
+        // Windows separators name the same patch file
+        #[cfg(windows)]
+        assert_eq!(store.patch_file_path(Path::new(r"src\schemas\user.py")), path);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_patch_paths_normalized() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PatchStore::new(dir.path().to_path_buf());
+        store.init().unwrap();
+        store.save_patch(Path::new(r"src\schemas\user.py"), "old\n", "new\n").unwrap();
+
+        // Indexed, diffed and found again under the forward-slash path
+        let index = store.load_index().unwrap();
+        assert_eq!(index.patches.keys().collect::<Vec<_>>(), vec![Path::new("src/schemas/user.py")]);
+        let patch = store.load_patch(Path::new("src/schemas/user.py")).unwrap();
+        assert!(patch.diff.starts_with("--- a/src/schemas/user.py\n+++ b/src/schemas/user.py\n"));
+        assert_eq!(store.scan_patch_files(), vec![PathBuf::from("src/schemas/user.py")]);
     }
 }
@@ -0,0 +1,81 @@
+//! Repo-relative paths in one form everywhere.
+//!
+//! Every path lit stores or compares (manifest, patch index, cache entries,
+//! generation records, frontmatter, git pathspecs) is relative to the repo
+//! root and uses `/` separators, whatever the platform. Paths read from the
+//! filesystem or typed on the command line go through [`relative_to`] or
+//! [`normalize`] before they are kept, so a Windows `src\models\user.py`
+//! and the frontmatter's `src/models/user.py` are the same key. Elsewhere
+//! `\` is an ordinary file name character and is left alone.
+
+use std::path::{Path, PathBuf};
+
+/// `path` as a string with `/` separators
+pub fn to_slash(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    }
+}
+
+/// `path` with `/` separators and without `./` segments
+pub fn normalize(path: &Path) -> PathBuf {
+    let slashed = to_slash(path);
+    let parts: Vec<&str> = slashed
+        .split('/')
+        .enumerate()
+        .filter(|(i, part)| *part != "." && (!part.is_empty() || *i == 0))
+        .map(|(_, part)| part)
+        .collect();
+    match parts.as_slice() {
+        [] => PathBuf::from("."),
+        _ => PathBuf::from(parts.join("/")),
+    }
+}
+
+/// `path` relative to `base` (the repo root, or code.lock/), normalized;
+/// `path` itself, normalized, if it isn't under `base`
+pub fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    normalize(path.strip_prefix(base).unwrap_or(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_separators() {
+        assert_eq!(to_slash(Path::new(r"src\models\user.py")), "src/models/user.py");
+        assert_eq!(normalize(Path::new(r"prompts\models\user.prompt.md")), PathBuf::from("prompts/models/user.prompt.md"));
+        assert_eq!(normalize(Path::new(r".\src/models\\user.py")), PathBuf::from("src/models/user.py"));
+        assert_eq!(relative_to(Path::new(r"prompts\user.prompt.md"), Path::new(r"C:\repo")), PathBuf::from("prompts/user.prompt.md"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_backslash_is_a_file_name_character() {
+        assert_eq!(to_slash(Path::new(r"src/a\b.py")), r"src/a\b.py");
+        assert_eq!(normalize(Path::new(r"./src/a\b.py")), PathBuf::from(r"src/a\b.py"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("./src//models/./user.py")), PathBuf::from("src/models/user.py"));
+        assert_eq!(normalize(Path::new("src/user.py")), PathBuf::from("src/user.py"));
+        assert_eq!(normalize(Path::new("code.lock/")), PathBuf::from("code.lock"));
+        assert_eq!(normalize(Path::new("./")), PathBuf::from("."));
+        // Absolute paths keep their root
+        assert_eq!(normalize(Path::new("/repo/src/user.py")), PathBuf::from("/repo/src/user.py"));
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let root = Path::new("/repo");
+        assert_eq!(relative_to(Path::new("/repo/prompts/user.prompt.md"), root), PathBuf::from("prompts/user.prompt.md"));
+        assert_eq!(relative_to(Path::new("./prompts/user.prompt.md"), root), PathBuf::from("prompts/user.prompt.md"));
+        assert_eq!(relative_to(Path::new("/elsewhere/user.py"), root), PathBuf::from("/elsewhere/user.py"));
+    }
+}
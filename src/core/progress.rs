@@ -0,0 +1,221 @@
+//! Progress output for generation runs, and the `--quiet` / `--verbose`
+//! output level.
+//!
+//! Pipeline output goes through [`line`], [`detail`] and [`warn`] rather than
+//! `eprintln!` so it respects the output level and doesn't tear the
+//! progress bar drawn while a prompt is generating.
+
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+use crate::core::generation_record::format_tokens;
+
+/// How much the CLI prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// `--quiet`: errors, warnings and final summaries only
+    Quiet,
+    Normal,
+    /// `--verbose`: also context files, input hashes and cache decisions
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// How often the bar is redrawn while waiting on the LLM
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Width of the bar itself, in characters
+const BAR_WIDTH: usize = 24;
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        2 => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    }
+}
+
+pub fn is_quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// The bar currently on screen, if any
+static BAR: Mutex<Option<Bar>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+struct Bar {
+    done: usize,
+    total: usize,
+    prompt: String,
+    tokens: u64,
+    started: Instant,
+    drawn: bool,
+}
+
+impl Bar {
+    /// `  [#########---------------] 3/8  prompts/api.prompt.md  4.2s  12,345 tokens`
+    fn render(&self) -> String {
+        let filled = self.filled();
+        format!(
+            "  [{}{}] {}/{}  {}  {}  {}",
+            "#".repeat(filled).cyan(),
+            "-".repeat(BAR_WIDTH - filled).dimmed(),
+            self.done,
+            self.total,
+            self.prompt.bold(),
+            format!("{:.1}s", self.started.elapsed().as_secs_f64()).dimmed(),
+            format!("{} tokens", format_tokens(self.tokens)).dimmed()
+        )
+    }
+
+    /// Characters of the bar filled in
+    fn filled(&self) -> usize {
+        (BAR_WIDTH * self.done).checked_div(self.total).unwrap_or(0).min(BAR_WIDTH)
+    }
+}
+
+/// Print `text` on its own line, taking the bar off the screen first
+fn print_above_bar(text: &str) {
+    let mut bar = BAR.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(bar) = bar.as_mut().filter(|bar| bar.drawn) {
+        eprint!("\r\x1b[2K");
+        bar.drawn = false;
+    }
+    eprintln!("{}", text);
+}
+
+/// A line of progress output (not with `--quiet`)
+pub fn line(text: &str) {
+    if !is_quiet() {
+        print_above_bar(text);
+    }
+}
+
+/// A line only shown with `--verbose`
+pub fn detail(text: &str) {
+    if verbosity() == Verbosity::Verbose {
+        print_above_bar(&text.dimmed().to_string());
+    }
+}
+
+/// A warning or error, shown at every output level
+pub fn warn(text: &str) {
+    print_above_bar(text);
+}
+
+/// Progress of a generation run over `total` prompts; the bar is only drawn
+/// on a terminal, and is cleared when this is dropped
+pub struct Progress {
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn start(total: usize) -> Self {
+        let enabled = !is_quiet() && std::io::stderr().is_terminal();
+        if enabled {
+            *BAR.lock().unwrap_or_else(|e| e.into_inner()) = Some(Bar {
+                done: 0,
+                total,
+                prompt: String::new(),
+                tokens: 0,
+                started: Instant::now(),
+                drawn: false,
+            });
+        }
+        Self { enabled }
+    }
+
+    /// Move on to `prompt`, with `done` of the prompts finished
+    pub fn prompt(&self, done: usize, prompt: &Path) {
+        self.update(|bar| {
+            bar.done = done;
+            bar.prompt = prompt.display().to_string();
+        });
+    }
+
+    /// Count tokens spent so far in this run
+    pub fn add_tokens(&self, tokens: u64) {
+        self.update(|bar| bar.tokens += tokens);
+    }
+
+    /// Await `future`, keeping the bar (and its elapsed time) up to date
+    /// until it finishes
+    pub async fn wait<F: Future>(&self, future: F) -> F::Output {
+        if !self.enabled {
+            return future.await;
+        }
+        let mut interval = tokio::time::interval(REDRAW_INTERVAL);
+        tokio::pin!(future);
+        loop {
+            tokio::select! {
+                output = &mut future => return output,
+                _ = interval.tick() => self.draw(),
+            }
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Bar)) {
+        if let Some(bar) = BAR.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            change(bar);
+        }
+    }
+
+    fn draw(&self) {
+        if let Some(bar) = BAR.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            eprint!("\r\x1b[2K{}", bar.render());
+            let _ = std::io::stderr().flush();
+            bar.drawn = true;
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(bar) = BAR.lock().unwrap_or_else(|e| e.into_inner()).take()
+            && bar.drawn
+        {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_render() {
+        let bar = Bar {
+            done: 3,
+            total: 8,
+            prompt: "prompts/api.prompt.md".to_string(),
+            tokens: 12345,
+            started: Instant::now(),
+            drawn: false,
+        };
+        assert_eq!(bar.filled(), 9);
+        let line = bar.render();
+        assert!(line.contains("3/8"));
+        assert!(line.contains("prompts/api.prompt.md"));
+
+        let finished = Bar { done: 8, ..bar.clone() };
+        assert_eq!(finished.filled(), BAR_WIDTH);
+        let empty = Bar { total: 0, done: 0, ..bar };
+        assert_eq!(empty.filled(), 0);
+        assert!(empty.render().contains("0/0"));
+    }
+}
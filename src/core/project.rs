@@ -0,0 +1,447 @@
+//! The steps of a regeneration run that don't involve the terminal:
+//! loading a project's prompts, choosing which to regenerate, and reading
+//! and writing its code directory. `lit regenerate` and the library's
+//! `Lit` facade both run on these.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+
+use crate::core::atomic;
+use crate::core::cache::Cache;
+use crate::core::config::{FileMode, LitConfig, is_contained_path};
+use crate::core::context::write_interface;
+use crate::core::dag::Dag;
+use crate::core::freeze::FreezeList;
+use crate::core::generator::{GenerationOutput, PipelineResult, current_input_hashes};
+use crate::core::header::{self, HeaderValues};
+use crate::core::hooks::{HookPoint, Hooks};
+use crate::core::last_run::WrittenFile;
+use crate::core::manifest::{Manifest, content_hash};
+use crate::core::patch::{PatchConflict, PatchMerge, PatchResult, PatchStatus, PatchStore};
+use crate::core::paths::{normalize, relative_to};
+use crate::core::progress;
+use crate::core::prompt::{Prompt, TagFilter, discover_project_prompts, exclude_drafts, resolve_imports};
+use crate::core::style;
+
+/// Fail with a hint when the project has no prompts to regenerate yet
+pub fn ensure_prompts(config: &LitConfig, root: &Path) -> Result<()> {
+    if !config.project.prompt_dirs.iter().any(|dir| root.join(dir).exists()) {
+        anyhow::bail!(
+            "No prompt directory ({}) found in {}\n\
+             Hint: Create prompt files in {} first, then run `lit regenerate`.",
+            config.project.prompt_dirs_display(),
+            root.display(),
+            config.project.prompt_dirs[0].display()
+        );
+    }
+    if discover_project_prompts(config, root)?.is_empty() {
+        anyhow::bail!(
+            "No .prompt.md files found in {}\n\
+             Hint: Create a prompt file like {} and try again.",
+            config.project.prompt_dirs_display(),
+            config.project.prompt_dirs[0].join("hello.prompt.md").display()
+        );
+    }
+    Ok(())
+}
+
+/// Parse every prompt and build the DAG
+pub fn load_prompts(config: &LitConfig, root: &Path) -> Result<(HashMap<PathBuf, Prompt>, Dag)> {
+    let mut prompts_vec = Vec::new();
+    for p in &discover_project_prompts(config, root)? {
+        prompts_vec.push(
+            Prompt::from_file(p, root, config)
+                .with_context(|| format!("Failed to parse {}", p.display()))?,
+        );
+    }
+    resolve_imports(&mut prompts_vec)?;
+    let dag = Dag::build(&prompts_vec)?;
+    let prompts = prompts_vec.into_iter().map(|p| (p.path.clone(), p)).collect();
+    Ok((prompts, dag))
+}
+
+/// The prompts a run regenerates, in DAG order
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub prompts: Vec<PathBuf>,
+    /// Prompts left out because they are frozen
+    pub frozen: usize,
+    /// Prompts left out because they are drafts
+    pub drafts: usize,
+}
+
+/// Choose the prompts to regenerate: `paths` and their dependents (just
+/// `paths` with `only`), or every prompt if `paths` is empty. Tag filters
+/// prune the set after the cascade, so excluded dependents keep their
+/// current code; frozen prompts and drafts are always left out.
+pub fn select_prompts(
+    root: &Path,
+    dag: &Dag,
+    prompts: &HashMap<PathBuf, Prompt>,
+    paths: &[PathBuf],
+    only: bool,
+    tags: &TagFilter,
+) -> Result<Selection> {
+    let mut set = if paths.is_empty() {
+        dag.order().to_vec()
+    } else {
+        let mut seeds = Vec::new();
+        for specific_path in paths {
+            let relative = relative_to(specific_path, root);
+            if !dag.order().contains(&relative) {
+                anyhow::bail!(
+                    "Prompt {} not found in DAG.\n\nAvailable prompts:\n{}",
+                    relative.display(),
+                    dag.order()
+                        .iter()
+                        .map(|p| format!("  {}", p.display()))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                );
+            }
+            seeds.push(relative);
+        }
+        if only {
+            dag.order().iter().filter(|p| seeds.contains(p)).cloned().collect()
+        } else {
+            dag.regeneration_set(&seeds)
+        }
+    };
+
+    if !tags.is_empty() {
+        set.retain(|p| tags.matches(&prompts[p]));
+        if set.is_empty() {
+            anyhow::bail!("No prompts to regenerate match the tag filter");
+        }
+    }
+
+    // Frozen prompts keep their code.lock/ content, even with --all or --no-cache
+    let frozen = FreezeList::load(&root.join(".lit"))?;
+    let before = set.len();
+    set.retain(|p| !frozen.is_frozen(p));
+    let frozen = before - set.len();
+
+    // Drafts are sketches: they stay out of the set, and so must their dependents
+    let drafts = exclude_drafts(&mut set, prompts)?;
+    Ok(Selection { prompts: set, frozen, drafts })
+}
+
+/// Write a generated file under `output_dir` (code.lock/, or a preview
+/// directory), creating its parent directories and setting `mode` if given.
+/// Returns the full path.
+pub fn write_output_file(
+    output_dir: &Path,
+    file_path: &Path,
+    content: impl AsRef<[u8]>,
+    mode: Option<FileMode>,
+) -> Result<PathBuf> {
+    if !is_contained_path(file_path) {
+        anyhow::bail!("Refusing to write {} outside {}", file_path.display(), output_dir.display());
+    }
+    let full_path = output_dir.join(file_path);
+    if let Some(parent) = full_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        // A symlinked directory inside the output root could still point out of it
+        if !parent.canonicalize()?.starts_with(output_dir.canonicalize()?) {
+            anyhow::bail!("Refusing to write {} through a symlink out of {}", file_path.display(), output_dir.display());
+        }
+    }
+    atomic::write(&full_path, content)
+        .with_context(|| format!("Failed to write {}", full_path.display()))?;
+    if let Some(FileMode(bits)) = mode {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&full_path, std::fs::Permissions::from_mode(bits))
+                .with_context(|| format!("Failed to set the mode of {}", full_path.display()))?;
+        }
+        #[cfg(not(unix))]
+        let _ = bits;
+    }
+    Ok(full_path)
+}
+
+/// Load existing files from code.lock/ directory for use as context
+pub fn load_existing_code(code_lock_dir: &Path) -> HashMap<PathBuf, String> {
+    let mut code = HashMap::new();
+
+    if !code_lock_dir.exists() {
+        return code;
+    }
+
+    fn walk_dir(dir: &Path, base: &Path, out: &mut HashMap<PathBuf, String>) {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    walk_dir(&path, base, out);
+                } else if path.is_file()
+                    && let Ok(content) = std::fs::read_to_string(&path)
+                    && let Ok(relative) = path.strip_prefix(base)
+                {
+                    out.insert(normalize(relative), content);
+                }
+            }
+        }
+    }
+
+    walk_dir(code_lock_dir, code_lock_dir, &mut code);
+    code
+}
+
+/// Generated code in code.lock/, with provenance headers
+/// (`[generation] file_header`) stripped
+pub fn load_code(config: &LitConfig, root: &Path) -> HashMap<PathBuf, String> {
+    let mut code = load_existing_code(&config.code_dir(root));
+    if let Some(template) = &config.generation.file_header {
+        for (path, content) in code.iter_mut() {
+            *content = header::strip(template, path, content);
+        }
+    }
+    code
+}
+
+/// What [`write_outputs`] wrote: each file's final content (without its
+/// provenance header) and patch state
+#[derive(Debug, Default)]
+pub struct WrittenOutputs {
+    pub files: HashMap<PathBuf, (String, Option<PatchStatus>)>,
+    pub patches_applied: usize,
+    pub conflicts: Vec<PatchConflict>,
+}
+
+impl WrittenOutputs {
+    /// The files for `.lit/last-run.json`, static files included, sorted
+    pub fn last_run_files(&self, config: &LitConfig, result: &PipelineResult) -> Vec<WrittenFile> {
+        let mut files: Vec<WrittenFile> = result
+            .outputs
+            .iter()
+            .flat_map(|output| {
+                output.files.keys().filter_map(|path| {
+                    self.files.get(path).map(|(_, patch)| WrittenFile {
+                        path: path.clone(),
+                        prompt: Some(output.prompt_path.clone()),
+                        patch: *patch,
+                    })
+                })
+            })
+            .collect();
+        files.extend(config.r#static.iter().map(|sf| WrittenFile {
+            path: PathBuf::from(&sf.path),
+            prompt: None,
+            patch: None,
+        }));
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+}
+
+/// Resolves a conflicting patch merge into the file's final content
+pub type ConflictResolver<'a> = &'a dyn Fn(&Path, &PatchMerge) -> Result<String>;
+
+/// Write generated files under `write_dir`, applying saved patches.
+///
+/// A patch that conflicts with the new output is passed to `resolve` when
+/// given, and otherwise written with conflict markers. With `detached` (a
+/// preview outside code.lock/), patches and interfaces are left as they are.
+#[allow(clippy::too_many_arguments)]
+pub fn write_outputs(
+    root: &Path,
+    config: &LitConfig,
+    outputs: &[GenerationOutput],
+    prompts: &HashMap<PathBuf, Prompt>,
+    write_dir: &Path,
+    patch_store: Option<&PatchStore>,
+    hooks: &Hooks,
+    detached: bool,
+    resolve: Option<ConflictResolver<'_>>,
+) -> Result<WrittenOutputs> {
+    let interfaces_dir = root.join(".lit").join("interfaces");
+    let written_at = Utc::now();
+    let mut written = WrittenOutputs::default();
+    for output in outputs {
+        for (file_path, content) in &output.files {
+            let mut final_content = content.clone();
+            let mut patch_status = None;
+
+            // Check if there's a saved patch for this file
+            if let Some(ps) = patch_store
+                && let Some(stored_patch) = ps.load_patch(file_path)
+            {
+                let mut merged = ps.apply_patch(
+                    &stored_patch.original_content,
+                    content,
+                    &stored_patch.manual_content,
+                );
+                if let Some(resolve) = resolve
+                    && matches!(merged, PatchResult::Conflict(_))
+                {
+                    let merge = ps.merge_patch(
+                        &stored_patch.original_content,
+                        content,
+                        &stored_patch.manual_content,
+                    );
+                    merged = PatchResult::Applied(resolve(file_path, &merge)?);
+                }
+                match merged {
+                    PatchResult::Applied(merged) => {
+                        progress::line(&style::patch_applied(&file_path.display().to_string()));
+                        final_content = merged;
+                        patch_status = Some(PatchStatus::Active);
+                        written.patches_applied += 1;
+
+                        if !detached
+                            && let Err(e) = ps.save_patch(file_path, content, &final_content)
+                        {
+                            progress::warn(&format!(
+                                "    {}", style::warning(&format!("Failed to update patch: {}", e))
+                            ));
+                        }
+                    }
+                    PatchResult::Conflict(conflict) => {
+                        written.conflicts.push(PatchConflict {
+                            output_path: file_path.clone(),
+                            prompt_path: output.prompt_path.clone(),
+                            original_generated: stored_patch.original_content.clone(),
+                            manual_content: stored_patch.manual_content.clone(),
+                            new_generated: content.clone(),
+                        });
+                        progress::warn(&style::patch_conflict(&file_path.display().to_string()));
+                        progress::warn(&format!(
+                            "      {}",
+                            "Wrote conflict markers — please resolve manually".dimmed()
+                        ));
+                        final_content = conflict;
+                        patch_status = Some(PatchStatus::Conflicted);
+
+                        if !detached
+                            && let Err(e) = ps.set_status(file_path, PatchStatus::Conflicted)
+                        {
+                            progress::warn(&format!(
+                                "    {}", style::warning(&format!("Failed to update patch index: {}", e))
+                            ));
+                        }
+                    }
+                }
+            }
+
+            let mode = prompts.get(&output.prompt_path).and_then(|p| p.frontmatter.mode);
+            let full_path = match &config.generation.file_header {
+                Some(template) => {
+                    let values = HeaderValues {
+                        prompt: &output.prompt_path,
+                        model: &output.model,
+                        timestamp: written_at,
+                        input_hash: &output.input_hash,
+                    };
+                    let headed = header::prepend(template, file_path, &final_content, &values);
+                    write_output_file(write_dir, file_path, headed, mode)?
+                }
+                None => write_output_file(write_dir, file_path, &final_content, mode)?,
+            };
+
+            // Post-write hooks may rewrite the file (e.g. a formatter)
+            if hooks.has(HookPoint::PostWrite) {
+                hooks.run(HookPoint::PostWrite, Some(full_path.strip_prefix(root).unwrap_or(&full_path)))?;
+                final_content = std::fs::read_to_string(&full_path).with_context(|| {
+                    format!("Failed to read {}", full_path.display())
+                })?;
+                if let Some(template) = &config.generation.file_header {
+                    final_content = header::strip(template, file_path, &final_content);
+                }
+            }
+
+            if config.generation.interfaces && !detached {
+                write_interface(&interfaces_dir, file_path, &final_content)?;
+            }
+            written.files.insert(file_path.clone(), (final_content, patch_status));
+        }
+    }
+    Ok(written)
+}
+
+/// Record in `.lit/manifest.json` where each written file came from and
+/// the inputs it was generated from, and mark `left_stale` (dependents
+/// `lit regenerate --only` skipped) stale (warn on failure)
+pub fn update_manifest(
+    root: &Path,
+    result: &PipelineResult,
+    generated_at: DateTime<Utc>,
+    written: &WrittenOutputs,
+    prompts: &HashMap<PathBuf, Prompt>,
+    left_stale: &[PathBuf],
+) {
+    let lit_dir = root.join(".lit");
+    let mut manifest = Manifest::load(&lit_dir).ok().flatten().unwrap_or_default();
+    manifest.mark_stale(left_stale);
+    for output in &result.outputs {
+        manifest.record(
+            &output.prompt_path,
+            &output.input_hash,
+            generated_at,
+            output.files.keys().filter_map(|path| {
+                written.files.get(path).map(|(content, patch)| (path, content.as_str(), *patch))
+            }),
+        );
+        if let Some(components) = result.input_components.get(&output.prompt_path) {
+            manifest.record_inputs(&output.prompt_path, components.clone());
+        }
+    }
+    manifest.retain_prompts(prompts);
+    if let Err(e) = manifest.save(&lit_dir) {
+        progress::warn(&format!("  {}", style::warning(&format!("Failed to write .lit/manifest.json: {}", e))));
+    }
+}
+
+/// Prompts whose current input hash differs from the one recorded in
+/// `.lit/manifest.json` for their outputs, in DAG order. Only prompts
+/// matching `tags` are reported.
+///
+/// Returns `None` when no manifest has been recorded yet. Imports are hashed
+/// from code.lock/, with patched files hashed as originally generated so
+/// hand-edits don't mark dependents stale.
+pub fn stale_prompts(config: &LitConfig, root: &Path, tags: &TagFilter) -> Result<Option<Vec<PathBuf>>> {
+    let Some(manifest) = Manifest::load(&root.join(".lit"))? else {
+        return Ok(None);
+    };
+    let (prompts, dag) = load_prompts(config, root)?;
+
+    let mut code = load_code(config, root);
+    let patch_store = PatchStore::new(root.join(".lit").join("patches"));
+    let cache = Cache::new(root.join(".lit").join("cache")).with_config(config.cache.clone());
+    for path in code.keys().cloned().collect::<Vec<_>>() {
+        if let Some(patch) = patch_store.load_patch(&path) {
+            code.insert(path, patch.original_content);
+        } else if let Some(entry) = manifest.owner(&path)
+            && entry.content_hash == content_hash(&code[&path])
+            && let Some(generated) =
+                cache.get(&entry.input_hash).and_then(|c| c.files.get(&path).cloned())
+        {
+            // Unchanged since lit wrote it, but post-write hooks may have
+            // reformatted it; hash what was generated
+            code.insert(path, generated);
+        }
+    }
+
+    // Frozen and draft prompts are never regenerated, so they are never stale
+    let frozen = FreezeList::load(&root.join(".lit"))?;
+    let input_hashes = current_input_hashes(config, &dag, &prompts, &code);
+    Ok(Some(
+        dag.order()
+            .iter()
+            .filter(|p| {
+                let prompt = &prompts[*p];
+                tags.matches(prompt)
+                    && !frozen.is_frozen(p)
+                    && !prompt.frontmatter.draft
+                    && manifest.is_stale(p, &prompt.frontmatter.outputs, &input_hashes[*p])
+            })
+            .cloned()
+            .collect(),
+    ))
+}
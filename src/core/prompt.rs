@@ -1,9 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 
-use crate::core::config::{LitConfig, ModelConfig};
+use crate::core::config::{
+    FileMode, LitConfig, ModelConfig, ModelRoute, glob_match, is_contained_path, language_extension,
+    module_entry_file,
+};
+use crate::core::assertions::OutputTest;
+use crate::core::error::LitError;
+use crate::core::migrate::{FIRST_SCHEMA, check_schema};
+use crate::core::paths::{normalize, to_slash};
+use crate::core::tokens::estimate_tokens;
 
 // ---------- Public types ----------
 
@@ -18,19 +27,53 @@ pub struct Prompt {
     pub body: String,
     /// Full raw file content
     pub raw: String,
+    /// Partials referenced with `@include()` (directly or from another
+    /// partial), path relative to repo root → content. Loaded by
+    /// [`Prompt::from_file`]; empty for prompts parsed from a string.
+    pub includes: BTreeMap<PathBuf, String>,
 }
 
 /// Parsed YAML frontmatter from a .prompt.md file
 #[derive(Debug, Clone)]
 pub struct PromptFrontmatter {
-    /// Output file paths (relative to code.lock/)
+    /// Output file paths (relative to code.lock/). In `inferred` mapping
+    /// mode these default to the `[layout]` output for the prompt's path.
     pub outputs: Vec<PathBuf>,
-    /// Import paths to other .prompt.md files
+    /// Import paths to other .prompt.md files. Glob patterns
+    /// (`prompts/models/*.prompt.md`) are expanded by [`resolve_imports`].
     pub imports: Vec<PathBuf>,
-    /// Per-prompt model override
+    /// Import aliases usable in `@import()` body references (alias → path)
+    pub aliases: BTreeMap<String, PathBuf>,
+    /// Per-prompt model override (the first model of a `model:` list)
     pub model: Option<ModelConfig>,
+    /// Models to fall back to, in order, when the provider of `model` fails
+    /// with an authentication, rate-limit or server error
+    pub fallback_models: Vec<ModelRoute>,
     /// Per-prompt language override
     pub language: Option<String>,
+    /// Extra system prompt instructions for this prompt only
+    pub system: Option<String>,
+    /// Module directory this prompt owns (`modular` mapping mode). Files the
+    /// model adds inside it count as this prompt's outputs.
+    pub module: Option<PathBuf>,
+    /// Free-form labels for selecting prompts (`--tag` / `--exclude-tag`)
+    pub tags: Vec<String>,
+    /// Permission bits for every file this prompt writes (`mode: 0755`)
+    pub mode: Option<FileMode>,
+    /// Assertions `lit test` checks against the generated code
+    pub tests: Vec<OutputTest>,
+    /// A sketch not ready to generate (`draft: true`, or `skip: true`): it
+    /// stays in the DAG but regenerate leaves it out, and nothing may import it
+    pub draft: bool,
+}
+
+/// Selects prompts by frontmatter `tags:`
+#[derive(Debug, Clone, Default)]
+pub struct TagFilter {
+    /// If non-empty, a prompt must carry at least one of these
+    pub include: Vec<String>,
+    /// A prompt carrying any of these is left out
+    pub exclude: Vec<String>,
 }
 
 // ---------- Raw frontmatter (for YAML deserialization) ----------
@@ -41,11 +84,58 @@ struct RawFrontmatter {
     #[serde(default)]
     outputs: Vec<String>,
     #[serde(default)]
-    imports: Vec<String>,
+    imports: Vec<RawImport>,
+    #[serde(default)]
+    model: Option<RawModel>,
     #[serde(default)]
-    model: Option<ModelConfig>,
+    tier: Option<String>,
     #[serde(default)]
     language: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    module: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    mode: Option<FileMode>,
+    #[serde(default)]
+    tests: Vec<OutputTest>,
+    #[serde(default, alias = "skip")]
+    draft: bool,
+    #[serde(default)]
+    schema: Option<u32>,
+}
+
+/// Internal: an `imports:` entry — a path or glob, or `{ path, as }` to alias it
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawImport {
+    Path(String),
+    Aliased {
+        path: String,
+        #[serde(rename = "as")]
+        alias: String,
+    },
+}
+
+/// Internal: `model:` — a full override, a model name, or an ordered list of
+/// models to fall back through
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawModel {
+    Config(Box<ModelConfig>),
+    Chain(Vec<RawRoute>),
+    Name(String),
+}
+
+/// Internal: a `model:` list entry — a model name (provider inferred) or
+/// `{ provider, model }`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawRoute {
+    Route(ModelRoute),
+    Name(String),
 }
 
 // ---------- Implementation ----------
@@ -67,30 +157,178 @@ impl Prompt {
             .unwrap_or(path)
             .to_path_buf();
 
-        Self::parse(&raw, relative_path, config)
+        Self::from_content(&raw, relative_path, repo_root, config)
+    }
+
+    /// Parse prompt content for `path` (relative to repo root), such as an
+    /// unsaved editor buffer, loading its partials from `repo_root`
+    pub fn from_content(raw: &str, path: PathBuf, repo_root: &Path, config: &LitConfig) -> Result<Self> {
+        let mut prompt = Self::parse(raw, path, config)?;
+        prompt.load_includes(repo_root)?;
+        Ok(prompt)
+    }
+
+    /// Read every partial the body includes, following includes inside
+    /// partials. Missing partials and include cycles are errors.
+    fn load_includes(&mut self, repo_root: &Path) -> Result<()> {
+        let mut pending: Vec<(PathBuf, Vec<PathBuf>)> = extract_directives(&self.body, "@include(")
+            .into_iter()
+            .map(|reference| (normalize(Path::new(&reference)), Vec::new()))
+            .collect();
+        while let Some((include, chain)) = pending.pop() {
+            if chain.contains(&include) {
+                bail!(
+                    "Include cycle in {}: {} includes itself",
+                    self.path.display(),
+                    include.display()
+                );
+            }
+            if self.includes.contains_key(&include) {
+                continue;
+            }
+            if !is_contained_path(&include) {
+                bail!(
+                    "Invalid include '{}' in {}. Include paths are relative to the repo root",
+                    include.display(),
+                    self.path.display()
+                );
+            }
+            let content = std::fs::read_to_string(repo_root.join(&include)).with_context(|| {
+                format!(
+                    "Partial {} included by {} not found",
+                    include.display(),
+                    self.path.display()
+                )
+            })?;
+            let mut nested_chain = chain.clone();
+            nested_chain.push(include.clone());
+            for nested in extract_directives(&content, "@include(") {
+                pending.push((normalize(Path::new(&nested)), nested_chain.clone()));
+            }
+            self.includes.insert(include, content);
+        }
+        Ok(())
+    }
+
+    /// The body with every `@include()` replaced by its partial's content,
+    /// as sent to the model
+    pub fn expanded_body(&self) -> String {
+        expand_includes(&self.body, &self.includes, 0)
     }
 
     /// Parse a prompt from raw string content
     pub fn parse(raw: &str, path: PathBuf, config: &LitConfig) -> Result<Self> {
-        let (frontmatter_str, body) = split_frontmatter(raw).with_context(|| {
-            format!(
-                "Failed to parse frontmatter in {}",
-                path.display()
-            )
-        })?;
+        let path = normalize(&path);
+        check_size(raw, &path, config)?;
+
+        let parse_error = |part: &str| LitError::Parse { path: path.clone(), part: part.to_string() };
+        let (frontmatter_str, body) = split_frontmatter(raw).with_context(|| parse_error("frontmatter"))?;
+        let raw_fm: RawFrontmatter =
+            serde_yaml::from_str(&frontmatter_str).with_context(|| parse_error("YAML frontmatter"))?;
+
+        check_schema(raw_fm.schema.unwrap_or(FIRST_SCHEMA), &path.display().to_string())?;
+
+        let mut imports = Vec::new();
+        let mut aliases = BTreeMap::new();
+        for import in raw_fm.imports {
+            match import {
+                RawImport::Path(import_path) => imports.push(normalize(Path::new(&import_path))),
+                RawImport::Aliased { path: import_path, alias } => {
+                    let import_path = normalize(Path::new(&import_path));
+                    if is_glob_import(&import_path) {
+                        bail!(
+                            "Import pattern '{}' in {} cannot have an alias",
+                            import_path.display(),
+                            path.display()
+                        );
+                    }
+                    if alias.is_empty() || alias.contains(|c: char| c.is_whitespace() || c == ')') {
+                        bail!("Invalid import alias '{}' in {}", alias, path.display());
+                    }
+                    if aliases.insert(alias.clone(), import_path.clone()).is_some() {
+                        bail!("Duplicate import alias '{}' in {}", alias, path.display());
+                    }
+                    imports.push(import_path);
+                }
+            }
+        }
 
-        let raw_fm: RawFrontmatter = serde_yaml::from_str(&frontmatter_str).with_context(|| {
-            format!(
-                "Failed to parse YAML frontmatter in {}",
+        let mapping = config.project.mapping.as_str();
+        let module = match raw_fm.module {
+            Some(_) if mapping != "modular" => bail!(
+                "Prompt {} declares `module`, which requires mapping = \"modular\" in lit.toml",
                 path.display()
-            )
-        })?;
+            ),
+            Some(module) => Some(normalize(Path::new(&module))),
+            None if mapping == "modular" => {
+                Some(PathBuf::from(mirrored_path(&path, &config.project.prompt_dirs)))
+            }
+            None => None,
+        };
+
+        // Modes other than manifest can derive outputs from the prompt path
+        let mut outputs: Vec<PathBuf> = raw_fm.outputs.iter().map(|output| normalize(Path::new(output))).collect();
+        if outputs.is_empty() {
+            let language = config.resolve_language(&path, raw_fm.language.as_deref()).language;
+            match mapping {
+                "inferred" => {
+                    let Some(output) = config.infer_output(&path, raw_fm.language.as_deref()) else {
+                        bail!(
+                            "Prompt {} has no outputs and no [layout] entry in lit.toml matches its path.\n\
+                             Hint: Declare `outputs:` in the frontmatter or add its directory to [layout].",
+                            path.display()
+                        );
+                    };
+                    outputs.push(output);
+                }
+                "direct" => outputs.push(PathBuf::from(format!(
+                    "{}.{}",
+                    mirrored_path(&path, &config.project.prompt_dirs),
+                    language_extension(&language)
+                ))),
+                "modular" => {
+                    if let Some(ref module) = module {
+                        let name = module.file_name().and_then(|n| n.to_str()).unwrap_or("module");
+                        outputs.push(normalize(&module.join(module_entry_file(&language, name))));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for test in &raw_fm.tests {
+            test.validate()
+                .with_context(|| format!("Invalid test in the frontmatter of {}", path.display()))?;
+        }
+
+        // Without a `model:` of its own, a prompt takes its tier's model
+        let tier = config.resolve_tier(&path, raw_fm.tier.as_deref())?;
+        let (model, fallback_models) = match raw_fm.model {
+            None => (tier.map(|tier| chain_model(config, tier.route(config))), Vec::new()),
+            Some(RawModel::Config(model)) => (Some(*model), Vec::new()),
+            Some(RawModel::Name(name)) => (Some(chain_model(config, route(config, RawRoute::Name(name)))), Vec::new()),
+            Some(RawModel::Chain(chain)) => {
+                let mut routes = chain.into_iter().map(|r| route(config, r));
+                let Some(primary) = routes.next() else {
+                    bail!("Empty `model:` list in {}", path.display());
+                };
+                (Some(chain_model(config, primary)), routes.collect())
+            }
+        };
 
         let frontmatter = PromptFrontmatter {
-            outputs: raw_fm.outputs.into_iter().map(PathBuf::from).collect(),
-            imports: raw_fm.imports.into_iter().map(PathBuf::from).collect(),
-            model: raw_fm.model,
+            outputs,
+            imports,
+            aliases,
+            model,
+            fallback_models,
             language: raw_fm.language,
+            system: raw_fm.system,
+            module,
+            tags: raw_fm.tags,
+            mode: raw_fm.mode,
+            tests: raw_fm.tests,
+            draft: raw_fm.draft,
         };
 
         let prompt = Prompt {
@@ -98,6 +336,7 @@ impl Prompt {
             frontmatter,
             body: body.to_string(),
             raw: raw.to_string(),
+            includes: BTreeMap::new(),
         };
 
         prompt.validate(config)?;
@@ -105,6 +344,24 @@ impl Prompt {
         Ok(prompt)
     }
 
+    /// A warning when the prompt is above `[limits] warn_prompt_tokens` but
+    /// within the hard limits checked at parse time
+    pub fn size_warning(&self, config: &LitConfig) -> Option<String> {
+        let warn_at = config.limits.warn_prompt_tokens;
+        if warn_at == 0 {
+            return None;
+        }
+        let tokens = estimate_tokens(&self.raw);
+        (tokens > warn_at).then(|| {
+            format!(
+                "{} is ~{} tokens (warning threshold {}); every regeneration sends all of it",
+                self.path.display(),
+                tokens,
+                warn_at
+            )
+        })
+    }
+
     /// Validate the prompt against the project config
     fn validate(&self, config: &LitConfig) -> Result<()> {
         // In manifest mode, outputs are required
@@ -116,6 +373,55 @@ impl Prompt {
             );
         }
 
+        // In direct mode, a prompt generates exactly one same-named file
+        if config.project.mapping == "direct" {
+            let name = prompt_name(&self.path);
+            let [output] = self.frontmatter.outputs.as_slice() else {
+                bail!(
+                    "Prompt {} declares {} outputs. In 'direct' mode each prompt \
+                     generates exactly one file.",
+                    self.path.display(),
+                    self.frontmatter.outputs.len()
+                );
+            };
+            if output.file_stem().and_then(|s| s.to_str()) != Some(name) {
+                bail!(
+                    "Output {} of {} must be named after the prompt ('{}.<ext>') in 'direct' mode",
+                    output.display(),
+                    self.path.display(),
+                    name
+                );
+            }
+        }
+
+        // In modular mode, every output lives in the prompt's module directory
+        if let Some(ref module) = self.frontmatter.module
+            && let Some(outside) = self.frontmatter.outputs.iter().find(|o| !o.starts_with(module))
+        {
+            bail!(
+                "Output {} of {} is outside its module directory {}/",
+                outside.display(),
+                self.path.display(),
+                module.display()
+            );
+        }
+
+        // Outputs are joined onto the code directory and must stay inside it
+        let unsafe_path = self
+            .frontmatter
+            .outputs
+            .iter()
+            .chain(&self.frontmatter.module)
+            .find(|p| !is_contained_path(p));
+        if let Some(path) = unsafe_path {
+            bail!(
+                "Invalid output '{}' in {}. Outputs are relative to {}/ and may not be absolute or use `..`",
+                path.display(),
+                self.path.display(),
+                config.project.code_dir.display()
+            );
+        }
+
         // Validate import paths end with .prompt.md
         for import in &self.frontmatter.imports {
             if import.extension().and_then(|e| e.to_str()) != Some("md") {
@@ -127,33 +433,193 @@ impl Prompt {
             }
         }
 
-        // Check that @import() references in body match imports in frontmatter
-        let body_imports = extract_body_imports(&self.body);
-        for body_import in &body_imports {
-            let body_path = PathBuf::from(body_import);
-            if !self.frontmatter.imports.contains(&body_path) {
-                eprintln!(
-                    "Warning: @import({}) found in body of {} but not declared in frontmatter imports",
-                    body_import,
-                    self.path.display()
-                );
-            }
-        }
-
         Ok(())
     }
 
-    /// Extract @import() references from the body text
+    /// `@import()` references in the body that match no frontmatter import
+    /// or alias. Only the declared imports are passed as context.
+    pub fn undeclared_body_imports(&self) -> Vec<String> {
+        extract_body_imports(&self.body)
+            .into_iter()
+            .filter(|reference| {
+                !self.frontmatter.aliases.contains_key(reference)
+                    && !self.frontmatter.imports.iter().any(|import| {
+                        import.as_path() == Path::new(reference)
+                            || (is_glob_import(import)
+                                && glob_match(&import.to_string_lossy(), reference))
+                    })
+            })
+            .collect()
+    }
+
+    /// The raw file with its body replaced, keeping the frontmatter (and the
+    /// blank lines after it) byte-for-byte
+    pub fn with_body(&self, body: &str) -> String {
+        let header = &self.raw[..self.raw.len() - self.body.trim_start().len()];
+        format!("{}{}", header, body)
+    }
+
+    /// Extract @import() references from the body text, with aliases
+    /// resolved to their paths
     pub fn body_imports(&self) -> Vec<PathBuf> {
         extract_body_imports(&self.body)
             .into_iter()
-            .map(PathBuf::from)
+            .map(|reference| match self.frontmatter.aliases.get(&reference) {
+                Some(path) => path.clone(),
+                None => normalize(Path::new(&reference)),
+            })
             .collect()
     }
 }
 
+/// Resolve a `model:` list entry to its provider
+fn route(config: &LitConfig, raw: RawRoute) -> ModelRoute {
+    match raw {
+        RawRoute::Route(route) => route,
+        RawRoute::Name(model) => ModelRoute {
+            provider: config.provider_for_model(&model),
+            model,
+        },
+    }
+}
+
+/// The model override for the head of a `model:` chain, with the project's
+/// temperature and seed
+fn chain_model(config: &LitConfig, route: ModelRoute) -> ModelConfig {
+    ModelConfig {
+        provider: route.provider,
+        model: route.model,
+        temperature: config.model.temperature,
+        seed: config.model.seed,
+        api: None,
+        pricing: None,
+        context_window: None,
+        tiers: BTreeMap::new(),
+        limits: Default::default(),
+    }
+}
+
+/// A prompt's name: its file name without `.prompt.md`
+fn prompt_name(path: &Path) -> &str {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    file_name.strip_suffix(".prompt.md").unwrap_or(file_name)
+}
+
+/// The prompt path below its prompt directory, without `.prompt.md`
+/// (`prompts/models/user.prompt.md` → `models/user`)
+fn mirrored_path(path: &Path, prompt_dirs: &[PathBuf]) -> String {
+    let relative = to_slash(prompt_dirs.iter().find_map(|dir| path.strip_prefix(dir).ok()).unwrap_or(path));
+    relative.strip_suffix(".prompt.md").unwrap_or(&relative).to_string()
+}
+
+// ---------- Tag filtering ----------
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, prompt: &Prompt) -> bool {
+        let tags = &prompt.frontmatter.tags;
+        (self.include.is_empty() || self.include.iter().any(|t| tags.contains(t)))
+            && !self.exclude.iter().any(|t| tags.contains(t))
+    }
+}
+
+/// Leave draft prompts out of a regeneration set, returning how many were
+/// dropped. A prompt left in the set that imports a draft is an error: the
+/// draft has no generated code to give it as context.
+pub fn exclude_drafts(set: &mut Vec<PathBuf>, prompts: &HashMap<PathBuf, Prompt>) -> Result<usize> {
+    let before = set.len();
+    set.retain(|p| !prompts[p].frontmatter.draft);
+    let imports: Vec<(PathBuf, PathBuf)> = set
+        .iter()
+        .flat_map(|p| {
+            prompts[p]
+                .frontmatter
+                .imports
+                .iter()
+                .filter(|import| prompts.get(*import).is_some_and(|i| i.frontmatter.draft))
+                .map(move |import| (p.clone(), import.clone()))
+        })
+        .collect();
+    if !imports.is_empty() {
+        return Err(LitError::DraftImports { imports }.into());
+    }
+    Ok(before - set.len())
+}
+
+// ---------- Import resolution ----------
+
+/// Whether an import entry is a glob pattern rather than a single path
+pub fn is_glob_import(import: &Path) -> bool {
+    import.to_string_lossy().contains(['*', '?'])
+}
+
+/// Expand glob imports against the full prompt set.
+///
+/// Matches are added in sorted path order at the pattern's position, so the
+/// result (and every cache key derived from it) is deterministic. A prompt
+/// never imports itself, and a pattern that matches nothing is an error.
+pub fn resolve_imports(prompts: &mut [Prompt]) -> Result<()> {
+    let mut all: Vec<PathBuf> = prompts.iter().map(|p| p.path.clone()).collect();
+    all.sort();
+
+    for prompt in prompts.iter_mut() {
+        if !prompt.frontmatter.imports.iter().any(|i| is_glob_import(i)) {
+            continue;
+        }
+        let mut resolved: Vec<PathBuf> = Vec::new();
+        for import in &prompt.frontmatter.imports {
+            if !is_glob_import(import) {
+                if !resolved.contains(import) {
+                    resolved.push(import.clone());
+                }
+                continue;
+            }
+            let pattern = to_slash(import);
+            let matches: Vec<&PathBuf> = all
+                .iter()
+                .filter(|p| **p != prompt.path)
+                .filter(|p| glob_match(&pattern, &to_slash(p)))
+                .collect();
+            if matches.is_empty() {
+                bail!(
+                    "Import pattern '{}' in {} matches no prompts",
+                    pattern,
+                    prompt.path.display()
+                );
+            }
+            for m in matches {
+                if !resolved.contains(m) {
+                    resolved.push(m.clone());
+                }
+            }
+        }
+        prompt.frontmatter.imports = resolved;
+    }
+    Ok(())
+}
+
 // ---------- Discovery ----------
 
+/// Discover the .prompt.md files in every `[project] prompt_dirs`
+/// directory under `root`, leaving out those matching `[project] exclude`;
+/// directories that don't exist are skipped
+pub fn discover_project_prompts(config: &LitConfig, root: &Path) -> Result<Vec<PathBuf>> {
+    let mut prompts = Vec::new();
+    for dir in &config.project.prompt_dirs {
+        prompts.extend(
+            discover_prompts(&root.join(dir))?
+                .into_iter()
+                .filter(|p| !config.project.is_excluded(p.strip_prefix(root).unwrap_or(p))),
+        );
+    }
+    prompts.sort();
+    prompts.dedup();
+    Ok(prompts)
+}
+
 /// Discover all .prompt.md files under a directory
 pub fn discover_prompts(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut prompts = Vec::new();
@@ -191,6 +657,34 @@ pub fn is_prompt_file(path: &Path) -> bool {
 
 // ---------- Frontmatter parsing ----------
 
+/// Reject prompt files over the `[limits]` size or token limits
+fn check_size(raw: &str, path: &Path, config: &LitConfig) -> Result<()> {
+    let limits = &config.limits;
+    let bytes = raw.len() as u64;
+    if limits.max_prompt_bytes > 0 && bytes > limits.max_prompt_bytes {
+        bail!(
+            "Prompt {} is {} bytes, over the {} byte limit.\n\
+             Hint: Check for accidentally pasted logs or data, or raise `[limits] max_prompt_bytes` in lit.toml.",
+            path.display(),
+            bytes,
+            limits.max_prompt_bytes
+        );
+    }
+    if limits.max_prompt_tokens > 0 {
+        let tokens = estimate_tokens(raw);
+        if tokens > limits.max_prompt_tokens {
+            bail!(
+                "Prompt {} is ~{} tokens, over the {} token limit.\n\
+                 Hint: Check for accidentally pasted logs or data, or raise `[limits] max_prompt_tokens` in lit.toml.",
+                path.display(),
+                tokens,
+                limits.max_prompt_tokens
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Split a prompt file into frontmatter and body
 ///
 /// Frontmatter is delimited by `---` on its own line at the start of the file.
@@ -249,21 +743,56 @@ fn split_frontmatter(content: &str) -> Result<(String, String)> {
 
 /// Extract @import(...) references from prompt body text
 fn extract_body_imports(body: &str) -> Vec<String> {
-    let mut imports = Vec::new();
-    let mut remaining = body;
+    extract_directives(body, "@import(")
+}
+
+/// Extract the arguments of `directive` (e.g. `@include(`) from text
+fn extract_directives(text: &str, directive: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut remaining = text;
 
-    while let Some(start) = remaining.find("@import(") {
-        let after_import = &remaining[start + 8..];
-        if let Some(end) = after_import.find(')') {
-            let path = after_import[..end].trim().to_string();
+    while let Some(start) = remaining.find(directive) {
+        let after_directive = &remaining[start + directive.len()..];
+        if let Some(end) = after_directive.find(')') {
+            let path = after_directive[..end].trim().to_string();
             if !path.is_empty() {
-                imports.push(path);
+                references.push(path);
             }
         }
-        remaining = &remaining[start + 8..];
+        remaining = after_directive;
     }
 
-    imports
+    references
+}
+
+/// Maximum nesting of partials, as a backstop to the cycle check in
+/// [`Prompt::from_file`]
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Replace `@include(path)` in text with the partial's content (trailing
+/// newlines trimmed). Unknown includes are left as written.
+fn expand_includes(text: &str, includes: &BTreeMap<PathBuf, String>, depth: usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut remaining = text;
+
+    while let Some(start) = remaining.find("@include(") {
+        let after_directive = &remaining[start + 9..];
+        let Some(end) = after_directive.find(')') else {
+            break;
+        };
+        let reference = after_directive[..end].trim();
+        expanded.push_str(&remaining[..start]);
+        match includes.get(Path::new(reference)) {
+            Some(content) if depth < MAX_INCLUDE_DEPTH => {
+                expanded.push_str(expand_includes(content, includes, depth + 1).trim_end_matches('\n'));
+            }
+            _ => expanded.push_str(&remaining[start..start + 9 + end + 1]),
+        }
+        remaining = &after_directive[end + 1..];
+    }
+
+    expanded.push_str(remaining);
+    expanded
 }
 
 // ---------- Tests ----------
@@ -271,6 +800,7 @@ fn extract_body_imports(body: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::config::ModelTier;
 
     fn test_config() -> LitConfig {
         LitConfig::from_str(
@@ -394,6 +924,16 @@ imports: []
         );
     }
 
+    #[test]
+    fn test_parse_prompt_unsafe_outputs_fail() {
+        let config = test_config();
+        for output in ["../../etc/passwd", "/etc/passwd", "src/../../x.py"] {
+            let raw = format!("---\noutputs:\n  - {}\n---\n\n# Prompt\n", output);
+            let err = Prompt::parse(&raw, PathBuf::from("prompts/x.prompt.md"), &config).unwrap_err();
+            assert!(err.to_string().contains("Invalid output"), "{}: {}", output, err);
+        }
+    }
+
     #[test]
     fn test_parse_prompt_no_outputs_in_direct_mode_ok() {
         let raw = r#"---
@@ -421,8 +961,57 @@ temperature = 0.0
         )
         .unwrap();
 
-        let prompt = Prompt::parse(raw, PathBuf::from("prompts/foo.prompt.md"), &config);
-        assert!(prompt.is_ok());
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/foo.prompt.md"), &config).unwrap();
+        assert_eq!(prompt.frontmatter.outputs, vec![PathBuf::from("foo.py")]);
+
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).unwrap();
+        assert_eq!(prompt.frontmatter.outputs, vec![PathBuf::from("models/user.py")]);
+
+        // Declared outputs must still be a single file named after the prompt
+        let raw = "---\noutputs: [models/user.py, models/extra.py]\n---\n";
+        assert!(Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).is_err());
+        let raw = "---\noutputs: [src/account.py]\n---\n";
+        assert!(Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).is_err());
+    }
+
+    #[test]
+    fn test_modular_outputs() {
+        let config = LitConfig::from_str(
+            r#"
+[project]
+name = "test"
+version = "0.1.0"
+mapping = "modular"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+"#,
+        )
+        .unwrap();
+
+        let raw = "---\n---\n";
+        let user = Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).unwrap();
+        assert_eq!(user.frontmatter.module, Some(PathBuf::from("models/user")));
+        assert_eq!(user.frontmatter.outputs, vec![PathBuf::from("models/user/__init__.py")]);
+
+        let raw = "---\nmodule: pkg/users\nlanguage: rust\n---\n";
+        let users = Prompt::parse(raw, PathBuf::from("prompts/users.prompt.md"), &config).unwrap();
+        assert_eq!(users.frontmatter.outputs, vec![PathBuf::from("pkg/users/mod.rs")]);
+
+        // Outputs must live inside the module directory
+        let raw = "---\noutputs: [elsewhere/user.py]\n---\n";
+        let err = Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).unwrap_err();
+        assert!(err.to_string().contains("module"), "got: {}", err);
+
+        // `module:` is only meaningful in modular mode
+        let raw = "---\nmodule: pkg\noutputs: [a.py]\n---\n";
+        assert!(Prompt::parse(raw, PathBuf::from("prompts/a.prompt.md"), &test_config()).is_err());
     }
 
     #[test]
@@ -463,6 +1052,25 @@ language: typescript
         assert_eq!(prompt.frontmatter.language.as_deref(), Some("typescript"));
     }
 
+    #[test]
+    fn test_parse_prompt_with_system_instructions() {
+        let raw = r#"---
+outputs:
+  - src/legacy.py
+system: Target Python 3.8 — no match statements.
+---
+
+# Legacy module
+"#;
+        let config = test_config();
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/legacy.prompt.md"), &config).unwrap();
+
+        assert_eq!(
+            prompt.frontmatter.system.as_deref(),
+            Some("Target Python 3.8 — no match statements.")
+        );
+    }
+
     #[test]
     fn test_missing_frontmatter() {
         let raw = "# No frontmatter here\n\nJust a regular markdown file.\n";
@@ -473,6 +1081,10 @@ language: typescript
             "Expected frontmatter error, got: {}",
             err
         );
+        assert!(matches!(
+            LitError::of(&err),
+            Some(LitError::Parse { path, .. }) if path == &PathBuf::from("prompts/bad.prompt.md")
+        ));
     }
 
     #[test]
@@ -499,6 +1111,42 @@ Also use @import(prompts/config/database.prompt.md) for DB access.
         assert_eq!(imports[1], "prompts/config/database.prompt.md");
     }
 
+    #[test]
+    fn test_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("prompts/_partials")).unwrap();
+        std::fs::write(
+            root.join("prompts/_partials/errors.md"),
+            "Raise ValueError on bad input.\n@include(prompts/_partials/logging.md)\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("prompts/_partials/logging.md"), "Log with the `logging` module.\n").unwrap();
+        std::fs::write(
+            root.join("prompts/app.prompt.md"),
+            "---\noutputs:\n  - app.py\n---\n\n# App\n\n@include(prompts/_partials/errors.md)\n\nDone.\n",
+        )
+        .unwrap();
+
+        let config = test_config();
+        let prompt = Prompt::from_file(Path::new("prompts/app.prompt.md"), root, &config).unwrap();
+        assert_eq!(prompt.includes.len(), 2);
+        assert_eq!(
+            prompt.expanded_body(),
+            "\n# App\n\nRaise ValueError on bad input.\nLog with the `logging` module.\n\nDone.\n"
+        );
+        // The written body is untouched
+        assert!(prompt.body.contains("@include(prompts/_partials/errors.md)"));
+
+        std::fs::write(root.join("prompts/_partials/logging.md"), "@include(prompts/_partials/errors.md)\n").unwrap();
+        let err = Prompt::from_file(Path::new("prompts/app.prompt.md"), root, &config).unwrap_err();
+        assert!(err.to_string().contains("Include cycle"), "{}", err);
+
+        std::fs::remove_file(root.join("prompts/_partials/logging.md")).unwrap();
+        let err = Prompt::from_file(Path::new("prompts/app.prompt.md"), root, &config).unwrap_err();
+        assert!(err.to_string().contains("not found"), "{}", err);
+    }
+
     #[test]
     fn test_extract_body_imports_none() {
         let body = "# Just a heading\n\nNo imports here.\n";
@@ -506,6 +1154,237 @@ Also use @import(prompts/config/database.prompt.md) for DB access.
         assert!(imports.is_empty());
     }
 
+    #[test]
+    fn test_import_aliases() {
+        let config = test_config();
+        let raw = r#"---
+outputs:
+  - src/api.py
+imports:
+  - prompts/models/base.prompt.md
+  - path: prompts/models/user.prompt.md
+    as: models.user
+---
+
+Expose @import(models.user) over HTTP.
+"#;
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/api.prompt.md"), &config).unwrap();
+        assert_eq!(
+            prompt.frontmatter.imports,
+            vec![
+                PathBuf::from("prompts/models/base.prompt.md"),
+                PathBuf::from("prompts/models/user.prompt.md"),
+            ]
+        );
+        assert_eq!(
+            prompt.frontmatter.aliases.get("models.user"),
+            Some(&PathBuf::from("prompts/models/user.prompt.md"))
+        );
+        assert_eq!(prompt.body_imports(), vec![PathBuf::from("prompts/models/user.prompt.md")]);
+
+        let dup = r#"---
+outputs: [src/a.py]
+imports:
+  - { path: prompts/a.prompt.md, as: x }
+  - { path: prompts/b.prompt.md, as: x }
+---
+"#;
+        let err = Prompt::parse(dup, PathBuf::from("prompts/c.prompt.md"), &config).unwrap_err();
+        assert!(err.to_string().contains("Duplicate import alias"));
+
+        let glob = "---\noutputs: [src/a.py]\nimports:\n  - { path: \"prompts/*.prompt.md\", as: all }\n---\n";
+        assert!(Prompt::parse(glob, PathBuf::from("prompts/c.prompt.md"), &config).is_err());
+    }
+
+    #[test]
+    fn test_resolve_glob_imports() {
+        let config = test_config();
+        let prompt = |path: &str, imports: &str| {
+            let raw = format!("---\noutputs: [{}.py]\nimports: {}\n---\n", path, imports);
+            Prompt::parse(&raw, PathBuf::from(path), &config).unwrap()
+        };
+        let mut prompts = vec![
+            prompt("prompts/models/user.prompt.md", "[]"),
+            prompt("prompts/models/base.prompt.md", "[]"),
+            prompt("prompts/models/order.prompt.md", "[\"prompts/models/*.prompt.md\"]"),
+            prompt(
+                "prompts/api.prompt.md",
+                "[prompts/models/user.prompt.md, \"prompts/models/*.prompt.md\"]",
+            ),
+        ];
+        resolve_imports(&mut prompts).unwrap();
+
+        // Sorted, deduplicated, never self-referential
+        assert_eq!(
+            prompts[2].frontmatter.imports,
+            vec![
+                PathBuf::from("prompts/models/base.prompt.md"),
+                PathBuf::from("prompts/models/user.prompt.md"),
+            ]
+        );
+        assert_eq!(
+            prompts[3].frontmatter.imports,
+            vec![
+                PathBuf::from("prompts/models/user.prompt.md"),
+                PathBuf::from("prompts/models/base.prompt.md"),
+                PathBuf::from("prompts/models/order.prompt.md"),
+            ]
+        );
+
+        let mut unmatched = vec![prompt("prompts/api.prompt.md", "[\"prompts/none/*.prompt.md\"]")];
+        let err = resolve_imports(&mut unmatched).unwrap_err();
+        assert!(err.to_string().contains("matches no prompts"));
+    }
+
+    #[test]
+    fn test_inferred_outputs_from_layout() {
+        let config = LitConfig::from_str(
+            r#"
+[project]
+name = "test"
+version = "0.1.0"
+mapping = "inferred"
+
+[language]
+default = "python"
+version = "3.12"
+
+[model]
+provider = "anthropic"
+model = "claude-sonnet-4-5-20250929"
+temperature = 0.0
+
+[layout]
+"prompts" = "src/"
+"prompts/api" = "src/routes/{name}_routes.{ext}"
+"#,
+        )
+        .unwrap();
+
+        let raw = "---\nimports: []\n---\n\n# User\n";
+        let user = Prompt::parse(raw, PathBuf::from("prompts/models/user.prompt.md"), &config).unwrap();
+        assert_eq!(user.frontmatter.outputs, vec![PathBuf::from("src/models/user.py")]);
+
+        // The most specific directory wins, and the language override picks the extension
+        let raw = "---\nlanguage: typescript\n---\n\n# Items\n";
+        let items = Prompt::parse(raw, PathBuf::from("prompts/api/items.prompt.md"), &config).unwrap();
+        assert_eq!(items.frontmatter.outputs, vec![PathBuf::from("src/routes/items_routes.ts")]);
+
+        // Declared outputs are kept as-is
+        let raw = "---\noutputs: [app.py]\n---\n";
+        let app = Prompt::parse(raw, PathBuf::from("prompts/app.prompt.md"), &config).unwrap();
+        assert_eq!(app.frontmatter.outputs, vec![PathBuf::from("app.py")]);
+
+        let err = Prompt::parse("---\n---\n", PathBuf::from("other/x.prompt.md"), &config).unwrap_err();
+        assert!(err.to_string().contains("no [layout] entry"));
+    }
+
+    #[test]
+    fn test_with_body_keeps_frontmatter() {
+        let raw = "---\n# owner: api team\noutputs: [src/user.py]\n---\n\n# User\nOld body\n";
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/user.prompt.md"), &test_config()).unwrap();
+        assert_eq!(
+            prompt.with_body("# User\nNew body\n"),
+            "---\n# owner: api team\noutputs: [src/user.py]\n---\n\n# User\nNew body\n"
+        );
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let config = test_config();
+        let fixture = Prompt::parse(
+            "---\noutputs: [tests/fixtures.py]\ntags: [testdata, slow]\n---\n",
+            PathBuf::from("prompts/fixtures.prompt.md"),
+            &config,
+        )
+        .unwrap();
+        let model = Prompt::parse(
+            "---\noutputs: [src/user.py]\n---\n",
+            PathBuf::from("prompts/user.prompt.md"),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(fixture.frontmatter.tags, vec!["testdata", "slow"]);
+
+        let all = TagFilter::default();
+        assert!(all.is_empty() && all.matches(&fixture) && all.matches(&model));
+
+        let only = TagFilter { include: vec!["testdata".into()], exclude: vec![] };
+        assert!(only.matches(&fixture) && !only.matches(&model));
+
+        let production = TagFilter { include: vec![], exclude: vec!["testdata".into()] };
+        assert!(!production.matches(&fixture) && production.matches(&model));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_paths_normalized() {
+        let raw = "---\noutputs: ['src\\models\\user.py']\nimports:\n  - prompts\\base.prompt.md\n  - path: .\\prompts\\schemas\\user.prompt.md\n    as: schema\n---\n";
+        let prompt = Prompt::parse(raw, PathBuf::from(r"prompts\models\user.prompt.md"), &test_config()).unwrap();
+        assert_eq!(prompt.path, PathBuf::from("prompts/models/user.prompt.md"));
+        assert_eq!(prompt.frontmatter.outputs, vec![PathBuf::from("src/models/user.py")]);
+        assert_eq!(
+            prompt.frontmatter.imports,
+            vec![PathBuf::from("prompts/base.prompt.md"), PathBuf::from("prompts/schemas/user.prompt.md")]
+        );
+        assert_eq!(prompt.frontmatter.aliases["schema"], PathBuf::from("prompts/schemas/user.prompt.md"));
+    }
+
+    #[test]
+    fn test_exclude_drafts() {
+        let config = test_config();
+        let parse = |raw: &str, path: &str| Prompt::parse(raw, PathBuf::from(path), &config).unwrap();
+        let base = parse("---\noutputs: [src/base.py]\n---\n", "prompts/base.prompt.md");
+        let draft = parse("---\noutputs: [src/user.py]\ndraft: true\n---\n", "prompts/user.prompt.md");
+        let skipped = parse("---\noutputs: [src/order.py]\nskip: true\n---\n", "prompts/order.prompt.md");
+        let api = parse(
+            "---\noutputs: [src/api.py]\nimports: [prompts/user.prompt.md]\n---\n",
+            "prompts/api.prompt.md",
+        );
+        assert!(draft.frontmatter.draft && skipped.frontmatter.draft && !base.frontmatter.draft);
+        let prompts: HashMap<PathBuf, Prompt> =
+            [base, draft, skipped, api].into_iter().map(|p| (p.path.clone(), p)).collect();
+
+        let mut set = vec![
+            PathBuf::from("prompts/base.prompt.md"),
+            PathBuf::from("prompts/user.prompt.md"),
+            PathBuf::from("prompts/order.prompt.md"),
+        ];
+        assert_eq!(exclude_drafts(&mut set, &prompts).unwrap(), 2);
+        assert_eq!(set, vec![PathBuf::from("prompts/base.prompt.md")]);
+
+        // A dependent of a draft can't be generated
+        let mut set = vec![PathBuf::from("prompts/user.prompt.md"), PathBuf::from("prompts/api.prompt.md")];
+        let err = exclude_drafts(&mut set, &prompts).unwrap_err();
+        assert!(err.to_string().contains("prompts/api.prompt.md imports prompts/user.prompt.md (draft)"), "{}", err);
+        assert!(matches!(LitError::of(&err), Some(LitError::DraftImports { imports }) if imports.len() == 1));
+    }
+
+    #[test]
+    fn test_prompt_size_limits() {
+        let mut config = test_config();
+        config.limits.max_prompt_bytes = 200;
+        config.limits.max_prompt_tokens = 60;
+        config.limits.warn_prompt_tokens = 20;
+        let path = PathBuf::from("prompts/user.prompt.md");
+
+        let small = Prompt::parse("---\noutputs: [src/user.py]\n---\nA user.\n", path.clone(), &config).unwrap();
+        assert!(small.size_warning(&config).is_none());
+
+        let raw = format!("---\noutputs: [src/user.py]\n---\n{}\n", "user ".repeat(30));
+        let large = Prompt::parse(&raw, path.clone(), &config).unwrap();
+        let warning = large.size_warning(&config).unwrap();
+        assert!(warning.contains("prompts/user.prompt.md"), "got: {}", warning);
+
+        let raw = format!("---\noutputs: [src/user.py]\n---\n{}\n", "user ".repeat(80));
+        let err = Prompt::parse(&raw, path.clone(), &config).unwrap_err();
+        assert!(err.to_string().contains("byte limit"), "got: {}", err);
+
+        config.limits.max_prompt_bytes = 0;
+        let err = Prompt::parse(&raw, path, &config).unwrap_err();
+        assert!(err.to_string().contains("token limit"), "got: {}", err);
+    }
+
     #[test]
     fn test_is_prompt_file() {
         assert!(is_prompt_file(Path::new("prompts/models/user.prompt.md")));
@@ -515,6 +1394,22 @@ Also use @import(prompts/config/database.prompt.md) for DB access.
         assert!(!is_prompt_file(Path::new("prompt.md"))); // missing the .prompt. part
     }
 
+    #[test]
+    fn test_discover_project_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in ["prompts/a.prompt.md", "prompts/wip/b.prompt.md", "specs/c.prompt.md", "specs/notes.md"] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let mut config = test_config();
+        config.project.prompt_dirs = vec![PathBuf::from("prompts"), PathBuf::from("specs")];
+        config.project.exclude = vec!["prompts/wip".to_string()];
+
+        let found = discover_project_prompts(&config, root).unwrap();
+        assert_eq!(found, vec![root.join("prompts/a.prompt.md"), root.join("specs/c.prompt.md")]);
+    }
+
     #[test]
     fn test_split_frontmatter() {
         let content = "---\nkey: value\n---\n\n# Body\n";
@@ -522,4 +1417,73 @@ Also use @import(prompts/config/database.prompt.md) for DB access.
         assert_eq!(fm.trim(), "key: value");
         assert!(body.contains("# Body"));
     }
+
+    #[test]
+    fn test_model_chain_frontmatter() {
+        let config = test_config();
+        let raw = "---\noutputs:\n  - src/a.py\nmodel: [claude-opus-4-6, gpt-4o, { provider: openai, model: ft-acme }]\n---\nBody\n";
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/a.prompt.md"), &config).unwrap();
+        let model = prompt.frontmatter.model.unwrap();
+        assert_eq!((model.provider.as_str(), model.model.as_str()), ("anthropic", "claude-opus-4-6"));
+        assert_eq!(model.temperature, config.model.temperature);
+        let fallbacks: Vec<(&str, &str)> = prompt
+            .frontmatter
+            .fallback_models
+            .iter()
+            .map(|r| (r.provider.as_str(), r.model.as_str()))
+            .collect();
+        assert_eq!(fallbacks, vec![("openai", "gpt-4o"), ("openai", "ft-acme")]);
+
+        let raw = "---\noutputs:\n  - src/a.py\nmodel: gpt-4o\n---\nBody\n";
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/a.prompt.md"), &config).unwrap();
+        assert_eq!(prompt.frontmatter.model.unwrap().provider, "openai");
+        assert!(prompt.frontmatter.fallback_models.is_empty());
+
+        let raw = "---\noutputs:\n  - src/a.py\nmodel: []\n---\nBody\n";
+        assert!(Prompt::parse(raw, PathBuf::from("prompts/a.prompt.md"), &config).is_err());
+    }
+
+    #[test]
+    fn test_output_mode_frontmatter() {
+        let config = test_config();
+        let raw = "---\noutputs:\n  - bin/deploy.sh\nmode: 0755\n---\nBody\n";
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/deploy.prompt.md"), &config).unwrap();
+        assert_eq!(prompt.frontmatter.mode, Some(FileMode(0o755)));
+
+        let raw = "---\noutputs:\n  - bin/deploy.sh\nmode: 0o700\n---\nBody\n";
+        let prompt = Prompt::parse(raw, PathBuf::from("prompts/deploy.prompt.md"), &config).unwrap();
+        assert_eq!(prompt.frontmatter.mode, Some(FileMode(0o700)));
+
+        let raw = "---\noutputs:\n  - bin/deploy.sh\nmode: 755\n---\nBody\n";
+        assert!(Prompt::parse(raw, PathBuf::from("prompts/deploy.prompt.md"), &config).is_err());
+    }
+
+    #[test]
+    fn test_model_tier_frontmatter() {
+        let mut config = test_config();
+        config.model.tiers.insert(
+            "scaffold".to_string(),
+            ModelTier {
+                model: "claude-haiku-4-5".to_string(),
+                provider: None,
+                paths: vec!["prompts/**/__init__.prompt.md".to_string()],
+            },
+        );
+        let model = |raw: &str, path: &str| {
+            Prompt::parse(raw, PathBuf::from(path), &config).map(|p| p.frontmatter.model.map(|m| m.model))
+        };
+        let plain = "---\noutputs:\n  - src/a.py\n---\nBody\n";
+        assert_eq!(model(plain, "prompts/models/__init__.prompt.md").unwrap().as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(model(plain, "prompts/models/user.prompt.md").unwrap(), None);
+
+        let tagged = "---\noutputs:\n  - src/a.py\ntier: scaffold\n---\nBody\n";
+        assert_eq!(model(tagged, "prompts/models/user.prompt.md").unwrap().as_deref(), Some("claude-haiku-4-5"));
+
+        // `model:` beats the tier
+        let both = "---\noutputs:\n  - src/a.py\ntier: scaffold\nmodel: gpt-4o\n---\nBody\n";
+        assert_eq!(model(both, "prompts/models/user.prompt.md").unwrap().as_deref(), Some("gpt-4o"));
+
+        let unknown = "---\noutputs:\n  - src/a.py\ntier: huge\n---\nBody\n";
+        assert!(model(unknown, "prompts/models/user.prompt.md").is_err());
+    }
 }
@@ -1,10 +1,39 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use git2::build::{CheckoutBuilder, RepoBuilder};
 use git2::{
-    DiffOptions, IndexAddOption, Repository, Signature, StatusOptions, StatusShow,
+    DiffOptions, FetchOptions, IndexAddOption, ProxyOptions, PushOptions, Repository, Signature, StatusOptions,
+    StatusShow,
 };
 
+use crate::core::credentials;
+use crate::core::config::{DEFAULT_CODE_DIR, DEFAULT_PROMPT_DIR, ProjectConfig};
+use crate::core::paths::to_slash;
+use crate::core::prompt::is_prompt_file;
+
+/// Entries every lit project's .gitignore must contain (local-only state
+/// under .lit/ and review files that should never be committed)
+pub const REQUIRED_GITIGNORE_ENTRIES: &[&str] = &[
+    ".lit/cache/",
+    ".lit/interfaces/",
+    ".lit/backups/",
+    ".lit/logs/",
+    ".lit/reports/",
+    ".lit/sessions/",
+    ".lit/transcripts/",
+    ".lit/preview/",
+    ".lit/eval/",
+    ".lit/stash.json",
+    ".lit/last-run.json",
+    ".lit/lock",
+    ".lit/patches/index.lock",
+    ".lit/patches/index.json.tmp",
+    "*.prompt.md.suggested",
+];
+
 /// Information about a single commit
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -16,6 +45,57 @@ pub struct CommitInfo {
     pub timestamp: i64,
 }
 
+/// A commit that changed one file, with the file's content on either side
+#[derive(Debug, Clone)]
+pub struct FileRevision {
+    pub commit: CommitInfo,
+    /// First parent's hash, or None for a root commit
+    pub parent: Option<String>,
+    /// Content before the commit (None if the commit added the file)
+    pub before: Option<String>,
+    /// Content after the commit (None if the commit deleted the file)
+    pub after: Option<String>,
+}
+
+/// What a path names in a commit's tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeObject {
+    /// A file's raw content
+    Blob(Vec<u8>),
+    /// A directory's entry names, sorted, directories with a trailing `/`
+    Tree(Vec<String>),
+}
+
+/// A configured git remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+    /// Push URL, when one is set apart from `url`
+    pub push_url: Option<String>,
+}
+
+/// The current branch and where it is pushed to and pulled from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    pub branch: String,
+    pub remote: String,
+    /// Branch name on the remote
+    pub remote_branch: String,
+    /// Whether git config records it; otherwise pushing or pulling sets it
+    pub tracked: bool,
+}
+
+/// What [`LitRepo::pull`] did to the current branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    /// A merge stopped on these conflicted files
+    Conflicts(Vec<PathBuf>),
+}
+
 /// Status of the working tree
 #[derive(Debug)]
 pub struct RepoStatus {
@@ -52,6 +132,10 @@ pub struct FileDiffStat {
 pub struct LitRepo {
     repo: Repository,
     root: PathBuf,
+    /// Generated code directory (`[project] code_dir`), relative to root
+    code_dir: String,
+    /// Prompt directories (`[project] prompt_dirs`), relative to root
+    prompt_dirs: Vec<String>,
 }
 
 impl LitRepo {
@@ -65,7 +149,12 @@ impl LitRepo {
             .canonicalize()
             .unwrap_or_else(|_| path.to_path_buf());
 
-        Ok(Self { root, repo })
+        Ok(Self {
+            root,
+            repo,
+            code_dir: DEFAULT_CODE_DIR.to_string(),
+            prompt_dirs: vec![DEFAULT_PROMPT_DIR.to_string()],
+        })
     }
 
     /// Open an existing repository at (or above) the given path.
@@ -86,7 +175,31 @@ impl LitRepo {
             .canonicalize()
             .unwrap_or_else(|_| workdir.to_path_buf());
 
-        Ok(Self { root, repo })
+        Ok(Self {
+            root,
+            repo,
+            code_dir: DEFAULT_CODE_DIR.to_string(),
+            prompt_dirs: vec![DEFAULT_PROMPT_DIR.to_string()],
+        })
+    }
+
+    /// Use the project's code and prompt directories (`[project] code_dir`
+    /// and `prompt_dirs`) instead of code.lock/ and prompts/
+    pub fn with_project_dirs(mut self, project: &ProjectConfig) -> Self {
+        let dir = |path: &Path| to_slash(path).trim_end_matches('/').to_string();
+        self.code_dir = dir(&project.code_dir);
+        self.prompt_dirs = project.prompt_dirs.iter().map(|p| dir(p)).collect();
+        self
+    }
+
+    /// Whether `path` (relative to root) is inside a prompt directory
+    pub fn in_prompt_dir(&self, path: &Path) -> bool {
+        self.prompt_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Pathspecs for the prompt directories, e.g. `prompts/`
+    fn prompt_pathspecs(&self) -> Vec<String> {
+        self.prompt_dirs.iter().map(|dir| format!("{}/", dir)).collect()
     }
 
     /// Get the repo root path.
@@ -99,21 +212,26 @@ impl LitRepo {
 
     /// Stage all relevant lit files for commit.
     ///
-    /// Stages: prompts/**, code.lock/**, lit.toml, .lit/generations/**, .lit/patches/**
+    /// Stages: the prompt directories (prompts/**), the code directory (code.lock/**), lit.toml, .lit/generations/**, .lit/patches/**,
+    /// .lit/pricing.toml, .lit/manifest.json, .lit/hooks/**
     /// Respects .gitignore.
     pub fn stage_all(&self) -> Result<()> {
         let mut index = self.repo.index().context("Failed to open git index")?;
 
         // Use add_all with pathspecs to add relevant paths
         // This respects .gitignore
-        let pathspecs = [
-            "prompts",
-            "code.lock",
+        let mut pathspecs: Vec<&str> = self.prompt_dirs.iter().map(String::as_str).collect();
+        pathspecs.extend([
+            self.code_dir.as_str(),
             "lit.toml",
             ".lit/generations",
             ".lit/patches",
+            ".lit/pricing.toml",
+            ".lit/manifest.json",
+            ".lit/freeze.json",
+            ".lit/hooks",
             ".gitignore",
-        ];
+        ]);
 
         index
             .add_all(pathspecs.iter(), IndexAddOption::DEFAULT, None)
@@ -140,9 +258,25 @@ impl LitRepo {
         Ok(())
     }
 
+    /// Stage a rename: drop `from` from the index and add `to`.
+    pub fn stage_rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut index = self.repo.index().context("Failed to open git index")?;
+        if index.get_path(from, 0).is_some() {
+            index
+                .remove_path(from)
+                .with_context(|| format!("Failed to unstage {}", from.display()))?;
+        }
+        index
+            .add_path(to)
+            .with_context(|| format!("Failed to stage {}", to.display()))?;
+        index.write().context("Failed to write git index")?;
+        Ok(())
+    }
+
     // ---------- Commit ----------
 
-    /// Create a commit with all staged changes.
+    /// Create a commit with all staged changes. Concludes a merge that
+    /// [`pull`](Self::pull) stopped on with conflicts.
     pub fn commit(&self, message: &str) -> Result<String> {
         let mut index = self.repo.index().context("Failed to open git index")?;
         let tree_oid = index.write_tree().context("Failed to write tree")?;
@@ -158,9 +292,16 @@ impl LitRepo {
             let parent = head
                 .peel_to_commit()
                 .context("Failed to find HEAD commit")?;
-            self.repo
-                .commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent])
-                .context("Failed to create commit")?
+            let merged = self.repo.revparse_single("MERGE_HEAD").and_then(|o| o.peel_to_commit()).ok();
+            let parents: Vec<&git2::Commit> = std::iter::once(&parent).chain(merged.as_ref()).collect();
+            let oid = self
+                .repo
+                .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+                .context("Failed to create commit")?;
+            if merged.is_some() {
+                self.repo.cleanup_state().context("Failed to finish the merge")?;
+            }
+            oid
         } else {
             // Initial commit (no parent)
             self.repo
@@ -168,6 +309,7 @@ impl LitRepo {
                 .context("Failed to create initial commit")?
         };
 
+        tracing::info!(commit = %commit_oid, message, "commit created");
         Ok(format!("{}", commit_oid))
     }
 
@@ -212,6 +354,62 @@ impl LitRepo {
         Ok(commits)
     }
 
+    /// Commits selected by `spec`, newest first: a single commit-ish, or a
+    /// range `from..to` of the commits reachable from `to` but not `from`,
+    /// as in `git log from..to`
+    pub fn commits_in(&self, spec: &str) -> Result<Vec<CommitInfo>> {
+        if !spec.contains("..") {
+            return Ok(vec![self.commit_info(spec)?]);
+        }
+        let mut revwalk = self.repo.revwalk().context("Failed to create revwalk")?;
+        revwalk
+            .push_range(spec)
+            .with_context(|| format!("Failed to resolve range '{}'", spec))?;
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            let oid = oid.context("Failed to read commit OID")?;
+            let commit = self
+                .repo
+                .find_commit(oid)
+                .with_context(|| format!("Failed to find commit {}", oid))?;
+            commits.push(commit_to_info(&commit));
+        }
+        Ok(commits)
+    }
+
+    /// Files under `dir` that a commit added (relative to its first
+    /// parent), with their content, sorted
+    pub fn files_added_in(&self, commit: &str, dir: &Path) -> Result<Vec<(PathBuf, String)>> {
+        let commit = self.resolve_commit(commit)?;
+        let tree = commit.tree().context("Failed to get commit tree")?;
+        let Ok(entry) = tree.get_path(dir) else {
+            return Ok(Vec::new());
+        };
+        let Ok(subtree) = entry.to_object(&self.repo).and_then(|o| o.peel_to_tree()) else {
+            return Ok(Vec::new());
+        };
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().context("Failed to get commit tree")?),
+            Err(_) => None,
+        };
+
+        let mut added = Vec::new();
+        subtree
+            .walk(git2::TreeWalkMode::PreOrder, |prefix, entry| {
+                let path = dir.join(format!("{}{}", prefix, entry.name().unwrap_or("")));
+                if entry.kind() == Some(git2::ObjectType::Blob)
+                    && parent_tree.as_ref().is_none_or(|tree| tree.get_path(&path).is_err())
+                    && let Ok(blob) = entry.to_object(&self.repo).and_then(|o| o.peel_to_blob())
+                {
+                    added.push((path, String::from_utf8_lossy(blob.content()).to_string()));
+                }
+                git2::TreeWalkResult::Ok
+            })
+            .context("Failed to read commit tree")?;
+        added.sort();
+        Ok(added)
+    }
+
     // ---------- Status ----------
 
     /// Get the working tree status, categorized by file type.
@@ -256,73 +454,127 @@ impl LitRepo {
                 || status.is_index_modified()
                 || status.is_wt_renamed()
                 || status.is_index_renamed();
+            self.categorize(&mut result, path, is_new, is_deleted, is_modified);
+        }
 
-            let path_str = path.to_string_lossy();
+        Ok(result)
+    }
 
-            if path_str.starts_with("prompts/") {
-                if is_new {
-                    result.prompts_new.push(path);
-                } else if is_deleted {
-                    result.prompts_deleted.push(path);
-                } else if is_modified {
-                    result.prompts_modified.push(path);
-                }
-            } else if path_str.starts_with("code.lock/") {
-                if is_new {
-                    result.code_new.push(path);
-                } else if is_modified {
-                    result.code_modified.push(path);
-                }
-            } else if path_str == "lit.toml"
-                || path_str.starts_with(".lit/")
-                || path_str == ".gitignore"
-            {
-                if is_new || is_modified {
-                    result.config_modified.push(path);
-                }
-            } else if is_new || is_modified || is_deleted {
-                result.other_modified.push(path);
+    /// What changed between two commit-ishes, categorized like [`status`](Self::status)
+    /// (`head_commit` is `to`)
+    pub fn status_between(&self, from: &str, to: &str) -> Result<RepoStatus> {
+        let diff = self.diff(&mut DiffOptions::new(), Some((from, to)))?;
+        let mut result = RepoStatus {
+            prompts_modified: Vec::new(),
+            prompts_new: Vec::new(),
+            prompts_deleted: Vec::new(),
+            code_modified: Vec::new(),
+            code_new: Vec::new(),
+            config_modified: Vec::new(),
+            other_modified: Vec::new(),
+            head_commit: Some(self.commit_info(to)?.short_hash),
+        };
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()).map(PathBuf::from) else {
+                continue;
+            };
+            let status = delta.status();
+            self.categorize(
+                &mut result,
+                path,
+                status == git2::Delta::Added,
+                status == git2::Delta::Deleted,
+                matches!(status, git2::Delta::Modified | git2::Delta::Renamed | git2::Delta::Typechange),
+            );
+        }
+        Ok(result)
+    }
+
+    fn categorize(&self, result: &mut RepoStatus, path: PathBuf, is_new: bool, is_deleted: bool, is_modified: bool) {
+        let path_str = path.to_string_lossy();
+
+        if self.in_prompt_dir(&path) {
+            if is_new {
+                result.prompts_new.push(path);
+            } else if is_deleted {
+                result.prompts_deleted.push(path);
+            } else if is_modified {
+                result.prompts_modified.push(path);
+            }
+        } else if path.starts_with(&self.code_dir) {
+            if is_new {
+                result.code_new.push(path);
+            } else if is_modified {
+                result.code_modified.push(path);
+            }
+        } else if path_str == "lit.toml"
+            || path_str.starts_with(".lit/")
+            || path_str == ".gitignore"
+        {
+            if is_new || is_modified {
+                result.config_modified.push(path);
             }
+        } else if is_new || is_modified || is_deleted {
+            result.other_modified.push(path);
         }
+    }
 
-        Ok(result)
+    /// Commit hashes for a range `from..to`, or `from...to` to diff from
+    /// where `to` branched off `from` (their merge base), as `git diff`
+    /// reads them. A side left empty is HEAD.
+    pub fn resolve_range(&self, spec: &str) -> Result<(String, String)> {
+        let (from, to, merge_base) = match spec.split_once("...") {
+            Some((from, to)) => (from, to, true),
+            None => match spec.split_once("..") {
+                Some((from, to)) => (from, to, false),
+                None => bail!("'{}' is not a range; expected <ref1>..<ref2>", spec),
+            },
+        };
+        let side = |s: &str| if s.is_empty() { "HEAD".to_string() } else { s.to_string() };
+        let from = self.resolve_commit(&side(from))?.id();
+        let to = self.resolve_commit(&side(to))?.id();
+        let from = if merge_base {
+            self
+                .repo
+                .merge_base(from, to)
+                .with_context(|| format!("'{}' has no common ancestor", spec))?
+        } else {
+            from
+        };
+        Ok((from.to_string(), to.to_string()))
     }
 
     // ---------- Diff ----------
 
-    /// Get a diff of prompts/ (working tree vs HEAD).
-    pub fn diff_prompts(&self) -> Result<String> {
-        self.diff_pathspec(&["prompts/"])
+    // The diffs below are of the working tree against HEAD, or between
+    // two commits when given a `range` of commit-ishes (see `resolve_range`).
+
+    /// Get a diff of the prompt directories.
+    pub fn diff_prompts(&self, range: Option<(&str, &str)>) -> Result<String> {
+        let specs = self.prompt_pathspecs();
+        self.diff_pathspec(&specs.iter().map(String::as_str).collect::<Vec<_>>(), range)
     }
 
-    /// Get a diff of code.lock/ (working tree vs HEAD).
-    pub fn diff_code(&self) -> Result<String> {
-        self.diff_pathspec(&["code.lock/"])
+    /// Get a diff of the code directory.
+    pub fn diff_code(&self, range: Option<(&str, &str)>) -> Result<String> {
+        self.diff_pathspec(&[&format!("{}/", self.code_dir)], range)
     }
 
     /// Get a diff of all lit-related paths.
-    pub fn diff_all(&self) -> Result<String> {
-        self.diff_pathspec(&["prompts/", "code.lock/", "lit.toml"])
+    pub fn diff_all(&self, range: Option<(&str, &str)>) -> Result<String> {
+        let mut specs = self.prompt_pathspecs();
+        specs.push(format!("{}/", self.code_dir));
+        specs.push("lit.toml".to_string());
+        self.diff_pathspec(&specs.iter().map(String::as_str).collect::<Vec<_>>(), range)
     }
 
-    /// Get diff for specific pathspecs.
     /// Get per-file insertion/deletion counts for prompt changes.
-    pub fn diff_prompt_stats(&self) -> Result<Vec<FileDiffStat>> {
+    pub fn diff_prompt_stats(&self, range: Option<(&str, &str)>) -> Result<Vec<FileDiffStat>> {
         let mut opts = DiffOptions::new();
-        opts.pathspec("prompts/");
-
-        let head_tree = match self.repo.head() {
-            Ok(head) => {
-                let commit = head.peel_to_commit().context("Failed to peel HEAD")?;
-                Some(commit.tree().context("Failed to get HEAD tree")?)
-            }
-            Err(_) => None,
-        };
-
-        let diff = self
-            .repo
-            .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
-            .context("Failed to compute diff")?;
+        for spec in self.prompt_pathspecs() {
+            opts.pathspec(spec);
+        }
+        let diff = self.diff(&mut opts, range)?;
 
         let mut stats_map: std::collections::HashMap<PathBuf, (usize, usize)> =
             std::collections::HashMap::new();
@@ -354,10 +606,15 @@ impl LitRepo {
         Ok(result)
     }
 
-    fn diff_pathspec(&self, pathspecs: &[&str]) -> Result<String> {
-        let mut opts = DiffOptions::new();
-        for spec in pathspecs {
-            opts.pathspec(spec);
+    /// The working tree (with the index) against HEAD, or `from` against `to`
+    fn diff(&self, opts: &mut DiffOptions, range: Option<(&str, &str)>) -> Result<git2::Diff<'_>> {
+        if let Some((from, to)) = range {
+            let from = self.resolve_commit(from)?.tree().context("Failed to get commit tree")?;
+            let to = self.resolve_commit(to)?.tree().context("Failed to get commit tree")?;
+            return self
+                .repo
+                .diff_tree_to_tree(Some(&from), Some(&to), Some(opts))
+                .context("Failed to compute diff");
         }
 
         let head_tree = match self.repo.head() {
@@ -368,10 +625,17 @@ impl LitRepo {
             Err(_) => None, // No commits yet — diff against empty tree
         };
 
-        let diff = self
-            .repo
-            .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
-            .context("Failed to compute diff")?;
+        self.repo
+            .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(opts))
+            .context("Failed to compute diff")
+    }
+
+    fn diff_pathspec(&self, pathspecs: &[&str], range: Option<(&str, &str)>) -> Result<String> {
+        let mut opts = DiffOptions::new();
+        for spec in pathspecs {
+            opts.pathspec(spec);
+        }
+        let diff = self.diff(&mut opts, range)?;
 
         let mut buf = Vec::new();
         diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
@@ -388,10 +652,479 @@ impl LitRepo {
         Ok(String::from_utf8_lossy(&buf).to_string())
     }
 
+    // ---------- Reading past commits ----------
+
+    /// The commit a commit-ish (commit hash, HEAD~N, branch name, etc.)
+    /// points to
+    fn resolve_commit(&self, ref_str: &str) -> Result<git2::Commit<'_>> {
+        self.repo
+            .revparse_single(ref_str)
+            .with_context(|| format!("Failed to resolve ref '{}'", ref_str))?
+            .peel_to_commit()
+            .with_context(|| format!("'{}' does not point to a commit", ref_str))
+    }
+
+    /// Information about the commit a commit-ish points to
+    pub fn commit_info(&self, ref_str: &str) -> Result<CommitInfo> {
+        Ok(commit_to_info(&self.resolve_commit(ref_str)?))
+    }
+
+    /// Prompt files under the prompt directories and their contents at a commit-ish
+    /// (commit hash, HEAD~N, branch name, etc.)
+    pub fn prompt_files_at(&self, ref_str: &str) -> Result<Vec<(PathBuf, String)>> {
+        let commit = self.resolve_commit(ref_str)?;
+        let tree = commit.tree().context("Failed to get commit tree")?;
+
+        let mut files = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            let path = PathBuf::from(format!("{}{}", dir, entry.name().unwrap_or("")));
+            if entry.kind() == Some(git2::ObjectType::Blob)
+                && self.in_prompt_dir(&path)
+                && is_prompt_file(&path)
+                && let Ok(blob) = entry.to_object(&self.repo).and_then(|o| o.peel_to_blob())
+            {
+                files.push((path, String::from_utf8_lossy(blob.content()).to_string()));
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .with_context(|| format!("Failed to read tree at '{}'", ref_str))?;
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Prompts added, modified or removed between two commit-ishes
+    pub fn prompts_changed_between(&self, from: &str, to: &str) -> Result<Vec<PathBuf>> {
+        let before: HashMap<PathBuf, String> = self.prompt_files_at(from)?.into_iter().collect();
+        let after: HashMap<PathBuf, String> = self.prompt_files_at(to)?.into_iter().collect();
+        let mut changed: Vec<PathBuf> = after
+            .iter()
+            .filter(|(path, content)| before.get(*path) != Some(content))
+            .map(|(path, _)| path.clone())
+            .chain(before.keys().filter(|path| !after.contains_key(*path)).cloned())
+            .collect();
+        changed.sort();
+        Ok(changed)
+    }
+
+    /// A file's content at a commit-ish, or None if it doesn't exist there
+    pub fn file_at(&self, ref_str: &str, path: &Path) -> Result<Option<String>> {
+        let tree = self.resolve_commit(ref_str)?.tree().context("Failed to get commit tree")?;
+        Ok(self.blob_in(&tree, path).map(|(_, content)| content))
+    }
+
+    /// What `path` names at a commit-ish, a file or a directory, or None if
+    /// nothing is there. An empty path names the root directory.
+    pub fn object_at(&self, ref_str: &str, path: &Path) -> Result<Option<TreeObject>> {
+        let tree = self.resolve_commit(ref_str)?.tree().context("Failed to get commit tree")?;
+        let object = if path.as_os_str().is_empty() {
+            tree.into_object()
+        } else {
+            match tree.get_path(path) {
+                Ok(entry) => entry
+                    .to_object(&self.repo)
+                    .with_context(|| format!("Failed to read {} at '{}'", path.display(), ref_str))?,
+                Err(_) => return Ok(None),
+            }
+        };
+        if let Some(blob) = object.as_blob() {
+            return Ok(Some(TreeObject::Blob(blob.content().to_vec())));
+        }
+        let Some(tree) = object.as_tree() else {
+            return Ok(None); // A submodule
+        };
+        let mut entries: Vec<String> = tree
+            .iter()
+            .map(|entry| {
+                let name = entry.name().unwrap_or("").to_string();
+                match entry.kind() {
+                    Some(git2::ObjectType::Tree) => format!("{}/", name),
+                    _ => name,
+                }
+            })
+            .collect();
+        entries.sort();
+        Ok(Some(TreeObject::Tree(entries)))
+    }
+
+    /// Commits that changed `path` (newest first, up to `limit`), following
+    /// first parents from HEAD — like `git log --first-parent -- <path>`
+    pub fn file_history(&self, path: &Path, limit: usize) -> Result<Vec<FileRevision>> {
+        let Ok(head) = self.repo.head() else {
+            return Ok(Vec::new()); // No commits yet
+        };
+        let mut commit = Some(head.peel_to_commit().context("Failed to find HEAD commit")?);
+        let mut revisions = Vec::new();
+        while let Some(current) = commit {
+            if revisions.len() >= limit {
+                break;
+            }
+            let parent = current.parent(0).ok();
+            let after = self.blob_in(&current.tree().context("Failed to get commit tree")?, path);
+            let before = match &parent {
+                Some(parent) => self.blob_in(&parent.tree().context("Failed to get commit tree")?, path),
+                None => None,
+            };
+            if after.as_ref().map(|(id, _)| *id) != before.as_ref().map(|(id, _)| *id) {
+                revisions.push(FileRevision {
+                    commit: commit_to_info(&current),
+                    parent: parent.as_ref().map(|p| format!("{}", p.id())),
+                    before: before.map(|(_, content)| content),
+                    after: after.map(|(_, content)| content),
+                });
+            }
+            commit = parent;
+        }
+        Ok(revisions)
+    }
+
+    /// The blob at `path` in `tree`: its id and (lossily decoded) content
+    fn blob_in(&self, tree: &git2::Tree, path: &Path) -> Option<(git2::Oid, String)> {
+        let entry = tree.get_path(path).ok()?;
+        let blob = entry.to_object(&self.repo).ok()?.peel_to_blob().ok()?;
+        Some((blob.id(), String::from_utf8_lossy(blob.content()).to_string()))
+    }
+
+    // ---------- Remotes ----------
+
+    /// Configured remotes, in git's order
+    pub fn remotes(&self) -> Result<Vec<RemoteInfo>> {
+        let names = self.repo.remotes().context("Failed to list remotes")?;
+        names
+            .iter()
+            .flatten()
+            .map(|name| {
+                let remote = self.repo.find_remote(name).with_context(|| format!("Failed to read remote '{}'", name))?;
+                Ok(RemoteInfo {
+                    name: name.to_string(),
+                    url: remote.url().unwrap_or("").to_string(),
+                    push_url: remote.pushurl().map(String::from),
+                })
+            })
+            .collect()
+    }
+
+    /// Add a remote; fails if one of that name exists
+    pub fn add_remote(&self, name: &str, url: &str) -> Result<()> {
+        if let Ok(existing) = self.repo.find_remote(name) {
+            bail!("Remote '{}' already exists ({})", name, existing.url().unwrap_or(""));
+        }
+        self.repo
+            .remote(name, url)
+            .with_context(|| format!("Failed to add remote '{}'", name))?;
+        Ok(())
+    }
+
+    /// The current branch and the remote branch it pushes to and pulls
+    /// from. A branch without an upstream gets the same-named branch on
+    /// `origin`, or on the only remote (`tracked` is then false).
+    pub fn upstream(&self) -> Result<Upstream> {
+        let head = match self.repo.head() {
+            Ok(head) => head,
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                bail!("No commits yet.\nHint: Run `lit commit` first.")
+            }
+            Err(e) => return Err(e).context("Failed to read HEAD"),
+        };
+        if !head.is_branch() {
+            bail!("HEAD is detached, so there is no branch to push or pull.\nHint: Check out a branch first (`git switch <branch>`).");
+        }
+        let branch = head.shorthand().unwrap_or_default().to_string();
+        let config = self.repo.config().context("Failed to read git config")?;
+        let tracked = (
+            config.get_string(&format!("branch.{}.remote", branch)),
+            config.get_string(&format!("branch.{}.merge", branch)),
+        );
+        if let (Ok(remote), Ok(merge)) = tracked {
+            let remote_branch = merge.trim_start_matches("refs/heads/").to_string();
+            return Ok(Upstream { branch, remote, remote_branch, tracked: true });
+        }
+
+        let remotes = self.remotes()?;
+        let remote = match remotes.as_slice() {
+            [] => bail!("No git remote configured.\nHint: Add one with `lit remote add origin <url>`."),
+            [only] => only.name.clone(),
+            _ if remotes.iter().any(|r| r.name == "origin") => "origin".to_string(),
+            _ => bail!(
+                "Branch '{}' has no upstream, and there are several remotes ({}).\n\
+                 Hint: Set one with `git branch --set-upstream-to <remote>/{}`.",
+                branch,
+                remotes.iter().map(|r| r.name.as_str()).collect::<Vec<_>>().join(", "),
+                branch
+            ),
+        };
+        Ok(Upstream { remote_branch: branch.clone(), branch, remote, tracked: false })
+    }
+
+    fn set_upstream(&self, upstream: &Upstream) -> Result<()> {
+        let mut config = self.repo.config().context("Failed to read git config")?;
+        config
+            .set_str(&format!("branch.{}.remote", upstream.branch), &upstream.remote)
+            .and_then(|_| {
+                config.set_str(
+                    &format!("branch.{}.merge", upstream.branch),
+                    &format!("refs/heads/{}", upstream.remote_branch),
+                )
+            })
+            .with_context(|| format!("Failed to set the upstream of '{}'", upstream.branch))
+    }
+
+    /// Push the current branch to its upstream, setting it if the branch
+    /// had none
+    pub fn push(&self, upstream: &Upstream) -> Result<()> {
+        let mut remote = self
+            .repo
+            .find_remote(&upstream.remote)
+            .with_context(|| format!("No remote named '{}'", upstream.remote))?;
+        let url = remote.pushurl().or(remote.url()).unwrap_or("").to_string();
+        let refspec = format!("refs/heads/{}:refs/heads/{}", upstream.branch, upstream.remote_branch);
+
+        let rejected = RefCell::new(Vec::new());
+        {
+            let mut callbacks = credentials::callbacks();
+            callbacks.push_update_reference(|refname, status| {
+                if let Some(status) = status {
+                    rejected.borrow_mut().push(format!("{} ({})", refname, status));
+                }
+                Ok(())
+            });
+            let mut options = PushOptions::new();
+            options.remote_callbacks(callbacks).proxy_options(auto_proxy());
+            remote
+                .push(&[refspec.as_str()], Some(&mut options))
+                .map_err(|e| remote_error(e, &format!("push to {}", upstream.remote), &url))?;
+        }
+        let rejected = rejected.into_inner();
+        if !rejected.is_empty() {
+            bail!(
+                "{} rejected the push of {}.\nHint: Run `lit pull` to merge its changes first.",
+                upstream.remote,
+                rejected.join(", ")
+            );
+        }
+        if !upstream.tracked {
+            self.set_upstream(upstream)?;
+        }
+        Ok(())
+    }
+
+    /// Fetch a remote's branches
+    pub fn fetch(&self, remote: &str) -> Result<()> {
+        let mut remote_handle = self
+            .repo
+            .find_remote(remote)
+            .with_context(|| format!("No remote named '{}'", remote))?;
+        let url = remote_handle.url().unwrap_or("").to_string();
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(credentials::callbacks()).proxy_options(auto_proxy());
+        remote_handle
+            .fetch(&[] as &[&str], Some(&mut options), None)
+            .map_err(|e| remote_error(e, &format!("fetch from {}", remote), &url))
+    }
+
+    /// Fetch the upstream and bring the current branch up to date with it:
+    /// fast-forward when possible, otherwise merge. A merge with conflicts
+    /// is left in progress, with conflict markers in the files, for the
+    /// user to resolve and commit.
+    pub fn pull(&self) -> Result<PullOutcome> {
+        let upstream = self.upstream()?;
+        self.fetch(&upstream.remote)?;
+        let tracking = format!("refs/remotes/{}/{}", upstream.remote, upstream.remote_branch);
+        let Ok(reference) = self.repo.find_reference(&tracking) else {
+            bail!(
+                "{} has no branch '{}'.\nHint: Run `lit push` to publish it.",
+                upstream.remote,
+                upstream.remote_branch
+            );
+        };
+        if !upstream.tracked {
+            self.set_upstream(&upstream)?;
+        }
+        let theirs = self
+            .repo
+            .reference_to_annotated_commit(&reference)
+            .context("Failed to read the fetched branch")?;
+        let (analysis, _) = self.repo.merge_analysis(&[&theirs]).context("Failed to analyze the merge")?;
+        let dirty = "Pulling would overwrite uncommitted changes.\nHint: Commit them, or set them aside with `lit stash`, first.";
+
+        if analysis.is_up_to_date() {
+            return Ok(PullOutcome::UpToDate);
+        }
+        if analysis.is_fast_forward() {
+            let target = self.repo.find_object(theirs.id(), None).context("Failed to find the fetched commit")?;
+            self.repo
+                .checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+                .map_err(|e| match e.code() {
+                    git2::ErrorCode::Conflict => anyhow::anyhow!(dirty),
+                    _ => anyhow::Error::new(e).context("Failed to check out the fetched commit"),
+                })?;
+            self.repo
+                .head()
+                .and_then(|mut head| head.set_target(theirs.id(), "lit pull: fast-forward"))
+                .context("Failed to move the branch")?;
+            return Ok(PullOutcome::FastForward);
+        }
+
+        let staged = self
+            .repo
+            .statuses(None)
+            .context("Failed to get repo status")?
+            .iter()
+            .any(|entry| {
+                entry.status().intersects(
+                    git2::Status::INDEX_NEW
+                        | git2::Status::INDEX_MODIFIED
+                        | git2::Status::INDEX_DELETED
+                        | git2::Status::INDEX_RENAMED,
+                )
+            });
+        if staged {
+            bail!(dirty);
+        }
+        self.repo
+            .merge(&[&theirs], None, Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::Conflict => anyhow::anyhow!(dirty),
+                _ => anyhow::Error::new(e).context("Failed to merge the fetched commit"),
+            })?;
+        let index = self.repo.index().context("Failed to open git index")?;
+        if index.has_conflicts() {
+            let mut conflicts: Vec<PathBuf> = index
+                .conflicts()
+                .context("Failed to read merge conflicts")?
+                .flatten()
+                .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+                .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).to_string()))
+                .collect();
+            conflicts.sort();
+            conflicts.dedup();
+            return Ok(PullOutcome::Conflicts(conflicts));
+        }
+        self.commit(&format!(
+            "Merge {}/{} into {}",
+            upstream.remote, upstream.remote_branch, upstream.branch
+        ))?;
+        Ok(PullOutcome::Merged)
+    }
+
+    /// Clone `url` into `path` and open it
+    pub fn clone(url: &str, path: &Path) -> Result<Self> {
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(credentials::callbacks()).proxy_options(auto_proxy());
+        RepoBuilder::new()
+            .fetch_options(options)
+            .clone(url, path)
+            .map_err(|e| remote_error(e, "clone", url))?;
+        Self::open(path)
+    }
+
     // ---------- Checkout ----------
 
-    /// Checkout a specific ref (commit hash, HEAD~N, branch name, etc.)
-    pub fn checkout_ref(&self, ref_str: &str) -> Result<String> {
+    /// Every path with staged, unstaged or untracked changes (deleted files
+    /// included, ignored files not), sorted
+    pub fn changed_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut opts = StatusOptions::new();
+        opts.show(StatusShow::IndexAndWorkdir);
+        opts.include_untracked(true);
+        opts.recurse_untracked_dirs(true);
+        let statuses = self
+            .repo
+            .statuses(Some(&mut opts))
+            .context("Failed to get repo status")?;
+        let mut paths: Vec<PathBuf> = statuses
+            .iter()
+            .filter(|entry| !entry.status().is_empty() && !entry.status().is_ignored())
+            .filter_map(|entry| entry.path().map(PathBuf::from))
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Put `paths` back as they are at HEAD, in the index and the working
+    /// tree. Paths HEAD doesn't have are unstaged and deleted.
+    pub fn restore_paths(&self, paths: &[PathBuf]) -> Result<()> {
+        let head_tree = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_tree().context("Failed to get HEAD tree")?),
+            Err(_) => None, // No commits yet
+        };
+        let (tracked, untracked): (Vec<&PathBuf>, Vec<&PathBuf>) = paths
+            .iter()
+            .partition(|path| head_tree.as_ref().is_some_and(|tree| tree.get_path(path).is_ok()));
+
+        if !tracked.is_empty() {
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force().update_index(true).disable_pathspec_match(true);
+            for path in &tracked {
+                checkout.path(path.as_path());
+            }
+            self.repo
+                .checkout_head(Some(&mut checkout))
+                .context("Failed to restore files from HEAD")?;
+        }
+
+        let mut index = self.repo.index().context("Failed to open git index")?;
+        for path in untracked {
+            let _ = index.remove_path(path);
+            let full = self.root.join(path);
+            if full.exists() {
+                std::fs::remove_file(&full).with_context(|| format!("Failed to remove {}", full.display()))?;
+            }
+        }
+        index.write().context("Failed to write git index")?;
+        Ok(())
+    }
+
+    /// Uncommitted work that checking out `ref_str` would overwrite or
+    /// discard: every tracked file with staged or unstaged changes, plus
+    /// untracked files at paths the target commit has. Sorted.
+    pub fn checkout_conflicts(&self, ref_str: &str) -> Result<Vec<PathBuf>> {
+        let tree = self.resolve_commit(ref_str)?.tree().context("Failed to get commit tree")?;
+
+        let mut opts = StatusOptions::new();
+        opts.show(StatusShow::IndexAndWorkdir);
+        opts.include_untracked(true);
+        opts.recurse_untracked_dirs(true);
+        let statuses = self
+            .repo
+            .statuses(Some(&mut opts))
+            .context("Failed to get repo status")?;
+
+        let mut conflicts = Vec::new();
+        for entry in statuses.iter() {
+            let Some(path) = entry.path().map(PathBuf::from) else {
+                continue;
+            };
+            let status = entry.status();
+            if status.is_empty() || status.is_ignored() {
+                continue;
+            }
+            // Untracked files survive a checkout unless the target has them
+            if status == git2::Status::WT_NEW && tree.get_path(&path).is_err() {
+                continue;
+            }
+            conflicts.push(path);
+        }
+        conflicts.sort();
+        Ok(conflicts)
+    }
+
+    /// Checkout a specific ref (commit hash, HEAD~N, branch name, etc.).
+    ///
+    /// Refuses, listing the files, when that would lose uncommitted work
+    /// (see [`checkout_conflicts`](Self::checkout_conflicts)), unless `force`.
+    pub fn checkout_ref(&self, ref_str: &str, force: bool) -> Result<String> {
+        if !force {
+            let conflicts = self.checkout_conflicts(ref_str)?;
+            if !conflicts.is_empty() {
+                bail!(
+                    "Checking out '{}' would discard uncommitted changes to {} file(s):\n{}",
+                    ref_str,
+                    conflicts.len(),
+                    conflicts.iter().map(|p| format!("  {}", p.display())).collect::<Vec<_>>().join("\n")
+                );
+            }
+        }
+
         // Parse the ref
         let obj = self
             .repo
@@ -424,8 +1157,22 @@ impl LitRepo {
     /// Write a standard .gitignore for a lit project.
     pub fn write_gitignore(&self) -> Result<()> {
         let gitignore_content = "\
-# Lit internal cache (local optimization, not committed)
+# Lit internal state (local only, not committed)
 .lit/cache/
+.lit/interfaces/
+.lit/backups/
+.lit/logs/
+.lit/reports/
+.lit/sessions/
+.lit/transcripts/
+.lit/preview/
+.lit/eval/
+.lit/stash.json
+.lit/last-run.json
+.lit/lock
+.lit/patches/index.lock
+.lit/patches/index.json.tmp
+*.prompt.md.suggested
 
 # Python artifacts
 __pycache__/
@@ -451,6 +1198,65 @@ Thumbs.db
             .with_context(|| format!("Failed to write .gitignore at {}", gitignore_path.display()))
     }
 
+    /// Required entries missing from the project's .gitignore (all of them if
+    /// there is no .gitignore).
+    pub fn missing_gitignore_entries(&self) -> Result<Vec<&'static str>> {
+        let gitignore_path = self.root.join(".gitignore");
+        if !gitignore_path.exists() {
+            return Ok(REQUIRED_GITIGNORE_ENTRIES.to_vec());
+        }
+        let content = std::fs::read_to_string(&gitignore_path)
+            .with_context(|| format!("Failed to read {}", gitignore_path.display()))?;
+
+        let present: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| l.trim_start_matches('/').trim_end_matches('/'))
+            .collect();
+
+        Ok(REQUIRED_GITIGNORE_ENTRIES
+            .iter()
+            .copied()
+            .filter(|entry| !present.contains(&entry.trim_end_matches('/')))
+            .collect())
+    }
+
+    /// Add any missing required entries to .gitignore, keeping everything the
+    /// user has added. Writes the standard file if none exists.
+    ///
+    /// Returns the entries that were added.
+    pub fn sync_gitignore(&self) -> Result<Vec<&'static str>> {
+        let gitignore_path = self.root.join(".gitignore");
+        if !gitignore_path.exists() {
+            self.write_gitignore()?;
+            return Ok(REQUIRED_GITIGNORE_ENTRIES.to_vec());
+        }
+
+        let missing = self.missing_gitignore_entries()?;
+        if missing.is_empty() {
+            return Ok(missing);
+        }
+
+        let mut content = std::fs::read_to_string(&gitignore_path)
+            .with_context(|| format!("Failed to read {}", gitignore_path.display()))?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str("# Lit internal state (added by `lit gitignore sync`)\n");
+        for entry in &missing {
+            content.push_str(entry);
+            content.push('\n');
+        }
+        std::fs::write(&gitignore_path, content)
+            .with_context(|| format!("Failed to write .gitignore at {}", gitignore_path.display()))?;
+
+        Ok(missing)
+    }
+
     // ---------- Internal ----------
 
     fn default_signature(&self) -> Result<Signature<'_>> {
@@ -462,6 +1268,27 @@ Thumbs.db
     }
 }
 
+/// Proxy settings from git config and the environment, as git uses them
+fn auto_proxy<'a>() -> ProxyOptions<'a> {
+    let mut proxy = ProxyOptions::new();
+    proxy.auto();
+    proxy
+}
+
+/// A failed remote operation, with a hint for the failures users can fix
+fn remote_error(e: git2::Error, action: &str, url: &str) -> anyhow::Error {
+    match e.code() {
+        git2::ErrorCode::Auth => {
+            anyhow::anyhow!("Failed to {} ({}): authentication failed.\n{}", action, url, credentials::auth_hint(url))
+        }
+        git2::ErrorCode::NotFastForward => anyhow::anyhow!(
+            "Failed to {}: the remote has commits this branch doesn't.\nHint: Run `lit pull` to merge them first.",
+            action
+        ),
+        _ => anyhow::Error::new(e).context(format!("Failed to {} ({})", action, url)),
+    }
+}
+
 impl RepoStatus {
     /// Returns true if there are any changes to commit.
     pub fn has_changes(&self) -> bool {
@@ -487,8 +1314,9 @@ impl RepoStatus {
 fn commit_to_info(commit: &git2::Commit) -> CommitInfo {
     let hash = format!("{}", commit.id());
     let short_hash = hash[..7.min(hash.len())].to_string();
+    // The subject line; trailers such as `Lit-Cost:` stay out of listings
     let message = commit
-        .message()
+        .summary()
         .unwrap_or("")
         .trim()
         .to_string();
@@ -573,6 +1401,261 @@ mod tests {
         assert_eq!(log[0].message, "Second");
     }
 
+    #[test]
+    fn test_commits_in_and_files_added_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        let generations = Path::new(".lit/generations");
+        std::fs::create_dir_all(dir.path().join(generations)).unwrap();
+
+        std::fs::write(dir.path().join(".lit/generations/1.json"), "one").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+        std::fs::write(dir.path().join(".lit/generations/2.json"), "two").unwrap();
+        std::fs::write(dir.path().join(".lit/generations/3.json"), "three").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Second").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "x").unwrap();
+        repo.stage_file(Path::new("notes.txt")).unwrap();
+        repo.commit("Third").unwrap();
+
+        assert_eq!(
+            repo.files_added_in("HEAD~1", generations).unwrap(),
+            vec![
+                (PathBuf::from(".lit/generations/2.json"), "two".to_string()),
+                (PathBuf::from(".lit/generations/3.json"), "three".to_string()),
+            ]
+        );
+        assert_eq!(repo.files_added_in("HEAD~2", generations).unwrap().len(), 1);
+        assert!(repo.files_added_in("HEAD", generations).unwrap().is_empty());
+
+        let messages = |spec: &str| -> Vec<String> {
+            repo.commits_in(spec).unwrap().into_iter().map(|c| c.message).collect()
+        };
+        assert_eq!(messages("HEAD~1"), vec!["Second"]);
+        assert_eq!(messages("HEAD~2..HEAD"), vec!["Third", "Second"]);
+        assert!(repo.commits_in("nope..HEAD").is_err());
+    }
+
+    #[test]
+    fn test_prompt_files_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("prompts/models")).unwrap();
+
+        std::fs::write(dir.path().join("prompts/models/user.prompt.md"), "v1").unwrap();
+        std::fs::write(dir.path().join("prompts/notes.md"), "not a prompt").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+
+        std::fs::write(dir.path().join("prompts/models/user.prompt.md"), "v2").unwrap();
+        std::fs::write(dir.path().join("prompts/api.prompt.md"), "api").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Second").unwrap();
+
+        let first = repo.prompt_files_at("HEAD~1").unwrap();
+        assert_eq!(first, vec![(PathBuf::from("prompts/models/user.prompt.md"), "v1".to_string())]);
+        let head = repo.prompt_files_at("HEAD").unwrap();
+        assert_eq!(head.len(), 2);
+        assert!(repo.prompt_files_at("no-such-ref").is_err());
+    }
+
+    #[test]
+    fn test_object_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("prompts/models")).unwrap();
+        std::fs::write(dir.path().join("prompts/app.prompt.md"), "v1").unwrap();
+        std::fs::write(dir.path().join("prompts/models/user.prompt.md"), "user").unwrap();
+        repo.stage_all().unwrap();
+        let first = repo.commit("First").unwrap();
+        std::fs::write(dir.path().join("prompts/app.prompt.md"), "v2").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Second").unwrap();
+
+        let app = Path::new("prompts/app.prompt.md");
+        assert_eq!(repo.object_at("HEAD~1", app).unwrap(), Some(TreeObject::Blob(b"v1".to_vec())));
+        assert_eq!(repo.object_at("HEAD", app).unwrap(), Some(TreeObject::Blob(b"v2".to_vec())));
+        assert_eq!(
+            repo.object_at("HEAD", Path::new("prompts")).unwrap(),
+            Some(TreeObject::Tree(vec!["app.prompt.md".to_string(), "models/".to_string()]))
+        );
+        assert_eq!(repo.object_at("HEAD", Path::new("")).unwrap(), Some(TreeObject::Tree(vec!["prompts/".to_string()])));
+        assert_eq!(repo.object_at("HEAD", Path::new("prompts/missing.prompt.md")).unwrap(), None);
+        assert!(repo.object_at("no-such-ref", app).is_err());
+
+        let info = repo.commit_info("HEAD~1").unwrap();
+        assert_eq!((info.hash.as_str(), info.message.as_str()), (first.as_str(), "First"));
+    }
+
+    #[test]
+    fn test_remotes_and_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        let err = repo.upstream().unwrap_err().to_string();
+        assert!(err.contains("lit commit"), "{}", err);
+
+        std::fs::create_dir_all(dir.path().join("prompts")).unwrap();
+        std::fs::write(dir.path().join("prompts/app.prompt.md"), "v1").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+        let err = repo.upstream().unwrap_err().to_string();
+        assert!(err.contains("lit remote add origin"), "{}", err);
+
+        repo.add_remote("backup", "https://example.com/backup.git").unwrap();
+        let upstream = repo.upstream().unwrap();
+        assert_eq!((upstream.remote.as_str(), upstream.tracked), ("backup", false));
+        repo.add_remote("origin", "git@example.com:me/app.git").unwrap();
+        assert_eq!(repo.upstream().unwrap().remote, "origin");
+        assert!(repo.add_remote("origin", "https://example.com/other.git").is_err());
+
+        let names: Vec<String> = repo.remotes().unwrap().into_iter().map(|r| r.name).collect();
+        assert_eq!(names, vec!["backup", "origin"]);
+    }
+
+    /// A bare "server" repo, and a clone of it with one pushed commit
+    fn setup_remote() -> (tempfile::TempDir, LitRepo, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("local");
+        let repo = LitRepo::init(&local).unwrap();
+        std::fs::create_dir_all(local.join("prompts")).unwrap();
+        std::fs::write(local.join("prompts/app.prompt.md"), "one\n").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+        let server = dir.path().join("server.git");
+        let branch = repo.repo.head().unwrap().shorthand().unwrap().to_string();
+        Repository::init_opts(&server, git2::RepositoryInitOptions::new().bare(true).initial_head(&branch)).unwrap();
+        repo.add_remote("origin", server.to_str().unwrap()).unwrap();
+        (dir, repo, server)
+    }
+
+    #[test]
+    fn test_push_sets_upstream_and_clone() {
+        let (dir, repo, server) = setup_remote();
+        let upstream = repo.upstream().unwrap();
+        assert!(!upstream.tracked);
+        repo.push(&upstream).unwrap();
+        let upstream = repo.upstream().unwrap();
+        assert!(upstream.tracked);
+        assert_eq!(upstream.remote_branch, upstream.branch);
+
+        let other = dir.path().join("other");
+        let cloned = LitRepo::clone(server.to_str().unwrap(), &other).unwrap();
+        assert_eq!(std::fs::read_to_string(other.join("prompts/app.prompt.md")).unwrap(), "one\n");
+        assert_eq!(cloned.head_commit().unwrap().hash, repo.head_commit().unwrap().hash);
+        assert_eq!(cloned.pull().unwrap(), PullOutcome::UpToDate);
+    }
+
+    #[test]
+    fn test_pull_fast_forwards_and_merges() {
+        let (dir, repo, server) = setup_remote();
+        repo.push(&repo.upstream().unwrap()).unwrap();
+        let other_dir = dir.path().join("other");
+        let other = LitRepo::clone(server.to_str().unwrap(), &other_dir).unwrap();
+
+        std::fs::write(other_dir.join("prompts/app.prompt.md"), "two\n").unwrap();
+        other.stage_all().unwrap();
+        other.commit("Second").unwrap();
+        other.push(&other.upstream().unwrap()).unwrap();
+        assert_eq!(repo.pull().unwrap(), PullOutcome::FastForward);
+        assert_eq!(std::fs::read_to_string(repo.root.join("prompts/app.prompt.md")).unwrap(), "two\n");
+
+        // Diverged: both sides commit, the second push is rejected until a
+        // pull merges
+        std::fs::write(other_dir.join("prompts/theirs.prompt.md"), "theirs").unwrap();
+        other.stage_all().unwrap();
+        other.commit("Theirs").unwrap();
+        other.push(&other.upstream().unwrap()).unwrap();
+        std::fs::write(repo.root.join("prompts/ours.prompt.md"), "ours").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Ours").unwrap();
+        let err = format!("{:#}", repo.push(&repo.upstream().unwrap()).unwrap_err());
+        assert!(err.contains("lit pull"), "{}", err);
+        assert_eq!(repo.pull().unwrap(), PullOutcome::Merged);
+        assert!(repo.root.join("prompts/theirs.prompt.md").exists());
+        let merge = repo.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge.parent_count(), 2);
+        repo.push(&repo.upstream().unwrap()).unwrap();
+
+        // Conflicting edits stop the merge; committing the resolution
+        // concludes it
+        assert_eq!(other.pull().unwrap(), PullOutcome::FastForward);
+        std::fs::write(other_dir.join("prompts/app.prompt.md"), "three\n").unwrap();
+        other.stage_all().unwrap();
+        other.commit("Theirs again").unwrap();
+        other.push(&other.upstream().unwrap()).unwrap();
+        std::fs::write(repo.root.join("prompts/app.prompt.md"), "four\n").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Ours again").unwrap();
+        assert_eq!(repo.pull().unwrap(), PullOutcome::Conflicts(vec![PathBuf::from("prompts/app.prompt.md")]));
+        std::fs::write(repo.root.join("prompts/app.prompt.md"), "resolved\n").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Merge").unwrap();
+        assert_eq!(repo.repo.head().unwrap().peel_to_commit().unwrap().parent_count(), 2);
+        assert_eq!(repo.repo.state(), git2::RepositoryState::Clean);
+    }
+
+    #[test]
+    fn test_file_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        let user = Path::new("prompts/user.prompt.md");
+        std::fs::create_dir_all(dir.path().join("prompts")).unwrap();
+        assert!(repo.file_history(user, 10).unwrap().is_empty());
+
+        std::fs::write(dir.path().join(user), "v1").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Add user").unwrap();
+        std::fs::write(dir.path().join("prompts/other.prompt.md"), "x").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Unrelated").unwrap();
+        std::fs::write(dir.path().join(user), "v2").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Edit user").unwrap();
+
+        let history = repo.file_history(user, 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].commit.message, "Edit user");
+        assert_eq!(history[0].before.as_deref(), Some("v1"));
+        assert_eq!(history[0].after.as_deref(), Some("v2"));
+        assert_eq!(history[1].commit.message, "Add user");
+        assert_eq!(history[1].before, None);
+        assert_eq!(history[1].parent, None);
+        assert_eq!(repo.file_history(user, 1).unwrap().len(), 1);
+
+        assert_eq!(repo.file_at("HEAD~1", user).unwrap().as_deref(), Some("v1"));
+        assert_eq!(repo.file_at("HEAD", Path::new("missing.txt")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_prompts_changed_between() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("prompts")).unwrap();
+
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "a").unwrap();
+        std::fs::write(dir.path().join("prompts/b.prompt.md"), "b").unwrap();
+        std::fs::write(dir.path().join("prompts/c.prompt.md"), "c").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "a2").unwrap();
+        std::fs::remove_file(dir.path().join("prompts/b.prompt.md")).unwrap();
+        std::fs::write(dir.path().join("prompts/d.prompt.md"), "d").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Second").unwrap();
+
+        assert_eq!(
+            repo.prompts_changed_between("HEAD~1", "HEAD").unwrap(),
+            vec![
+                PathBuf::from("prompts/a.prompt.md"),
+                PathBuf::from("prompts/b.prompt.md"),
+                PathBuf::from("prompts/d.prompt.md"),
+            ]
+        );
+        assert!(repo.prompts_changed_between("HEAD", "HEAD").unwrap().is_empty());
+    }
+
     #[test]
     fn test_status_categorization() {
         let dir = tempfile::tempdir().unwrap();
@@ -629,12 +1712,52 @@ mod tests {
         // Modify
         std::fs::write(dir.path().join("prompts/a.prompt.md"), "v2").unwrap();
 
-        let diff = repo.diff_prompts().unwrap();
+        let diff = repo.diff_prompts(None).unwrap();
         assert!(!diff.is_empty(), "Expected non-empty diff");
         assert!(diff.contains("-v1"), "Expected old content in diff");
         assert!(diff.contains("+v2"), "Expected new content in diff");
     }
 
+    #[test]
+    fn test_diff_between_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("prompts")).unwrap();
+        std::fs::create_dir_all(dir.path().join("code.lock")).unwrap();
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "v1").unwrap();
+        std::fs::write(dir.path().join("prompts/b.prompt.md"), "b").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("v1").unwrap();
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "v2").unwrap();
+        std::fs::remove_file(dir.path().join("prompts/b.prompt.md")).unwrap();
+        std::fs::write(dir.path().join("prompts/c.prompt.md"), "c").unwrap();
+        std::fs::write(dir.path().join("code.lock/a.py"), "a = 2").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("v2").unwrap();
+        // Uncommitted edits are not part of a range
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "v3").unwrap();
+
+        let (from, to) = repo.resolve_range("HEAD~1..").unwrap();
+        assert_eq!(to, repo.head_commit().unwrap().hash);
+        let diff = repo.diff_prompts(Some((&from, &to))).unwrap();
+        assert!(diff.contains("-v1") && diff.contains("+v2"), "got: {}", diff);
+        assert!(!diff.contains("v3"));
+        assert!(repo.diff_code(Some((&from, &to))).unwrap().contains("+a = 2"));
+
+        let status = repo.status_between(&from, &to).unwrap();
+        assert_eq!(status.prompts_modified, vec![PathBuf::from("prompts/a.prompt.md")]);
+        assert_eq!(status.prompts_new, vec![PathBuf::from("prompts/c.prompt.md")]);
+        assert_eq!(status.prompts_deleted, vec![PathBuf::from("prompts/b.prompt.md")]);
+        assert_eq!(status.code_new, vec![PathBuf::from("code.lock/a.py")]);
+        let stats = repo.diff_prompt_stats(Some((&from, &to))).unwrap();
+        assert_eq!(stats.len(), 3);
+
+        // A merge base of a commit and its descendant is the commit itself
+        assert_eq!(repo.resolve_range("HEAD~1...HEAD").unwrap(), (from, to));
+        assert!(repo.resolve_range("HEAD~1").is_err());
+        assert!(repo.resolve_range("HEAD..nope").is_err());
+    }
+
     #[test]
     fn test_checkout_ref() {
         let dir = tempfile::tempdir().unwrap();
@@ -655,7 +1778,7 @@ mod tests {
         assert_eq!(content, "version 2");
 
         // Checkout first commit
-        let checked_out = repo.checkout_ref(&first_hash).unwrap();
+        let checked_out = repo.checkout_ref(&first_hash, false).unwrap();
         assert_eq!(checked_out, first_hash);
 
         // File should be version 1
@@ -663,6 +1786,62 @@ mod tests {
         assert_eq!(content, "version 1");
     }
 
+    #[test]
+    fn test_restore_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::create_dir_all(dir.path().join("prompts")).unwrap();
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "a").unwrap();
+        std::fs::write(dir.path().join("prompts/b.prompt.md"), "b").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("First").unwrap();
+        assert!(repo.changed_paths().unwrap().is_empty());
+
+        std::fs::write(dir.path().join("prompts/a.prompt.md"), "a2").unwrap();
+        std::fs::remove_file(dir.path().join("prompts/b.prompt.md")).unwrap();
+        std::fs::write(dir.path().join("prompts/c.prompt.md"), "c").unwrap();
+        std::fs::write(dir.path().join("prompts/d.prompt.md"), "d").unwrap();
+        repo.stage_file(Path::new("prompts/a.prompt.md")).unwrap();
+        repo.stage_file(Path::new("prompts/d.prompt.md")).unwrap();
+        let changed = repo.changed_paths().unwrap();
+        assert_eq!(
+            changed,
+            ["a", "b", "c", "d"].map(|n| PathBuf::from(format!("prompts/{}.prompt.md", n)))
+        );
+
+        repo.restore_paths(&changed).unwrap();
+        assert!(repo.changed_paths().unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(dir.path().join("prompts/a.prompt.md")).unwrap(), "a");
+        assert_eq!(std::fs::read_to_string(dir.path().join("prompts/b.prompt.md")).unwrap(), "b");
+        assert!(!dir.path().join("prompts/c.prompt.md").exists());
+    }
+
+    #[test]
+    fn test_checkout_refuses_to_discard_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("file.txt"), "version 1").unwrap();
+        repo.stage_file(Path::new("file.txt")).unwrap();
+        let first_hash = repo.commit("First").unwrap();
+        std::fs::write(dir.path().join("file.txt"), "version 2").unwrap();
+        std::fs::write(dir.path().join("added.txt"), "added").unwrap();
+        repo.stage_all().unwrap();
+        repo.commit("Second").unwrap();
+
+        // Untracked files the target doesn't have are safe
+        std::fs::write(dir.path().join("file.txt"), "hand edit").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "scratch").unwrap();
+        assert_eq!(repo.checkout_conflicts(&first_hash).unwrap(), vec![PathBuf::from("file.txt")]);
+
+        let err = repo.checkout_ref(&first_hash, false).unwrap_err().to_string();
+        assert!(err.contains("1 file(s):\n  file.txt"), "{}", err);
+        assert_eq!(std::fs::read_to_string(dir.path().join("file.txt")).unwrap(), "hand edit");
+
+        repo.checkout_ref(&first_hash, true).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("file.txt")).unwrap(), "version 1");
+        assert_eq!(std::fs::read_to_string(dir.path().join("notes.txt")).unwrap(), "scratch");
+    }
+
     #[test]
     fn test_write_gitignore() {
         let dir = tempfile::tempdir().unwrap();
@@ -674,6 +1853,27 @@ mod tests {
         assert!(content.contains("__pycache__/"));
     }
 
+    #[test]
+    fn test_sync_gitignore_keeps_user_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LitRepo::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "node_modules/\n/.lit/cache\n.env").unwrap();
+
+        let missing = repo.missing_gitignore_entries().unwrap();
+        assert!(!missing.contains(&".lit/cache/"));
+        assert!(missing.contains(&".lit/logs/"));
+
+        let added = repo.sync_gitignore().unwrap();
+        assert_eq!(added, missing);
+        let content = std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert!(content.starts_with("node_modules/\n/.lit/cache\n.env\n"));
+        assert!(content.contains(".lit/backups/\n"));
+
+        // Already in sync: nothing changes
+        assert!(repo.sync_gitignore().unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(dir.path().join(".gitignore")).unwrap(), content);
+    }
+
     #[test]
     fn test_repo_status_has_changes() {
         let status = RepoStatus {
@@ -0,0 +1,153 @@
+//! Structured run log: `tracing` events from lit's own modules, written as
+//! JSON lines to the file given with `--log-file` (e.g.
+//! `.lit/logs/run.jsonl`).
+//!
+//! Nothing is recorded without `--log-file`; the `tracing` macros in the
+//! core modules are then no-ops.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Events from this crate's modules only; HTTP and runtime crates log too
+const TARGET_PREFIX: &str = "lit";
+
+/// Send `tracing` events to `path`, appending one JSON object per line
+pub fn init(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let file = File::options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    tracing::subscriber::set_global_default(JsonLines::new(file))
+        .context("A log subscriber is already installed")?;
+    tracing::info!(command = %std::env::args().skip(1).collect::<Vec<_>>().join(" "), "run started");
+    Ok(())
+}
+
+/// Writes each event as `{"timestamp", "level", "target", "message", ...fields}`
+pub struct JsonLines<W: Write> {
+    writer: Mutex<W>,
+    next_span: AtomicU64,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            next_span: AtomicU64::new(1),
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> Subscriber for JsonLines<W> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= Level::DEBUG && metadata.target().starts_with(TARGET_PREFIX)
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = JsonFields(Map::new());
+        fields.0.insert("timestamp".into(), chrono::Utc::now().to_rfc3339().into());
+        fields.0.insert("level".into(), metadata.level().as_str().into());
+        fields.0.insert("target".into(), metadata.target().into());
+        event.record(&mut fields);
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        // A log that can't be written must not fail the run it describes
+        let _ = writeln!(writer, "{}", Value::Object(fields.0));
+        let _ = writer.flush();
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Collects an event's fields, keeping numbers and booleans typed
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A writer the test can read back after the subscriber is done with it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let buffer = Shared::default();
+        tracing::subscriber::with_default(JsonLines::new(buffer.clone()), || {
+            tracing::info!(prompt = "prompts/a.prompt.md", latency_ms = 1200u64, cached = false, "generation finished");
+            tracing::trace!("too detailed to record");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "generation finished");
+        assert_eq!(lines[0]["prompt"], "prompts/a.prompt.md");
+        assert_eq!(lines[0]["latency_ms"], 1200);
+        assert_eq!(lines[0]["cached"], false);
+        assert!(lines[0]["target"].as_str().unwrap().starts_with("lit"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::core::atomic;
+
+/// Directory of in-progress session state, under `.lit/` (gitignored)
+pub const SESSIONS_DIR: &str = "sessions";
+
+/// In-progress state of interactive commands, in `.lit/sessions/<name>.json`.
+///
+/// Interactive flows save their progress after every step, so re-running the
+/// command after a dropped terminal can offer to resume instead of starting
+/// over (and paying for the same generations again). A session is cleared
+/// when its flow finishes.
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(lit_dir: &Path) -> Self {
+        Self {
+            dir: lit_dir.join(SESSIONS_DIR),
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Load a saved session, if there is one. An unreadable session (e.g.
+    /// from an older lit version) is treated as absent.
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let content = std::fs::read_to_string(self.path(name)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save a session, replacing any earlier state
+    pub fn save<T: Serialize>(&self, name: &str, state: &T) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.path(name);
+        atomic::write(&path, serde_json::to_string_pretty(state)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Remove a finished or abandoned session
+    pub fn clear(&self, name: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove session {}", name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::new(dir.path());
+        assert!(store.load::<BTreeMap<String, String>>("review").is_none());
+
+        let state = BTreeMap::from([("prompts/user.prompt.md".to_string(), "skip".to_string())]);
+        store.save("review", &state).unwrap();
+        assert_eq!(store.load::<BTreeMap<String, String>>("review"), Some(state));
+
+        std::fs::write(dir.path().join(SESSIONS_DIR).join("review.json"), "{not json").unwrap();
+        assert!(store.load::<BTreeMap<String, String>>("review").is_none());
+
+        store.clear("review").unwrap();
+        store.clear("review").unwrap();
+        assert!(store.load::<BTreeMap<String, String>>("review").is_none());
+    }
+}
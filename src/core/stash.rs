@@ -0,0 +1,121 @@
+//! `lit stash`: park uncommitted prompt edits and patches, then bring them
+//! back. Only prompt directories and .lit/patches/ are stashed; code.lock/
+//! is regenerated from prompts and so is left alone.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic;
+
+/// Stashed prompt work, under `.lit/` (local only, gitignored)
+pub const STASH_FILE: &str = "stash.json";
+
+/// Uncommitted prompt and patch changes parked with `lit stash`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    /// HEAD when the changes were stashed, if there was a commit
+    #[serde(default)]
+    pub base: Option<String>,
+    /// Each stashed file (relative to the repo root) and its content, or
+    /// None if the change was deleting it
+    pub files: BTreeMap<PathBuf, Option<String>>,
+}
+
+/// The stash stack: `stash@{0}` is the most recent entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stash {
+    #[serde(default)]
+    pub entries: Vec<StashEntry>,
+}
+
+impl Stash {
+    /// Load `.lit/stash.json`; empty when nothing is stashed
+    pub fn load(lit_dir: &Path) -> Result<Self> {
+        let path = lit_dir.join(STASH_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write `.lit/stash.json`, removing it once the stash is empty
+    pub fn save(&self, lit_dir: &Path) -> Result<()> {
+        let path = lit_dir.join(STASH_FILE);
+        if self.entries.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            return Ok(());
+        }
+        std::fs::create_dir_all(lit_dir)
+            .with_context(|| format!("Failed to create {}", lit_dir.display()))?;
+        atomic::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Put an entry on top of the stack, as `stash@{0}`
+    pub fn push(&mut self, entry: StashEntry) {
+        self.entries.insert(0, entry);
+    }
+
+    pub fn get(&self, index: usize) -> Result<&StashEntry> {
+        match self.entries.get(index) {
+            Some(entry) => Ok(entry),
+            None if self.entries.is_empty() => bail!("No stash entries"),
+            None => bail!("No stash@{{{}}}; there are {} entries", index, self.entries.len()),
+        }
+    }
+
+    /// Remove and return `stash@{index}`
+    pub fn remove(&mut self, index: usize) -> Result<StashEntry> {
+        self.get(index)?;
+        Ok(self.entries.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(message: &str) -> StashEntry {
+        StashEntry {
+            message: message.to_string(),
+            created_at: Utc::now(),
+            base: None,
+            files: BTreeMap::from([
+                (PathBuf::from("prompts/user.prompt.md"), Some("edited".to_string())),
+                (PathBuf::from("prompts/old.prompt.md"), None),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_stash_stack() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut stash = Stash::load(dir.path()).unwrap();
+        assert!(stash.get(0).is_err());
+
+        stash.push(entry("first"));
+        stash.push(entry("second"));
+        stash.save(dir.path()).unwrap();
+
+        let mut stash = Stash::load(dir.path()).unwrap();
+        assert_eq!(stash.get(0).unwrap().message, "second");
+        assert_eq!(stash.get(1).unwrap().files, entry("first").files);
+        assert!(stash.get(2).unwrap_err().to_string().contains("there are 2 entries"));
+
+        assert_eq!(stash.remove(0).unwrap().message, "second");
+        assert_eq!(stash.remove(0).unwrap().message, "first");
+        stash.save(dir.path()).unwrap();
+        assert!(!dir.path().join(STASH_FILE).exists());
+    }
+}
@@ -0,0 +1,53 @@
+use tiktoken_rs::o200k_base_singleton;
+
+/// Fraction of the context window above which the generator warns
+pub const CONTEXT_WARN_RATIO: f64 = 0.8;
+
+/// Estimate the number of tokens in a piece of text.
+///
+/// Uses the o200k BPE tokenizer. This is exact for recent OpenAI models and a
+/// close approximation for Claude, which is good enough for budgeting.
+pub fn estimate_tokens(text: &str) -> u64 {
+    if text.is_empty() {
+        return 0;
+    }
+    o200k_base_singleton().lock().encode_ordinary(text).len() as u64
+}
+
+/// Get the context window (in tokens) for a known model.
+///
+/// Falls back to a conservative 128k for unknown models. Override in lit.toml
+/// with `[model] context_window = ...`.
+pub fn get_context_window(model: &str) -> u64 {
+    match model {
+        m if m.starts_with("claude") => 200_000,
+        m if m.contains("gpt-4o") || m.contains("gpt-4-turbo") => 128_000,
+        m if m.starts_with("gpt-4.1") => 1_047_576,
+        m if m.starts_with("gpt-4") => 8_192,
+        _ => 128_000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        let short = estimate_tokens("def greet(name: str) -> str:");
+        assert!(short > 0 && short < 20, "got {}", short);
+
+        // Roughly linear in input size
+        let long = estimate_tokens(&"def greet(name: str) -> str:\n".repeat(100));
+        assert!(long > short * 50, "got {}", long);
+    }
+
+    #[test]
+    fn test_context_windows() {
+        assert_eq!(get_context_window("claude-sonnet-4-5-20250929"), 200_000);
+        assert_eq!(get_context_window("gpt-4o-mini"), 128_000);
+        assert_eq!(get_context_window("gpt-4"), 8_192);
+        assert_eq!(get_context_window("some-future-model"), 128_000);
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic;
+
+/// Transcripts directory, under `.lit/`
+pub const TRANSCRIPTS_DIR: &str = "transcripts";
+
+/// The exact request sent for one generation and the raw response, saved
+/// when `[logging] transcripts = true`.
+///
+/// Keyed by input hash, so the transcript for a prompt's current inputs can
+/// be found again; a retry with the same inputs overwrites the earlier one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub prompt: PathBuf,
+    pub input_hash: String,
+    pub timestamp: DateTime<Utc>,
+    /// Model that answered (the last one tried in a fallback chain)
+    pub model: String,
+    pub temperature: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub context: String,
+    /// The response exactly as returned, before it was split into files
+    pub response: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    /// Whether the response came from a batch job
+    #[serde(default)]
+    pub batch: bool,
+}
+
+/// Transcripts stored as `.lit/transcripts/<input-hash>.json`
+#[derive(Debug, Clone)]
+pub struct TranscriptStore {
+    dir: PathBuf,
+}
+
+impl TranscriptStore {
+    pub fn new(lit_dir: &Path) -> Self {
+        Self { dir: lit_dir.join(TRANSCRIPTS_DIR) }
+    }
+
+    fn path(&self, input_hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", input_hash))
+    }
+
+    /// Write a transcript, replacing any earlier one for the same input hash
+    pub fn save(&self, transcript: &Transcript) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.path(&transcript.input_hash);
+        atomic::write(&path, serde_json::to_string_pretty(transcript)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The transcript for an input hash, if one was saved
+    pub fn load(&self, input_hash: &str) -> Result<Option<Transcript>> {
+        let path = self.path(input_hash);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let transcript = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(transcript))
+    }
+
+    /// The most recent transcript for a prompt, whatever its inputs were.
+    /// Unreadable files are skipped.
+    pub fn latest_for(&self, prompt: &Path) -> Result<Option<Transcript>> {
+        if !self.dir.exists() {
+            return Ok(None);
+        }
+        let mut latest: Option<Transcript> = None;
+        for entry in std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read {}", self.dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(transcript) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Transcript>(&content).ok())
+            else {
+                continue;
+            };
+            if transcript.prompt == prompt
+                && latest.as_ref().is_none_or(|l| transcript.timestamp > l.timestamp)
+            {
+                latest = Some(transcript);
+            }
+        }
+        Ok(latest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(prompt: &str, input_hash: &str, timestamp: DateTime<Utc>) -> Transcript {
+        Transcript {
+            prompt: PathBuf::from(prompt),
+            input_hash: input_hash.to_string(),
+            timestamp,
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            temperature: 0.0,
+            seed: None,
+            system_prompt: "You are a code generator.".to_string(),
+            user_prompt: "# User\n".to_string(),
+            context: String::new(),
+            response: "=== FILE: src/user.py ===\nclass User: ...\n=== END FILE ===".to_string(),
+            tokens_in: 100,
+            tokens_out: 20,
+            batch: false,
+        }
+    }
+
+    #[test]
+    fn test_transcript_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TranscriptStore::new(dir.path());
+        assert!(store.load("abc").unwrap().is_none());
+        assert!(store.latest_for(Path::new("prompts/user.prompt.md")).unwrap().is_none());
+
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+        store.save(&transcript("prompts/user.prompt.md", "abc", earlier)).unwrap();
+        store.save(&transcript("prompts/user.prompt.md", "def", Utc::now())).unwrap();
+        store.save(&transcript("prompts/base.prompt.md", "ghi", Utc::now())).unwrap();
+        assert!(dir.path().join(TRANSCRIPTS_DIR).join("abc.json").exists());
+
+        let loaded = store.load("abc").unwrap().unwrap();
+        assert_eq!(loaded.prompt, PathBuf::from("prompts/user.prompt.md"));
+        assert!(loaded.response.contains("class User"));
+
+        let latest = store.latest_for(Path::new("prompts/user.prompt.md")).unwrap().unwrap();
+        assert_eq!(latest.input_hash, "def");
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// Workspace file at the root of a repo holding several lit projects
+pub const WORKSPACE_FILE: &str = "lit-workspace.toml";
+
+/// A set of lit projects (members) in one repo, each with its own lit.toml,
+/// prompts/, code.lock/ and .lit/.
+///
+/// ```toml
+/// [workspace]
+/// members = ["backend", "frontend"]
+/// ```
+///
+/// Members are isolated: prompts can only import prompts of their own
+/// member. Workspace-aware commands run from the workspace root (outside
+/// every member) run once per member.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// Member directories relative to `root`, in the order listed
+    pub members: Vec<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceFile {
+    workspace: WorkspaceSection,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceSection {
+    #[serde(default)]
+    members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Parse and validate a workspace file's content
+    pub fn from_str(content: &str, root: &Path) -> Result<Self> {
+        let file: WorkspaceFile =
+            toml::from_str(content).with_context(|| format!("Failed to parse {}", WORKSPACE_FILE))?;
+        let mut members: Vec<PathBuf> = Vec::new();
+        for member in file.workspace.members {
+            if member.is_absolute()
+                || member.components().any(|c| !matches!(c, Component::Normal(_)))
+            {
+                bail!(
+                    "Invalid member '{}' in {}. Members must be relative paths inside the workspace",
+                    member.display(),
+                    WORKSPACE_FILE
+                );
+            }
+            if members.contains(&member) {
+                bail!("Duplicate member '{}' in {}", member.display(), WORKSPACE_FILE);
+            }
+            members.push(member);
+        }
+        if members.is_empty() {
+            bail!("No members listed under [workspace] in {}", WORKSPACE_FILE);
+        }
+        Ok(Self { root: root.to_path_buf(), members })
+    }
+
+    /// Load the workspace file in `root`, checking every member has a lit.toml
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(WORKSPACE_FILE);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let workspace = Self::from_str(&content, root)?;
+        for member in &workspace.members {
+            if !root.join(member).join("lit.toml").exists() {
+                bail!(
+                    "Workspace member '{}' has no lit.toml.\n\
+                     Hint: Run `lit init` in {} or remove it from {}.",
+                    member.display(),
+                    member.display(),
+                    WORKSPACE_FILE
+                );
+            }
+        }
+        Ok(workspace)
+    }
+
+    /// The workspace to run across from `start_dir`: one whose root is
+    /// `start_dir` or an ancestor, as long as `start_dir` is not inside a
+    /// lit project (commands inside a member run for that member only).
+    pub fn find(start_dir: &Path) -> Result<Option<Self>> {
+        let mut current = start_dir.to_path_buf();
+        loop {
+            if current.join(WORKSPACE_FILE).exists() {
+                return Self::load(&current).map(Some);
+            }
+            if current.join("lit.toml").exists() || !current.pop() {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn member_dirs(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.members.iter().map(|member| self.root.join(member))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workspace() {
+        let root = Path::new("/repo");
+        let ws = Workspace::from_str("[workspace]\nmembers = [\"backend\", \"apps/web\"]\n", root).unwrap();
+        assert_eq!(ws.members, vec![PathBuf::from("backend"), PathBuf::from("apps/web")]);
+        assert_eq!(ws.member_dirs().last(), Some(PathBuf::from("/repo/apps/web")));
+
+        for bad in [
+            "[workspace]\nmembers = []\n",
+            "[workspace]\nmembers = [\"../elsewhere\"]\n",
+            "[workspace]\nmembers = [\"/abs\"]\n",
+            "[workspace]\nmembers = [\"a\", \"a\"]\n",
+        ] {
+            assert!(Workspace::from_str(bad, root).is_err(), "accepted: {}", bad);
+        }
+    }
+
+    #[test]
+    fn test_find_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join(WORKSPACE_FILE), "[workspace]\nmembers = [\"backend\"]\n").unwrap();
+        std::fs::create_dir_all(root.join("backend/prompts")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+
+        // A member without lit.toml is an error
+        assert!(Workspace::find(root).is_err());
+
+        std::fs::write(root.join("backend/lit.toml"), "").unwrap();
+        assert_eq!(Workspace::find(root).unwrap().unwrap().root, root);
+        assert!(Workspace::find(&root.join("docs")).unwrap().is_some());
+        // Inside a member, commands apply to that member alone
+        assert!(Workspace::find(&root.join("backend/prompts")).unwrap().is_none());
+    }
+}
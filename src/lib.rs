@@ -1,2 +1,5 @@
+pub mod api;
 pub mod core;
 pub mod providers;
+
+pub use api::Lit;
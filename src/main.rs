@@ -1,12 +1,24 @@
+// `lit regenerate` and `lit commit` run through the library facade; the
+// rest of it is for library users
+#[allow(dead_code)]
+mod api;
 mod cli;
 mod core;
 mod providers;
 
 use clap::Parser;
 use cli::Cli;
+use core::interrupt::{EXIT_INTERRUPTED, Interrupted};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    cli.run().await
+    match cli.run().await {
+        // Exit the way a shell expects of a process stopped by Ctrl-C
+        Err(e) if e.downcast_ref::<Interrupted>().is_some() => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        result => result,
+    }
 }
@@ -1,11 +1,16 @@
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::{GenerationRequest, GenerationResponse, LlmProvider};
+use super::{
+    BATCH_POLL_INTERVAL, GenerationRequest, GenerationResponse, LlmProvider, PromptCacheTokens,
+    ProviderError, batch_id, collect_batch_results, is_retriable_status,
+};
 
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_BATCHES_URL: &str = "https://api.anthropic.com/v1/messages/batches";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 const MAX_TOKENS: u64 = 16384;
 
@@ -22,6 +27,46 @@ impl AnthropicProvider {
             api_key,
         }
     }
+
+    /// Send requests with this client (proxy, CA and timeout settings)
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send an authenticated request and return the response body, mapping
+    /// HTTP failures to provider errors
+    async fn request_text(&self, request: RequestBuilder) -> Result<String> {
+        let response = request
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .send()
+            .await
+            .map_err(|e| ProviderError::network(self.name(), &e))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| ProviderError::network(self.name(), &e))?;
+
+        if !status.is_success() {
+            return Err(classify_error(status.as_u16(), &body).into());
+        }
+        Ok(body)
+    }
+
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let body = self.request_text(request).await?;
+        serde_json::from_str(&body).with_context(|| {
+            format!(
+                "Failed to parse Anthropic API response: {}",
+                &body[..body.len().min(200)]
+            )
+        })
+    }
 }
 
 // ---------- API request/response types ----------
@@ -30,7 +75,7 @@ impl AnthropicProvider {
 struct ApiRequest {
     model: String,
     max_tokens: u64,
-    system: String,
+    system: Vec<TextBlock>,
     messages: Vec<ApiMessage>,
     temperature: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -40,7 +85,40 @@ struct ApiRequest {
 #[derive(Debug, Serialize)]
 struct ApiMessage {
     role: String,
-    content: String,
+    content: Vec<TextBlock>,
+}
+
+#[derive(Debug, Serialize)]
+struct TextBlock {
+    #[serde(rename = "type")]
+    block_type: &'static str,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+impl TextBlock {
+    fn text(text: String) -> Self {
+        Self {
+            block_type: "text",
+            text,
+            cache_control: None,
+        }
+    }
+
+    /// A block that ends a cacheable prefix of the request
+    fn cached(text: String) -> Self {
+        Self {
+            cache_control: Some(CacheControl { cache_type: "ephemeral" }),
+            ..Self::text(text)
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    cache_type: &'static str,
 }
 
 #[derive(Debug, Serialize)]
@@ -65,8 +143,22 @@ struct ContentBlock {
 
 #[derive(Debug, Deserialize)]
 struct Usage {
+    /// Input tokens outside the prompt cache
     input_tokens: u64,
     output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u64>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u64>,
+}
+
+impl Usage {
+    fn prompt_cache(&self) -> PromptCacheTokens {
+        PromptCacheTokens {
+            read: self.cache_read_input_tokens.unwrap_or(0),
+            write: self.cache_creation_input_tokens.unwrap_or(0),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -81,120 +173,328 @@ struct ApiErrorDetail {
     message: String,
 }
 
+#[derive(Debug, Serialize)]
+struct BatchRequest {
+    requests: Vec<BatchItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchItem {
+    custom_id: String,
+    params: ApiRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchStatus {
+    id: String,
+    /// "in_progress", "canceling" or "ended"
+    processing_status: String,
+    request_counts: BatchCounts,
+    results_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchCounts {
+    processing: u64,
+}
+
+/// One line of a batch's results file
+#[derive(Debug, Deserialize)]
+struct BatchResultLine {
+    custom_id: String,
+    result: BatchResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum BatchResult {
+    Succeeded { message: ApiResponse },
+    Errored { error: ApiError },
+    Canceled,
+    Expired,
+}
+
 // ---------- LlmProvider implementation ----------
 
 #[async_trait]
 impl LlmProvider for AnthropicProvider {
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
-        let user_content = if request.context.is_empty() {
-            request.user_prompt.clone()
-        } else {
+        let api_request = build_api_request(&request);
+
+        let api_response: ApiResponse = self
+            .send(self.client.post(ANTHROPIC_API_URL).json(&api_request))
+            .await?;
+        into_response(api_response)
+    }
+
+    /// Submit the requests as a Message Batch (half price), poll until it
+    /// ends and fetch the results
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<Vec<Result<GenerationResponse>>> {
+        let len = requests.len();
+        let batch_request = BatchRequest {
+            requests: requests
+                .iter()
+                .enumerate()
+                .map(|(i, request)| BatchItem {
+                    custom_id: batch_id(i),
+                    params: build_api_request(request),
+                })
+                .collect(),
+        };
+        let mut batch: BatchStatus = self
+            .send(self.client.post(ANTHROPIC_BATCHES_URL).json(&batch_request))
+            .await?;
+        eprintln!("    Submitted batch {} ({} request(s))", batch.id, len);
+
+        while batch.processing_status != "ended" {
+            tokio::time::sleep(BATCH_POLL_INTERVAL).await;
+            let url = format!("{}/{}", ANTHROPIC_BATCHES_URL, batch.id);
+            batch = self.send(self.client.get(&url)).await?;
+            let counts = &batch.request_counts;
+            eprintln!(
+                "    Batch {}: {} ({} of {} done)",
+                batch.id,
+                batch.processing_status,
+                len as u64 - counts.processing,
+                len
+            );
+        }
+
+        let results_url = batch.results_url.with_context(|| {
+            format!("Anthropic batch {} ended without a results URL", batch.id)
+        })?;
+        let body = self.request_text(self.client.get(&results_url)).await?;
+        Ok(collect_batch_results(self.name(), len, parse_batch_results(&body)?))
+    }
+
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+}
+
+/// Turn a Messages API response into a generation response
+fn into_response(api_response: ApiResponse) -> Result<GenerationResponse> {
+    let content = api_response
+        .content
+        .iter()
+        .filter(|block| block.block_type == "text")
+        .filter_map(|block| block.text.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if content.is_empty() {
+        return Err(ProviderError::new(
+            "anthropic",
+            "empty_response",
             format!(
-                "{}\n\n---\n\n## Context (generated code from imported prompts)\n\n{}\n",
-                request.user_prompt, request.context
+                "Anthropic API returned empty response (stop_reason: {:?})",
+                api_response.stop_reason
+            ),
+        )
+        .into());
+    }
+
+    let prompt_cache = api_response.usage.prompt_cache();
+    Ok(GenerationResponse {
+        content,
+        tokens_in: api_response.usage.input_tokens + prompt_cache.read + prompt_cache.write,
+        tokens_out: api_response.usage.output_tokens,
+        prompt_cache,
+        model: api_response.model,
+        batch: false,
+    })
+}
+
+/// Parse a batch results file (JSON lines) into a result per `custom_id`
+fn parse_batch_results(body: &str) -> Result<Vec<(String, Result<GenerationResponse>)>> {
+    let mut results = Vec::new();
+    for line in body.lines().filter(|l| !l.trim().is_empty()) {
+        let line: BatchResultLine = serde_json::from_str(line).with_context(|| {
+            format!("Failed to parse Anthropic batch result: {}", &line[..line.len().min(200)])
+        })?;
+        let result = match line.result {
+            BatchResult::Succeeded { message } => into_response(message),
+            BatchResult::Errored { error } => Err(ProviderError::new(
+                "anthropic",
+                &error.error.error_type,
+                format!(
+                    "Anthropic API error ({}): {}",
+                    error.error.error_type, error.error.message
+                ),
             )
+            .into()),
+            BatchResult::Canceled | BatchResult::Expired => Err(ProviderError::new(
+                "anthropic",
+                "batch_expired",
+                "The batch request was canceled or expired before it ran".to_string(),
+            )
+            .retriable(true)
+            .into()),
         };
+        results.push((line.custom_id, result));
+    }
+    Ok(results)
+}
 
-        let api_request = ApiRequest {
-            model: request.model.clone(),
-            max_tokens: MAX_TOKENS,
-            system: request.system_prompt.clone(),
-            messages: vec![ApiMessage {
-                role: "user".to_string(),
-                content: user_content,
-            }],
-            temperature: request.temperature,
-            metadata: None,
-        };
+/// Build the Messages API request.
+///
+/// The system prompt and the imported context are marked as cache
+/// breakpoints: they are shared by every prompt in a run and by reruns, so
+/// later requests read them from Anthropic's prompt cache at a fraction of
+/// the input price. The context goes before the prompt body so it stays
+/// part of the cached prefix. Blocks below the model's minimum cacheable
+/// size are simply not cached.
+fn build_api_request(request: &GenerationRequest) -> ApiRequest {
+    let mut content = Vec::new();
+    if !request.context.is_empty() {
+        content.push(TextBlock::cached(format!(
+            "## Context (generated code from imported prompts)\n\n{}\n",
+            request.context
+        )));
+    }
+    content.push(TextBlock::text(request.user_prompt.clone()));
 
-        let response = self
-            .client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&api_request)
-            .send()
-            .await
-            .context("Failed to send request to Anthropic API")?;
+    ApiRequest {
+        model: request.model.clone(),
+        max_tokens: MAX_TOKENS,
+        system: vec![TextBlock::cached(request.system_prompt.clone())],
+        messages: vec![ApiMessage {
+            role: "user".to_string(),
+            content,
+        }],
+        temperature: request.temperature,
+        metadata: None,
+    }
+}
 
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .context("Failed to read Anthropic API response body")?;
+/// Map a non-success HTTP response to a structured provider error
+fn classify_error(status: u16, body: &str) -> ProviderError {
+    let retriable = is_retriable_status(status);
 
-        if !status.is_success() {
-            // Try to parse error details
-            if let Ok(api_error) = serde_json::from_str::<ApiError>(&body) {
-                match api_error.error.error_type.as_str() {
-                    "authentication_error" => {
-                        bail!(
-                            "Anthropic API authentication failed. Check your API key.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    "rate_limit_error" => {
-                        bail!(
-                            "Anthropic API rate limit hit. Try again in a moment.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    "overloaded_error" => {
-                        bail!(
-                            "Anthropic API is overloaded. Try again shortly.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    _ => {
-                        bail!(
-                            "Anthropic API error ({}): {}",
-                            api_error.error.error_type,
-                            api_error.error.message
-                        );
-                    }
-                }
-            }
-
-            bail!(
+    // Try to parse error details
+    let Ok(api_error) = serde_json::from_str::<ApiError>(body) else {
+        return ProviderError::new(
+            "anthropic",
+            "http_error",
+            format!(
                 "Anthropic API returned HTTP {}: {}",
                 status,
                 &body[..body.len().min(500)]
-            );
-        }
+            ),
+        )
+        .with_status(status)
+        .retriable(retriable);
+    };
 
-        let api_response: ApiResponse = serde_json::from_str(&body).with_context(|| {
-            format!(
-                "Failed to parse Anthropic API response: {}",
-                &body[..body.len().min(200)]
-            )
-        })?;
+    let message = match api_error.error.error_type.as_str() {
+        "authentication_error" => format!(
+            "Anthropic API authentication failed. Check your API key.\n  {}",
+            api_error.error.message
+        ),
+        "rate_limit_error" => format!(
+            "Anthropic API rate limit hit. Try again in a moment.\n  {}",
+            api_error.error.message
+        ),
+        "overloaded_error" => format!(
+            "Anthropic API is overloaded. Try again shortly.\n  {}",
+            api_error.error.message
+        ),
+        _ => format!(
+            "Anthropic API error ({}): {}",
+            api_error.error.error_type, api_error.error.message
+        ),
+    };
 
-        // Extract text from content blocks
-        let content = api_response
-            .content
-            .iter()
-            .filter(|block| block.block_type == "text")
-            .filter_map(|block| block.text.as_deref())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if content.is_empty() {
-            bail!(
-                "Anthropic API returned empty response (stop_reason: {:?})",
-                api_response.stop_reason
-            );
-        }
+    ProviderError::new("anthropic", &api_error.error.error_type, message)
+        .with_status(status)
+        .retriable(retriable)
+}
 
-        Ok(GenerationResponse {
-            content,
-            tokens_in: api_response.usage.input_tokens,
-            tokens_out: api_response.usage.output_tokens,
-            model: api_response.model,
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_rate_limit() {
+        let body = r#"{"type":"error","error":{"type":"rate_limit_error","message":"slow down"}}"#;
+        let err = classify_error(429, body);
+        assert_eq!(err.provider, "anthropic");
+        assert_eq!(err.status, Some(429));
+        assert_eq!(err.error_type, "rate_limit_error");
+        assert!(err.retriable);
+        assert!(err.message.contains("slow down"));
     }
 
-    fn name(&self) -> &str {
-        "anthropic"
+    #[test]
+    fn test_request_marks_cache_breakpoints() {
+        let request = GenerationRequest {
+            system_prompt: "You are a senior engineer.".to_string(),
+            context: "class Base: ...".to_string(),
+            user_prompt: "# User".to_string(),
+            provider: "anthropic".to_string(),
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            temperature: 0.0,
+            seed: None,
+            json: false,
+        };
+        let json = serde_json::to_value(build_api_request(&request)).unwrap();
+        assert_eq!(json["system"][0]["cache_control"]["type"], "ephemeral");
+        let content = &json["messages"][0]["content"];
+        assert!(content[0]["text"].as_str().unwrap().contains("class Base: ..."));
+        assert_eq!(content[0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(content[1]["text"], "# User");
+        assert!(content[1].get("cache_control").is_none());
+
+        let without_context = GenerationRequest {
+            context: String::new(),
+            ..request
+        };
+        let json = serde_json::to_value(build_api_request(&without_context)).unwrap();
+        assert_eq!(json["messages"][0]["content"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_usage_prompt_cache() {
+        let usage: Usage = serde_json::from_str(
+            r#"{"input_tokens":50,"output_tokens":20,"cache_creation_input_tokens":0,"cache_read_input_tokens":3000}"#,
+        )
+        .unwrap();
+        assert_eq!(usage.prompt_cache(), PromptCacheTokens { read: 3000, write: 0 });
+
+        let usage: Usage = serde_json::from_str(r#"{"input_tokens":50,"output_tokens":20}"#).unwrap();
+        assert_eq!(usage.prompt_cache(), PromptCacheTokens::default());
+    }
+
+    #[test]
+    fn test_parse_batch_results() {
+        let body = concat!(
+            r#"{"custom_id":"req-1","result":{"type":"succeeded","message":{"content":[{"type":"text","text":"hi"}],"model":"claude-sonnet-4-5","usage":{"input_tokens":5,"output_tokens":2},"stop_reason":"end_turn"}}}"#,
+            "\n",
+            r#"{"custom_id":"req-0","result":{"type":"errored","error":{"type":"error","error":{"type":"invalid_request_error","message":"too long"}}}}"#,
+            "\n",
+            r#"{"custom_id":"req-2","result":{"type":"expired"}}"#,
+            "\n",
+        );
+        let results = collect_batch_results("anthropic", 3, parse_batch_results(body).unwrap());
+        let err = results[0].as_ref().unwrap_err().downcast_ref::<ProviderError>().unwrap();
+        assert_eq!(err.error_type, "invalid_request_error");
+        assert_eq!(results[1].as_ref().unwrap().content, "hi");
+        let err = results[2].as_ref().unwrap_err().downcast_ref::<ProviderError>().unwrap();
+        assert!(err.retriable);
+    }
+
+    #[test]
+    fn test_classify_auth_and_unparseable() {
+        let body = r#"{"type":"error","error":{"type":"authentication_error","message":"bad key"}}"#;
+        let err = classify_error(401, body);
+        assert_eq!(err.error_type, "authentication_error");
+        assert!(!err.retriable);
+
+        let err = classify_error(502, "<html>Bad Gateway</html>");
+        assert_eq!(err.error_type, "http_error");
+        assert!(err.retriable);
     }
 }
@@ -1,33 +1,570 @@
 pub mod anthropic;
 pub mod openai;
+pub mod pacer;
+pub mod setup;
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 /// Trait for LLM providers
 #[async_trait]
 #[allow(dead_code)]
 pub trait LlmProvider: Send + Sync {
     async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse>;
+
+    /// Generate independent requests as one batch job, returning a result
+    /// per request in order. The outer error is for a batch that could not
+    /// be run at all.
+    ///
+    /// Providers with a discounted batch API submit the requests and poll
+    /// until the job ends; the rest generate them one at a time.
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> anyhow::Result<Vec<anyhow::Result<GenerationResponse>>> {
+        Ok(generate_each(self, requests).await)
+    }
+
     fn name(&self) -> &str;
 }
 
+/// Generate requests one at a time (for providers without a batch API)
+pub async fn generate_each<P: LlmProvider + ?Sized>(
+    provider: &P,
+    requests: Vec<GenerationRequest>,
+) -> Vec<anyhow::Result<GenerationResponse>> {
+    let mut results = Vec::new();
+    for request in requests {
+        results.push(provider.generate(request).await);
+    }
+    results
+}
+
+/// The HTTP client for a provider's requests: through `proxy_url` when set,
+/// trusting the PEM certificates in `ca_bundle` besides the system's, and
+/// abandoning requests after `timeout`
+pub fn http_client(
+    proxy_url: Option<&str>,
+    ca_bundle: Option<&std::path::Path>,
+    timeout: Option<std::time::Duration>,
+) -> anyhow::Result<reqwest::Client> {
+    use anyhow::Context;
+
+    let mut builder = reqwest::Client::builder();
+    if let Some(url) = proxy_url {
+        let proxy = reqwest::Proxy::all(url).with_context(|| format!("Invalid proxy_url '{}'", url))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = ca_bundle {
+        let pem = std::fs::read(path).with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Failed to parse CA bundle {}", path.display()))?;
+        if certificates.is_empty() {
+            anyhow::bail!("CA bundle {} has no PEM certificates", path.display());
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder.build().context("Failed to build the HTTP client")
+}
+
+/// Timeout for lit's own web requests, see [`service_client`]
+const SERVICE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The HTTP client for lit's requests other than to LLM providers (forge
+/// APIs, budget webhooks): built once, with a timeout and lit's User-Agent
+pub fn service_client() -> anyhow::Result<reqwest::Client> {
+    use anyhow::Context;
+
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(SERVICE_TIMEOUT)
+        .user_agent(concat!("lit/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to build the HTTP client")?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+/// How often a submitted batch job is polled for completion
+pub const BATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// `custom_id` of the request at `index` in a batch job
+fn batch_id(index: usize) -> String {
+    format!("req-{}", index)
+}
+
+/// Index of the request a batch result's `custom_id` refers to
+fn batch_index(custom_id: &str, len: usize) -> Option<usize> {
+    custom_id
+        .strip_prefix("req-")?
+        .parse()
+        .ok()
+        .filter(|&i| i < len)
+}
+
+/// Order batch results by request, reporting requests the job returned
+/// nothing for
+fn collect_batch_results(
+    provider: &str,
+    len: usize,
+    results: impl IntoIterator<Item = (String, anyhow::Result<GenerationResponse>)>,
+) -> Vec<anyhow::Result<GenerationResponse>> {
+    let mut ordered: Vec<Option<anyhow::Result<GenerationResponse>>> =
+        (0..len).map(|_| None).collect();
+    for (custom_id, result) in results {
+        if let Some(i) = batch_index(&custom_id, len) {
+            ordered[i] = Some(result);
+        }
+    }
+    ordered
+        .into_iter()
+        .map(|result| {
+            let result = result.map(|r| r.map(|response| GenerationResponse { batch: true, ..response }));
+            result.unwrap_or_else(|| {
+                Err(ProviderError::new(
+                    provider,
+                    "batch_missing_result",
+                    "The batch job returned no result for this request".to_string(),
+                )
+                .retriable(true)
+                .into())
+            })
+        })
+        .collect()
+}
+
 /// Request to an LLM provider
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct GenerationRequest {
     pub system_prompt: String,
     pub context: String,
     pub user_prompt: String,
+    /// Provider to send the request to (see [`ProviderRegistry`])
+    pub provider: String,
     pub model: String,
     pub temperature: f64,
     pub seed: Option<u64>,
+    /// Ask for a JSON object response (`[generation] response_format = "json"`)
+    pub json: bool,
+}
+
+/// Whether a provider has a JSON mode that [`GenerationRequest::json`] turns on
+pub fn has_json_mode(provider: &str) -> bool {
+    matches!(provider, "openai" | "azure" | "openrouter")
 }
 
 /// Response from an LLM provider
 #[derive(Debug)]
 pub struct GenerationResponse {
     pub content: String,
+    /// Input tokens, including any read from or written to the prompt cache
     pub tokens_in: u64,
     pub tokens_out: u64,
+    /// Part of `tokens_in` served by the provider's prompt cache
+    pub prompt_cache: PromptCacheTokens,
     pub model: String,
+    /// Whether the response came from a discounted batch job
+    pub batch: bool,
+}
+
+/// Input tokens read from and written to a provider's prompt cache
+/// (Anthropic `cache_control` blocks), which are billed at different rates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptCacheTokens {
+    /// Tokens served from the cache
+    pub read: u64,
+    /// Tokens written to the cache
+    pub write: u64,
+}
+
+impl std::ops::AddAssign for PromptCacheTokens {
+    fn add_assign(&mut self, other: Self) {
+        self.read += other.read;
+        self.write += other.write;
+    }
+}
+
+/// Structured failure from an LLM provider.
+///
+/// Providers return this (wrapped in `anyhow::Error`) for HTTP and transport
+/// failures so the pipeline can decide whether to retry and record the
+/// failure in the generation record.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct ProviderError {
+    /// Provider name (e.g. "anthropic")
+    pub provider: String,
+    /// HTTP status, if a response was received
+    pub status: Option<u16>,
+    /// Error type reported by the API, or a local category
+    /// ("network", "invalid_response", "empty_response")
+    pub error_type: String,
+    /// Whether retrying the same request may succeed
+    pub retriable: bool,
+    /// Human-readable message
+    pub message: String,
+    /// Number of attempts made (updated by the generator's retry loop)
+    pub attempts: u32,
+}
+
+/// What went wrong with a provider request, for callers that handle
+/// failures by kind. Derived from the HTTP status when there is one, and
+/// from `error_type` otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    /// No API key, or no provider registered under the requested name
+    NotConfigured,
+    /// The API couldn't be reached
+    Network,
+    /// The API key was rejected (401/403)
+    Auth,
+    /// Throttled by the API (429)
+    RateLimit,
+    /// The API failed on its side (5xx)
+    Server,
+    /// The API rejected the request itself (other 4xx)
+    InvalidRequest,
+    /// The request doesn't fit the model's context window
+    ContextWindow,
+    /// The response couldn't be used: unparseable, empty, or missing
+    InvalidResponse,
+    Other,
+}
+
+impl ProviderError {
+    pub fn new(provider: &str, error_type: &str, message: String) -> Self {
+        Self {
+            provider: provider.to_string(),
+            status: None,
+            error_type: error_type.to_string(),
+            retriable: false,
+            message,
+            attempts: 1,
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn retriable(mut self, retriable: bool) -> Self {
+        self.retriable = retriable;
+        self
+    }
+
+    pub fn kind(&self) -> ProviderErrorKind {
+        match self.status {
+            Some(401 | 403) => return ProviderErrorKind::Auth,
+            Some(429) => return ProviderErrorKind::RateLimit,
+            Some(s) if s >= 500 => return ProviderErrorKind::Server,
+            Some(_) => return ProviderErrorKind::InvalidRequest,
+            None => {}
+        }
+        match self.error_type.as_str() {
+            "missing_api_key" | "unconfigured_provider" => ProviderErrorKind::NotConfigured,
+            "network" => ProviderErrorKind::Network,
+            "context_window_exceeded" => ProviderErrorKind::ContextWindow,
+            "invalid_response" | "empty_response" | "batch_missing_result" => ProviderErrorKind::InvalidResponse,
+            _ => ProviderErrorKind::Other,
+        }
+    }
+
+    /// Whether the next model in a prompt's `model:` chain may succeed where
+    /// this provider failed: missing or rejected credentials, rate limits,
+    /// server errors and unreachable APIs. Problems with the request itself
+    /// would fail on any provider.
+    pub fn allows_fallback(&self) -> bool {
+        matches!(
+            self.kind(),
+            ProviderErrorKind::NotConfigured
+                | ProviderErrorKind::Network
+                | ProviderErrorKind::Auth
+                | ProviderErrorKind::RateLimit
+                | ProviderErrorKind::Server
+        )
+    }
+
+    /// Transport failure before any response was received (always retriable)
+    pub fn network(provider: &str, err: &reqwest::Error) -> Self {
+        Self::new(provider, "network", format!("Failed to reach {} API: {}", provider, err))
+            .retriable(true)
+    }
+}
+
+/// Builds the real provider on the first request.
+///
+/// Resolving the API key is deferred to the first cache miss, so runs served
+/// entirely from cache work without one. A failed build is reported as a
+/// non-retriable `missing_api_key` error.
+pub struct LazyProvider {
+    name: String,
+    build: Box<dyn Fn() -> anyhow::Result<Box<dyn LlmProvider>> + Send + Sync>,
+    provider: std::sync::OnceLock<Box<dyn LlmProvider>>,
+}
+
+impl LazyProvider {
+    pub fn new(
+        name: &str,
+        build: impl Fn() -> anyhow::Result<Box<dyn LlmProvider>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            build: Box::new(build),
+            provider: std::sync::OnceLock::new(),
+        }
+    }
+
+    fn get(&self) -> anyhow::Result<&dyn LlmProvider> {
+        if let Some(provider) = self.provider.get() {
+            return Ok(provider.as_ref());
+        }
+        let provider = (self.build)().map_err(|e| {
+            ProviderError::new(&self.name, "missing_api_key", format!("{:#}", e))
+        })?;
+        Ok(self.provider.get_or_init(|| provider).as_ref())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for LazyProvider {
+    async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+        self.get()?.generate(request).await
+    }
+
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> anyhow::Result<Vec<anyhow::Result<GenerationResponse>>> {
+        self.get()?.generate_batch(requests).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Routes each request to the provider it names.
+///
+/// Holds one provider per configured name (usually [`LazyProvider`]s, so
+/// each API key is only needed once that provider is actually called).
+/// Requests for an unregistered provider fail with a non-retriable
+/// `unconfigured_provider` error.
+pub struct ProviderRegistry {
+    default: String,
+    providers: std::collections::BTreeMap<String, Box<dyn LlmProvider>>,
+}
+
+impl ProviderRegistry {
+    /// A registry whose requests without a provider go to `default`
+    pub fn new(default: &str) -> Self {
+        Self {
+            default: default.to_string(),
+            providers: std::collections::BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &str, provider: Box<dyn LlmProvider>) -> Self {
+        self.providers.insert(name.to_string(), provider);
+        self
+    }
+
+    /// The provider a request is for
+    fn route(&self, request: &GenerationRequest) -> Result<&dyn LlmProvider, ProviderError> {
+        let name = if request.provider.is_empty() { &self.default } else { &request.provider };
+        match self.providers.get(name) {
+            Some(provider) => Ok(provider.as_ref()),
+            None => Err(ProviderError::new(
+                name,
+                "unconfigured_provider",
+                format!(
+                    "Provider '{}' is not configured.\n\
+                     Hint: Add [providers.{}] with key_env to lit.toml.",
+                    name, name
+                ),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ProviderRegistry {
+    async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+        self.route(&request)?.generate(request).await
+    }
+
+    /// One batch job per provider, with results put back in request order
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> anyhow::Result<Vec<anyhow::Result<GenerationResponse>>> {
+        let mut results: Vec<Option<anyhow::Result<GenerationResponse>>> =
+            (0..requests.len()).map(|_| None).collect();
+        let mut groups: std::collections::BTreeMap<&str, Vec<(usize, GenerationRequest)>> =
+            std::collections::BTreeMap::new();
+        for (i, request) in requests.into_iter().enumerate() {
+            match self.route(&request) {
+                Ok(provider) => groups.entry(provider.name()).or_default().push((i, request)),
+                Err(e) => results[i] = Some(Err(e.into())),
+            }
+        }
+        for group in groups.into_values() {
+            let provider = self.route(&group[0].1)?;
+            let (indices, requests): (Vec<usize>, Vec<GenerationRequest>) = group.into_iter().unzip();
+            for (i, result) in indices.into_iter().zip(provider.generate_batch(requests).await?) {
+                results[i] = Some(result);
+            }
+        }
+        Ok(results.into_iter().map(|r| r.expect("every request has a result")).collect())
+    }
+
+    fn name(&self) -> &str {
+        &self.default
+    }
+}
+
+/// Whether an HTTP status is worth retrying (rate limits, timeouts, server errors)
+pub fn is_retriable_status(status: u16) -> bool {
+    matches!(status, 408 | 409 | 429) || status >= 500
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo(&'static str);
+
+    #[async_trait]
+    impl LlmProvider for Echo {
+        async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+            Ok(GenerationResponse {
+                content: self.0.to_string(),
+                tokens_in: 0,
+                tokens_out: 0,
+                prompt_cache: PromptCacheTokens::default(),
+                model: request.model,
+                batch: false,
+            })
+        }
+
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn request(provider: &str) -> GenerationRequest {
+        GenerationRequest {
+            system_prompt: String::new(),
+            context: String::new(),
+            user_prompt: String::new(),
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            temperature: 0.0,
+            seed: None,
+            json: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_routes_by_provider() {
+        let registry = ProviderRegistry::new("anthropic")
+            .with("anthropic", Box::new(Echo("anthropic")))
+            .with("openai", Box::new(Echo("openai")));
+
+        assert_eq!(registry.generate(request("openai")).await.unwrap().content, "openai");
+        assert_eq!(registry.generate(request("")).await.unwrap().content, "anthropic");
+
+        let err = registry.generate(request("mystery")).await.unwrap_err();
+        let err = err.downcast_ref::<ProviderError>().unwrap();
+        assert_eq!(err.error_type, "unconfigured_provider");
+        assert!(err.allows_fallback());
+        assert_eq!(err.kind(), ProviderErrorKind::NotConfigured);
+        let bad_request = ProviderError::new("openai", "invalid_request_error", String::new()).with_status(400);
+        assert_eq!(bad_request.kind(), ProviderErrorKind::InvalidRequest);
+        assert!(!bad_request.allows_fallback());
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let kind = |error_type: &str, status: Option<u16>| {
+            let err = ProviderError::new("anthropic", error_type, String::new());
+            match status {
+                Some(s) => err.with_status(s).kind(),
+                None => err.kind(),
+            }
+        };
+        assert_eq!(kind("authentication_error", Some(401)), ProviderErrorKind::Auth);
+        assert_eq!(kind("rate_limit_error", Some(429)), ProviderErrorKind::RateLimit);
+        assert_eq!(kind("overloaded_error", Some(529)), ProviderErrorKind::Server);
+        assert_eq!(kind("network", None), ProviderErrorKind::Network);
+        assert_eq!(kind("missing_api_key", None), ProviderErrorKind::NotConfigured);
+        assert_eq!(kind("context_window_exceeded", None), ProviderErrorKind::ContextWindow);
+        assert_eq!(kind("empty_response", None), ProviderErrorKind::InvalidResponse);
+        // Batch results carry the API's error type but no status
+        assert_eq!(kind("invalid_request_error", None), ProviderErrorKind::Other);
+    }
+
+    #[tokio::test]
+    async fn test_registry_batch_keeps_request_order() {
+        let registry = ProviderRegistry::new("anthropic")
+            .with("anthropic", Box::new(Echo("anthropic")))
+            .with("openai", Box::new(Echo("openai")));
+
+        let requests = vec![request("openai"), request(""), request("mystery"), request("openai")];
+        let results = registry.generate_batch(requests).await.unwrap();
+        let contents: Vec<_> = results
+            .iter()
+            .map(|r| r.as_ref().map(|r| r.content.as_str()).unwrap_or("error"))
+            .collect();
+        assert_eq!(contents, vec!["openai", "anthropic", "error", "openai"]);
+    }
+
+    #[test]
+    fn test_collect_batch_results() {
+        let response = |content: &str| GenerationResponse {
+            content: content.to_string(),
+            tokens_in: 0,
+            tokens_out: 0,
+            prompt_cache: PromptCacheTokens::default(),
+            model: "m".to_string(),
+            batch: false,
+        };
+        let results = collect_batch_results(
+            "anthropic",
+            3,
+            vec![
+                (batch_id(2), Ok(response("c"))),
+                (batch_id(0), Ok(response("a"))),
+                ("req-9".to_string(), Ok(response("stray"))),
+            ],
+        );
+        assert_eq!(results[0].as_ref().unwrap().content, "a");
+        assert!(results[0].as_ref().unwrap().batch);
+        assert_eq!(results[2].as_ref().unwrap().content, "c");
+        let err = results[1].as_ref().unwrap_err().downcast_ref::<ProviderError>().unwrap();
+        assert_eq!(err.error_type, "batch_missing_result");
+    }
+
+    #[test]
+    fn test_http_client_settings() {
+        let timeout = Some(std::time::Duration::from_secs(30));
+        assert!(http_client(Some("http://proxy.corp:8080"), None, timeout).is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.pem");
+        let err = http_client(None, Some(&missing), None).unwrap_err().to_string();
+        assert!(err.contains("Failed to read CA bundle"), "got: {}", err);
+        let empty = dir.path().join("empty.pem");
+        std::fs::write(&empty, "not a certificate\n").unwrap();
+        let err = http_client(None, Some(&empty), None).unwrap_err().to_string();
+        assert!(err.contains("no PEM certificates"), "got: {}", err);
+    }
 }
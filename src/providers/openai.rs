@@ -1,17 +1,37 @@
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use super::{GenerationRequest, GenerationResponse, LlmProvider};
+use super::{
+    BATCH_POLL_INTERVAL, GenerationRequest, GenerationResponse, LlmProvider, PromptCacheTokens,
+    ProviderError, batch_id, collect_batch_results, generate_each, is_retriable_status,
+};
 
-const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+const OPENROUTER_API_BASE: &str = "https://openrouter.ai/api/v1";
+/// Azure OpenAI `api-version` used when lit.toml doesn't set one
+pub const AZURE_API_VERSION: &str = "2024-10-21";
 const MAX_TOKENS: u64 = 16384;
+/// Endpoint every request in a batch is sent to
+const BATCH_ENDPOINT: &str = "/v1/chat/completions";
 
-/// OpenAI API provider (GPT-4o, GPT-4, etc.)
+/// Provider for the OpenAI chat completions API (GPT-4o, GPT-4, etc.) and
+/// the services that speak it: Azure OpenAI and OpenRouter
 pub struct OpenAiProvider {
     client: Client,
     api_key: String,
+    /// Provider name, for errors and the registry
+    name: &'static str,
+    /// Service name used in error messages
+    label: &'static str,
+    base_url: String,
+    /// Azure OpenAI: `api-version` query parameter, and the model is a
+    /// deployment name in the URL rather than a request field
+    azure_api_version: Option<String>,
+    headers: Vec<(String, String)>,
 }
 
 impl OpenAiProvider {
@@ -19,6 +39,73 @@ impl OpenAiProvider {
         Self {
             client: Client::new(),
             api_key,
+            name: "openai",
+            label: "OpenAI",
+            base_url: OPENAI_API_BASE.to_string(),
+            azure_api_version: None,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Azure OpenAI, at a resource endpoint such as
+    /// `https://<resource>.openai.azure.com`. Models are deployment names.
+    pub fn azure(api_key: String, endpoint: &str, api_version: &str) -> Self {
+        Self {
+            name: "azure",
+            label: "Azure OpenAI",
+            base_url: endpoint.to_string(),
+            azure_api_version: Some(api_version.to_string()),
+            ..Self::new(api_key)
+        }
+    }
+
+    /// OpenRouter, with models named `<vendor>/<model>`
+    pub fn openrouter(api_key: String) -> Self {
+        Self {
+            name: "openrouter",
+            label: "OpenRouter",
+            base_url: OPENROUTER_API_BASE.to_string(),
+            ..Self::new(api_key)
+        }
+    }
+
+    /// Send requests to another API base URL (up to, not including,
+    /// `/chat/completions`)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Send requests with this client (proxy, CA and timeout settings)
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Extra headers sent with every request
+    pub fn with_headers(mut self, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Chat completions URL for a model
+    fn url(&self, model: &str) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        match self.azure_api_version {
+            Some(ref version) => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                base, model, version
+            ),
+            None => format!("{}/chat/completions", base),
+        }
+    }
+
+    /// Authentication header: Azure takes the key as-is in `api-key`
+    fn auth_header(&self) -> (&'static str, String) {
+        if self.azure_api_version.is_some() {
+            ("api-key", self.api_key.clone())
+        } else {
+            ("Authorization", format!("Bearer {}", self.api_key))
         }
     }
 }
@@ -33,6 +120,8 @@ struct ApiRequest {
     temperature: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -78,109 +167,162 @@ struct ApiErrorDetail {
     error_type: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct FileObject {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchObject {
+    id: String,
+    status: String,
+    #[serde(default)]
+    request_counts: Option<BatchCounts>,
+    #[serde(default)]
+    output_file_id: Option<String>,
+    #[serde(default)]
+    error_file_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BatchCounts {
+    completed: u64,
+    failed: u64,
+}
+
+/// One line of a batch output or error file
+#[derive(Debug, Deserialize)]
+struct BatchOutputLine {
+    custom_id: String,
+    response: Option<BatchResponse>,
+    error: Option<ApiErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
 // ---------- LlmProvider implementation ----------
 
 #[async_trait]
 impl LlmProvider for OpenAiProvider {
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
-        let mut messages = Vec::new();
+        let api_request = build_api_request(&request);
+        let http_request = self.client.post(self.url(&request.model)).json(&api_request);
+        let api_response: ApiResponse = self.send(http_request).await?;
+        self.to_response(api_response)
+    }
 
-        // System message
-        messages.push(ApiMessage {
-            role: "system".to_string(),
-            content: request.system_prompt.clone(),
-        });
+    /// Run the requests through the Batch API (half price): upload them as
+    /// a JSON lines file, create a batch, poll until it ends and download
+    /// the output. Azure and OpenRouter generate one request at a time.
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<Vec<Result<GenerationResponse>>> {
+        if self.name != "openai" {
+            return Ok(generate_each(self, requests).await);
+        }
+        let len = requests.len();
+        let base = self.base_url.trim_end_matches('/');
 
-        // User message: prompt body + context
-        let user_content = if request.context.is_empty() {
-            request.user_prompt.clone()
-        } else {
-            format!(
-                "{}\n\n---\n\n## Context (generated code from imported prompts)\n\n{}\n",
-                request.user_prompt, request.context
+        let (content_type, upload) = batch_upload(&batch_input(&requests)?);
+        let file: FileObject = self
+            .send(
+                self.client
+                    .post(format!("{}/files", base))
+                    .header("Content-Type", content_type)
+                    .body(upload),
             )
-        };
+            .await?;
+        let mut batch: BatchObject = self
+            .send(self.client.post(format!("{}/batches", base)).json(&serde_json::json!({
+                "input_file_id": file.id,
+                "endpoint": BATCH_ENDPOINT,
+                "completion_window": "24h",
+            })))
+            .await?;
+        eprintln!("    Submitted batch {} ({} request(s))", batch.id, len);
 
-        messages.push(ApiMessage {
-            role: "user".to_string(),
-            content: user_content,
-        });
+        while !matches!(batch.status.as_str(), "completed" | "failed" | "expired" | "cancelled") {
+            tokio::time::sleep(BATCH_POLL_INTERVAL).await;
+            batch = self
+                .send(self.client.get(format!("{}/batches/{}", base, batch.id)))
+                .await?;
+            let counts = batch.request_counts.unwrap_or_default();
+            eprintln!(
+                "    Batch {}: {} ({} of {} done)",
+                batch.id,
+                batch.status,
+                counts.completed + counts.failed,
+                len
+            );
+        }
+        if batch.status == "failed" {
+            anyhow::bail!("OpenAI batch {} failed", batch.id);
+        }
 
-        let api_request = ApiRequest {
-            model: request.model.clone(),
-            max_tokens: MAX_TOKENS,
-            messages,
-            temperature: request.temperature,
-            seed: request.seed,
-        };
+        // Expired and cancelled batches still return what finished; the rest
+        // are reported as missing
+        let mut results = Vec::new();
+        for file_id in [batch.output_file_id, batch.error_file_id].into_iter().flatten() {
+            let url = format!("{}/files/{}/content", base, file_id);
+            let body = self.request_text(self.client.get(url)).await?;
+            for (custom_id, response) in parse_batch_output(&body)? {
+                let result = response.and_then(|r| self.to_response(r));
+                results.push((custom_id, result));
+            }
+        }
+        Ok(collect_batch_results(self.name, len, results))
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
 
-        let response = self
-            .client
-            .post(OPENAI_API_URL)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&api_request)
+impl OpenAiProvider {
+    /// Send an authenticated request and return the response body, mapping
+    /// HTTP failures to provider errors
+    async fn request_text(&self, request: RequestBuilder) -> Result<String> {
+        let (auth_name, auth_value) = self.auth_header();
+        let mut request = request.header(auth_name, auth_value);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request
             .send()
             .await
-            .context("Failed to send request to OpenAI API")?;
+            .map_err(|e| ProviderError::network(self.name(), &e))?;
 
         let status = response.status();
         let body = response
             .text()
             .await
-            .context("Failed to read OpenAI API response body")?;
+            .map_err(|e| ProviderError::network(self.name(), &e))?;
 
         if !status.is_success() {
-            if let Ok(api_error) = serde_json::from_str::<ApiError>(&body) {
-                let error_type = api_error
-                    .error
-                    .error_type
-                    .as_deref()
-                    .unwrap_or("unknown");
-
-                match error_type {
-                    "authentication_error" | "invalid_api_key" => {
-                        bail!(
-                            "OpenAI API authentication failed. Check your API key.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    "rate_limit_error" | "rate_limit_exceeded" => {
-                        bail!(
-                            "OpenAI API rate limit hit. Try again in a moment.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    "server_error" => {
-                        bail!(
-                            "OpenAI API server error. Try again shortly.\n  {}",
-                            api_error.error.message
-                        );
-                    }
-                    _ => {
-                        bail!(
-                            "OpenAI API error ({}): {}",
-                            error_type,
-                            api_error.error.message
-                        );
-                    }
-                }
-            }
-
-            bail!(
-                "OpenAI API returned HTTP {}: {}",
-                status,
-                &body[..body.len().min(500)]
-            );
+            return Err(classify_error(self.name, self.label, status.as_u16(), &body).into());
         }
+        Ok(body)
+    }
 
-        let api_response: ApiResponse = serde_json::from_str(&body).with_context(|| {
+    /// Send an authenticated request and parse the JSON response
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let body = self.request_text(request).await?;
+        serde_json::from_str(&body).with_context(|| {
             format!(
-                "Failed to parse OpenAI API response: {}",
+                "Failed to parse {} API response: {}",
+                self.label,
                 &body[..body.len().min(200)]
             )
-        })?;
+        })
+    }
 
+    /// Turn a chat completions response into a generation response
+    fn to_response(&self, api_response: ApiResponse) -> Result<GenerationResponse> {
         let content = api_response
             .choices
             .first()
@@ -189,21 +331,274 @@ impl LlmProvider for OpenAiProvider {
             .to_string();
 
         if content.is_empty() {
-            bail!(
-                "OpenAI API returned empty response (choices: {})",
-                api_response.choices.len()
-            );
+            return Err(ProviderError::new(
+                self.name(),
+                "empty_response",
+                format!(
+                    "{} API returned empty response (choices: {})",
+                    self.label,
+                    api_response.choices.len()
+                ),
+            )
+            .into());
         }
 
         Ok(GenerationResponse {
             content,
             tokens_in: api_response.usage.prompt_tokens,
             tokens_out: api_response.usage.completion_tokens,
+            prompt_cache: PromptCacheTokens::default(),
             model: api_response.model,
+            batch: false,
         })
     }
+}
 
-    fn name(&self) -> &str {
-        "openai"
+/// Build the chat completions request: the system prompt, then the prompt
+/// body followed by the imported context
+fn build_api_request(request: &GenerationRequest) -> ApiRequest {
+    let user_content = if request.context.is_empty() {
+        request.user_prompt.clone()
+    } else {
+        format!(
+            "{}\n\n---\n\n## Context (generated code from imported prompts)\n\n{}\n",
+            request.user_prompt, request.context
+        )
+    };
+
+    ApiRequest {
+        model: request.model.clone(),
+        max_tokens: MAX_TOKENS,
+        messages: vec![
+            ApiMessage {
+                role: "system".to_string(),
+                content: request.system_prompt.clone(),
+            },
+            ApiMessage {
+                role: "user".to_string(),
+                content: user_content,
+            },
+        ],
+        temperature: request.temperature,
+        seed: request.seed,
+        response_format: request.json.then(|| serde_json::json!({ "type": "json_object" })),
+    }
+}
+
+/// The Batch API input file: one chat completions request per line
+fn batch_input(requests: &[GenerationRequest]) -> Result<String> {
+    let mut lines = String::new();
+    for (i, request) in requests.iter().enumerate() {
+        let line = serde_json::json!({
+            "custom_id": batch_id(i),
+            "method": "POST",
+            "url": BATCH_ENDPOINT,
+            "body": build_api_request(request),
+        });
+        lines.push_str(&serde_json::to_string(&line)?);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+/// A `multipart/form-data` upload of a batch input file, as (content type,
+/// body). The boundary is a hash of the content, so it can't occur in it.
+fn batch_upload(content: &str) -> (String, String) {
+    let boundary = format!("lit-{:x}", Sha256::digest(content.as_bytes()));
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+         Content-Type: application/jsonl\r\n\r\n{content}\r\n--{b}--\r\n",
+        b = boundary,
+    );
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
+
+/// Parse a batch output or error file (JSON lines) into a response per
+/// `custom_id`
+fn parse_batch_output(body: &str) -> Result<Vec<(String, Result<ApiResponse>)>> {
+    let mut results = Vec::new();
+    for line in body.lines().filter(|l| !l.trim().is_empty()) {
+        let line: BatchOutputLine = serde_json::from_str(line).with_context(|| {
+            format!("Failed to parse OpenAI batch output: {}", &line[..line.len().min(200)])
+        })?;
+        let result = match (line.response, line.error) {
+            (Some(response), _) if response.status_code == 200 => {
+                serde_json::from_value(response.body).context("Failed to parse OpenAI batch response")
+            }
+            (Some(response), _) => Err(classify_error(
+                "openai",
+                "OpenAI",
+                response.status_code,
+                &response.body.to_string(),
+            )
+            .into()),
+            (None, error) => Err(ProviderError::new(
+                "openai",
+                "batch_error",
+                format!(
+                    "OpenAI batch request failed: {}",
+                    error.map_or_else(|| "no response".to_string(), |e| e.message)
+                ),
+            )
+            .into()),
+        };
+        results.push((line.custom_id, result));
+    }
+    Ok(results)
+}
+
+/// Map a non-success HTTP response to a structured provider error
+fn classify_error(provider: &str, label: &str, status: u16, body: &str) -> ProviderError {
+    let retriable = is_retriable_status(status);
+
+    let Ok(api_error) = serde_json::from_str::<ApiError>(body) else {
+        return ProviderError::new(
+            provider,
+            "http_error",
+            format!(
+                "{} API returned HTTP {}: {}",
+                label,
+                status,
+                &body[..body.len().min(500)]
+            ),
+        )
+        .with_status(status)
+        .retriable(retriable);
+    };
+
+    let error_type = api_error
+        .error
+        .error_type
+        .as_deref()
+        .unwrap_or("unknown");
+
+    let message = match error_type {
+        "authentication_error" | "invalid_api_key" => format!(
+            "{} API authentication failed. Check your API key.\n  {}",
+            label, api_error.error.message
+        ),
+        "rate_limit_error" | "rate_limit_exceeded" => format!(
+            "{} API rate limit hit. Try again in a moment.\n  {}",
+            label, api_error.error.message
+        ),
+        "server_error" => format!(
+            "{} API server error. Try again shortly.\n  {}",
+            label, api_error.error.message
+        ),
+        _ => format!("{} API error ({}): {}", label, error_type, api_error.error.message),
+    };
+
+    ProviderError::new(provider, error_type, message)
+        .with_status(status)
+        .retriable(retriable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_server_error() {
+        let body = r#"{"error":{"type":"server_error","message":"oops"}}"#;
+        let err = classify_error("openai", "OpenAI", 500, body);
+        assert_eq!(err.provider, "openai");
+        assert_eq!(err.error_type, "server_error");
+        assert!(err.retriable);
+    }
+
+    #[test]
+    fn test_classify_invalid_key() {
+        let body = r#"{"error":{"type":"invalid_api_key","message":"nope"}}"#;
+        let err = classify_error("azure", "Azure OpenAI", 401, body);
+        assert_eq!(err.status, Some(401));
+        assert!(!err.retriable);
+        assert_eq!(err.provider, "azure");
+        assert!(
+            err.message
+                .contains("Azure OpenAI API authentication failed")
+        );
+    }
+
+    #[test]
+    fn test_batch_input_and_output() {
+        let request = GenerationRequest {
+            system_prompt: "system".to_string(),
+            context: String::new(),
+            user_prompt: "# User".to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            temperature: 0.0,
+            seed: Some(42),
+            json: false,
+        };
+        let json_request = GenerationRequest { json: true, ..request.clone() };
+        assert_eq!(build_api_request(&json_request).response_format.unwrap()["type"], "json_object");
+        let input = batch_input(&[request.clone(), request]).unwrap();
+        let lines: Vec<serde_json::Value> =
+            input.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["custom_id"], "req-1");
+        assert_eq!(lines[1]["url"], "/v1/chat/completions");
+        assert_eq!(lines[1]["body"]["messages"][1]["content"], "# User");
+
+        let (content_type, body) = batch_upload(&input);
+        let boundary = content_type.strip_prefix("multipart/form-data; boundary=").unwrap();
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+        assert!(body.contains(&input));
+
+        let output = concat!(
+            r#"{"custom_id":"req-1","response":{"status_code":200,"body":{"choices":[{"message":{"content":"hi"},"finish_reason":"stop"}],"model":"gpt-4o","usage":{"prompt_tokens":5,"completion_tokens":2}}},"error":null}"#,
+            "\n",
+            r#"{"custom_id":"req-0","response":{"status_code":429,"body":{"error":{"type":"rate_limit_exceeded","message":"slow"}}},"error":null}"#,
+            "\n",
+        );
+        let results = parse_batch_output(output).unwrap();
+        assert_eq!(results[0].0, "req-1");
+        assert_eq!(results[0].1.as_ref().unwrap().usage.prompt_tokens, 5);
+        let err = results[1].1.as_ref().unwrap_err().downcast_ref::<ProviderError>().unwrap();
+        assert_eq!(err.status, Some(429));
+        assert!(err.retriable);
+    }
+
+    #[test]
+    fn test_request_urls() {
+        let openai = OpenAiProvider::new("sk".to_string());
+        assert_eq!(
+            openai.url("gpt-4o"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            openai.auth_header(),
+            ("Authorization", "Bearer sk".to_string())
+        );
+
+        let azure = OpenAiProvider::azure(
+            "key".to_string(),
+            "https://acme.openai.azure.com/",
+            "2024-10-21",
+        );
+        assert_eq!(
+            azure.url("prod-gpt4o"),
+            "https://acme.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(azure.auth_header(), ("api-key", "key".to_string()));
+        assert_eq!(azure.name(), "azure");
+
+        let openrouter = OpenAiProvider::openrouter("sk-or".to_string())
+            .with_headers([("X-Title".to_string(), "demo".to_string())]);
+        assert_eq!(
+            openrouter.url("anthropic/claude-3.5-sonnet"),
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+        assert_eq!(
+            openrouter.headers,
+            vec![("X-Title".to_string(), "demo".to_string())]
+        );
+        let proxied = openrouter.with_base_url("https://proxy.internal/v1");
+        assert_eq!(
+            proxied.url("x"),
+            "https://proxy.internal/v1/chat/completions"
+        );
     }
 }
@@ -0,0 +1,181 @@
+//! Client-side rate limit pacing (`[model.limits]` and
+//! `[providers.<name>.limits]`): requests to a provider wait until
+//! they fit under the requests-per-minute and tokens-per-minute ceilings,
+//! instead of being sent and throttled with 429s.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use colored::Colorize;
+
+use super::{GenerationRequest, GenerationResponse, LlmProvider};
+use crate::core::config::RateLimits;
+use crate::core::progress;
+use crate::core::tokens::estimate_tokens;
+
+/// The rolling window limits are measured over
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// A request sent within the last minute
+#[derive(Debug, Clone)]
+struct Sent {
+    id: u64,
+    at: Instant,
+    /// Estimated until the response reports the actual input + output tokens
+    tokens: u64,
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    sent: VecDeque<Sent>,
+    next_id: u64,
+}
+
+/// How long a request of `tokens` must wait before it fits under `limits`,
+/// given what was sent in the last minute (oldest first); None to send now.
+/// A request larger than the whole token budget waits for an empty window.
+fn wait_time(limits: &RateLimits, sent: &VecDeque<Sent>, tokens: u64, now: Instant) -> Option<Duration> {
+    let expires = |s: &Sent| (s.at + WINDOW).saturating_duration_since(now);
+    let mut wait = Duration::ZERO;
+    if let Some(rpm) = limits.requests_per_minute
+        && sent.len() >= rpm as usize
+    {
+        wait = wait.max(expires(&sent[sent.len() - rpm as usize]));
+    }
+    if let Some(tpm) = limits.tokens_per_minute {
+        let mut used: u64 = sent.iter().map(|s| s.tokens).sum();
+        for s in sent {
+            if used == 0 || used + tokens <= tpm {
+                break;
+            }
+            used -= s.tokens;
+            wait = wait.max(expires(s));
+        }
+    }
+    Some(wait).filter(|w| !w.is_zero())
+}
+
+/// Wraps a provider so its requests are paced under `limits`. Batch jobs
+/// are queued by the provider and go through unpaced.
+pub struct PacedProvider {
+    inner: Box<dyn LlmProvider>,
+    limits: RateLimits,
+    window: tokio::sync::Mutex<Window>,
+}
+
+impl PacedProvider {
+    pub fn new(inner: Box<dyn LlmProvider>, limits: RateLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            window: tokio::sync::Mutex::new(Window::default()),
+        }
+    }
+
+    /// Wait for room for a request of `tokens`, then count it as sent
+    async fn acquire(&self, tokens: u64) -> u64 {
+        let mut announced = false;
+        loop {
+            let mut window = self.window.lock().await;
+            let now = Instant::now();
+            while window.sent.front().is_some_and(|s| now.duration_since(s.at) >= WINDOW) {
+                window.sent.pop_front();
+            }
+            let Some(wait) = wait_time(&self.limits, &window.sent, tokens, now) else {
+                let id = window.next_id;
+                window.next_id += 1;
+                window.sent.push_back(Sent { id, at: now, tokens });
+                return id;
+            };
+            drop(window);
+            if !announced {
+                progress::detail(&format!(
+                    "    {} waiting {:.1}s to stay under {}'s rate limits",
+                    "⏸".dimmed(),
+                    wait.as_secs_f64(),
+                    self.inner.name()
+                ));
+                announced = true;
+            }
+            tracing::info!(provider = %self.inner.name(), wait_ms = wait.as_millis() as u64, "pacing request");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Replace a sent request's estimate with the tokens it actually used
+    async fn settle(&self, id: u64, tokens: u64) {
+        let mut window = self.window.lock().await;
+        if let Some(sent) = window.sent.iter_mut().find(|s| s.id == id) {
+            sent.tokens = tokens;
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for PacedProvider {
+    async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+        let estimate = estimate_tokens(&request.system_prompt)
+            + estimate_tokens(&request.context)
+            + estimate_tokens(&request.user_prompt);
+        let id = self.acquire(estimate).await;
+        let response = self.inner.generate(request).await;
+        // A failed request still counts, with its estimate
+        if let Ok(ref response) = response {
+            self.settle(id, response.tokens_in + response.tokens_out).await;
+        }
+        response
+    }
+
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> anyhow::Result<Vec<anyhow::Result<GenerationResponse>>> {
+        self.inner.generate_batch(requests).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(rpm: Option<u32>, tpm: Option<u64>) -> RateLimits {
+        RateLimits { requests_per_minute: rpm, tokens_per_minute: tpm }
+    }
+
+    fn sent(now: Instant, ages_and_tokens: &[(u64, u64)]) -> VecDeque<Sent> {
+        ages_and_tokens
+            .iter()
+            .enumerate()
+            .map(|(id, &(age, tokens))| Sent { id: id as u64, at: now - Duration::from_secs(age), tokens })
+            .collect()
+    }
+
+    #[test]
+    fn test_wait_time_requests_per_minute() {
+        let now = Instant::now();
+        let window = sent(now, &[(50, 0), (20, 0)]);
+        assert_eq!(wait_time(&limits(Some(3), None), &window, 0, now), None);
+        // The oldest of the last 2 requests leaves the window in 10s
+        assert_eq!(wait_time(&limits(Some(2), None), &window, 0, now), Some(Duration::from_secs(10)));
+        assert_eq!(wait_time(&limits(Some(1), None), &window, 0, now), Some(Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn test_wait_time_tokens_per_minute() {
+        let now = Instant::now();
+        let window = sent(now, &[(50, 600), (30, 300)]);
+        assert_eq!(wait_time(&limits(None, Some(1000)), &window, 100, now), None);
+        assert_eq!(wait_time(&limits(None, Some(1000)), &window, 500, now), Some(Duration::from_secs(10)));
+        assert_eq!(wait_time(&limits(None, Some(1000)), &window, 800, now), Some(Duration::from_secs(30)));
+        // Too big for any window: sent alone once the window is empty
+        assert_eq!(wait_time(&limits(None, Some(1000)), &window, 5000, now), Some(Duration::from_secs(30)));
+        assert_eq!(wait_time(&limits(None, Some(1000)), &VecDeque::new(), 5000, now), None);
+        // The longer of the two waits applies
+        assert_eq!(wait_time(&limits(Some(1), Some(1000)), &window, 500, now), Some(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,106 @@
+//! Providers built from lit.toml: the `[model]` provider and every
+//! `[providers.*]` entry, with their API keys, endpoints and HTTP settings.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+use super::anthropic::AnthropicProvider;
+use super::openai::{AZURE_API_VERSION, OpenAiProvider};
+use super::pacer::PacedProvider;
+use super::{LazyProvider, LlmProvider, ProviderRegistry, http_client};
+use crate::core::config::LitConfig;
+use crate::core::style;
+
+/// Build the configured LLM provider, resolving its API key. With neither
+/// the environment variable nor a keychain entry, the key is asked for on
+/// the terminal if `ask_keys`; callers that run unattended (`lit serve`, the
+/// LSP, regenerating as a step of another command) pass false and get the
+/// error instead of a prompt nobody answers.
+pub fn build_provider(config: &LitConfig, name: &str, ask_keys: bool) -> Result<Box<dyn LlmProvider>> {
+    let api_key = match config.resolve_provider_key(name) {
+        Ok(key) => key,
+        Err(e) if ask_keys => ask_api_key(name, &e)?,
+        Err(e) => {
+            return Err(e).with_context(|| {
+                if name == config.model.provider {
+                    "Failed to resolve API key.\n\
+                     Hint: Set the environment variable specified in lit.toml [model.api] key_env,\n\
+                     e.g.: export LIT_API_KEY=sk-ant-..."
+                        .to_string()
+                } else {
+                    format!("Failed to resolve API key for {}", name)
+                }
+            });
+        }
+    };
+    let api = config.api_config(name);
+    let base_url = api.and_then(|api| api.base_url.as_deref());
+    let client = http_client(
+        api.and_then(|api| api.proxy_url.as_deref()),
+        api.and_then(|api| api.ca_bundle.as_deref()),
+        api.and_then(|api| api.timeout_secs).map(std::time::Duration::from_secs),
+    )
+    .with_context(|| format!("Failed to set up the HTTP client for {}", name))?;
+    let provider = match name {
+        "openai" => OpenAiProvider::new(api_key),
+        "azure" => {
+            let endpoint = base_url.context("Provider 'azure' needs base_url in lit.toml")?;
+            let version = api
+                .and_then(|api| api.api_version.as_deref())
+                .unwrap_or(AZURE_API_VERSION);
+            OpenAiProvider::azure(api_key, endpoint, version)
+        }
+        "openrouter" => OpenAiProvider::openrouter(api_key),
+        _ => return Ok(Box::new(AnthropicProvider::new(api_key).with_client(client))),
+    };
+    let provider = match base_url {
+        Some(url) => provider.with_base_url(url),
+        None => provider,
+    };
+    let headers = api.map(|api| api.headers.clone()).unwrap_or_default();
+    Ok(Box::new(provider.with_client(client).with_headers(headers)))
+}
+
+/// Ask for a provider's API key on the terminal, once per run: providers are
+/// built lazily, possibly by several prompts at once, and an empty answer
+/// shouldn't be asked again for each of them
+fn ask_api_key(name: &str, missing: &anyhow::Error) -> Result<String> {
+    static ANSWERS: std::sync::Mutex<BTreeMap<String, Option<String>>> = std::sync::Mutex::new(BTreeMap::new());
+    let mut answers = ANSWERS.lock().unwrap_or_else(|e| e.into_inner());
+    let answer = match answers.get(name) {
+        Some(answer) => answer.clone(),
+        None => {
+            eprintln!("{}", style::warning(&missing.to_string()));
+            let key = rpassword::prompt_password(format!("API key for {} (not saved): ", name))
+                .context("Failed to read the API key")?;
+            let key = Some(key.trim().to_string()).filter(|k| !k.is_empty());
+            answers.insert(name.to_string(), key.clone());
+            key
+        }
+    };
+    answer.with_context(|| format!("No API key given for {}", name))
+}
+
+/// Every configured provider (`[model]` and `[providers.*]`), each built on
+/// its first request so runs only need the API keys they actually use.
+/// Requests to each are paced under its limits (`[model.limits]`,
+/// `[providers.<name>.limits]`), whether they come from the `[model]`
+/// default, a tier or a fallback chain. Missing keys are asked for only if
+/// `ask_keys` (see [`build_provider`]).
+pub fn build_registry(config: &LitConfig, ask_keys: bool) -> ProviderRegistry {
+    let mut names = vec![config.model.provider.clone()];
+    names.extend(config.providers.keys().filter(|n| **n != config.model.provider).cloned());
+
+    let mut registry = ProviderRegistry::new(&config.model.provider);
+    for name in names {
+        let provider_config = config.clone();
+        let provider_name = name.clone();
+        let provider: Box<dyn LlmProvider> =
+            Box::new(LazyProvider::new(&name, move || build_provider(&provider_config, &provider_name, ask_keys)));
+        let limits = config.rate_limits(&name);
+        let provider = if limits.is_empty() { provider } else { Box::new(PacedProvider::new(provider, limits)) };
+        registry = registry.with(&name, provider);
+    }
+    registry
+}
@@ -111,7 +111,7 @@ Create a simple Python module with:
         .run_pipeline(
             &dag,
             &prompts_map,
-            &dag.order().to_vec(),
+            dag.order(),
             &HashMap::new(),
             Some(&cache),
         )
@@ -148,7 +148,7 @@ Create a simple Python module with:
         .run_pipeline(
             &dag,
             &prompts_map,
-            &dag.order().to_vec(),
+            dag.order(),
             &HashMap::new(),
             Some(&cache),
         )
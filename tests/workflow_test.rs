@@ -6,15 +6,20 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use lit::api::{Lit, RegenerateOptions};
 use lit::core::cache::Cache;
 use lit::core::config::LitConfig;
 use lit::core::dag::Dag;
+use lit::core::error::LitError;
 use lit::core::generation_record::{
     GenerationRecord, GenerationSummary, PromptRecord, estimate_cost,
 };
 use lit::core::generator::parse_response;
+use lit::core::manifest::Manifest;
+use lit::core::project::load_code;
 use lit::core::prompt::{Prompt, discover_prompts};
 use lit::core::repo::LitRepo;
+use lit::providers::{GenerationRequest, GenerationResponse, LlmProvider, PromptCacheTokens};
 
 // ---------- Helpers ----------
 
@@ -154,7 +159,7 @@ fn test_full_workflow() {
     assert_eq!(status.prompts_modified.len(), 1);
 
     // 9. Diff should show the modification
-    let diff = repo.diff_prompts().unwrap();
+    let diff = repo.diff_prompts(None).unwrap();
     assert!(!diff.is_empty(), "Expected prompt diff after modify");
 
     // 10. Update generated code and commit again
@@ -174,13 +179,13 @@ fn test_full_workflow() {
     assert_eq!(log[2].message, "lit init");
 
     // 12. Checkout first commit, verify file is restored
-    repo.checkout_ref(&commit_hash).unwrap();
+    repo.checkout_ref(&commit_hash, false).unwrap();
     let content =
         std::fs::read_to_string(dir.path().join("code.lock/src/hello.py")).unwrap();
     assert!(content.contains("Hello, World!"), "Should be original code after checkout");
 
     // 13. Checkout back to latest
-    repo.checkout_ref(&second_hash).unwrap();
+    repo.checkout_ref(&second_hash, false).unwrap();
     let content =
         std::fs::read_to_string(dir.path().join("code.lock/src/hello.py")).unwrap();
     assert!(
@@ -278,6 +283,7 @@ fn test_cache_hit_miss() {
         Some(42),
         "python",
         Some("fastapi"),
+        &[],
     );
 
     // Cache miss
@@ -293,6 +299,9 @@ fn test_cache_hit_miss() {
         },
         tokens_in: 100,
         tokens_out: 50,
+        created_at: None,
+        model: None,
+        parse_version: lit::core::generator::PARSE_VERSION,
     };
     cache.put(&cached).unwrap();
 
@@ -311,6 +320,7 @@ fn test_cache_hit_miss() {
         Some(42),
         "python",
         Some("fastapi"),
+        &[],
     );
     assert_eq!(hash, hash2);
 
@@ -323,6 +333,7 @@ fn test_cache_hit_miss() {
         Some(42),
         "python",
         Some("fastapi"),
+        &[],
     );
     assert_ne!(hash, hash3);
     assert!(cache.get(&hash3).is_none()); // Miss for new content
@@ -351,9 +362,12 @@ fn test_generation_record_roundtrip() {
                 from_cache: false,
                 tokens_in: 500,
                 tokens_out: 200,
+                prompt_cache: PromptCacheTokens::default(),
+                batch: false,
                 duration_ms: 3000,
                 model: "claude-sonnet-4-5-20250929".to_string(),
                 cost_usd: 0.005,
+                input_breakdown: None,
             },
         ],
         summary: GenerationSummary {
@@ -363,12 +377,15 @@ fn test_generation_record_roundtrip() {
             skipped: 0,
             total_tokens_in: 500,
             total_tokens_out: 200,
+            total_prompt_cache: PromptCacheTokens::default(),
             total_cost_usd: 0.005,
             total_duration_ms: 3000,
             total_files_written: 1,
             patches_applied: 0,
             patches_conflicted: 0,
         },
+        failures: Vec::new(),
+        command: None,
     };
 
     record.write(&gen_dir).unwrap();
@@ -388,11 +405,11 @@ fn test_generation_record_roundtrip() {
 #[test]
 fn test_cost_estimation() {
     // Claude Sonnet pricing should be non-zero
-    let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 500, None);
+    let cost = estimate_cost("claude-sonnet-4-5-20250929", 1000, 500, Default::default(), None);
     assert!(cost > 0.0, "Cost should be > 0 for known model");
 
     // Unknown model → fallback pricing
-    let cost_unknown = estimate_cost("unknown-model-xyz", 1000, 500, None);
+    let cost_unknown = estimate_cost("unknown-model-xyz", 1000, 500, Default::default(), None);
     assert!(cost_unknown > 0.0, "Should have fallback pricing");
 }
 
@@ -433,12 +450,19 @@ This imports a nonexistent prompt.
     // DAG build should fail — import target doesn't exist
     let dag_result = Dag::build(&prompts);
     assert!(dag_result.is_err(), "DAG should fail with missing import");
-    let err_msg = dag_result.unwrap_err().to_string();
+    let err = dag_result.unwrap_err();
+    let err_msg = err.to_string();
     assert!(
         err_msg.contains("nonexistent"),
         "Error should mention missing prompt: {}",
         err_msg
     );
+    match LitError::of(&err) {
+        Some(LitError::MissingImports { imports }) => {
+            assert_eq!(imports[0].1, PathBuf::from("prompts/nonexistent.prompt.md"));
+        }
+        other => panic!("Expected MissingImports, got: {:?}", other),
+    }
 
     // Fix the prompt by removing the bad import
     std::fs::write(
@@ -559,7 +583,7 @@ fn test_status_detects_code_edits() {
     );
 
     // Code diff should show the change
-    let diff = repo.diff_code().unwrap();
+    let diff = repo.diff_code(None).unwrap();
     assert!(diff.contains("hand-edited"));
 }
 
@@ -689,12 +713,12 @@ fn test_checkout_roundtrip() {
     assert_eq!(content, "v3 content");
 
     // Checkout v1
-    repo.checkout_ref(&v1).unwrap();
+    repo.checkout_ref(&v1, false).unwrap();
     let content = std::fs::read_to_string(dir.path().join("prompts/app.prompt.md")).unwrap();
     assert_eq!(content, "v1 content");
 
     // Checkout v2
-    repo.checkout_ref(&v2).unwrap();
+    repo.checkout_ref(&v2, false).unwrap();
     let content = std::fs::read_to_string(dir.path().join("prompts/app.prompt.md")).unwrap();
     assert_eq!(content, "v2 content");
 }
@@ -710,7 +734,7 @@ fn test_diff_clean_tree() {
     repo.stage_all().unwrap();
     repo.commit("clean").unwrap();
 
-    let diff = repo.diff_prompts().unwrap();
+    let diff = repo.diff_prompts(None).unwrap();
     assert!(diff.is_empty(), "Diff should be empty for clean tree");
 }
 
@@ -742,3 +766,238 @@ fn test_stage_all_deleted_files() {
     let status = repo.status().unwrap();
     assert!(!status.has_changes());
 }
+
+/// Provider answering every request with a module for its first declared output
+struct StubProvider;
+
+#[async_trait::async_trait]
+impl LlmProvider for StubProvider {
+    async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+        let output = request
+            .system_prompt
+            .lines()
+            .find_map(|l| l.strip_prefix("  - "))
+            .unwrap_or("src/app.py")
+            .to_string();
+        Ok(GenerationResponse {
+            content: format!("=== FILE: {} ===\nVALUE = 42\n", output),
+            tokens_in: 100,
+            tokens_out: 20,
+            prompt_cache: PromptCacheTokens::default(),
+            model: request.model,
+            batch: false,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+}
+
+/// Test: the library facade regenerates, reports status and sums costs in-process
+#[tokio::test]
+async fn test_library_facade() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_lit_project(dir.path(), "facade-test");
+    std::fs::write(dir.path().join("prompts/base.prompt.md"), simple_prompt("src/base.py")).unwrap();
+    std::fs::write(
+        dir.path().join("prompts/app.prompt.md"),
+        prompt_with_import("src/app.py", "prompts/base.prompt.md"),
+    )
+    .unwrap();
+
+    let lit = Lit::open(dir.path().join("prompts")).unwrap();
+    assert_eq!(lit.config().project.name, "facade-test");
+    assert!(lit.status().unwrap().stale.is_none(), "Nothing generated yet");
+
+    let report = lit
+        .regenerate(RegenerateOptions { provider: Some(Box::new(StubProvider)), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(report.selected, vec![PathBuf::from("prompts/base.prompt.md"), PathBuf::from("prompts/app.prompt.md")]);
+    assert_eq!(report.generated.len(), 2);
+    assert_eq!(report.files, vec![PathBuf::from("src/app.py"), PathBuf::from("src/base.py")]);
+    assert!(dir.path().join("code.lock/src/base.py").exists());
+
+    let status = lit.status().unwrap();
+    assert_eq!(status.prompts.len(), 2);
+    assert_eq!(status.stale, Some(Vec::new()));
+    assert!(status.changes.is_none(), "No git repository");
+
+    let costs = lit.cost_summary().unwrap();
+    assert_eq!(costs.generations, 1);
+    assert_eq!(costs.cache_misses, 2);
+    assert_eq!(costs.total_tokens_in, 200);
+
+    // A second run comes from the cache
+    let report = lit
+        .regenerate(RegenerateOptions {
+            prompts: vec![PathBuf::from("prompts/app.prompt.md")],
+            provider: Some(Box::new(StubProvider)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(report.cached, vec![PathBuf::from("prompts/app.prompt.md")]);
+    assert!(report.generated.is_empty());
+}
+
+/// Test: `only` regenerates just the selection and marks its dependents
+/// stale in the manifest, even when their input hash didn't change
+#[tokio::test]
+async fn test_library_facade_only_marks_dependents_stale() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_lit_project(dir.path(), "facade-only");
+    std::fs::write(dir.path().join("prompts/base.prompt.md"), simple_prompt("src/base.py")).unwrap();
+    std::fs::write(
+        dir.path().join("prompts/app.prompt.md"),
+        prompt_with_import("src/app.py", "prompts/base.prompt.md"),
+    )
+    .unwrap();
+    let lit = Lit::open(dir.path()).unwrap();
+    lit.regenerate(RegenerateOptions { provider: Some(Box::new(StubProvider)), ..Default::default() })
+        .await
+        .unwrap();
+
+    // The stub answers the reworded prompt with the same code, so app's
+    // inputs hash as before
+    let base = PathBuf::from("prompts/base.prompt.md");
+    let app = PathBuf::from("prompts/app.prompt.md");
+    std::fs::write(dir.path().join(&base), format!("{}\nUse type hints.\n", simple_prompt("src/base.py"))).unwrap();
+    let report = lit
+        .regenerate(RegenerateOptions {
+            prompts: vec![base.clone()],
+            only: true,
+            provider: Some(Box::new(StubProvider)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(report.generated, vec![base.clone()]);
+    assert!(report.cached.is_empty());
+    assert_eq!(report.left_stale, vec![app.clone()]);
+    let manifest = Manifest::load(&dir.path().join(".lit")).unwrap().unwrap();
+    assert!(manifest.stale.contains(&app));
+    assert_eq!(lit.status().unwrap().stale, Some(vec![app.clone()]));
+
+    // Regenerating the dependent clears the mark
+    let report = lit
+        .regenerate(RegenerateOptions {
+            prompts: vec![app.clone()],
+            provider: Some(Box::new(StubProvider)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(report.cached, vec![app]);
+    assert!(Manifest::load(&dir.path().join(".lit")).unwrap().unwrap().stale.is_empty());
+    assert_eq!(lit.status().unwrap().stale, Some(Vec::new()));
+}
+
+/// Test: commits refuse code.lock/ behind its prompts unless stale code is
+/// allowed, and carry the cost of the generation runs they record
+#[tokio::test]
+async fn test_library_facade_commit_refuses_stale() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = LitRepo::init(dir.path()).unwrap();
+    setup_lit_project(dir.path(), "facade-commit");
+    repo.write_gitignore().unwrap();
+    let base = PathBuf::from("prompts/base.prompt.md");
+    std::fs::write(dir.path().join(&base), simple_prompt("src/base.py")).unwrap();
+    let lit = Lit::open(dir.path()).unwrap();
+    lit.regenerate(RegenerateOptions { provider: Some(Box::new(StubProvider)), ..Default::default() })
+        .await
+        .unwrap();
+    let commit = lit.commit("Add base", false).unwrap().unwrap();
+    assert_eq!(commit.changes.prompts_new, vec![base.clone()]);
+    assert_eq!(commit.records.len(), 1);
+    assert!(repo.commit_info(&commit.hash).unwrap().message.starts_with("Add base"));
+
+    // Edited without regenerating
+    std::fs::write(dir.path().join(&base), format!("{}\nUse type hints.\n", simple_prompt("src/base.py"))).unwrap();
+    let err = lit.commit("Reword base", false).unwrap_err();
+    assert_eq!(LitError::of(&err), Some(&LitError::Stale { prompts: vec![base.clone()] }));
+    assert!(err.to_string().contains("  prompts/base.prompt.md\n"), "{}", err);
+    assert_eq!(repo.head_commit().unwrap().hash, commit.hash, "Nothing committed");
+
+    let commit = lit.commit("Reword base", true).unwrap().unwrap();
+    assert_eq!(commit.changes.prompts_modified, vec![base]);
+    assert!(commit.records.is_empty());
+    assert!(lit.commit("Nothing left", true).unwrap().is_none());
+}
+
+struct FailingProvider;
+
+#[async_trait::async_trait]
+impl LlmProvider for FailingProvider {
+    async fn generate(&self, _request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
+        anyhow::bail!("provider unavailable")
+    }
+
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+}
+
+/// Test: static files are only written by a successful run, which also
+/// checks budget alerts
+#[tokio::test]
+async fn test_library_facade_static_files_and_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_lit_project(dir.path(), "facade-budget");
+    let config = format!(
+        "{}\n[[static]]\npath = \"README.txt\"\ncontent = \"Generated by lit\\n\"\n\n[budget.alerts]\nwarn_at_usd_per_run = 0.0001\n",
+        minimal_config("facade-budget")
+    );
+    std::fs::write(dir.path().join("lit.toml"), config).unwrap();
+    std::fs::write(dir.path().join("prompts/base.prompt.md"), simple_prompt("src/base.py")).unwrap();
+
+    let lit = Lit::open(dir.path()).unwrap();
+    let failed = lit
+        .regenerate(RegenerateOptions { provider: Some(Box::new(FailingProvider)), ..Default::default() })
+        .await;
+    assert!(failed.is_err());
+    assert!(!dir.path().join("code.lock/README.txt").exists());
+
+    let report = lit
+        .regenerate(RegenerateOptions { provider: Some(Box::new(StubProvider)), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(dir.path().join("code.lock/README.txt")).unwrap(), "Generated by lit\n");
+    assert!(report.warnings.iter().any(|w| w.contains("per-run budget")), "{:?}", report.warnings);
+}
+
+/// Test: provenance headers are written to code.lock/ but never read back as code
+#[tokio::test]
+async fn test_file_header() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_lit_project(dir.path(), "header-test");
+    let config = format!(
+        "{}\n[generation]\nfile_header = \"Generated by lit from {{prompt}} ({{model}}) at {{timestamp}}\"\n",
+        minimal_config("header-test")
+    );
+    std::fs::write(dir.path().join("lit.toml"), config).unwrap();
+    std::fs::write(dir.path().join("prompts/base.prompt.md"), simple_prompt("src/base.py")).unwrap();
+    std::fs::write(
+        dir.path().join("prompts/app.prompt.md"),
+        prompt_with_import("src/app.py", "prompts/base.prompt.md"),
+    )
+    .unwrap();
+
+    let lit = Lit::open(dir.path()).unwrap();
+    lit.regenerate(RegenerateOptions { provider: Some(Box::new(StubProvider)), ..Default::default() })
+        .await
+        .unwrap();
+    let on_disk = std::fs::read_to_string(dir.path().join("code.lock/src/base.py")).unwrap();
+    assert!(
+        on_disk.starts_with("# Generated by lit from prompts/base.prompt.md (claude-sonnet-4-5-20250929) at 20"),
+        "got: {}",
+        on_disk
+    );
+    assert!(on_disk.ends_with("\n\nVALUE = 42\n"));
+
+    let code = load_code(lit.config(), lit.root());
+    assert_eq!(code[Path::new("src/base.py")], "VALUE = 42\n");
+    // The header (and its timestamp) doesn't make the importing prompt stale
+    assert_eq!(lit.status().unwrap().stale, Some(Vec::new()));
+}